@@ -48,11 +48,101 @@ impl Color {
         // If not a basic ANSI color, return unchanged
         *self
     }
+
+    /// The same color with full alpha
+    pub fn opaque(&self) -> Self {
+        Self { a: 1.0, ..*self }
+    }
+
+    /// WCAG relative luminance (sRGB, alpha ignored)
+    pub fn relative_luminance(&self) -> f64 {
+        let linear = |v: f64| {
+            let v = v.clamp(0.0, 1.0);
+            if v <= 0.03928 { v / 12.92 } else { ((v + 0.055) / 1.055).powf(2.4) }
+        };
+        0.2126 * linear(self.r) + 0.7152 * linear(self.g) + 0.0722 * linear(self.b)
+    }
+
+    /// WCAG contrast ratio with `other`: 1.0 for identical colors up to 21.0 for black on white
+    pub fn contrast_ratio(&self, other: &Color) -> f64 {
+        let (a, b) = (self.relative_luminance(), other.relative_luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    /// Format as an X11 color specification (`rgb:RRRR/GGGG/BBBB`), the form
+    /// xterm uses when answering OSC color queries
+    pub fn to_x11_rgb(&self) -> String {
+        let channel = |v: f64| (v.clamp(0.0, 1.0) * 65535.0).round() as u16;
+        format!("rgb:{:04x}/{:04x}/{:04x}", channel(self.r), channel(self.g), channel(self.b))
+    }
+}
+
+/// Dynamic colors addressable by OSC 10-12
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DynamicColor {
+    /// Default text color (OSC 10)
+    Foreground,
+    /// Default background color (OSC 11)
+    Background,
+    /// Text cursor color (OSC 12)
+    Cursor,
+}
+
+impl DynamicColor {
+    /// Map an OSC command number to the dynamic color it addresses
+    pub fn from_osc(code: u16) -> Option<Self> {
+        match code {
+            10 => Some(DynamicColor::Foreground),
+            11 => Some(DynamicColor::Background),
+            12 => Some(DynamicColor::Cursor),
+            _ => None,
+        }
+    }
+
+    /// OSC command number of this dynamic color
+    pub fn osc_code(self) -> u16 {
+        match self {
+            DynamicColor::Foreground => 10,
+            DynamicColor::Background => 11,
+            DynamicColor::Cursor => 12,
+        }
+    }
 }
 
-// Utility function for bold_is_bright functionality - brighten ANSI colors when bold is enabled
-pub fn brighten_color(color: Color) -> Color {
-    color.to_bright_ansi_color()
+/// Logical origin of a cell color, kept alongside the resolved RGBA value so
+/// that palette-dependent rendering (bold-is-bright, palette changes) can be
+/// applied at render time without mutating stored colors
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LogicalColor {
+    /// The theme's default foreground/background (SGR 39 / 49, reset state),
+    /// resolved from the embedder's configuration at render time
+    #[default]
+    Default,
+    /// Direct RGB color (SGR 38;2 / 48;2) or legacy `set_fg`/`set_bg` callers
+    Rgb,
+    /// Palette entry 0-255 (SGR 30-37, 90-97, 38;5 and background equivalents)
+    Indexed(u8),
+}
+
+/// Resolve a 256-color palette index to its RGBA value
+///
+/// 0-15 map to [`COLOR_PALETTE`], 16-231 to the 6x6x6 color cube and
+/// 232-255 to the grayscale ramp.
+pub fn palette_color(index: u8) -> Color {
+    match index {
+        0..=15 => COLOR_PALETTE[index as usize],
+        16..=231 => {
+            let idx = index - 16;
+            let r = (idx / 36) % 6;
+            let g = (idx / 6) % 6;
+            let b = idx % 6;
+            Color::rgba(r as f64 / 5.0, g as f64 / 5.0, b as f64 / 5.0, 1.0)
+        }
+        232..=255 => {
+            let gray = (index - 232) as f64 / 23.0;
+            Color::rgba(gray, gray, gray, 1.0)
+        }
+    }
 }
 
 // 16-color ANSI palette
@@ -1,20 +1,103 @@
-use crate::color::Color;
+use crate::color::{palette_color, Color, DynamicColor, LogicalColor, COLOR_PALETTE};
 
 /// A single character cell with styling attributes.
 ///
 /// Represents one character position in a terminal grid, containing the character
 /// itself and all text styling that should be applied when rendering it.
-#[derive(Clone, Copy, Default, Debug)]
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
 pub struct Cell {
     pub ch: char,
+    /// Left half of a double-width character; the next cell is its spacer
+    pub wide: bool,
+    /// Right half of a double-width character: holds no character of its
+    /// own, the cell before it is drawn across both
+    pub spacer: bool,
     pub fg: Color,
     pub bg: Color,
     pub bold: bool,
     pub italic: bool,
     pub underline: bool,
+    /// Underline shape (SGR 4:n); `None` exactly when `underline` is false
+    pub underline_style: UnderlineStyle,
     pub dim: bool,
+    /// Crossed out (SGR 9)
+    pub strikethrough: bool,
+    /// Line above the text (SGR 53)
+    pub overline: bool,
+    /// Font slot selected with SGR 10-20 (see [`PRIMARY_FONT`], [`FRAKTUR_FONT`])
+    pub font: u8,
+    /// Logical origin of `fg` (palette index or direct RGB)
+    pub fg_logical: LogicalColor,
+    /// Logical origin of `bg` (palette index or direct RGB)
+    pub bg_logical: LogicalColor,
+    /// Color of the underline, overline and strikethrough (SGR 58)
+    pub decoration: Color,
+    /// Logical origin of `decoration`; [`LogicalColor::Default`] (SGR 59)
+    /// draws decorations in the color the glyph is drawn in
+    pub decoration_logical: LogicalColor,
+    /// OSC 8 hyperlink id, resolved by the grid that stores the cell (0 = none)
+    pub hyperlink: u32,
+    /// Active region (clickable button, OSC 7777) id, resolved like `hyperlink`
+    pub region: u32,
+    /// Combining marks and variation selectors following `ch`, an id
+    /// resolved like `hyperlink` (0 = none)
+    pub combining: u32,
+    /// Set on the last cell of a row whose text soft-wrapped onto the next row
+    pub wrapped: bool,
+    /// Written under DECSCA or SPA: selective erase (DECSED, DECSEL) skips it
+    pub protected: bool,
 }
 
+impl Cell {
+    /// Foreground color to display, applying the bold-is-bright mapping
+    ///
+    /// Bold text in one of the eight basic palette colors is shown in the
+    /// corresponding bright color (8-15). RGB and 256-color foregrounds are
+    /// never altered, and the stored color itself is left untouched.
+    /// [`LogicalColor::Default`] cells return the stored snapshot; embedders
+    /// with a theme substitute their default foreground instead.
+    pub fn display_fg(&self, bold_is_bright: bool) -> Color {
+        match self.fg_logical {
+            LogicalColor::Indexed(idx) if bold_is_bright && self.bold && idx < 8 => {
+                COLOR_PALETTE[idx as usize + 8]
+            }
+            _ => self.fg,
+        }
+    }
+}
+
+/// Underline shape selected with SGR 4 / 4:n
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnderlineStyle {
+    #[default]
+    None,
+    Single,
+    Double,
+    Curly,
+    Dotted,
+    Dashed,
+}
+
+impl UnderlineStyle {
+    /// Style for the sub-parameter of `SGR 4:n`; unknown values yield `None`
+    pub fn from_sgr(n: u16) -> Option<Self> {
+        match n {
+            0 => Some(UnderlineStyle::None),
+            1 => Some(UnderlineStyle::Single),
+            2 => Some(UnderlineStyle::Double),
+            3 => Some(UnderlineStyle::Curly),
+            4 => Some(UnderlineStyle::Dotted),
+            5 => Some(UnderlineStyle::Dashed),
+            _ => None,
+        }
+    }
+}
+
+/// Font slot of the primary font (SGR 10)
+pub const PRIMARY_FONT: u8 = 0;
+/// Font slot selected by SGR 20 (Fraktur); slots 1-9 are SGR 11-19
+pub const FRAKTUR_FONT: u8 = 10;
+
 /// Key event for input handling
 #[derive(Debug, Clone)]
 pub struct KeyEvent {
@@ -31,6 +114,44 @@ pub struct MouseEvent {
     pub modifiers: u32,
 }
 
+/// Value of a terminfo capability, as answered to XTGETTCAP
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TermcapValue {
+    /// Boolean capability that is present
+    Flag,
+    Number(u32),
+    /// String capability, with actual control characters (not `\E` notation)
+    String(String),
+}
+
+/// What an OSC sequence does, for security policies
+///
+/// Shell integration marks (OSC 133) are the only sequences outside these
+/// categories; they are not subject to the policy check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OscCategory {
+    /// Window title (OSC 0, 2)
+    Title,
+    /// Setting the clipboard (OSC 52 with data)
+    ClipboardWrite,
+    /// Reading the clipboard (OSC 52 `?`)
+    ClipboardRead,
+    /// Hyperlinks (OSC 8)
+    Hyperlink,
+    /// Desktop notifications (OSC 9, OSC 777 `notify`)
+    Notification,
+    /// Working directory reports (OSC 7)
+    CurrentDirectory,
+    /// Setting and querying the palette and dynamic colors (OSC 4, 10-12)
+    Colors,
+    /// Mouse pointer shape (OSC 22)
+    PointerShape,
+    /// Clickable regions (OSC 7777)
+    ActiveRegion,
+    /// OSC numbers without a built-in meaning, for embedder handlers
+    Custom,
+}
+
 /// Trait for ANSI escape sequence grid operations.
 ///
 /// Implement this trait to handle text and control operations that are
@@ -80,6 +201,10 @@ pub struct MouseEvent {
 ///     // ... other state
 /// }
 ///
+/// impl MyGrid {
+///     fn draw_at(&mut self, _x: usize, _y: usize, _ch: char) { /* ... */ }
+/// }
+///
 /// impl AnsiGrid for MyGrid {
 ///     fn put(&mut self, ch: char) {
 ///         // Draw character at cursor position
@@ -89,7 +214,31 @@ pub struct MouseEvent {
 ///     fn advance(&mut self) { self.cursor_x += 1; }
 ///
 ///     // ... implement other required methods
+///     # fn left(&mut self, _n: usize) {}
+///     # fn right(&mut self, _n: usize) {}
+///     # fn up(&mut self, _n: usize) {}
+///     # fn down(&mut self, _n: usize) {}
+///     # fn newline(&mut self) {}
+///     # fn carriage_return(&mut self) {}
+///     # fn backspace(&mut self) {}
+///     # fn move_rel(&mut self, _dx: i32, _dy: i32) {}
+///     # fn move_abs(&mut self, _row: usize, _col: usize) {}
+///     # fn clear_screen(&mut self) {}
+///     # fn clear_line(&mut self) {}
+///     # fn reset_attrs(&mut self) {}
+///     # fn set_bold(&mut self, _bold: bool) {}
+///     # fn set_italic(&mut self, _italic: bool) {}
+///     # fn set_underline(&mut self, _underline: bool) {}
+///     # fn set_dim(&mut self, _dim: bool) {}
+///     # fn set_fg(&mut self, _color: Color) {}
+///     # fn set_bg(&mut self, _color: Color) {}
+///     # fn get_fg(&self) -> Color { Color::default() }
+///     # fn get_bg(&self) -> Color { Color::default() }
 /// }
+///
+/// let mut grid = MyGrid { cursor_x: 0, cursor_y: 0 };
+/// AnsiParser::new().feed_str("hi", &mut grid);
+/// assert_eq!(grid.cursor_x, 2);
 /// ```
 ///
 pub trait AnsiGrid {
@@ -110,9 +259,46 @@ pub trait AnsiGrid {
     fn set_bold(&mut self, bold: bool);
     fn set_italic(&mut self, italic: bool);
     fn set_underline(&mut self, underline: bool);
+    /// Set the underline shape (SGR 4:n); the fallback maps it to on/off
+    fn set_underline_style(&mut self, style: UnderlineStyle) {
+        self.set_underline(style != UnderlineStyle::None);
+    }
     fn set_dim(&mut self, dim: bool);
+    /// Cross out the text (SGR 9) or stop (SGR 29)
+    fn set_strikethrough(&mut self, _strikethrough: bool) {}
+    /// Draw a line above the text (SGR 53) or stop (SGR 55)
+    fn set_overline(&mut self, _overline: bool) {}
+    /// Color of underline, overline and strikethrough (SGR 58); `None`
+    /// (SGR 59) makes them follow the foreground again
+    fn set_decoration_color(&mut self, _color: Option<Color>) {}
+    /// Decoration color from a 256-color palette entry (SGR 58:5:n)
+    fn set_decoration_indexed(&mut self, index: u8) {
+        self.set_decoration_color(Some(palette_color(index)));
+    }
     fn set_fg(&mut self, color: Color);
     fn set_bg(&mut self, color: Color);
+    /// Set the foreground to a 256-color palette entry (SGR 30-37, 90-97, 38;5)
+    fn set_fg_indexed(&mut self, index: u8) {
+        self.set_fg(palette_color(index));
+    }
+    /// Set the background to a 256-color palette entry (SGR 40-47, 100-107, 48;5)
+    fn set_bg_indexed(&mut self, index: u8) {
+        self.set_bg(palette_color(index));
+    }
+    /// Restore the default foreground (SGR 39)
+    ///
+    /// Implementations with a configurable theme should override this to
+    /// record [`LogicalColor::Default`] instead of a fixed color.
+    fn set_fg_default(&mut self) {
+        self.set_fg(Color::default());
+    }
+    /// Restore the default background (SGR 49)
+    ///
+    /// The fallback keeps the historical black background; themed
+    /// implementations should override it.
+    fn set_bg_default(&mut self) {
+        self.set_bg(Color::rgb(0.0, 0.0, 0.0));
+    }
     fn set_title(&mut self, title: &str) {
         let _ = title;
     }
@@ -124,9 +310,19 @@ pub trait AnsiGrid {
     fn clear_screen_up(&mut self) {}
     fn clear_line_right(&mut self) {}
     fn clear_line_left(&mut self) {}
+    /// ED 3: erase the scrollback (saved lines)
+    fn clear_scrollback(&mut self) {}
+    /// RIS (`ESC c`): reset state the attributes and the screen do not
+    /// cover; called after those were reset
+    fn full_reset(&mut self) {}
+    /// DECSTR (`CSI ! p`): reset modes the attributes do not cover, keeping
+    /// the screen; called after the attributes were reset
+    fn soft_reset(&mut self) {}
     fn save_cursor(&mut self) {}
     fn restore_cursor(&mut self) {}
     fn set_cursor_visible(&mut self, _visible: bool) {}
+    /// DECSET 12: application request to start (`true`) or stop cursor blinking
+    fn set_cursor_blink(&mut self, _enable: bool) {}
 
     // Phase-2 scrolling operations
     fn scroll_up(&mut self, _n: usize) {}
@@ -140,6 +336,19 @@ pub trait AnsiGrid {
     fn insert_chars(&mut self, _n: usize) {}
     fn delete_chars(&mut self, _n: usize) {}
     fn erase_chars(&mut self, _n: usize) {}
+    /// DECIC (`CSI Pn ' }`): insert blank columns at the cursor column in
+    /// every row of the screen
+    fn insert_columns(&mut self, _n: usize) {}
+    /// DECDC (`CSI Pn ' ~`): delete columns at the cursor column in every
+    /// row of the screen
+    fn delete_columns(&mut self, _n: usize) {}
+    /// DECSCA (`CSI 1 " q`) or SPA/EPA (`ESC V` / `ESC W`): protect the
+    /// characters written from now on from selective erase
+    fn set_protected(&mut self, _protected: bool) {}
+    /// DECSED (`CSI ? Ps J`): erase in display, sparing protected cells
+    fn selective_erase_display(&mut self, _mode: usize) {}
+    /// DECSEL (`CSI ? Ps K`): erase in line, sparing protected cells
+    fn selective_erase_line(&mut self, _mode: usize) {}
 
     // Phase-4 alternate screen
     fn use_alternate_screen(&mut self, _enable: bool) {}
@@ -147,17 +356,77 @@ pub trait AnsiGrid {
     // Phase-4 additional modes
     fn set_insert_mode(&mut self, _enable: bool) {}
     fn set_auto_wrap(&mut self, _enable: bool) {}
+    /// DECSET 45: backspace at column 0 moves to the end of the previous line
+    fn set_reverse_wraparound(&mut self, _enable: bool) {}
 
     // Phase-2 DEC private modes
     fn set_application_cursor_keys(&mut self, _enable: bool) {}
     fn set_mouse_reporting_mode(&mut self, _mode: u16, _enable: bool) {}
     fn set_focus_reporting(&mut self, _enable: bool) {}
+    /// Mode 2031: notify of light/dark color scheme changes
+    fn set_color_scheme_reporting(&mut self, _enable: bool) {}
+    /// `CSI ? 996 n`: report the current light/dark color scheme
+    fn report_color_scheme(&mut self) {}
+    /// BEL (0x07) outside a control string
+    fn bell(&mut self) {}
     fn set_origin_mode(&mut self, _enable: bool) {}
 
+    // DEC locator
+    /// DECELR (`CSI Ps ; Pu ' z`): locator reports off (0), on (1) or on
+    /// for a single report (2), positions in pixels when `pixels` (Pu 1)
+    fn set_locator_reporting(&mut self, _mode: u16, _pixels: bool) {}
+    /// DECSLE (`CSI Pm ' {`): button events reported without a request
+    /// (0 none, 1/2 presses on/off, 3/4 releases on/off)
+    fn select_locator_events(&mut self, _events: &[u16]) {}
+    /// DECRQLP (`CSI Ps ' |`): report the locator position now
+    fn request_locator_position(&mut self) {}
+
     // Phase-2 OSC sequences
+    /// Policy check run before an OSC sequence in `category` is dispatched;
+    /// `osc` is the whole string (`number;data`). Returning false drops it.
+    fn allow_osc(&mut self, _category: OscCategory, _osc: &str) -> bool {
+        true
+    }
+    /// Check run before a complete device control string is dispatched;
+    /// `dcs` is its body without the introducer and ST. Returning false drops it.
+    fn allow_dcs(&mut self, _dcs: &str) -> bool {
+        true
+    }
     fn set_current_directory(&mut self, _directory: &str) {}
+    /// Sixel image (`DCS P1 ; P2 ; P3 q data ST`): `params` are the P values
+    /// before the `q` (P2 1 keeps unset pixels transparent) and `data` the
+    /// sixel body after it
+    fn sixel(&mut self, _params: &str, _data: &str) {}
+    /// DECUDK (`DCS Pc ; Pl | key/hex ; ... ST`): program function keys.
+    /// `keys` holds (key number, decoded string) pairs; `clear` (Pc 0) erases
+    /// all definitions first and `lock` (Pl 0) refuses later ones
+    fn define_user_keys(&mut self, _keys: &[(u16, String)], _clear: bool, _lock: bool) {}
     fn handle_clipboard_data(&mut self, _clipboard_id: u8, _data: &str) {}
+    /// OSC 52 read request (`?` data) for `selection`, e.g. `c`; a reply
+    /// carries the base64 contents and ends with `terminator`
+    fn request_clipboard(&mut self, _selection: &str, _terminator: &str) {}
     fn handle_hyperlink(&mut self, _params: Option<&str>, _uri: &str) {}
+    /// OSC 7777 ; payload (hugovte extension): cells written from now on form
+    /// a clickable region reporting `payload`; an empty payload ends it
+    fn handle_active_region(&mut self, _payload: &str) {}
+    /// Desktop notification: OSC 9 ; body (title empty) or
+    /// OSC 777 ; notify ; title ; body
+    fn show_notification(&mut self, _title: &str, _body: &str) {}
+    /// OSC `code` ; data for a number without a built-in meaning
+    fn handle_custom_osc(&mut self, _code: u16, _data: &str) {}
+    /// Shell integration mark (OSC 133): `A` prompt start, `B` command start,
+    /// `C` output start, `D` command finished; `params` holds the text after the
+    /// kind (e.g. the exit status of `D;0`), without the leading `;`
+    fn handle_semantic_prompt(&mut self, _kind: char, _params: &str) {}
+    /// Mouse pointer shape requested with OSC 22, e.g. `text` or `pointer`;
+    /// empty restores the default
+    fn set_pointer_shape(&mut self, _name: &str) {}
+    /// XTPUSHSGR (`CSI # {`): save the SGR attributes; `attrs` lists the
+    /// ones to restore on pop (1 bold, 2 dim, 3 italic, 4 underline, 9
+    /// strikethrough, 30 foreground, 31 background), empty for all
+    fn push_sgr(&mut self, _attrs: &[u16]) {}
+    /// XTPOPSGR (`CSI # }`): restore the attributes of the latest push
+    fn pop_sgr(&mut self) {}
 
     // Bracketed paste mode
     fn set_bracketed_paste_mode(&mut self, _enable: bool) {}
@@ -167,4 +436,72 @@ pub trait AnsiGrid {
 
     // Keypad mode (Application vs Numeric)
     fn set_keypad_mode(&mut self, _application: bool) {}
+
+    // Character sets (ISO 2022)
+    /// SCS (`ESC ( F`, `ESC ) F`, `ESC * F`, `ESC + F`): designate the 94
+    /// character set with final byte `charset` ('B' US-ASCII, '0' DEC
+    /// Special Graphics, 'A' UK) as G`slot`
+    fn designate_charset(&mut self, _slot: u8, _charset: char) {}
+    /// SI / SO (and LS2 / LS3, `ESC n` / `ESC o`): invoke G`slot` into GL
+    fn invoke_charset(&mut self, _slot: u8) {}
+    /// SS2 / SS3 (`ESC N` / `ESC O`): take the next character from G`slot`
+    fn single_shift(&mut self, _slot: u8) {}
+
+    // Alternate fonts (SGR 10-20)
+    fn set_font(&mut self, _slot: u8) {}
+    fn get_font(&self) -> u8 {
+        PRIMARY_FONT
+    }
+
+    // Color queries (OSC 4 / 10 / 11 / 12 with a `?` spec)
+    /// Current value of a palette entry; `None` suppresses the reply
+    fn query_palette_color(&self, index: u8) -> Option<Color> {
+        Some(palette_color(index))
+    }
+    /// Current value of a dynamic color; `None` suppresses the reply
+    fn query_dynamic_color(&self, _which: DynamicColor) -> Option<Color> {
+        None
+    }
+
+    // Terminal capabilities (XTGETTCAP)
+    /// Value of terminfo capability `name`; `None` for unknown names
+    fn query_termcap(&self, _name: &str) -> Option<TermcapValue> {
+        None
+    }
+
+    // Response channel
+    /// Queue a reply to be written back to the PTY (query responses)
+    fn write_response(&mut self, _response: &str) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_fg_bold_is_bright() {
+        let cell = Cell {
+            fg: COLOR_PALETTE[1],
+            fg_logical: LogicalColor::Indexed(1),
+            bold: true,
+            ..Default::default()
+        };
+        assert_eq!(cell.display_fg(true), COLOR_PALETTE[9]);
+        assert_eq!(cell.display_fg(false), COLOR_PALETTE[1]);
+
+        let not_bold = Cell { bold: false, ..cell };
+        assert_eq!(not_bold.display_fg(true), COLOR_PALETTE[1]);
+
+        // Truecolor text that happens to match a palette entry is not brightened
+        let rgb = Cell { fg_logical: LogicalColor::Rgb, ..cell };
+        assert_eq!(rgb.display_fg(true), COLOR_PALETTE[1]);
+
+        // Cells default to the theme colors
+        assert_eq!(Cell::default().fg_logical, LogicalColor::Default);
+        assert_eq!(Cell::default().bg_logical, LogicalColor::Default);
+
+        // 256-color and already-bright indices are unchanged
+        let cube = Cell { fg: palette_color(100), fg_logical: LogicalColor::Indexed(100), ..cell };
+        assert_eq!(cube.display_fg(true), palette_color(100));
+    }
 }
@@ -4,10 +4,25 @@
 //! This crate provides the core ANSI parsing functionality that was originally
 //! part of the `vte-core` library, extracted as a standalone crate.
 
-pub mod color;
-pub mod grid;
-pub mod parser;
+//!
+//! # Public API
+//!
+//! Everything is exported from the crate root; the internal module layout is
+//! not part of the API. `vte-core` re-exports this crate as `vte_core::ansi`
+//! instead of defining its own cell and color types.
+//!
+//! - Parsing: [`AnsiParser`], [`AnsiError`], [`ErrorCallback`], [`ParserStats`]
+//! - Grid interface: [`AnsiGrid`], [`Cell`], [`UnderlineStyle`], [`KeyEvent`],
+//!   [`MouseEvent`], [`PRIMARY_FONT`], [`FRAKTUR_FONT`]
+//! - Colors: [`Color`], [`LogicalColor`], [`DynamicColor`], [`COLOR_PALETTE`],
+//!   [`palette_color`]
+//!
+//! The `tests/public_api.rs` integration test pins this surface.
+
+mod color;
+mod grid;
+mod parser;
 
-pub use color::{Color, COLOR_PALETTE};
-pub use grid::{AnsiGrid, Cell, KeyEvent, MouseEvent};
-pub use parser::{AnsiParser, AnsiError, ErrorCallback};
+pub use color::{palette_color, Color, DynamicColor, LogicalColor, COLOR_PALETTE};
+pub use grid::{AnsiGrid, Cell, KeyEvent, MouseEvent, OscCategory, TermcapValue, UnderlineStyle, FRAKTUR_FONT, PRIMARY_FONT};
+pub use parser::{AnsiParser, AnsiError, ErrorCallback, ParserStats};
@@ -1,7 +1,7 @@
 use std::fmt;
 use base64::prelude::*;
-use crate::color::{Color, COLOR_PALETTE};
-use crate::grid::AnsiGrid;
+use crate::color::{Color, DynamicColor};
+use crate::grid::{AnsiGrid, OscCategory, TermcapValue, UnderlineStyle, FRAKTUR_FONT, PRIMARY_FONT};
 
 /// Errors that can occur during ANSI parsing
 #[derive(Debug, Clone, PartialEq)]
@@ -43,7 +43,10 @@ pub type ErrorCallback = Box<dyn FnMut(AnsiError)>;
 // ---------- safety constants ----------
 const MAX_PARAMS: usize = 32;
 const MAX_OSC_LEN: usize = 2048;
+/// Longest sixel image body kept; larger images are dropped whole
+const MAX_SIXEL_LEN: usize = 4 * 1024 * 1024;
 const MAX_PARAM_VALUE: u16 = 9999;
+const MAX_SUBPARAMS: usize = 8;
 
 /// Parser state
 #[derive(PartialEq, Clone, Copy, Debug)]
@@ -52,7 +55,12 @@ enum AnsiState {
     Escape,
     Csi,
     Osc,
-    Charset,
+    /// Device control string (`ESC P` ... ST), collected in `osc_buffer`
+    Dcs,
+    /// SCS awaiting the final byte naming the set to designate as G0-G3
+    Charset(u8),
+    /// `ESC %` awaiting the coding system final byte (DOCS)
+    CodingSystem,
 }
 
 /// An ANSI/VT escape sequence parser that converts control sequences into actions on a display grid.
@@ -71,7 +79,7 @@ enum AnsiState {
 /// Basic usage with a simple grid:
 ///
 /// ```rust
-/// use vte_ansi::{AnsiParser, AnsiGrid};
+/// use vte_ansi::{AnsiParser, AnsiGrid, Color};
 ///
 /// struct SimpleGrid {
 ///     output: Vec<char>,
@@ -85,6 +93,26 @@ enum AnsiState {
 ///     fn put(&mut self, ch: char) { self.output.push(ch); }
 ///     fn advance(&mut self) { }
 ///     // ... implement other required methods
+///     # fn left(&mut self, _n: usize) {}
+///     # fn right(&mut self, _n: usize) {}
+///     # fn up(&mut self, _n: usize) {}
+///     # fn down(&mut self, _n: usize) {}
+///     # fn newline(&mut self) {}
+///     # fn carriage_return(&mut self) {}
+///     # fn backspace(&mut self) {}
+///     # fn move_rel(&mut self, _dx: i32, _dy: i32) {}
+///     # fn move_abs(&mut self, _row: usize, _col: usize) {}
+///     # fn clear_screen(&mut self) {}
+///     # fn clear_line(&mut self) {}
+///     # fn reset_attrs(&mut self) {}
+///     # fn set_bold(&mut self, _bold: bool) {}
+///     # fn set_italic(&mut self, _italic: bool) {}
+///     # fn set_underline(&mut self, _underline: bool) {}
+///     # fn set_dim(&mut self, _dim: bool) {}
+///     # fn set_fg(&mut self, _color: Color) {}
+///     # fn set_bg(&mut self, _color: Color) {}
+///     # fn get_fg(&self) -> Color { Color::default() }
+///     # fn get_bg(&self) -> Color { Color::default() }
 /// }
 ///
 /// // Parse colored text
@@ -93,14 +121,25 @@ enum AnsiState {
 ///
 /// parser.feed_str("Hello \x1B[31mRed\x1B[0m World!", &mut grid);
 /// // Grid now contains "Hello Red World!" with color states
+/// assert_eq!(grid.output.iter().collect::<String>(), "Hello Red World!");
 /// ```
 pub struct AnsiParser {
     state: AnsiState,
     params: Vec<u16>,
+    /// Colon-separated sub-parameters of each entry in `params` (ITU T.416 form)
+    subparams: Vec<Vec<u16>>,
     current_param: u16,
+    /// Sub-parameters collected so far for the parameter being parsed; the
+    /// first entry is the main parameter once a ':' has been seen
+    current_subparams: Vec<u16>,
     osc_buffer: String,
     in_osc_escape: bool,
+    /// The device control string being collected is sixel image data
+    /// (`DCS P1 ; P2 ; P3 q`), which may run past `MAX_OSC_LEN`
+    dcs_sixel: bool,
     private: bool, // for '?'
+    secondary: bool, // for '>', '<', '=' (xterm extensions, ignored)
+    intermediate: Option<char>, // e.g. '#' in XTPUSHSGR
     error_callback: Option<ErrorCallback>,
     // Statistics for monitoring
     stats: ParserStats,
@@ -115,6 +154,8 @@ pub struct ParserStats {
     pub errors_encountered: u64,
     pub max_params_seen: usize,
     pub max_osc_length_seen: usize,
+    /// OSC sequences passed to the grid (not dropped by `allow_osc`)
+    pub osc_dispatched: u64,
 }
 
 impl ParserStats {
@@ -123,16 +164,41 @@ impl ParserStats {
     }
 }
 
+/// Color selected by SGR 38/48
+enum ExtendedColor {
+    Indexed(u8),
+    Rgb(Color),
+    /// Palette index out of range (> 255)
+    Invalid,
+}
+
+impl ExtendedColor {
+    fn indexed(n: u16) -> Self {
+        u8::try_from(n).map_or(ExtendedColor::Invalid, ExtendedColor::Indexed)
+    }
+
+    /// Components beyond the slice are treated as 0
+    fn rgb(components: &[u16]) -> Self {
+        let channel = |k: usize| components.get(k).copied().unwrap_or(0).min(255) as f64 / 255.0;
+        ExtendedColor::Rgb(Color::rgb(channel(0), channel(1), channel(2)))
+    }
+}
+
 #[allow(clippy::new_without_default)]
 impl AnsiParser {
     pub fn new() -> Self {
         Self {
             state: AnsiState::Normal,
             params: Vec::new(),
+            subparams: Vec::new(),
             current_param: 0,
+            current_subparams: Vec::new(),
             osc_buffer: String::new(),
             in_osc_escape: false,
+            dcs_sixel: false,
             private: false,
+            secondary: false,
+            intermediate: None,
             error_callback: None,
             stats: ParserStats::default(),
             sequence_has_error: false,
@@ -209,7 +275,9 @@ impl AnsiParser {
             AnsiState::Escape => self.escape_char(ch, grid),
             AnsiState::Csi => self.csi_char(ch, grid),
             AnsiState::Osc => self.osc_char(ch, grid),
-            AnsiState::Charset => self.charset_char(ch, grid),
+            AnsiState::Dcs => self.dcs_char(ch, grid),
+            AnsiState::Charset(slot) => self.charset_char(slot, ch, grid),
+            AnsiState::CodingSystem => self.coding_system_char(ch, grid),
         }
     }
 
@@ -218,7 +286,11 @@ impl AnsiParser {
             '\x1B' => self.state = AnsiState::Escape,
             '\n' => grid.newline(),
             '\r' => grid.carriage_return(),
+            '\x07' => grid.bell(),
             '\x08' => grid.backspace(),
+            // SO / SI: switch GL between G1 and G0
+            '\x0E' => grid.invoke_charset(1),
+            '\x0F' => grid.invoke_charset(0),
             '\t' => {
                 for _ in 0..4 {
                     grid.put(' ');
@@ -237,9 +309,10 @@ impl AnsiParser {
         match ch {
             '[' => {
                 self.state = AnsiState::Csi;
-                self.params.clear();
-                self.current_param = 0;
+                self.clear_params();
                 self.private = false;
+                self.secondary = false;
+                self.intermediate = None;
                 self.sequence_has_error = false;
             }
             ']' => {
@@ -247,21 +320,41 @@ impl AnsiParser {
                 self.osc_buffer.clear();
                 self.in_osc_escape = false;
             }
+            'P' => {
+                self.state = AnsiState::Dcs;
+                self.osc_buffer.clear();
+                self.in_osc_escape = false;
+                self.dcs_sixel = false;
+            }
             '(' => {
                 // ESC (<designator> - designate G0 character set
-                self.state = AnsiState::Charset;
+                self.state = AnsiState::Charset(0);
             }
             ')' => {
                 // ESC )<designator> - designate G1 character set
-                self.state = AnsiState::Charset;
+                self.state = AnsiState::Charset(1);
             }
             '*' => {
                 // ESC *<designator> - designate G2 character set
-                self.state = AnsiState::Charset;
+                self.state = AnsiState::Charset(2);
             }
             '+' => {
                 // ESC +<designator> - designate G3 character set
-                self.state = AnsiState::Charset;
+                self.state = AnsiState::Charset(3);
+            }
+            // SS2 / SS3: one character from G2 / G3
+            'N' | 'O' => {
+                grid.single_shift(if ch == 'N' { 2 } else { 3 });
+                self.state = AnsiState::Normal;
+            }
+            // LS2 / LS3: G2 / G3 into GL
+            'n' | 'o' => {
+                grid.invoke_charset(if ch == 'n' { 2 } else { 3 });
+                self.state = AnsiState::Normal;
+            }
+            '%' => {
+                // ESC %<final> - select coding system (DOCS)
+                self.state = AnsiState::CodingSystem;
             }
             '7' => {
                 grid.save_cursor();
@@ -273,7 +366,9 @@ impl AnsiParser {
             }
             'c' => {
                 grid.reset_attrs();
+                grid.set_protected(false);
                 grid.clear_screen();
+                grid.full_reset();
                 self.state = AnsiState::Normal;
             }
             'D' => {
@@ -289,6 +384,11 @@ impl AnsiParser {
                 grid.up(1);
                 self.state = AnsiState::Normal;
             }
+            // SPA / EPA: start and end of a protected area
+            'V' | 'W' => {
+                grid.set_protected(ch == 'V');
+                self.state = AnsiState::Normal;
+            }
             '=' => {
                 grid.set_keypad_mode(true);
                 self.state = AnsiState::Normal;
@@ -330,31 +430,104 @@ impl AnsiParser {
                             count: self.params.len() + 1,
                         });
                     }
+                    self.current_subparams.clear();
                 } else {
-                    self.params.push(self.current_param);
+                    self.push_param();
+                }
+                self.current_param = 0;
+            }
+            ':' => {
+                // Sub-parameter separator; extra sub-parameters are dropped
+                if self.current_subparams.len() < MAX_SUBPARAMS {
+                    self.current_subparams.push(self.current_param);
                 }
                 self.current_param = 0;
             }
             '?' => self.private = true,
+            '>' | '<' | '=' => self.secondary = true,
+            ' '..='/' => self.intermediate = Some(ch),
             _ => {
                 if self.params.len() < MAX_PARAMS
-                    && (self.current_param > 0 || self.params.is_empty())
+                    && (self.current_param > 0 || self.params.is_empty() || !self.current_subparams.is_empty())
                 {
-                    self.params.push(self.current_param);
+                    self.push_param();
                 }
 
                 self.stats.sequences_processed += 1;
                 self.stats.max_params_seen = self.stats.max_params_seen.max(self.params.len());
 
-                self.execute_csi(ch, grid);
+                // Sequences like XTMODKEYS (`CSI > 4 ; 2 m`) must not reach
+                // the standard handlers that share their final byte
+                if let Some(intermediate) = self.intermediate {
+                    self.execute_csi_intermediate(intermediate, ch, grid);
+                } else if !self.secondary {
+                    self.execute_csi(ch, grid);
+                }
                 self.state = AnsiState::Normal;
-                self.params.clear();
-                self.current_param = 0;
+                self.clear_params();
                 self.private = false;
+                self.secondary = false;
+                self.intermediate = None;
             }
         }
     }
 
+    /// Finish the parameter being parsed, splitting off its sub-parameters
+    fn push_param(&mut self) {
+        if self.current_subparams.is_empty() {
+            self.params.push(self.current_param);
+            self.subparams.push(Vec::new());
+        } else {
+            let mut subs = std::mem::take(&mut self.current_subparams);
+            if subs.len() < MAX_SUBPARAMS {
+                subs.push(self.current_param);
+            }
+            let main = subs.remove(0);
+            self.params.push(main);
+            self.subparams.push(subs);
+        }
+    }
+
+    fn clear_params(&mut self) {
+        self.params.clear();
+        self.subparams.clear();
+        self.current_param = 0;
+        self.current_subparams.clear();
+    }
+
+    /// CSI sequences with an intermediate byte before the final byte; unknown
+    /// ones are ignored rather than mistaken for the plain sequence
+    fn execute_csi_intermediate(&mut self, intermediate: char, ch: char, grid: &mut dyn AnsiGrid) {
+        match (intermediate, ch) {
+            // XTPUSHSGR / XTPOPSGR
+            ('#', '{' | 'p') => {
+                let attrs: Vec<u16> = self.params.iter().copied().filter(|&p| p != 0).collect();
+                grid.push_sgr(&attrs);
+            }
+            ('#', '}' | 'q') => grid.pop_sgr(),
+            // DECSTR
+            ('!', 'p') => {
+                grid.reset_attrs();
+                grid.set_protected(false);
+                grid.soft_reset();
+            }
+            // DECIC / DECDC; a missing or zero count means one column
+            ('\'', '}') => grid.insert_columns(self.get_param(0, 1).max(1)),
+            ('\'', '~') => grid.delete_columns(self.get_param(0, 1).max(1)),
+            // DEC locator: DECELR, DECSLE, DECRQLP
+            ('\'', 'z') => grid.set_locator_reporting(self.get_param(0, 0) as u16, self.get_param(1, 0) == 1),
+            ('\'', '{') => grid.select_locator_events(&self.params),
+            ('\'', '|') => grid.request_locator_position(),
+            // DECSCA: 1 protects, 0 and 2 do not
+            ('"', 'q') => match self.get_param(0, 0) {
+                1 => grid.set_protected(true),
+                0 | 2 => grid.set_protected(false),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
     fn execute_csi(&mut self, ch: char, grid: &mut dyn AnsiGrid) {
         match ch {
             'A' => grid.up(self.get_param(0, 1)),
@@ -366,10 +539,13 @@ impl AnsiParser {
                 let col = self.get_param(1, 1).saturating_sub(1);
                 grid.move_abs(row, col);
             }
+            'J' if self.private => grid.selective_erase_display(self.get_param(0, 0)),
+            'K' if self.private => grid.selective_erase_line(self.get_param(0, 0)),
             'J' => match self.get_param(0, 0) {
                 0 => grid.clear_screen_down(),
                 1 => grid.clear_screen_up(),
                 2 => grid.clear_screen(),
+                3 => grid.clear_scrollback(),
                 _ => {}
             },
             'K' => match self.get_param(0, 0) {
@@ -387,15 +563,19 @@ impl AnsiParser {
             'h' if self.private => {
                 match self.params.first() {
                     Some(&1) => grid.set_application_cursor_keys(true),
+                    Some(&12) => grid.set_cursor_blink(true),
                     Some(&25) => grid.set_cursor_visible(true),
                     Some(&47) => grid.use_alternate_screen(true),
                     Some(&1049) => grid.use_alternate_screen(true),
                     Some(&7) => grid.set_auto_wrap(true),
+                    Some(&45) => grid.set_reverse_wraparound(true),
                     Some(&1000) => grid.set_mouse_reporting_mode(1000, true),
                     Some(&1002) => grid.set_mouse_reporting_mode(1002, true),
+                    Some(&1003) => grid.set_mouse_reporting_mode(1003, true),
                     Some(&1005) => grid.set_mouse_reporting_mode(1005, true),
                     Some(&1006) => grid.set_mouse_reporting_mode(1006, true),
                     Some(&1004) => grid.set_focus_reporting(true),
+                    Some(&2031) => grid.set_color_scheme_reporting(true),
                     Some(&2004) => grid.set_bracketed_paste_mode(true),
                     Some(&6) => grid.set_origin_mode(true), // DECOM - DEC Origin Mode
                     _ => {}
@@ -404,28 +584,26 @@ impl AnsiParser {
             'l' if self.private => {
                 match self.params.first() {
                     Some(&1) => grid.set_application_cursor_keys(false),
+                    Some(&12) => grid.set_cursor_blink(false),
                     Some(&25) => grid.set_cursor_visible(false),
                     Some(&47) => grid.use_alternate_screen(false),
                     Some(&1049) => grid.use_alternate_screen(false),
                     Some(&7) => grid.set_auto_wrap(false),
+                    Some(&45) => grid.set_reverse_wraparound(false),
                     Some(&1000) => grid.set_mouse_reporting_mode(1000, false),
                     Some(&1002) => grid.set_mouse_reporting_mode(1002, false),
+                    Some(&1003) => grid.set_mouse_reporting_mode(1003, false),
                     Some(&1005) => grid.set_mouse_reporting_mode(1005, false),
                     Some(&1006) => grid.set_mouse_reporting_mode(1006, false),
                     Some(&1004) => grid.set_focus_reporting(false),
+                    Some(&2031) => grid.set_color_scheme_reporting(false),
+                    Some(&2004) => grid.set_bracketed_paste_mode(false),
                     _ => {}
                 }
             }
-            'h' => {
-                if self.params.first() == Some(&4) {
-                    grid.set_insert_mode(true);
-                }
-            }
-            'l' => {
-                if self.params.first() == Some(&4) {
-                    grid.set_insert_mode(false);
-                }
-            }
+            'n' if self.private && self.params.first() == Some(&996) => grid.report_color_scheme(),
+            'h' if self.params.first() == Some(&4) => grid.set_insert_mode(true),
+            'l' if self.params.first() == Some(&4) => grid.set_insert_mode(false),
             'S' => grid.scroll_up(self.get_param(0, 1)),
             'T' => grid.scroll_down(self.get_param(0, 1)),
             's' => grid.save_cursor(),
@@ -434,15 +612,35 @@ impl AnsiParser {
         }
     }
 
-    fn charset_char(&mut self, _ch: char, _grid: &mut dyn AnsiGrid) {
-        // Character set designation: ESC <designator> <charset>
-        // Parsed but not processed - character set handling is implementation-specific
-        // and should be done at the Grid level through translation tables
-        self.state = AnsiState::Normal;
+    fn charset_char(&mut self, slot: u8, ch: char, grid: &mut dyn AnsiGrid) {
+        // Character set designation: ESC <designator> <charset>; the grid
+        // does the translation. Intermediates of multi-byte designators
+        // (e.g. ESC ( % 5) are skipped and the set is named by its final byte
+        if !matches!(ch, ' '..='/') {
+            grid.designate_charset(slot, ch);
+            self.state = AnsiState::Normal;
+        }
+    }
+
+    fn coding_system_char(&mut self, ch: char, _grid: &mut dyn AnsiGrid) {
+        // ESC % G (UTF-8) and ESC % @ (ISO 2022 default) change how bytes
+        // are decoded, which happens before parsing (vte-core's
+        // `StreamDecoder`); here the sequence, with any intermediates as in
+        // ESC % / F, is only consumed
+        if !matches!(ch, ' '..='/') {
+            self.state = AnsiState::Normal;
+        }
     }
 
     fn osc_char(&mut self, ch: char, grid: &mut dyn AnsiGrid) {
-        if self.osc_buffer.len() >= MAX_OSC_LEN {
+        // The limit is in bytes, checked before whole characters are added
+        let added = match ch {
+            '\\' if self.in_osc_escape => 0,
+            _ if self.in_osc_escape => 1 + ch.len_utf8(),
+            '\x1B' | '\x07' => 0,
+            _ => ch.len_utf8(),
+        };
+        if self.osc_buffer.len() + added > MAX_OSC_LEN {
             self.report_error(AnsiError::OscTooLong { length: self.osc_buffer.len() });
             self.state = AnsiState::Normal;
             return;
@@ -451,7 +649,7 @@ impl AnsiParser {
         
         if self.in_osc_escape {
             if ch == '\\' {
-                self.finish_osc(grid);
+                self.finish_osc(grid, "\x1B\\");
             } else {
                 self.osc_buffer.push('\x1B');
                 self.osc_buffer.push(ch);
@@ -460,21 +658,118 @@ impl AnsiParser {
         } else if ch == '\x1B' {
             self.in_osc_escape = true;
         } else if ch == '\x07' {
-            self.finish_osc(grid);
+            self.finish_osc(grid, "\x07");
         } else {
             self.osc_buffer.push(ch);
         }
     }
 
-    fn finish_osc(&mut self, grid: &mut dyn AnsiGrid) {
+    /// Collect a device control string up to ST; overlong strings are dropped
+    /// whole. Sixel images get a larger limit than other strings.
+    fn dcs_char(&mut self, ch: char, grid: &mut dyn AnsiGrid) {
+        let limit = if self.dcs_sixel { MAX_SIXEL_LEN } else { MAX_OSC_LEN };
+        if self.in_osc_escape {
+            self.in_osc_escape = false;
+            if ch == '\\' {
+                if self.osc_buffer.len() <= limit {
+                    self.finish_dcs(grid);
+                }
+                self.osc_buffer.clear();
+                if self.dcs_sixel {
+                    // Do not hold on to an image-sized buffer
+                    self.osc_buffer.shrink_to(MAX_OSC_LEN);
+                }
+                self.state = AnsiState::Normal;
+                return;
+            }
+        }
+        if ch == '\x1B' {
+            self.in_osc_escape = true;
+        } else if self.osc_buffer.len() <= limit {
+            if ch == 'q' && !self.dcs_sixel {
+                self.dcs_sixel = self.osc_buffer.bytes().all(|b| b.is_ascii_digit() || b == b';');
+            }
+            self.osc_buffer.push(ch);
+        }
+    }
+
+    fn finish_dcs(&mut self, grid: &mut dyn AnsiGrid) {
+        if !grid.allow_dcs(&self.osc_buffer) {
+            return;
+        }
+        // Sixel data may contain '|', so it is told apart first
+        if self.dcs_sixel {
+            if let Some((params, data)) = self.osc_buffer.split_once('q') {
+                grid.sixel(params, data);
+            }
+        } else if let Some(names) = self.osc_buffer.strip_prefix("+q") {
+            let names = names.to_string();
+            self.handle_xtgettcap(&names, grid);
+        } else if let Some((params, definitions)) = self.osc_buffer.split_once('|') {
+            if params.bytes().all(|b| b.is_ascii_digit() || b == b';') {
+                Self::handle_decudk(params, definitions, grid);
+            }
+        }
+    }
+
+    /// DECUDK (`DCS Pc ; Pl | key/hex ; ... ST`); Pc and Pl default to 0,
+    /// which clear the old definitions and lock the new ones. Definitions
+    /// that are not `number/hex` are skipped.
+    fn handle_decudk(params: &str, definitions: &str, grid: &mut dyn AnsiGrid) {
+        let mut params = params.split(';').map(|p| p.parse::<u16>().unwrap_or(0));
+        let clear = params.next().unwrap_or(0) == 0;
+        let lock = params.next().unwrap_or(0) == 0;
+        let keys: Vec<(u16, String)> = definitions
+            .split(';')
+            .filter_map(|definition| {
+                let (key, hex) = definition.split_once('/')?;
+                Some((key.parse().ok()?, hex_decode(hex)?))
+            })
+            .collect();
+        grid.define_user_keys(&keys, clear, lock);
+    }
+
+    /// XTGETTCAP (`DCS + q` hex names ST): answer each capability with
+    /// `DCS 1 + r name=value ST`, hex-encoded; stop at the first unknown one,
+    /// answered with `DCS 0 + r name ST`
+    fn handle_xtgettcap(&mut self, names: &str, grid: &mut dyn AnsiGrid) {
+        for hex_name in names.split(';') {
+            let name = hex_decode(hex_name);
+            match name.as_deref().and_then(|name| grid.query_termcap(name)) {
+                Some(TermcapValue::Flag) => grid.write_response(&format!("\x1BP1+r{}\x1B\\", hex_name)),
+                Some(TermcapValue::Number(n)) => {
+                    grid.write_response(&format!("\x1BP1+r{}={}\x1B\\", hex_name, hex_encode(&n.to_string())));
+                }
+                Some(TermcapValue::String(value)) => {
+                    grid.write_response(&format!("\x1BP1+r{}={}\x1B\\", hex_name, hex_encode(&value)));
+                }
+                None => {
+                    grid.write_response(&format!("\x1BP0+r{}\x1B\\", hex_name));
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Dispatch a complete OSC string; `terminator` (BEL or ST) is echoed in replies
+    fn finish_osc(&mut self, grid: &mut dyn AnsiGrid, terminator: &str) {
         let buffer = self.osc_buffer.clone();
         if let Some((num, text)) = buffer.split_once(';') {
+            // Policy-controlled sequences are checked centrally, before any handler runs
+            let allowed = match osc_category(num, text) {
+                Some(category) => grid.allow_osc(category, &buffer),
+                None => true,
+            };
+            if allowed {
+                self.stats.osc_dispatched += 1;
+            }
             match num {
+                _ if !allowed => {}
                 "0" | "2" => {
                     grid.set_title(text);
                 }
                 "52" => {
-                    self.handle_clipboard_osc(text, grid);
+                    self.handle_clipboard_osc(text, grid, terminator);
                 }
                 "7" => {
                     grid.set_current_directory(text);
@@ -482,7 +777,40 @@ impl AnsiParser {
                 "8" => {
                     self.handle_hyperlink_osc(text, grid);
                 }
-                _ => {}
+                "22" => {
+                    grid.set_pointer_shape(text);
+                }
+                "7777" => {
+                    grid.handle_active_region(text);
+                }
+                "133" => {
+                    let mut chars = text.chars();
+                    if let Some(kind) = chars.next() {
+                        let params = chars.as_str();
+                        grid.handle_semantic_prompt(kind, params.strip_prefix(';').unwrap_or(params));
+                    }
+                }
+                "4" => {
+                    self.handle_palette_osc(text, grid, terminator);
+                }
+                "10" | "11" | "12" => {
+                    let code = num.parse().unwrap_or_default();
+                    self.handle_dynamic_color_osc(code, text, grid, terminator);
+                }
+                "9" if !is_conemu_command(text) => {
+                    grid.show_notification("", text);
+                }
+                "777" => {
+                    if let Some(notification) = text.strip_prefix("notify;") {
+                        let (title, body) = notification.split_once(';').unwrap_or((notification, ""));
+                        grid.show_notification(title, body);
+                    }
+                }
+                _ => {
+                    if let Some(code) = custom_osc_code(num) {
+                        grid.handle_custom_osc(code, text);
+                    }
+                }
             }
         }
         self.state = AnsiState::Normal;
@@ -490,8 +818,17 @@ impl AnsiParser {
         self.in_osc_escape = false;
     }
 
-    fn handle_clipboard_osc(&mut self, text: &str, grid: &mut dyn AnsiGrid) {
+    /// OSC 52 ; selection ; data - set the clipboard to base64 `data`, or
+    /// request its contents with `?`
+    fn handle_clipboard_osc(&mut self, text: &str, grid: &mut dyn AnsiGrid, terminator: &str) {
         if let Some((clipboard_type, data)) = text.split_once(';') {
+            if data == "?" {
+                // Selection names: clipboard, primary, secondary, select, cut buffers
+                if clipboard_type.chars().all(|c| matches!(c, 'c' | 'p' | 'q' | 's' | '0'..='7')) {
+                    grid.request_clipboard(clipboard_type, terminator);
+                }
+                return;
+            }
             if let Ok(clipboard_id) = clipboard_type.parse::<u8>() {
                 if clipboard_id <= 1 {
                     if let Ok(decoded) = BASE64_STANDARD.decode(data) {
@@ -504,6 +841,39 @@ impl AnsiParser {
         }
     }
 
+    /// OSC 4 ; index ; spec [; index ; spec ...] - answer `?` specs with the
+    /// current palette entry. Setting palette entries is not supported.
+    fn handle_palette_osc(&mut self, text: &str, grid: &mut dyn AnsiGrid, terminator: &str) {
+        let mut parts = text.split(';');
+        while let (Some(index), Some(spec)) = (parts.next(), parts.next()) {
+            if spec != "?" {
+                continue;
+            }
+            let Ok(index) = index.parse::<u8>() else {
+                continue;
+            };
+            if let Some(color) = grid.query_palette_color(index) {
+                grid.write_response(&format!("\x1B]4;{};{}{}", index, color.to_x11_rgb(), terminator));
+            }
+        }
+    }
+
+    /// OSC 10 / 11 / 12 ; spec [; spec ...] - answer `?` specs with the current
+    /// dynamic color. Additional specs address the following dynamic colors.
+    fn handle_dynamic_color_osc(&mut self, code: u16, text: &str, grid: &mut dyn AnsiGrid, terminator: &str) {
+        for (offset, spec) in text.split(';').enumerate() {
+            let Some(which) = DynamicColor::from_osc(code + offset as u16) else {
+                break;
+            };
+            if spec != "?" {
+                continue;
+            }
+            if let Some(color) = grid.query_dynamic_color(which) {
+                grid.write_response(&format!("\x1B]{};{}{}", which.osc_code(), color.to_x11_rgb(), terminator));
+            }
+        }
+    }
+
     fn handle_hyperlink_osc(&mut self, text: &str, grid: &mut dyn AnsiGrid) {
         if let Some((params, uri)) = text.split_once(';') {
             let params = if params.is_empty() { None } else { Some(params) };
@@ -524,100 +894,130 @@ impl AnsiParser {
                 1 => grid.set_bold(true),
                 2 => grid.set_dim(true),
                 3 => grid.set_italic(true),
-                4 => grid.set_underline(true),
+                4 => match self.subparams[i].first() {
+                    // 4:n selects the underline shape; unknown shapes are ignored
+                    Some(&n) => {
+                        if let Some(style) = UnderlineStyle::from_sgr(n) {
+                            grid.set_underline_style(style);
+                        }
+                    }
+                    None => grid.set_underline(true),
+                },
+                // Double underline, as in xterm and VTE (the Linux console's
+                // "bold off" reading is not followed); 24 clears it
+                21 => grid.set_underline_style(UnderlineStyle::Double),
+                // Normal intensity: clears bold and dim, leaves underline
                 22 => {
                     grid.set_bold(false);
                     grid.set_dim(false);
                 }
-                23 => grid.set_italic(false),
-                24 => grid.set_underline(false),
-                30..=37 => grid.set_fg(ansi_color(param - 30)),
-                38 => {
-                    if i + 1 < self.params.len() {
-                        match self.params[i + 1] {
-                            5 if i + 2 < self.params.len() => {
-                                let idx = self.params[i + 2];
-                                grid.set_fg(ansi_256_color(idx));
-                                i += 2;
-                            }
-                            2 => {
-                                let r = self.params.get(i + 2).copied().unwrap_or(0).min(255) as f64 / 255.0;
-                                let g = self.params.get(i + 3).copied().unwrap_or(0).min(255) as f64 / 255.0;
-                                let b = self.params.get(i + 4).copied().unwrap_or(0).min(255) as f64 / 255.0;
-                                grid.set_fg(Color::rgb(r, g, b));
-                                i += 4;
-                            }
-                            _ => {}
-                        }
+                9 => grid.set_strikethrough(true),
+                10 => grid.set_font(PRIMARY_FONT),
+                11..=19 => grid.set_font((param - 10) as u8),
+                20 => grid.set_font(FRAKTUR_FONT),
+                23 => {
+                    // "Neither italic nor Fraktur"
+                    grid.set_italic(false);
+                    if grid.get_font() == FRAKTUR_FONT {
+                        grid.set_font(PRIMARY_FONT);
                     }
                 }
-                39 => grid.set_fg(Color::default()),
-                40..=47 => grid.set_bg(ansi_color(param - 40)),
-                48 => {
-                    if i + 1 < self.params.len() {
-                        match self.params[i + 1] {
-                            5 if i + 2 < self.params.len() => {
-                                let idx = self.params[i + 2];
-                                grid.set_bg(ansi_256_color(idx));
-                                i += 2;
-                            }
-                            2 => {
-                                let r = self.params.get(i + 2).copied().unwrap_or(0).min(255) as f64 / 255.0;
-                                let g = self.params.get(i + 3).copied().unwrap_or(0).min(255) as f64 / 255.0;
-                                let b = self.params.get(i + 4).copied().unwrap_or(0).min(255) as f64 / 255.0;
-                                grid.set_bg(Color::rgb(r, g, b));
-                                i += 4;
-                            }
-                            _ => {}
-                        }
-                    }
-                }
-                49 => grid.set_bg(Color::rgb(0.0, 0.0, 0.0)),
-                90..=97 => grid.set_fg(ansi_bright_color(param - 90)),
-                100..=107 => grid.set_bg(ansi_bright_color(param - 100)),
+                24 => grid.set_underline(false),
+                29 => grid.set_strikethrough(false),
+                30..=37 => grid.set_fg_indexed((param - 30) as u8),
+                38 => match self.extended_color(&mut i) {
+                    Some(ExtendedColor::Indexed(idx)) => grid.set_fg_indexed(idx),
+                    Some(ExtendedColor::Rgb(color)) => grid.set_fg(color),
+                    Some(ExtendedColor::Invalid) => grid.set_fg(Color::default()),
+                    None => {}
+                },
+                39 => grid.set_fg_default(),
+                40..=47 => grid.set_bg_indexed((param - 40) as u8),
+                48 => match self.extended_color(&mut i) {
+                    Some(ExtendedColor::Indexed(idx)) => grid.set_bg_indexed(idx),
+                    Some(ExtendedColor::Rgb(color)) => grid.set_bg(color),
+                    Some(ExtendedColor::Invalid) => grid.set_bg(Color::default()),
+                    None => {}
+                },
+                49 => grid.set_bg_default(),
+                53 => grid.set_overline(true),
+                55 => grid.set_overline(false),
+                // Decoration color; an invalid one follows the foreground
+                58 => match self.extended_color(&mut i) {
+                    Some(ExtendedColor::Indexed(idx)) => grid.set_decoration_indexed(idx),
+                    Some(ExtendedColor::Rgb(color)) => grid.set_decoration_color(Some(color)),
+                    Some(ExtendedColor::Invalid) => grid.set_decoration_color(None),
+                    None => {}
+                },
+                59 => grid.set_decoration_color(None),
+                90..=97 => grid.set_fg_indexed((param - 90 + 8) as u8),
+                100..=107 => grid.set_bg_indexed((param - 100 + 8) as u8),
                 _ => {}
             }
             i += 1;
         }
     }
 
+    /// Decode the color following SGR 38/48 at `params[*i]`
+    ///
+    /// Handles the colon forms `38:5:n`, `38:2::r:g:b` (with the optional
+    /// color space id) and `38:2:r:g:b`, and the semicolon forms `38;5;n` and
+    /// `38;2;r;g;b`. For the semicolon forms `*i` is advanced past the
+    /// consumed parameters.
+    fn extended_color(&self, i: &mut usize) -> Option<ExtendedColor> {
+        let subs = &self.subparams[*i];
+        if !subs.is_empty() {
+            return match subs[0] {
+                5 => subs.get(1).map(|&n| ExtendedColor::indexed(n)),
+                2 => {
+                    let rgb = if subs.len() >= 5 { &subs[2..5] } else { &subs[1..] };
+                    Some(ExtendedColor::rgb(rgb))
+                }
+                _ => None,
+            };
+        }
+
+        match self.params.get(*i + 1) {
+            Some(5) if *i + 2 < self.params.len() => {
+                let n = self.params[*i + 2];
+                *i += 2;
+                Some(ExtendedColor::indexed(n))
+            }
+            Some(2) => {
+                let end = (*i + 5).min(self.params.len());
+                let color = ExtendedColor::rgb(&self.params[*i + 2..end]);
+                *i += 4;
+                Some(color)
+            }
+            _ => None,
+        }
+    }
+
     fn get_param(&self, idx: usize, default: u16) -> usize {
         self.params.get(idx).copied().unwrap_or(default) as usize
     }
 }
 
-// ---------- helper functions ----------
+// ---------- helper functions (palette lookups used by tests) ----------
+#[cfg(test)]
 fn ansi_color(idx: u16) -> Color {
-    COLOR_PALETTE
+    crate::color::COLOR_PALETTE
         .get(idx as usize & 7)
         .copied()
         .unwrap_or_default()
 }
 
+#[cfg(test)]
 fn ansi_bright_color(idx: u16) -> Color {
-    COLOR_PALETTE
+    crate::color::COLOR_PALETTE
         .get((idx as usize & 7) + 8)
         .copied()
         .unwrap_or_default()
 }
 
+#[cfg(test)]
 fn ansi_256_color(index: u16) -> Color {
-    match index {
-        0..=7 => ansi_color(index),
-        8..=15 => ansi_bright_color(index - 8),
-        16..=231 => {
-            let idx = index - 16;
-            let r = (idx / 36) % 6;
-            let g = (idx / 6) % 6;
-            let b = idx % 6;
-            Color::rgba(r as f64 / 5.0, g as f64 / 5.0, b as f64 / 5.0, 1.0)
-        }
-        232..=255 => {
-            let gray = (index - 232) as f64 / 23.0;
-            Color::rgba(gray, gray, gray, 1.0)
-        }
-        _ => Color::default(),
-    }
+    u8::try_from(index).map_or_else(|_| Color::default(), crate::color::palette_color)
 }
 
 // ---------- UTF-8 utilities ----------
@@ -635,6 +1035,60 @@ fn decode_utf8(buf: &[u8]) -> (char, usize) {
     }
 }
 
+// ---------- OSC classification ----------
+/// Policy category of OSC `num` with data `text`; `None` for sequences that
+/// are always handled or ignored
+fn osc_category(num: &str, text: &str) -> Option<OscCategory> {
+    match num {
+        "0" | "2" => Some(OscCategory::Title),
+        "52" if text.split_once(';').is_some_and(|(_, data)| data == "?") => Some(OscCategory::ClipboardRead),
+        "52" => Some(OscCategory::ClipboardWrite),
+        "8" => Some(OscCategory::Hyperlink),
+        "7" => Some(OscCategory::CurrentDirectory),
+        "4" | "10" | "11" | "12" => Some(OscCategory::Colors),
+        "22" => Some(OscCategory::PointerShape),
+        "7777" => Some(OscCategory::ActiveRegion),
+        "9" if !is_conemu_command(text) => Some(OscCategory::Notification),
+        "777" if text.starts_with("notify;") => Some(OscCategory::Notification),
+        _ => custom_osc_code(num).map(|_| OscCategory::Custom),
+    }
+}
+
+/// ConEmu's OSC 9 subcommands (`9;4;...` progress and the like), which are
+/// not notifications
+fn is_conemu_command(text: &str) -> bool {
+    text.split_once(';').is_some_and(|(command, _)| !command.is_empty() && command.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Number of an OSC without a well-known meaning: outside xterm's 0-119 and
+/// the shell integration, notification and image extensions of other
+/// terminals
+fn custom_osc_code(num: &str) -> Option<u16> {
+    if num.bytes().any(|b| !b.is_ascii_digit()) {
+        return None;
+    }
+    match num.parse().ok()? {
+        0..=119 | 133 | 633 | 777 | 1337 | 7777 => None,
+        code => Some(code),
+    }
+}
+
+// ---------- hex utilities (XTGETTCAP) ----------
+fn hex_encode(text: &str) -> String {
+    text.bytes().map(|b| format!("{:02X}", b)).collect()
+}
+
+fn hex_decode(hex: &str) -> Option<String> {
+    let bytes = hex.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let pair = std::str::from_utf8(pair).ok().filter(|pair| pair.len() == 2)?;
+            u8::from_str_radix(pair, 16).ok()
+        })
+        .collect::<Option<Vec<u8>>>()?;
+    String::from_utf8(bytes).ok()
+}
+
 // ---------- tests ----------
 #[cfg(test)]
 mod tests {
@@ -661,6 +1115,14 @@ mod tests {
         auto_wrap: bool,
         line_ops: Vec<String>,  // Tracks insert/delete lines
         char_ops: Vec<String>,  // Tracks insert/delete/erase chars
+        font: u8,
+        underline_style: UnderlineStyle,
+        strikethrough: bool,
+        overline: bool,
+        decoration: Option<Color>,
+        responses: Vec<String>,
+        denied_osc: Vec<OscCategory>,
+        deny_dcs: bool,
     }
     
     impl MockGrid {
@@ -682,6 +1144,14 @@ mod tests {
                 auto_wrap: true,
                 line_ops: Vec::new(),
                 char_ops: Vec::new(),
+                font: 0,
+                underline_style: UnderlineStyle::None,
+                strikethrough: false,
+                overline: false,
+                decoration: None,
+                responses: Vec::new(),
+                denied_osc: Vec::new(),
+                deny_dcs: false,
             }
         }
     }
@@ -726,8 +1196,8 @@ mod tests {
             self.left(1);
         }
         fn move_rel(&mut self, dx: i32, dy: i32) {
-            self.cursor_col = ((self.cursor_col as i32 + dx) as usize).max(0);
-            self.cursor_row = ((self.cursor_row as i32 + dy) as usize).max(0);
+            self.cursor_col = (self.cursor_col as i32 + dx).max(0) as usize;
+            self.cursor_row = (self.cursor_row as i32 + dy).max(0) as usize;
         }
         fn move_abs(&mut self, row: usize, col: usize) {
             self.cursor_row = row;
@@ -735,6 +1205,8 @@ mod tests {
         }
         fn clear_screen(&mut self) { self.output.push_str("[CLEAR]"); }
         fn clear_line(&mut self) { self.output.push_str("[CLEAR_LINE]"); }
+        fn clear_scrollback(&mut self) { self.output.push_str("[CLEAR_SCROLLBACK]"); }
+        fn set_cursor_blink(&mut self, enable: bool) { self.output.push_str(&format!("[BLINK {}]", enable)); }
         fn reset_attrs(&mut self) {
             self.fg = Color::default();
             self.bg = Color::rgb(0., 0., 0.);
@@ -745,8 +1217,14 @@ mod tests {
         }
         fn set_bold(&mut self, v: bool) { self.bold = v; }
         fn set_italic(&mut self, v: bool) { self.italic = v; }
-        fn set_underline(&mut self, v: bool) { self.underline = v; }
+        fn set_underline(&mut self, v: bool) {
+            self.underline = v;
+            self.underline_style = if v { UnderlineStyle::Single } else { UnderlineStyle::None };
+        }
         fn set_dim(&mut self, v: bool) { self.dim = v; }
+        fn set_strikethrough(&mut self, v: bool) { self.strikethrough = v; }
+        fn set_overline(&mut self, v: bool) { self.overline = v; }
+        fn set_decoration_color(&mut self, c: Option<Color>) { self.decoration = c; }
         fn set_fg(&mut self, c: Color) { self.fg = c; }
         fn set_bg(&mut self, c: Color) { self.bg = c; }
         fn set_title(&mut self, t: &str) { self.output.push_str(&format!("[TITLE: {}]", t)); }
@@ -805,6 +1283,9 @@ mod tests {
             self.auto_wrap = enable;
             self.output.push_str(if enable { "[AUTO_WRAP_ON]" } else { "[AUTO_WRAP_OFF]" });
         }
+        fn set_reverse_wraparound(&mut self, enable: bool) {
+            self.output.push_str(&format!("[REVERSE_WRAP {}]", enable));
+        }
 
         // Phase-2 DEC private modes
         fn set_application_cursor_keys(&mut self, _enable: bool) {
@@ -823,6 +1304,120 @@ mod tests {
         fn set_keypad_mode(&mut self, application: bool) {
             self.output.push_str(&format!("[KEYPAD_MODE_{}]", if application { "APPLICATION" } else { "NUMERIC" }));
         }
+
+        fn set_underline_style(&mut self, style: UnderlineStyle) {
+            self.underline_style = style;
+            self.underline = style != UnderlineStyle::None;
+        }
+        fn designate_charset(&mut self, slot: u8, charset: char) {
+            self.output.push_str(&format!("[G{}={}]", slot, charset));
+        }
+        fn invoke_charset(&mut self, slot: u8) {
+            self.output.push_str(&format!("[GL=G{}]", slot));
+        }
+        fn single_shift(&mut self, slot: u8) {
+            self.output.push_str(&format!("[SS{}]", slot));
+        }
+
+        fn set_font(&mut self, slot: u8) { self.font = slot; }
+        fn get_font(&self) -> u8 { self.font }
+
+        fn query_dynamic_color(&self, which: DynamicColor) -> Option<Color> {
+            match which {
+                DynamicColor::Foreground => Some(self.fg),
+                DynamicColor::Background => Some(self.bg),
+                DynamicColor::Cursor => None,
+            }
+        }
+        fn write_response(&mut self, response: &str) { self.responses.push(response.to_string()); }
+        fn handle_semantic_prompt(&mut self, kind: char, params: &str) {
+            self.output.push_str(&format!("[PROMPT {} {}]", kind, params));
+        }
+        fn set_pointer_shape(&mut self, name: &str) {
+            self.output.push_str(&format!("[POINTER {}]", name));
+        }
+        fn push_sgr(&mut self, attrs: &[u16]) {
+            self.output.push_str(&format!("[PUSH_SGR {:?}]", attrs));
+        }
+
+        fn soft_reset(&mut self) {
+            self.output.push_str("[DECSTR]");
+        }
+        fn pop_sgr(&mut self) {
+            self.output.push_str("[POP_SGR]");
+        }
+        fn set_color_scheme_reporting(&mut self, enable: bool) {
+            self.output.push_str(&format!("[SCHEME_REPORTING {}]", enable));
+        }
+        fn report_color_scheme(&mut self) {
+            self.output.push_str("[REPORT_SCHEME]");
+        }
+        fn bell(&mut self) {
+            self.output.push_str("[BELL]");
+        }
+        fn handle_active_region(&mut self, payload: &str) {
+            self.output.push_str(&format!("[REGION {}]", payload));
+        }
+        fn set_protected(&mut self, protected: bool) {
+            self.output.push_str(&format!("[PROTECTED {}]", protected));
+        }
+        fn insert_columns(&mut self, n: usize) {
+            self.output.push_str(&format!("[DECIC {}]", n));
+        }
+        fn delete_columns(&mut self, n: usize) {
+            self.output.push_str(&format!("[DECDC {}]", n));
+        }
+        fn set_locator_reporting(&mut self, mode: u16, pixels: bool) {
+            self.output.push_str(&format!("[DECELR {} pixels={}]", mode, pixels));
+        }
+        fn select_locator_events(&mut self, events: &[u16]) {
+            self.output.push_str(&format!("[DECSLE {:?}]", events));
+        }
+        fn request_locator_position(&mut self) {
+            self.output.push_str("[DECRQLP]");
+        }
+        fn selective_erase_display(&mut self, mode: usize) {
+            self.output.push_str(&format!("[DECSED {}]", mode));
+        }
+        fn selective_erase_line(&mut self, mode: usize) {
+            self.output.push_str(&format!("[DECSEL {}]", mode));
+        }
+        fn define_user_keys(&mut self, keys: &[(u16, String)], clear: bool, lock: bool) {
+            self.output.push_str(&format!("[DECUDK {:?} clear={} lock={}]", keys, clear, lock));
+        }
+        fn query_termcap(&self, name: &str) -> Option<TermcapValue> {
+            match name {
+                "am" => Some(TermcapValue::Flag),
+                "colors" => Some(TermcapValue::Number(256)),
+                "TN" => Some(TermcapValue::String("xterm-256color".to_string())),
+                _ => None,
+            }
+        }
+        fn request_clipboard(&mut self, selection: &str, terminator: &str) {
+            self.output.push_str(&format!("[CLIPBOARD_READ {} {:?}]", selection, terminator));
+        }
+        fn allow_osc(&mut self, category: OscCategory, osc: &str) -> bool {
+            let allowed = !self.denied_osc.contains(&category);
+            if !allowed {
+                self.output.push_str(&format!("[DENIED {:?} {}]", category, osc));
+            }
+            allowed
+        }
+        fn allow_dcs(&mut self, dcs: &str) -> bool {
+            if self.deny_dcs {
+                self.output.push_str(&format!("[DENIED DCS {}]", dcs));
+            }
+            !self.deny_dcs
+        }
+        fn show_notification(&mut self, title: &str, body: &str) {
+            self.output.push_str(&format!("[NOTIFY {}: {}]", title, body));
+        }
+        fn sixel(&mut self, params: &str, data: &str) {
+            self.output.push_str(&format!("[SIXEL {} {} bytes]", params, data.len()));
+        }
+        fn handle_custom_osc(&mut self, code: u16, data: &str) {
+            self.output.push_str(&format!("[OSC {} {}]", code, data));
+        }
     }
 
     #[test]
@@ -1284,6 +1879,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn osc_limit_counts_whole_characters() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+
+        // "2;" plus 2045 bytes leaves 1 byte, too few for a 2-byte character
+        let title = format!("{}\u{e9}", "x".repeat(MAX_OSC_LEN - 3));
+        p.feed_str(&format!("\x1B]2;{}\x07", title), &mut g);
+        assert!(!g.output.contains("[TITLE"));
+
+        let mut g = MockGrid::new();
+        let title = format!("{}\u{e9}", "x".repeat(MAX_OSC_LEN - 4));
+        p.feed_str(&format!("\x1B]2;{}\x07", title), &mut g);
+        assert_eq!(g.output, format!("[TITLE: {}]", title));
+    }
+
     // ---------- Phase-3 robustness tests ----------
     
     #[test]
@@ -1359,6 +1970,7 @@ mod tests {
         let stats = p.stats();
         assert_eq!(stats.sequences_processed, 2); // Two CSI sequences
         assert_eq!(stats.max_params_seen, 5); // First sequence had 5 params
+        assert_eq!(stats.osc_dispatched, 1);
     }
 
     #[test]
@@ -1818,6 +2430,18 @@ mod tests {
         assert!(g.output.contains("text"));
     }
 
+    #[test]
+    fn character_set_controls_reach_grid() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+
+        p.feed_str("\x1B(0\x1B)A\x1B*B\x1B+%5\x0Ea\x0F\x1BNb\x1BOc\x1Bn\x1Bo", &mut g);
+        assert_eq!(
+            g.output,
+            "[G0=0][G1=A][G2=B][G3=5][GL=G1]a[GL=G0][SS2]b[SS3]c[GL=G2][GL=G3]"
+        );
+    }
+
     #[test]
     fn mouse_reporting_mode_verification() {
         let mut p = AnsiParser::new();
@@ -1827,6 +2451,7 @@ mod tests {
         let modes = vec![
             ("\x1B[?1000h", "1000"), // Normal tracking
             ("\x1B[?1002h", "1002"), // Button event
+            ("\x1B[?1003h", "1003"), // Any event
             ("\x1B[?1005h", "1005"), // UTF-8 mode
             ("\x1B[?1006h", "1006"), // SGR mode
         ];
@@ -1873,4 +2498,428 @@ mod tests {
 
         // The actual paste handling is tested elsewhere in the terminal
     }
+
+    #[test]
+    fn sgr_alternate_fonts() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+
+        p.feed_str("\x1B[11m", &mut g);
+        assert_eq!(g.font, 1);
+        p.feed_str("\x1B[19m", &mut g);
+        assert_eq!(g.font, 9);
+        p.feed_str("\x1B[10m", &mut g);
+        assert_eq!(g.font, PRIMARY_FONT);
+
+        // SGR 20 selects Fraktur, SGR 23 clears it together with italic
+        p.feed_str("\x1B[3;20m", &mut g);
+        assert_eq!(g.font, FRAKTUR_FONT);
+        p.feed_str("\x1B[23m", &mut g);
+        assert_eq!(g.font, PRIMARY_FONT);
+        assert!(!g.italic);
+
+        // SGR 23 leaves other alternate fonts alone
+        p.feed_str("\x1B[12;23m", &mut g);
+        assert_eq!(g.font, 2);
+    }
+
+    #[test]
+    fn osc_palette_query() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+
+        // Palette red (0.8) scales to 0xcccc in 16-bit X11 notation
+        p.feed_str("\x1B]4;1;?\x07", &mut g);
+        assert_eq!(g.responses, vec!["\x1B]4;1;rgb:cccc/0000/0000\x07"]);
+
+        // Multiple index/spec pairs; set requests are ignored; ST is echoed
+        g.responses.clear();
+        p.feed_str("\x1B]4;0;?;2;#ffffff;15;?\x1B\\", &mut g);
+        assert_eq!(g.responses, vec![
+            "\x1B]4;0;rgb:0000/0000/0000\x1B\\",
+            "\x1B]4;15;rgb:ffff/ffff/ffff\x1B\\",
+        ]);
+
+        // Out-of-range index produces no reply
+        g.responses.clear();
+        p.feed_str("\x1B]4;256;?\x07", &mut g);
+        assert!(g.responses.is_empty());
+    }
+
+    #[test]
+    fn osc_dynamic_color_query() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+
+        p.feed_str("\x1B]11;?\x07", &mut g);
+        assert_eq!(g.responses, vec!["\x1B]11;rgb:0000/0000/0000\x07"]);
+
+        // "10;?;?" queries foreground then background
+        g.responses.clear();
+        p.feed_str("\x1B]10;?;?\x1B\\", &mut g);
+        assert_eq!(g.responses, vec![
+            "\x1B]10;rgb:ffff/ffff/ffff\x1B\\",
+            "\x1B]11;rgb:0000/0000/0000\x1B\\",
+        ]);
+
+        // Unknown dynamic colors are not answered
+        g.responses.clear();
+        p.feed_str("\x1B]12;?\x07", &mut g);
+        assert!(g.responses.is_empty());
+        assert!(g.output.is_empty());
+    }
+
+    #[test]
+    fn osc_notifications_and_custom_codes() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+        // ConEmu progress (9;4) is not a notification; xterm's OSC 104 is not custom
+        p.feed_str("\x1B]9;done\x07\x1B]9;4;1;50\x07\x1B]777;notify;make;ok\x1B\\", &mut g);
+        p.feed_str("\x1B]104;1\x07\x1B]5113;x=1\x07", &mut g);
+        assert_eq!(g.output, "[NOTIFY : done][NOTIFY make: ok][OSC 5113 x=1]");
+    }
+
+    #[test]
+    fn osc_policy_checked_before_dispatch() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+        g.denied_osc = vec![OscCategory::Title, OscCategory::ClipboardRead];
+        p.feed_str("\x1B]2;pwned\x07\x1B]52;c;?\x07\x1B]0;\x07", &mut g);
+        assert_eq!(g.output, "[DENIED Title 2;pwned][DENIED ClipboardRead 52;c;?][DENIED Title 0;]");
+        // Color queries, pointer shapes and active regions are checked too
+        p.feed_str("\x1B]11;?\x07", &mut g);
+        assert_eq!(g.responses.len(), 1);
+        g.output.clear();
+        g.denied_osc = vec![OscCategory::Colors, OscCategory::PointerShape, OscCategory::ActiveRegion];
+        p.feed_str("\x1B]4;1;?\x07\x1B]11;?\x07\x1B]22;wait\x07\x1B]7777;run\x07", &mut g);
+        assert_eq!(g.responses.len(), 1);
+        assert_eq!(g.output, "[DENIED Colors 4;1;?][DENIED Colors 11;?][DENIED PointerShape 22;wait][DENIED ActiveRegion 7777;run]");
+    }
+
+    #[test]
+    fn osc_semantic_prompt() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+        p.feed_str("\x1B]133;A\x07$ \x1B]133;D;1\x1B\\", &mut g);
+        assert_eq!(g.output, "[PROMPT A ]$ [PROMPT D 1]");
+    }
+
+    #[test]
+    fn sgr_colon_truecolor() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+
+        // ITU T.416 form with an empty color space id
+        p.feed_str("\x1B[38:2::255:128:0mX", &mut g);
+        assert_eq!(g.fg, Color::rgb(1.0, 128.0 / 255.0, 0.0));
+        assert_eq!(g.output, "X");
+
+        // Color space id present, and the common form without it
+        p.feed_str("\x1B[48:2:1:0:0:255m", &mut g);
+        assert_eq!(g.bg, Color::rgb(0.0, 0.0, 1.0));
+        p.feed_str("\x1B[38:2:0:255:0m", &mut g);
+        assert_eq!(g.fg, Color::rgb(0.0, 1.0, 0.0));
+
+        // Sub-parameters do not consume the following parameters
+        p.feed_str("\x1B[38:2::10:20:30;1m", &mut g);
+        assert!(g.bold);
+        assert_eq!(g.fg, Color::rgb(10.0 / 255.0, 20.0 / 255.0, 30.0 / 255.0));
+    }
+
+    #[test]
+    fn sgr_colon_indexed() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+        p.feed_str("\x1B[38:5:196;48:5:21m", &mut g);
+        assert_eq!(g.fg, crate::color::palette_color(196));
+        assert_eq!(g.bg, crate::color::palette_color(21));
+
+        // Semicolon forms keep working
+        p.feed_str("\x1B[38;5;46;48;2;1;2;3m", &mut g);
+        assert_eq!(g.fg, crate::color::palette_color(46));
+        assert_eq!(g.bg, Color::rgb(1.0 / 255.0, 2.0 / 255.0, 3.0 / 255.0));
+    }
+
+    #[test]
+    fn sgr_underline_styles() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+
+        p.feed_str("\x1B[4:3m", &mut g);
+        assert_eq!(g.underline_style, UnderlineStyle::Curly);
+        assert!(g.underline);
+        p.feed_str("\x1B[4:2m", &mut g);
+        assert_eq!(g.underline_style, UnderlineStyle::Double);
+        p.feed_str("\x1B[4:0m", &mut g);
+        assert!(!g.underline);
+
+        // Unknown styles are ignored, plain SGR 4 still works
+        p.feed_str("\x1B[4:9m", &mut g);
+        assert!(!g.underline);
+        p.feed_str("\x1B[4m", &mut g);
+        assert!(g.underline);
+        assert_eq!(g.output, "");
+    }
+
+    #[test]
+    fn sgr_21_double_underline() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+
+        // 21 underlines twice and leaves intensity alone
+        p.feed_str("\x1B[1;2;21m", &mut g);
+        assert_eq!(g.underline_style, UnderlineStyle::Double);
+        assert!(g.underline && g.bold && g.dim);
+
+        // 22 restores normal intensity and keeps the underline
+        p.feed_str("\x1B[22m", &mut g);
+        assert!(!g.bold && !g.dim);
+        assert_eq!(g.underline_style, UnderlineStyle::Double);
+
+        // 4 switches back to a single underline, 24 clears either
+        p.feed_str("\x1B[4m", &mut g);
+        assert_eq!(g.underline_style, UnderlineStyle::Single);
+        p.feed_str("\x1B[21;24m", &mut g);
+        assert_eq!(g.underline_style, UnderlineStyle::None);
+        assert!(!g.underline);
+        assert_eq!(g.output, "");
+    }
+
+    #[test]
+    fn sgr_decorations_and_color() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+
+        p.feed_str("\x1B[9;53m", &mut g);
+        assert!(g.strikethrough && g.overline);
+        p.feed_str("\x1B[29;55m", &mut g);
+        assert!(!g.strikethrough && !g.overline);
+
+        // Colon and semicolon forms, palette and RGB
+        p.feed_str("\x1B[58:2::255:0:0m", &mut g);
+        assert_eq!(g.decoration, Some(Color::rgb(1.0, 0.0, 0.0)));
+        p.feed_str("\x1B[58;5;46m", &mut g);
+        assert_eq!(g.decoration, Some(crate::color::palette_color(46)));
+        // The foreground is not touched, 59 goes back to following it
+        assert_eq!(g.fg, Color::default());
+        p.feed_str("\x1B[59m", &mut g);
+        assert_eq!(g.decoration, None);
+
+        p.feed_str("\x1B[58:2::0:0:255m\x1B[58:5:300m", &mut g);
+        assert_eq!(g.decoration, None);
+        assert_eq!(g.output, "");
+    }
+
+    #[test]
+    fn csi_subparams_do_not_terminate_sequence() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+        // Excess sub-parameters are dropped without leaking text
+        p.feed_str("\x1B[38:2:1:2:3:4:5:6:7:8:9:10mok", &mut g);
+        assert_eq!(g.output, "ok");
+    }
+
+    #[test]
+    fn csi_secondary_markers_are_ignored() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+        // XTMODKEYS, as sent by vim, is not SGR underline + dim
+        p.feed_str("\x1B[>4;2m\x1B[>4;mok", &mut g);
+        assert_eq!(g.output, "ok");
+        assert!(!g.underline);
+        assert!(!g.dim);
+        // The marker does not leak into the next sequence
+        p.feed_str("\x1B[4m", &mut g);
+        assert!(g.underline);
+    }
+
+    #[test]
+    fn erase_display_modes() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+        // `clear` sends ED 2 followed by ED 3
+        p.feed_str("\x1B[H\x1B[2J\x1B[3J", &mut g);
+        assert_eq!(g.output, "[CLEAR][CLEAR_SCROLLBACK]");
+    }
+
+    #[test]
+    fn cursor_blink_mode() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+        p.feed_str("\x1B[?12h\x1B[?12l", &mut g);
+        assert_eq!(g.output, "[BLINK true][BLINK false]");
+    }
+
+    #[test]
+    fn reverse_wraparound_mode() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+        p.feed_str("\x1B[?45h\x1B[?45l", &mut g);
+        assert_eq!(g.output, "[REVERSE_WRAP true][REVERSE_WRAP false]");
+    }
+
+    #[test]
+    fn osc_pointer_shape() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+        p.feed_str("\x1B]22;pointer\x07\x1B]22;\x1B\\", &mut g);
+        assert_eq!(g.output, "[POINTER pointer][POINTER ]");
+    }
+
+    #[test]
+    fn csi_push_pop_sgr() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+        p.feed_str("\x1B[#{\x1B[1;31#{\x1B[#}\x1B[#p\x1B[#q", &mut g);
+        assert_eq!(g.output, "[PUSH_SGR []][PUSH_SGR [1, 31]][POP_SGR][PUSH_SGR []][POP_SGR]");
+        // Unknown intermediate sequences are dropped, not run as the plain
+        // final byte (`CSI ! q` is not a pop, `CSI SP q` not DECSCUSR)
+        p.feed_str("\x1B[!q\x1B[2 q\x1B[#mok", &mut g);
+        assert_eq!(g.output, "[PUSH_SGR []][PUSH_SGR [1, 31]][POP_SGR][PUSH_SGR []][POP_SGR]ok");
+    }
+
+    #[test]
+    fn csi_soft_reset() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+        p.feed_str("\x1B[!p", &mut g);
+        assert_eq!(g.output, "[PROTECTED false][DECSTR]");
+    }
+
+    #[test]
+    fn csi_insert_delete_columns() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+        p.feed_str("\x1B['}\x1B[3'}\x1B[2'~\x1B[~", &mut g);
+        assert_eq!(g.output, "[DECIC 1][DECIC 3][DECDC 2]");
+    }
+
+    #[test]
+    fn csi_dec_locator() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+        p.feed_str("\x1B[1;1'z\x1B[1;3'{\x1B['|\x1B[2'z\x1B['z", &mut g);
+        assert_eq!(
+            g.output,
+            "[DECELR 1 pixels=true][DECSLE [1, 3]][DECRQLP][DECELR 2 pixels=false][DECELR 0 pixels=false]"
+        );
+    }
+
+    #[test]
+    fn color_scheme_reporting() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+        p.feed_str("\x1B[?2031h\x1B[?996n\x1B[?2031l\x1B[996n", &mut g);
+        assert_eq!(g.output, "[SCHEME_REPORTING true][REPORT_SCHEME][SCHEME_REPORTING false]");
+    }
+
+    #[test]
+    fn bell_outside_control_strings() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+        // A BEL terminating an OSC is not a bell
+        p.feed_str("a\x07\x1B]0;title\x07", &mut g);
+        assert_eq!(g.output, "a[BELL][TITLE: title]");
+    }
+
+    #[test]
+    fn selective_erase_and_protection() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+        p.feed_str("\x1B[1\"q\x1B[\"q\x1B[2\"q\x1B[3\"q\x1BV\x1BW", &mut g);
+        assert_eq!(g.output, "[PROTECTED true][PROTECTED false][PROTECTED false][PROTECTED true][PROTECTED false]");
+        g.output.clear();
+        // The private marker selects the selective variants
+        p.feed_str("\x1B[?J\x1B[?2J\x1B[?1K\x1B[2K", &mut g);
+        assert_eq!(g.output, "[DECSED 0][DECSED 2][DECSEL 1][CLEAR_LINE]");
+    }
+
+    #[test]
+    fn active_region_osc() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+        p.feed_str("\x1B]7777;save\x07OK\x1B]7777;\x1B\\", &mut g);
+        assert_eq!(g.output, "[REGION save]OK[REGION ]");
+    }
+
+    #[test]
+    fn coding_system_selection() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+        // Consumed whole, including the unsupported ESC % / F
+        p.feed_str("\x1B%@a\x1B%Gb\x1B%/Fc", &mut g);
+        assert_eq!(g.output, "abc");
+        assert_eq!(p.stats().errors_encountered, 0);
+    }
+
+    #[test]
+    fn clipboard_read_request() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+        p.feed_str("\x1B]52;c;?\x07\x1B]52;;?\x1B\\\x1B]52;x;?\x07", &mut g);
+        assert_eq!(g.output, "[CLIPBOARD_READ c \"\\u{7}\"][CLIPBOARD_READ  \"\\u{1b}\\\\\"]");
+    }
+
+    #[test]
+    fn xtgettcap_queries() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+        // am; colors; TN; then an unknown name ends the reply
+        p.feed_str("\x1BP+q616d;636f6c6f7273;544e;787878;616d\x1B\\x", &mut g);
+        assert_eq!(g.responses, vec![
+            "\x1BP1+r616d\x1B\\",
+            "\x1BP1+r636f6c6f7273=323536\x1B\\",
+            "\x1BP1+r544e=787465726D2D323536636F6C6F72\x1B\\",
+            "\x1BP0+r787878\x1B\\",
+        ]);
+        assert_eq!(g.output, "x");
+    }
+
+    #[test]
+    fn dcs_checked_before_dispatch() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+        g.deny_dcs = true;
+        p.feed_str("\x1BP+q616d\x1B\\x", &mut g);
+        assert!(g.responses.is_empty());
+        assert_eq!(g.output, "[DENIED DCS +q616d]x");
+    }
+
+    #[test]
+    fn decudk_defines_function_keys() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+        p.feed_str("\x1BP1;1|17/6C730D;18/zz;23/\x1B\\", &mut g);
+        assert_eq!(g.output, "[DECUDK [(17, \"ls\\r\"), (23, \"\")] clear=false lock=false]");
+
+        g.output.clear();
+        p.feed_str("\x1BP|\x1B\\", &mut g);
+        assert_eq!(g.output, "[DECUDK [] clear=true lock=true]");
+    }
+
+    #[test]
+    fn other_device_control_strings_are_swallowed() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+        p.feed_str("a\x1BP$qm\x1B\\b", &mut g);
+        assert_eq!(g.output, "ab");
+    }
+
+    #[test]
+    fn sixel_images_are_dispatched_past_the_string_limit() {
+        let mut p = AnsiParser::new();
+        let mut g = MockGrid::new();
+        p.feed_str("a\x1BPq#0;2;0;0;0#0~~@@vv\x1B\\b", &mut g);
+        assert_eq!(g.output, "a[SIXEL  18 bytes]b");
+
+        g.output.clear();
+        let body = "~".repeat(MAX_OSC_LEN * 2);
+        p.feed_str(&format!("\x1BP0;1;0q{}\x1B\\", body), &mut g);
+        assert_eq!(g.output, format!("[SIXEL 0;1;0 {} bytes]", body.len()));
+
+        // A 'q' after other bytes is not a sixel introducer
+        g.output.clear();
+        p.feed_str("\x1BP+q616d\x1B\\", &mut g);
+        assert_eq!(g.output, "");
+    }
 }
@@ -55,6 +55,7 @@ impl AnsiGrid for TestGrid {
                 italic: self.italic,
                 underline: self.underline,
                 dim: self.dim,
+                ..Default::default()
             };
         }
         self.output.push(ch);
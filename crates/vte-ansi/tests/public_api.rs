@@ -0,0 +1,113 @@
+// tests/public_api.rs
+//! Pins the public API exported from the crate root
+//!
+//! Downstream crates (vte-core, embedders) only use these paths. Removing or
+//! changing any of them is a breaking change and must bump the minor version
+//! while the crate is 0.x.
+
+use vte_ansi::{
+    palette_color, AnsiError, AnsiGrid, AnsiParser, Cell, Color, DynamicColor, ErrorCallback,
+    KeyEvent, LogicalColor, MouseEvent, ParserStats, COLOR_PALETTE, FRAKTUR_FONT, PRIMARY_FONT,
+};
+
+/// Grid implementing only the required methods; every other method must keep a default
+#[derive(Default)]
+struct MinimalGrid {
+    text: String,
+    fg: Color,
+    bg: Color,
+}
+
+impl AnsiGrid for MinimalGrid {
+    fn put(&mut self, ch: char) {
+        self.text.push(ch);
+    }
+    fn advance(&mut self) {}
+    fn left(&mut self, _n: usize) {}
+    fn right(&mut self, _n: usize) {}
+    fn up(&mut self, _n: usize) {}
+    fn down(&mut self, _n: usize) {}
+    fn newline(&mut self) {
+        self.text.push('\n');
+    }
+    fn carriage_return(&mut self) {}
+    fn backspace(&mut self) {}
+    fn move_rel(&mut self, _dx: i32, _dy: i32) {}
+    fn move_abs(&mut self, _row: usize, _col: usize) {}
+    fn clear_screen(&mut self) {}
+    fn clear_line(&mut self) {}
+    fn reset_attrs(&mut self) {}
+    fn set_bold(&mut self, _bold: bool) {}
+    fn set_italic(&mut self, _italic: bool) {}
+    fn set_underline(&mut self, _underline: bool) {}
+    fn set_dim(&mut self, _dim: bool) {}
+    fn set_fg(&mut self, color: Color) {
+        self.fg = color;
+    }
+    fn set_bg(&mut self, color: Color) {
+        self.bg = color;
+    }
+    fn get_fg(&self) -> Color {
+        self.fg
+    }
+    fn get_bg(&self) -> Color {
+        self.bg
+    }
+}
+
+#[test]
+fn parser_drives_minimal_grid() {
+    let mut parser = AnsiParser::new();
+    let mut grid = MinimalGrid::default();
+    parser.feed_str("a\x1B[31mb\x1B[0m\n", &mut grid);
+    assert_eq!(grid.text, "ab\n");
+
+    // Defaulted extension methods fall back to the required ones
+    grid.set_fg_indexed(1);
+    assert_eq!(grid.get_fg(), COLOR_PALETTE[1]);
+    grid.set_bg_indexed(196);
+    assert_eq!(grid.get_bg(), palette_color(196));
+    assert_eq!(grid.get_font(), PRIMARY_FONT);
+    assert_eq!(grid.query_palette_color(9), Some(COLOR_PALETTE[9]));
+    assert_eq!(grid.query_dynamic_color(DynamicColor::Background), None);
+}
+
+#[test]
+fn error_callback_and_stats() {
+    use std::sync::{Arc, Mutex};
+
+    let seen: Arc<Mutex<Vec<AnsiError>>> = Arc::default();
+    let sink = Arc::clone(&seen);
+    let callback: ErrorCallback = Box::new(move |err| sink.lock().unwrap().push(err));
+    let mut parser = AnsiParser::new().with_error_callback(callback);
+    let mut grid = MinimalGrid::default();
+
+    parser.feed_str("\x1B]0;title\x07ok", &mut grid);
+    assert_eq!(grid.text, "ok");
+    let stats: &ParserStats = parser.stats();
+    assert_eq!(stats.errors_encountered, 0);
+    assert!(seen.lock().unwrap().is_empty());
+}
+
+#[test]
+fn cell_and_color_types() {
+    let cell = Cell::default();
+    assert_eq!(cell.font, PRIMARY_FONT);
+    assert_eq!(cell.fg_logical, LogicalColor::Default);
+    assert_ne!(FRAKTUR_FONT, PRIMARY_FONT);
+
+    let bold_red = Cell { fg: COLOR_PALETTE[1], fg_logical: LogicalColor::Indexed(1), bold: true, ..cell };
+    assert_eq!(bold_red.display_fg(true), COLOR_PALETTE[9]);
+
+    assert_eq!(Color::rgb(1.0, 0.0, 0.0).to_x11_rgb(), "rgb:ffff/0000/0000");
+    assert_eq!(DynamicColor::from_osc(11), Some(DynamicColor::Background));
+    assert_eq!(DynamicColor::Cursor.osc_code(), 12);
+}
+
+#[test]
+fn input_event_types() {
+    let key = KeyEvent { keyval: 'a' as u32, state: 0 };
+    let mouse = MouseEvent { button: 1, x: 1.0, y: 2.0, modifiers: 0 };
+    assert_eq!(key.clone().keyval, 97);
+    assert_eq!(mouse.clone().button, 1);
+}
@@ -0,0 +1,75 @@
+//! Grid benchmarks: writing output, resizing and switching screens
+//!
+//! The screen buffer allocates rows on first write and shares blank ones, so
+//! resizes and screen switches should cost in proportion to the rows that
+//! hold content rather than to the window size. Each group runs on a typical
+//! and a huge window.
+
+use std::hint::black_box;
+use std::sync::Arc;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use vte_core::{AnsiGrid, AnsiParser, Grid, TerminalConfig};
+
+/// Window sizes as (cols, rows)
+const SIZES: [(usize, usize); 2] = [(80, 24), (300, 100)];
+
+fn grid(cols: usize, rows: usize) -> Grid {
+    Grid::new(cols, rows, Arc::new(TerminalConfig::default()))
+}
+
+/// `lines` full lines of text, each ending in CR LF
+fn output(cols: usize, lines: usize) -> String {
+    let line: String = (0..cols).map(|i| (b'a' + (i % 26) as u8) as char).collect();
+    (0..lines).map(|_| format!("{}\r\n", line)).collect()
+}
+
+fn bench_write(c: &mut Criterion) {
+    let mut group = c.benchmark_group("grid_write");
+    for (cols, rows) in SIZES {
+        let text = output(cols, rows * 2);
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(format!("{}x{}", cols, rows)), &text, |b, text| {
+            let mut parser = AnsiParser::new();
+            let mut grid = grid(cols, rows);
+            b.iter(|| parser.feed_str(black_box(text), &mut grid));
+        });
+    }
+    group.finish();
+}
+
+fn bench_resize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("grid_resize");
+    for (cols, rows) in SIZES {
+        // A shell prompt or two on an otherwise empty screen
+        for (name, lines) in [("sparse", 2), ("full", rows)] {
+            let mut grid = grid(cols, rows);
+            AnsiParser::new().feed_str(&output(cols, lines), &mut grid);
+            group.bench_function(BenchmarkId::new(name, format!("{}x{}", cols, rows)), |b| {
+                b.iter(|| {
+                    grid.resize(cols - 10, rows - 5);
+                    grid.resize(cols, rows);
+                });
+            });
+        }
+    }
+    group.finish();
+}
+
+fn bench_alternate_screen(c: &mut Criterion) {
+    let mut group = c.benchmark_group("alternate_screen_switch");
+    for (cols, rows) in SIZES {
+        let mut grid = grid(cols, rows);
+        AnsiParser::new().feed_str(&output(cols, rows), &mut grid);
+        group.bench_function(BenchmarkId::from_parameter(format!("{}x{}", cols, rows)), |b| {
+            b.iter(|| {
+                AnsiGrid::use_alternate_screen(&mut grid, true);
+                AnsiGrid::use_alternate_screen(&mut grid, false);
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_write, bench_resize, bench_alternate_screen);
+criterion_main!(benches);
@@ -0,0 +1,121 @@
+// src/activity.rs
+//! Silence and activity monitoring, like tmux's `monitor-silence`
+//!
+//! After launching a long build, a tab UI wants to know when the terminal
+//! has gone quiet. With a silence threshold set
+//! (`TerminalConfig::silence_threshold_ms` or
+//! `VteTerminalCore::set_silence_threshold`), the core emits
+//! `TerminalEvent::Silence` once the child has written nothing for that
+//! long, and `TerminalEvent::Activity` when output resumes after it. Each
+//! quiet period is reported once.
+
+use std::time::{Duration, Instant};
+
+/// Time of the last output and whether its silence was reported
+#[derive(Clone, Debug)]
+pub struct ActivityMonitor {
+    silence_after: Option<Duration>,
+    last_output: Instant,
+    /// Silence was reported and no output arrived since
+    silent: bool,
+}
+
+impl ActivityMonitor {
+    /// Monitor reporting silence after `silence_after`; `None` disables
+    pub fn new(silence_after: Option<Duration>, now: Instant) -> Self {
+        Self { silence_after, last_output: now, silent: false }
+    }
+
+    pub fn silence_after(&self) -> Option<Duration> {
+        self.silence_after
+    }
+
+    /// Change the threshold; the quiet period counts from `now`
+    pub fn set_silence_after(&mut self, silence_after: Option<Duration>, now: Instant) {
+        self.silence_after = silence_after;
+        self.last_output = now;
+        self.silent = false;
+    }
+
+    /// Whether silence was reported and output has not resumed
+    pub fn is_silent(&self) -> bool {
+        self.silent
+    }
+
+    /// Record output at `now`; true if it ends a reported silence
+    pub fn output(&mut self, now: Instant) -> bool {
+        self.last_output = now;
+        std::mem::take(&mut self.silent)
+    }
+
+    /// Whether a poll at `now` reports silence
+    pub fn is_due(&self, now: Instant) -> bool {
+        self.silence_after
+            .is_some_and(|threshold| !self.silent && now.saturating_duration_since(self.last_output) >= threshold)
+    }
+
+    /// How long the terminal has been quiet, the first time that reaches the
+    /// threshold; `None` otherwise
+    pub fn poll(&mut self, now: Instant) -> Option<Duration> {
+        if !self.is_due(now) {
+            return None;
+        }
+        self.silent = true;
+        Some(now.saturating_duration_since(self.last_output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::config::TerminalConfig;
+    use crate::grid::Grid;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_silence_reported_once_per_quiet_period() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut monitor = ActivityMonitor::new(Some(Duration::from_secs(5)), start);
+
+        assert_eq!(monitor.poll(at(4999)), None);
+        assert!(monitor.is_due(at(5200)));
+        assert_eq!(monitor.poll(at(5200)), Some(Duration::from_millis(5200)));
+        assert!(!monitor.is_due(at(9000)));
+        assert_eq!(monitor.poll(at(9000)), None);
+        assert!(monitor.is_silent());
+
+        assert!(monitor.output(at(9500)));
+        assert!(!monitor.output(at(9600)));
+        assert_eq!(monitor.poll(at(14000)), None);
+        assert_eq!(monitor.poll(at(14600)), Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_disabled_and_changed_threshold() {
+        let start = Instant::now();
+        let mut monitor = ActivityMonitor::new(None, start);
+        assert_eq!(monitor.poll(start + Duration::from_secs(3600)), None);
+
+        monitor.set_silence_after(Some(Duration::from_secs(1)), start + Duration::from_secs(3600));
+        assert_eq!(monitor.poll(start + Duration::from_millis(3_600_500)), None);
+        assert!(monitor.poll(start + Duration::from_secs(3601)).is_some());
+    }
+
+    #[test]
+    fn test_grid_uses_its_clock() {
+        let config = TerminalConfig::default().with_silence_threshold(2000);
+        let mut grid = Grid::new(10, 2, Arc::new(config));
+        let clock = MockClock::new();
+        grid.set_clock(Arc::new(clock.clone()));
+
+        clock.advance(Duration::from_secs(2));
+        assert_eq!(grid.poll_silence(), Some(Duration::from_secs(2)));
+        assert!(grid.note_output());
+
+        grid.set_silence_threshold(None);
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(grid.poll_silence(), None);
+    }
+}
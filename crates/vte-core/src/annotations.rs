@@ -0,0 +1,147 @@
+// src/annotations.rs
+//! Embedder-defined row annotations for gutter markers
+//!
+//! Annotations hang off absolute rows like [scroll marks](crate::marks), so
+//! a bookmark or build status stays next to its output as it scrolls, and
+//! is dropped once the row is trimmed from the scrollback. The terminal
+//! never interprets them: the icon name and tooltip are for the embedder's
+//! own gutter, and renderers only draw the color.
+
+use crate::ansi::Color;
+
+/// Identifies an annotation for `Grid::remove_annotation`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AnnotationId(u64);
+
+/// What to show next to a row
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Annotation {
+    /// Icon name understood by the embedder (e.g. "breakpoint")
+    pub icon: Option<String>,
+    /// Gutter color; rows without one are not drawn by the built-in renderer
+    pub color: Option<Color>,
+    /// Text shown when hovering the marker
+    pub tooltip: Option<String>,
+}
+
+impl Annotation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_icon(mut self, icon: impl Into<String>) -> Self {
+        self.icon = Some(icon.into());
+        self
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    pub fn with_tooltip(mut self, tooltip: impl Into<String>) -> Self {
+        self.tooltip = Some(tooltip.into());
+        self
+    }
+}
+
+/// Annotation attached to an absolute row
+#[derive(Clone, Debug, PartialEq)]
+pub struct RowAnnotation {
+    pub id: AnnotationId,
+    pub row: u64,
+    pub annotation: Annotation,
+}
+
+/// Annotations sorted by absolute row, oldest first within a row
+#[derive(Clone, Debug, Default)]
+pub struct RowAnnotations {
+    annotations: Vec<RowAnnotation>,
+    next_id: u64,
+}
+
+impl RowAnnotations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, row: u64, annotation: Annotation) -> AnnotationId {
+        let id = AnnotationId(self.next_id);
+        self.next_id += 1;
+        let idx = self.annotations.partition_point(|a| a.row <= row);
+        self.annotations.insert(idx, RowAnnotation { id, row, annotation });
+        id
+    }
+
+    /// Remove one annotation; returns false if it is gone already
+    pub fn remove(&mut self, id: AnnotationId) -> bool {
+        let before = self.annotations.len();
+        self.annotations.retain(|a| a.id != id);
+        self.annotations.len() != before
+    }
+
+    /// Remove all annotations
+    pub fn clear(&mut self) {
+        self.annotations.clear();
+    }
+
+    /// Drop annotations on rows before `first_row` (trimmed from the scrollback)
+    pub fn prune_before(&mut self, first_row: u64) {
+        let idx = self.annotations.partition_point(|a| a.row < first_row);
+        self.annotations.drain(..idx);
+    }
+
+    /// All annotations in row order
+    pub fn as_slice(&self) -> &[RowAnnotation] {
+        &self.annotations
+    }
+
+    /// Annotations on rows `start..end`
+    pub fn in_rows(&self, start: u64, end: u64) -> &[RowAnnotation] {
+        let from = self.annotations.partition_point(|a| a.row < start);
+        let to = self.annotations.partition_point(|a| a.row < end);
+        &self.annotations[from..to.max(from)]
+    }
+
+    pub fn len(&self) -> usize {
+        self.annotations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.annotations.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_remove_and_range() {
+        let mut annotations = RowAnnotations::new();
+        let a = annotations.add(7, Annotation::new().with_icon("bookmark"));
+        let b = annotations.add(2, Annotation::new().with_tooltip("build failed"));
+        let c = annotations.add(7, Annotation::new().with_color(Color::rgb(1.0, 0.0, 0.0)));
+
+        let ids: Vec<AnnotationId> = annotations.as_slice().iter().map(|a| a.id).collect();
+        assert_eq!(ids, vec![b, a, c]);
+        assert_eq!(annotations.in_rows(3, 8).len(), 2);
+        assert!(annotations.in_rows(8, 3).is_empty());
+
+        assert!(annotations.remove(a));
+        assert!(!annotations.remove(a));
+        assert_eq!(annotations.in_rows(7, 8)[0].id, c);
+    }
+
+    #[test]
+    fn test_prune_before() {
+        let mut annotations = RowAnnotations::new();
+        annotations.add(1, Annotation::new());
+        annotations.add(4, Annotation::new());
+        annotations.add(9, Annotation::new());
+
+        annotations.prune_before(4);
+        let rows: Vec<u64> = annotations.as_slice().iter().map(|a| a.row).collect();
+        assert_eq!(rows, vec![4, 9]);
+    }
+}
@@ -1,2 +0,0 @@
-// Re-export the ANSI parser from the dedicated crate
-pub use vte_ansi::{AnsiParser, AnsiGrid, AnsiError, ErrorCallback, Color, COLOR_PALETTE, Cell, KeyEvent, MouseEvent};
@@ -0,0 +1,319 @@
+// src/automation.rs
+//! Expect-style scripting of a terminal
+//!
+//! [`Automation`] drives a terminal the way a user at the keyboard would:
+//! [`send`](Automation::send) types text into the PTY and
+//! [`wait_for`](Automation::wait_for) blocks until the output matches a regular
+//! expression. Only output after the previous match (initially, after the
+//! cursor position when the automation was created) is searched, so a script
+//! moves forward through the transcript like `expect`.
+//!
+//! The transcript is read from the grid's scrollback and primary screen, one
+//! character per cell (double-width characters once) and one line per row. A
+//! wait registers a change hook (see `changes`) and re-reads it each time the
+//! grid commits a change, sleeping in between. Rows trimmed from the scrollback in the meantime
+//! are lost to the search. Note that the shell echoes typed input, so a
+//! pattern also matches its own appearance in a command line that was sent.
+
+use crate::broadcast::InputSink;
+use crate::error::{TerminalError, TerminalResult};
+use crate::grid::Grid;
+use regex::Regex;
+use std::sync::mpsc;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// Output matched by [`Automation::wait_for`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WaitMatch {
+    /// The whole match; rows are joined with '\n'
+    pub text: String,
+    /// Capture groups 1.., `None` for groups that did not participate
+    pub groups: Vec<Option<String>>,
+    /// Absolute row the match starts on
+    pub row: u64,
+}
+
+/// Transcript of rows from a position to the end of the screen
+struct Transcript {
+    text: String,
+    /// Absolute row and byte offset in `text` of each row; the first row may
+    /// start mid-row, at `first_col`
+    rows: Vec<(u64, usize)>,
+    first_col: usize,
+    /// Column of each character of `text`, then of its end; a line break is
+    /// at the column where its row ends
+    cols: Vec<usize>,
+}
+
+impl Transcript {
+    /// Text of `grid` from `(row, col)` on, rows trimmed of trailing blanks
+    /// except for what lies before the cursor
+    fn read(grid: &Grid, (row, col): (u64, usize)) -> Self {
+        let first_row = row.max(grid.first_absolute_row());
+        let first_col = if first_row == row { col } else { 0 };
+        let cursor_row = grid.absolute_row(grid.row);
+        let mut transcript = Transcript { text: String::new(), rows: Vec::new(), first_col, cols: Vec::new() };
+
+        for row in first_row.. {
+            let Some(cells) = grid.absolute_row_cells(row) else { break };
+            if row > first_row {
+                transcript.text.push('\n');
+            }
+            transcript.rows.push((row, transcript.text.len()));
+
+            let start = if row == first_row { first_col.min(cells.len()) } else { 0 };
+            let keep = if row == cursor_row { grid.col.min(cells.len()) } else { 0 };
+            let end = cells.iter().rposition(|cell| cell.ch != '\0' && cell.ch != ' ').map_or(0, |i| i + 1).max(keep);
+            let end = end.max(start);
+            for (col, cell) in cells.iter().enumerate().take(end).skip(start) {
+                if !cell.spacer {
+                    transcript.text.push(if cell.ch == '\0' { ' ' } else { cell.ch });
+                    transcript.cols.push(col);
+                }
+            }
+            transcript.cols.push(end);
+        }
+        transcript
+    }
+
+    /// Absolute row and column of a byte offset in the text
+    fn position(&self, offset: usize) -> (u64, usize) {
+        let index = self.rows.partition_point(|&(_, start)| start <= offset).saturating_sub(1);
+        let Some(&(row, _)) = self.rows.get(index) else {
+            return (0, self.first_col);
+        };
+        let col = self.cols.get(self.text[..offset].chars().count()).copied().unwrap_or(self.first_col);
+        (row, col)
+    }
+}
+
+/// Scripted input and output matching for one terminal
+pub struct Automation {
+    grid: Arc<RwLock<Grid>>,
+    input: InputSink,
+    /// Output before this absolute row and column has been matched already
+    consumed: (u64, usize),
+}
+
+impl Automation {
+    /// Automation of the terminal showing `grid` and reading `input`
+    ///
+    /// Output already on screen up to the cursor is not searched.
+    pub fn new(grid: Arc<RwLock<Grid>>, input: InputSink) -> Self {
+        let consumed = match grid.read() {
+            Ok(g) => (g.absolute_row(g.row), g.col),
+            Err(e) => {
+                let g = e.into_inner();
+                (g.absolute_row(g.row), g.col)
+            }
+        };
+        Self { grid, input, consumed }
+    }
+
+    /// Type `text` into the terminal, e.g. `"ls -l\n"`
+    pub fn send(&self, text: &str) -> TerminalResult<()> {
+        self.input.send(text.as_bytes())
+    }
+
+    /// Block until output after the previous match matches `pattern`
+    ///
+    /// Later waits search from the end of this match on. Fails with
+    /// [`TerminalError::WaitTimeout`] if nothing matches within `timeout`.
+    pub fn wait_for(&mut self, pattern: &str, timeout: Duration) -> TerminalResult<WaitMatch> {
+        let regex = Regex::new(pattern).map_err(|e| TerminalError::InvalidPattern {
+            pattern: pattern.to_string(),
+            message: e.to_string(),
+        })?;
+        self.wait_for_regex(&regex, timeout)
+    }
+
+    /// Block until the text between the next `start` and `end` markers has
+    /// been written, and return it without surrounding newlines
+    ///
+    /// Markers are matched literally, e.g. from
+    /// `send("echo BEG''IN; make; echo EN''D\n")` - the quotes keep the echoed
+    /// command line from matching.
+    pub fn capture_between(&mut self, start: &str, end: &str, timeout: Duration) -> TerminalResult<String> {
+        let pattern = format!("(?s){}(.*?){}", regex::escape(start), regex::escape(end));
+        let regex = Regex::new(&pattern).map_err(|e| TerminalError::InvalidPattern {
+            pattern: pattern.clone(),
+            message: e.to_string(),
+        })?;
+        let found = self.wait_for_regex(&regex, timeout)?;
+        let captured = found.groups.into_iter().next().flatten().unwrap_or_default();
+        Ok(captured.trim_matches('\n').to_string())
+    }
+
+    fn wait_for_regex(&mut self, regex: &Regex, timeout: Duration) -> TerminalResult<WaitMatch> {
+        let deadline = Instant::now() + timeout;
+        // One pending wake-up is enough: every wake re-reads the whole grid
+        let (changed, wake) = mpsc::sync_channel(1);
+        let hook = self.grid.write()
+            .map_err(|e| TerminalError::GridLockError { message: e.to_string() })?
+            .add_change_hook(move |_| {
+                let _ = changed.try_send(());
+            });
+        let result = self.wait_for_change(regex, deadline, timeout, &wake);
+        if let Ok(mut grid) = self.grid.write() {
+            grid.remove_change_hook(hook);
+        }
+        result
+    }
+
+    /// Search the grid now and after every change `wake` reports, until a
+    /// match or `deadline`
+    fn wait_for_change(&mut self, regex: &Regex, deadline: Instant, timeout: Duration, wake: &mpsc::Receiver<()>) -> TerminalResult<WaitMatch> {
+        let mut searched = None;
+        loop {
+            let grid = Arc::clone(&self.grid);
+            let grid = grid.read().map_err(|e| TerminalError::GridLockError { message: e.to_string() })?;
+            if searched != Some(grid.change_counters()) {
+                searched = Some(grid.change_counters());
+                if let Some(found) = self.find(&grid, regex) {
+                    return Ok(found);
+                }
+            }
+            drop(grid);
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(TerminalError::WaitTimeout { pattern: regex.as_str().to_string(), timeout });
+            }
+            let _ = wake.recv_timeout(deadline - now);
+        }
+    }
+
+    /// Search new output in `grid`, consuming it up to the end of a match
+    fn find(&mut self, grid: &Grid, regex: &Regex) -> Option<WaitMatch> {
+        let transcript = Transcript::read(grid, self.consumed);
+        let captures = regex.captures(&transcript.text)?;
+        let whole = captures.get(0)?;
+
+        let found = WaitMatch {
+            text: whole.as_str().to_string(),
+            groups: captures.iter().skip(1).map(|group| group.map(|m| m.as_str().to_string())).collect(),
+            row: transcript.position(whole.start()).0,
+        };
+        self.consumed = transcript.position(whole.end());
+        Some(found)
+    }
+}
+
+impl std::fmt::Debug for Automation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Automation").field("consumed", &self.consumed).finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::AnsiParser;
+    use crate::config::TerminalConfig;
+    use crate::test_support::SharedBuffer;
+    use std::thread;
+
+    fn terminal(cols: usize, rows: usize) -> (Arc<RwLock<Grid>>, SharedBuffer) {
+        let grid = Grid::new(cols, rows, Arc::new(TerminalConfig::default()));
+        (Arc::new(RwLock::new(grid)), SharedBuffer::default())
+    }
+
+    /// Feed `output` as the PTY reader would: one chunk, then a commit
+    fn output(grid: &RwLock<Grid>, output: &str) {
+        let mut g = grid.write().unwrap();
+        AnsiParser::new().feed_str(output, &mut *g);
+        g.commit_generation();
+    }
+
+    #[test]
+    fn test_send_writes_to_pty() {
+        let (grid, buffer) = terminal(20, 4);
+        let automation = Automation::new(grid, InputSink::new(buffer.clone()));
+        automation.send("ls -l\n").unwrap();
+        assert_eq!(buffer.contents(), b"ls -l\n");
+    }
+
+    #[test]
+    fn test_wait_for_sees_only_new_output() {
+        let (grid, buffer) = terminal(20, 4);
+        output(&grid, "old: 1\r\n");
+        let mut automation = Automation::new(Arc::clone(&grid), InputSink::new(buffer));
+
+        let writer = Arc::clone(&grid);
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(30));
+            output(&writer, "new: 2\r\nnew: 3\r\n$ ");
+        });
+
+        let found = automation.wait_for(r"\w+: (\d)", Duration::from_secs(5)).unwrap();
+        handle.join().unwrap();
+        assert_eq!(found.text, "new: 2");
+        assert_eq!(found.groups, vec![Some("2".to_string())]);
+        assert_eq!(found.row, 1);
+
+        // Matching moves forward through the transcript
+        let found = automation.wait_for(r"\w+: (\d)", Duration::from_secs(1)).unwrap();
+        assert_eq!(found.groups, vec![Some("3".to_string())]);
+        // The prompt's trailing space is kept up to the cursor
+        assert_eq!(automation.wait_for(r"\$ $", Duration::from_secs(1)).unwrap().row, 3);
+    }
+
+    #[test]
+    fn test_wait_wakes_on_commits() {
+        let (grid, buffer) = terminal(20, 4);
+        let mut automation = Automation::new(Arc::clone(&grid), InputSink::new(buffer));
+
+        // The wait sleeps until the writer commits, well within the timeout
+        let writer = Arc::clone(&grid);
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(30));
+            output(&writer, "ready\r\n");
+        });
+        let started = Instant::now();
+        assert_eq!(automation.wait_for("ready", Duration::from_secs(30)).unwrap().text, "ready");
+        assert!(started.elapsed() < Duration::from_secs(5));
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_wide_characters_match_once_at_their_columns() {
+        let (grid, buffer) = terminal(20, 4);
+        let mut automation = Automation::new(Arc::clone(&grid), InputSink::new(buffer));
+        output(&grid, "中文 done\r\n次 ok\r\n");
+
+        let found = automation.wait_for("中文", Duration::from_secs(1)).unwrap();
+        assert_eq!((found.text.as_str(), found.row), ("中文", 0));
+        // Consumed up to column 4, after the two wide characters
+        assert_eq!(automation.consumed, (0, 4));
+        assert_eq!(automation.wait_for(r"\w+ ok", Duration::from_secs(1)).unwrap().text, "次 ok");
+        assert_eq!(automation.consumed, (1, 5));
+    }
+
+    #[test]
+    fn test_wait_for_times_out_and_rejects_bad_patterns() {
+        let (grid, buffer) = terminal(20, 4);
+        output(&grid, "done\r\n");
+        let mut automation = Automation::new(grid, InputSink::new(buffer));
+
+        let started = Instant::now();
+        let err = automation.wait_for("done", Duration::from_millis(50)).unwrap_err();
+        assert!(matches!(err, TerminalError::WaitTimeout { .. }));
+        assert!(started.elapsed() >= Duration::from_millis(50));
+
+        let err = automation.wait_for("(", Duration::from_millis(50)).unwrap_err();
+        assert!(matches!(err, TerminalError::InvalidPattern { .. }));
+    }
+
+    #[test]
+    fn test_capture_between_markers_across_rows_and_scrolling() {
+        let (grid, buffer) = terminal(20, 3);
+        let mut automation = Automation::new(Arc::clone(&grid), InputSink::new(buffer));
+
+        // Enough output that the start marker scrolls into the scrollback
+        output(&grid, "BEGIN\r\none\r\ntwo\r\nthree\r\n");
+        output(&grid, "END\r\n");
+        let captured = automation.capture_between("BEGIN", "END", Duration::from_secs(1)).unwrap();
+        assert_eq!(captured, "one\ntwo\nthree");
+    }
+}
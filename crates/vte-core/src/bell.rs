@@ -0,0 +1,318 @@
+// src/bell.rs
+//! Visual bell and bell policy
+//!
+//! A BEL from the application can be shown on screen: a brief flash of the
+//! widget border, an inverted screen, or a pulse of the background that fades
+//! out over `TerminalConfig::visual_bell_duration_ms`. [`VisualBell`] is the
+//! toolkit-independent state machine. Backends ring it when
+//! `Grid::bell_count` advances, drive the fade with [`VisualBell::animate`]
+//! on their `EventLoop`, and draw [`VisualBell::flash`] over each frame.
+//!
+//! What else a bell does is decided in the core by [`BellPolicy`]
+//! (`TerminalConfig::bell_policy`, or `VteTerminalCore::set_bell_policy` to
+//! switch profiles): `TerminalEvent::Bell` carries the [`BellAction`] for the
+//! terminal's focus state, and the embedder marks the tab, sets the urgency
+//! hint or plays the sound. Bells closer together than
+//! `BellPolicy::min_interval_ms` are dropped, dropped ones included, so a
+//! command spewing BELs (`find /` hitting unreadable directories) raises one
+//! alert, not hundreds, until it pauses.
+
+use crate::config::TerminalConfig;
+use crate::constants::{BELL_MIN_INTERVAL_MS, VISUAL_BELL_FRAME_MS};
+use crate::traits::EventLoop;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// How a bell is shown
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum VisualBellStyle {
+    /// No visual effect
+    #[default]
+    Off,
+    /// Outline the widget edges
+    BorderFlash,
+    /// Invert every color on screen
+    Invert,
+    /// Tint the background with the foreground color
+    BackgroundPulse,
+}
+
+/// Effect to draw over the current frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BellFlash {
+    pub style: VisualBellStyle,
+    /// Strength of the effect, fading from 1.0 to 0.0
+    pub intensity: f64,
+}
+
+/// Fading visual bell
+#[derive(Clone, Debug)]
+pub struct VisualBell {
+    style: VisualBellStyle,
+    duration: Duration,
+    rung_at: Option<Instant>,
+}
+
+impl VisualBell {
+    pub fn new(style: VisualBellStyle, duration: Duration) -> Self {
+        Self { style, duration, rung_at: None }
+    }
+
+    /// Bell with the configured style and duration
+    pub fn from_config(config: &TerminalConfig) -> Self {
+        Self::new(config.visual_bell, Duration::from_millis(config.visual_bell_duration_ms))
+    }
+
+    /// Start the effect over; returns false if the style is `Off`
+    pub fn ring(&mut self, now: Instant) -> bool {
+        if self.style == VisualBellStyle::Off {
+            return false;
+        }
+        self.rung_at = Some(now);
+        true
+    }
+
+    /// Whether the effect is still fading
+    pub fn is_active(&self, now: Instant) -> bool {
+        self.flash(now).is_some()
+    }
+
+    /// Effect to draw at `now`, if any
+    pub fn flash(&self, now: Instant) -> Option<BellFlash> {
+        let elapsed = now.saturating_duration_since(self.rung_at?);
+        if elapsed >= self.duration {
+            return None;
+        }
+        let intensity = 1.0 - elapsed.as_secs_f64() / self.duration.as_secs_f64();
+        Some(BellFlash { style: self.style, intensity })
+    }
+
+    /// Ring `bell` and call `redraw` on every frame of the fade
+    ///
+    /// Frames come from a timer on `event_loop`, which stops after the
+    /// redraw that clears the effect. A bell ringing again while fading
+    /// restarts it on the running timer.
+    pub fn animate(bell: &Rc<RefCell<Self>>, event_loop: &mut dyn EventLoop, mut redraw: impl FnMut() + 'static) {
+        let now = Instant::now();
+        let running = bell.borrow().is_active(now);
+        if !bell.borrow_mut().ring(now) {
+            return;
+        }
+        redraw();
+        if running {
+            return;
+        }
+        let bell = Rc::clone(bell);
+        event_loop.schedule_timer(VISUAL_BELL_FRAME_MS, Box::new(move || {
+            redraw();
+            bell.borrow().is_active(Instant::now())
+        }));
+    }
+}
+
+/// What a bell does beyond the visual bell
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BellAction {
+    /// Nothing; no event is sent
+    Ignore,
+    /// Send `TerminalEvent::Bell` and leave the rest to the embedder
+    #[default]
+    EventOnly,
+    /// Mark the terminal's tab until the user switches to it
+    MarkTab,
+    /// Ask the window manager for attention (urgency hint)
+    RaiseUrgency,
+    /// Play the desktop's bell sound
+    Sound,
+}
+
+/// Bell actions by focus state, with rate limiting
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BellPolicy {
+    /// Action while the terminal has focus
+    pub focused: BellAction,
+    /// Action while it does not, e.g. in a background tab
+    pub unfocused: BellAction,
+    /// Bells within this long of the previous bell, acted on or dropped,
+    /// are dropped
+    pub min_interval_ms: u64,
+}
+
+impl Default for BellPolicy {
+    fn default() -> Self {
+        Self { focused: BellAction::EventOnly, unfocused: BellAction::EventOnly, min_interval_ms: BELL_MIN_INTERVAL_MS }
+    }
+}
+
+impl BellPolicy {
+    /// `action` whether focused or not
+    pub fn always(action: BellAction) -> Self {
+        Self { focused: action, unfocused: action, ..Self::default() }
+    }
+
+    /// Action for a bell in a terminal that is `focused` or not
+    pub fn action(&self, focused: bool) -> BellAction {
+        if focused { self.focused } else { self.unfocused }
+    }
+}
+
+/// Applies a [`BellPolicy`] to the bells of one terminal
+#[derive(Clone, Debug, Default)]
+pub struct BellGate {
+    policy: BellPolicy,
+    /// When the last bell rang, whether acted on or dropped
+    last_rung: Option<Instant>,
+}
+
+impl BellGate {
+    pub fn new(policy: BellPolicy) -> Self {
+        Self { policy, last_rung: None }
+    }
+
+    pub fn policy(&self) -> BellPolicy {
+        self.policy
+    }
+
+    pub fn set_policy(&mut self, policy: BellPolicy) {
+        self.policy = policy;
+    }
+
+    /// Action for a bell at `now`; `None` when ignored or rate limited
+    pub fn ring(&mut self, focused: bool, now: Instant) -> Option<BellAction> {
+        let action = self.policy.action(focused);
+        if action == BellAction::Ignore {
+            return None;
+        }
+        let interval = Duration::from_millis(self.policy.min_interval_ms);
+        // A dropped bell extends the quiet period, so a storm stays quiet
+        let previous = self.last_rung.replace(now);
+        if previous.is_some_and(|last| now.saturating_duration_since(last) < interval) {
+            return None;
+        }
+        Some(action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Event loop whose timers run when the test says so
+    #[derive(Default)]
+    struct ManualLoop {
+        timers: Vec<Box<dyn FnMut() -> bool>>,
+    }
+
+    impl EventLoop for ManualLoop {
+        fn schedule_redraw(&mut self, mut callback: Box<dyn FnMut()>) {
+            callback();
+        }
+
+        fn schedule_timer(&mut self, _interval_ms: u64, callback: Box<dyn FnMut() -> bool>) -> bool {
+            self.timers.push(callback);
+            true
+        }
+    }
+
+    #[test]
+    fn test_flash_fades_out() {
+        let start = Instant::now();
+        let mut bell = VisualBell::new(VisualBellStyle::Invert, Duration::from_millis(100));
+        assert_eq!(bell.flash(start), None);
+
+        assert!(bell.ring(start));
+        assert_eq!(bell.flash(start), Some(BellFlash { style: VisualBellStyle::Invert, intensity: 1.0 }));
+        let half = bell.flash(start + Duration::from_millis(50)).unwrap();
+        assert!((half.intensity - 0.5).abs() < 1e-9);
+        assert!(!bell.is_active(start + Duration::from_millis(100)));
+
+        let mut off = VisualBell::new(VisualBellStyle::Off, Duration::from_millis(100));
+        assert!(!off.ring(start));
+        assert!(!off.is_active(start));
+    }
+
+    #[test]
+    fn test_animate_runs_one_timer_until_faded() {
+        let bell = Rc::new(RefCell::new(VisualBell::new(VisualBellStyle::BorderFlash, Duration::from_millis(50))));
+        let redraws = Rc::new(std::cell::Cell::new(0));
+        let mut event_loop = ManualLoop::default();
+        let count = |redraws: &Rc<std::cell::Cell<u32>>| {
+            let redraws = Rc::clone(redraws);
+            move || redraws.set(redraws.get() + 1)
+        };
+
+        VisualBell::animate(&bell, &mut event_loop, count(&redraws));
+        VisualBell::animate(&bell, &mut event_loop, count(&redraws));
+        assert_eq!(event_loop.timers.len(), 1);
+        assert_eq!(redraws.get(), 2);
+
+        // The timer keeps going while the flash fades, then stops
+        let timer = &mut event_loop.timers[0];
+        assert!(timer());
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(!timer());
+        assert_eq!(redraws.get(), 4);
+    }
+
+    #[test]
+    fn test_bell_gate_follows_focus_and_rate_limit() {
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let policy = BellPolicy { focused: BellAction::Ignore, unfocused: BellAction::MarkTab, min_interval_ms: 1000 };
+        let mut gate = BellGate::new(policy);
+
+        assert_eq!(gate.ring(true, at(0)), None);
+        assert_eq!(gate.ring(false, at(0)), Some(BellAction::MarkTab));
+        assert_eq!(gate.ring(false, at(999)), None);
+        // Measured from the dropped bell
+        assert_eq!(gate.ring(false, at(1000)), None);
+        assert_eq!(gate.ring(false, at(2000)), Some(BellAction::MarkTab));
+
+        gate.set_policy(BellPolicy { min_interval_ms: 0, ..BellPolicy::always(BellAction::Sound) });
+        assert_eq!(gate.ring(true, at(2000)), Some(BellAction::Sound));
+        assert_eq!(gate.ring(true, at(2000)), Some(BellAction::Sound));
+    }
+
+    #[test]
+    fn test_bell_storm_raises_one_alert() {
+        let start = Instant::now();
+        let mut gate = BellGate::new(BellPolicy::always(BellAction::Sound));
+
+        // A bell every 100 ms for 2 s, well inside the 500 ms interval
+        let alerts = (0..20)
+            .filter_map(|i| gate.ring(true, start + Duration::from_millis(i * 100)))
+            .count();
+        assert_eq!(alerts, 1);
+
+        // The first bell after a pause is acted on again
+        let after = start + Duration::from_millis(1900 + BELL_MIN_INTERVAL_MS);
+        assert_eq!(gate.ring(true, after), Some(BellAction::Sound));
+    }
+
+    #[test]
+    fn test_grid_reports_one_bell_per_burst() {
+        use crate::ansi::AnsiParser;
+        use crate::clock::MockClock;
+        use crate::grid::Grid;
+        use std::sync::Arc;
+
+        let policy = BellPolicy { unfocused: BellAction::RaiseUrgency, ..BellPolicy::default() };
+        let mut grid = Grid::new(10, 2, Arc::new(TerminalConfig::default().with_bell_policy(policy)));
+        let clock = MockClock::new();
+        grid.set_clock(Arc::new(clock.clone()));
+        let mut parser = AnsiParser::new();
+
+        parser.feed_str("\x07\x07\x07", &mut grid);
+        assert_eq!(grid.bell_count(), 3);
+        assert_eq!(grid.take_bell(), Some(BellAction::EventOnly));
+        assert_eq!(grid.take_bell(), None);
+
+        grid.set_focused(false);
+        parser.feed_str("\x07", &mut grid);
+        assert_eq!(grid.take_bell(), None);
+        clock.advance(Duration::from_millis(BELL_MIN_INTERVAL_MS));
+        parser.feed_str("\x07", &mut grid);
+        assert_eq!(grid.take_bell(), Some(BellAction::RaiseUrgency));
+    }
+}
@@ -0,0 +1,192 @@
+// src/broadcast.rs
+//! Input broadcast across several terminal cores
+//!
+//! Embedders with splits or tabs register each core's [`InputSink`] in a
+//! [`BroadcastGroup`] and route typed input through
+//! [`BroadcastGroup::broadcast_input`] while broadcasting is on, like tmux's
+//! `synchronize-panes`. Each member can be excluded without leaving the group.
+
+use crate::error::TerminalError;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+/// Cloneable handle writing to one terminal's PTY
+#[derive(Clone)]
+pub struct InputSink {
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl InputSink {
+    /// Sink writing to `writer` (a PTY master, or a buffer in tests)
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        Self::from_shared(Arc::new(Mutex::new(Box::new(writer))))
+    }
+
+    /// Sink sharing an existing writer, e.g. the one owned by `VteTerminalCore`
+    pub fn from_shared(writer: Arc<Mutex<Box<dyn Write + Send>>>) -> Self {
+        Self { writer }
+    }
+
+    /// Write and flush `data`
+    pub fn send(&self, data: &[u8]) -> Result<(), TerminalError> {
+        let mut writer = self.writer.lock()
+            .map_err(|_| TerminalError::GridLockError { message: "Writer lock poisoned".to_string() })?;
+
+        writer.write_all(data).map_err(TerminalError::from)?;
+        writer.flush().map_err(TerminalError::from)?;
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for InputSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InputSink").finish_non_exhaustive()
+    }
+}
+
+/// Identifier of a group member, returned by [`BroadcastGroup::register`]
+pub type MemberId = usize;
+
+#[derive(Debug)]
+struct Member {
+    id: MemberId,
+    sink: InputSink,
+    enabled: bool,
+}
+
+/// Set of terminals that receive the same input
+#[derive(Debug, Default)]
+pub struct BroadcastGroup {
+    members: Vec<Member>,
+    next_id: MemberId,
+}
+
+impl BroadcastGroup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a terminal; new members receive broadcasts until disabled
+    pub fn register(&mut self, sink: InputSink) -> MemberId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.members.push(Member { id, sink, enabled: true });
+        id
+    }
+
+    /// Remove a member; returns false if it was not registered
+    pub fn unregister(&mut self, id: MemberId) -> bool {
+        let before = self.members.len();
+        self.members.retain(|member| member.id != id);
+        self.members.len() != before
+    }
+
+    /// Include or exclude a member from broadcasts; returns false if it is not registered
+    pub fn set_enabled(&mut self, id: MemberId, enabled: bool) -> bool {
+        match self.members.iter_mut().find(|member| member.id == id) {
+            Some(member) => {
+                member.enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether a member receives broadcasts, `None` if it is not registered
+    pub fn is_enabled(&self, id: MemberId) -> Option<bool> {
+        self.members.iter().find(|member| member.id == id).map(|member| member.enabled)
+    }
+
+    /// Number of registered members
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Send `data` to every enabled member
+    ///
+    /// A failing member does not stop delivery to the others. Returns the
+    /// number of members written to, or the first error encountered.
+    pub fn broadcast_input(&self, data: &[u8]) -> Result<usize, TerminalError> {
+        let mut delivered = 0;
+        let mut first_error = None;
+        for member in self.members.iter().filter(|member| member.enabled) {
+            match member.sink.send(data) {
+                Ok(()) => delivered += 1,
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(delivered),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::SharedBuffer;
+
+    struct BrokenPipe;
+
+    impl Write for BrokenPipe {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_broadcast_reaches_enabled_members() {
+        let (a, b, c) = (SharedBuffer::default(), SharedBuffer::default(), SharedBuffer::default());
+        let mut group = BroadcastGroup::new();
+        let id_a = group.register(InputSink::new(a.clone()));
+        let id_b = group.register(InputSink::new(b.clone()));
+        group.register(InputSink::new(c.clone()));
+        assert_eq!(group.len(), 3);
+
+        assert_eq!(group.broadcast_input(b"uptime\r").unwrap(), 3);
+        assert_eq!(b.take(), b"uptime\r");
+        assert!(group.set_enabled(id_b, false));
+        assert_eq!(group.is_enabled(id_b), Some(false));
+        assert_eq!(group.broadcast_input(b"ls\r").unwrap(), 2);
+
+        assert_eq!(a.contents(), b"uptime\rls\r");
+        assert!(b.contents().is_empty());
+        assert_eq!(c.contents(), b"uptime\rls\r");
+
+        assert!(group.unregister(id_a));
+        assert!(!group.unregister(id_a));
+        assert!(!group.set_enabled(id_a, true));
+        assert_eq!(group.is_enabled(id_a), None);
+        assert_eq!(group.broadcast_input(b"x").unwrap(), 1);
+    }
+
+    #[test]
+    fn test_failing_member_does_not_block_others() {
+        let ok = SharedBuffer::default();
+        let mut group = BroadcastGroup::new();
+        group.register(InputSink::new(BrokenPipe));
+        group.register(InputSink::new(ok.clone()));
+
+        assert!(group.broadcast_input(b"hi").is_err());
+        assert_eq!(ok.contents(), b"hi");
+    }
+
+    #[test]
+    fn test_empty_group() {
+        let group = BroadcastGroup::new();
+        assert!(group.is_empty());
+        assert_eq!(group.broadcast_input(b"x").unwrap(), 0);
+    }
+}
@@ -0,0 +1,181 @@
+// src/changes.rs
+//! Change notifications for data derived from the grid
+//!
+//! Search indexes, link caches and accessibility layers keep data computed
+//! from the grid and need to know what to recompute. The grid records
+//! scrollback and screen changes as they happen and delivers them, with the
+//! screen rows changed since the previous commit, to hooks registered with
+//! `Grid::add_change_hook` when it commits a mutation batch
+//! (`Grid::commit_generation`, once per PTY chunk). Consumers that poll
+//! instead compare [`ChangeCounters`] from `Grid::change_counters`.
+//!
+//! Hooks run with the grid locked: they must not lock it again, but note
+//! what changed and rebuild later.
+
+use std::sync::Arc;
+
+/// One change to the grid, as delivered to change hooks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GridChange {
+    /// Screen rows whose cells changed in the commit stamped `generation`
+    RowsChanged { rows: Vec<usize>, generation: u64 },
+    /// Rows moved from the top of the screen into the scrollback
+    ScrollbackAppended { rows: usize },
+    /// Oldest scrollback rows dropped (limit reached or scrollback cleared)
+    ScrollbackTrimmed { rows: usize },
+    /// The scrollback was rebuilt (resize with rewrap, truncation); derived
+    /// data should be recomputed from scratch
+    ScrollbackRewritten,
+    /// Switched to (`alternate`) or from the alternate screen
+    ScreenSwapped { alternate: bool },
+}
+
+/// Monotonic counts of grid changes; any difference between two readings
+/// means derived data of that kind is stale
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChangeCounters {
+    /// Screen generation (see `diff`)
+    pub generation: u64,
+    pub scrollback_appended: u64,
+    pub scrollback_trimmed: u64,
+    pub scrollback_rewrites: u64,
+    pub screen_swaps: u64,
+}
+
+/// Identifies a registered hook for `Grid::remove_change_hook`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChangeHookId(u64);
+
+type ChangeHook = Arc<dyn Fn(&GridChange) + Send + Sync>;
+
+/// Registered hooks, changes awaiting delivery and the change counters
+#[derive(Clone, Default)]
+pub struct ChangeLog {
+    hooks: Vec<(ChangeHookId, ChangeHook)>,
+    next_id: u64,
+    pending: Vec<GridChange>,
+    counters: ChangeCounters,
+}
+
+impl ChangeLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Note a change for the next delivery, merging consecutive scrollback
+    /// appends and trims
+    pub fn record(&mut self, change: GridChange) {
+        match &change {
+            GridChange::RowsChanged { .. } => {}
+            GridChange::ScrollbackAppended { rows } => self.counters.scrollback_appended += *rows as u64,
+            GridChange::ScrollbackTrimmed { rows } => self.counters.scrollback_trimmed += *rows as u64,
+            GridChange::ScrollbackRewritten => self.counters.scrollback_rewrites += 1,
+            GridChange::ScreenSwapped { .. } => self.counters.screen_swaps += 1,
+        }
+        if self.hooks.is_empty() {
+            return;
+        }
+        match (self.pending.last_mut(), &change) {
+            (Some(GridChange::ScrollbackAppended { rows }), GridChange::ScrollbackAppended { rows: more })
+            | (Some(GridChange::ScrollbackTrimmed { rows }), GridChange::ScrollbackTrimmed { rows: more }) => *rows += more,
+            _ => self.pending.push(change),
+        }
+    }
+
+    /// Deliver the recorded changes, then `rows` if any changed in `generation`
+    pub fn deliver(&mut self, rows: Vec<usize>, generation: u64) {
+        if !rows.is_empty() {
+            self.record(GridChange::RowsChanged { rows, generation });
+        }
+        for change in std::mem::take(&mut self.pending) {
+            for (_, hook) in &self.hooks {
+                hook(&change);
+            }
+        }
+    }
+
+    pub fn add_hook(&mut self, hook: impl Fn(&GridChange) + Send + Sync + 'static) -> ChangeHookId {
+        let id = ChangeHookId(self.next_id);
+        self.next_id += 1;
+        self.hooks.push((id, Arc::new(hook)));
+        id
+    }
+
+    /// Unregister a hook; returns false if it was not registered
+    pub fn remove_hook(&mut self, id: ChangeHookId) -> bool {
+        let before = self.hooks.len();
+        self.hooks.retain(|(hook_id, _)| *hook_id != id);
+        self.hooks.len() != before
+    }
+
+    /// Counts so far; `generation` is left for the grid to fill in
+    pub fn counters(&self) -> ChangeCounters {
+        self.counters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::AnsiParser;
+    use crate::config::TerminalConfig;
+    use crate::grid::Grid;
+    use std::sync::Mutex;
+
+    fn recording_grid(cols: usize, rows: usize) -> (Grid, Arc<Mutex<Vec<GridChange>>>) {
+        let mut grid = Grid::new(cols, rows, Arc::new(TerminalConfig::default()));
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&seen);
+        grid.add_change_hook(move |change| log.lock().unwrap().push(change.clone()));
+        grid.commit_generation();
+        seen.lock().unwrap().clear();
+        (grid, seen)
+    }
+
+    #[test]
+    fn test_changes_are_delivered_per_commit() {
+        let (mut grid, seen) = recording_grid(4, 2);
+        let mut parser = AnsiParser::new();
+
+        parser.feed_str("a\r\nb\r\nc\r\nd", &mut grid);
+        assert!(seen.lock().unwrap().is_empty(), "nothing is delivered before the commit");
+        let generation = grid.commit_generation();
+        assert_eq!(*seen.lock().unwrap(), vec![
+            GridChange::ScrollbackAppended { rows: 2 },
+            GridChange::RowsChanged { rows: vec![0, 1], generation },
+        ]);
+        seen.lock().unwrap().clear();
+
+        parser.feed_str("\x1b[?1049h", &mut grid);
+        grid.commit_generation();
+        assert_eq!(seen.lock().unwrap()[0], GridChange::ScreenSwapped { alternate: true });
+
+        let counters = grid.change_counters();
+        assert_eq!((counters.generation, counters.scrollback_appended, counters.screen_swaps), (grid.generation(), 2, 1));
+    }
+
+    #[test]
+    fn test_scrollback_trim_and_rewrite() {
+        let (mut grid, seen) = recording_grid(4, 2);
+        let mut parser = AnsiParser::new();
+        parser.feed_str("a\r\nb\r\nc", &mut grid);
+        parser.feed_str("\x1b[3J", &mut grid);
+        grid.resize_with_rewrap(2, 2);
+        grid.commit_generation();
+        let seen = seen.lock().unwrap();
+        assert!(seen.contains(&GridChange::ScrollbackTrimmed { rows: 1 }));
+        assert!(seen.contains(&GridChange::ScrollbackRewritten));
+    }
+
+    #[test]
+    fn test_removed_hook_is_not_called() {
+        let mut grid = Grid::new(4, 2, Arc::new(TerminalConfig::default()));
+        let calls = Arc::new(Mutex::new(0));
+        let counter = Arc::clone(&calls);
+        let id = grid.add_change_hook(move |_| *counter.lock().unwrap() += 1);
+        assert!(grid.remove_change_hook(id));
+        assert!(!grid.remove_change_hook(id));
+        grid.commit_generation();
+        assert_eq!(*calls.lock().unwrap(), 0);
+    }
+}
@@ -0,0 +1,137 @@
+// src/clipboard.rs
+//! OSC 52 clipboard read requests
+//!
+//! Applications can ask for the clipboard contents with `OSC 52 ; c ; ?`.
+//! Handing the clipboard to whatever runs in the terminal (possibly a remote
+//! host) leaks passwords and tokens, so reads are refused unless
+//! `SecurityConfig::allow_clipboard_read` is set, and even then each request
+//! waits for the user: the core emits `TerminalEvent::ClipboardReadRequest`,
+//! the embedder asks for permission, and answers with
+//! `VteTerminalCore::answer_clipboard_request`. Denied requests get no reply,
+//! as in xterm.
+
+use crate::constants::MAX_PENDING_CLIPBOARD_READS;
+use base64::prelude::*;
+
+/// A clipboard read awaiting the user's permission
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClipboardRequest {
+    pub id: u64,
+    /// Selection names as sent by the application, e.g. `c` for the
+    /// clipboard or `p` for the primary selection; empty means `s0`
+    pub selection: String,
+    /// BEL or ST, matched in the reply
+    terminator: String,
+}
+
+impl ClipboardRequest {
+    /// Whether the application asked for the primary selection rather than
+    /// the clipboard
+    pub fn wants_primary(&self) -> bool {
+        self.selection.starts_with('p')
+    }
+
+    /// OSC 52 reply carrying `text`
+    pub fn reply(&self, text: &str) -> String {
+        format!("\x1B]52;{};{}{}", self.selection, BASE64_STANDARD.encode(text), self.terminator)
+    }
+}
+
+/// Pending clipboard reads of one grid
+#[derive(Clone, Debug, Default)]
+pub struct ClipboardReads {
+    pending: Vec<ClipboardRequest>,
+    /// Requests not yet handed out by `take_new`
+    unannounced: usize,
+    next_id: u64,
+}
+
+impl ClipboardReads {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a request; returns false if too many are already waiting
+    pub fn request(&mut self, selection: &str, terminator: &str) -> bool {
+        if self.pending.len() >= MAX_PENDING_CLIPBOARD_READS {
+            return false;
+        }
+        self.next_id += 1;
+        self.pending.push(ClipboardRequest {
+            id: self.next_id,
+            selection: selection.to_string(),
+            terminator: terminator.to_string(),
+        });
+        self.unannounced += 1;
+        true
+    }
+
+    /// Requests queued since the last call, to ask the user about
+    pub fn take_new(&mut self) -> Vec<ClipboardRequest> {
+        let new = self.pending[self.pending.len() - self.unannounced..].to_vec();
+        self.unannounced = 0;
+        new
+    }
+
+    /// Settle request `id` with the clipboard `text`, or `None` if the user
+    /// refused; returns the reply to send, if any
+    pub fn answer(&mut self, id: u64, text: Option<&str>) -> Option<String> {
+        let index = self.pending.iter().position(|request| request.id == id)?;
+        // An answer can arrive before the request was taken
+        if index >= self.pending.len() - self.unannounced {
+            self.unannounced -= 1;
+        }
+        let request = self.pending.remove(index);
+        text.map(|text| request.reply(text))
+    }
+
+    pub fn pending(&self) -> &[ClipboardRequest] {
+        &self.pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::AnsiParser;
+    use crate::config::TerminalConfig;
+    use crate::grid::Grid;
+    use crate::security::SecurityConfig;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_reads_need_opt_in_and_permission() {
+        let mut parser = AnsiParser::new();
+        let mut grid = Grid::new(10, 2, Arc::new(TerminalConfig::default()));
+        parser.feed_str("\x1b]52;c;?\x07", &mut grid);
+        assert!(grid.take_clipboard_requests().is_empty());
+        assert!(grid.take_responses().is_empty());
+
+        let security = SecurityConfig { allow_clipboard_read: true, ..SecurityConfig::default() };
+        let mut grid = Grid::new(10, 2, Arc::new(TerminalConfig::default().with_security(security)));
+        parser.feed_str("\x1b]52;c;?\x07\x1b]52;p;?\x1b\\", &mut grid);
+        let requests = grid.take_clipboard_requests();
+        assert_eq!(requests.len(), 2);
+        assert!(grid.take_clipboard_requests().is_empty());
+        assert!(requests[1].wants_primary());
+
+        // Nothing is sent back without an answer, or for a refusal
+        assert!(grid.take_responses().is_empty());
+        assert_eq!(grid.answer_clipboard_request(requests[1].id, None), None);
+        assert_eq!(grid.answer_clipboard_request(requests[0].id, Some("hi")), Some("\x1b]52;c;aGk=\x07".to_string()));
+        assert_eq!(grid.answer_clipboard_request(requests[0].id, Some("hi")), None);
+    }
+
+    #[test]
+    fn test_pending_reads_are_bounded() {
+        let mut reads = ClipboardReads::new();
+        for _ in 0..MAX_PENDING_CLIPBOARD_READS {
+            assert!(reads.request("c", "\x07"));
+        }
+        assert!(!reads.request("c", "\x07"));
+        let first = reads.take_new()[0].id;
+        reads.answer(first, None);
+        assert!(reads.request("c", "\x07"));
+        assert_eq!(reads.take_new().len(), 1);
+    }
+}
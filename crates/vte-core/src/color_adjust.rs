@@ -0,0 +1,98 @@
+// src/color_adjust.rs
+//! Brightness, contrast and gamma of the rendered colors
+//!
+//! Projectors wash out dark themes, and some users need a gentler screen to
+//! avoid photosensitive strain. Backends pass every color they draw through
+//! [`ColorAdjust::apply`], after any other draw-time change such as
+//! `InactiveStyle`. The grid and its palette are untouched, so applications
+//! querying colors (OSC 4, 10, 11) still see the logical ones.
+
+use crate::ansi::Color;
+
+/// Adjustment applied to each color channel: gamma, then contrast around
+/// mid-gray, then brightness
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorAdjust {
+    /// Added to each channel, -1.0 to 1.0
+    pub brightness: f64,
+    /// Scales each channel's distance from 0.5; 1.0 leaves it unchanged
+    pub contrast: f64,
+    /// Channels are raised to `1 / gamma`; above 1.0 lightens mid-tones
+    pub gamma: f64,
+}
+
+impl Default for ColorAdjust {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+impl ColorAdjust {
+    /// Colors are drawn as they are
+    pub const NONE: ColorAdjust = ColorAdjust { brightness: 0.0, contrast: 1.0, gamma: 1.0 };
+
+    pub fn is_none(&self) -> bool {
+        *self == Self::NONE
+    }
+
+    /// `color` as drawn; alpha is kept
+    pub fn apply(&self, color: Color) -> Color {
+        if self.is_none() {
+            return color;
+        }
+        let exponent = 1.0 / self.gamma.max(0.01);
+        let contrast = self.contrast.max(0.0);
+        let brightness = self.brightness.clamp(-1.0, 1.0);
+        let channel = |v: f64| ((v.clamp(0.0, 1.0).powf(exponent) - 0.5) * contrast + 0.5 + brightness).clamp(0.0, 1.0);
+        Color { r: channel(color.r), g: channel(color.g), b: channel(color.b), a: color.a }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TerminalConfig;
+    use crate::grid::Grid;
+    use std::sync::Arc;
+
+    fn close(a: Color, b: Color) -> bool {
+        [(a.r, b.r), (a.g, b.g), (a.b, b.b), (a.a, b.a)].iter().all(|(x, y)| (x - y).abs() < 1e-9)
+    }
+
+    #[test]
+    fn test_each_knob() {
+        let color = Color::rgba(0.25, 0.5, 1.0, 0.5);
+        assert_eq!(ColorAdjust::NONE.apply(color), color);
+
+        let brighter = ColorAdjust { brightness: 0.25, ..ColorAdjust::NONE };
+        assert!(close(brighter.apply(color), Color::rgba(0.5, 0.75, 1.0, 0.5)));
+
+        let flatter = ColorAdjust { contrast: 0.5, ..ColorAdjust::NONE };
+        assert!(close(flatter.apply(color), Color::rgba(0.375, 0.5, 0.75, 0.5)));
+
+        let gamma = ColorAdjust { gamma: 2.0, ..ColorAdjust::NONE };
+        assert!(close(gamma.apply(color), Color::rgba(0.5, 0.5f64.sqrt(), 1.0, 0.5)));
+    }
+
+    #[test]
+    fn test_out_of_range_values_are_clamped() {
+        let extreme = ColorAdjust { brightness: 5.0, contrast: -1.0, gamma: 0.0 };
+        let color = extreme.apply(Color::rgb(0.2, 0.4, 0.6));
+        assert_eq!((color.r, color.g, color.b), (1.0, 1.0, 1.0));
+        let dark = ColorAdjust { brightness: -1.0, ..ColorAdjust::NONE };
+        assert_eq!(dark.apply(Color::rgb(0.9, 0.9, 0.9)), Color::rgb(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_reported_palette_is_unchanged() {
+        let reply = |config: TerminalConfig| {
+            let mut grid = Grid::new(10, 2, Arc::new(config));
+            crate::ansi::AnsiParser::new().feed_str("\x1b]4;1;?\x07\x1b]11;?\x07", &mut grid);
+            grid.take_responses()
+        };
+        let adjust = ColorAdjust { brightness: 0.3, contrast: 0.5, gamma: 2.2 };
+        let plain = reply(TerminalConfig::default());
+        assert!(!plain.is_empty());
+        assert_eq!(reply(TerminalConfig::default().with_color_adjust(adjust)), plain);
+    }
+}
@@ -0,0 +1,137 @@
+// src/color_env.rs
+//! Color environment of the child process
+//!
+//! Programs decide whether and how to color their output from a handful of
+//! environment variables: `TERM` names the terminal's capabilities,
+//! `COLORTERM=truecolor` announces 24-bit color, `CLICOLOR` turns on colors
+//! in BSD-style tools such as `ls`, and `NO_COLOR` asks every program
+//! honoring <https://no-color.org> for plain text. `TerminalConfig::color_env`
+//! sets them as one policy:
+//!
+//! - [`ColorPolicy::Color`] (default) advertises `TerminalConfig::term` and
+//!   `colorterm` and turns `CLICOLOR` on. An inherited `NO_COLOR` is the
+//!   user's own choice and is passed on unchanged.
+//! - [`ColorPolicy::Monochrome`] sets `NO_COLOR` and turns `CLICOLOR` off.
+//!   Programs that color their output anyway are not obeyed either: the grid
+//!   ignores SGR color changes, so text keeps the default colors. Other
+//!   attributes (bold, italic, underline) still apply.
+//! - [`ColorPolicy::Inherit`] sets only `TERM` and passes the inherited color
+//!   variables on unchanged.
+//!
+//! [`ChildColorEnv`] records the variables set and removed for the child.
+
+use crate::constants::DEFAULT_LSCOLORS;
+
+/// Color environment for the child process
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorPolicy {
+    /// Advertise color support
+    #[default]
+    Color,
+    /// Ask for plain text and ignore SGR colors
+    Monochrome,
+    /// Set `TERM`, keep the inherited color variables
+    Inherit,
+}
+
+impl ColorPolicy {
+    /// Whether the grid applies SGR color changes
+    pub fn applies_colors(self) -> bool {
+        self != ColorPolicy::Monochrome
+    }
+}
+
+/// Color variables as given to the child
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChildColorEnv {
+    /// Variables set for the child, overriding inherited values
+    pub set: Vec<(String, String)>,
+    /// Inherited variables removed
+    pub removed: Vec<String>,
+}
+
+impl ChildColorEnv {
+    /// Variables for `policy`, advertising `term` and `colorterm`
+    pub fn resolve(policy: ColorPolicy, term: &str, colorterm: Option<&str>) -> Self {
+        let mut env = ChildColorEnv::default();
+        env.set("TERM", term);
+        match policy {
+            ColorPolicy::Color => {
+                match colorterm {
+                    Some(colorterm) => env.set("COLORTERM", colorterm),
+                    None => env.remove("COLORTERM"),
+                }
+                env.set("CLICOLOR", "1");
+                env.set("LSCOLORS", DEFAULT_LSCOLORS);
+            }
+            ColorPolicy::Monochrome => {
+                env.set("NO_COLOR", "1");
+                env.set("CLICOLOR", "0");
+                env.remove("COLORTERM");
+                env.remove("CLICOLOR_FORCE");
+                env.remove("LSCOLORS");
+            }
+            ColorPolicy::Inherit => {}
+        }
+        env
+    }
+
+    /// Value set for `name`, if the policy sets it
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.set.iter().find(|(n, _)| n == name).map(|(_, value)| value.as_str())
+    }
+
+    fn set(&mut self, name: &str, value: &str) {
+        self.set.push((name.to_string(), value.to_string()));
+    }
+
+    fn remove(&mut self, name: &str) {
+        self.removed.push(name.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::AnsiParser;
+    use crate::config::TerminalConfig;
+    use crate::grid::Grid;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_policies_set_a_coherent_environment() {
+        let color = ChildColorEnv::resolve(ColorPolicy::Color, "xterm-256color", Some("truecolor"));
+        assert_eq!(color.get("TERM"), Some("xterm-256color"));
+        assert_eq!(color.get("COLORTERM"), Some("truecolor"));
+        assert_eq!(color.get("CLICOLOR"), Some("1"));
+        assert_eq!(color.get("NO_COLOR"), None);
+        assert!(!color.removed.contains(&"NO_COLOR".to_string()));
+
+        let mono = ChildColorEnv::resolve(ColorPolicy::Monochrome, "xterm-256color", Some("truecolor"));
+        assert_eq!(mono.get("NO_COLOR"), Some("1"));
+        assert_eq!(mono.get("CLICOLOR"), Some("0"));
+        assert_eq!(mono.get("COLORTERM"), None);
+        assert!(mono.removed.contains(&"COLORTERM".to_string()));
+
+        let inherit = ChildColorEnv::resolve(ColorPolicy::Inherit, "hugovte", None);
+        assert_eq!(inherit.set, [("TERM".to_string(), "hugovte".to_string())]);
+        assert!(inherit.removed.is_empty());
+    }
+
+    #[test]
+    fn test_monochrome_grid_ignores_sgr_colors() {
+        let config = TerminalConfig::default().with_color_env(ColorPolicy::Monochrome);
+        let mut grid = Grid::new(10, 2, Arc::new(config));
+        let mut parser = AnsiParser::new();
+        parser.feed_str("\x1b[1;31;42ma\x1b[38;2;1;2;3;48;5;200;58;5;9;4mb", &mut grid);
+
+        for col in 0..2 {
+            let cell = grid.get_cell(0, col);
+            assert_eq!(cell.fg, grid.config.default_fg);
+            assert_eq!(cell.bg, grid.config.default_bg);
+            assert_eq!(grid.config.resolve_decoration(cell), None);
+        }
+        assert!(grid.get_cell(0, 0).bold);
+        assert!(grid.get_cell(0, 1).underline);
+    }
+}
@@ -0,0 +1,118 @@
+// src/commands.rs
+//! Shell command tracking from OSC 133 boundaries
+//!
+//! Shell integration marks the command line (`B`), the start of execution
+//! (`C`) and completion with an exit status (`D`). The tracker times each
+//! command so the grid can report long-running ones that finish while the
+//! terminal is unfocused.
+
+use std::time::{Duration, Instant};
+
+/// Characters that end common shell prompts, for finding prompts when the
+/// shell sends no OSC 133 marks
+pub const PROMPT_CHARS: [char; 5] = ['$', '#', '%', '>', '❯'];
+
+/// A command that ran to completion
+#[derive(Debug, Clone, PartialEq)]
+pub struct FinishedCommand {
+    /// Command line as typed at the prompt (may be empty)
+    pub command: String,
+    /// Time from `OSC 133;C` to completion
+    pub duration: Duration,
+    /// Exit status from `OSC 133;D`, if the shell reported one
+    pub exit_code: Option<i32>,
+}
+
+impl FinishedCommand {
+    /// One-line description for a notification, e.g. `make finished (2m13s, exit 0)`
+    pub fn summary(&self) -> String {
+        let name = self.command.split_whitespace().next().unwrap_or("Command");
+        match self.exit_code {
+            Some(code) => format!("{} finished ({}, exit {})", name, format_duration(self.duration), code),
+            None => format!("{} finished ({})", name, format_duration(self.duration)),
+        }
+    }
+}
+
+/// Compact duration: `42s`, `2m13s`, `1h05m`
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if h > 0 {
+        format!("{}h{:02}m", h, m)
+    } else if m > 0 {
+        format!("{}m{:02}s", m, s)
+    } else {
+        format!("{}s", s)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct RunningCommand {
+    command: String,
+    started: Instant,
+}
+
+/// Start and end of the command currently running in the shell
+#[derive(Debug, Clone, Default)]
+pub struct CommandTracker {
+    running: Option<RunningCommand>,
+}
+
+impl CommandTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Execution of `command` began (`OSC 133;C`)
+    pub fn start(&mut self, command: String, now: Instant) {
+        self.running = Some(RunningCommand { command, started: now });
+    }
+
+    /// The running command ended; `None` if no command was running
+    pub fn finish(&mut self, exit_code: Option<i32>, now: Instant) -> Option<FinishedCommand> {
+        self.running.take().map(|running| FinishedCommand {
+            command: running.command,
+            duration: now.saturating_duration_since(running.started),
+            exit_code,
+        })
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(999)), "0s");
+        assert_eq!(format_duration(Duration::from_secs(42)), "42s");
+        assert_eq!(format_duration(Duration::from_secs(133)), "2m13s");
+        assert_eq!(format_duration(Duration::from_secs(3900)), "1h05m");
+    }
+
+    #[test]
+    fn test_tracker_times_one_command() {
+        let mut tracker = CommandTracker::new();
+        let start = Instant::now();
+        assert_eq!(tracker.finish(Some(0), start), None);
+
+        tracker.start("make -j8".to_string(), start);
+        assert!(tracker.is_running());
+        let finished = tracker.finish(Some(0), start + Duration::from_secs(133)).unwrap();
+        assert_eq!(finished.duration, Duration::from_secs(133));
+        assert_eq!(finished.summary(), "make finished (2m13s, exit 0)");
+        assert!(!tracker.is_running());
+        assert_eq!(tracker.finish(Some(1), start), None);
+    }
+
+    #[test]
+    fn test_summary_without_command_or_status() {
+        let finished = FinishedCommand { command: String::new(), duration: Duration::from_secs(5), exit_code: None };
+        assert_eq!(finished.summary(), "Command finished (5s)");
+    }
+}
@@ -1,8 +1,27 @@
 // src/config.rs
-use crate::ansi::Color;
+use crate::ansi::{Cell, Color, LogicalColor};
 use crate::constants::{DEFAULT_FONT_SIZE, DEFAULT_FONT_FAMILY, SCROLLBACK_LIMIT,
-                      CURSOR_BLINK_INTERVAL_MS, CLICK_TIMEOUT_MS, DEFAULT_FG, DEFAULT_BG,
-                      DEFAULT_BOLD_IS_BRIGHT};
+                      CURSOR_BLINK_INTERVAL_MS, CLICK_TIMEOUT_MS, DRAG_THRESHOLD_PX, HOVER_DELAY_MS, REMOTE_CURSOR_TIMEOUT_MS, RESIZE_DEBOUNCE_MS, SNAPSHOT_CAPACITY, VISUAL_BELL_DURATION_MS, DEFAULT_FG, DEFAULT_BG,
+                      DEFAULT_BOLD_IS_BRIGHT, ALTERNATE_FONT_SLOTS, DEFAULT_SELECTION_CORNER_RADIUS,
+                      DEFAULT_LETTER_SPACING, DEFAULT_LINE_SPACING, DEFAULT_PADDING, MIN_ZOOM, MAX_ZOOM, ZOOM_STEP,
+                      DEFAULT_TERM, DEFAULT_COLORTERM, WHEEL_SCROLL_LINES, PARSE_SLICE_BYTES, PARSE_SLICE_US};
+use crate::bell::{BellPolicy, VisualBellStyle};
+use crate::color_adjust::ColorAdjust;
+use crate::color_env::ColorPolicy;
+use crate::encoding::Encoding;
+use crate::contrast::{ensure_contrast, selection_colors, MIN_SELECTION_CONTRAST, HIGH_CONTRAST_BG, HIGH_CONTRAST_FG, HIGH_CONTRAST_MIN_RATIO, HIGH_CONTRAST_PALETTE};
+use crate::hyperlinks::HyperlinkCopyMode;
+use crate::inactive::InactiveStyle;
+use crate::link_rules::LinkRule;
+use crate::locale::LocalePolicy;
+use crate::parse_budget::ParseBudget;
+use crate::predict::LocalEcho;
+use crate::security::SecurityConfig;
+use crate::theme::{ColorScheme, Theme, ThemePair};
+use crate::traits::CursorShape;
+use crate::width::{AmbiguousWidth, EmojiWidth, WidthPolicy};
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Clone, Debug)]
 pub struct TerminalConfig {
@@ -10,7 +29,15 @@ pub struct TerminalConfig {
     pub font_family: String,
     pub scrollback_limit: usize,
     pub cursor_blink_interval_ms: u64,
+    /// Multi-click interval: clicks on one cell closer together than this
+    /// count as a double or triple click, and a press released sooner is a
+    /// click rather than a one-cell selection. The GTK4 backend uses the
+    /// desktop's double-click time while this holds the default.
     pub click_timeout_ms: u128,
+    /// Pointer travel, in logical pixels, before a press starts a selection
+    /// drag; the GTK4 backend uses the desktop's drag threshold while this
+    /// holds the default
+    pub drag_threshold_px: f64,
     pub default_fg: Color,
     pub default_bg: Color,
     pub enable_cursor_blink: bool,
@@ -19,6 +46,140 @@ pub struct TerminalConfig {
     pub grid_line_alpha: f64,
     /// Legacy compatibility: bold also makes colors bright (ANSI 8-15 instead of 0-7)
     pub bold_is_bright: bool,
+    /// Column width of East Asian ambiguous-width characters
+    pub ambiguous_width: AmbiguousWidth,
+    /// Emoji / variation selector width rules
+    pub emoji_width: EmojiWidth,
+    /// Font families for SGR 11-19 (slots 1-9) and SGR 20 Fraktur (slot 10),
+    /// indexed by `slot - 1`; `None` falls back to `font_family`
+    pub alternate_fonts: [Option<String>; ALTERNATE_FONT_SLOTS],
+    /// Hide the mouse pointer while typing; it reappears on the next motion
+    pub hide_pointer_while_typing: bool,
+    /// Erase display (ED 2) on the primary screen moves the screen contents
+    /// into the scrollback instead of discarding them
+    pub scroll_on_clear: bool,
+    /// New output scrolls the viewport back to the bottom; when off, a
+    /// scrolled-back viewport stays put and the rows below it are counted as
+    /// unseen (`TerminalEvent::UnseenOutput`)
+    pub scroll_on_output: bool,
+    /// Pin the viewport while a selection is being dragged: output still
+    /// goes to the screen and scrollback, but the rows under the pointer
+    /// stay put until the button is released
+    pub freeze_on_select: bool,
+    /// Viewport lines scrolled per mouse wheel notch
+    pub wheel_scroll_lines: f64,
+    /// The wheel with Shift held scrolls a screen height per notch
+    pub shift_wheel_scrolls_page: bool,
+    /// Reverse the wheel direction (content follows the fingers, as on touchpads)
+    pub natural_scrolling: bool,
+    /// Report commands that ran at least this long and finished while the
+    /// terminal was unfocused (`TerminalEvent::CommandFinished`); `None` disables
+    pub command_notify_threshold_ms: Option<u64>,
+    /// Emit `TerminalEvent::Damage` at most this often while the screen
+    /// changes, e.g. for tab thumbnails; `None` disables
+    pub damage_event_interval_ms: Option<u64>,
+    /// Emit `TerminalEvent::Silence` after this long without output, and
+    /// `TerminalEvent::Activity` when output resumes (see `activity`); `None`
+    /// disables
+    pub silence_threshold_ms: Option<u64>,
+    /// Emit `TerminalEvent::ParserHealth` with parser rates and the last
+    /// parse error every this long while output is parsed (see `health`);
+    /// `None` disables
+    pub parser_health_interval_ms: Option<u64>,
+    /// Text cursor shape
+    pub cursor_shape: CursorShape,
+    /// Cursor fill color; `None` uses reverse video of the cell under it
+    pub cursor_color: Option<Color>,
+    /// Glyph color under a block cursor; `None` uses the cell's background
+    pub cursor_text_color: Option<Color>,
+    /// Parse each PTY chunk into a copy of the grid and commit it whole, so a
+    /// parser panic leaves the last committed screen intact (see `journal`);
+    /// off by default, as the copy grows with the scrollback
+    pub journaled_parsing: bool,
+    /// Bytes of output parsed before the reader releases the grid lock for
+    /// the renderer (see `parse_budget`)
+    pub parse_slice_bytes: usize,
+    /// Microseconds spent parsing before the reader releases the grid lock
+    pub parse_slice_us: u64,
+    /// Record key events with their encoding and the modes in effect (see
+    /// `input_trace`)
+    pub input_trace: bool,
+    /// File each traced key event is appended to
+    pub input_trace_file: Option<PathBuf>,
+    /// High-contrast rendering: high-contrast theme and palette, opaque
+    /// background, thicker cursor and underlines (see `contrast`)
+    pub high_contrast: bool,
+    /// What copying a selection produces for OSC 8 hyperlinked text
+    pub copy_hyperlinks: HyperlinkCopyMode,
+    /// Corner radius of the selection highlight in pixels; 0 draws square
+    /// corners
+    pub selection_corner_radius: f64,
+    /// Selection background; `None` derives it from the theme (see `contrast`)
+    pub selection_bg: Option<Color>,
+    /// Selected text color; `None` uses the theme foreground, kept readable
+    pub selection_fg: Option<Color>,
+    /// How a terminal is muted while its container marks it inactive (see
+    /// `inactive`)
+    pub inactive_style: InactiveStyle,
+    /// Brightness, contrast and gamma of every color drawn (see
+    /// `color_adjust`)
+    pub color_adjust: ColorAdjust,
+    /// How long the pointer rests on a link before `TerminalEvent::HoverUrl`
+    pub hover_delay_ms: u64,
+    /// Patterns linked like URLs, e.g. issue numbers or commit hashes (see
+    /// `link_rules`)
+    pub link_rules: Vec<LinkRule>,
+    /// Extra horizontal space per cell in pixels (letter spacing)
+    pub letter_spacing: f64,
+    /// Extra vertical space per cell in pixels (line spacing)
+    pub line_spacing: f64,
+    /// Space between the widget edges and the cells in pixels
+    pub padding: f64,
+    /// Light and dark themes; when set, the default colors follow `color_scheme`
+    pub themes: Option<ThemePair>,
+    /// Current light/dark scheme, usually the desktop preference (see `theme`)
+    pub color_scheme: ColorScheme,
+    /// Draw typed characters before their echo arrives (see `predict`)
+    pub local_echo: LocalEcho,
+    /// How a BEL is shown on screen (see `bell`)
+    pub visual_bell: VisualBellStyle,
+    /// How long the visual bell takes to fade out
+    pub visual_bell_duration_ms: u64,
+    /// What a bell does besides the visual bell, by focus state (see `bell`)
+    pub bell_policy: BellPolicy,
+    /// Zoom factor over `font_size` (Ctrl+wheel), between `MIN_ZOOM` and `MAX_ZOOM`
+    pub zoom: f64,
+    /// Opt-ins for sequences that can leak data, e.g. OSC 52 clipboard reads
+    pub security: SecurityConfig,
+    /// Remote collaborators' cursors disappear after this long without an
+    /// update (see `remote`)
+    pub remote_cursor_timeout_ms: u64,
+    /// The PTY (and so the child's SIGWINCH) follows a resize once the size
+    /// has been stable this long; the grid resizes right away. 0 resizes
+    /// the PTY on every change
+    pub resize_debounce_ms: u64,
+    /// Copy the screen into the time-travel ring at most this often while
+    /// it changes (see `snapshots`); `None` disables
+    pub snapshot_interval_ms: Option<u64>,
+    /// Screen snapshots kept for time travel
+    pub snapshot_capacity: usize,
+    /// `TERM` for the child process; `hugovte` matches the implemented
+    /// capabilities exactly once installed (see `termcap`)
+    pub term: String,
+    /// `COLORTERM` for the child process, unset if `None`
+    pub colorterm: Option<String>,
+    /// Color variables for the child process (`COLORTERM`, `CLICOLOR`,
+    /// `NO_COLOR`); `Monochrome` also ignores SGR colors (see `color_env`)
+    pub color_env: ColorPolicy,
+    /// Terminfo database holding the `hugovte` entry, exported as `TERMINFO`
+    pub terminfo_dir: Option<PathBuf>,
+    /// `LANG` and `LC_*` for the child process (see `locale`)
+    pub locale: LocalePolicy,
+    /// Encoding of the child's output; `None` decodes UTF-8 and switches to
+    /// `fallback_encoding` when the output keeps failing validation
+    pub encoding: Option<Encoding>,
+    /// Encoding assumed for output that is not UTF-8 (see `encoding`)
+    pub fallback_encoding: Encoding,
 }
 
 impl Default for TerminalConfig {
@@ -29,6 +190,7 @@ impl Default for TerminalConfig {
             scrollback_limit: SCROLLBACK_LIMIT,
             cursor_blink_interval_ms: CURSOR_BLINK_INTERVAL_MS,
             click_timeout_ms: CLICK_TIMEOUT_MS,
+            drag_threshold_px: DRAG_THRESHOLD_PX,
             default_fg: DEFAULT_FG,
             default_bg: DEFAULT_BG,
             enable_cursor_blink: true,
@@ -36,6 +198,59 @@ impl Default for TerminalConfig {
             draw_grid_lines: false,
             grid_line_alpha: 0.8,
             bold_is_bright: DEFAULT_BOLD_IS_BRIGHT,
+            ambiguous_width: AmbiguousWidth::default(),
+            emoji_width: EmojiWidth::default(),
+            alternate_fonts: Default::default(),
+            hide_pointer_while_typing: true,
+            scroll_on_clear: true,
+            scroll_on_output: true,
+            freeze_on_select: false,
+            wheel_scroll_lines: WHEEL_SCROLL_LINES,
+            shift_wheel_scrolls_page: true,
+            natural_scrolling: false,
+            command_notify_threshold_ms: None,
+            damage_event_interval_ms: None,
+            silence_threshold_ms: None,
+            parser_health_interval_ms: None,
+            cursor_shape: CursorShape::default(),
+            cursor_color: None,
+            cursor_text_color: None,
+            journaled_parsing: false,
+            parse_slice_bytes: PARSE_SLICE_BYTES,
+            parse_slice_us: PARSE_SLICE_US,
+            input_trace: false,
+            input_trace_file: None,
+            high_contrast: false,
+            copy_hyperlinks: HyperlinkCopyMode::default(),
+            selection_corner_radius: DEFAULT_SELECTION_CORNER_RADIUS,
+            selection_bg: None,
+            selection_fg: None,
+            inactive_style: InactiveStyle::default(),
+            color_adjust: ColorAdjust::NONE,
+            hover_delay_ms: HOVER_DELAY_MS,
+            link_rules: Vec::new(),
+            letter_spacing: DEFAULT_LETTER_SPACING,
+            line_spacing: DEFAULT_LINE_SPACING,
+            padding: DEFAULT_PADDING,
+            themes: None,
+            color_scheme: ColorScheme::default(),
+            local_echo: LocalEcho::default(),
+            visual_bell: VisualBellStyle::default(),
+            visual_bell_duration_ms: VISUAL_BELL_DURATION_MS,
+            bell_policy: BellPolicy::default(),
+            zoom: 1.0,
+            security: SecurityConfig::default(),
+            remote_cursor_timeout_ms: REMOTE_CURSOR_TIMEOUT_MS,
+            resize_debounce_ms: RESIZE_DEBOUNCE_MS,
+            snapshot_interval_ms: None,
+            snapshot_capacity: SNAPSHOT_CAPACITY,
+            term: DEFAULT_TERM.to_string(),
+            colorterm: Some(DEFAULT_COLORTERM.to_string()),
+            color_env: ColorPolicy::default(),
+            terminfo_dir: None,
+            locale: LocalePolicy::default(),
+            encoding: None,
+            fallback_encoding: Encoding::Cp1252,
         }
     }
 }
@@ -50,6 +265,32 @@ impl TerminalConfig {
         self
     }
     
+    pub fn with_zoom(mut self, zoom: f64) -> Self {
+        self.set_zoom(zoom);
+        self
+    }
+    
+    /// Set the zoom factor, clamped to `MIN_ZOOM..=MAX_ZOOM`; returns whether it changed
+    ///
+    /// `font_size` is left alone, so choosing another font size keeps the zoom.
+    pub fn set_zoom(&mut self, zoom: f64) -> bool {
+        let zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        let changed = zoom != self.zoom;
+        self.zoom = zoom;
+        changed
+    }
+    
+    /// Zoom in (`notches` < 0, wheel up) or out by `ZOOM_STEP` per wheel notch;
+    /// fractional notches from smooth scrolling zoom proportionally
+    pub fn zoom_by(&mut self, notches: f64) -> bool {
+        self.set_zoom(self.zoom * ZOOM_STEP.powf(-notches))
+    }
+    
+    /// Font size to render with: `font_size` times `zoom`
+    pub fn zoomed_font_size(&self) -> f64 {
+        self.font_size * self.zoom
+    }
+    
     pub fn with_font_family(mut self, family: &str) -> Self {
         self.font_family = family.to_string();
         self
@@ -71,6 +312,110 @@ impl TerminalConfig {
         self
     }
     
+    /// Paired themes; the default colors come from the one for `color_scheme`
+    pub fn with_themes(mut self, light: Theme, dark: Theme) -> Self {
+        self.themes = Some(ThemePair { light, dark });
+        self.apply_color_scheme(self.color_scheme);
+        self
+    }
+    
+    pub fn with_color_scheme(mut self, scheme: ColorScheme) -> Self {
+        self.apply_color_scheme(scheme);
+        self
+    }
+    
+    /// Switch to `scheme`, taking the default colors from its theme when
+    /// `themes` is set; returns whether the scheme or the colors changed
+    pub fn apply_color_scheme(&mut self, scheme: ColorScheme) -> bool {
+        let mut changed = self.color_scheme != scheme;
+        self.color_scheme = scheme;
+        if let Some(themes) = self.themes {
+            let theme = themes.theme(scheme);
+            changed |= (self.default_fg, self.default_bg) != (theme.foreground, theme.background);
+            self.default_fg = theme.foreground;
+            self.default_bg = theme.background;
+        }
+        changed
+    }
+    
+    /// The scheme the default colors actually follow, as reported for `CSI
+    /// ? 996 n`: `color_scheme` with `themes` set, otherwise whether the
+    /// default colors are light text on a dark background or the reverse
+    pub fn applied_color_scheme(&self) -> ColorScheme {
+        if self.themes.is_some() {
+            return self.color_scheme;
+        }
+        if self.default_fg.relative_luminance() >= self.default_bg.relative_luminance() {
+            ColorScheme::Dark
+        } else {
+            ColorScheme::Light
+        }
+    }
+    
+    pub fn with_local_echo(mut self, mode: LocalEcho) -> Self {
+        self.local_echo = mode;
+        self
+    }
+    
+    pub fn with_visual_bell(mut self, style: VisualBellStyle, duration_ms: u64) -> Self {
+        self.visual_bell = style;
+        self.visual_bell_duration_ms = duration_ms;
+        self
+    }
+    
+    pub fn with_bell_policy(mut self, policy: BellPolicy) -> Self {
+        self.bell_policy = policy;
+        self
+    }
+    
+    /// Coalesce PTY resizes until the size has been stable for `debounce_ms`; 0 disables
+    pub fn with_resize_debounce(mut self, debounce_ms: u64) -> Self {
+        self.resize_debounce_ms = debounce_ms;
+        self
+    }
+    
+    /// Keep up to `capacity` screen snapshots, taken at most every `interval_ms`
+    pub fn with_snapshots(mut self, interval_ms: u64, capacity: usize) -> Self {
+        self.snapshot_interval_ms = Some(interval_ms);
+        self.snapshot_capacity = capacity;
+        self
+    }
+    
+    /// Advertise `term` as `TERM` and `colorterm` as `COLORTERM`
+    pub fn with_term(mut self, term: &str, colorterm: Option<&str>) -> Self {
+        self.term = term.to_string();
+        self.colorterm = colorterm.map(str::to_string);
+        self
+    }
+    
+    pub fn with_color_env(mut self, policy: ColorPolicy) -> Self {
+        self.color_env = policy;
+        self
+    }
+    
+    pub fn with_terminfo_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.terminfo_dir = Some(dir.into());
+        self
+    }
+    
+    pub fn with_locale(mut self, locale: LocalePolicy) -> Self {
+        self.locale = locale;
+        self
+    }
+    
+    /// Decode output as `encoding`, or detect it if `None`, assuming
+    /// `fallback` for output that is not UTF-8
+    pub fn with_encoding(mut self, encoding: Option<Encoding>, fallback: Encoding) -> Self {
+        self.encoding = encoding;
+        self.fallback_encoding = fallback;
+        self
+    }
+    
+    pub fn with_security(mut self, security: SecurityConfig) -> Self {
+        self.security = security;
+        self
+    }
+    
     pub fn with_grid_lines(mut self, enabled: bool) -> Self {
         self.draw_grid_lines = enabled;
         self
@@ -80,4 +425,326 @@ impl TerminalConfig {
         self.grid_line_alpha = alpha.clamp(0.0, 1.0);
         self
     }
+    
+    pub fn with_ambiguous_width(mut self, width: AmbiguousWidth) -> Self {
+        self.ambiguous_width = width;
+        self
+    }
+    
+    pub fn with_emoji_width(mut self, width: EmojiWidth) -> Self {
+        self.emoji_width = width;
+        self
+    }
+    
+    pub fn with_hide_pointer_while_typing(mut self, enabled: bool) -> Self {
+        self.hide_pointer_while_typing = enabled;
+        self
+    }
+    
+    pub fn with_scroll_on_clear(mut self, enabled: bool) -> Self {
+        self.scroll_on_clear = enabled;
+        self
+    }
+    
+    pub fn with_scroll_on_output(mut self, enabled: bool) -> Self {
+        self.scroll_on_output = enabled;
+        self
+    }
+
+    pub fn with_freeze_on_select(mut self, enabled: bool) -> Self {
+        self.freeze_on_select = enabled;
+        self
+    }
+
+    /// Count clicks less than `timeout_ms` apart as a double or triple click
+    pub fn with_click_timeout(mut self, timeout_ms: u128) -> Self {
+        self.click_timeout_ms = timeout_ms;
+        self
+    }
+
+    /// Start a selection drag once the pointer moved `pixels` from the press
+    pub fn with_drag_threshold(mut self, pixels: f64) -> Self {
+        self.drag_threshold_px = pixels.max(0.0);
+        self
+    }
+
+    /// Scroll `lines` per wheel notch (clamped to at least a tenth of a line)
+    pub fn with_wheel_scroll_lines(mut self, lines: f64) -> Self {
+        self.wheel_scroll_lines = lines.max(0.1);
+        self
+    }
+
+    pub fn with_shift_wheel_scrolls_page(mut self, enabled: bool) -> Self {
+        self.shift_wheel_scrolls_page = enabled;
+        self
+    }
+
+    pub fn with_natural_scrolling(mut self, enabled: bool) -> Self {
+        self.natural_scrolling = enabled;
+        self
+    }
+    
+    /// Notify about commands running longer than `threshold_ms` that finish while unfocused
+    pub fn with_command_notify_threshold(mut self, threshold_ms: u64) -> Self {
+        self.command_notify_threshold_ms = Some(threshold_ms);
+        self
+    }
+    
+    /// Report screen changes with `TerminalEvent::Damage`, at most once per `interval_ms`
+    pub fn with_damage_events(mut self, interval_ms: u64) -> Self {
+        self.damage_event_interval_ms = Some(interval_ms);
+        self
+    }
+    
+    /// Report silence after `threshold_ms` without output, and the activity ending it
+    pub fn with_silence_threshold(mut self, threshold_ms: u64) -> Self {
+        self.silence_threshold_ms = Some(threshold_ms);
+        self
+    }
+    
+    /// Report parser health with `TerminalEvent::ParserHealth` every `interval_ms`
+    pub fn with_parser_health(mut self, interval_ms: u64) -> Self {
+        self.parser_health_interval_ms = Some(interval_ms);
+        self
+    }
+    
+    pub fn with_cursor_shape(mut self, shape: CursorShape) -> Self {
+        self.cursor_shape = shape;
+        self
+    }
+    
+    pub fn with_cursor_color(mut self, color: Color) -> Self {
+        self.cursor_color = Some(color);
+        self
+    }
+    
+    pub fn with_cursor_text_color(mut self, color: Color) -> Self {
+        self.cursor_text_color = Some(color);
+        self
+    }
+    
+    pub fn with_journaled_parsing(mut self, enabled: bool) -> Self {
+        self.journaled_parsing = enabled;
+        self
+    }
+    
+    /// Parse at most `bytes` of output or for `micros` microseconds before
+    /// letting the renderer at the grid
+    pub fn with_parse_slice(mut self, bytes: usize, micros: u64) -> Self {
+        self.parse_slice_bytes = bytes.max(1);
+        self.parse_slice_us = micros;
+        self
+    }
+    
+    /// Limits on one slice of parsing, from `parse_slice_bytes` and `parse_slice_us`
+    pub fn parse_budget(&self) -> ParseBudget {
+        ParseBudget::new(self.parse_slice_bytes, Duration::from_micros(self.parse_slice_us))
+    }
+    
+    /// Trace key input, also appending each event to `file` if given
+    pub fn with_input_trace(mut self, file: Option<PathBuf>) -> Self {
+        self.input_trace = true;
+        self.input_trace_file = file;
+        self
+    }
+    
+    pub fn with_high_contrast(mut self, enabled: bool) -> Self {
+        self.high_contrast = enabled;
+        self
+    }
+    
+    pub fn with_copy_hyperlinks(mut self, mode: HyperlinkCopyMode) -> Self {
+        self.copy_hyperlinks = mode;
+        self
+    }
+    
+    pub fn with_selection_corner_radius(mut self, radius: f64) -> Self {
+        self.selection_corner_radius = radius.max(0.0);
+        self
+    }
+    
+    /// Fix the selection background and/or text color; `None` derives it from the theme
+    pub fn with_selection_colors(mut self, bg: Option<Color>, fg: Option<Color>) -> Self {
+        self.selection_bg = bg;
+        self.selection_fg = fg;
+        self
+    }
+    
+    pub fn with_inactive_style(mut self, style: InactiveStyle) -> Self {
+        self.inactive_style = style;
+        self
+    }
+    
+    pub fn with_color_adjust(mut self, adjust: ColorAdjust) -> Self {
+        self.color_adjust = adjust;
+        self
+    }
+    
+    pub fn with_hover_delay_ms(mut self, delay_ms: u64) -> Self {
+        self.hover_delay_ms = delay_ms;
+        self
+    }
+    
+    /// Link text matching `rule` to the URL it generates; earlier rules win
+    pub fn with_link_rule(mut self, rule: LinkRule) -> Self {
+        self.link_rules.push(rule);
+        self
+    }
+    
+    /// Extra space between columns and between rows, in pixels
+    pub fn with_cell_spacing(mut self, letter_spacing: f64, line_spacing: f64) -> Self {
+        self.letter_spacing = letter_spacing.max(0.0);
+        self.line_spacing = line_spacing.max(0.0);
+        self
+    }
+    
+    pub fn with_padding(mut self, padding: f64) -> Self {
+        self.padding = padding.max(0.0);
+        self
+    }
+    
+    /// Default cursor blinking; applications may override it with DECSET 12
+    pub fn with_cursor_blink(mut self, enabled: bool) -> Self {
+        self.enable_cursor_blink = enabled;
+        self
+    }
+    
+    /// Assign a font family to an alternate font slot (1-10); other slots are ignored
+    pub fn with_alternate_font(mut self, slot: u8, family: &str) -> Self {
+        if let Some(entry) = (slot as usize).checked_sub(1).and_then(|i| self.alternate_fonts.get_mut(i)) {
+            *entry = Some(family.to_string());
+        }
+        self
+    }
+    
+    /// Font family for a cell's font slot, falling back to the primary font
+    pub fn font_family_for_slot(&self, slot: u8) -> &str {
+        (slot as usize).checked_sub(1)
+            .and_then(|i| self.alternate_fonts.get(i))
+            .and_then(|family| family.as_deref())
+            .unwrap_or(&self.font_family)
+    }
+    
+    /// Foreground color to render for a cell
+    ///
+    /// Resolves the theme default (SGR 39) and applies bold-is-bright.
+    pub fn resolve_fg(&self, cell: &Cell) -> Color {
+        if self.high_contrast {
+            let fg = match cell.fg_logical {
+                LogicalColor::Default => HIGH_CONTRAST_FG,
+                LogicalColor::Indexed(idx) if idx < 8 && self.bold_is_bright && cell.bold => {
+                    HIGH_CONTRAST_PALETTE[idx as usize + 8]
+                }
+                LogicalColor::Indexed(idx) if idx < 16 => HIGH_CONTRAST_PALETTE[idx as usize],
+                _ => cell.fg.opaque(),
+            };
+            return ensure_contrast(fg, self.resolve_bg(cell), HIGH_CONTRAST_MIN_RATIO);
+        }
+        match cell.fg_logical {
+            LogicalColor::Default => self.default_fg,
+            _ => cell.display_fg(self.bold_is_bright),
+        }
+    }
+    
+    /// Background color to render for a cell, resolving the theme default (SGR 49)
+    pub fn resolve_bg(&self, cell: &Cell) -> Color {
+        if self.high_contrast {
+            return match cell.bg_logical {
+                LogicalColor::Default => HIGH_CONTRAST_BG,
+                LogicalColor::Indexed(idx) if idx < 16 => HIGH_CONTRAST_PALETTE[idx as usize],
+                _ => cell.bg.opaque(),
+            };
+        }
+        match cell.bg_logical {
+            LogicalColor::Default => self.default_bg,
+            _ => cell.bg,
+        }
+    }
+    
+    /// Color to draw a cell's underline, overline and strikethrough in, or
+    /// `None` to draw them in the color its glyph is drawn in
+    ///
+    /// Decorations follow the foreground unless SGR 58 set a color, so they
+    /// change with it mid-row and take the cursor or selection text color
+    /// where the glyph does. An SGR 58 color is kept as is under the
+    /// block cursor.
+    pub fn resolve_decoration(&self, cell: &Cell) -> Option<Color> {
+        match cell.decoration_logical {
+            LogicalColor::Default => None,
+            _ if self.high_contrast => Some(cell.decoration.opaque()),
+            _ => Some(cell.decoration),
+        }
+    }
+    
+    /// Default background to paint behind the cells; never transparent in high-contrast mode
+    pub fn background(&self) -> Color {
+        if self.high_contrast { HIGH_CONTRAST_BG } else { self.default_bg }
+    }
+    
+    /// Selection background and text color, both opaque
+    ///
+    /// High-contrast mode inverts the theme. Otherwise unset colors are
+    /// derived from the theme, and a derived text color keeps
+    /// `MIN_SELECTION_CONTRAST` against the background.
+    pub fn selection_colors(&self) -> (Color, Color) {
+        if self.high_contrast {
+            return (HIGH_CONTRAST_FG, HIGH_CONTRAST_BG);
+        }
+        let bg = self.selection_bg.map_or_else(|| selection_colors(self.default_fg, self.default_bg).0, |c| c.opaque());
+        let fg = self.selection_fg.map_or_else(
+            || ensure_contrast(self.default_fg.opaque(), bg, MIN_SELECTION_CONTRAST),
+            |c| c.opaque(),
+        );
+        (bg, fg)
+    }
+    
+    /// Multiplier for cursor and underline thickness
+    pub fn decoration_scale(&self) -> f64 {
+        if self.high_contrast { 2.0 } else { 1.0 }
+    }
+    
+    /// Width policy derived from the ambiguous/emoji width settings
+    pub fn width_policy(&self) -> WidthPolicy {
+        WidthPolicy::new(self.ambiguous_width, self.emoji_width)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alternate_font_slots() {
+        let config = TerminalConfig::default()
+            .with_font_family("Mono")
+            .with_alternate_font(1, "Serif")
+            .with_alternate_font(10, "UnifrakturMaguntia")
+            .with_alternate_font(42, "Ignored");
+
+        assert_eq!(config.font_family_for_slot(0), "Mono");
+        assert_eq!(config.font_family_for_slot(1), "Serif");
+        assert_eq!(config.font_family_for_slot(2), "Mono"); // unset falls back
+        assert_eq!(config.font_family_for_slot(10), "UnifrakturMaguntia");
+        assert_eq!(config.font_family_for_slot(11), "Mono");
+    }
+
+    #[test]
+    fn test_zoom_layers_over_font_size() {
+        let mut config = TerminalConfig::default().with_font_size(12.0);
+        assert!(config.zoom_by(-1.0));
+        assert!((config.zoomed_font_size() - 12.0 * ZOOM_STEP).abs() < 1e-9);
+        // Half a notch of smooth scrolling, then back out
+        config.zoom_by(-0.5);
+        config.zoom_by(1.5);
+        assert!((config.zoom - 1.0).abs() < 1e-9);
+
+        // A new font size keeps the zoom
+        config.set_zoom(2.0);
+        config = config.with_font_size(10.0);
+        assert_eq!(config.zoomed_font_size(), 20.0);
+
+        assert!(config.set_zoom(100.0));
+        assert_eq!(config.zoom, MAX_ZOOM);
+        assert!(!config.zoom_by(-1.0));
+    }
 }
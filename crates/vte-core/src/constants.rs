@@ -6,19 +6,93 @@ use crate::ansi::Color;
 // pub const DEFAULT_FONT_FAMILY: &str = "Monospace";
 
 pub const DEFAULT_FONT_SIZE: f64 = 13.0;
+
+// Font zoom (Ctrl+wheel): factor range and change per wheel notch
+pub const MIN_ZOOM: f64 = 0.25;
+pub const MAX_ZOOM: f64 = 4.0;
+pub const ZOOM_STEP: f64 = 1.1;
 pub const DEFAULT_FONT_FAMILY: &str = "Monaco";
 
 pub const SCROLLBACK_LIMIT: usize = 1000;
+pub const WHEEL_SCROLL_LINES: f64 = 3.0; // Viewport lines per mouse wheel notch
+// Size the PTY is spawned with when the embedder does not choose one
+pub const DEFAULT_COLS: usize = 80;
+pub const DEFAULT_ROWS: usize = 24;
 pub const TAB_WIDTH: usize = 4;
+pub const DEFAULT_TERM: &str = "xterm-256color";
+pub const DEFAULT_COLORTERM: &str = "truecolor";
+pub const DEFAULT_LSCOLORS: &str = "ExGxFxdxCxDxDxBxBxExEx"; // BSD `ls` colors under `ColorPolicy::Color`
+// Locale given to a child whose inherited locale names no language (see `locale`)
+pub const FALLBACK_LOCALE: &str = "C.UTF-8";
+
+// Output parsed per slice before the reader lets the renderer at the grid (see `parse_budget`)
+pub const PARSE_SLICE_BYTES: usize = 16 * 1024;
+pub const PARSE_SLICE_US: u64 = 4000; // A quarter of a 60 Hz frame
+pub const FRAME_ACK_TIMEOUT_MS: u64 = 50; // Longest wait for the embedder to draw a slice
+
+// Alternate font slots selectable with SGR 11-19 (1-9) and SGR 20 (10, Fraktur)
+pub const ALTERNATE_FONT_SLOTS: usize = 10;
 
 // Security constants
 pub const MAX_OSC_LEN: usize = 2048;
 pub const MAX_PARAMS: usize = 32;
 pub const MAX_PARAM_VALUE: u16 = 9999;
+pub const MAX_SGR_STACK_DEPTH: usize = 10; // XTPUSHSGR saves, as in xterm
+pub const MAX_PENDING_CLIPBOARD_READS: usize = 4; // OSC 52 reads awaiting permission
+pub const MAX_ACTIVE_REGION_PAYLOAD: usize = 256; // OSC 7777 button payload, in bytes
+pub const MAX_ACTIVE_REGIONS: usize = 1024; // Distinct OSC 7777 payloads held at once (see `regions`)
+pub const MAX_PENDING_OSC_PROMPTS: usize = 8; // OSC sequences held for the user's permission
+pub const MAX_OSC_AUDIT_ENTRIES: usize = 64; // Denied OSC sequences kept for the audit log
+pub const MAX_QUEUED_OSC_EVENTS: usize = 64; // Notifications, and custom OSC sequences, awaiting the embedder
+pub const OSC_AUDIT_PREVIEW_LEN: usize = 80; // Characters of a denied sequence kept in the log
+pub const MAX_TITLE_LEN: usize = 256; // Characters of an application title kept
+pub const MAX_HYPERLINKS: usize = 4096; // Distinct OSC 8 links held at once (see `hyperlinks`)
+pub const MAX_COMBINING_SEQUENCES: usize = 4096; // Distinct runs of combining marks held at once
+pub const MAX_COMBINING_MARKS: usize = 16; // Combining marks kept per cell; further ones are dropped
 
 // Timing constants
 pub const CURSOR_BLINK_INTERVAL_MS: u64 = 500;
 pub const CLICK_TIMEOUT_MS: u128 = 200;
+pub const DRAG_THRESHOLD_PX: f64 = 4.0; // Pointer travel before a press becomes a selection drag
+pub const HOVER_DELAY_MS: u64 = 400;
+pub const REMOTE_CURSOR_TIMEOUT_MS: u64 = 30_000;
+pub const VISUAL_BELL_DURATION_MS: u64 = 150;
+pub const VISUAL_BELL_FRAME_MS: u64 = 16;
+pub const BELL_MIN_INTERVAL_MS: u64 = 500; // Bells closer together are dropped (see `BellPolicy`)
+pub const RESIZE_DEBOUNCE_MS: u64 = 50;
+pub const SNAPSHOT_CAPACITY: usize = 30; // Time-travel screen snapshots kept
+
+// Paste streaming to the PTY
+pub const PASTE_CHUNK_SIZE: usize = 4096;
+pub const PASTE_STREAM_THRESHOLD: usize = 64 * 1024; // Larger pastes are written in chunks on a background thread
+
+// Function keys programmed with DECUDK, all definitions together
+pub const MAX_USER_KEY_BYTES: usize = 1024;
+
+// Key events kept by the input trace (see `input_trace`)
+pub const MAX_INPUT_TRACE_ENTRIES: usize = 1000;
+
+// Sixel images (see `sixel`): largest decoded size, pixels painted before
+// decoding gives up, color registers, and the cell size images are placed
+// on until the backend reports its own
+pub const SIXEL_MAX_WIDTH: usize = 2048;
+pub const SIXEL_MAX_HEIGHT: usize = 2048;
+pub const SIXEL_MAX_PAINTED: usize = 4 * SIXEL_MAX_WIDTH * SIXEL_MAX_HEIGHT;
+pub const SIXEL_COLOR_REGISTERS: usize = 256;
+pub const DEFAULT_CELL_PIXEL_WIDTH: usize = 10;
+pub const DEFAULT_CELL_PIXEL_HEIGHT: usize = 16;
+pub const MAX_IMAGE_LAYER_BYTES: usize = 64 * 1024 * 1024; // Topmost placed images are dropped past this
+
+// How often the silence threshold is checked (see `activity`)
+pub const SILENCE_POLL_INTERVAL_MS: u64 = 250;
+
+// Selection highlight corner radius in pixels
+pub const DEFAULT_SELECTION_CORNER_RADIUS: f64 = 3.0;
+
+// Extra cell spacing and outer padding in pixels
+pub const DEFAULT_LETTER_SPACING: f64 = 0.0;
+pub const DEFAULT_LINE_SPACING: f64 = 0.0;
+pub const DEFAULT_PADDING: f64 = 0.0;
 
 // Legacy compatibility constants
 pub const DEFAULT_BOLD_IS_BRIGHT: bool = true; // For backwards compatibility
@@ -26,7 +100,7 @@ pub const DEFAULT_BOLD_IS_BRIGHT: bool = true; // For backwards compatibility
 // Color constants - with transparency support
 pub const DEFAULT_FG: Color = Color { r: 1.0, g: 1.0, b: 1.0, a: 1.0 };
 pub const DEFAULT_BG: Color = Color { r: 0.0, g: 0.0, b: 0.0, a: 0.0 }; // Fully transparent by default
-pub const SELECTION_BG: Color = Color { r: 0.3, g: 0.5, b: 0.8, a: 0.7 }; // Semi-transparent selection
+pub const SELECTION_BG: Color = Color { r: 0.3, g: 0.5, b: 0.8, a: 0.4 }; // Its alpha tints collaborators' selections; see `TerminalConfig::selection_colors` for the local one
 pub const GRID_LINE_COLOR: Color = Color { r: 1.0, g: 0.0, b: 0.0, a: 1.0 };
 
 // 16-color ANSI palette
@@ -0,0 +1,155 @@
+// src/contrast.rs
+//! High-contrast rendering mode
+//!
+//! With `TerminalConfig::high_contrast` the theme colors and the 16-color
+//! palette are replaced by [`HIGH_CONTRAST_PALETTE`] on an opaque black
+//! background, every foreground keeps [`HIGH_CONTRAST_MIN_RATIO`] against its
+//! background, and the cursor and underlines are drawn thicker
+//! (`TerminalConfig::decoration_scale`).
+//!
+//! The same utilities derive the selection highlight from the theme
+//! ([`selection_colors`]), so it reads on light and dark backgrounds alike.
+
+use crate::ansi::Color;
+
+/// Minimum contrast ratio of text in high-contrast mode (WCAG AAA)
+pub const HIGH_CONTRAST_MIN_RATIO: f64 = 7.0;
+
+/// Default foreground in high-contrast mode
+pub const HIGH_CONTRAST_FG: Color = Color { r: 1.0, g: 1.0, b: 1.0, a: 1.0 };
+/// Default background in high-contrast mode (opaque)
+pub const HIGH_CONTRAST_BG: Color = Color { r: 0.0, g: 0.0, b: 0.0, a: 1.0 };
+
+/// ANSI colors 0-15 in high-contrast mode; 1-7 and 9-15 all reach 7:1 on black
+pub const HIGH_CONTRAST_PALETTE: [Color; 16] = [
+    Color { r: 0.0, g: 0.0, b: 0.0, a: 1.0 },   // Black
+    Color { r: 1.0, g: 0.45, b: 0.45, a: 1.0 }, // Red
+    Color { r: 0.3, g: 1.0, b: 0.3, a: 1.0 },   // Green
+    Color { r: 1.0, g: 1.0, b: 0.0, a: 1.0 },   // Yellow
+    Color { r: 0.55, g: 0.7, b: 1.0, a: 1.0 },  // Blue
+    Color { r: 1.0, g: 0.5, b: 1.0, a: 1.0 },   // Magenta
+    Color { r: 0.0, g: 1.0, b: 1.0, a: 1.0 },   // Cyan
+    Color { r: 0.9, g: 0.9, b: 0.9, a: 1.0 },   // White
+    Color { r: 0.5, g: 0.5, b: 0.5, a: 1.0 },   // Bright black
+    Color { r: 1.0, g: 0.6, b: 0.6, a: 1.0 },   // Bright red
+    Color { r: 0.6, g: 1.0, b: 0.6, a: 1.0 },   // Bright green
+    Color { r: 1.0, g: 1.0, b: 0.6, a: 1.0 },   // Bright yellow
+    Color { r: 0.7, g: 0.8, b: 1.0, a: 1.0 },   // Bright blue
+    Color { r: 1.0, g: 0.7, b: 1.0, a: 1.0 },   // Bright magenta
+    Color { r: 0.6, g: 1.0, b: 1.0, a: 1.0 },   // Bright cyan
+    Color { r: 1.0, g: 1.0, b: 1.0, a: 1.0 },   // Bright white
+];
+
+/// Minimum contrast ratio of selected text against the selection (WCAG AA)
+pub const MIN_SELECTION_CONTRAST: f64 = 4.5;
+
+/// How far a derived selection background moves from the theme background
+/// toward its foreground
+const SELECTION_BLEND: f64 = 0.35;
+
+const BLACK: Color = Color { r: 0.0, g: 0.0, b: 0.0, a: 1.0 };
+const WHITE: Color = Color { r: 1.0, g: 1.0, b: 1.0, a: 1.0 };
+
+/// `color`, or black/white (whichever reads better) if its contrast against
+/// `against` is below `min_ratio`
+pub fn ensure_contrast(color: Color, against: Color, min_ratio: f64) -> Color {
+    if color.contrast_ratio(&against) >= min_ratio {
+        color
+    } else if BLACK.contrast_ratio(&against) >= WHITE.contrast_ratio(&against) {
+        BLACK
+    } else {
+        WHITE
+    }
+}
+
+/// Selection background and text color derived from a theme's `fg` and `bg`
+///
+/// The background is `bg` blended toward `fg`, which lightens dark themes
+/// and darkens light ones; the text is `fg` unless that falls below
+/// [`MIN_SELECTION_CONTRAST`] on it. Both are opaque.
+pub fn selection_colors(fg: Color, bg: Color) -> (Color, Color) {
+    let (fg, bg) = (fg.opaque(), bg.opaque());
+    let mix = |from: f64, to: f64| from + (to - from) * SELECTION_BLEND;
+    let selection_bg = Color { r: mix(bg.r, fg.r), g: mix(bg.g, fg.g), b: mix(bg.b, fg.b), a: 1.0 };
+    (selection_bg, ensure_contrast(fg, selection_bg, MIN_SELECTION_CONTRAST))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::{AnsiParser, Cell};
+    use crate::config::TerminalConfig;
+    use crate::grid::Grid;
+    use std::sync::Arc;
+
+    fn cells(input: &str, config: &TerminalConfig) -> Vec<Cell> {
+        let mut grid = Grid::new(8, 1, Arc::new(config.clone()));
+        AnsiParser::new().feed_str(input, &mut grid);
+        (0..8).map(|col| *grid.get_cell(0, col)).collect()
+    }
+
+    #[test]
+    fn test_palette_reaches_aaa_on_background() {
+        for (index, color) in HIGH_CONTRAST_PALETTE.iter().enumerate().filter(|&(i, _)| i != 0 && i != 8) {
+            let ratio = color.contrast_ratio(&HIGH_CONTRAST_BG);
+            assert!(ratio >= HIGH_CONTRAST_MIN_RATIO, "palette {} only reaches {:.2}", index, ratio);
+        }
+    }
+
+    #[test]
+    fn test_high_contrast_overrides_theme_and_palette() {
+        let config = TerminalConfig::default().with_high_contrast(true);
+        let row = cells("a\x1b[34mb\x1b[44mc", &config);
+
+        // Default colors come from the high-contrast theme, background opaque
+        assert_eq!(config.resolve_fg(&row[0]), HIGH_CONTRAST_FG);
+        assert_eq!(config.resolve_bg(&row[0]), HIGH_CONTRAST_BG);
+        // Palette blue is replaced by the readable high-contrast blue
+        assert_eq!(config.resolve_fg(&row[1]), HIGH_CONTRAST_PALETTE[4]);
+        // Blue text on a blue background falls back to black or white
+        let fg = config.resolve_fg(&row[2]);
+        assert!(fg.contrast_ratio(&config.resolve_bg(&row[2])) >= HIGH_CONTRAST_MIN_RATIO);
+    }
+
+    #[test]
+    fn test_truecolor_is_made_readable_and_opaque() {
+        let config = TerminalConfig::default().with_high_contrast(true);
+        let row = cells("\x1b[38;2;40;40;40m\x1b[48;2;0;0;0;mx", &config);
+        assert_eq!(config.resolve_fg(&row[0]), WHITE);
+        assert_eq!(config.resolve_bg(&row[0]).a, 1.0);
+    }
+
+    #[test]
+    fn test_decoration_scale() {
+        assert_eq!(TerminalConfig::default().decoration_scale(), 1.0);
+        assert_eq!(TerminalConfig::default().with_high_contrast(true).decoration_scale(), 2.0);
+    }
+
+    #[test]
+    fn test_selection_colors_follow_theme() {
+        let dark = Color { r: 0.1, g: 0.1, b: 0.1, a: 0.8 };
+        let light = Color { r: 0.98, g: 0.98, b: 0.95, a: 1.0 };
+        for (fg, bg) in [(light, dark), (dark, light), (light, light)] {
+            let (selection_bg, selection_fg) = selection_colors(fg, bg);
+            assert_eq!(selection_bg.a, 1.0);
+            assert!(selection_bg.contrast_ratio(&bg) > 1.5 || fg == bg);
+            assert!(selection_fg.contrast_ratio(&selection_bg) >= MIN_SELECTION_CONTRAST);
+        }
+        // Dark themes get a lighter highlight, light themes a darker one
+        assert!(selection_colors(light, dark).0.relative_luminance() > dark.relative_luminance());
+        assert!(selection_colors(dark, light).0.relative_luminance() < light.relative_luminance());
+
+        let config = TerminalConfig::default().with_selection_colors(Some(WHITE), None);
+        assert_eq!(config.selection_colors(), (WHITE, BLACK));
+        let config = TerminalConfig::default().with_high_contrast(true);
+        assert_eq!(config.selection_colors(), (HIGH_CONTRAST_FG, HIGH_CONTRAST_BG));
+    }
+
+    #[test]
+    fn test_normal_mode_unchanged() {
+        let config = TerminalConfig::default();
+        let row = cells("\x1b[34mb", &config);
+        assert_eq!(config.resolve_fg(&row[0]), row[0].fg);
+        assert_eq!(config.resolve_bg(&row[0]), config.default_bg);
+    }
+}
@@ -0,0 +1,232 @@
+// src/cursor.rs
+//! Text cursor rendering contract
+//!
+//! Backends draw the cursor over the already rendered cell using
+//! [`CursorPaint`]:
+//!
+//! - **Block**: fill the cell with `bg`, then re-render the cell's glyph
+//!   (and underline) in `fg`. The glyph is never drawn in the cell's own
+//!   background, which is invisible when the background is transparent.
+//! - **Bar / Underline**: fill [`CursorPaint::rect`] with `bg`; the glyph is
+//!   left as drawn.
+//!
+//! Colors come from the theme (`TerminalConfig::cursor_color` and
+//! `cursor_text_color`) or, by default, reverse video of the cell. Either way
+//! the cursor keeps [`MIN_CURSOR_CONTRAST`] against the cell background and
+//! the re-rendered glyph keeps it against the cursor. High-contrast mode
+//! raises the minimum and doubles the bar/underline thickness.
+//!
+//! Embedders placing popovers at the cursor (autocomplete, an emoji picker,
+//! suggestion overlays) use [`CursorPosition`] from
+//! `Grid::cursor_screen_position`, and `TerminalEvent::CursorMoved` to follow
+//! it as output, scrolling, resizing and zooming move it.
+
+use crate::ansi::{Cell, Color};
+use crate::config::TerminalConfig;
+use crate::contrast::{ensure_contrast, HIGH_CONTRAST_MIN_RATIO};
+use crate::traits::CursorShape;
+
+/// Minimum WCAG contrast ratio kept by cursor colors
+pub const MIN_CURSOR_CONTRAST: f64 = 3.0;
+
+/// Thickness of bar and underline cursors as a fraction of the cell size
+const THIN_CURSOR_FRACTION: f64 = 0.12;
+
+/// How to draw the cursor on one cell
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CursorPaint {
+    pub shape: CursorShape,
+    /// Fill color of the cursor (opaque)
+    pub bg: Color,
+    /// Glyph color under a block cursor (opaque)
+    pub fg: Color,
+    /// Bar and underline thickness as a fraction of the cell size
+    pub thickness: f64,
+}
+
+impl CursorPaint {
+    /// Cursor colors for `cell` under `config`
+    pub fn for_cell(cell: &Cell, shape: CursorShape, config: &TerminalConfig) -> Self {
+        let cell_fg = config.resolve_fg(cell).opaque();
+        let cell_bg = config.resolve_bg(cell).opaque();
+
+        let min_ratio = if config.high_contrast { HIGH_CONTRAST_MIN_RATIO } else { MIN_CURSOR_CONTRAST };
+        let bg = ensure_contrast(config.cursor_color.map_or(cell_fg, |c| c.opaque()), cell_bg, min_ratio);
+        let fg = ensure_contrast(config.cursor_text_color.map_or(cell_bg, |c| c.opaque()), bg, min_ratio);
+        let thickness = THIN_CURSOR_FRACTION * config.decoration_scale();
+        Self { shape, bg, fg, thickness }
+    }
+
+    /// Whether the glyph is re-rendered in `fg` on top of the cursor
+    pub fn repaints_glyph(&self) -> bool {
+        self.shape == CursorShape::Block
+    }
+
+    /// Area to fill with `bg` as `(x, y, width, height)` relative to the cell origin
+    pub fn rect(&self, cell_width: f64, cell_height: f64) -> (f64, f64, f64, f64) {
+        match self.shape {
+            CursorShape::Block => (0.0, 0.0, cell_width, cell_height),
+            CursorShape::Bar => (0.0, 0.0, (cell_width * self.thickness).max(1.0), cell_height),
+            CursorShape::Underline => {
+                let thickness = (cell_height * self.thickness).max(1.0);
+                (0.0, cell_height - thickness, cell_width, thickness)
+            }
+        }
+    }
+}
+
+/// Where the cursor is in the viewport
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CursorPosition {
+    /// Viewport row, counting a scrolled-back viewport's offset
+    pub row: usize,
+    pub col: usize,
+    /// The cursor cell as `(x, y, width, height)` in logical pixels from the
+    /// widget origin; two cells wide on a double-width character
+    pub rect: (f64, f64, f64, f64),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::{AnsiGrid, AnsiParser};
+
+    const BLACK: Color = Color { r: 0.0, g: 0.0, b: 0.0, a: 1.0 };
+    const WHITE: Color = Color { r: 1.0, g: 1.0, b: 1.0, a: 1.0 };
+    use crate::drawing::CellGeometry;
+    use crate::grid::Grid;
+    use std::sync::Arc;
+
+    /// Cell at (0, 0) after feeding `input`
+    fn cell(input: &str, config: &TerminalConfig) -> Cell {
+        let mut grid = Grid::new(4, 1, Arc::new(config.clone()));
+        AnsiParser::new().feed_str(input, &mut grid);
+        *grid.get_cell(0, 0)
+    }
+
+    #[test]
+    fn test_contrast_ratio() {
+        assert!((BLACK.contrast_ratio(&WHITE) - 21.0).abs() < 0.01);
+        assert!((WHITE.contrast_ratio(&WHITE) - 1.0).abs() < 0.01);
+        assert_eq!(Color::rgba(0.2, 0.4, 0.6, 0.0).opaque(), Color::rgb(0.2, 0.4, 0.6));
+    }
+
+    #[test]
+    fn test_reverse_video_on_transparent_background() {
+        let config = TerminalConfig::default();
+        assert_eq!(config.default_bg.a, 0.0);
+        let paint = CursorPaint::for_cell(&cell("a", &config), CursorShape::Block, &config);
+        // White cursor, glyph in the (now opaque) black background instead of transparent
+        assert_eq!(paint.bg, WHITE);
+        assert_eq!(paint.fg, BLACK);
+        assert!(paint.repaints_glyph());
+    }
+
+    #[test]
+    fn test_colored_glyph_uses_its_color_as_cursor() {
+        let config = TerminalConfig::default().with_background_color(Color::rgb(0.1, 0.1, 0.1));
+        let orange = cell("\x1b[38;2;255;128;0ma", &config);
+        let paint = CursorPaint::for_cell(&orange, CursorShape::Block, &config);
+        assert_eq!(paint.bg, config.resolve_fg(&orange));
+        assert!(paint.fg.contrast_ratio(&paint.bg) >= MIN_CURSOR_CONTRAST);
+    }
+
+    #[test]
+    fn test_minimum_contrast_overrides_low_contrast_colors() {
+        // Text drawn in its own background color (concealed / fg == bg)
+        let gray = Color::rgb(0.2, 0.2, 0.2);
+        let config = TerminalConfig::default().with_colors(gray, gray);
+        let paint = CursorPaint::for_cell(&cell("a", &config), CursorShape::Block, &config);
+        assert_eq!(paint.bg, WHITE);
+        assert_eq!(paint.fg, gray);
+
+        // A theme cursor color too close to the background
+        let config = TerminalConfig::default()
+            .with_background_color(Color::rgb(1.0, 1.0, 1.0))
+            .with_cursor_color(Color::rgb(0.95, 0.95, 0.9));
+        let paint = CursorPaint::for_cell(&cell("a", &config), CursorShape::Bar, &config);
+        assert_eq!(paint.bg, BLACK);
+    }
+
+    #[test]
+    fn test_theme_cursor_colors() {
+        let cursor = Color::rgb(1.0, 0.6, 0.0);
+        let text = Color::rgb(0.0, 0.0, 0.3);
+        let config = TerminalConfig::default()
+            .with_background_color(Color::rgb(0.0, 0.0, 0.0))
+            .with_cursor_color(cursor)
+            .with_cursor_text_color(text);
+        let paint = CursorPaint::for_cell(&cell("a", &config), CursorShape::Block, &config);
+        assert_eq!((paint.bg, paint.fg), (cursor, text));
+    }
+
+    #[test]
+    fn test_thin_shapes_do_not_repaint_glyph() {
+        let config = TerminalConfig::default();
+        let mut grid = Grid::new(4, 1, Arc::new(config.clone()));
+        grid.put('a');
+        let bar = CursorPaint::for_cell(grid.get_cell(0, 0), CursorShape::Bar, &config);
+        assert!(!bar.repaints_glyph());
+        assert_eq!(bar.rect(10.0, 20.0), (0.0, 0.0, 1.2, 20.0));
+
+        let underline = CursorPaint { shape: CursorShape::Underline, ..bar };
+        assert!(!underline.repaints_glyph());
+        let (x, y, w, h) = underline.rect(10.0, 20.0);
+        assert_eq!((x, w), (0.0, 10.0));
+        assert!((y - 17.6).abs() < 1e-9 && (h - 2.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_high_contrast_cursor_is_thicker() {
+        let config = TerminalConfig::default().with_high_contrast(true);
+        let paint = CursorPaint::for_cell(&cell("a", &config), CursorShape::Bar, &config);
+        assert_eq!(paint.rect(10.0, 20.0).2, 2.4);
+        assert!(paint.bg.contrast_ratio(&config.background()) >= HIGH_CONTRAST_MIN_RATIO);
+    }
+
+    #[test]
+    fn test_screen_position_in_pixels() {
+        let mut grid = Grid::new(10, 3, Arc::new(TerminalConfig::default()));
+        let geometry = CellGeometry::new(8.0, 16.0, 12.0, 1.0).with_padding(4.0);
+        let mut parser = AnsiParser::new();
+        parser.feed_str("ab", &mut grid);
+        let expected = CursorPosition { row: 0, col: 2, rect: (20.0, 4.0, 8.0, 16.0) };
+        assert_eq!(grid.cursor_screen_position(&geometry), Some(expected));
+
+        // Two cells wide on a double-width character
+        parser.feed_str("\r\n漢\x1b[2;1H", &mut grid);
+        assert_eq!(grid.cursor_screen_position(&geometry).map(|p| p.rect), Some((4.0, 20.0, 16.0, 16.0)));
+
+        // The viewport shows the cursor one row lower, then not at all
+        parser.feed_str("\r\n\r\n\r\n", &mut grid);
+        assert_eq!(grid.cursor_screen_position(&geometry).map(|p| p.row), Some(2));
+        assert_eq!(grid.cursor_viewport_cell(), Some((2, 0)));
+        grid.scroll_offset = 1;
+        assert_eq!(grid.cursor_screen_position(&geometry), None);
+        assert_eq!(grid.cursor_viewport_cell(), None);
+    }
+
+    #[test]
+    fn test_cursor_moves_with_output_scrolling_and_resize() {
+        let mut grid = Grid::new(10, 3, Arc::new(TerminalConfig::default()));
+        let mut parser = AnsiParser::new();
+        assert_eq!(grid.take_cursor_move(), Some((0, 0)));
+        assert_eq!(grid.take_cursor_move(), None);
+
+        parser.feed_str("1\r\n2\r\n3\r\n4", &mut grid);
+        assert_eq!(grid.take_cursor_move(), Some((2, 1)));
+
+        // Scrolled back one row the cursor leaves the view, and comes back
+        grid.scroll_offset = 1;
+        assert_eq!(grid.take_cursor_move(), None);
+        grid.scroll_offset = 0;
+        assert_eq!(grid.take_cursor_move(), Some((2, 1)));
+
+        // A shorter window can't keep the cursor on its third row
+        grid.resize_with_rewrap(10, 2);
+        let moved = grid.take_cursor_move();
+        assert!(moved.is_some_and(|(row, _)| row < 2));
+        assert_eq!(moved, grid.cursor_viewport_cell());
+        assert_eq!(grid.take_cursor_move(), None);
+    }
+}
@@ -0,0 +1,192 @@
+// src/damage.rs
+//! Rate-limited damage reports for thumbnails and previews
+//!
+//! With `TerminalConfig::damage_event_interval_ms` set, the core registers a
+//! change hook (see `changes`) that collects the screen rows each commit
+//! changed and emits `TerminalEvent::Damage` at most once per interval,
+//! straight from the commit: the first change after a quiet interval is
+//! reported at once, and changes within the interval are merged and reported
+//! by the first commit after it ends. Changes left over when output stops
+//! are reported at the end of the interval by a flusher thread, which sleeps
+//! on a condition variable until such a report is pending.
+
+use crate::changes::GridChange;
+use crate::events::{EventEmitter, TerminalEvent};
+use std::collections::BTreeSet;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Rows changed since the last report, and when that was
+#[derive(Debug, Default)]
+struct DamageState {
+    rows: BTreeSet<usize>,
+    generation: u64,
+    reported_at: Option<Instant>,
+    /// The reporter was dropped; the flusher stops
+    closed: bool,
+}
+
+/// State shared with the flusher, woken when rows start waiting
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<DamageState>,
+    pending: Condvar,
+    events: EventEmitter,
+    interval: Duration,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, DamageState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// When the rows waiting may be reported, `now` if nothing was reported
+    /// yet; `None` if none are waiting
+    fn due(&self, state: &DamageState, now: Instant) -> Option<Instant> {
+        if state.rows.is_empty() {
+            return None;
+        }
+        Some(state.reported_at.map_or(now, |at| at + self.interval))
+    }
+
+    fn report(&self, state: &mut DamageState, now: Instant) {
+        let rows = std::mem::take(&mut state.rows).len();
+        state.reported_at = Some(now);
+        self.events.emit(TerminalEvent::Damage { rows, generation: state.generation });
+    }
+}
+
+/// Merges screen changes into `TerminalEvent::Damage` events
+#[derive(Debug)]
+pub struct DamageReporter {
+    shared: Arc<Shared>,
+}
+
+impl DamageReporter {
+    /// Reporter emitting on `events` at most once per `interval`
+    pub fn new(events: EventEmitter, interval: Duration) -> Self {
+        let shared = Arc::new(Shared { state: Mutex::default(), pending: Condvar::new(), events, interval });
+        let flusher = Arc::clone(&shared);
+        thread::spawn(move || flush(&flusher));
+        Self { shared }
+    }
+
+    /// Change hook for `Grid::add_change_hook`
+    pub fn hook(self) -> impl Fn(&GridChange) + Send + Sync + 'static {
+        move |change| {
+            if let GridChange::RowsChanged { rows, generation } = change {
+                self.note(rows, *generation);
+            }
+        }
+    }
+
+    /// Record `rows` changed in the commit stamped `generation`, reporting
+    /// them if the interval since the last report is over
+    pub fn note(&self, rows: &[usize], generation: u64) {
+        let mut state = self.shared.lock();
+        let waiting = !state.rows.is_empty();
+        state.rows.extend(rows.iter().copied());
+        state.generation = generation;
+        let now = Instant::now();
+        match self.shared.due(&state, now) {
+            Some(due) if due <= now => self.shared.report(&mut state, now),
+            Some(_) if !waiting => self.shared.pending.notify_one(),
+            _ => {}
+        }
+    }
+}
+
+impl Drop for DamageReporter {
+    fn drop(&mut self) {
+        self.shared.lock().closed = true;
+        self.shared.pending.notify_one();
+    }
+}
+
+/// Report rows left waiting at the end of their interval, until the
+/// reporter is dropped
+fn flush(shared: &Shared) {
+    let mut state = shared.lock();
+    while !state.closed {
+        let now = Instant::now();
+        state = match shared.due(&state, now) {
+            None => shared.pending.wait(state).unwrap_or_else(|e| e.into_inner()),
+            Some(due) => {
+                if due <= now {
+                    shared.report(&mut state, now);
+                    continue;
+                }
+                shared.pending.wait_timeout(state, due - now).unwrap_or_else(|e| e.into_inner()).0
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::AnsiParser;
+    use crate::config::TerminalConfig;
+    use crate::grid::Grid;
+
+    fn next_event(events: &async_channel::Receiver<TerminalEvent>, within: Duration) -> Option<TerminalEvent> {
+        let deadline = Instant::now() + within;
+        loop {
+            if let Ok(event) = events.try_recv() {
+                return Some(event);
+            }
+            if Instant::now() >= deadline {
+                return None;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_changes_within_an_interval_are_merged() {
+        let emitter = EventEmitter::new();
+        let events = emitter.subscribe();
+        let reporter = DamageReporter::new(emitter, Duration::from_millis(50));
+
+        reporter.note(&[0], 1);
+        assert_eq!(events.try_recv().ok(), Some(TerminalEvent::Damage { rows: 1, generation: 1 }));
+
+        // Reported together once the interval ends
+        reporter.note(&[1, 2], 2);
+        reporter.note(&[2, 3], 3);
+        assert!(events.try_recv().is_err());
+        let merged = next_event(&events, Duration::from_secs(2));
+        assert_eq!(merged, Some(TerminalEvent::Damage { rows: 3, generation: 3 }));
+        assert_eq!(next_event(&events, Duration::from_millis(100)), None);
+    }
+
+    #[test]
+    fn test_dropping_the_reporter_stops_the_flusher() {
+        let emitter = EventEmitter::new();
+        let events = emitter.subscribe();
+        let reporter = DamageReporter::new(emitter, Duration::from_millis(20));
+        reporter.note(&[0], 1);
+        assert!(events.try_recv().is_ok());
+
+        reporter.note(&[1], 2);
+        drop(reporter);
+        assert_eq!(next_event(&events, Duration::from_millis(100)), None);
+    }
+
+    #[test]
+    fn test_grid_commits_drive_reports() {
+        let emitter = EventEmitter::new();
+        let events = emitter.subscribe();
+        let mut grid = Grid::new(10, 3, Arc::new(TerminalConfig::default()));
+        grid.add_change_hook(DamageReporter::new(emitter, Duration::from_secs(60)).hook());
+
+        // The first commit covers the whole screen
+        AnsiParser::new().feed_str("a\r\nb", &mut grid);
+        let generation = grid.commit_generation();
+        assert_eq!(events.try_recv().ok(), Some(TerminalEvent::Damage { rows: 3, generation }));
+        // Nothing changed, nothing reported
+        grid.commit_generation();
+        assert!(events.try_recv().is_err());
+    }
+}
@@ -0,0 +1,183 @@
+// src/diff.rs
+//! Incremental screen updates by generation
+//!
+//! The grid commits its visible screen once per mutation batch (one PTY
+//! chunk) with `Grid::commit_generation`. Rows that changed since the last
+//! commit are stamped with a new, strictly increasing generation, so a
+//! consumer that remembers the generation it last saw - a thin client at the
+//! other end of a socket, or a web renderer - asks `Grid::diff_since` for
+//! just the rows it is missing. Generation 0 is never stamped: asking for
+//! `diff_since(0)` returns the whole screen.
+
+use crate::ansi::Cell;
+use crate::screen::ScreenBuffer;
+
+/// New contents of one screen row
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowDelta {
+    /// Screen row, 0 at the top
+    pub row: usize,
+    /// All cells of the row, left to right
+    pub cells: Vec<Cell>,
+}
+
+/// Last committed screen and the generation each of its rows changed in
+#[derive(Debug, Clone, Default)]
+pub struct GenerationTracker {
+    generation: u64,
+    /// Shares its rows with the screen until the screen writes to them
+    committed: Option<ScreenBuffer>,
+    row_generations: Vec<u64>,
+}
+
+impl GenerationTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Generation of the last commit that changed anything
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Record `screen` as the current screen
+    ///
+    /// Bumps the generation if any row differs from the previous commit; a
+    /// change of screen size counts as every row changing. Rows the screen
+    /// has not written to since are skipped without comparing cells.
+    /// Returns the current generation.
+    pub fn commit(&mut self, screen: &ScreenBuffer) -> u64 {
+        let next = self.generation + 1;
+
+        let committed = match &mut self.committed {
+            Some(committed) if committed.cols() == screen.cols() && committed.row_count() == screen.row_count() => committed,
+            _ => {
+                self.committed = Some(screen.clone());
+                self.row_generations = vec![next; screen.row_count()];
+                self.generation = next;
+                return next;
+            }
+        };
+
+        let mut changed = false;
+        for (row, row_generation) in self.row_generations.iter_mut().enumerate() {
+            if committed.shares_row(screen, row) {
+                continue;
+            }
+            if committed.row(row) != screen.row(row) {
+                *row_generation = next;
+                changed = true;
+            }
+            committed.share_row(screen, row);
+        }
+
+        if changed {
+            self.generation = next;
+        }
+        self.generation
+    }
+
+    /// Screen rows changed after `generation`, top to bottom
+    pub fn rows_changed_since(&self, generation: u64) -> Vec<usize> {
+        self.row_generations
+            .iter()
+            .enumerate()
+            .filter(|&(_, &row_generation)| row_generation > generation)
+            .map(|(row, _)| row)
+            .collect()
+    }
+
+    /// Rows changed after `generation`, as committed, in top-to-bottom order
+    pub fn diff_since(&self, generation: u64) -> Vec<RowDelta> {
+        self.row_generations
+            .iter()
+            .enumerate()
+            .filter(|&(_, &row_generation)| row_generation > generation)
+            .filter_map(|(row, _)| {
+                let cells = self.committed.as_ref()?.row(row).to_vec();
+                Some(RowDelta { row, cells })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::AnsiParser;
+    use crate::config::TerminalConfig;
+    use crate::grid::Grid;
+    use std::sync::Arc;
+
+    fn row_text(delta: &RowDelta) -> String {
+        delta.cells.iter().map(|cell| cell.ch).filter(|&ch| ch != '\0').collect()
+    }
+
+    fn rows(deltas: &[RowDelta]) -> Vec<usize> {
+        deltas.iter().map(|delta| delta.row).collect()
+    }
+
+    #[test]
+    fn test_first_commit_covers_whole_screen() {
+        let mut grid = Grid::new(4, 3, Arc::new(TerminalConfig::default()));
+        assert_eq!(grid.generation(), 0);
+        assert_eq!(grid.commit_generation(), 1);
+        assert_eq!(rows(&grid.diff_since(0)), vec![0, 1, 2]);
+        assert!(grid.diff_since(1).is_empty());
+    }
+
+    #[test]
+    fn test_only_changed_rows_are_reported() {
+        let mut grid = Grid::new(4, 3, Arc::new(TerminalConfig::default()));
+        let mut parser = AnsiParser::new();
+        let start = grid.commit_generation();
+
+        parser.feed_str("\x1b[2;1Hab", &mut grid);
+        let first = grid.commit_generation();
+        assert_eq!(first, start + 1);
+        let deltas = grid.diff_since(start);
+        assert_eq!(rows(&deltas), vec![1]);
+        assert_eq!(row_text(&deltas[0]), "ab");
+
+        parser.feed_str("\x1b[3;1Hc", &mut grid);
+        let second = grid.commit_generation();
+        assert_eq!(rows(&grid.diff_since(first)), vec![2]);
+        assert_eq!(rows(&grid.diff_since(start)), vec![1, 2]);
+
+        // A batch that changes nothing keeps the generation
+        parser.feed_str("\x1b[1;1H", &mut grid);
+        assert_eq!(grid.commit_generation(), second);
+        assert!(grid.diff_since(second).is_empty());
+    }
+
+    #[test]
+    fn test_style_change_is_a_change() {
+        let mut grid = Grid::new(4, 1, Arc::new(TerminalConfig::default()));
+        let mut parser = AnsiParser::new();
+        parser.feed_str("a", &mut grid);
+        let before = grid.commit_generation();
+
+        parser.feed_str("\r\x1b[1ma", &mut grid);
+        assert!(grid.commit_generation() > before);
+        assert!(grid.diff_since(before)[0].cells[0].bold);
+    }
+
+    #[test]
+    fn test_resize_and_screen_switch_resend_rows() {
+        let mut grid = Grid::new(4, 2, Arc::new(TerminalConfig::default()));
+        let before = grid.commit_generation();
+
+        grid.resize(6, 3);
+        grid.commit_generation();
+        let deltas = grid.diff_since(before);
+        assert_eq!(rows(&deltas), vec![0, 1, 2]);
+        assert_eq!(deltas[0].cells.len(), 6);
+
+        let mut parser = AnsiParser::new();
+        parser.feed_str("x", &mut grid);
+        let primary = grid.commit_generation();
+        parser.feed_str("\x1b[?1049h", &mut grid);
+        grid.commit_generation();
+        assert_eq!(rows(&grid.diff_since(primary)), vec![0]);
+    }
+}
@@ -25,6 +25,120 @@ pub struct CharMetrics {
     pub ascent: f64,
 }
 
+/// Cell size and baseline snapped to whole device pixels
+///
+/// Sizes are in logical pixels but always a whole number of device pixels at
+/// `scale`, so every cell boundary and baseline lands exactly on the device
+/// pixel grid: no blurry glyph placement, no seams between cell backgrounds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CellGeometry {
+    /// Cell width in logical pixels
+    pub width: f64,
+    /// Cell height in logical pixels
+    pub height: f64,
+    /// Baseline offset from the cell top in logical pixels
+    pub ascent: f64,
+    /// Device pixels per logical pixel
+    pub scale: f64,
+    /// Glyph offset from the cell's left edge (half the letter spacing)
+    pub glyph_offset: f64,
+    /// Space between the widget edge and the first row and column
+    pub padding: f64,
+    device_width: u32,
+    device_height: u32,
+}
+
+impl CellGeometry {
+    /// Snap fractional cell metrics to the device pixel grid at `scale`
+    pub fn new(width: f64, height: f64, ascent: f64, scale: f64) -> Self {
+        let scale = if scale.is_finite() && scale > 0.0 { scale } else { 1.0 };
+        let device_width = (width * scale).round().max(1.0) as u32;
+        let device_height = (height * scale).round().max(1.0) as u32;
+        let device_ascent = (ascent * scale).round().clamp(0.0, device_height as f64);
+        Self {
+            width: device_width as f64 / scale,
+            height: device_height as f64 / scale,
+            ascent: device_ascent / scale,
+            scale,
+            glyph_offset: 0.0,
+            padding: 0.0,
+            device_width,
+            device_height,
+        }
+    }
+
+    /// Widen cells by `letter` and heighten them by `line` logical pixels,
+    /// keeping glyphs centered in the extra space
+    pub fn with_spacing(self, letter: f64, line: f64) -> Self {
+        let (letter, line) = (letter.max(0.0), line.max(0.0));
+        let snap = |v: f64| (v * self.scale).round() / self.scale;
+        Self {
+            glyph_offset: snap(letter / 2.0),
+            padding: self.padding,
+            ..Self::new(self.width + letter, self.height + line, self.ascent + snap(line / 2.0), self.scale)
+        }
+    }
+
+    /// Leave `padding` logical pixels between the widget edges and the cells
+    pub fn with_padding(self, padding: f64) -> Self {
+        Self { padding: (padding.max(0.0) * self.scale).round() / self.scale, ..self }
+    }
+
+    /// Apply the letter/line spacing and padding of `config`
+    pub fn with_config_spacing(self, config: &crate::config::TerminalConfig) -> Self {
+        self.with_spacing(config.letter_spacing, config.line_spacing).with_padding(config.padding)
+    }
+
+    /// Cell width in device pixels
+    pub fn device_width(&self) -> u32 {
+        self.device_width
+    }
+
+    /// Cell height in device pixels
+    pub fn device_height(&self) -> u32 {
+        self.device_height
+    }
+
+    /// Logical x of the left edge of `col`
+    pub fn col_x(&self, col: usize) -> f64 {
+        self.padding + (col as f64 * self.device_width as f64) / self.scale
+    }
+
+    /// Logical y of the top edge of `row`
+    pub fn row_y(&self, row: usize) -> f64 {
+        self.padding + (row as f64 * self.device_height as f64) / self.scale
+    }
+
+    /// Logical x of the glyph origin in `col`
+    pub fn glyph_x(&self, col: usize) -> f64 {
+        self.col_x(col) + self.glyph_offset
+    }
+
+    /// Logical y of the baseline of `row`
+    pub fn baseline_y(&self, row: usize) -> f64 {
+        self.row_y(row) + self.ascent
+    }
+
+    /// Cell (row, col) containing the logical point (x, y); points in the
+    /// padding map to the nearest edge cell
+    pub fn cell_at(&self, x: f64, y: f64) -> (usize, usize) {
+        let (x, y) = ((x - self.padding).max(0.0), (y - self.padding).max(0.0));
+        ((y / self.height) as usize, (x / self.width) as usize)
+    }
+
+    /// Columns and rows that fit in a widget of `width` x `height` logical
+    /// pixels, at least one of each
+    pub fn grid_size(&self, width: f64, height: f64) -> (usize, usize) {
+        let fit = |extent: f64, cell: f64| ((extent - 2.0 * self.padding) / cell).floor().max(1.0) as usize;
+        (fit(width, self.width), fit(height, self.height))
+    }
+
+    /// Logical size of a widget showing exactly `cols` x `rows` cells
+    pub fn size_for(&self, cols: usize, rows: usize) -> (f64, f64) {
+        (self.col_x(cols) + self.padding, self.row_y(rows) + self.padding)
+    }
+}
+
 /// Backend-agnostic font cache using fontdue
 pub struct DrawingCache {
     /// Font family name
@@ -41,6 +155,12 @@ pub struct DrawingCache {
     char_height: f64,
     /// Font ascent (baseline offset)
     ascent: f64,
+    /// Font families for alternate font slots (SGR 11-20)
+    slot_families: HashMap<u8, String>,
+    /// Device pixels per logical pixel of the target widget
+    scale_factor: f64,
+    /// Cell metrics snapped to device pixels at `scale_factor`
+    geometry: CellGeometry,
 }
 
 impl DrawingCache {
@@ -85,6 +205,9 @@ impl DrawingCache {
             char_width: monospace_advance,
             char_height: line_height,
             ascent: baseline_offset,
+            slot_families: HashMap::new(),
+            scale_factor: 1.0,
+            geometry: CellGeometry::new(monospace_advance, line_height, baseline_offset, 1.0),
         })
     }
 
@@ -172,12 +295,55 @@ impl DrawingCache {
     pub fn font_family(&self) -> &str {
         &self.font_family
     }
+
+    /// Device pixels per logical pixel the metrics are snapped for
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    /// Update the scale factor (e.g. on a GTK `scale-factor` notification)
+    /// and re-snap the cell geometry; returns true if the geometry changed
+    pub fn set_scale_factor(&mut self, scale: f64) -> bool {
+        self.scale_factor = scale;
+        let geometry = CellGeometry::new(self.char_width, self.char_height, self.ascent, scale);
+        let changed = geometry != self.geometry;
+        self.geometry = geometry;
+        changed
+    }
+
+    /// Cell size and baseline snapped to device pixels, for laying out cells
+    pub fn cell_geometry(&self) -> CellGeometry {
+        self.geometry
+    }
+
+    /// Register the font family used for an alternate font slot
+    pub fn set_slot_family(&mut self, slot: u8, family: &str) {
+        self.slot_families.insert(slot, family.to_string());
+    }
+
+    /// Load all alternate font slots from the terminal configuration
+    pub fn set_slot_families_from_config(&mut self, config: &crate::config::TerminalConfig) {
+        self.slot_families.clear();
+        for (i, family) in config.alternate_fonts.iter().enumerate() {
+            if let Some(family) = family {
+                self.slot_families.insert(i as u8 + 1, family.clone());
+            }
+        }
+    }
+
+    /// Font family for a cell's font slot, falling back to the primary family
+    pub fn font_family_for_slot(&self, slot: u8) -> &str {
+        self.slot_families.get(&slot).map_or(&self.font_family, |f| f)
+    }
 }
 
 impl Clone for DrawingCache {
     fn clone(&self) -> Self {
-        Self::new(&self.font_family, self.font_size)
-            .expect("Failed to clone DrawingCache")
+        let mut cache = Self::new(&self.font_family, self.font_size)
+            .expect("Failed to clone DrawingCache");
+        cache.slot_families = self.slot_families.clone();
+        cache.set_scale_factor(self.scale_factor);
+        cache
     }
 }
 
@@ -185,6 +351,20 @@ impl Clone for DrawingCache {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_font_slot_families() {
+        let config = crate::config::TerminalConfig::default()
+            .with_font_family("monospace")
+            .with_alternate_font(2, "serif");
+        let mut cache = DrawingCache::new("monospace", 12.0).unwrap();
+        cache.set_slot_families_from_config(&config);
+
+        assert_eq!(cache.font_family_for_slot(0), "monospace");
+        assert_eq!(cache.font_family_for_slot(1), "monospace");
+        assert_eq!(cache.font_family_for_slot(2), "serif");
+        assert_eq!(cache.clone().font_family_for_slot(2), "serif");
+    }
+
     #[test]
     fn test_drawing_cache_creation() {
         let cache = DrawingCache::new("monospace", 12.0).unwrap();
@@ -457,4 +637,65 @@ mod tests {
         let combining = cache.get_char_metrics('\u{0301}'); // Combining acute accent
         assert!(combining.width >= 0.0, "Combining characters should be handled");
     }
+
+    #[test]
+    fn test_cell_geometry_snaps_to_device_pixels() {
+        // 13px font: 7.8 x 15.6 logical pixels
+        let geometry = CellGeometry::new(7.8, 15.6, 10.4, 1.0);
+        assert_eq!((geometry.width, geometry.height, geometry.ascent), (8.0, 16.0, 10.0));
+
+        // At 1.5x the cell is 12 x 23 device pixels
+        let geometry = CellGeometry::new(7.8, 15.6, 10.4, 1.5);
+        assert_eq!((geometry.device_width(), geometry.device_height()), (12, 23));
+        for col in 0..200 {
+            let device_x = geometry.col_x(col) * geometry.scale;
+            assert!((device_x - device_x.round()).abs() < 1e-9, "column {} off the pixel grid", col);
+            // Adjacent cells share an edge: no gaps between backgrounds
+            assert!((geometry.col_x(col) + geometry.width - geometry.col_x(col + 1)).abs() < 1e-9);
+        }
+        let baseline = geometry.baseline_y(7) * geometry.scale;
+        assert!((baseline - baseline.round()).abs() < 1e-9);
+        assert_eq!(geometry.cell_at(geometry.col_x(3) + 0.1, geometry.row_y(2) + 0.1), (2, 3));
+        assert_eq!(geometry.cell_at(-5.0, -5.0), (0, 0));
+    }
+
+    #[test]
+    fn test_spacing_and_padding() {
+        let geometry = CellGeometry::new(8.0, 16.0, 12.0, 1.0).with_spacing(2.0, 4.0).with_padding(5.0);
+        assert_eq!((geometry.width, geometry.height), (10.0, 20.0));
+        // Glyphs sit centered in the added space
+        assert_eq!((geometry.glyph_x(0), geometry.baseline_y(0)), (6.0, 19.0));
+        assert_eq!((geometry.col_x(2), geometry.row_y(1)), (25.0, 25.0));
+
+        // Hit-testing, sizing and rendering agree on the layout
+        assert_eq!(geometry.cell_at(geometry.col_x(3) + 0.1, geometry.row_y(2) + 0.1), (2, 3));
+        assert_eq!(geometry.cell_at(1.0, 1.0), (0, 0));
+        assert_eq!(geometry.grid_size(210.0, 110.0), (20, 5));
+        assert_eq!(geometry.size_for(20, 5), (210.0, 110.0));
+        assert_eq!(geometry.grid_size(3.0, 3.0), (1, 1));
+
+        let config = crate::config::TerminalConfig::default().with_cell_spacing(2.0, 4.0).with_padding(5.0);
+        assert_eq!(CellGeometry::new(8.0, 16.0, 12.0, 1.0).with_config_spacing(&config), geometry);
+    }
+
+    #[test]
+    fn test_invalid_scale_falls_back_to_one() {
+        assert_eq!(CellGeometry::new(8.0, 16.0, 12.0, 0.0).scale, 1.0);
+        assert_eq!(CellGeometry::new(8.0, 16.0, 12.0, f64::NAN).scale, 1.0);
+    }
+
+    #[test]
+    fn test_scale_factor_change_recomputes_geometry() {
+        // Expectations follow the cache's own metrics, whatever font it found
+        let mut cache = DrawingCache::new("monospace", 13.0).unwrap();
+        let (width, height, ascent) = (cache.char_width(), cache.char_height(), cache.ascent());
+        assert_eq!(cache.cell_geometry(), CellGeometry::new(width, height, ascent, 1.0));
+        assert!(cache.set_scale_factor(2.0));
+        assert_eq!(cache.cell_geometry(), CellGeometry::new(width, height, ascent, 2.0));
+        assert_eq!(cache.cell_geometry().device_width(), (width * 2.0).round() as u32);
+        assert!(!cache.set_scale_factor(2.0));
+        assert!(cache.set_scale_factor(1.25));
+        assert_eq!(cache.cell_geometry(), CellGeometry::new(width, height, ascent, 1.25));
+        assert_eq!(cache.clone().cell_geometry(), cache.cell_geometry());
+    }
 }
@@ -1,6 +1,6 @@
 //! Dummy backend for testing trait implementations without GTK
 
-use crate::{Renderer, TextRenderer, GraphicsRenderer, UIRenderer, InputHandler, EventLoop, CursorShape, ImageData, Grid, Cell};
+use crate::{Renderer, TextRenderer, GraphicsRenderer, UIRenderer, InputHandler, EventLoop, CursorShape, PointerShape, ImageData, Grid, Cell};
 use crate::drawing::CharMetrics;
 use std::io::Write;
 use std::sync::{Arc, RwLock, Mutex};
@@ -96,8 +96,8 @@ impl InputHandler for CompleteDummyBackend {
         self.input_handler.handle_mouse(event, grid);
     }
 
-    fn handle_scroll(&mut self, delta: f64, grid: &Arc<RwLock<Grid>>) {
-        self.input_handler.handle_scroll(delta, grid);
+    fn handle_scroll(&mut self, delta: f64, shift: bool, grid: &Arc<RwLock<Grid>>) {
+        self.input_handler.handle_scroll(delta, shift, grid);
     }
 }
 
@@ -160,14 +160,14 @@ impl TextRenderer for DummyTextRenderer {
 
 /// Dummy graphics renderer - records operations
 pub struct DummyGraphicsRenderer {
-    pub sixel_data: Vec<Vec<u8>>,
+    pub sixels: Vec<ImageData>,
     pub images: Vec<ImageData>,
 }
 
 impl Default for DummyGraphicsRenderer {
     fn default() -> Self {
         DummyGraphicsRenderer {
-            sixel_data: Vec::new(),
+            sixels: Vec::new(),
             images: Vec::new(),
         }
     }
@@ -176,14 +176,14 @@ impl Default for DummyGraphicsRenderer {
 impl DummyGraphicsRenderer {
     /// Clear recorded operations
     pub fn clear(&mut self) {
-        self.sixel_data.clear();
+        self.sixels.clear();
         self.images.clear();
     }
 }
 
 impl GraphicsRenderer for DummyGraphicsRenderer {
-    fn draw_sixel(&mut self, data: &[u8], _x: usize, _y: usize) {
-        self.sixel_data.push(data.to_vec());
+    fn draw_sixel(&mut self, image: &ImageData, _x: f64, _y: f64) {
+        self.sixels.push(image.clone());
     }
 
     fn draw_image(&mut self, image: ImageData, _x: usize, _y: usize) {
@@ -196,6 +196,7 @@ pub struct DummyUIRenderer {
     pub cleared: bool,
     pub flushed: bool,
     pub cursor_shape: Option<CursorShape>,
+    pub pointer_shape: Option<PointerShape>,
 }
 
 impl Default for DummyUIRenderer {
@@ -204,6 +205,7 @@ impl Default for DummyUIRenderer {
             cleared: false,
             flushed: false,
             cursor_shape: None,
+            pointer_shape: None,
         }
     }
 }
@@ -214,6 +216,7 @@ impl DummyUIRenderer {
         self.cleared = false;
         self.flushed = false;
         self.cursor_shape = None;
+        self.pointer_shape = None;
     }
 }
 
@@ -230,6 +233,10 @@ impl UIRenderer for DummyUIRenderer {
         self.cursor_shape = Some(shape);
     }
 
+    fn set_pointer_shape(&mut self, shape: PointerShape) {
+        self.pointer_shape = Some(shape);
+    }
+
     fn handle_hyperlink(&mut self, url: &str) -> bool {
         // Dummy backend just records that a hyperlink was handled
         // In a real implementation, this would open the URL in a browser
@@ -268,8 +275,11 @@ impl InputHandler for DummyInputHandler {
         self.mouse_events.push(event);
     }
 
-    fn handle_scroll(&mut self, delta: f64, _grid: &Arc<RwLock<Grid>>) {
+    fn handle_scroll(&mut self, delta: f64, shift: bool, grid: &Arc<RwLock<Grid>>) {
         self.scroll_events.push(delta);
+        if let Ok(mut grid) = grid.write() {
+            grid.scroll_wheel(delta, shift);
+        }
     }
 }
 
@@ -344,7 +354,7 @@ mod tests {
 
         let key_event = KeyEvent { keyval: 'a' as u32, state: 0 };
         handler.handle_key(key_event, &grid, &writer);
-        handler.handle_scroll(1.0, &grid);
+        handler.handle_scroll(1.0, false, &grid);
 
         assert_eq!(handler.key_events.len(), 1);
         assert_eq!(handler.scroll_events.len(), 1);
@@ -386,7 +396,61 @@ mod resource_management_tests {
             enable_selection: false,
             scrollback_limit: 1000,
             click_timeout_ms: 300,
+            drag_threshold_px: 4.0,
             bold_is_bright: true,
+            ambiguous_width: Default::default(),
+            emoji_width: Default::default(),
+            alternate_fonts: Default::default(),
+            hide_pointer_while_typing: true,
+            scroll_on_clear: true,
+            scroll_on_output: true,
+            freeze_on_select: false,
+            command_notify_threshold_ms: None,
+            damage_event_interval_ms: None,
+            silence_threshold_ms: None,
+            parser_health_interval_ms: None,
+            cursor_shape: CursorShape::Block,
+            cursor_color: None,
+            cursor_text_color: None,
+            journaled_parsing: false,
+            parse_slice_bytes: 16 * 1024,
+            parse_slice_us: 4000,
+            input_trace: false,
+            input_trace_file: None,
+            high_contrast: false,
+            copy_hyperlinks: Default::default(),
+            selection_corner_radius: 0.0,
+            selection_bg: None,
+            selection_fg: None,
+            inactive_style: Default::default(),
+            color_adjust: Default::default(),
+            hover_delay_ms: 0,
+            letter_spacing: 0.0,
+            line_spacing: 0.0,
+            padding: 0.0,
+            themes: None,
+            color_scheme: Default::default(),
+            local_echo: Default::default(),
+            visual_bell: Default::default(),
+            visual_bell_duration_ms: crate::constants::VISUAL_BELL_DURATION_MS,
+            bell_policy: Default::default(),
+            zoom: 1.0,
+            security: Default::default(),
+            remote_cursor_timeout_ms: 30_000,
+            resize_debounce_ms: crate::constants::RESIZE_DEBOUNCE_MS,
+            term: "xterm-256color".to_string(),
+            colorterm: Some("truecolor".to_string()),
+            color_env: Default::default(),
+            terminfo_dir: None,
+            locale: Default::default(),
+            encoding: None,
+            fallback_encoding: crate::encoding::Encoding::Cp1252,
+            link_rules: Vec::new(),
+            snapshot_interval_ms: None,
+            snapshot_capacity: crate::constants::SNAPSHOT_CAPACITY,
+            wheel_scroll_lines: crate::constants::WHEEL_SCROLL_LINES,
+            shift_wheel_scrolls_page: true,
+            natural_scrolling: false,
         };
 
         let terminal = VteTerminalCore::new().expect("Failed to create terminal for testing");
@@ -400,7 +464,8 @@ mod resource_management_tests {
         // Total should equal sum of all buffers
         let expected_total = memory_info.primary_buffer_bytes +
                            memory_info.alternate_buffer_bytes +
-                           memory_info.scrollback_buffer_bytes;
+                           memory_info.scrollback_buffer_bytes +
+                           memory_info.scrollback_index_bytes;
         assert_eq!(memory_info.total_grid_bytes, expected_total);
 
         // Primary and alternate buffers should have same size initially
@@ -421,7 +486,61 @@ mod resource_management_tests {
             enable_selection: false,
             scrollback_limit: 1000,
             click_timeout_ms: 300,
+            drag_threshold_px: 4.0,
             bold_is_bright: true,
+            ambiguous_width: Default::default(),
+            emoji_width: Default::default(),
+            alternate_fonts: Default::default(),
+            hide_pointer_while_typing: true,
+            scroll_on_clear: true,
+            scroll_on_output: true,
+            freeze_on_select: false,
+            command_notify_threshold_ms: None,
+            damage_event_interval_ms: None,
+            silence_threshold_ms: None,
+            parser_health_interval_ms: None,
+            cursor_shape: CursorShape::Block,
+            cursor_color: None,
+            cursor_text_color: None,
+            journaled_parsing: false,
+            parse_slice_bytes: 16 * 1024,
+            parse_slice_us: 4000,
+            input_trace: false,
+            input_trace_file: None,
+            high_contrast: false,
+            copy_hyperlinks: Default::default(),
+            selection_corner_radius: 0.0,
+            selection_bg: None,
+            selection_fg: None,
+            inactive_style: Default::default(),
+            color_adjust: Default::default(),
+            hover_delay_ms: 0,
+            letter_spacing: 0.0,
+            line_spacing: 0.0,
+            padding: 0.0,
+            themes: None,
+            color_scheme: Default::default(),
+            local_echo: Default::default(),
+            visual_bell: Default::default(),
+            visual_bell_duration_ms: crate::constants::VISUAL_BELL_DURATION_MS,
+            bell_policy: Default::default(),
+            zoom: 1.0,
+            security: Default::default(),
+            remote_cursor_timeout_ms: 30_000,
+            resize_debounce_ms: crate::constants::RESIZE_DEBOUNCE_MS,
+            term: "xterm-256color".to_string(),
+            colorterm: Some("truecolor".to_string()),
+            color_env: Default::default(),
+            terminfo_dir: None,
+            locale: Default::default(),
+            encoding: None,
+            fallback_encoding: crate::encoding::Encoding::Cp1252,
+            link_rules: Vec::new(),
+            snapshot_interval_ms: None,
+            snapshot_capacity: crate::constants::SNAPSHOT_CAPACITY,
+            wheel_scroll_lines: crate::constants::WHEEL_SCROLL_LINES,
+            shift_wheel_scrolls_page: true,
+            natural_scrolling: false,
         };
 
         let terminal = VteTerminalCore::new().expect("Failed to create terminal for cleanup testing");
@@ -454,12 +573,30 @@ mod resource_management_tests {
             primary_buffer_bytes: 1024,
             alternate_buffer_bytes: 1024,
             scrollback_buffer_bytes: 512,
-            total_grid_bytes: 2560,
+            scrollback_index_bytes: 64,
+            total_grid_bytes: 2624,
         };
 
         assert_eq!(memory_info.primary_buffer_bytes, 1024);
         assert_eq!(memory_info.alternate_buffer_bytes, 1024);
         assert_eq!(memory_info.scrollback_buffer_bytes, 512);
-        assert_eq!(memory_info.total_grid_bytes, 2560);
+        assert_eq!(memory_info.scrollback_index_bytes, 64);
+        assert_eq!(memory_info.total_grid_bytes, 2624);
+    }
+
+    #[test]
+    fn test_blank_screens_share_one_row() {
+        let row_bytes = 300 * std::mem::size_of::<Cell>();
+        let mut grid = Grid::new(300, 100, Arc::new(TerminalConfig::default()));
+        // Neither screen holds a row of its own until something is written
+        assert!(grid.alternate_cells.memory_bytes() < 2 * row_bytes);
+
+        crate::ansi::AnsiParser::new().feed_str("\x1b[?1049h\x1b[50;1Hvim", &mut grid);
+        assert_eq!(grid.alternate_cells.materialized_rows(), 1);
+        // Resizing copies only the row with content
+        grid.resize(400, 200);
+        crate::ansi::AnsiParser::new().feed_str("\x1b[?1049l", &mut grid);
+        assert_eq!(grid.cells.materialized_rows(), 0);
+        assert_eq!(grid.alternate_cells.materialized_rows(), 1);
     }
 }
@@ -0,0 +1,323 @@
+// src/encoding.rs
+//! Decoding PTY output into text
+//!
+//! Output is UTF-8 almost everywhere, but legacy systems (old Unix hosts,
+//! serial consoles, some mainframe gateways) still send Latin-1 or CP1252.
+//! Decoded as UTF-8, every accented letter of such output turns into U+FFFD.
+//! [`StreamDecoder`] decodes UTF-8, carrying sequences split across reads
+//! over to the next one, and watches for streams that keep failing
+//! validation: once invalid sequences clearly outnumber valid multi-byte
+//! ones, it switches to `TerminalConfig::fallback_encoding`, and back to
+//! UTF-8 when well-formed multi-byte UTF-8 shows up again. Applications can
+//! also announce the switch with the ISO 2022 coding system sequences
+//! `ESC % G` (UTF-8) and `ESC % @` (back to the 8-bit default, taken to be the
+//! fallback encoding), which take effect at the byte after them, also when
+//! a read ends in the middle of one. Setting an encoding explicitly
+//! (`TerminalConfig::encoding`, `VteTerminalCore::set_encoding`) turns
+//! detection and announcements off.
+//!
+//! Escape sequences are plain ASCII in all supported encodings, so the
+//! parser sees the same control stream whatever the choice. As UTF-8, each
+//! invalid sequence becomes one U+FFFD, so the parser only ever gets valid
+//! text: an OSC payload (a title, an OSC 7 path) cut off mid-character by
+//! its terminator carries a replacement character, not stray bytes.
+
+/// Character encoding of PTY output
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    /// ISO-8859-1: every byte is the code point of the same value
+    Latin1,
+    /// Windows-1252: Latin-1 with printable characters in 0x80-0x9F
+    Cp1252,
+}
+
+/// Invalid sequences needed before switching to the fallback encoding, or
+/// valid multi-byte sequences before switching back
+const SWITCH_THRESHOLD: u32 = 4;
+/// Invalid sequences must outnumber valid multi-byte ones this many times
+const INVALID_RATIO: u32 = 4;
+/// Evidence older than this many bytes is discarded
+const DETECTION_WINDOW: usize = 64 * 1024;
+
+/// CP1252 characters for 0x80-0x9F; unassigned bytes map to U+FFFD
+const CP1252_HIGH: [char; 32] = [
+    '€', '\u{FFFD}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{FFFD}', 'Ž', '\u{FFFD}',
+    '\u{FFFD}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{FFFD}', 'ž', 'Ÿ',
+];
+
+/// Incremental decoder with fallback detection
+#[derive(Clone, Debug)]
+pub struct StreamDecoder {
+    /// Explicit encoding; `None` detects
+    forced: Option<Encoding>,
+    fallback: Encoding,
+    active: Encoding,
+    /// Start of a UTF-8 sequence cut off at the end of the last read
+    pending: Vec<u8>,
+    /// How much of `ESC %` the last read ended with (0 to 2 bytes)
+    escape: u8,
+    invalid: u32,
+    valid_multibyte: u32,
+    window_bytes: usize,
+}
+
+impl StreamDecoder {
+    /// Decoder using `forced`, or UTF-8 falling back to `fallback` if `None`
+    pub fn new(forced: Option<Encoding>, fallback: Encoding) -> Self {
+        Self {
+            forced,
+            fallback,
+            active: forced.unwrap_or(Encoding::Utf8),
+            pending: Vec::new(),
+            escape: 0,
+            invalid: 0,
+            valid_multibyte: 0,
+            window_bytes: 0,
+        }
+    }
+
+    /// Encoding the next bytes are decoded with
+    pub fn encoding(&self) -> Encoding {
+        self.active
+    }
+
+    /// Use `encoding` from now on, or detect again with `None`
+    pub fn set_encoding(&mut self, encoding: Option<Encoding>) {
+        self.forced = encoding;
+        self.switch_to(encoding.unwrap_or(Encoding::Utf8));
+    }
+
+    /// Decode one read's worth of output
+    pub fn decode(&mut self, bytes: &[u8]) -> String {
+        let mut input = std::mem::take(&mut self.pending);
+        input.extend_from_slice(bytes);
+
+        let mut text = String::new();
+        let mut rest = &input[..];
+        while let Some((end, utf8)) = find_coding_system(rest, &mut self.escape) {
+            text.push_str(&self.decode_segment(&rest[..end]));
+            if self.forced.is_none() {
+                self.switch_to(if utf8 { Encoding::Utf8 } else { self.fallback });
+            }
+            rest = &rest[end..];
+        }
+        text.push_str(&self.decode_segment(rest));
+        text
+    }
+
+    /// Decode `input` in the active encoding, keeping an unfinished UTF-8
+    /// sequence at its end for the next read
+    fn decode_segment(&mut self, input: &[u8]) -> String {
+        if self.forced.is_none() {
+            self.detect(input);
+        }
+        match self.active {
+            Encoding::Utf8 => {
+                let complete = input.len() - incomplete_suffix(input);
+                self.pending = input[complete..].to_vec();
+                String::from_utf8_lossy(&input[..complete]).into_owned()
+            }
+            Encoding::Latin1 => input.iter().map(|&b| b as char).collect(),
+            Encoding::Cp1252 => input.iter().map(|&b| cp1252_char(b)).collect(),
+        }
+    }
+
+    /// Weigh `input` as UTF-8 and switch encodings if the evidence is clear
+    fn detect(&mut self, input: &[u8]) {
+        self.window_bytes += input.len();
+        if self.window_bytes > DETECTION_WINDOW {
+            self.invalid = 0;
+            self.valid_multibyte = 0;
+            self.window_bytes = input.len();
+        }
+        let (invalid, valid_multibyte) = utf8_evidence(&input[..input.len() - incomplete_suffix(input)]);
+        self.invalid += invalid;
+        self.valid_multibyte += valid_multibyte;
+
+        match self.active {
+            Encoding::Utf8 if self.invalid >= SWITCH_THRESHOLD && self.invalid > self.valid_multibyte * INVALID_RATIO => {
+                self.switch_to(self.fallback);
+            }
+            Encoding::Latin1 | Encoding::Cp1252 if self.valid_multibyte >= SWITCH_THRESHOLD && self.invalid == 0 => {
+                self.switch_to(Encoding::Utf8);
+            }
+            _ => {}
+        }
+    }
+
+    fn switch_to(&mut self, encoding: Encoding) {
+        self.active = encoding;
+        self.invalid = 0;
+        self.valid_multibyte = 0;
+        self.window_bytes = 0;
+    }
+}
+
+/// End of the first `ESC % G` or `ESC % @` in `bytes`, and whether it
+/// selects UTF-8
+///
+/// `matched` is how much of `ESC %` came before `bytes`, so a sequence split
+/// across reads is found where it ends; it is left at how much of `ESC %`
+/// was scanned last.
+fn find_coding_system(bytes: &[u8], matched: &mut u8) -> Option<(usize, bool)> {
+    for (at, &byte) in bytes.iter().enumerate() {
+        *matched = match (*matched, byte) {
+            (2, b'G' | b'@') => {
+                *matched = 0;
+                return Some((at + 1, byte == b'G'));
+            }
+            (_, 0x1B) => 1,
+            (1, b'%') => 2,
+            _ => 0,
+        };
+    }
+    None
+}
+
+/// Length of a UTF-8 sequence left unfinished at the end of `bytes`
+fn incomplete_suffix(bytes: &[u8]) -> usize {
+    for back in 1..=bytes.len().min(3) {
+        let byte = bytes[bytes.len() - back];
+        if byte & 0xC0 != 0x80 {
+            let needed = match byte {
+                0xC2..=0xDF => 2,
+                0xE0..=0xEF => 3,
+                0xF0..=0xF4 => 4,
+                _ => return 0,
+            };
+            return if needed > back { back } else { 0 };
+        }
+    }
+    0
+}
+
+/// Count invalid sequences and valid multi-byte characters in `bytes`
+fn utf8_evidence(mut bytes: &[u8]) -> (u32, u32) {
+    let (mut invalid, mut multibyte) = (0, 0);
+    loop {
+        let (valid, rest) = match std::str::from_utf8(bytes) {
+            Ok(text) => (text, &[][..]),
+            Err(e) => {
+                let (valid, rest) = bytes.split_at(e.valid_up_to());
+                invalid += 1;
+                let skip = e.error_len().unwrap_or(rest.len());
+                (std::str::from_utf8(valid).unwrap_or_default(), &rest[skip..])
+            }
+        };
+        multibyte += valid.chars().filter(|ch| !ch.is_ascii()).count() as u32;
+        if rest.is_empty() {
+            return (invalid, multibyte);
+        }
+        bytes = rest;
+    }
+}
+
+fn cp1252_char(byte: u8) -> char {
+    match byte {
+        0x80..=0x9F => CP1252_HIGH[(byte - 0x80) as usize],
+        _ => byte as char,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utf8_split_across_reads() {
+        let mut decoder = StreamDecoder::new(None, Encoding::Cp1252);
+        let text = "caf\u{e9} \u{2500}\u{1F600}";
+        let bytes = text.as_bytes();
+        let decoded: String = bytes.chunks(1).map(|chunk| decoder.decode(chunk)).collect();
+        assert_eq!(decoded, text);
+        assert_eq!(decoder.encoding(), Encoding::Utf8);
+    }
+
+    #[test]
+    fn test_falls_back_for_legacy_output_and_recovers() {
+        let mut decoder = StreamDecoder::new(None, Encoding::Cp1252);
+        // A stray invalid byte does not switch
+        assert_eq!(decoder.decode(b"a\xffb"), "a\u{FFFD}b");
+        assert_eq!(decoder.encoding(), Encoding::Utf8);
+
+        // CP1252 text: é, ü, and curly quotes
+        assert_eq!(decoder.decode(b"caf\xe9 M\xfcller \x93ok\x94\r\n"), "caf\u{e9} M\u{fc}ller \u{201c}ok\u{201d}\r\n");
+        assert_eq!(decoder.encoding(), Encoding::Cp1252);
+
+        // Well-formed UTF-8 brings detection back
+        decoder.decode("\u{2500}\u{2500}\u{2500}\u{2500}".as_bytes());
+        assert_eq!(decoder.encoding(), Encoding::Utf8);
+        assert_eq!(decoder.decode("\u{e9}".as_bytes()), "\u{e9}");
+    }
+
+    #[test]
+    fn test_coding_system_sequences_switch_mid_read() {
+        let mut decoder = StreamDecoder::new(None, Encoding::Latin1);
+        assert_eq!(decoder.decode(b"\xe9\x1b%@\xe9\x1b%G\xc3\xa9"), "\u{FFFD}\x1b%@\u{e9}\x1b%G\u{e9}");
+        decoder.decode(b"\x1b%@");
+        assert_eq!(decoder.encoding(), Encoding::Latin1);
+
+        // Announcements split across reads switch where they end
+        for split in 1..3 {
+            let mut decoder = StreamDecoder::new(None, Encoding::Latin1);
+            let (first, second) = b"\x1b%@\xe9".split_at(split);
+            let decoded = decoder.decode(first) + &decoder.decode(second);
+            assert_eq!(decoded, "\x1b%@\u{e9}", "split at {}", split);
+            assert_eq!(decoder.encoding(), Encoding::Latin1);
+        }
+        let mut decoder = StreamDecoder::new(None, Encoding::Latin1);
+        let decoded: String = b"\x1b%@\x1b%G\xc3\xa9".chunks(1).map(|chunk| decoder.decode(chunk)).collect();
+        assert_eq!(decoded, "\x1b%@\x1b%G\u{e9}");
+        assert_eq!(decoder.encoding(), Encoding::Utf8);
+
+        // An explicit encoding wins over announcements
+        decoder.set_encoding(Some(Encoding::Utf8));
+        assert_eq!(decoder.decode(b"\x1b%@\xc3\xa9"), "\x1b%@\u{e9}");
+    }
+
+    #[test]
+    fn test_invalid_utf8_becomes_replacement_characters() {
+        let mut decoder = StreamDecoder::new(None, Encoding::Cp1252);
+        // A lone continuation byte, and a character cut off by an OSC terminator
+        assert_eq!(
+            decoder.decode(b"a\x80\xc3\xa9\x1b]2;t\xe2\x82\x07"),
+            "a\u{FFFD}\u{e9}\x1b]2;t\u{FFFD}\x07"
+        );
+    }
+
+    #[test]
+    fn test_truncated_characters_in_osc_payloads() {
+        use crate::ansi::AnsiParser;
+        use crate::config::TerminalConfig;
+        use crate::grid::Grid;
+        use std::sync::Arc;
+
+        let payload = "2;caf\u{e9} \u{1F600} \u{4E2D}\u{6587}".as_bytes();
+        for cut in 0..=payload.len() {
+            for terminator in [&b"\x07"[..], b"\x1b\\"] {
+                // Cut anywhere, including inside a character, and terminate
+                let mut grid = Grid::new(20, 4, Arc::new(TerminalConfig::default()));
+                let mut parser = AnsiParser::new();
+                let mut bytes = b"\x1b]".to_vec();
+                bytes.extend_from_slice(&payload[..cut]);
+                bytes.extend_from_slice(terminator);
+                bytes.extend_from_slice(&payload[cut..]);
+                bytes.extend_from_slice(b"\x1b]2;ok\x07");
+                let text = grid.decode_output(&bytes);
+                parser.feed_str(&text, &mut grid);
+                assert_eq!(grid.title(), "ok", "cut at {}", cut);
+            }
+        }
+    }
+
+    #[test]
+    fn test_explicit_encoding_disables_detection() {
+        let mut decoder = StreamDecoder::new(Some(Encoding::Latin1), Encoding::Cp1252);
+        assert_eq!(decoder.decode(b"\x93\xe9"), "\u{93}\u{e9}");
+        decoder.set_encoding(Some(Encoding::Utf8));
+        assert_eq!(decoder.decode(b"\xe9\xe9\xe9\xe9\xe9 "), "\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD} ");
+        assert_eq!(decoder.encoding(), Encoding::Utf8);
+    }
+}
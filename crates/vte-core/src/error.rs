@@ -65,6 +65,12 @@ pub enum TerminalError {
     #[error("Async runtime error: channel closed")]
     RuntimeError,
 
+    #[error("Timed out after {timeout:?} waiting for output matching {pattern}")]
+    WaitTimeout { pattern: String, timeout: std::time::Duration },
+
+    #[error("Invalid pattern {pattern}: {message}")]
+    InvalidPattern { pattern: String, message: String },
+
     // Parser and Protocol Errors (expanded from AnsiError)
     #[error("ANSI/VT parser error: {message}")]
     ParserError { message: String },
@@ -101,6 +107,24 @@ impl TerminalError {
         )
     }
 
+    /// Severity reported to embedders when this error is surfaced as an event
+    pub fn severity(&self) -> ErrorSeverity {
+        match self {
+            TerminalError::PtyCreationFailed { .. } |
+            TerminalError::PtyDisconnected { .. } |
+            TerminalError::ProcessSpawnFailed { .. } |
+            TerminalError::InitializationError { .. } =>
+                ErrorSeverity::Fatal,
+
+            TerminalError::ParserError { .. } |
+            TerminalError::InvalidEscapeSequence { .. } =>
+                ErrorSeverity::Info,
+
+            _ if self.is_recoverable() => ErrorSeverity::Warning,
+            _ => ErrorSeverity::Error,
+        }
+    }
+
     /// Suggest recovery action for this error type
     pub fn recovery_strategy(&self) -> RecoveryStrategy {
         match self {
@@ -154,6 +178,43 @@ impl TerminalError {
     }
 }
 
+impl From<vte_ansi::AnsiError> for TerminalError {
+    fn from(err: vte_ansi::AnsiError) -> Self {
+        use vte_ansi::AnsiError;
+        match err {
+            AnsiError::TooManyParams { sequence, count } =>
+                TerminalError::ParserError {
+                    message: format!("Too many parameters ({}) in sequence: {}", count, sequence)
+                },
+            AnsiError::OscTooLong { length } =>
+                TerminalError::ParserError {
+                    message: format!("OSC sequence too long: {} bytes", length)
+                },
+            AnsiError::ParamTooLarge { value } =>
+                TerminalError::ParserError {
+                    message: format!("Parameter value {} exceeded maximum", value)
+                },
+            AnsiError::MalformedSequence { context } =>
+                TerminalError::InvalidEscapeSequence {
+                    sequence: context
+                },
+        }
+    }
+}
+
+/// How serious an error is from the embedder's point of view
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ErrorSeverity {
+    /// Diagnostic only, terminal behaviour is unaffected
+    Info,
+    /// Recovered automatically, but worth surfacing
+    Warning,
+    /// An operation failed; the terminal keeps running
+    Error,
+    /// The terminal session cannot continue
+    Fatal,
+}
+
 /// Recovery strategies for different error types
 #[derive(Debug, Clone, Copy)]
 pub enum RecoveryStrategy {
@@ -172,3 +233,33 @@ pub enum RecoveryStrategy {
     /// Continue with error (no recovery possible)
     PropagateError,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_severity() {
+        let read = TerminalError::PtyReadError {
+            source: std::io::Error::other("eio"),
+        };
+        assert_eq!(read.severity(), ErrorSeverity::Warning);
+
+        let gone = TerminalError::PtyDisconnected { message: "eof".to_string() };
+        assert_eq!(gone.severity(), ErrorSeverity::Fatal);
+
+        let parse = TerminalError::ParserError { message: "bad".to_string() };
+        assert_eq!(parse.severity(), ErrorSeverity::Info);
+
+        let font = TerminalError::FontError { message: "missing".to_string() };
+        assert_eq!(font.severity(), ErrorSeverity::Error);
+        assert!(ErrorSeverity::Fatal > ErrorSeverity::Warning);
+    }
+
+    #[test]
+    fn test_ansi_error_conversion() {
+        let err: TerminalError = vte_ansi::AnsiError::OscTooLong { length: 4096 }.into();
+        assert!(matches!(err, TerminalError::ParserError { .. }));
+        assert!(err.to_string().contains("4096"));
+    }
+}
@@ -0,0 +1,313 @@
+// src/events.rs
+//! Terminal events delivered to embedders
+//!
+//! The core publishes notable state changes and diagnostics on a bounded
+//! channel. Embedders obtain a receiver from
+//! [`VteTerminalCore::events`](crate::terminal::VteTerminalCore::events) and
+//! drain it from their main loop. When nobody keeps up, the oldest events are
+//! discarded so the PTY reader never blocks on a slow consumer.
+
+use crate::bell::BellAction;
+use crate::commands::FinishedCommand;
+use crate::encoding::Encoding;
+use crate::error::{ErrorSeverity, TerminalError};
+use crate::health::ParserHealth;
+use crate::osc_policy::OscCategory;
+use async_channel::{Receiver, Sender, TrySendError};
+use std::time::Duration;
+use tracing::trace;
+
+/// Maximum number of undelivered events kept in the queue
+pub const EVENT_QUEUE_CAPACITY: usize = 256;
+
+/// Event emitted by the terminal core
+#[derive(Debug, Clone, PartialEq)]
+pub enum TerminalEvent {
+    /// The PTY reader thread panicked and was restarted
+    ReaderRecovered {
+        /// Panic payload (message) captured from the unwinding thread
+        panic_message: String,
+        /// Number of restarts performed so far
+        restarts: usize,
+    },
+    /// A recoverable or fatal error surfaced to the embedder
+    Error {
+        severity: ErrorSeverity,
+        /// Human-readable description (the `TerminalError` display text)
+        message: String,
+        /// Whether the core retries or recovers on its own
+        recoverable: bool,
+    },
+    /// A command that ran past `TerminalConfig::command_notify_threshold_ms`
+    /// finished while the terminal was unfocused (OSC 133 shell integration)
+    CommandFinished {
+        /// Command line as typed at the prompt (may be empty)
+        command: String,
+        duration: Duration,
+        /// Exit status reported by the shell, if any
+        exit_code: Option<i32>,
+    },
+    /// The pointer rested on a link (OSC 8 or a detected URL); embedders
+    /// show a preview until `HoverUrlCancelled`
+    HoverUrl {
+        uri: String,
+        /// Screen row of the link
+        row: usize,
+        /// Columns covered by the link, end exclusive
+        start_col: usize,
+        end_col: usize,
+    },
+    /// The pointer left the link reported by the last `HoverUrl`
+    HoverUrlCancelled,
+    /// Output was held (scroll lock) or released; embedders show an indicator
+    /// while held
+    OutputHold { held: bool },
+    /// The application rang the bell (BEL) and `TerminalConfig::bell_policy`
+    /// did not drop it; at most one event per chunk of output
+    Bell {
+        /// What the policy asks the embedder to do
+        action: BellAction,
+    },
+    /// The window title was set (OSC 0 or 2); also in `Grid::title`
+    TitleChanged { title: String },
+    /// The shell's working directory (OSC 7) moved to another machine or
+    /// back; also in `Grid::remote_host`
+    RemoteHostChanged {
+        /// Remote host, `None` for this machine
+        host: Option<String>,
+    },
+    /// The child process closed the PTY (e.g. the shell exited); no more
+    /// output will arrive
+    ChildExited,
+    /// The font zoom factor changed (e.g. Ctrl+wheel); embedders may show it
+    ZoomChanged { zoom: f64 },
+    /// The application asked for the clipboard contents (OSC 52) and
+    /// `SecurityConfig::allow_clipboard_read` is set; ask the user, then call
+    /// `VteTerminalCore::answer_clipboard_request` with `id`
+    ClipboardReadRequest {
+        id: u64,
+        /// Whether the primary selection was asked for, not the clipboard
+        primary: bool,
+    },
+    /// An OSC sequence is held because `SecurityConfig::osc_policy` says to
+    /// ask; ask the user, then call `VteTerminalCore::answer_osc_request`
+    /// with `id`
+    OscPermissionRequest {
+        id: u64,
+        category: OscCategory,
+        /// Start of the sequence (`number;data`), to show the user
+        preview: String,
+    },
+    /// The application asked for a desktop notification (OSC 9 or OSC 777)
+    Notification {
+        /// Empty for OSC 9, which only carries a body
+        title: String,
+        body: String,
+    },
+    /// An OSC sequence with a number listed in `OscPolicy::custom_codes`
+    CustomOsc { code: u16, data: String },
+    /// Output is now decoded as `encoding`: detection switched to
+    /// `TerminalConfig::fallback_encoding` or back to UTF-8
+    EncodingChanged { encoding: Encoding },
+    /// New output arrived below a scrolled-back or held viewport; embedders
+    /// show a "new output below" indicator until `Grid::unseen_output` is 0
+    UnseenOutput {
+        /// Rows of output below the viewport not seen yet
+        rows: usize,
+    },
+    /// Screen rows changed; sent at most once per
+    /// `TerminalConfig::damage_event_interval_ms`, so embedders can refresh
+    /// thumbnails or previews without watching every redraw
+    Damage {
+        /// Screen rows changed since the previous `Damage` event
+        rows: usize,
+        /// Generation the change was committed in (see `Grid::diff_since`)
+        generation: u64,
+    },
+    /// A paste streamed in the background made progress; sent at most every
+    /// 100ms while it runs (see `PasteStreamer`)
+    PasteProgress {
+        /// Bytes written to the PTY so far, out of `total`
+        sent: usize,
+        total: usize,
+    },
+    /// A streamed paste ended, completely or because it was cancelled
+    PasteFinished { sent: usize, total: usize, cancelled: bool },
+    /// No output for `TerminalConfig::silence_threshold_ms`; sent once per
+    /// quiet period (see `activity`)
+    Silence {
+        /// Time since the last output
+        duration: Duration,
+    },
+    /// Output resumed after a `Silence` event
+    Activity,
+    /// The cursor moved on screen, for popovers following it (pixels from
+    /// `Grid::cursor_screen_position`)
+    ///
+    /// Sent at most once per chunk of output, and by the widget when
+    /// scrolling, resizing or zooming moves the cursor; not while the cursor
+    /// is scrolled out of view.
+    CursorMoved {
+        /// Viewport row and column of the cursor, as in `CursorPosition`
+        row: usize,
+        col: usize,
+    },
+    /// Parser activity over the last `TerminalConfig::parser_health_interval_ms`,
+    /// sent only for intervals in which output was parsed (see `health`)
+    ParserHealth { health: ParserHealth },
+}
+
+impl TerminalEvent {
+    /// Build an error event from a terminal error
+    pub fn from_error(err: &TerminalError) -> Self {
+        TerminalEvent::Error {
+            severity: err.severity(),
+            message: err.to_string(),
+            recoverable: err.is_recoverable(),
+        }
+    }
+
+    /// Desktop notification text for events worth one, e.g. `make finished (2m13s, exit 0)`
+    pub fn notification_text(&self) -> Option<String> {
+        match self {
+            TerminalEvent::CommandFinished { command, duration, exit_code } => Some(
+                FinishedCommand { command: command.clone(), duration: *duration, exit_code: *exit_code }.summary(),
+            ),
+            _ => None,
+        }
+    }
+}
+
+impl From<FinishedCommand> for TerminalEvent {
+    fn from(finished: FinishedCommand) -> Self {
+        TerminalEvent::CommandFinished {
+            command: finished.command,
+            duration: finished.duration,
+            exit_code: finished.exit_code,
+        }
+    }
+}
+
+/// Cloneable publishing handle shared by the core's threads
+#[derive(Debug, Clone)]
+pub struct EventEmitter {
+    sender: Sender<TerminalEvent>,
+    receiver: Receiver<TerminalEvent>,
+}
+
+impl EventEmitter {
+    pub fn new() -> Self {
+        let (sender, receiver) = async_channel::bounded(EVENT_QUEUE_CAPACITY);
+        Self { sender, receiver }
+    }
+
+    /// Publish an event, dropping the oldest queued event if the queue is full
+    pub fn emit(&self, event: TerminalEvent) {
+        let mut event = event;
+        loop {
+            match self.sender.try_send(event) {
+                Ok(()) => return,
+                Err(TrySendError::Full(returned)) => {
+                    trace!("Event queue full, dropping oldest event");
+                    let _ = self.receiver.try_recv();
+                    event = returned;
+                }
+                Err(TrySendError::Closed(_)) => return,
+            }
+        }
+    }
+
+    /// Publish a terminal error with its severity
+    pub fn emit_error(&self, err: &TerminalError) {
+        self.emit(TerminalEvent::from_error(err));
+    }
+
+    /// New receiver for the event stream
+    ///
+    /// All receivers share one queue: each event is delivered to exactly one of them.
+    pub fn subscribe(&self) -> Receiver<TerminalEvent> {
+        self.receiver.clone()
+    }
+}
+
+impl Default for EventEmitter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Extract a readable message from a panic payload
+pub fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        (*s).to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic payload".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recovered(restarts: usize) -> TerminalEvent {
+        TerminalEvent::ReaderRecovered { panic_message: "boom".to_string(), restarts }
+    }
+
+    #[test]
+    fn test_emit_and_receive() {
+        let emitter = EventEmitter::new();
+        let rx = emitter.subscribe();
+        emitter.emit(recovered(1));
+        assert_eq!(rx.try_recv().ok(), Some(recovered(1)));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_full_queue_drops_oldest() {
+        let emitter = EventEmitter::new();
+        let rx = emitter.subscribe();
+        for i in 0..EVENT_QUEUE_CAPACITY + 2 {
+            emitter.emit(recovered(i));
+        }
+        assert_eq!(rx.len(), EVENT_QUEUE_CAPACITY);
+        assert_eq!(rx.try_recv().ok(), Some(recovered(2)));
+    }
+
+    #[test]
+    fn test_error_event_carries_severity() {
+        let emitter = EventEmitter::new();
+        let rx = emitter.subscribe();
+        emitter.emit_error(&TerminalError::GridLockError { message: "poisoned".to_string() });
+
+        match rx.try_recv() {
+            Ok(TerminalEvent::Error { severity, message, recoverable }) => {
+                assert_eq!(severity, ErrorSeverity::Warning);
+                assert!(message.contains("poisoned"));
+                assert!(recoverable);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_command_finished_notification_text() {
+        let event = TerminalEvent::from(FinishedCommand {
+            command: "make".to_string(),
+            duration: Duration::from_secs(133),
+            exit_code: Some(0),
+        });
+        assert_eq!(event.notification_text().as_deref(), Some("make finished (2m13s, exit 0)"));
+        assert_eq!(recovered(1).notification_text(), None);
+    }
+
+    #[test]
+    fn test_panic_message_extraction() {
+        let payload = std::panic::catch_unwind(|| panic!("parser exploded")).unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "parser exploded");
+
+        let payload = std::panic::catch_unwind(|| panic!("{} {}", "formatted", 42)).unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "formatted 42");
+    }
+}
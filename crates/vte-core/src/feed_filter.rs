@@ -0,0 +1,113 @@
+// src/feed_filter.rs
+//! Filtering of untrusted input in screen-only mode
+//!
+//! A log viewer that feeds files through `AnsiParser` into a `Grid` shows
+//! whatever escape sequences the file contains: a crafted log can retitle
+//! the window, write the clipboard or switch on mouse reporting. Setting a
+//! [`FilterPolicy`] with `Grid::set_feed_filter` drops those sequences
+//! before they take effect, while text, colors and cursor motion are
+//! rendered as usual. Dropped sequences are not recorded in the OSC audit
+//! log; the filter describes the input, not a decision about it.
+
+use crate::ansi::OscCategory;
+
+/// Sequence classes dropped from fed input
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FilterPolicy {
+    /// OSC categories to drop (title, clipboard, hyperlinks, ...)
+    pub osc: Vec<OscCategory>,
+    /// Drop device control strings (e.g. XTGETTCAP queries)
+    pub dcs: bool,
+    /// Ignore mouse, focus, bracketed paste and color scheme reporting modes
+    pub input_modes: bool,
+}
+
+impl FilterPolicy {
+    /// Allow everything; the same as not setting a filter
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drop every OSC category, DCS and input mode change, for logs from
+    /// unknown sources
+    pub fn untrusted() -> Self {
+        Self {
+            osc: vec![
+                OscCategory::Title,
+                OscCategory::ClipboardWrite,
+                OscCategory::ClipboardRead,
+                OscCategory::Hyperlink,
+                OscCategory::Notification,
+                OscCategory::CurrentDirectory,
+                OscCategory::Colors,
+                OscCategory::PointerShape,
+                OscCategory::ActiveRegion,
+                OscCategory::Custom,
+            ],
+            dcs: true,
+            input_modes: true,
+        }
+    }
+
+    pub fn with_osc_blocked(mut self, category: OscCategory) -> Self {
+        if !self.osc.contains(&category) {
+            self.osc.push(category);
+        }
+        self
+    }
+
+    pub fn with_dcs_blocked(mut self, blocked: bool) -> Self {
+        self.dcs = blocked;
+        self
+    }
+
+    pub fn with_input_modes_blocked(mut self, blocked: bool) -> Self {
+        self.input_modes = blocked;
+        self
+    }
+
+    /// Whether OSC sequences in `category` get through
+    pub fn allows_osc(&self, category: OscCategory) -> bool {
+        !self.osc.contains(&category)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::AnsiParser;
+    use crate::config::TerminalConfig;
+    use crate::grid::Grid;
+    use std::sync::Arc;
+
+    fn filtered_grid(filter: FilterPolicy) -> Grid {
+        let mut grid = Grid::new(20, 2, Arc::new(TerminalConfig::default()));
+        grid.set_feed_filter(Some(filter));
+        grid
+    }
+
+    #[test]
+    fn test_untrusted_keeps_text_and_colors() {
+        let mut grid = filtered_grid(FilterPolicy::untrusted());
+        AnsiParser::new().feed_str(
+            "\x1b]2;pwned\x07\x1b]52;c;?\x07\x1b]11;?\x07\x1b]4;1;?\x07\x1b[?1000h\x1b[?2004h\x1bP+q544e\x1b\\\x1b[31mred\x1b[0m\x1b[2;3Hok",
+            &mut grid,
+        );
+        assert_eq!(grid.title(), "");
+        assert!(grid.take_clipboard_requests().is_empty());
+        assert!(!grid.modes().mouse_reporting() && !grid.modes().bracketed_paste);
+        assert!(grid.take_responses().is_empty());
+        assert_eq!(grid.get_cell(0, 0).ch, 'r');
+        assert_ne!(grid.get_cell(0, 0).fg, grid.get_cell(1, 2).fg);
+        assert_eq!(grid.get_cell(1, 2).ch, 'o');
+        assert!(grid.denied_osc().is_empty());
+    }
+
+    #[test]
+    fn test_only_listed_categories_are_dropped() {
+        let mut grid = filtered_grid(FilterPolicy::new().with_osc_blocked(OscCategory::ClipboardWrite));
+        AnsiParser::new().feed_str("\x1b]2;build\x07\x1b[?1000h", &mut grid);
+        assert_eq!(grid.title(), "build");
+        assert!(grid.modes().mouse_reporting());
+    }
+}
@@ -241,16 +241,13 @@ impl FontCache {
         Ok((font, metrics))
     }
 
-    /// Render glyph to bitmap
-    pub fn rasterize_glyph(&mut self, ch: char, weight: FontWeight, slant: FontSlant) -> Result<(Vec<u8>, u32, u32), FontSelectionError> {
+    /// Render glyph to bitmap, with the metrics placing it against the pen
+    /// position and baseline
+    pub fn rasterize_glyph(&mut self, ch: char, weight: FontWeight, slant: FontSlant) -> Result<(Vec<u8>, fontdue::Metrics), FontSelectionError> {
         let handle = self.select_font_for_char(ch, weight, slant)?;
         let (font, _, _, _, _) = &self.loaded_fonts[handle.chain_index];
         let (metrics, bitmap) = font.rasterize(ch, self.font_size);
-        Ok((
-            bitmap,
-            metrics.width.try_into().unwrap_or(0),
-            metrics.height.try_into().unwrap_or(0)
-        ))
+        Ok((bitmap, metrics))
     }
 
     /// Get default font metrics for the cache
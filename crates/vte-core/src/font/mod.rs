@@ -6,10 +6,12 @@
 pub mod cache;
 pub mod discovery;
 pub mod fallback;
+pub mod shared;
 
 pub use cache::{FontCache, FontHandle, FontSelectionError};
 pub use discovery::{discover_fonts, FontSource, FontLocation};
 pub use fallback::{build_fallback_chain, FallbackMetrics, score_font_for_chars};
+pub use shared::{Glyph, SharedFontCache, SharedFontKey};
 
 /// Font weight variants for terminal rendering
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
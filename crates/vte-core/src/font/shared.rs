@@ -0,0 +1,240 @@
+//! Font caches shared between the terminals of a process
+//!
+//! Loading a fallback chain reads every font file of the chain, and each
+//! rasterized glyph is a bitmap; a tabbed application with twenty terminals
+//! in the same font would otherwise hold twenty copies of both. Renderers
+//! get a [`SharedFontCache`] for their family, size and scale factor from
+//! [`SharedFontCache::acquire`] instead: terminals asking for the same key
+//! share one cache, its fallback decisions and its glyph bitmaps, and the
+//! cache is freed when the last terminal holding it drops its `Arc`.
+
+use super::{FontCache, FontSelectionError, FontSlant, FontWeight};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError, Weak};
+
+/// Rasterized glyphs kept per cache; glyphs past this are rasterized on
+/// every use instead of being stored
+pub const MAX_SHARED_GLYPHS: usize = 4096;
+
+/// What a shared cache is looked up by: family, size in pixels and the
+/// scale factor of the target
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SharedFontKey {
+    pub family: String,
+    size_bits: u32,
+    scale_bits: u64,
+}
+
+impl SharedFontKey {
+    pub fn new(family: &str, size: f32, scale: f64) -> Self {
+        Self { family: family.to_string(), size_bits: size.to_bits(), scale_bits: scale.to_bits() }
+    }
+
+    /// Font size in pixels
+    pub fn size(&self) -> f32 {
+        f32::from_bits(self.size_bits)
+    }
+
+    /// Device pixels per logical pixel
+    pub fn scale(&self) -> f64 {
+        f64::from_bits(self.scale_bits)
+    }
+}
+
+/// An alpha-only glyph bitmap, one byte per device pixel
+///
+/// Glyphs are rasterized at the cache's size times its scale factor, so the
+/// bitmap and its metrics are in device pixels; renderers divide by
+/// [`SharedFontKey::scale`] to place them in logical pixels.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Glyph {
+    pub bitmap: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    /// Pixels from the pen position to the bitmap's left edge
+    pub xmin: i32,
+    /// Pixels from the baseline up to the bitmap's bottom edge; negative
+    /// for descenders
+    pub ymin: i32,
+}
+
+/// A font cache and its rasterized glyphs, shared by every terminal using
+/// the same [`SharedFontKey`]
+pub struct SharedFontCache {
+    key: SharedFontKey,
+    fonts: Mutex<FontCache>,
+    /// Rasterized glyphs; `None` for characters no font in the chain has
+    glyphs: Mutex<HashMap<GlyphKey, Option<Arc<Glyph>>>>,
+}
+
+/// Character and variant of a rasterized glyph
+type GlyphKey = (char, FontWeight, FontSlant);
+
+/// The process-wide caches by key
+fn caches() -> &'static Registry<SharedFontCache> {
+    static CACHES: OnceLock<Registry<SharedFontCache>> = OnceLock::new();
+    CACHES.get_or_init(Registry::new)
+}
+
+impl SharedFontCache {
+    /// The process's cache for `family` at `size` pixels and `scale`, loaded
+    /// on first use and shared until its last holder drops it
+    ///
+    /// Glyphs are rasterized at `size * scale` device pixels, sharp on HiDPI
+    /// displays.
+    pub fn acquire(family: &str, size: f32, scale: f64) -> Result<Arc<Self>, FontSelectionError> {
+        let key = SharedFontKey::new(family, size, scale);
+        caches().acquire(&key, || {
+            let fonts = FontCache::new(family, size * scale as f32)?;
+            Ok(Self { key: key.clone(), fonts: Mutex::new(fonts), glyphs: Mutex::new(HashMap::new()) })
+        })
+    }
+
+    pub fn key(&self) -> &SharedFontKey {
+        &self.key
+    }
+
+    /// Bitmap of `ch` in the given variant, rasterized once for all holders;
+    /// `None` when no font in the fallback chain has the character
+    pub fn glyph(&self, ch: char, weight: FontWeight, slant: FontSlant) -> Option<Arc<Glyph>> {
+        let key = (ch, weight, slant);
+        if let Some(glyph) = lock(&self.glyphs).get(&key) {
+            return glyph.clone();
+        }
+        let glyph = lock(&self.fonts)
+            .rasterize_glyph(ch, weight, slant)
+            .ok()
+            .map(|(bitmap, metrics)| {
+                Arc::new(Glyph {
+                    bitmap,
+                    width: metrics.width as u32,
+                    height: metrics.height as u32,
+                    xmin: metrics.xmin,
+                    ymin: metrics.ymin,
+                })
+            });
+        let mut glyphs = lock(&self.glyphs);
+        if glyphs.len() < MAX_SHARED_GLYPHS {
+            glyphs.insert(key, glyph.clone());
+        }
+        glyph
+    }
+
+    /// Rasterized glyphs held, for memory diagnostics
+    pub fn glyph_count(&self) -> usize {
+        lock(&self.glyphs).len()
+    }
+
+    /// Caches some terminal holds, for memory diagnostics
+    pub fn live_caches() -> usize {
+        caches().live()
+    }
+}
+
+/// Lock `mutex`, going on with the data of a holder that panicked: the caches
+/// only ever gain complete entries
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Values by key, kept alive only by their holders
+struct Registry<V> {
+    entries: Mutex<HashMap<SharedFontKey, Weak<V>>>,
+}
+
+impl<V> Registry<V> {
+    fn new() -> Self {
+        Self { entries: Mutex::new(HashMap::new()) }
+    }
+
+    /// The live value for `key`, or a new one from `load`; the registry stays
+    /// locked while loading so a key is never loaded twice at once
+    fn acquire<E>(&self, key: &SharedFontKey, load: impl FnOnce() -> Result<V, E>) -> Result<Arc<V>, E> {
+        let mut entries = lock(&self.entries);
+        if let Some(value) = entries.get(key).and_then(Weak::upgrade) {
+            return Ok(value);
+        }
+        entries.retain(|_, value| value.strong_count() > 0);
+        let value = Arc::new(load()?);
+        entries.insert(key.clone(), Arc::downgrade(&value));
+        Ok(value)
+    }
+
+    /// Keys with at least one holder
+    fn live(&self) -> usize {
+        lock(&self.entries).values().filter(|value| value.strong_count() > 0).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_shares_until_last_holder_drops() {
+        let registry = Registry::new();
+        let mono = SharedFontKey::new("Mono", 13.0, 1.0);
+        let mut loads = 0;
+        let mut load = |value: u32| {
+            loads += 1;
+            Ok::<_, ()>(value)
+        };
+
+        let first = registry.acquire(&mono, || load(1)).unwrap();
+        let second = registry.acquire(&mono, || load(2)).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        // Another scale factor is another cache
+        let hidpi = registry.acquire(&SharedFontKey::new("Mono", 13.0, 2.0), || load(3)).unwrap();
+        assert_eq!(*hidpi, 3);
+        assert_eq!(registry.live(), 2);
+
+        drop((first, second, hidpi));
+        assert_eq!(registry.live(), 0);
+        assert_eq!(*registry.acquire(&mono, || load(4)).unwrap(), 4);
+        assert_eq!(loads, 3);
+    }
+
+    #[test]
+    fn test_failed_load_is_not_cached() {
+        let registry = Registry::<u32>::new();
+        let key = SharedFontKey::new("Missing", 13.0, 1.0);
+        assert!(registry.acquire(&key, || Err("no fonts")).is_err());
+        assert_eq!(*registry.acquire(&key, || Ok::<_, &str>(7)).unwrap(), 7);
+    }
+
+    #[test]
+    fn test_terminals_share_glyphs() {
+        // Needs system fonts
+        let Ok(first) = SharedFontCache::acquire("DejaVu Sans Mono", 13.0, 1.0) else {
+            return;
+        };
+        let second = SharedFontCache::acquire("DejaVu Sans Mono", 13.0, 1.0).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(first.key().size(), 13.0);
+        assert!(SharedFontCache::live_caches() >= 1);
+
+        let a = first.glyph('A', FontWeight::Normal, FontSlant::Normal);
+        let b = second.glyph('A', FontWeight::Normal, FontSlant::Normal);
+        if let (Some(a), Some(b)) = (a, b) {
+            assert!(Arc::ptr_eq(&a, &b));
+        }
+        assert_eq!(second.glyph_count(), 1);
+    }
+
+    #[test]
+    fn test_glyphs_are_rasterized_at_the_scale_factor() {
+        // Needs system fonts
+        let (Ok(normal), Ok(hidpi)) = (
+            SharedFontCache::acquire("DejaVu Sans Mono", 13.0, 1.0),
+            SharedFontCache::acquire("DejaVu Sans Mono", 13.0, 2.0),
+        ) else {
+            return;
+        };
+        let glyph = |cache: &SharedFontCache| cache.glyph('M', FontWeight::Normal, FontSlant::Normal);
+        if let (Some(normal), Some(hidpi)) = (glyph(&normal), glyph(&hidpi)) {
+            assert!(hidpi.height.abs_diff(normal.height * 2) <= 1);
+            assert!(hidpi.width.abs_diff(normal.width * 2) <= 1);
+        }
+    }
+}
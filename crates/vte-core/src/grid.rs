@@ -1,42 +1,179 @@
 // src/grid.rs
-use crate::ansi::{AnsiGrid, Cell, Color};
-use crate::selection::Selection;
-use vte_ansi::color::brighten_color;
-use std::time::Instant;
+use crate::activity::ActivityMonitor;
+use crate::ansi::{palette_color, AnsiGrid, AnsiParser, Cell, Color, DynamicColor, LogicalColor, OscCategory, ParserStats, TermcapValue, UnderlineStyle};
+use crate::annotations::{Annotation, AnnotationId, RowAnnotation, RowAnnotations};
+use crate::bell::{BellAction, BellGate, BellPolicy};
+use crate::changes::{ChangeCounters, ChangeHookId, ChangeLog, GridChange};
+use crate::clipboard::{ClipboardReads, ClipboardRequest};
+use crate::clock::{Clock, SystemClock};
+use crate::commands::{CommandTracker, FinishedCommand, PROMPT_CHARS};
+use crate::cursor::CursorPosition;
+use crate::diff::{GenerationTracker, RowDelta};
+use crate::drawing::CellGeometry;
+use crate::encoding::{Encoding, StreamDecoder};
+use crate::feed_filter::FilterPolicy;
+use crate::health::{HealthMonitor, ParserHealth};
+use crate::highlights::{visible_part, HighlightSpan, HighlightStyle, Highlights};
+use crate::hyperlinks::{HyperlinkCopyMode, HyperlinkTable, NO_HYPERLINK};
+use crate::images::{ImageLayer, PlacedImage};
+use crate::input_trace::{InputTrace, InputTraceEntry, TracedInput};
+use crate::keys::{Key, KeyEncoder};
+use crate::link_rules::find_rule_link_at;
+use crate::locator::{Locator, LocatorButton};
+use crate::marks::{MarkCategory, MarkDirection, ScrollMark, ScrollMarks};
+use crate::modes::{DisabledModes, ForcibleMode, Modes, MouseEncoding, MouseTracking};
+use crate::osc_policy::{DeniedOsc, OscAction, OscGate, OscRequest};
+use crate::pointer::{app_pointer_shape, find_url_at, HoveredLink, PointerState};
+use crate::predict::{EchoPredictor, LocalEcho};
+use crate::regions::NO_REGION;
+use crate::remote::{RemoteCursor, RemoteCursors};
+use crate::scrollback::{MemoryScrollback, ScrollbackStore};
+use crate::scrollback_index::ScrollbackIndex;
+use crate::screen::ScreenBuffer;
+use crate::selection::{ClickCounter, Selection, SelectionSpan};
+use crate::snapshots::{ScreenSnapshot, SnapshotRing};
+use crate::text_input::TextSource;
+use crate::theme::ColorScheme;
+use crate::traits::{ImageData, PointerShape};
+use crate::user_keys::UserKeys;
+use crate::width::NO_COMBINING;
+use crate::working_directory::WorkingDirectory;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+/// Pen attributes saved and restored as a unit (alternate screen, notices)
+#[derive(Clone, Copy, Debug)]
+struct SavedAttrs {
+    fg: Color,
+    bg: Color,
+    fg_logical: LogicalColor,
+    bg_logical: LogicalColor,
+    bold: bool,
+    italic: bool,
+    underline: bool,
+    underline_style: UnderlineStyle,
+    dim: bool,
+    strikethrough: bool,
+    overline: bool,
+    decoration: Color,
+    decoration_logical: LogicalColor,
+    font: u8,
+}
+
+impl Default for SavedAttrs {
+    fn default() -> Self {
+        Self {
+            fg: crate::constants::DEFAULT_FG,
+            bg: crate::constants::DEFAULT_BG,
+            fg_logical: LogicalColor::Default,
+            bg_logical: LogicalColor::Default,
+            bold: false,
+            italic: false,
+            underline: false,
+            underline_style: UnderlineStyle::None,
+            dim: false,
+            strikethrough: false,
+            overline: false,
+            decoration: crate::constants::DEFAULT_FG,
+            decoration_logical: LogicalColor::Default,
+            font: crate::ansi::PRIMARY_FONT,
+        }
+    }
+}
+
+/// Cursor state saved by DECSC and restored by DECRC
+#[derive(Clone, Copy, Debug)]
+struct SavedCursor {
+    row: usize,
+    col: usize,
+    /// DECSCA/SPA protection, which DECSC saves along with the position
+    protected: bool,
+}
 
 /// Terminal grid - manages cell storage and cursor state
+#[derive(Clone)]
 pub struct Grid {
     pub cols: usize,
     pub rows: usize,
-    pub cells: Vec<Cell>, // Flat storage for better cache locality
-    pub alternate_cells: Vec<Cell>, // Alternate screen buffer
-    pub scrollback: Vec<Cell>, // Also flat storage (primary buffer only)
+    pub cells: ScreenBuffer, // Rows allocated on first write, see `screen`
+    pub alternate_cells: ScreenBuffer, // Alternate screen buffer
+    // Rows scrolled off the primary screen, oldest first
+    scrollback: Box<dyn ScrollbackStore>,
     pub config: std::sync::Arc<crate::config::TerminalConfig>,
     pub scroll_offset: usize,
+    /// Scroll lock: output no longer scrolls the viewport to the bottom
+    output_held: bool,
+    // Viewport pinned while a selection is dragged (`freeze_on_select`)
+    selection_frozen: bool,
+    // Rows scrolled into the scrollback while the viewport stayed put
+    unseen_rows: usize,
+    // Fraction of a line left over from smooth wheel scrolling
+    wheel_remainder: f64,
     pub col: usize,
     pub row: usize,
     // Alternate screen state
     primary_cursor: (usize, usize), // Saved for alternate screen
     alternate_cursor: (usize, usize), // Primary screen cursor
-    primary_attrs: (Color, Color, bool, bool, bool, bool), // fg, bg, bold, italic, underline, dim
-    alternate_attrs: (Color, Color, bool, bool, bool, bool), // fg, bg, bold, italic, underline, dim
+    primary_attrs: SavedAttrs,
+    alternate_attrs: SavedAttrs,
+    // Primary screen scroll offset and unseen rows while the alternate screen is shown
+    primary_viewport: (usize, usize),
+    /// XTPUSHSGR saves, newest last, with the attributes each restores
+    sgr_stack: Vec<(SavedAttrs, Vec<u16>)>,
     pub fg: Color,
     pub bg: Color,
+    fg_logical: LogicalColor,
+    bg_logical: LogicalColor,
     bold: bool,
     italic: bool,
     underline: bool,
+    underline_style: UnderlineStyle,
     dim: bool,
+    strikethrough: bool,
+    overline: bool,
+    // SGR 58 decoration color; `LogicalColor::Default` follows the foreground
+    decoration: Color,
+    decoration_logical: LogicalColor,
+    font: u8,
+    // OSC 8 link applied to written cells (id into `hyperlinks`)
+    hyperlink: u32,
+    // DECSCA/SPA protection applied to written cells
+    protected: bool,
+    // Active region applied to written cells (id into `regions`)
+    region: u32,
     // Selection state
     pub selection: Selection,
-    // Cursor blink state
+    // DECTCEM cursor visibility
     cursor_visible: bool,
+    // Cursor blink phase: false while the blinking cursor is in its off half
+    cursor_blink_on: bool,
+    // DECSET 12 request from the running application, overriding the config
+    cursor_blink_mode: Option<bool>,
+    // Mouse pointer visibility and hover state
+    pointer: PointerState,
+    // Viewport cell last reported by `take_cursor_move`
+    cursor_reported: Option<(usize, usize)>,
     // Cursor stack for save/restore
-    cursor_stack: Vec<(usize, usize)>,
+    cursor_stack: Vec<SavedCursor>,
     // Terminal modes
     insert_mode: bool,
     auto_wrap: bool,
     bracketed_paste_mode: bool,
+    color_scheme_reporting: bool, // mode 2031
+    application_cursor_keys: bool, // DECCKM
+    application_keypad: bool,      // DECKPAM / DECKPNM
     origin_mode: bool, // DECOM - DEC Origin Mode
+    mouse_tracking: MouseTracking, // DECSET 1000/1002/1003
+    mouse_encoding: MouseEncoding, // DECSET 1005/1006
+    focus_reporting: bool,         // DECSET 1004
+    // DEC locator reporting (DECELR/DECSLE) and the pointer it reports
+    locator: Locator,
+    // Modes the embedder forced off; the application's requests above are kept
+    disabled_modes: DisabledModes,
+    // Function keys programmed with DECUDK, shared with the key encoders handed out
+    user_keys: std::sync::Arc<UserKeys>,
+    // Key events with their encoding, while tracing (see `input_trace`)
+    input_trace: InputTrace,
 
     // Character set state (ISO-2022)
     g0_charset: char,  // G0 character set designator
@@ -51,61 +188,196 @@ pub struct Grid {
     use_alternate_screen: bool,
     // Terminal title
     title: String,
+    // Columns the next advance() moves (width of the last put character)
+    pending_advance: usize,
+    // Last character written, for variation selector widening
+    last_put: Option<char>,
+    // DECAWM pending wrap: the last column was written and the cursor waits
+    // there; the next printable character wraps to a new line first
+    wrap_pending: bool,
+    // DECSET 45: backspace at column 0 moves to the end of the previous line
+    reverse_wraparound: bool,
+    // Replies to queries (OSC 4/10/11/12) awaiting delivery to the PTY
+    responses: String,
+    // Scroll marks anchored to absolute rows
+    marks: ScrollMarks,
+    // Embedder annotations anchored to absolute rows, trimmed like the marks
+    annotations: RowAnnotations,
+    // Embedder highlights (e.g. find matches), trimmed like the marks
+    highlights: Highlights,
+    // Sixel images of the primary and alternate screens, trimmed like the marks
+    images: ImageLayer,
+    alternate_images: ImageLayer,
+    // Cell size in pixels reported by the backend, for the cells an image covers
+    cell_pixel_size: (usize, usize),
+    // Rows dropped from the top of the scrollback, i.e. absolute row of its first line
+    rows_trimmed: u64,
+    // Searchable text of the scrollback, kept in step with `scrollback`
+    scrollback_index: ScrollbackIndex,
+    // Whether the terminal widget has keyboard focus
+    focused: bool,
+    // Command line start (absolute row, column) from OSC 133;B
+    command_input: Option<(u64, usize)>,
+    // Timing of the running shell command (OSC 133;C .. D)
+    commands: CommandTracker,
+    // Long-running commands that finished while unfocused, awaiting an event
+    finished_commands: Vec<FinishedCommand>,
+    // Time of the last output, for silence events (see `activity`)
+    activity: ActivityMonitor,
+    // Parser counters recorded by the PTY reader, for health reports (see `health`)
+    parser_health: HealthMonitor,
+    // Exit status from the most recent OSC 133;D
+    last_command_status: Option<i32>,
+    // Output start (absolute row, column) of the running command, from OSC 133;C
+    output_start: Option<(u64, usize)>,
+    // Output of the most recent finished command, start inclusive, end exclusive
+    last_output: Option<((u64, usize), (u64, usize))>,
+    // URIs of the OSC 8 links referenced by cells
+    hyperlinks: HyperlinkTable,
+    // Payloads of active regions, by id
+    regions: HyperlinkTable,
+    // Combining mark sequences referenced by cells
+    combining: HyperlinkTable,
+    // Last committed screen and per-row change generations
+    generations: GenerationTracker,
+    // Locally echoed keystrokes awaiting confirmation by PTY output
+    predictor: EchoPredictor,
+    // BEL characters received so far
+    bells: u64,
+    // Bell policy, and the action of a bell awaiting `TerminalEvent::Bell`
+    bell_gate: BellGate,
+    pending_bell: Option<BellAction>,
+    // OSC 52 clipboard reads awaiting the user's permission
+    clipboard_reads: ClipboardReads,
+    // OSC sequences held for permission, and the log of denied ones
+    osc_gate: OscGate,
+    // Set while an allowed held sequence is replayed, bypassing the policy
+    osc_replay: bool,
+    // OSC 8 link held for permission that no later OSC 8 has ended yet
+    held_hyperlink: Option<String>,
+    // Sequence classes dropped from untrusted input (screen-only mode)
+    feed_filter: Option<FilterPolicy>,
+    // Desktop notifications (OSC 9/777) and custom OSC sequences for the embedder
+    notifications: Vec<(String, String)>,
+    custom_osc: Vec<(u16, String)>,
+    // Working directory last reported with OSC 7 that decoded cleanly
+    current_directory: Option<WorkingDirectory>,
+    // Collaborators' cursors and selections, supplied by the embedder
+    remote_cursors: RemoteCursors,
+    // Recent screens for time travel, captured on commit (see `snapshots`)
+    snapshots: SnapshotRing,
+    // Decoder for PTY output, detecting legacy encodings
+    decoder: StreamDecoder,
+    // Time source for click, blink and command timing
+    clock: std::sync::Arc<dyn Clock>,
+    // Consecutive clicks for double/triple-click selection
+    clicks: ClickCounter,
+    // Start of the current blink cycle; the cursor is on in its first half
+    blink_epoch: Instant,
+    // Change hooks and scrollback/screen changes awaiting the next commit
+    changes: ChangeLog,
 }
 
 impl Grid {
     fn default_cell() -> Cell {
         Cell {
             ch: '\0',
+            wide: false,
+            spacer: false,
             fg: crate::constants::DEFAULT_FG,
             bg: crate::constants::DEFAULT_BG,
             bold: false,
             italic: false,
             underline: false,
+            underline_style: UnderlineStyle::None,
             dim: false,
+            strikethrough: false,
+            overline: false,
+            font: crate::ansi::PRIMARY_FONT,
+            fg_logical: LogicalColor::Default,
+            bg_logical: LogicalColor::Default,
+            decoration: crate::constants::DEFAULT_FG,
+            decoration_logical: LogicalColor::Default,
+            hyperlink: NO_HYPERLINK,
+            region: NO_REGION,
+            combining: NO_COMBINING,
+            wrapped: false,
+            protected: false,
         }
     }
 
     pub fn new(cols: usize, rows: usize, config: std::sync::Arc<crate::config::TerminalConfig>) -> Self {
-        let total_cells = cols * rows;
-        let cells = vec![Self::default_cell(); total_cells];
-        let alternate_cells = vec![Self::default_cell(); total_cells];
+        let cells = ScreenBuffer::new(cols, rows, Self::default_cell());
+        let alternate_cells = ScreenBuffer::new(cols, rows, Self::default_cell());
+        let decoder = StreamDecoder::new(config.encoding, config.fallback_encoding);
+        let snapshots = match config.snapshot_interval_ms {
+            Some(interval_ms) => SnapshotRing::new(Duration::from_millis(interval_ms), config.snapshot_capacity),
+            None => SnapshotRing::default(),
+        };
+        let input_trace = InputTrace::new(config.input_trace, config.input_trace_file.clone());
+        let bell_gate = BellGate::new(config.bell_policy);
+        let clock: std::sync::Arc<dyn Clock> = std::sync::Arc::new(SystemClock);
+        let now = clock.now();
+        let activity = ActivityMonitor::new(config.silence_threshold_ms.map(Duration::from_millis), now);
         Self {
             cols,
             rows,
             cells,
             alternate_cells,
-            scrollback: Vec::new(),
+            scrollback: Box::new(MemoryScrollback::new()),
             config,
             scroll_offset: 0,
+            output_held: false,
+            selection_frozen: false,
+            unseen_rows: 0,
+            wheel_remainder: 0.0,
             col: 0,
             row: 0,
             // Alternate screen state - initially on primary
             primary_cursor: (0, 0),
+            primary_viewport: (0, 0),
             alternate_cursor: (0, 0),
-            primary_attrs: (
-                crate::constants::DEFAULT_FG,
-                crate::constants::DEFAULT_BG,
-                false, false, false, false  // bold, italic, underline, dim
-            ),
-            alternate_attrs: (
-                crate::constants::DEFAULT_FG,
-                crate::constants::DEFAULT_BG,
-                false, false, false, false  // bold, italic, underline, dim
-            ),
+            primary_attrs: SavedAttrs::default(),
+            alternate_attrs: SavedAttrs::default(),
+            sgr_stack: Vec::new(),
             fg: crate::constants::DEFAULT_FG,
             bg: crate::constants::DEFAULT_BG,
+            fg_logical: LogicalColor::Default,
+            bg_logical: LogicalColor::Default,
             bold: false,
             italic: false,
             underline: false,
+            underline_style: UnderlineStyle::None,
             dim: false,
+            strikethrough: false,
+            overline: false,
+            decoration: crate::constants::DEFAULT_FG,
+            decoration_logical: LogicalColor::Default,
+            font: crate::ansi::PRIMARY_FONT,
+            hyperlink: NO_HYPERLINK,
+            protected: false,
+            region: NO_REGION,
             selection: Selection::new(),
             cursor_visible: true,
+            cursor_blink_on: true,
+            cursor_blink_mode: None,
+            cursor_reported: None,
+            pointer: PointerState::new(),
             cursor_stack: Vec::new(),
             insert_mode: false,
             auto_wrap: true,
             bracketed_paste_mode: false,
+            color_scheme_reporting: false,
+            application_cursor_keys: false,
+            application_keypad: false,
             origin_mode: false,
+            mouse_tracking: MouseTracking::Off,
+            mouse_encoding: MouseEncoding::Default,
+            focus_reporting: false,
+            locator: Locator::new(),
+            user_keys: std::sync::Arc::new(UserKeys::new()),
+            input_trace,
+            disabled_modes: DisabledModes::default(),
 
             // ISO-2022 character set state - default to US-ASCII (B)
             g0_charset: 'B',
@@ -118,11 +390,56 @@ impl Grid {
 
             use_alternate_screen: false,
             title: String::new(),
+            pending_advance: 1,
+            last_put: None,
+            wrap_pending: false,
+            reverse_wraparound: false,
+            responses: String::new(),
+            marks: ScrollMarks::new(),
+            annotations: RowAnnotations::new(),
+            highlights: Highlights::new(),
+            images: ImageLayer::new(),
+            alternate_images: ImageLayer::new(),
+            cell_pixel_size: (crate::constants::DEFAULT_CELL_PIXEL_WIDTH, crate::constants::DEFAULT_CELL_PIXEL_HEIGHT),
+            rows_trimmed: 0,
+            scrollback_index: ScrollbackIndex::new(),
+            focused: true,
+            command_input: None,
+            commands: CommandTracker::new(),
+            finished_commands: Vec::new(),
+            activity,
+            parser_health: HealthMonitor::new(now),
+            last_command_status: None,
+            output_start: None,
+            last_output: None,
+            hyperlinks: HyperlinkTable::with_capacity(crate::constants::MAX_HYPERLINKS),
+            regions: HyperlinkTable::with_capacity(crate::constants::MAX_ACTIVE_REGIONS),
+            combining: HyperlinkTable::with_capacity(crate::constants::MAX_COMBINING_SEQUENCES),
+            generations: GenerationTracker::new(),
+            predictor: EchoPredictor::new(),
+            bells: 0,
+            bell_gate,
+            pending_bell: None,
+            clipboard_reads: ClipboardReads::new(),
+            osc_gate: OscGate::new(),
+            osc_replay: false,
+            held_hyperlink: None,
+            feed_filter: None,
+            notifications: Vec::new(),
+            custom_osc: Vec::new(),
+            current_directory: None,
+            remote_cursors: RemoteCursors::new(),
+            snapshots,
+            decoder,
+            clock,
+            clicks: ClickCounter::default(),
+            blink_epoch: now,
+            changes: ChangeLog::new(),
         }
     }
 
     // Get the active cell buffer (primary or alternate)
-    fn active_cells(&self) -> &[Cell] {
+    fn active_cells(&self) -> &ScreenBuffer {
         if self.use_alternate_screen {
             &self.alternate_cells
         } else {
@@ -130,7 +447,7 @@ impl Grid {
         }
     }
 
-    fn active_cells_mut(&mut self) -> &mut Vec<Cell> {
+    fn active_cells_mut(&mut self) -> &mut ScreenBuffer {
         if self.use_alternate_screen {
             &mut self.alternate_cells
         } else {
@@ -149,38 +466,36 @@ impl Grid {
     }
 
     pub fn clear(&mut self) {
-        self.active_cells_mut().fill(Self::default_cell());
+        self.active_cells_mut().clear();
         self.col = 0;
         self.row = 0;
+        self.wrap_pending = false;
+        self.record_scrollback_cleared();
+        self.rows_trimmed += self.scrollback_rows() as u64;
         self.scrollback.clear();
+        self.scrollback_index.clear();
         self.scroll_offset = 0;
         self.selection.clear();
+        self.marks.clear();
+        self.annotations.clear();
+        self.highlights.clear();
+        self.images.clear();
+        self.alternate_images.clear();
     }
 
     pub fn resize(&mut self, new_cols: usize, new_rows: usize) {
-        let new_total = new_cols * new_rows;
-
-        // Resize both primary and alternate buffers
-        let mut new_cells = vec![Self::default_cell(); new_total];
-        let mut new_alternate_cells = vec![Self::default_cell(); new_total];
-
-        // Copy existing content for both buffers
-        for r in 0..self.rows.min(new_rows) {
-            for c in 0..self.cols.min(new_cols) {
-                let old_idx = r * self.cols + c;
-                let new_idx = r * new_cols + c;
-                new_cells[new_idx] = self.cells[old_idx];
-                new_alternate_cells[new_idx] = self.alternate_cells[old_idx];
-            }
-        }
+        self.wrap_pending = false;
 
-        self.cells = new_cells;
-        self.alternate_cells = new_alternate_cells;
+        // Resize both primary and alternate buffers, copying only rows with content
+        self.cells.resize(new_cols, new_rows);
+        self.alternate_cells.resize(new_cols, new_rows);
         self.cols = new_cols;
         self.rows = new_rows;
         self.col = self.col.min(new_cols.saturating_sub(1));
         self.row = self.row.min(new_rows.saturating_sub(1));
         self.selection.clear();
+        #[cfg(feature = "debug-invariants")]
+        self.assert_invariants("resize");
     }
 
     /// Resize with line rewrapping (like vte4)
@@ -191,33 +506,23 @@ impl Grid {
             return;
         }
 
+        self.wrap_pending = false;
+        self.changes.record(GridChange::ScrollbackRewritten);
+
         // Resize active buffer with rewrapping
         let (new_active_cells, new_cursor_pos) = self.resize_buffer_with_rewrap(
-            self.active_cells().to_vec(),
+            self.active_cells(),
             new_cols,
             new_rows,
         );
 
-        // Resize alternate buffer without rewrapping (maintain as-is)
-        let new_total_alt = new_cols * new_rows;
-        let mut new_alt_cells = vec![Self::default_cell(); new_total_alt];
-
-        // Copy existing alternate content (simple resize, no rewrap)
-        for r in 0..self.rows.min(new_rows) {
-            for c in 0..self.cols.min(new_cols) {
-                let old_idx = r * self.cols + c;
-                let new_idx = r * new_cols + c;
-                if old_idx < self.alternate_cells.len() {
-                    new_alt_cells[new_idx] = self.alternate_cells[old_idx];
-                }
-            }
-        }
-
-        // Update buffers
+        // Resize the inactive buffer without rewrapping (maintain as-is)
         if self.use_alternate_screen {
             self.alternate_cells = new_active_cells;
+            self.cells.resize(new_cols, new_rows);
         } else {
             self.cells = new_active_cells;
+            self.alternate_cells.resize(new_cols, new_rows);
         }
 
         let old_cols = self.cols;
@@ -237,18 +542,21 @@ impl Grid {
         }
 
         self.selection.clear();
+        #[cfg(feature = "debug-invariants")]
+        self.assert_invariants("resize");
     }
 
     /// Resize a specific buffer with rewrapping logic
-    fn resize_buffer_with_rewrap(&self, old_cells: Vec<Cell>, new_cols: usize, new_rows: usize)
-        -> (Vec<Cell>, (usize, usize)) {
+    fn resize_buffer_with_rewrap(&self, old_cells: &ScreenBuffer, new_cols: usize, new_rows: usize)
+        -> (ScreenBuffer, (usize, usize)) {
 
+        let mut new_cells = ScreenBuffer::new(new_cols, new_rows, Self::default_cell());
         if self.cols == 0 {
-            return (vec![Self::default_cell(); new_cols * new_rows], (0, 0));
+            return (new_cells, (0, 0));
         }
 
         // Extract logical lines (merge wrapped lines)
-        let logical_lines = self.extract_logical_lines_from_buffer(&old_cells);
+        let logical_lines = self.extract_logical_lines_from_buffer(old_cells);
 
         // Rewrap logical lines to new column width
         let mut rewrapped_lines = Vec::new();
@@ -303,38 +611,28 @@ impl Grid {
             );
         }
 
-        // Pad remaining rows with default cells
-        while rewrapped_lines.len() < new_rows {
-            rewrapped_lines.push(vec![Self::default_cell(); new_cols]);
-        }
-
-        // Flatten rows into flat cell array
-        let mut new_cells = Vec::with_capacity(new_cols * new_rows);
-        for row in rewrapped_lines {
-            new_cells.extend(row);
+        // Rows past the rewrapped lines stay blank
+        for (row, cells) in rewrapped_lines.into_iter().enumerate() {
+            new_cells.set_row(row, cells);
         }
 
         (new_cells, cursor_pos)
     }
 
     /// Extract logical lines from a buffer (merge hard-wrapped lines)
-    fn extract_logical_lines_from_buffer(&self, buffer: &[Cell]) -> Vec<Vec<Cell>> {
+    fn extract_logical_lines_from_buffer(&self, buffer: &ScreenBuffer) -> Vec<Vec<Cell>> {
         let mut logical_lines = Vec::new();
 
         for row in 0..self.rows {
-            let row_start = row * self.cols;
-
             // Check if this row exists in buffer
-            if (row_start + self.cols) > buffer.len() {
+            let Some(row_slice) = buffer.get_row(row) else {
                 break;
-            }
-
-            let row_slice = &buffer[row_start..row_start + self.cols];
+            };
 
             // Find the actual content in this row (cells with non-null characters)
             let mut line_cells = Vec::new();
             for cell in row_slice {
-            if cell.ch != '\0' {
+            if cell.ch != '\0' || cell.spacer {
                 line_cells.push(cell.clone());
             } else {
                 break; // Stop at first null (line terminator)
@@ -355,8 +653,18 @@ impl Grid {
         let mut wrapped = Vec::new();
         let mut current_row = Vec::new();
 
+        // Too narrow for a wide character: keep its left half as a narrow one
+        let fits_wide = new_cols >= 2;
         for &cell in line {
-            current_row.push(cell);
+            if cell.spacer && !fits_wide {
+                continue;
+            }
+            // A wide character never straddles the right margin
+            if cell.wide && fits_wide && current_row.len() + 1 == new_cols {
+                current_row.push(Self::default_cell());
+                wrapped.push(std::mem::take(&mut current_row));
+            }
+            current_row.push(Cell { wrapped: false, wide: cell.wide && fits_wide, ..cell });
 
             if current_row.len() >= new_cols {
                 wrapped.push(current_row.clone());
@@ -372,68 +680,467 @@ impl Grid {
             wrapped.push(current_row);
         }
 
+        // Every row but the last continues on the next
+        let continued = wrapped.len().saturating_sub(1);
+        for row in &mut wrapped[..continued] {
+            if let Some(last) = row.last_mut() {
+                last.wrapped = true;
+            }
+        }
+
         wrapped
     }
 
     // Selection delegation
     pub fn clear_selection(&mut self) {
         self.selection.clear();
+        self.selection_frozen = false;
     }
 
+    /// Press at a cell; with `TerminalConfig::freeze_on_select` the viewport
+    /// stays on its rows until `complete_selection`
     pub fn start_selection(&mut self, row: usize, col: usize) {
-        self.selection.start(row, col, Instant::now());
+        let col = self.char_start(row, col);
+        self.selection.start(row, col, self.clock.now());
+        self.selection_frozen = self.config.freeze_on_select && !self.use_alternate_screen;
+    }
+
+    /// Press at a cell and logical pixel (`x`, `y`), for backends that know
+    /// the pointer position; moves shorter than
+    /// `TerminalConfig::drag_threshold_px` then do not start a drag
+    pub fn start_selection_at(&mut self, row: usize, col: usize, x: f64, y: f64) {
+        self.start_selection(row, col);
+        let col = self.char_start(row, col);
+        self.selection.start_at(row, col, (x, y), self.clock.now());
     }
 
     pub fn update_selection(&mut self, row: usize, col: usize) {
+        let col = self.char_start(row, col);
         self.selection.update(row, col);
     }
 
+    /// Pointer motion to a cell and logical pixel (`x`, `y`) while pressed
+    pub fn update_selection_at(&mut self, row: usize, col: usize, x: f64, y: f64) {
+        let col = self.char_start(row, col);
+        self.selection.drag(row, col, (x, y), self.config.drag_threshold_px);
+    }
+
+    /// Release at a cell, ending a selection freeze; output below counts
+    /// as unseen until the viewport returns to the bottom. A release within
+    /// `TerminalConfig::click_timeout_ms` of an undragged press is a click.
     pub fn complete_selection(&mut self, row: usize, col: usize) -> bool {
-        self.selection.complete(row, col, Instant::now())
+        self.selection_frozen = false;
+        let timeout = Duration::from_millis(self.config.click_timeout_ms as u64);
+        let col = self.char_start(row, col);
+        self.selection.complete(row, col, self.clock.now(), timeout)
+    }
+
+    /// End a press or drag that will see no release (the backend's gesture
+    /// was cancelled), ending a selection freeze; a drag keeps the cells it
+    /// covered so far
+    pub fn cancel_selection(&mut self) {
+        self.selection_frozen = false;
+        self.selection.cancel();
+    }
+
+    /// Whether a selection in progress pins the viewport (see
+    /// `TerminalConfig::freeze_on_select`)
+    pub fn is_selection_frozen(&self) -> bool {
+        self.selection_frozen
     }
 
+    /// Advance the cursor blink phase; called by the backend's blink timer
     pub fn toggle_cursor(&mut self) {
-        self.cursor_visible = !self.cursor_visible;
+        self.cursor_blink_on = !self.cursor_blink_on;
+    }
+
+    /// Set the cursor blink phase from the clock; called by the backend's
+    /// blink timer. Returns whether the phase changed.
+    ///
+    /// The cursor is on for the first `cursor_blink_interval_ms` of each
+    /// cycle, counted from the last time blinking restarted.
+    pub fn tick_cursor_blink(&mut self) -> bool {
+        let interval = self.config.cursor_blink_interval_ms.max(1) as u128;
+        let elapsed = self.clock.now().saturating_duration_since(self.blink_epoch).as_millis();
+        let on = (elapsed / interval) % 2 == 0;
+        std::mem::replace(&mut self.cursor_blink_on, on) != on
+    }
+
+    /// Start the blink cycle over with the cursor on
+    fn restart_cursor_blink(&mut self) {
+        self.cursor_blink_on = true;
+        self.blink_epoch = self.clock.now();
+    }
+
+    /// Count a click at a cell: 1, 2 (double click) or 3 (triple click)
+    ///
+    /// Clicks on the same cell within `TerminalConfig::click_timeout_ms` of
+    /// each other continue the sequence.
+    pub fn register_click(&mut self, row: usize, col: usize) -> u32 {
+        let timeout = Duration::from_millis(self.config.click_timeout_ms as u64);
+        self.clicks.click((row, col), self.clock.now(), timeout)
+    }
+
+    /// Drop the sequence classes in `filter` from everything parsed from now on
+    ///
+    /// For screen-only embedders feeding untrusted logs; `None` lets
+    /// everything through again (see the `feed_filter` module).
+    pub fn set_feed_filter(&mut self, filter: Option<FilterPolicy>) {
+        self.feed_filter = filter;
+    }
+
+    pub fn feed_filter(&self) -> Option<&FilterPolicy> {
+        self.feed_filter.as_ref()
+    }
+
+    /// Whether the feed filter drops input mode changes (mouse, focus, paste)
+    fn input_modes_filtered(&self) -> bool {
+        self.feed_filter.as_ref().is_some_and(|filter| filter.input_modes)
+    }
+
+    /// The current time according to the grid's clock
+    pub(crate) fn now(&self) -> std::time::Instant {
+        self.clock.now()
+    }
+
+    /// Read the time from `clock` from now on, e.g. a `MockClock` in tests
+    pub fn set_clock(&mut self, clock: std::sync::Arc<dyn Clock>) {
+        self.clock = clock;
+        self.blink_epoch = self.clock.now();
+        self.activity.output(self.blink_epoch);
     }
 
+    /// Whether the cursor should be drawn right now (DECTCEM and blink phase)
     pub fn is_cursor_visible(&self) -> bool {
-        self.cursor_visible
+        self.cursor_visible && (self.cursor_blink_on || !self.cursor_blinks())
+    }
+
+    /// The cursor's viewport row and column, counting a scrolled-back
+    /// viewport's offset; `None` while the cursor is scrolled out of view
+    pub fn cursor_viewport_cell(&self) -> Option<(usize, usize)> {
+        let row = self.row + self.scroll_offset.min(self.scrollback_rows());
+        (row < self.rows).then(|| (row, self.col.min(self.cols.saturating_sub(1))))
+    }
+
+    /// The cursor's viewport cell if it changed since the last call, for
+    /// `TerminalEvent::CursorMoved`
+    ///
+    /// Output, scrolling the viewport and resizing all count; `None` while
+    /// the cursor is scrolled out of view.
+    pub fn take_cursor_move(&mut self) -> Option<(usize, usize)> {
+        let cell = self.cursor_viewport_cell();
+        if std::mem::replace(&mut self.cursor_reported, cell) == cell {
+            return None;
+        }
+        cell
+    }
+
+    /// Where the cursor is in the viewport, with its cell in pixels laid out
+    /// by `geometry`; `None` while the viewport is scrolled back past it
+    pub fn cursor_screen_position(&self, geometry: &CellGeometry) -> Option<CursorPosition> {
+        let (row, col) = self.cursor_viewport_cell()?;
+        let width = if self.is_wide_cell(self.row, col) { 2 } else { 1 };
+        let (x, y) = (geometry.col_x(col), geometry.row_y(row));
+        Some(CursorPosition { row, col, rect: (x, y, geometry.col_x(col + width) - x, geometry.row_y(row + 1) - y) })
+    }
+
+    /// Whether the cursor blinks: the application's DECSET 12 request if
+    /// one is active, otherwise `TerminalConfig::enable_cursor_blink`
+    pub fn cursor_blinks(&self) -> bool {
+        self.cursor_blink_mode.unwrap_or(self.config.enable_cursor_blink)
+    }
+
+    /// Mouse pointer shape to show over the terminal
+    pub fn pointer_shape(&self) -> PointerShape {
+        self.pointer.shape()
+    }
+
+    /// Hide the pointer for a key press, if configured; returns true if the shape changed
+    pub fn pointer_key_typed(&mut self) -> bool {
+        self.config.hide_pointer_while_typing && self.pointer.key_typed()
+    }
+
+    /// Show the pointer after motion over a cell; returns true if the shape changed
+    pub fn pointer_moved(&mut self, row: usize, col: usize) -> bool {
+        let over_link = self.url_at(row, col).is_some() || self.active_region_at(row, col).is_some();
+        self.pointer.moved(over_link)
+    }
+
+    /// Record pointer motion to logical point (`x`, `y`) for DEC locator
+    /// position requests (see `locator`)
+    pub fn locator_moved(&mut self, x: f64, y: f64, geometry: &CellGeometry) {
+        self.locator.moved(x, y, geometry);
+    }
+
+    /// Record that the pointer left the terminal; locator requests then
+    /// report the position as unavailable
+    pub fn locator_left(&mut self) {
+        self.locator.left();
+    }
+
+    /// Feed a button press or release at logical point (`x`, `y`) to the DEC
+    /// locator; returns the report to send if the application asked for it
+    ///
+    /// The report is not queued with the parser's responses, so the caller
+    /// sends exactly this event's report.
+    pub fn locator_button(&mut self, button: LocatorButton, pressed: bool, x: f64, y: f64, geometry: &CellGeometry) -> Option<String> {
+        self.locator.button(button, pressed, x, y, geometry)
+    }
+
+    /// Whether the application enabled DEC locator reports (DECELR), e.g. to
+    /// leave button presses to it rather than start a selection
+    pub fn locator_enabled(&self) -> bool {
+        self.locator.is_enabled()
+    }
+
+    /// Payload of the active region (OSC 7777) at the given screen cell, if any
+    pub fn active_region_at(&self, row: usize, col: usize) -> Option<&str> {
+        if row >= self.rows || col >= self.cols {
+            return None;
+        }
+        self.regions.uri(self.get_cell(row, col).region)
+    }
+
+    /// Input to send for a click at the given screen cell, if it is inside an
+    /// active region
+    pub fn click_active_region(&self, row: usize, col: usize) -> Option<String> {
+        self.active_region_at(row, col).map(crate::regions::click_report)
+    }
+
+    /// OSC 8 hyperlink target of the given screen cell, if any
+    pub fn hyperlink_at(&self, row: usize, col: usize) -> Option<&str> {
+        if row >= self.rows || col >= self.cols {
+            return None;
+        }
+        self.hyperlinks.uri(self.get_cell(row, col).hyperlink)
+    }
+
+    /// Combining marks and variation selectors stored with `cell`'s
+    /// character, empty for none
+    pub fn combining_marks(&self, cell: &Cell) -> &str {
+        self.combining.uri(cell.combining).unwrap_or("")
+    }
+
+    /// Ids `id` reads from the cells of both screens, the scrollback and the
+    /// time-travel snapshots
+    fn referenced_ids(&self, id: impl Fn(&Cell) -> u32) -> std::collections::HashSet<u32> {
+        let mut ids = std::collections::HashSet::new();
+        for row in self.cells.iter_rows().chain(self.alternate_cells.iter_rows()) {
+            ids.extend(row.iter().map(&id));
+        }
+        for index in 0..self.scrollback.len() {
+            if let Some(row) = self.scrollback.get_row(index) {
+                ids.extend(row.iter().map(&id));
+            }
+        }
+        for snapshot in self.snapshots.iter() {
+            ids.extend(snapshot.cells.iter().map(&id));
+        }
+        ids
+    }
+
+    /// Link at the given cell: an OSC 8 hyperlink, or a URL or link rule match in the screen text
+    pub fn url_at(&self, row: usize, col: usize) -> Option<String> {
+        self.link_at(row, col).map(|link| link.uri)
+    }
+
+    /// Link at the given cell with the run of cells it covers on that row:
+    /// an OSC 8 hyperlink, a URL, or text matching one of the link rules
+    pub fn link_at(&self, row: usize, col: usize) -> Option<HoveredLink> {
+        if row >= self.rows || col >= self.cols {
+            return None;
+        }
+        let line = self.active_cells().row(row);
+
+        if let Some(uri) = self.hyperlink_at(row, col) {
+            let id = line[col].hyperlink;
+            let first = line[..col].iter().rposition(|cell| cell.hyperlink != id).map_or(0, |i| i + 1);
+            let end = line[col..].iter().position(|cell| cell.hyperlink != id).map_or(self.cols, |i| col + i);
+            return Some(HoveredLink { uri: uri.to_string(), row, cols: first..end });
+        }
+        let chars: Vec<char> = line.iter().map(|cell| if cell.ch == '\0' { ' ' } else { cell.ch }).collect();
+        if let Some(cols) = find_url_at(&chars, col) {
+            return Some(HoveredLink { uri: chars[cols.clone()].iter().collect(), row, cols });
+        }
+        find_rule_link_at(&self.config.link_rules, &chars, col).map(|(cols, uri)| HoveredLink { uri, row, cols })
+    }
+
+    /// Whether the application enabled bracketed paste (DECSET 2004) and it
+    /// is not forced off
+    pub fn is_bracketed_paste_mode(&self) -> bool {
+        self.bracketed_paste_mode && !self.disabled_modes.bracketed_paste
+    }
+
+    /// Snapshot of the modes in effect; forced-off modes read as off
+    pub fn modes(&self) -> Modes {
+        let disabled = self.disabled_modes;
+        Modes {
+            mouse_tracking: self.mouse_tracking,
+            mouse_encoding: self.mouse_encoding,
+            focus_reporting: self.focus_reporting,
+            bracketed_paste: self.is_bracketed_paste_mode(),
+            alternate_screen: self.use_alternate_screen,
+            application_cursor_keys: self.application_cursor_keys,
+            application_keypad: self.application_keypad,
+            insert: self.insert_mode,
+            auto_wrap: self.auto_wrap,
+            origin: self.origin_mode,
+            cursor_visible: self.cursor_visible,
+            disabled,
+        }
+    }
+
+    /// Force `mode` off whatever the application requests, or stop doing so
+    ///
+    /// Disabling the alternate screen while it is shown switches back to the
+    /// primary screen; re-enabling it takes effect with the application's
+    /// next request.
+    pub fn set_mode_disabled(&mut self, mode: ForcibleMode, disabled: bool) {
+        self.disabled_modes.set(mode, disabled);
+        if mode == ForcibleMode::AlternateScreen && disabled && self.use_alternate_screen {
+            Grid::use_alternate_screen(self, false);
+        }
+    }
+
+    /// Whether the embedder forced `mode` off
+    pub fn is_mode_disabled(&self, mode: ForcibleMode) -> bool {
+        self.disabled_modes.contains(mode)
+    }
+
+    /// Encoder for special keys under the current DECCKM / keypad modes and
+    /// DECUDK definitions
+    pub fn key_encoder(&self) -> KeyEncoder {
+        KeyEncoder::new(self.application_cursor_keys, self.application_keypad)
+            .with_user_keys(std::sync::Arc::clone(&self.user_keys))
+    }
+
+    /// Bytes to send for `key` under the current modes, recorded in the
+    /// input trace while it is enabled
+    pub fn encode_key(&mut self, key: Key) -> Option<Vec<u8>> {
+        let bytes = self.key_encoder().encode_key(key);
+        if self.input_trace.is_enabled() {
+            let entry = InputTraceEntry {
+                input: TracedInput::Key(key),
+                source: TextSource::Key,
+                bytes: bytes.clone(),
+                modes: self.modes(),
+                user_keys: self.user_keys.len(),
+            };
+            self.input_trace.record(entry);
+        }
+        bytes
+    }
+
+    /// Record `text` from `source` in the input trace while it is enabled,
+    /// with the `bytes` it is written to the PTY as (see `encode_text`)
+    pub fn trace_text(&mut self, text: &str, source: TextSource, bytes: &[u8]) {
+        if !self.input_trace.is_enabled() {
+            return;
+        }
+        let entry = InputTraceEntry {
+            input: TracedInput::Text(text.to_string()),
+            source,
+            bytes: Some(bytes.to_vec()),
+            modes: self.modes(),
+            user_keys: self.user_keys.len(),
+        };
+        self.input_trace.record(entry);
+    }
+
+    /// Key events recorded while tracing
+    pub fn input_trace(&self) -> &InputTrace {
+        &self.input_trace
+    }
+
+    /// Start or stop tracing key input (see `input_trace`)
+    pub fn set_input_tracing(&mut self, enabled: bool) {
+        self.input_trace.set_enabled(enabled);
+    }
+
+    pub fn clear_input_trace(&mut self) {
+        self.input_trace.clear();
+    }
+
+    /// Function keys programmed by the application (DECUDK)
+    pub fn user_keys(&self) -> &UserKeys {
+        &self.user_keys
+    }
+
+    /// Remove the application's function key definitions and unlock them
+    pub fn reset_user_keys(&mut self) {
+        std::sync::Arc::make_mut(&mut self.user_keys).reset();
+    }
+
+    /// Whether the text of screen row `row` soft-wrapped onto the next row
+    pub fn is_row_wrapped(&self, row: usize) -> bool {
+        row < self.rows && self.cols > 0 && self.get_cell(row, self.cols - 1).wrapped
     }
 
     /// Select word at the given position using Unicode word boundaries
+    ///
+    /// Words continue across soft wraps, so a long path wrapped over several
+    /// rows is selected whole.
     pub fn select_word(&mut self, row: usize, col: usize) {
-        // Get the text content of the row
-        let row_text = self.get_row_text(row);
-        if row_text.is_empty() {
+        if row >= self.rows {
             return;
         }
 
+        // Assemble the logical line around `row` from the rows wrapped into it
+        let mut first_row = row;
+        while first_row > 0 && self.is_row_wrapped(first_row - 1) {
+            first_row -= 1;
+        }
+        let mut chars: Vec<(usize, usize, char)> = Vec::new();
+        let mut index = None;
+        let col = self.char_start(row, col);
+        for r in first_row..self.rows {
+            let wrapped = self.is_row_wrapped(r);
+            for (c, cell) in self.active_cells().row(r).iter().enumerate() {
+                if cell.spacer {
+                    continue;
+                }
+                if cell.ch == '\0' {
+                    break;
+                }
+                if (r, c) == (row, col) {
+                    index = Some(chars.len());
+                }
+                chars.push((r, c, cell.ch));
+            }
+            if !wrapped {
+                break;
+            }
+        }
+
         // Find word boundaries around the cursor position
         // For simplicity, treat alphanumeric sequences as words, separated by spaces/punctuation
-        let chars: Vec<char> = row_text.chars().collect();
-        if col >= chars.len() {
+        let Some(index) = index else {
             return;
-        }
+        };
+        let is_word = |i: usize| chars[i].2.is_alphanumeric();
 
         // Find word start (work backwards from cursor)
-        let mut word_start = col;
-        while word_start > 0 && chars[word_start - 1].is_alphanumeric() {
+        let mut word_start = index;
+        while word_start > 0 && is_word(word_start - 1) {
             word_start -= 1;
         }
 
         // Find word end (work forwards from cursor)
-        let mut word_end = col;
-        while word_end < chars.len() - 1 && chars[word_end + 1].is_alphanumeric() {
+        let mut word_end = index;
+        while word_end < chars.len() - 1 && is_word(word_end + 1) {
             word_end += 1;
         }
 
         // If single char, ensure it's at least a valid position
-        if word_start == word_end && !chars[word_start].is_alphanumeric() {
+        if word_start == word_end && !is_word(word_start) {
             return; // Not a valid word position
         }
 
         // Create selection directly
-        self.selection.create_selection(row, word_start, row, word_end);
+        let (start_row, start_col, _) = chars[word_start];
+        let (end_row, end_col, _) = chars[word_end];
+        let end_col = self.wide_partner(end_row, end_col).map_or(end_col, |other| other.max(end_col));
+        self.selection.create_selection(start_row, start_col, end_row, end_col);
     }
 
     /// Get normalized selection bounds
@@ -468,18 +1175,28 @@ impl Grid {
             return;
         }
 
+        // A wide character at the end is selected with its right half
+        if self.is_wide_cell(row, end_col) {
+            end_col += 1;
+        }
+
         // Create selection directly
         self.selection.create_selection(row, start_col, row, end_col);
     }
 
     /// Get text content of a specific row as a string
+    #[cfg(test)]
     fn get_row_text(&self, row: usize) -> String {
         let mut text = String::new();
 
         for col in 0..self.cols {
             let cell = self.get_cell(row, col);
+            if cell.spacer {
+                continue;
+            }
             if cell.ch != '\0' {
                 text.push(cell.ch);
+                text.push_str(self.combining_marks(cell));
             } else {
                 break; // Stop at first null (line terminator)
             }
@@ -504,8 +1221,24 @@ impl Grid {
         self.selection.has_selection()
     }
 
+    /// Whether the cell is selected; both halves of a wide character are
+    /// selected if either is
     pub fn is_selected(&self, row: usize, col: usize) -> bool {
         self.selection.is_position_selected(row, col)
+            || self.wide_partner(row, col).is_some_and(|other| self.selection.is_position_selected(row, other))
+    }
+
+    /// Selection highlight spans on the screen, for an overlay pass
+    pub fn selection_spans(&self) -> Vec<SelectionSpan> {
+        let mut spans = self.selection.spans(self.rows, self.cols);
+        // Wide characters are highlighted whole
+        for span in &mut spans {
+            span.start_col = self.char_start(span.row, span.start_col);
+            if let Some(other) = self.wide_partner(span.row, span.end_col - 1).filter(|&other| other >= span.end_col) {
+                span.end_col = other + 1;
+            }
+        }
+        spans
     }
 
     pub fn get_selected_text(&self) -> String {
@@ -513,38 +1246,68 @@ impl Grid {
             return String::new();
         };
 
-        let total_rows = self.scrollback.len() / self.cols + self.rows;
+        let scrollback_rows = self.scrollback_rows();
+        let total_rows = scrollback_rows + self.rows;
 
         if start_row >= total_rows || end_row >= total_rows {
             return String::new();
         }
 
+        let mode = self.config.copy_hyperlinks;
         let mut result = String::new();
 
         for row in start_row..=end_row {
-            let line = if row < self.scrollback.len() / self.cols {
+            let line = if row < scrollback_rows {
                 // Scrollback row (always from primary)
-                let start_idx = row * self.cols;
-                let end_idx = start_idx + self.cols;
-                &self.scrollback[start_idx..end_idx]
+                match self.scrollback.get_row(row) {
+                    Some(line) => line,
+                    None => continue,
+                }
             } else {
                 // Grid row (from active buffer)
-                let grid_row = row - self.scrollback.len() / self.cols;
+                let grid_row = row - scrollback_rows;
                 if grid_row < self.rows {
-                    let start_idx = grid_row * self.cols;
-                    let end_idx = start_idx + self.cols;
-                    &self.active_cells()[start_idx..end_idx]
+                    std::borrow::Cow::Borrowed(self.active_cells().row(grid_row))
                 } else {
                     continue;
                 }
             };
 
-            let start_c = if row == start_row { start_col.min(self.cols.saturating_sub(1)) } else { 0 };
+            let mut start_c = if row == start_row { start_col.min(self.cols.saturating_sub(1)) } else { 0 };
+            // Starting on the right half of a wide character copies all of it
+            if start_c > 0 && line.get(start_c).is_some_and(|cell| cell.spacer) {
+                start_c -= 1;
+            }
             let end_c = if row == end_row { end_col.min(self.cols.saturating_sub(1)) } else { self.cols.saturating_sub(1) };
 
+            // Runs of hyperlinked cells are copied as the configured mode asks
+            let mut link: Option<(&str, String)> = None;
             for col in start_c..=end_c {
-                let ch = line.get(col).map_or(' ', |cell| if cell.ch == '\0' { ' ' } else { cell.ch });
-                result.push(ch);
+                let cell = line.get(col);
+                if cell.is_some_and(|cell| cell.spacer) {
+                    continue;
+                }
+                let ch = cell.map_or(' ', |cell| if cell.ch == '\0' { ' ' } else { cell.ch });
+                let marks = cell.map_or("", |cell| self.combining_marks(cell));
+                let uri = match mode {
+                    HyperlinkCopyMode::TextOnly => None,
+                    _ => cell.and_then(|cell| self.hyperlinks.uri(cell.hyperlink)),
+                };
+                if link.as_ref().map(|(current, _)| *current) != uri {
+                    if let Some((current, text)) = link.take() {
+                        result.push_str(&mode.format(&text, current));
+                    }
+                    link = uri.map(|uri| (uri, String::new()));
+                }
+                let text = match link.as_mut() {
+                    Some((_, text)) => text,
+                    None => &mut result,
+                };
+                text.push(ch);
+                text.push_str(marks);
+            }
+            if let Some((current, text)) = link {
+                result.push_str(&mode.format(&text, current));
             }
 
             if row < end_row {
@@ -579,172 +1342,1394 @@ impl Grid {
 
         // Apply charset translation
         match charset {
-            '0' => self.dec_special_graphics(ch),  // DEC Special Graphics
+            '0' => Self::dec_special_graphics(ch), // DEC Special Graphics
+            'A' if ch == '#' => '\u{00A3}',         // UK: pound sign
             _ => ch, // All other charsets: return unchanged for now
         }
     }
 
     /// DEC Special Graphics character mapping
-    /// Converts ASCII characters to box-drawing and symbol equivalents
-    fn dec_special_graphics(&self, ch: char) -> char {
+    /// Converts ASCII characters to box-drawing and symbol equivalents, as
+    /// in the VT100's table (0x5F-0x7E)
+    fn dec_special_graphics(ch: char) -> char {
         match ch {
-            'j' => '\u{256D}', // Box drawing light arc down and right
-            'k' => '\u{256E}', // Box drawing light arc down and left
-            'l' => '\u{256F}', // Box drawing light arc up and right
-            'm' => '\u{2570}', // Box drawing light arc up and left
+            '_' => ' ',        // Blank
+            '`' => '\u{25C6}', // Diamond
+            'a' => '\u{2592}', // Checkerboard
+            'b' => '\u{2409}', // HT symbol
+            'c' => '\u{240C}', // FF symbol
+            'd' => '\u{240D}', // CR symbol
+            'e' => '\u{240A}', // LF symbol
+            'f' => '\u{00B0}', // Degree sign
+            'g' => '\u{00B1}', // Plus/minus
+            'h' => '\u{2424}', // NL symbol
+            'i' => '\u{240B}', // VT symbol
+            'j' => '\u{2518}', // Box drawing light up and left
+            'k' => '\u{2510}', // Box drawing light down and left
+            'l' => '\u{250C}', // Box drawing light down and right
+            'm' => '\u{2514}', // Box drawing light up and right
             'n' => '\u{253C}', // Box drawing light vertical and horizontal
-            'o' => '\u{23BA}', // Horizontal line (scan line)
-            'p' => '\u{23BB}', // Horizontal line with scan lines
-            'q' => '\u{2500}', // Box drawing light horizontal
-            'r' => '\u{2501}', // Box drawing heavy horizontal
-            's' => '\u{2502}', // Box drawing light vertical
-            't' => '\u{2503}', // Box drawing heavy vertical
-            'u' => '\u{250C}', // Box drawing light down and right
-            'v' => '\u{2510}', // Box drawing light down and left
-            'w' => '\u{2514}', // Box drawing light up and right
-            'x' => '\u{2518}', // Box drawing light up and left
-            'y' => '\u{251C}', // Box drawing light vertical and right
-            'z' => '\u{2524}', // Box drawing light vertical and left
-            '{' => '\u{252C}', // Box drawing light down and horizontal
-            '|' => '\u{2534}', // Box drawing light up and horizontal
-            '}' => '\u{253C}', // Box drawing light vertical and horizontal
+            'o' => '\u{23BA}', // Horizontal scan line 1
+            'p' => '\u{23BB}', // Horizontal scan line 3
+            'q' => '\u{2500}', // Box drawing light horizontal (scan line 5)
+            'r' => '\u{23BC}', // Horizontal scan line 7
+            's' => '\u{23BD}', // Horizontal scan line 9
+            't' => '\u{251C}', // Box drawing light vertical and right
+            'u' => '\u{2524}', // Box drawing light vertical and left
+            'v' => '\u{2534}', // Box drawing light up and horizontal
+            'w' => '\u{252C}', // Box drawing light down and horizontal
+            'x' => '\u{2502}', // Box drawing light vertical
+            'y' => '\u{2264}', // Less-than or equal to
+            'z' => '\u{2265}', // Greater-than or equal to
+            '{' => '\u{03C0}', // Pi
+            '|' => '\u{2260}', // Not equal to
+            '}' => '\u{00A3}', // Pound sign
             '~' => '\u{00B7}', // Middle dot
             _ => ch, // Return original char if no mapping
         }
     }
 
-    /// Enable or disable alternate screen buffer
-    /// When enabled, switches to the alternate buffer and saves state
-    /// When disabled, switches back to primary buffer and restores state
-    pub fn use_alternate_screen(&mut self, enable: bool) {
-        if self.use_alternate_screen == enable {
-            return; // No change needed
+    /// Write an informational line (e.g. "[parser recovered]") at the cursor
+    ///
+    /// The notice starts on a fresh line, uses default attributes and leaves
+    /// the cursor at the start of the following line.
+    pub fn write_notice(&mut self, text: &str) {
+        let attrs = self.save_attrs();
+        self.reset_attrs();
+        if self.col > 0 {
+            self.newline();
         }
-
-        if enable {
-            // Switch TO alternate screen - save primary state
-            self.primary_cursor = (self.row, self.col);
-            self.primary_attrs = (
-                self.fg, self.bg,
-                self.bold, self.italic, self.underline, self.dim
-            );
-            // Switch to alternate state
-            self.use_alternate_screen = true;
-            (self.row, self.col) = self.alternate_cursor;
-            (self.fg, self.bg, self.bold, self.italic, self.underline, self.dim) = self.alternate_attrs;
-        } else {
-            // Switch FROM alternate screen - save alternate state
-            self.alternate_cursor = (self.row, self.col);
-            self.alternate_attrs = (
-                self.fg, self.bg,
-                self.bold, self.italic, self.underline, self.dim
-            );
-            // Switch to primary state
-            self.use_alternate_screen = false;
-            (self.row, self.col) = self.primary_cursor;
-            (self.fg, self.bg, self.bold, self.italic, self.underline, self.dim) = self.primary_attrs;
+        for ch in text.chars() {
+            self.put(ch);
+            self.advance();
         }
+        self.newline();
+        self.restore_attrs(attrs);
     }
-}
 
-impl AnsiGrid for Grid {
-    fn put(&mut self, ch: char) {
-        if self.col < self.cols && self.row < self.rows {
-            if self.insert_mode {
-                self.insert_chars(1);
+    /// Number of rows currently held in the scrollback
+    pub fn scrollback_rows(&self) -> usize {
+        self.scrollback.len()
+    }
+
+    /// Scrollback row `index`, 0 being the oldest, at the width it had when
+    /// it scrolled off
+    pub fn scrollback_row(&self, index: usize) -> Option<std::borrow::Cow<'_, [Cell]>> {
+        self.scrollback.get_row(index)
+    }
+
+    /// Keep the scrollback in `store` from now on, e.g. a compressed or
+    /// disk-backed one (see `scrollback`)
+    ///
+    /// Rows already in the scrollback are appended to `store` first.
+    pub fn set_scrollback_store(&mut self, mut store: Box<dyn ScrollbackStore>) {
+        for index in 0..self.scrollback.len() {
+            if let Some(row) = self.scrollback.get_row(index) {
+                store.append_row(&row);
             }
+        }
+        self.scrollback = store;
+    }
 
-            // Apply character set translation
-            let translated_ch = self.translate_char(ch);
+    /// Heap bytes held by the scrollback store
+    pub fn scrollback_bytes(&self) -> usize {
+        self.scrollback.memory_bytes()
+    }
 
-            // Store attributes
-            let fg = self.fg;
-            let bg = self.bg;
-            let bold = self.bold;
-            let italic = self.italic;
-            let underline = self.underline;
-            let dim = self.dim;
+    /// Release spare scrollback capacity
+    pub fn shrink_scrollback(&mut self) {
+        self.scrollback.shrink_to_fit();
+    }
 
-            let cell = self.get_cell_mut(self.row, self.col);
-            *cell = Cell {
-                ch: translated_ch,
-                fg,
-                bg,
-                bold,
-                italic,
-                underline,
-                dim,
-            };
+    /// Append a row to the scrollback, dropping the oldest row past the limit
+    fn push_scrollback_row(&mut self, row: Vec<Cell>) {
+        self.scrollback_index.push_row(row.iter().filter(|c| !c.spacer).map(|c| if c.ch == '\0' { ' ' } else { c.ch }));
+        self.scrollback.append_row(&row);
+        self.changes.record(GridChange::ScrollbackAppended { rows: 1 });
+
+        if self.scrollback.len() > crate::constants::SCROLLBACK_LIMIT {
+            self.scrollback.trim(1);
+            self.scrollback_index.trim_front(1);
+            self.rows_trimmed += 1;
+            self.marks.prune_before(self.rows_trimmed);
+            self.annotations.prune_before(self.rows_trimmed);
+            self.highlights.prune_before(self.rows_trimmed);
+            self.images.prune_before(self.rows_trimmed);
+            self.changes.record(GridChange::ScrollbackTrimmed { rows: 1 });
         }
     }
 
-    fn advance(&mut self) {
-        self.col += 1;
-        if self.auto_wrap && self.col >= self.cols {
-            self.newline();
-        } else {
-            self.col = self.col.min(self.cols - 1);
+    /// Keep only the `keep_rows` oldest scrollback rows
+    pub fn truncate_scrollback(&mut self, keep_rows: usize) {
+        self.scrollback.truncate(keep_rows);
+        self.scrollback_index.truncate(keep_rows);
+        self.changes.record(GridChange::ScrollbackRewritten);
+    }
+
+    /// Note that every scrollback row is about to be dropped
+    fn record_scrollback_cleared(&mut self) {
+        let rows = self.scrollback_rows();
+        if rows > 0 {
+            self.changes.record(GridChange::ScrollbackTrimmed { rows });
         }
     }
 
-    fn left(&mut self, n: usize) {
-        self.col = self.col.saturating_sub(n);
+    /// Absolute rows of scrollback lines containing `query`, ignoring case
+    ///
+    /// Served from an index maintained as rows enter the scrollback, so it
+    /// stays fast on very long histories. Rows are in ascending order and
+    /// can be passed to [`Grid::add_mark_at`] as search matches.
+    pub fn search_scrollback(&self, query: &str) -> Vec<u64> {
+        self.scrollback_index
+            .search(query)
+            .into_iter()
+            .map(|row| self.rows_trimmed + row as u64)
+            .collect()
     }
-    
-    fn right(&mut self, n: usize) {
-        self.col = (self.col + n).min(self.cols - 1);
+
+    /// Heap bytes held by the scrollback search index
+    pub fn scrollback_index_bytes(&self) -> usize {
+        self.scrollback_index.memory_bytes()
     }
-    
-    fn up(&mut self, n: usize) {
-        self.row = self.row.saturating_sub(n);
+
+    /// Absolute row number of the oldest row still in the scrollback
+    pub fn first_absolute_row(&self) -> u64 {
+        self.rows_trimmed
     }
-    
-    fn down(&mut self, n: usize) {
-        self.row = (self.row + n).min(self.rows - 1);
+
+    /// Absolute row number of a screen row
+    pub fn absolute_row(&self, screen_row: usize) -> u64 {
+        self.rows_trimmed + (self.scrollback_rows() + screen_row) as u64
     }
 
-    fn newline(&mut self) {
-        self.col = 0;
-        self.row += 1;
-        if self.row >= self.rows {
-            // Move top row to scrollback
-            let start_idx = 0;
-            let end_idx = self.cols;
-            let top_row: Vec<Cell> = self.cells[start_idx..end_idx].to_vec();
-            self.scrollback.extend(top_row);
-            
-            // Scroll up
-            self.cells.copy_within(self.cols.., 0);
-            
-            // Clear new bottom row
-            let bottom_start = (self.rows - 1) * self.cols;
-            for i in 0..self.cols {
-                self.cells[bottom_start + i] = Self::default_cell();
-            }
-            
-            self.row = self.rows - 1;
-            self.scroll_offset = 0; // Auto-scroll to bottom on new output
-            
-            // Limit scrollback
-            if self.scrollback.len() > crate::constants::SCROLLBACK_LIMIT * self.cols {
-                self.scrollback.drain(0..self.cols);
-            }
-        }
+    /// Absolute row shown at the top of the viewport (honours `scroll_offset`)
+    pub fn viewport_top_row(&self) -> u64 {
+        self.absolute_row(0) - self.scroll_offset.min(self.scrollback_rows()) as u64
     }
 
-    fn carriage_return(&mut self) {
+    /// Mark the cursor row; ignored on the alternate screen, which has no scrollback
+    pub fn add_mark(&mut self, category: MarkCategory) {
+        if self.use_alternate_screen {
+            return;
+        }
+        let row = self.absolute_row(self.row);
+        self.marks.add(row, category);
+    }
+
+    /// Mark an absolute row (e.g. a search match found in the scrollback)
+    pub fn add_mark_at(&mut self, row: u64, category: MarkCategory) {
+        if row >= self.first_absolute_row() {
+            self.marks.add(row, category);
+        }
+    }
+
+    /// Remove all marks of one category
+    pub fn clear_marks(&mut self, category: MarkCategory) {
+        self.marks.clear_category(category);
+    }
+
+    /// Scroll marks in row order, for scrollbar/overlay rendering
+    pub fn scroll_marks(&self) -> &[ScrollMark] {
+        self.marks.as_slice()
+    }
+
+    /// Attach `annotation` to an absolute row, e.g. a bookmark or build status
+    ///
+    /// Returns `None` if the row was already trimmed from the scrollback.
+    pub fn annotate_row(&mut self, row: u64, annotation: Annotation) -> Option<AnnotationId> {
+        (row >= self.first_absolute_row()).then(|| self.annotations.add(row, annotation))
+    }
+
+    /// Remove an annotation; returns false if it is gone already
+    pub fn remove_annotation(&mut self, id: AnnotationId) -> bool {
+        self.annotations.remove(id)
+    }
+
+    /// Remove all annotations
+    pub fn clear_annotations(&mut self) {
+        self.annotations.clear();
+    }
+
+    /// All annotations in row order
+    pub fn row_annotations(&self) -> &[RowAnnotation] {
+        self.annotations.as_slice()
+    }
+
+    /// Annotations on rows in the viewport, for gutter rendering
+    ///
+    /// The viewport row of each is `annotation.row - grid.viewport_top_row()`.
+    pub fn visible_annotations(&self) -> &[RowAnnotation] {
+        let top = self.viewport_top_row();
+        self.annotations.in_rows(top, top + self.rows as u64)
+    }
+
+    /// Images of the active screen covering the viewport, topmost first
+    ///
+    /// The viewport row of each is `image.row - grid.viewport_top_row()`,
+    /// negative for an image that starts above the viewport.
+    pub fn visible_images(&self) -> impl Iterator<Item = &PlacedImage> {
+        let top = self.viewport_top_row();
+        let layer = if self.use_alternate_screen { &self.alternate_images } else { &self.images };
+        layer.in_rows(top, top + self.rows as u64)
+    }
+
+    /// Pixel bytes held by placed images, for memory diagnostics
+    pub fn image_bytes(&self) -> usize {
+        self.images.bytes() + self.alternate_images.bytes()
+    }
+
+    /// Cell size in pixels, used to work out the cells a sixel image covers;
+    /// backends set it whenever their font metrics change
+    pub fn set_cell_pixel_size(&mut self, width: usize, height: usize) {
+        self.cell_pixel_size = (width.max(1), height.max(1));
+    }
+
+    fn active_images_mut(&mut self) -> &mut ImageLayer {
+        if self.use_alternate_screen { &mut self.alternate_images } else { &mut self.images }
+    }
+
+    /// Move the images on screen `rows` by `delta` rows along with their text,
+    /// dropping those moved off those rows
+    fn shift_images(&mut self, rows: std::ops::Range<usize>, delta: i64) {
+        let top = self.absolute_row(0);
+        self.active_images_mut().shift_rows(top + rows.start as u64, top + rows.end as u64, delta);
+    }
+
+    /// Drop the images over cells `range` (indices into the active screen),
+    /// whose text is being erased or overwritten
+    fn drop_images_over(&mut self, range: std::ops::Range<usize>) {
+        let (cols, top) = (self.cols, self.absolute_row(0));
+        let layer = self.active_images_mut();
+        if range.is_empty() || layer.is_empty() {
+            return;
+        }
+        for row in range.start / cols..=(range.end - 1) / cols {
+            let first = row * cols;
+            layer.remove_covering(top + row as u64, range.start.max(first) - first..range.end.min(first + cols) - first);
+        }
+    }
+
+    /// Place `image` with its top-left corner at the cursor, which then moves
+    /// to the image's column on the row below it, scrolling as needed
+    fn place_image(&mut self, image: ImageData) {
+        let (cell_width, cell_height) = self.cell_pixel_size;
+        let col = self.col;
+        let placed = PlacedImage {
+            row: self.absolute_row(self.row),
+            col,
+            cols: image.width.div_ceil(cell_width),
+            rows: image.height.div_ceil(cell_height),
+            image: std::sync::Arc::new(image),
+        };
+        let rows = placed.rows;
+        self.active_images_mut().add(placed, crate::constants::MAX_IMAGE_LAYER_BYTES);
+        for _ in 0..rows {
+            AnsiGrid::newline(self);
+        }
+        self.col = col;
+    }
+
+    /// Scroll the viewport so the next/previous mark is at its top
+    ///
+    /// The search starts from the row currently at the top of the viewport.
+    /// Returns the mark jumped to, or `None` when there is none in that
+    /// direction (the viewport is left unchanged).
+    pub fn scroll_to_mark(&mut self, direction: MarkDirection, category: Option<MarkCategory>) -> Option<ScrollMark> {
+        let mark = self.marks.find(self.viewport_top_row(), direction, category)?;
+        self.scroll_offset = self.absolute_row(0).saturating_sub(mark.row) as usize;
+        Some(mark)
+    }
+
+    /// Hold output (scroll lock) or release it
+    ///
+    /// While held, output is still parsed into the screen and scrollback but
+    /// the viewport stays on the rows it shows. Releasing jumps back to the
+    /// live tail.
+    pub fn set_output_held(&mut self, held: bool) {
+        self.output_held = held;
+        if !held {
+            self.scroll_to_bottom();
+        }
+    }
+
+    /// Number of bells (BEL) received; backends ring the visual bell when it
+    /// advances between frames
+    pub fn bell_count(&self) -> u64 {
+        self.bells
+    }
+
+    /// Action of the first bell since the last call that `BellPolicy` did
+    /// not drop, for `TerminalEvent::Bell`
+    pub fn take_bell(&mut self) -> Option<BellAction> {
+        self.pending_bell.take()
+    }
+
+    pub fn bell_policy(&self) -> BellPolicy {
+        self.bell_gate.policy()
+    }
+
+    /// Replace the bell policy, e.g. when the terminal switches profiles
+    pub fn set_bell_policy(&mut self, policy: BellPolicy) {
+        self.bell_gate.set_policy(policy);
+    }
+
+    /// Whether output is held (see `set_output_held`)
+    pub fn is_output_held(&self) -> bool {
+        self.output_held
+    }
+
+    /// Keep the viewport on the live tail, or, with output held, a
+    /// selection freeze or a scrolled-back viewport that `scroll_on_output`
+    /// leaves alone, on the rows it shows after `scrolled_rows` rows moved
+    /// into the scrollback
+    fn follow_output(&mut self, scrolled_rows: usize) {
+        let scrolled_back = self.scroll_offset > 0 && !self.config.scroll_on_output;
+        if self.output_held || self.selection_frozen || scrolled_back {
+            self.unseen_rows = self.unseen_rows.min(self.scroll_offset) + scrolled_rows;
+            self.scroll_offset = (self.scroll_offset + scrolled_rows).min(self.scrollback_rows());
+        } else {
+            self.scroll_offset = 0;
+            self.unseen_rows = 0;
+        }
+    }
+
+    /// Rows of new output below the viewport the user has not seen yet
+    pub fn unseen_output(&self) -> usize {
+        self.unseen_rows.min(self.scroll_offset)
+    }
+
+    /// Scroll the viewport for a mouse wheel movement of `delta` notches,
+    /// positive toward the user (down, to newer output); `shift` is whether
+    /// Shift was held
+    ///
+    /// Backends pass the raw wheel delta so that lines per notch, Shift
+    /// paging and natural scrolling follow the configuration the same way
+    /// everywhere. Fractions of a line from smooth-scrolling devices carry
+    /// over to the next call. Returns whether the viewport moved.
+    pub fn scroll_wheel(&mut self, delta: f64, shift: bool) -> bool {
+        let per_notch = if shift && self.config.shift_wheel_scrolls_page {
+            self.rows.saturating_sub(1).max(1) as f64
+        } else {
+            self.config.wheel_scroll_lines
+        };
+        let delta = if self.config.natural_scrolling { -delta } else { delta };
+        let lines = self.wheel_remainder + delta * per_notch;
+        self.wheel_remainder = lines.fract();
+
+        let before = self.scroll_offset;
+        let max = self.scrollback_rows() as i64;
+        self.scroll_offset = (self.scroll_offset as i64 - lines.trunc() as i64).clamp(0, max) as usize;
+        self.scroll_offset != before
+    }
+
+    /// Scroll the viewport to the live tail
+    pub fn scroll_to_bottom(&mut self) {
+        self.scroll_offset = 0;
+        self.unseen_rows = 0;
+    }
+
+    /// Auto-wrap: flag the row as continuing on the next one and move there
+    fn soft_wrap(&mut self) {
+        let (row, col) = (self.row, self.cols - 1);
+        self.get_cell_mut(row, col).wrapped = true;
+        self.newline();
+    }
+
+    /// Switch between the light and dark themes of `TerminalConfig::themes`
+    ///
+    /// Existing cells with default colors change at the next render, OSC
+    /// 10/11 queries see the new colors, and applications that enabled mode
+    /// 2031 get a report queued when the applied scheme changes (see
+    /// `TerminalConfig::applied_color_scheme`). Returns whether anything changed.
+    pub fn set_color_scheme(&mut self, scheme: ColorScheme) -> bool {
+        let applied = self.config.applied_color_scheme();
+        let mut config = (*self.config).clone();
+        if !config.apply_color_scheme(scheme) {
+            return false;
+        }
+        self.config = std::sync::Arc::new(config);
+        let now_applied = self.config.applied_color_scheme();
+        if self.color_scheme_reporting && now_applied != applied {
+            self.responses.push_str(now_applied.report());
+        }
+        true
+    }
+
+    /// Full-screen applications draw their own input; predicting there misleads
+    fn predicts_echo(&self) -> bool {
+        self.config.local_echo != LocalEcho::Off
+            && !self.use_alternate_screen
+            && !self.application_cursor_keys
+            && self.cursor_visible
+    }
+
+    /// Record text sent to the PTY for local echo prediction
+    ///
+    /// Typed text is predicted at the cursor; pasted or injected text drops
+    /// pending predictions, as do special keys (pass an empty string).
+    pub fn predict_echo(&mut self, text: &str, source: TextSource) {
+        if !self.predicts_echo() || !matches!(source, TextSource::Key | TextSource::InputMethod) || text.is_empty() {
+            self.predictor.clear();
+            return;
+        }
+        // A pending wrap leaves no room on the cursor row
+        let col = if self.wrap_pending { self.cols } else { self.col };
+        self.predictor.predict(text, (self.row, col), self.cols, self.clock.now());
+    }
+
+    /// Confirm or drop predictions against the output processed so far
+    pub fn reconcile_predictions(&mut self) {
+        if !self.predicts_echo() {
+            self.predictor.clear();
+            return;
+        }
+        let cols = self.cols;
+        let cells = if self.use_alternate_screen { &self.alternate_cells } else { &self.cells };
+        let cell = |row: usize, col: usize| (col < cols).then(|| cells.get(row * cols + col).map(|c| c.ch)).flatten();
+        self.predictor.reconcile((self.row, self.col), cell, self.clock.now());
+    }
+
+    /// Show collaborator `id`'s cursor at screen position `pos` in `color`
+    ///
+    /// Each call keeps the cursor alive for
+    /// `TerminalConfig::remote_cursor_timeout_ms`; cursors past it are dropped.
+    pub fn set_remote_cursor(&mut self, id: u64, pos: (usize, usize), color: Color) {
+        let now = self.clock.now();
+        self.remote_cursors.expire(now, self.remote_cursor_timeout());
+        self.remote_cursors.set_cursor(id, pos, color, now);
+    }
+
+    /// Set or clear collaborator `id`'s selection, start and end inclusive;
+    /// returns false if `id` has no cursor
+    pub fn set_remote_selection(&mut self, id: u64, selection: Option<((usize, usize), (usize, usize))>) -> bool {
+        self.remote_cursors.set_selection(id, selection, self.clock.now())
+    }
+
+    /// Stop showing collaborator `id`; returns false if it was not shown
+    pub fn remove_remote_cursor(&mut self, id: u64) -> bool {
+        self.remote_cursors.remove(id)
+    }
+
+    /// Collaborators' cursors to draw, those that timed out left out
+    pub fn remote_cursors(&self) -> Vec<RemoteCursor> {
+        self.remote_cursors.live(self.clock.now(), self.remote_cursor_timeout()).cloned().collect()
+    }
+
+    /// Replace the selection with the text from `start` up to `end`
+    /// (exclusive), as (absolute row, column) pairs, e.g. a find match
+    ///
+    /// The viewport scrolls to the start row if it is not shown. Returns
+    /// false, leaving the selection alone, if the range is empty or starts
+    /// on a row trimmed from the scrollback.
+    pub fn select_range(&mut self, start: (u64, usize), end: (u64, usize)) -> bool {
+        if start >= end || start.0 < self.first_absolute_row() || start.0 > self.absolute_row(self.rows.saturating_sub(1)) {
+            return false;
+        }
+        let top = self.viewport_top_row();
+        if start.0 < top || start.0 >= top + self.rows as u64 {
+            self.scroll_offset = self.absolute_row(0).saturating_sub(start.0) as usize;
+        }
+        self.select_visible(start..end)
+    }
+
+    /// Highlight `ranges` of (absolute row, column) pairs, end exclusive, in
+    /// `style`, on top of the highlights already shown
+    ///
+    /// Highlights are independent of the selection and stay until
+    /// `clear_highlights` or until their rows are trimmed from the scrollback.
+    pub fn highlight_ranges(&mut self, ranges: Vec<Range<(u64, usize)>>, style: HighlightStyle) {
+        self.highlights.add(ranges, style);
+    }
+
+    /// Remove every highlight added with `highlight_ranges`
+    pub fn clear_highlights(&mut self) {
+        self.highlights.clear();
+    }
+
+    /// Highlight spans on the viewport, for an overlay pass
+    pub fn highlight_spans(&self) -> Vec<HighlightSpan> {
+        self.highlights.spans(self.viewport_top_row(), self.rows, self.cols)
+    }
+
+    /// Decode a chunk of PTY output for the parser
+    pub fn decode_output(&mut self, bytes: &[u8]) -> String {
+        self.decoder.decode(bytes)
+    }
+
+    /// Encoding output is currently decoded with
+    pub fn encoding(&self) -> Encoding {
+        self.decoder.encoding()
+    }
+
+    /// Decode output as `encoding` from now on, or detect it again with `None`
+    pub fn set_encoding(&mut self, encoding: Option<Encoding>) {
+        self.decoder.set_encoding(encoding);
+    }
+
+    /// Blank the unprotected cells in `range` of the active screen (DECSED, DECSEL)
+    fn selective_erase(&mut self, range: std::ops::Range<usize>) {
+        self.drop_images_over(range.clone());
+        let default = Self::default_cell();
+        let cells = self.active_cells_mut();
+        for idx in range {
+            // Reading first leaves blank rows shared
+            if !cells[idx].protected && cells[idx] != default {
+                cells[idx] = default;
+            }
+        }
+    }
+
+    fn remote_cursor_timeout(&self) -> Duration {
+        Duration::from_millis(self.config.remote_cursor_timeout_ms)
+    }
+
+    /// Time until the predicted characters drawn now expire, so a frontend
+    /// can redraw then if no output arrives to settle them
+    pub fn prediction_expiry(&self) -> Option<Duration> {
+        self.predictor.expires_in(self.config.local_echo, self.clock.now())
+    }
+
+    /// Predicted characters as drawn: the cell underneath, underlined, with
+    /// the predicted character
+    pub fn predicted_cells(&self) -> Vec<(usize, usize, Cell)> {
+        self.predictor.visible(self.config.local_echo, self.clock.now()).iter()
+            .filter(|p| p.row < self.rows && p.col < self.cols)
+            .map(|p| {
+                let mut cell = *self.get_cell(p.row, p.col);
+                cell.ch = p.ch;
+                cell.underline = true;
+                cell.underline_style = UnderlineStyle::Single;
+                (p.row, p.col, cell)
+            })
+            .collect()
+    }
+
+    /// Take the queued query replies, leaving the queue empty
+    ///
+    /// The PTY reader drains this after each chunk and writes the bytes back
+    /// to the child process.
+    pub fn take_responses(&mut self) -> String {
+        std::mem::take(&mut self.responses)
+    }
+
+    /// Clipboard reads requested since the last call (see `clipboard`)
+    pub fn take_clipboard_requests(&mut self) -> Vec<ClipboardRequest> {
+        self.clipboard_reads.take_new()
+    }
+
+    /// Settle clipboard read `id` with `text`, or `None` if the user refused;
+    /// returns the reply to write to the PTY
+    pub fn answer_clipboard_request(&mut self, id: u64, text: Option<&str>) -> Option<String> {
+        self.clipboard_reads.answer(id, text)
+    }
+
+    /// OSC sequences held for the user's permission since the last call
+    /// (see `osc_policy`)
+    pub fn take_osc_requests(&mut self) -> Vec<OscRequest> {
+        self.osc_gate.take_new()
+    }
+
+    /// Settle held sequence `id`, running it now if `allow`; returns whether
+    /// it ran
+    ///
+    /// A held OSC 8 link only opens while the stream is still inside it: once
+    /// a later OSC 8 has ended or replaced it, the text it covered is already
+    /// written and the link is not opened over unrelated output.
+    pub fn answer_osc_request(&mut self, id: u64, allow: bool) -> bool {
+        let now = self.clock.now();
+        let Some(osc) = self.osc_gate.answer(id, allow, now) else {
+            return false;
+        };
+        if osc.starts_with("8;") {
+            if self.held_hyperlink.as_deref() != Some(osc.as_str()) {
+                return false;
+            }
+            self.held_hyperlink = None;
+        }
+        self.osc_replay = true;
+        AnsiParser::new().feed_str(&format!("\x1B]{}\x1B\\", osc), self);
+        self.osc_replay = false;
+        true
+    }
+
+    /// OSC sequences denied by the policy or the user, oldest first
+    pub fn denied_osc(&self) -> Vec<DeniedOsc> {
+        self.osc_gate.denied().iter().cloned().collect()
+    }
+
+    /// Desktop notifications (title, body) requested since the last call
+    pub fn take_notifications(&mut self) -> Vec<(String, String)> {
+        std::mem::take(&mut self.notifications)
+    }
+
+    /// Custom OSC sequences (code, data) received since the last call, for
+    /// the codes in `OscPolicy::custom_codes`
+    pub fn take_custom_osc(&mut self) -> Vec<(u16, String)> {
+        std::mem::take(&mut self.custom_osc)
+    }
+
+    /// Window title set with OSC 0 or 2
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Working directory last reported with OSC 7, as sent
+    pub fn current_directory(&self) -> Option<&str> {
+        self.current_directory.as_ref().map(|dir| dir.uri.as_str())
+    }
+
+    /// Working directory last reported with OSC 7, decoded; reports with
+    /// malformed escapes or paths that are not UTF-8 are ignored
+    pub fn working_directory(&self) -> Option<&WorkingDirectory> {
+        self.current_directory.as_ref()
+    }
+
+    /// Whether a new shell may start in the reported working directory:
+    /// one was reported and it is on this machine, not across SSH
+    pub fn can_inherit_cwd(&self) -> bool {
+        self.current_directory.as_ref().is_some_and(WorkingDirectory::is_local)
+    }
+
+    /// Host of the reported working directory when it is another machine
+    pub fn remote_host(&self) -> Option<&str> {
+        self.current_directory.as_ref().filter(|dir| !dir.is_local()).and_then(|dir| dir.host.as_deref())
+    }
+
+    /// Record keyboard focus changes from the backend
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Exit status of the last command, as reported by OSC 133;D
+    ///
+    /// `None` before the first report or when the shell sent no status.
+    pub fn last_command_status(&self) -> Option<i32> {
+        self.last_command_status
+    }
+
+    /// Output region of the most recent command, start inclusive and end
+    /// exclusive, as (absolute row, column) pairs
+    ///
+    /// With shell integration this is the text between OSC 133;C and 133;D.
+    /// Without it, the rows between the previous prompt and the cursor's, a
+    /// prompt being a row that starts like the cursor row up to its last
+    /// prompt character (`$`, `#`, `%`, `>` or `❯`).
+    pub fn last_command_output_zone(&self) -> Option<((u64, usize), (u64, usize))> {
+        if self.last_output.is_some() {
+            return self.last_output;
+        }
+        if self.use_alternate_screen {
+            return None;
+        }
+        let cursor_row = self.absolute_row(self.row);
+        let line = self.absolute_row_text(cursor_row, 0, self.col);
+        let prompt = match line.char_indices().rev().find(|(_, ch)| PROMPT_CHARS.contains(ch)) {
+            Some((index, ch)) => line[..index + ch.len_utf8()].to_string(),
+            None => line,
+        };
+        if prompt.trim().is_empty() {
+            return None;
+        }
+        let previous = (self.first_absolute_row()..cursor_row)
+            .rev()
+            .find(|&row| self.absolute_row_text(row, 0, self.cols).starts_with(&prompt))?;
+        (previous + 1 < cursor_row).then_some(((previous + 1, 0), (cursor_row, 0)))
+    }
+
+    /// Text of the most recent command's output (see
+    /// `last_command_output_zone`), without trailing blank lines
+    pub fn last_command_output(&self) -> Option<String> {
+        let ((start_row, start_col), (end_row, end_col)) = self.last_command_output_zone()?;
+        let mut lines: Vec<String> = (start_row..=end_row)
+            .filter(|&row| row < end_row || end_col > 0)
+            .map(|row| {
+                let from = if row == start_row { start_col } else { 0 };
+                let to = if row == end_row { end_col } else { self.cols };
+                self.absolute_row_text(row, from, to).trim_end().to_string()
+            })
+            .collect();
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+
+    /// Select the visible part of the most recent command's output and
+    /// return its text, for copying
+    pub fn select_last_command_output(&mut self) -> Option<String> {
+        let text = self.last_command_output()?;
+        let (start, end) = self.last_command_output_zone()?;
+        self.select_visible(start..end);
+        Some(text)
+    }
+
+    /// Select the visible part of an absolute range; returns false if none
+    /// of it is on the viewport
+    fn select_visible(&mut self, range: Range<(u64, usize)>) -> bool {
+        let Some(((start_row, start_col), (end_row, end_col))) = visible_part(&range, self.viewport_top_row(), self.rows, self.cols) else {
+            return false;
+        };
+        self.selection.create_selection(start_row, start_col, end_row, end_col);
+        true
+    }
+
+    /// Characters `from..to` of an absolute row, blanks as spaces
+    fn absolute_row_text(&self, row: u64, from: usize, to: usize) -> String {
+        let Some(cells) = self.absolute_row_cells(row) else {
+            return String::new();
+        };
+        let to = to.min(cells.len());
+        self.cells_text(&cells[from.min(to)..to])
+    }
+
+    /// Text of `cells` with their combining marks, blanks as spaces and
+    /// double-width characters once
+    fn cells_text(&self, cells: &[Cell]) -> String {
+        let mut text = String::new();
+        for cell in cells.iter().filter(|cell| !cell.spacer) {
+            text.push(if cell.ch == '\0' { ' ' } else { cell.ch });
+            text.push_str(self.combining_marks(cell));
+        }
+        text
+    }
+
+    /// Silence threshold for `TerminalEvent::Silence`; `None` when disabled
+    pub fn silence_threshold(&self) -> Option<Duration> {
+        self.activity.silence_after()
+    }
+
+    /// Change the silence threshold; the quiet period starts over
+    pub fn set_silence_threshold(&mut self, threshold: Option<Duration>) {
+        self.activity.set_silence_after(threshold, self.clock.now());
+    }
+
+    /// Record a chunk of output from the child; true if it ends a reported
+    /// silence (`TerminalEvent::Activity`)
+    pub fn note_output(&mut self) -> bool {
+        self.activity.output(self.clock.now())
+    }
+
+    /// Whether `poll_silence` would report silence now
+    pub fn silence_due(&self) -> bool {
+        self.activity.is_due(self.clock.now())
+    }
+
+    /// How long the terminal has been quiet, once per quiet period that
+    /// reaches the silence threshold (`TerminalEvent::Silence`)
+    pub fn poll_silence(&mut self) -> Option<Duration> {
+        self.activity.poll(self.clock.now())
+    }
+
+    /// Record the parser's counters after a chunk of output, with the text
+    /// of an error it reported in that chunk
+    pub fn record_parser_stats(&mut self, stats: &ParserStats, last_error: Option<&str>) {
+        self.parser_health.record(stats, last_error);
+    }
+
+    /// Parser health since the previous call, if the parser was busy (see `health`)
+    pub fn poll_parser_health(&mut self) -> Option<ParserHealth> {
+        self.parser_health.poll(self.clock.now())
+    }
+
+    /// Drain the commands to report as `TerminalEvent::CommandFinished`
+    pub fn take_finished_commands(&mut self) -> Vec<FinishedCommand> {
+        std::mem::take(&mut self.finished_commands)
+    }
+
+    /// Commit the visible screen as one mutation batch; returns the current generation
+    ///
+    /// The generation only moves when a row changed since the previous
+    /// commit. The PTY reader commits after every chunk.
+    pub fn commit_generation(&mut self) -> u64 {
+        let before = self.generations.generation();
+        let cells = if self.use_alternate_screen { &self.alternate_cells } else { &self.cells };
+        let generation = self.generations.commit(cells);
+        let rows = if generation != before { self.generations.rows_changed_since(before) } else { Vec::new() };
+        if generation != before {
+            self.capture_snapshot();
+        }
+        // Hooks hear about this batch's scrollback and screen changes too
+        self.changes.deliver(rows, generation);
+        #[cfg(feature = "debug-invariants")]
+        self.assert_invariants("commit");
+        generation
+    }
+
+    /// Hand the changed screen to the time-travel ring, which records the
+    /// screen it replaces if a snapshot is due
+    fn capture_snapshot(&mut self) {
+        if !self.snapshots.is_enabled() {
+            return;
+        }
+        self.snapshots.record(ScreenSnapshot {
+            taken_at: self.clock.now(),
+            cols: self.cols,
+            rows: self.rows,
+            cells: self.active_cells().to_vec(),
+            cursor: (self.row, self.col),
+            cursor_visible: self.cursor_visible,
+        });
+    }
+
+    /// Latest screen snapshot taken at or before `at`, e.g. "ten seconds ago"
+    pub fn snapshot_at(&self, at: Instant) -> Option<&ScreenSnapshot> {
+        self.snapshots.at(at)
+    }
+
+    /// Snapshot shown in place of the live screen while time travelling
+    pub fn shown_snapshot(&self) -> Option<&ScreenSnapshot> {
+        self.snapshots.shown()
+    }
+
+    /// Show the previous screen snapshot; returns false if there is none older
+    ///
+    /// From the live screen this starts with the newest snapshot that
+    /// differs from it.
+    pub fn step_snapshot_back(&mut self) -> bool {
+        let live = self.active_cells();
+        let skip_newest = self.snapshots.newest().is_some_and(|s| live.same_cells(&s.cells));
+        self.snapshots.step_back(skip_newest)
+    }
+
+    /// Show the next screen snapshot, or the live screen after the newest;
+    /// returns false if the live screen was already shown
+    pub fn step_snapshot_forward(&mut self) -> bool {
+        self.snapshots.step_forward()
+    }
+
+    /// Stop time travelling and show the live screen
+    pub fn leave_snapshot(&mut self) {
+        self.snapshots.leave();
+    }
+
+    /// Broken grid invariants, empty for a consistent grid
+    ///
+    /// Checks the buffer sizes, the cursor and viewport against the screen
+    /// and scrollback, the search index against the scrollback, and the
+    /// selection bounds. With the `debug-invariants` feature this runs after
+    /// every mutation batch and resize.
+    pub fn invariant_violations(&self) -> Vec<String> {
+        let mut violations = Vec::new();
+        let screen = self.cols * self.rows;
+        for (name, buffer) in [("primary", &self.cells), ("alternate", &self.alternate_cells)] {
+            let widths_match = buffer.iter_rows().all(|row| row.len() == self.cols);
+            if buffer.len() != screen || buffer.cols() != self.cols || !widths_match {
+                violations.push(format!(
+                    "{} buffer has {}x{} cells, expected {}x{}",
+                    name,
+                    buffer.cols(),
+                    buffer.row_count(),
+                    self.cols,
+                    self.rows
+                ));
+            }
+        }
+        for (name, buffer) in [("primary", &self.cells), ("alternate", &self.alternate_cells)] {
+            // Wide characters fill two cells: a left half, then its right half
+            let split = buffer.iter_rows().position(|row| {
+                row.iter().enumerate().any(|(col, cell)| {
+                    cell.spacer != (col > 0 && row[col - 1].wide) || (cell.wide && col + 1 == row.len())
+                })
+            });
+            if let Some(row) = split {
+                violations.push(format!("{} row {} holds half a wide character", name, row));
+            }
+        }
+        if self.row >= self.rows.max(1) || self.col >= self.cols.max(1) {
+            violations.push(format!("cursor ({}, {}) outside {}x{}", self.row, self.col, self.cols, self.rows));
+        }
+        let scrollback_rows = self.scrollback_rows();
+        if self.scrollback_index.len() != scrollback_rows {
+            violations.push(format!("search index has {} rows, scrollback {}", self.scrollback_index.len(), scrollback_rows));
+        }
+        if self.scroll_offset > scrollback_rows {
+            violations.push(format!("scroll offset {} past {} scrollback rows", self.scroll_offset, scrollback_rows));
+        }
+        if let Some(((start_row, start_col), (end_row, end_col))) = self.selection.get_bounds() {
+            let total_rows = scrollback_rows + self.rows;
+            if start_row.max(end_row) >= total_rows || start_col.max(end_col) > self.cols {
+                violations.push(format!(
+                    "selection ({}, {})-({}, {}) outside {} columns and {} rows",
+                    start_row, start_col, end_row, end_col, self.cols, total_rows
+                ));
+            }
+        }
+        violations
+    }
+
+    /// Panic (debug builds) or log (release builds) on broken invariants
+    #[cfg(feature = "debug-invariants")]
+    fn assert_invariants(&self, after: &str) {
+        let violations = self.invariant_violations();
+        if violations.is_empty() {
+            return;
+        }
+        let message = format!("grid invariants broken after {}: {}", after, violations.join("; "));
+        if cfg!(debug_assertions) {
+            panic!("{}", message);
+        }
+        tracing::error!("{}", message);
+    }
+
+    /// Call `hook` with every change delivered at `commit_generation`
+    ///
+    /// The hook runs with the grid locked and must not lock it again.
+    pub fn add_change_hook(&mut self, hook: impl Fn(&GridChange) + Send + Sync + 'static) -> ChangeHookId {
+        self.changes.add_hook(hook)
+    }
+
+    /// Unregister a change hook; returns false if it was not registered
+    pub fn remove_change_hook(&mut self, id: ChangeHookId) -> bool {
+        self.changes.remove_hook(id)
+    }
+
+    /// Counts of screen, scrollback and screen-swap changes so far
+    pub fn change_counters(&self) -> ChangeCounters {
+        ChangeCounters { generation: self.generations.generation(), ..self.changes.counters() }
+    }
+
+    /// Generation of the last committed change
+    pub fn generation(&self) -> u64 {
+        self.generations.generation()
+    }
+
+    /// Indices of the screen rows changed after `generation`, as of the last commit
+    pub fn rows_changed_since(&self, generation: u64) -> Vec<usize> {
+        self.generations.rows_changed_since(generation)
+    }
+
+    /// Screen rows changed after `generation`, as of the last commit
+    ///
+    /// Pass the generation returned with the previous update, or 0 for the
+    /// whole screen.
+    pub fn diff_since(&self, generation: u64) -> Vec<RowDelta> {
+        self.generations.diff_since(generation)
+    }
+
+    /// Cells of an absolute row, whether in the scrollback or on the primary screen
+    pub fn absolute_row_cells(&self, row: u64) -> Option<std::borrow::Cow<'_, [Cell]>> {
+        let index = usize::try_from(row.checked_sub(self.rows_trimmed)?).ok()?;
+        if index < self.scrollback_rows() {
+            return self.scrollback.get_row(index);
+        }
+        self.cells.get_row(index - self.scrollback_rows()).map(std::borrow::Cow::Borrowed)
+    }
+
+    /// Whether the cell at (`row`, `col`) holds a double-width character,
+    /// whose right half is the following cell
+    fn is_wide_cell(&self, row: usize, col: usize) -> bool {
+        col + 1 < self.cols && self.get_cell(row, col).wide
+    }
+
+    /// The other half of the wide character covering (`row`, `col`), if
+    /// the screen cell is half of one
+    fn wide_partner(&self, row: usize, col: usize) -> Option<usize> {
+        if row >= self.rows || col >= self.cols {
+            return None;
+        }
+        if self.is_wide_cell(row, col) {
+            Some(col + 1)
+        } else {
+            (col > 0 && self.get_cell(row, col).spacer).then(|| col - 1)
+        }
+    }
+
+    /// Column of the character covering screen cell (`row`, `col`): the
+    /// left half for the right half of a wide character
+    fn char_start(&self, row: usize, col: usize) -> usize {
+        self.wide_partner(row, col).filter(|&other| other < col).unwrap_or(col)
+    }
+
+    /// Make (`row`, `col`) the right half of the wide character before it, a
+    /// blank in its colors
+    fn set_spacer(&mut self, row: usize, col: usize) {
+        let head = *self.get_cell(row, col - 1);
+        let tail = self.get_cell_mut(row, col);
+        *tail = Cell { ch: '\0', wide: false, spacer: true, wrapped: tail.wrapped, ..head };
+    }
+
+    /// Turn the character just written into a double-width one (VS16 emoji
+    /// presentation), taking the cell after it as its right half
+    /// Store zero-width `mark` with the character before the cursor, which
+    /// is still under it while a wrap is pending
+    fn attach_combining(&mut self, mark: char) {
+        let row = self.row;
+        let mut col = if self.wrap_pending { self.col } else { self.col.wrapping_sub(1) };
+        if row >= self.rows || col >= self.cols {
+            return;
+        }
+        if self.get_cell(row, col).spacer && col > 0 {
+            col -= 1;
+        }
+        let cell = *self.get_cell(row, col);
+        let mut marks = self.combining_marks(&cell).to_string();
+        if cell.ch == '\0' || marks.chars().count() >= crate::constants::MAX_COMBINING_MARKS {
+            return;
+        }
+        marks.push(mark);
+        let id = self.combining.intern(None, &marks).or_else(|| {
+            // Full: make room by dropping the sequences no cell carries
+            let referenced = self.referenced_ids(|cell| cell.combining);
+            self.combining.retain(|id| referenced.contains(&id));
+            self.combining.intern(None, &marks)
+        });
+        match id {
+            Some(id) => self.get_cell_mut(row, col).combining = id,
+            None => tracing::debug!("Dropping combining mark U+{:04X}: {} sequences on screen", mark as u32, self.combining.len()),
+        }
+    }
+
+    fn widen_previous(&mut self) {
+        // At the right margin there is no room, and the cursor still covers it
+        if self.wrap_pending || self.col == 0 || self.col >= self.cols {
+            return;
+        }
+        let (row, col) = (self.row, self.col);
+        self.split_wide_at(row, col + 1);
+        self.get_cell_mut(row, col - 1).wide = true;
+        self.set_spacer(row, col);
+    }
+
+    /// Blank both halves of a wide character that the column boundary before
+    /// `col` cuts in two, so an edit starting or ending there does not leave
+    /// half a character behind
+    fn split_wide_at(&mut self, row: usize, col: usize) {
+        if col == 0 || col >= self.cols || !self.is_wide_cell(row, col - 1) {
+            return;
+        }
+        for cell in &mut self.active_cells_mut().row_mut(row)[col - 1..col + 1] {
+            *cell = Cell { wrapped: cell.wrapped, ..Self::default_cell() };
+        }
+    }
+
+    /// Move the cells from column `col` to the right margin `n` columns right
+    /// (`insert`) or left in every row of the screen, blanking the vacated
+    /// columns (DECIC/DECDC)
+    fn shift_columns(&mut self, col: usize, n: usize, insert: bool) {
+        if n == 0 || col >= self.cols {
+            return;
+        }
+        let (cols, n) = (self.cols, n.min(self.cols - col));
+        // Wide characters cut by the shift or pushed off the line go whole,
+        // and so do the images over the moved columns
+        for row in 0..self.rows {
+            self.drop_images_over(row * cols + col..(row + 1) * cols);
+            self.split_wide_at(row, col);
+            self.split_wide_at(row, if insert { cols - n } else { col + n });
+        }
+        let blank = Self::default_cell();
+        let cells = self.active_cells_mut();
+        for row in 0..cells.row_count() {
+            if cells.is_blank_row(row) {
+                continue;
+            }
+            let row = cells.row_mut(row);
+            // Soft wraps belong to the row, not to the cell that moves away
+            let wrapped = row[cols - 1].wrapped;
+            let span = &mut row[col..];
+            if insert {
+                span.rotate_right(n);
+                span[..n].fill(blank);
+            } else {
+                span.rotate_left(n);
+                let len = span.len();
+                span[len - n..].fill(blank);
+            }
+            row[cols - 1].wrapped = wrapped;
+        }
+    }
+
+    /// Command line text typed after the OSC 133;B mark
+    fn command_line(&self) -> String {
+        let Some((row, col)) = self.command_input else {
+            return String::new();
+        };
+        let Some(cells) = self.absolute_row_cells(row) else {
+            return String::new();
+        };
+        self.cells_text(&cells[col.min(cells.len())..]).trim().to_string()
+    }
+
+    /// Stop timing the running command, queueing it for notification if it
+    /// ran past the threshold while the terminal was unfocused
+    fn finish_command(&mut self, exit_code: Option<i32>) {
+        if let Some(start) = self.output_start.take() {
+            self.last_output = Some((start, (self.absolute_row(self.row), self.col)));
+        }
+        let Some(finished) = self.commands.finish(exit_code, self.clock.now()) else {
+            return;
+        };
+        let long_running = self.config.command_notify_threshold_ms
+            .is_some_and(|ms| finished.duration >= Duration::from_millis(ms));
+        if long_running && !self.focused {
+            self.finished_commands.push(finished);
+        }
+    }
+
+    fn save_attrs(&self) -> SavedAttrs {
+        SavedAttrs {
+            fg: self.fg,
+            bg: self.bg,
+            fg_logical: self.fg_logical,
+            bg_logical: self.bg_logical,
+            bold: self.bold,
+            italic: self.italic,
+            underline: self.underline,
+            underline_style: self.underline_style,
+            dim: self.dim,
+            strikethrough: self.strikethrough,
+            overline: self.overline,
+            decoration: self.decoration,
+            decoration_logical: self.decoration_logical,
+            font: self.font,
+        }
+    }
+
+    fn restore_attrs(&mut self, attrs: SavedAttrs) {
+        self.fg = attrs.fg;
+        self.bg = attrs.bg;
+        self.fg_logical = attrs.fg_logical;
+        self.bg_logical = attrs.bg_logical;
+        self.bold = attrs.bold;
+        self.italic = attrs.italic;
+        self.underline = attrs.underline;
+        self.underline_style = attrs.underline_style;
+        self.dim = attrs.dim;
+        self.strikethrough = attrs.strikethrough;
+        self.overline = attrs.overline;
+        self.decoration = attrs.decoration;
+        self.decoration_logical = attrs.decoration_logical;
+        self.font = attrs.font;
+    }
+
+    /// Enable or disable alternate screen buffer
+    /// When enabled, switches to the alternate buffer and saves state
+    /// When disabled, switches back to primary buffer and restores state
+    pub fn use_alternate_screen(&mut self, enable: bool) {
+        if self.use_alternate_screen == enable {
+            return; // No change needed
+        }
+        self.wrap_pending = false;
+        // Images on the alternate screen do not outlive a switch, nor does an open region
+        self.alternate_images.clear();
+        self.region = NO_REGION;
+
+        if enable {
+            // Switch TO alternate screen - save primary state
+            self.primary_cursor = (self.row, self.col);
+            self.primary_attrs = self.save_attrs();
+            // The alternate screen has no history to scroll; keep the
+            // primary viewport for the way back
+            self.primary_viewport = (self.scroll_offset, self.unseen_rows);
+            self.scroll_offset = 0;
+            self.unseen_rows = 0;
+            // Switch to alternate state
+            self.use_alternate_screen = true;
+            (self.row, self.col) = self.alternate_cursor;
+            self.restore_attrs(self.alternate_attrs);
+        } else {
+            // Switch FROM alternate screen - save alternate state
+            self.alternate_cursor = (self.row, self.col);
+            // Full-screen apps leave their blink request behind on exit
+            self.cursor_blink_mode = None;
+            self.restart_cursor_blink();
+            self.alternate_attrs = self.save_attrs();
+            // Switch to primary state
+            self.use_alternate_screen = false;
+            (self.row, self.col) = self.primary_cursor;
+            self.restore_attrs(self.primary_attrs);
+            // Back where the user was reading, e.g. after quitting less;
+            // the scrollback may have been cleared meanwhile
+            (self.scroll_offset, self.unseen_rows) = self.primary_viewport;
+            self.scroll_offset = self.scroll_offset.min(self.scrollback_rows());
+        }
+        self.changes.record(GridChange::ScreenSwapped { alternate: enable });
+    }
+}
+
+impl AnsiGrid for Grid {
+    fn put(&mut self, ch: char) {
+        let policy = self.config.width_policy();
+        let width = policy.char_width(ch);
+
+        if width == 0 {
+            // Combining marks and variation selectors attach to the previous
+            // cell; VS16 may widen a text-default emoji to two columns
+            self.attach_combining(ch);
+            self.pending_advance = self.last_put.take()
+                .map_or(0, |base| policy.selector_widening(base, ch));
+            if self.pending_advance > 0 {
+                self.widen_previous();
+            }
+            return;
+        }
+
+        if std::mem::take(&mut self.wrap_pending) && self.auto_wrap {
+            self.soft_wrap();
+        }
+
+        // A wide character never straddles the right margin
+        if width > 1 && self.auto_wrap && self.col > 0 && self.col + width > self.cols {
+            self.soft_wrap();
+        }
+
+        self.pending_advance = width;
+        self.last_put = Some(ch);
+
+        if self.col < self.cols && self.row < self.rows {
+            if self.insert_mode {
+                self.insert_chars(width);
+            }
+            // Overwriting half of a wide character erases all of it, and
+            // overwriting part of an image removes the image
+            self.split_wide_at(self.row, self.col);
+            self.split_wide_at(self.row, self.col + width);
+            let start = self.row * self.cols + self.col;
+            self.drop_images_over(start..start + width.min(self.cols - self.col));
+
+            // Apply character set translation
+            let translated_ch = self.translate_char(ch);
+
+            // Store attributes
+            let wide = width > 1 && self.col + 1 < self.cols;
+            let fg = self.fg;
+            let bg = self.bg;
+            let bold = self.bold;
+            let italic = self.italic;
+            let underline = self.underline;
+            let underline_style = self.underline_style;
+            let dim = self.dim;
+            let strikethrough = self.strikethrough;
+            let overline = self.overline;
+            let font = self.font;
+            let fg_logical = self.fg_logical;
+            let bg_logical = self.bg_logical;
+            let decoration = self.decoration;
+            let decoration_logical = self.decoration_logical;
+            let hyperlink = self.hyperlink;
+            let protected = self.protected;
+            let region = self.region;
+
+            let cell = self.get_cell_mut(self.row, self.col);
+            *cell = Cell {
+                ch: translated_ch,
+                wide,
+                spacer: false,
+                fg,
+                bg,
+                bold,
+                italic,
+                underline,
+                underline_style,
+                dim,
+                strikethrough,
+                overline,
+                font,
+                fg_logical,
+                bg_logical,
+                decoration,
+                decoration_logical,
+                hyperlink,
+                region,
+                combining: NO_COMBINING,
+                wrapped: false,
+                protected,
+            };
+            if width > 1 && self.col + 1 < self.cols {
+                self.set_spacer(self.row, self.col + 1);
+            }
+        }
+    }
+
+    fn advance(&mut self) {
+        let n = std::mem::replace(&mut self.pending_advance, 1);
+        if n == 0 {
+            return;
+        }
+        self.col += n;
+        if self.col >= self.cols {
+            // The cursor stays on the last column; the wrap happens when the
+            // next character arrives, so a CR LF right after a full line
+            // does not leave an empty line behind
+            self.col = self.cols - 1;
+            self.wrap_pending = self.auto_wrap;
+        }
+    }
+
+    fn left(&mut self, n: usize) {
+        self.wrap_pending = false;
+        self.col = self.col.saturating_sub(n);
+    }
+    
+    fn right(&mut self, n: usize) {
+        self.wrap_pending = false;
+        self.col = (self.col + n).min(self.cols - 1);
+    }
+    
+    fn up(&mut self, n: usize) {
+        self.wrap_pending = false;
+        self.row = self.row.saturating_sub(n);
+    }
+    
+    fn down(&mut self, n: usize) {
+        self.wrap_pending = false;
+        self.row = (self.row + n).min(self.rows - 1);
+    }
+
+    fn newline(&mut self) {
+        self.wrap_pending = false;
+        self.col = 0;
+        self.row += 1;
+        if self.row >= self.rows {
+            // Move top row to scrollback
+            // The alternate screen has no scrollback
+            if !self.use_alternate_screen {
+                let top_row: Vec<Cell> = self.cells.row(0).to_vec();
+                self.push_scrollback_row(top_row);
+            }
+
+            // Scroll up, blanking the new bottom row; without scrollback the
+            // absolute rows stay put, so the images move up instead
+            let rows = self.rows;
+            self.active_cells_mut().scroll_up(0..rows, 1);
+            if self.use_alternate_screen {
+                self.shift_images(0..rows, -1);
+            }
+
+            self.row = self.rows - 1;
+            // Auto-scroll to bottom on new output
+            self.follow_output(if self.use_alternate_screen { 0 } else { 1 });
+        }
+    }
+
+    fn carriage_return(&mut self) {
+        self.wrap_pending = false;
         self.col = 0;
     }
     
     fn backspace(&mut self) {
         // Just move cursor left - don't erase
         // Bash will send \x1B[K to clear if needed
+        let reverse = self.reverse_wraparound && self.auto_wrap;
+        // With a wrap pending the cursor is logically past the last column;
+        // under reverse wraparound BS steps back onto it (as in xterm)
+        if std::mem::take(&mut self.wrap_pending) && reverse {
+            return;
+        }
         if self.col > 0 {
             self.col -= 1;
+        } else if reverse && self.row > 0 {
+            self.row -= 1;
+            self.col = self.cols - 1;
         }
     }
 
     fn move_rel(&mut self, dx: i32, dy: i32) {
+        self.wrap_pending = false;
         let new_col = (self.col as i32 + dx).max(0) as usize;
         let new_row = (self.row as i32 + dy).max(0) as usize;
         self.col = new_col.min(self.cols - 1);
@@ -752,35 +2737,84 @@ impl AnsiGrid for Grid {
     }
 
     fn move_abs(&mut self, row: usize, col: usize) {
+        self.wrap_pending = false;
         self.col = col.min(self.cols.saturating_sub(1));
         self.row = row.min(self.rows.saturating_sub(1));
     }
 
     fn clear_screen(&mut self) {
-        self.clear();
+        // Scroll on clear: keep what was on screen as history; the alternate
+        // screen never feeds the scrollback
+        let mut scrolled_rows = 0;
+        if self.config.scroll_on_clear && !self.use_alternate_screen {
+            scrolled_rows = (0..self.rows)
+                .rposition(|r| self.cells.row(r).iter().any(|c| c.ch != '\0' && c.ch != ' '))
+                .map_or(0, |r| r + 1);
+            for r in 0..scrolled_rows {
+                let row = self.cells.row(r).to_vec();
+                self.push_scrollback_row(row);
+            }
+        }
+
+        self.active_cells_mut().clear();
+        let screen_top = self.absolute_row(0);
+        self.active_images_mut().remove_from(screen_top);
+        self.col = 0;
+        self.row = 0;
+        self.wrap_pending = false;
+        self.follow_output(scrolled_rows);
+        self.selection.clear();
+    }
+
+    fn clear_scrollback(&mut self) {
+        self.record_scrollback_cleared();
+        self.rows_trimmed += self.scrollback_rows() as u64;
+        self.scrollback.clear();
+        self.scrollback_index.clear();
+        self.marks.prune_before(self.rows_trimmed);
+        self.annotations.prune_before(self.rows_trimmed);
+        self.highlights.prune_before(self.rows_trimmed);
+        self.images.prune_before(self.rows_trimmed);
+        self.scroll_offset = 0;
+    }
+
+    fn full_reset(&mut self) {
+        self.region = NO_REGION;
+        self.reset_user_keys();
+    }
+
+    fn soft_reset(&mut self) {
+        self.cursor_visible = true;
+        self.insert_mode = false;
+        self.application_cursor_keys = false;
+        self.application_keypad = false;
+        self.cursor_stack.clear();
+        self.reset_user_keys();
     }
 
     fn clear_line(&mut self) {
-        let default = Self::default_cell();
-        let start_idx = self.row * self.cols;
-        for i in 0..self.cols {
-            self.active_cells_mut()[start_idx + i] = default;
-        }
+        let row = self.row;
+        self.drop_images_over(row * self.cols..(row + 1) * self.cols);
+        self.active_cells_mut().clear_row(row);
     }
 
     fn clear_line_right(&mut self) {
+        self.split_wide_at(self.row, self.col);
         let default = Self::default_cell();
         let start_idx = self.row * self.cols + self.col;
         let end_idx = (self.row + 1) * self.cols;
+        self.drop_images_over(start_idx..end_idx);
         for i in start_idx..end_idx {
             self.active_cells_mut()[i] = default;
         }
     }
 
     fn clear_line_left(&mut self) {
+        self.split_wide_at(self.row, self.col + 1);
         let default = Self::default_cell();
         let start_idx = self.row * self.cols;
         let end_idx = self.row * self.cols + self.col + 1;
+        self.drop_images_over(start_idx..end_idx);
         for i in start_idx..end_idx {
             self.active_cells_mut()[i] = default;
         }
@@ -789,59 +2823,137 @@ impl AnsiGrid for Grid {
     fn clear_screen_down(&mut self) {
         // Clear from cursor to end of screen
         self.clear_line_right();
-        let default = Self::default_cell();
-        let start_idx = (self.row + 1) * self.cols;
-        let end_idx = self.rows * self.cols;
-        for i in start_idx..end_idx {
-            self.active_cells_mut()[i] = default;
+        self.drop_images_over((self.row + 1) * self.cols..self.rows * self.cols);
+        for row in self.row + 1..self.rows {
+            self.active_cells_mut().clear_row(row);
         }
     }
 
     fn clear_screen_up(&mut self) {
         // Clear from top of screen to cursor
         self.clear_line_left();
-        let default = Self::default_cell();
-        let end_idx = self.row * self.cols;
-        for i in 0..end_idx {
-            self.active_cells_mut()[i] = default;
+        self.drop_images_over(0..self.row * self.cols);
+        for row in 0..self.row {
+            self.active_cells_mut().clear_row(row);
         }
     }
 
     fn reset_attrs(&mut self) {
         self.fg = crate::constants::DEFAULT_FG;
         self.bg = crate::constants::DEFAULT_BG;
+        self.fg_logical = LogicalColor::Default;
+        self.bg_logical = LogicalColor::Default;
         self.bold = false;
         self.italic = false;
         self.underline = false;
+        self.underline_style = UnderlineStyle::None;
         self.dim = false;
+        self.strikethrough = false;
+        self.overline = false;
+        self.decoration_logical = LogicalColor::Default;
+        self.font = crate::ansi::PRIMARY_FONT;
     }
 
     fn set_bold(&mut self, bold: bool) {
-        if self.config.bold_is_bright && bold && !self.bold {
-            // When enabling bold and bold_is_bright is enabled, brighten basic ANSI colors
-            self.fg = brighten_color(self.fg);
-        }
+        // Bold-is-bright is applied at render time (Cell::display_fg), so the
+        // stored color survives SGR 22
         self.bold = bold;
     }
     
     fn set_italic(&mut self, italic: bool) {
         self.italic = italic;
     }
+
+    fn set_strikethrough(&mut self, strikethrough: bool) {
+        self.strikethrough = strikethrough;
+    }
+
+    fn set_overline(&mut self, overline: bool) {
+        self.overline = overline;
+    }
+
+    fn set_decoration_color(&mut self, color: Option<Color>) {
+        if !self.config.color_env.applies_colors() {
+            return;
+        }
+        match color {
+            Some(color) => {
+                self.decoration = color;
+                self.decoration_logical = LogicalColor::Rgb;
+            }
+            None => self.decoration_logical = LogicalColor::Default,
+        }
+    }
+
+    fn set_decoration_indexed(&mut self, index: u8) {
+        if !self.config.color_env.applies_colors() {
+            return;
+        }
+        self.decoration = palette_color(index);
+        self.decoration_logical = LogicalColor::Indexed(index);
+    }
     
     fn set_underline(&mut self, underline: bool) {
-        self.underline = underline;
+        self.set_underline_style(if underline { UnderlineStyle::Single } else { UnderlineStyle::None });
+    }
+
+    fn set_underline_style(&mut self, style: UnderlineStyle) {
+        self.underline_style = style;
+        self.underline = style != UnderlineStyle::None;
     }
     
     fn set_dim(&mut self, dim: bool) {
         self.dim = dim;
     }
     
+    fn set_font(&mut self, slot: u8) {
+        self.font = slot;
+    }
+
+    fn get_font(&self) -> u8 {
+        self.font
+    }
+
     fn set_fg(&mut self, color: Color) {
+        if !self.config.color_env.applies_colors() {
+            return;
+        }
         self.fg = color;
+        self.fg_logical = LogicalColor::Rgb;
     }
     
     fn set_bg(&mut self, color: Color) {
+        if !self.config.color_env.applies_colors() {
+            return;
+        }
         self.bg = color;
+        self.bg_logical = LogicalColor::Rgb;
+    }
+
+    fn set_fg_indexed(&mut self, index: u8) {
+        if !self.config.color_env.applies_colors() {
+            return;
+        }
+        self.fg = palette_color(index);
+        self.fg_logical = LogicalColor::Indexed(index);
+    }
+
+    fn set_bg_indexed(&mut self, index: u8) {
+        if !self.config.color_env.applies_colors() {
+            return;
+        }
+        self.bg = palette_color(index);
+        self.bg_logical = LogicalColor::Indexed(index);
+    }
+
+    fn set_fg_default(&mut self) {
+        self.fg = self.config.default_fg;
+        self.fg_logical = LogicalColor::Default;
+    }
+
+    fn set_bg_default(&mut self) {
+        self.bg = self.config.default_bg;
+        self.bg_logical = LogicalColor::Default;
     }
 
     fn get_fg(&self) -> Color {
@@ -853,13 +2965,15 @@ impl AnsiGrid for Grid {
     }
 
     fn save_cursor(&mut self) {
-        self.cursor_stack.push((self.row, self.col));
+        self.cursor_stack.push(SavedCursor { row: self.row, col: self.col, protected: self.protected });
     }
 
     fn restore_cursor(&mut self) {
-        if let Some((row, col)) = self.cursor_stack.pop() {
-            self.row = row;
-            self.col = col;
+        self.wrap_pending = false;
+        if let Some(saved) = self.cursor_stack.pop() {
+            self.row = saved.row;
+            self.col = saved.col;
+            self.protected = saved.protected;
         }
     }
 
@@ -867,39 +2981,31 @@ impl AnsiGrid for Grid {
         self.cursor_visible = visible;
     }
 
+    fn set_cursor_blink(&mut self, enable: bool) {
+        self.cursor_blink_mode = Some(enable);
+        self.restart_cursor_blink();
+    }
+
+    fn use_alternate_screen(&mut self, enable: bool) {
+        if enable && self.disabled_modes.alternate_screen {
+            return;
+        }
+        Grid::use_alternate_screen(self, enable);
+    }
+
     fn scroll_up(&mut self, n: usize) {
         if n == 0 {
             return;
         }
         if n >= self.rows {
-            self.clear_screen();
+            self.clear();
             return;
         }
 
-        let cols = self.cols; // Avoid borrowing issues with self.cols
-
-        // Move content up by n rows
-        for r in 0..(self.rows - n) {
-            let src_start = (r + n) * cols;
-            let dst_start = r * cols;
-            if self.use_alternate_screen {
-                self.alternate_cells.copy_within(src_start..(src_start + cols), dst_start);
-            } else {
-                self.cells.copy_within(src_start..(src_start + cols), dst_start);
-            }
-        }
-
-        // Clear bottom n rows
-        for r in (self.rows - n)..self.rows {
-            for c in 0..cols {
-                let idx = r * cols + c;
-                if self.use_alternate_screen {
-                    self.alternate_cells[idx] = Self::default_cell();
-                } else {
-                    self.cells[idx] = Self::default_cell();
-                }
-            }
-        }
+        // Move content up by n rows, blanking the bottom n
+        let rows = self.rows;
+        self.active_cells_mut().scroll_up(0..rows, n);
+        self.shift_images(0..rows, -(n as i64));
     }
 
     fn scroll_down(&mut self, n: usize) {
@@ -907,100 +3013,34 @@ impl AnsiGrid for Grid {
             return;
         }
         if n >= self.rows {
-            self.clear_screen();
+            self.clear();
             return;
         }
 
-        let cols = self.cols; // Avoid borrowing issues with self.cols
-
-        // Move content down by n rows
-        for r in (0..(self.rows - n)).rev() {
-            let dst_start = (r + n) * cols;
-            let src_start = r * cols;
-            if self.use_alternate_screen {
-                self.alternate_cells.copy_within(src_start..(src_start + cols), dst_start);
-            } else {
-                self.cells.copy_within(src_start..(src_start + cols), dst_start);
-            }
-        }
-
-        // Clear top n rows
-        for r in 0..n {
-            for c in 0..cols {
-                let idx = r * cols + c;
-                if self.use_alternate_screen {
-                    self.alternate_cells[idx] = Self::default_cell();
-                } else {
-                    self.cells[idx] = Self::default_cell();
-                }
-            }
-        }
+        // Move content down by n rows, blanking the top n
+        let rows = self.rows;
+        self.active_cells_mut().scroll_down(0..rows, n);
+        self.shift_images(0..rows, n as i64);
     }
 
     fn insert_lines(&mut self, n: usize) {
         if n == 0 {
             return;
         }
-        let n_clamped = n.min(self.rows - self.row);
-        let cols = self.cols; // Avoid borrowing issues with self.cols
-        let start_row = self.row;
-        let end_row = self.rows - n_clamped;
-
-        // Shift rows below current row down by n_clamped
-        for r in (start_row..end_row).rev() {
-            let dst_start = (r + n_clamped) * cols;
-            let src_start = r * cols;
-            if self.use_alternate_screen {
-                self.alternate_cells.copy_within(src_start..(src_start + cols), dst_start);
-            } else {
-                self.cells.copy_within(src_start..(src_start + cols), dst_start);
-            }
-        }
-
-        // Clear inserted rows
-        for r in start_row..(start_row + n_clamped) {
-            for c in 0..cols {
-                let idx = r * cols + c;
-                if self.use_alternate_screen {
-                    self.alternate_cells[idx] = Self::default_cell();
-                } else {
-                    self.cells[idx] = Self::default_cell();
-                }
-            }
-        }
+        // Shift rows from the cursor row down, blanking the inserted ones
+        let (row, rows) = (self.row, self.rows);
+        self.active_cells_mut().scroll_down(row..rows, n);
+        self.shift_images(row..rows, n as i64);
     }
 
     fn delete_lines(&mut self, n: usize) {
         if n == 0 {
             return;
         }
-        let n_clamped = n.min(self.rows - self.row);
-        let cols = self.cols; // Avoid borrowing issues with self.cols
-        let start_row = self.row;
-        let end_row = self.rows;
-
-        // Shift rows up by n_clamped
-        for r in start_row..end_row {
-            if r + n_clamped < self.rows {
-                let dst_start = r * cols;
-                let src_start = (r + n_clamped) * cols;
-                if self.use_alternate_screen {
-                    self.alternate_cells.copy_within(src_start..(src_start + cols), dst_start);
-                } else {
-                    self.cells.copy_within(src_start..(src_start + cols), dst_start);
-                }
-            } else {
-                // Clear row
-                for c in 0..cols {
-                    let idx = r * cols + c;
-                    if self.use_alternate_screen {
-                        self.alternate_cells[idx] = Self::default_cell();
-                    } else {
-                        self.cells[idx] = Self::default_cell();
-                    }
-                }
-            }
-        }
+        // Shift rows below the deleted ones up, blanking the bottom
+        let (row, rows) = (self.row, self.rows);
+        self.active_cells_mut().scroll_up(row..rows, n);
+        self.shift_images(row..rows, -(n as i64));
     }
 
     fn insert_chars(&mut self, n: usize) {
@@ -1008,7 +3048,12 @@ impl AnsiGrid for Grid {
             return;
         }
         let n_clamped = n.min(self.cols - self.col);
+        // Neither the character split by the insertion point nor one whose
+        // right half is pushed off the line survives in halves
+        self.split_wide_at(self.row, self.col);
+        self.split_wide_at(self.row, self.cols - n_clamped);
         let row_start = self.row * self.cols;
+        self.drop_images_over(row_start + self.col..row_start + self.cols);
         let insert_pos = self.col;
         let row_end = self.cols;
 
@@ -1047,7 +3092,10 @@ impl AnsiGrid for Grid {
             return;
         }
         let n_clamped = n.min(self.cols - self.col);
+        self.split_wide_at(self.row, self.col);
+        self.split_wide_at(self.row, self.col + n_clamped);
         let row_start = self.row * self.cols;
+        self.drop_images_over(row_start + self.col..row_start + self.cols);
         let end_col = self.cols - n_clamped;
 
         // Shift left to cursor position
@@ -1071,17 +3119,52 @@ impl AnsiGrid for Grid {
         }
     }
 
+    fn insert_columns(&mut self, n: usize) {
+        self.shift_columns(self.col, n, true);
+    }
+
+    fn delete_columns(&mut self, n: usize) {
+        self.shift_columns(self.col, n, false);
+    }
+
     fn erase_chars(&mut self, n: usize) {
         if n == 0 {
             return;
         }
         let row_start = self.row * self.cols;
         let end_idx = (self.col + n).min(self.cols);
+        self.split_wide_at(self.row, self.col);
+        self.split_wide_at(self.row, end_idx);
+        self.drop_images_over(row_start + self.col..row_start + end_idx);
         for idx in row_start + self.col..row_start + end_idx {
             self.active_cells_mut()[idx] = Self::default_cell();
         }
     }
 
+    fn set_protected(&mut self, protected: bool) {
+        self.protected = protected;
+    }
+
+    fn selective_erase_display(&mut self, mode: usize) {
+        let cursor = self.row * self.cols + self.col;
+        match mode {
+            0 => self.selective_erase(cursor..self.rows * self.cols),
+            1 => self.selective_erase(0..cursor + 1),
+            2 => self.selective_erase(0..self.rows * self.cols),
+            _ => {}
+        }
+    }
+
+    fn selective_erase_line(&mut self, mode: usize) {
+        let row_start = self.row * self.cols;
+        match mode {
+            0 => self.selective_erase(row_start + self.col..row_start + self.cols),
+            1 => self.selective_erase(row_start..row_start + self.col + 1),
+            2 => self.selective_erase(row_start..row_start + self.cols),
+            _ => {}
+        }
+    }
+
     fn set_insert_mode(&mut self, enable: bool) {
         self.insert_mode = enable;
     }
@@ -1090,14 +3173,200 @@ impl AnsiGrid for Grid {
         self.auto_wrap = enable;
     }
 
+    fn set_reverse_wraparound(&mut self, enable: bool) {
+        self.reverse_wraparound = enable;
+    }
+
     fn set_title(&mut self, title: &str) {
-        self.title = title.to_string();
+        match self.config.security.title_policy.apply(title) {
+            Some(title) => self.title = title,
+            None => tracing::debug!("Ignoring title outside the allowlist: {:?}", title),
+        }
+    }
+
+    fn query_dynamic_color(&self, which: DynamicColor) -> Option<Color> {
+        match which {
+            DynamicColor::Foreground => Some(self.config.default_fg),
+            DynamicColor::Cursor => Some(self.config.cursor_color.unwrap_or(self.config.default_fg)),
+            DynamicColor::Background => Some(self.config.default_bg),
+        }
+    }
+
+    fn set_color_scheme_reporting(&mut self, enable: bool) {
+        if self.input_modes_filtered() {
+            return;
+        }
+        self.color_scheme_reporting = enable;
+    }
+
+    fn set_mouse_reporting_mode(&mut self, mode: u16, enable: bool) {
+        if self.input_modes_filtered() {
+            return;
+        }
+        match mode {
+            1005 | 1006 => {
+                let encoding = if mode == 1005 { MouseEncoding::Utf8 } else { MouseEncoding::Sgr };
+                if enable {
+                    self.mouse_encoding = encoding;
+                } else if self.mouse_encoding == encoding {
+                    self.mouse_encoding = MouseEncoding::Default;
+                }
+            }
+            // Like xterm, resetting any tracking mode turns tracking off
+            1000 | 1002 | 1003 if !enable => self.mouse_tracking = MouseTracking::Off,
+            1000 => self.mouse_tracking = MouseTracking::Click,
+            1002 => self.mouse_tracking = MouseTracking::Drag,
+            1003 => self.mouse_tracking = MouseTracking::Motion,
+            _ => {}
+        }
+    }
+
+    fn set_focus_reporting(&mut self, enable: bool) {
+        if self.input_modes_filtered() {
+            return;
+        }
+        self.focus_reporting = enable;
+    }
+
+    fn set_locator_reporting(&mut self, mode: u16, pixels: bool) {
+        if self.input_modes_filtered() {
+            return;
+        }
+        self.locator.set_reporting(mode, pixels);
+    }
+
+    fn select_locator_events(&mut self, events: &[u16]) {
+        self.locator.select_events(events);
+    }
+
+    fn request_locator_position(&mut self) {
+        if let Some(report) = self.locator.request() {
+            self.responses.push_str(&report);
+        }
+    }
+
+    fn report_color_scheme(&mut self) {
+        self.responses.push_str(self.config.applied_color_scheme().report());
+    }
+
+    fn bell(&mut self) {
+        self.bells = self.bells.wrapping_add(1);
+        if let Some(action) = self.bell_gate.ring(self.focused, self.clock.now()) {
+            self.pending_bell.get_or_insert(action);
+        }
+    }
+
+    fn write_response(&mut self, response: &str) {
+        self.responses.push_str(response);
+    }
+
+    fn handle_semantic_prompt(&mut self, kind: char, params: &str) {
+        match kind {
+            'A' => {
+                // A new prompt also ends a command whose shell sent no D
+                self.finish_command(None);
+                self.add_mark(MarkCategory::Prompt);
+            }
+            'B' => self.command_input = Some((self.absolute_row(self.row), self.col)),
+            'C' => {
+                let command = self.command_line();
+                self.commands.start(command, self.clock.now());
+                if !self.use_alternate_screen {
+                    self.output_start = Some((self.absolute_row(self.row), self.col));
+                }
+            }
+            'D' => {
+                let exit_code = params.split(';').next().and_then(|code| code.parse().ok());
+                self.last_command_status = exit_code;
+                if let Some(code) = exit_code {
+                    self.marks.set_last_prompt_exit_code(code);
+                }
+                self.finish_command(exit_code);
+            }
+            _ => {}
+        }
+    }
+
+    fn set_pointer_shape(&mut self, name: &str) {
+        // Names outside the allowlist are ignored
+        if name.is_empty() {
+            self.pointer.request(None);
+        } else if let Some(shape) = app_pointer_shape(name) {
+            self.pointer.request(Some(shape));
+        }
+    }
+
+    fn push_sgr(&mut self, attrs: &[u16]) {
+        // Unbalanced pushes must not grow without bound; the oldest save goes
+        if self.sgr_stack.len() >= crate::constants::MAX_SGR_STACK_DEPTH {
+            self.sgr_stack.remove(0);
+        }
+        self.sgr_stack.push((self.save_attrs(), attrs.to_vec()));
+    }
+
+    fn pop_sgr(&mut self) {
+        let Some((saved, attrs)) = self.sgr_stack.pop() else {
+            return;
+        };
+        if attrs.is_empty() {
+            self.restore_attrs(saved);
+            return;
+        }
+        for attr in attrs {
+            match attr {
+                1 => self.bold = saved.bold,
+                2 => self.dim = saved.dim,
+                3 => self.italic = saved.italic,
+                4 | 21 => {
+                    self.underline = saved.underline;
+                    self.underline_style = saved.underline_style;
+                }
+                9 => self.strikethrough = saved.strikethrough,
+                30 => {
+                    self.fg = saved.fg;
+                    self.fg_logical = saved.fg_logical;
+                }
+                31 => {
+                    self.bg = saved.bg;
+                    self.bg_logical = saved.bg_logical;
+                }
+                _ => {}
+            }
+        }
     }
 
     fn set_bracketed_paste_mode(&mut self, enable: bool) {
+        if self.input_modes_filtered() {
+            return;
+        }
         self.bracketed_paste_mode = enable;
     }
 
+    fn set_application_cursor_keys(&mut self, enable: bool) {
+        self.application_cursor_keys = enable;
+    }
+
+    fn set_keypad_mode(&mut self, application: bool) {
+        self.application_keypad = application;
+    }
+
+    fn designate_charset(&mut self, slot: u8, charset: char) {
+        match slot {
+            0 => self.g0_charset = charset,
+            1 => self.g1_charset = charset,
+            2 => self.g2_charset = charset,
+            _ => self.g3_charset = charset,
+        }
+    }
+
+    fn invoke_charset(&mut self, slot: u8) {
+        self.gl_set = slot;
+    }
+
+    fn single_shift(&mut self, slot: u8) {
+        self.single_shift = Some(slot);
+    }
+
     fn set_origin_mode(&mut self, enable: bool) {
         self.origin_mode = enable;
     }
@@ -1107,9 +3376,132 @@ impl AnsiGrid for Grid {
         // For now, clipboards are handled via OSC 52 sequences parsed at terminal level
     }
 
-    fn handle_hyperlink(&mut self, _params: Option<&str>, _uri: &str) {
-        // Placeholder - hyperlinks would require Cell hyperlink field
-        // For now, hyperlinks are handled via OSC 8 sequences parsed at terminal level
+    fn query_termcap(&self, name: &str) -> Option<TermcapValue> {
+        crate::termcap::lookup(name, &self.config.term)
+    }
+
+    fn request_clipboard(&mut self, selection: &str, terminator: &str) {
+        if self.osc_replay || self.config.security.osc_action(OscCategory::ClipboardRead) != OscAction::Deny {
+            self.clipboard_reads.request(selection, terminator);
+        }
+    }
+
+    fn allow_osc(&mut self, category: OscCategory, osc: &str) -> bool {
+        if self.osc_replay {
+            return true;
+        }
+        if self.feed_filter.as_ref().is_some_and(|filter| !filter.allows_osc(category)) {
+            return false;
+        }
+        // Ending a link plants nothing, and a held link must not outlive it
+        let hyperlink_close = category == OscCategory::Hyperlink && osc.splitn(3, ';').nth(2).unwrap_or("").is_empty();
+        if hyperlink_close {
+            self.held_hyperlink = None;
+            return true;
+        }
+        let action = match category {
+            // Unregistered custom codes are ignored rather than denied
+            OscCategory::Custom => {
+                let code = osc.split(';').next().and_then(|code| code.parse().ok());
+                if !code.is_some_and(|code| self.config.security.osc_policy.handles_custom(code)) {
+                    return false;
+                }
+                self.config.security.osc_action(category)
+            }
+            // Each clipboard read is confirmed on its own (see `clipboard`)
+            OscCategory::ClipboardRead if self.config.security.osc_action(category) == OscAction::Ask => OscAction::Allow,
+            _ => self.config.security.osc_action(category),
+        };
+        let now = self.clock.now();
+        let allowed = self.osc_gate.check(category, action, osc, now);
+        if category == OscCategory::Hyperlink {
+            // A link held or denied still ends the one before it
+            self.held_hyperlink = (!allowed && action == OscAction::Ask).then(|| osc.to_string());
+            if !allowed {
+                self.hyperlink = NO_HYPERLINK;
+            }
+        }
+        allowed
+    }
+
+    fn allow_dcs(&mut self, _dcs: &str) -> bool {
+        !self.feed_filter.as_ref().is_some_and(|filter| filter.dcs)
+    }
+
+    fn sixel(&mut self, params: &str, data: &str) {
+        let channel = |v: f64| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        let background = [channel(self.bg.r), channel(self.bg.g), channel(self.bg.b), channel(self.bg.a)];
+        match crate::sixel::decode(params, data, background) {
+            Some(image) => self.place_image(image),
+            None => tracing::debug!("Ignoring sixel image without pixels"),
+        }
+    }
+
+    fn set_current_directory(&mut self, directory: &str) {
+        match WorkingDirectory::parse(directory) {
+            Some(dir) => self.current_directory = Some(dir),
+            None => tracing::debug!("Ignoring malformed OSC 7 working directory {:?}", directory),
+        }
+    }
+
+    fn define_user_keys(&mut self, keys: &[(u16, String)], clear: bool, lock: bool) {
+        if !self.config.security.allow_user_keys {
+            tracing::debug!("Ignoring DECUDK: user-defined keys are not allowed");
+            return;
+        }
+        if !std::sync::Arc::make_mut(&mut self.user_keys).define(keys, clear, lock) {
+            tracing::debug!("Ignoring DECUDK: user-defined keys are locked");
+        }
+    }
+
+    fn show_notification(&mut self, title: &str, body: &str) {
+        if self.notifications.len() >= crate::constants::MAX_QUEUED_OSC_EVENTS {
+            tracing::debug!("Dropping notification {:?}: too many awaiting the embedder", title);
+            return;
+        }
+        self.notifications.push((title.to_string(), body.to_string()));
+    }
+
+    fn handle_custom_osc(&mut self, code: u16, data: &str) {
+        if self.custom_osc.len() >= crate::constants::MAX_QUEUED_OSC_EVENTS {
+            tracing::debug!("Dropping custom OSC {}: too many awaiting the embedder", code);
+            return;
+        }
+        self.custom_osc.push((code, data.to_string()));
+    }
+
+    fn handle_active_region(&mut self, payload: &str) {
+        // Without the opt-in, or for an invalid payload, the text stays plain
+        let allowed = self.config.security.allow_active_regions && crate::regions::valid_payload(payload);
+        self.region = if allowed {
+            self.regions.intern(None, payload).or_else(|| {
+                // Full: make room by dropping the regions no cell is part of
+                let referenced = self.referenced_ids(|cell| cell.region);
+                self.regions.retain(|id| referenced.contains(&id));
+                self.regions.intern(None, payload)
+            }).unwrap_or(NO_REGION)
+        } else {
+            NO_REGION
+        };
+    }
+
+    fn handle_hyperlink(&mut self, params: Option<&str>, uri: &str) {
+        // An empty URI closes the link; targets failing validation (unknown
+        // schemes, oversized) are shown as plain text
+        let data = format!("{};{}", params.unwrap_or(""), uri);
+        self.hyperlink = if !uri.is_empty() && crate::security::validate_osc_sequence("8", &data) {
+            self.hyperlinks.intern(params, uri).or_else(|| {
+                // Full: make room by dropping the links no cell refers to
+                let referenced = self.referenced_ids(|cell| cell.hyperlink);
+                self.hyperlinks.retain(|id| referenced.contains(&id));
+                self.hyperlinks.intern(params, uri)
+            }).unwrap_or_else(|| {
+                tracing::debug!("Showing OSC 8 link as plain text: {} links still on screen", self.hyperlinks.len());
+                NO_HYPERLINK
+            })
+        } else {
+            NO_HYPERLINK
+        };
     }
 }
 
@@ -1137,7 +3529,7 @@ mod tests {
         assert_eq!(grid.col, 0);
         assert_eq!(grid.row, 0);
         assert!(!grid.use_alternate_screen);
-        assert!(grid.scrollback.is_empty());
+        assert_eq!(grid.scrollback_rows(), 0);
     }
 
     #[test]
@@ -1199,6 +3591,7 @@ mod tests {
             italic: false,
             underline: false,
             dim: false,
+            ..Default::default()
         };
 
         *grid.get_cell_mut(1, 2) = test_cell.clone();
@@ -1214,7 +3607,8 @@ mod tests {
 
     #[test]
     fn test_clear_operations() {
-        let mut grid = grid_new(5, 5);
+        let config = crate::config::TerminalConfig::default().with_scroll_on_clear(false);
+        let mut grid = Grid::new(5, 5, std::sync::Arc::new(config));
 
         // Put some content
         *grid.get_cell_mut(0, 0) = Cell { ch: 'A', ..Default::default() };
@@ -1231,7 +3625,7 @@ mod tests {
         grid.clear_screen();
         assert_eq!(grid.col, 0);
         assert_eq!(grid.row, 0);
-        assert!(grid.scrollback.is_empty());
+        assert_eq!(grid.scrollback_rows(), 0);
     }
 
     #[test]
@@ -1258,7 +3652,7 @@ mod tests {
         assert_eq!(grid.get_cell(2, 0).ch, '\0');
 
         // Scroll operations don't create scrollback (only newlines do)
-        assert!(grid.scrollback.is_empty());
+        assert_eq!(grid.scrollback_rows(), 0);
     }
 
     #[test]
@@ -1375,6 +3769,30 @@ mod tests {
         assert_eq!(grid.get_cell(1, 1).ch, 'R');
     }
 
+    #[test]
+    fn test_alternate_screen_restores_scroll_position() {
+        let config = std::sync::Arc::new(crate::config::TerminalConfig::default());
+        let mut grid = Grid::new(10, 3, config);
+        let mut parser = AnsiParser::new();
+        for i in 0..10 {
+            parser.feed_str(&format!("line {}\r\n", i), &mut grid);
+        }
+        assert!(grid.scroll_wheel(-2.0, false));
+        let offset = grid.scroll_offset;
+        assert!(offset > 0);
+
+        // A pager shows on the live alternate screen, quitting returns to history
+        parser.feed_str("\x1b[?1049hpager\r\n\x1b[?1049l", &mut grid);
+        assert_eq!(grid.scroll_offset, offset);
+
+        // History cleared meanwhile: the offset is clamped
+        parser.feed_str("\x1b[?1049h", &mut grid);
+        assert_eq!(grid.scroll_offset, 0);
+        grid.scrollback.clear();
+        parser.feed_str("\x1b[?1049l", &mut grid);
+        assert_eq!(grid.scroll_offset, 0);
+    }
+
     #[test]
     fn test_cursor_save_restore() {
         let config = std::sync::Arc::new(crate::config::TerminalConfig::default());
@@ -1430,8 +3848,8 @@ mod tests {
         grid.put('D'); grid.newline(); // This should cause scroll
 
         // Should have scrolled A from row 0 to scrollback
-        assert_eq!(grid.scrollback[0].ch, 'A');
-        assert_eq!(grid.scrollback[1].ch, 'B');
+        assert_eq!(grid.scrollback_row(0).unwrap()[0].ch, 'A');
+        assert_eq!(grid.scrollback_row(0).unwrap()[1].ch, 'B');
 
         // Row 0 should now have C D
         assert_eq!(grid.get_cell(0, 0).ch, 'C');
@@ -1498,6 +3916,215 @@ mod tests {
         assert!(grid.is_cursor_visible());
     }
 
+    #[test]
+    fn test_cursor_blink_phase_follows_clock() {
+        use crate::clock::MockClock;
+        let config = crate::config::TerminalConfig::default().with_cursor_blink(true);
+        let interval = Duration::from_millis(config.cursor_blink_interval_ms);
+        let mut grid = Grid::new(5, 5, std::sync::Arc::new(config));
+        let clock = MockClock::new();
+        grid.set_clock(std::sync::Arc::new(clock.clone()));
+
+        assert!(!grid.tick_cursor_blink());
+        clock.advance(interval - Duration::from_millis(1));
+        assert!(!grid.tick_cursor_blink());
+        assert!(grid.is_cursor_visible());
+        clock.advance(Duration::from_millis(1));
+        assert!(grid.tick_cursor_blink());
+        assert!(!grid.is_cursor_visible());
+        // A late tick lands in the right phase
+        clock.advance(interval * 3);
+        grid.tick_cursor_blink();
+        assert!(grid.is_cursor_visible());
+
+        // DECSET 12 restarts the cycle with the cursor on
+        clock.advance(interval);
+        grid.tick_cursor_blink();
+        crate::ansi::AnsiParser::new().feed_str("\x1b[?12h", &mut grid);
+        assert!(grid.is_cursor_visible());
+        clock.advance(interval / 2);
+        assert!(!grid.tick_cursor_blink());
+    }
+
+    #[test]
+    fn test_time_travel_snapshots() {
+        use crate::ansi::AnsiParser;
+        use crate::clock::MockClock;
+        let config = crate::config::TerminalConfig::default().with_snapshots(1000, 4);
+        let mut grid = Grid::new(10, 2, std::sync::Arc::new(config));
+        let clock = MockClock::new();
+        grid.set_clock(std::sync::Arc::new(clock.clone()));
+        let start = clock.now();
+        let mut parser = AnsiParser::new();
+
+        parser.feed_str("one", &mut grid);
+        grid.commit_generation();
+        clock.advance(Duration::from_millis(500));
+        parser.feed_str("\x1b[2J\x1b[Hhalf", &mut grid);
+        grid.commit_generation();
+        // Replaced within the interval: only the last screen of it is kept
+        clock.advance(Duration::from_millis(100));
+        parser.feed_str("\x1b[2J\x1b[Hlast", &mut grid);
+        grid.commit_generation();
+        clock.advance(Duration::from_millis(400));
+        parser.feed_str("\x1b[2J\x1b[Htwo", &mut grid);
+        grid.commit_generation();
+
+        assert_eq!(grid.snapshot_at(start).map(|s| s.text()), Some("one\n".to_string()));
+        assert_eq!(grid.snapshot_at(start + Duration::from_millis(700)).map(|s| s.text()), Some("last\n".to_string()));
+        assert_eq!(grid.snapshot_at(clock.now()).map(|s| s.cursor), Some((0, 3)));
+
+        assert!(grid.step_snapshot_back());
+        assert_eq!(grid.shown_snapshot().map(|s| s.text()), Some("last\n".to_string()));
+        assert!(grid.step_snapshot_back());
+        assert_eq!(grid.shown_snapshot().map(|s| s.text()), Some("one\n".to_string()));
+        assert!(!grid.step_snapshot_back());
+        assert!(grid.step_snapshot_forward());
+        assert!(grid.step_snapshot_forward());
+        assert!(grid.shown_snapshot().is_none());
+        assert!(!grid.step_snapshot_forward());
+
+        // Disabled by default
+        let mut grid = grid_new(2, 10);
+        parser.feed_str("x", &mut grid);
+        grid.commit_generation();
+        assert!(grid.snapshot_at(Instant::now()).is_none());
+    }
+
+    #[test]
+    fn test_double_click_detection() {
+        use crate::clock::MockClock;
+        let mut grid = Grid::new(10, 5, config());
+        let clock = MockClock::new();
+        grid.set_clock(std::sync::Arc::new(clock.clone()));
+        let timeout = Duration::from_millis(grid.config.click_timeout_ms as u64);
+
+        assert_eq!(grid.register_click(1, 2), 1);
+        clock.advance(timeout - Duration::from_millis(1));
+        assert_eq!(grid.register_click(1, 2), 2);
+        assert_eq!(grid.register_click(1, 2), 3);
+        assert_eq!(grid.register_click(1, 2), 1);
+
+        // Too slow, or on another cell, starts over
+        clock.advance(timeout);
+        assert_eq!(grid.register_click(1, 2), 1);
+        assert_eq!(grid.register_click(1, 3), 1);
+    }
+
+    #[test]
+    fn test_click_timeout_and_drag_threshold_from_config() {
+        use crate::clock::MockClock;
+        let config = crate::config::TerminalConfig::default().with_click_timeout(500).with_drag_threshold(6.0);
+        let mut grid = Grid::new(10, 5, std::sync::Arc::new(config));
+        let clock = MockClock::new();
+        grid.set_clock(std::sync::Arc::new(clock.clone()));
+
+        // Small moves and a release within the timeout make a click
+        grid.start_selection_at(1, 2, 25.0, 25.0);
+        grid.update_selection_at(1, 3, 30.0, 25.0);
+        assert!(!grid.is_dragging());
+        clock.advance(Duration::from_millis(499));
+        assert!(!grid.complete_selection(1, 3));
+        assert!(!grid.has_selection());
+
+        // A long press selects its cell
+        grid.start_selection_at(1, 2, 25.0, 25.0);
+        clock.advance(Duration::from_millis(500));
+        assert!(grid.complete_selection(1, 2));
+
+        // Past the threshold the press drags
+        grid.start_selection_at(1, 2, 25.0, 25.0);
+        grid.update_selection_at(1, 3, 31.0, 25.0);
+        assert!(grid.is_dragging());
+        assert!(grid.complete_selection(1, 3));
+    }
+
+    #[test]
+    fn test_cursor_blink_mode_overrides_config_until_alt_screen_exit() {
+        let config = crate::config::TerminalConfig::default().with_cursor_blink(false);
+        let mut grid = Grid::new(5, 5, std::sync::Arc::new(config));
+        let mut parser = crate::ansi::AnsiParser::new();
+        assert!(!grid.cursor_blinks());
+
+        // Blink phase has no effect on a steady cursor
+        grid.toggle_cursor();
+        assert!(grid.is_cursor_visible());
+
+        // vim: enter alternate screen, request a blinking cursor
+        parser.feed_str("\x1b[?1049h\x1b[?12h", &mut grid);
+        assert!(grid.cursor_blinks());
+        grid.toggle_cursor();
+        assert!(!grid.is_cursor_visible());
+
+        parser.feed_str("\x1b[?12l", &mut grid);
+        assert!(!grid.cursor_blinks());
+        assert!(grid.is_cursor_visible());
+
+        parser.feed_str("\x1b[?12h\x1b[?1049l", &mut grid);
+        assert!(!grid.cursor_blinks(), "config default restored on alt-screen exit");
+
+        // DECTCEM hides the cursor regardless of blinking
+        parser.feed_str("\x1b[?25l", &mut grid);
+        assert!(!grid.is_cursor_visible());
+    }
+
+    #[test]
+    fn test_newline_on_alternate_screen_skips_scrollback() {
+        let mut grid = Grid::new(5, 2, config());
+        let mut parser = crate::ansi::AnsiParser::new();
+        parser.feed_str("\x1b[?1049ha\r\nb\r\nc", &mut grid);
+        assert_eq!(grid.scrollback_rows(), 0);
+        assert_eq!(grid.get_cell(0, 0).ch, 'b');
+        assert_eq!(grid.get_cell(1, 0).ch, 'c');
+
+        parser.feed_str("\x1b[?1049l", &mut grid);
+        assert_eq!(grid.get_cell(0, 0).ch, Grid::default_cell().ch, "primary screen untouched");
+    }
+
+    #[test]
+    fn test_selective_erase_spares_protected_cells() {
+        let mut grid = Grid::new(6, 2, config());
+        let mut parser = crate::ansi::AnsiParser::new();
+        let row = |grid: &Grid, r: usize| (0..6).map(|c| grid.get_cell(r, c).ch).collect::<String>();
+        // "ID:" is a protected label, the values after it are not
+        parser.feed_str("\x1b[1\"qID:\x1b[0\"q123\r\n\x1bVPW:\x1bWabc", &mut grid);
+        assert!(grid.get_cell(0, 0).protected && !grid.get_cell(0, 3).protected);
+
+        parser.feed_str("\x1b[?2K", &mut grid);
+        assert_eq!(row(&grid, 1), "PW:\0\0\0");
+        parser.feed_str("\x1b[H\x1b[?J", &mut grid);
+        assert_eq!(row(&grid, 0), "ID:\0\0\0");
+
+        // Ordinary erase still clears everything
+        parser.feed_str("\x1b[2J", &mut grid);
+        assert_eq!(row(&grid, 1), "\0\0\0\0\0\0");
+
+        // DECSC/DECRC save and restore the protection with the cursor
+        parser.feed_str("\x1b[H\x1b[1\"q\x1b7\x1b[0\"q\x1b[1;4Hx\x1b8a", &mut grid);
+        assert!(grid.get_cell(0, 0).protected && !grid.get_cell(0, 3).protected);
+        parser.feed_str("\x1b[0\"q\x1b7\x1b[1\"q\x1b8b", &mut grid);
+        assert!(!grid.get_cell(0, 1).protected);
+    }
+
+    #[test]
+    fn test_insert_delete_columns() {
+        let mut grid = Grid::new(6, 2, config());
+        let mut parser = crate::ansi::AnsiParser::new();
+        let row = |grid: &Grid, r: usize| (0..6).map(|c| grid.get_cell(r, c).ch).collect::<String>();
+        parser.feed_str("abcdef\r\nuvwxyz\x1b[1;3H", &mut grid);
+
+        // Every row shifts, not just the cursor's
+        parser.feed_str("\x1b[2'}", &mut grid);
+        assert_eq!((row(&grid, 0), row(&grid, 1)), ("ab\0\0cd".to_string(), "uv\0\0wx".to_string()));
+        parser.feed_str("\x1b[3'~", &mut grid);
+        assert_eq!((row(&grid, 0), row(&grid, 1)), ("abd\0\0\0".to_string(), "uvx\0\0\0".to_string()));
+        assert_eq!((grid.row, grid.col), (0, 2));
+
+        // Counts past the right margin clear to it
+        parser.feed_str("\x1b[1;2H\x1b[99'~", &mut grid);
+        assert_eq!(row(&grid, 0), "a\0\0\0\0\0");
+    }
+
     #[test]
     fn test_resize_with_rewrap_basic() {
         let mut grid = Grid::new(5, 3, config());
@@ -1748,6 +4375,31 @@ mod tests {
         assert_eq!(bounds, ((0, 6), (0, 10))); // "word2"
     }
 
+    #[test]
+    fn test_word_selection_across_soft_wrap() {
+        use crate::ansi::AnsiParser;
+        let mut grid = Grid::new(10, 5, config());
+        let mut parser = AnsiParser::new();
+        // The word fills row 0 after "rm " and wraps onto row 1; row 2 follows a hard break
+        parser.feed_str("rm abcdefghijklmnop\r\nqrs", &mut grid);
+        assert!(grid.is_row_wrapped(0));
+        assert!(!grid.is_row_wrapped(1));
+
+        // From either half, the whole word is selected
+        grid.select_word(1, 2);
+        assert_eq!(grid.get_normalized_bounds().unwrap(), ((0, 3), (1, 8)));
+        grid.select_word(0, 5);
+        assert_eq!(grid.get_normalized_bounds().unwrap(), ((0, 3), (1, 8)));
+
+        // A hard line break still ends the word
+        grid.select_word(2, 1);
+        assert_eq!(grid.get_normalized_bounds().unwrap(), ((2, 0), (2, 2)));
+
+        // Overwriting the last column clears the continuation
+        parser.feed_str("\x1b[1;10Hx\r", &mut grid);
+        assert!(!grid.is_row_wrapped(0));
+    }
+
     #[test]
     fn test_line_selection() {
         let mut grid = Grid::new(10, 5, config());
@@ -1766,6 +4418,64 @@ mod tests {
         assert_eq!(bounds, ((2, 0), (2, 4))); // "Hello"
     }
 
+    #[test]
+    fn test_copy_hyperlinked_text() {
+        use crate::ansi::AnsiParser;
+        use crate::hyperlinks::HyperlinkCopyMode;
+        let copy = |mode: HyperlinkCopyMode| {
+            let config = crate::config::TerminalConfig::default().with_copy_hyperlinks(mode);
+            let mut grid = Grid::new(20, 2, std::sync::Arc::new(config));
+            AnsiParser::new().feed_str("see \x1b]8;;https://example.com/a\x1b\\docs\x1b]8;;\x1b\\ now", &mut grid);
+            grid.start_selection(0, 0);
+            grid.update_selection(0, 11);
+            grid.complete_selection(0, 11);
+            grid.get_selected_text()
+        };
+
+        assert_eq!(copy(HyperlinkCopyMode::TextOnly), "see docs now");
+        assert_eq!(copy(HyperlinkCopyMode::UriOnly), "see https://example.com/a now");
+        assert_eq!(copy(HyperlinkCopyMode::Markdown), "see [docs](https://example.com/a) now");
+    }
+
+    #[test]
+    fn test_hyperlink_storage() {
+        use crate::ansi::AnsiParser;
+        let mut grid = grid_new(2, 20);
+        let mut parser = AnsiParser::new();
+        parser.feed_str("\x1b]8;;https://example.com\x07ab\x1b]8;;\x07c", &mut grid);
+        assert_eq!(grid.hyperlink_at(0, 1), Some("https://example.com"));
+        assert_eq!(grid.hyperlink_at(0, 2), None);
+        assert_eq!(grid.url_at(0, 0).as_deref(), Some("https://example.com"));
+        assert_eq!(grid.link_at(0, 1).map(|link| link.cols), Some(0..2));
+
+        // Unsafe targets are not linked
+        parser.feed_str("\r\n\x1b]8;;javascript:alert(1)\x07x", &mut grid);
+        assert_eq!(grid.hyperlink_at(1, 0), None);
+
+        // Plain URLs in the text cover their own extent
+        parser.feed_str("\x1b]8;;\x07 ftp://h/f.", &mut grid);
+        let link = grid.link_at(1, 5).unwrap();
+        assert_eq!((link.uri.as_str(), link.row, link.cols), ("ftp://h/f", 1, 2..11));
+    }
+
+    #[test]
+    fn test_hyperlinks_no_cell_refers_to_are_dropped() {
+        use crate::ansi::AnsiParser;
+        use crate::constants::MAX_HYPERLINKS;
+        let mut grid = grid_new(2, 20);
+        let mut parser = AnsiParser::new();
+        parser.feed_str("\x1b[2H\x1b]8;;https://example.com/kept\x07k\x1b]8;;\x07\x1b[H", &mut grid);
+
+        // Each link overwrites the previous one in the same cell
+        for i in 0..MAX_HYPERLINKS * 2 {
+            parser.feed_str(&format!("\x1b]8;;https://example.com/{}\x07x\x1b]8;;\x07\r", i), &mut grid);
+        }
+        assert!(grid.hyperlinks.len() <= MAX_HYPERLINKS);
+        let last = format!("https://example.com/{}", MAX_HYPERLINKS * 2 - 1);
+        assert_eq!(grid.hyperlink_at(0, 0), Some(last.as_str()));
+        assert_eq!(grid.hyperlink_at(1, 0), Some("https://example.com/kept"));
+    }
+
     #[test]
     fn test_word_selection_single_character() {
         let mut grid = Grid::new(10, 5, config());
@@ -1812,21 +4522,27 @@ mod tests {
             bold_is_bright: true,
             ..Default::default()
         };
+        let bold_is_bright = config.bold_is_bright;
         let mut grid = Grid::new(80, 24, std::sync::Arc::new(config));
 
         // Set foreground to basic red (color index 1)
-        grid.fg = COLOR_PALETTE[1]; // Basic red
+        grid.set_fg_indexed(1);
         assert_eq!(grid.fg, COLOR_PALETTE[1]);
 
-        // Enable bold - should automatically make it bright red (color index 9)
+        // Enable bold - rendered as bright red (color index 9), stored color unchanged
         grid.set_bold(true);
-        assert_eq!(grid.fg, COLOR_PALETTE[9]); // Bright red
+        grid.put('A');
+        grid.advance();
+        assert_eq!(grid.fg, COLOR_PALETTE[1]);
         assert!(grid.bold);
+        assert_eq!(grid.get_cell(0, 0).display_fg(bold_is_bright), COLOR_PALETTE[9]); // Bright red
 
-        // Disable bold - should keep the bright color (legacy behavior)
+        // Disable bold (SGR 22) - back to basic red
         grid.set_bold(false);
+        grid.put('B');
         assert!(!grid.bold);
-        assert_eq!(grid.fg, COLOR_PALETTE[9]); // Still bright red
+        assert_eq!(grid.fg, COLOR_PALETTE[1]);
+        assert_eq!(grid.get_cell(0, 1).display_fg(bold_is_bright), COLOR_PALETTE[1]);
     }
 
     #[test]
@@ -1836,15 +4552,18 @@ mod tests {
             bold_is_bright: false, // Explicitly disabled
             ..Default::default()
         };
+        let bold_is_bright = config.bold_is_bright;
         let mut grid = Grid::new(80, 24, std::sync::Arc::new(config));
 
         // Set foreground to basic red (color index 1)
-        grid.fg = COLOR_PALETTE[1]; // Basic red
+        grid.set_fg_indexed(1);
 
         // Enable bold - should NOT change color when disabled
         grid.set_bold(true);
+        grid.put('A');
         assert_eq!(grid.fg, COLOR_PALETTE[1]); // Still basic red
         assert!(grid.bold);
+        assert_eq!(grid.get_cell(0, 0).display_fg(bold_is_bright), COLOR_PALETTE[1]);
     }
 
     #[test]
@@ -1857,11 +4576,1063 @@ mod tests {
         };
         let mut grid = Grid::new(80, 24, std::sync::Arc::new(config));
 
-        grid.fg = custom_color;
+        grid.set_fg(custom_color);
 
         // Enable bold - custom colors should be unchanged
         grid.set_bold(true);
+        grid.put('A');
         assert_eq!(grid.fg, custom_color);
         assert!(grid.bold);
+        assert_eq!(grid.get_cell(0, 0).display_fg(true), custom_color);
+
+        // Truecolor that equals a palette entry is not brightened either
+        grid.set_fg(crate::ansi::COLOR_PALETTE[1]);
+        grid.put('B');
+        assert_eq!(grid.get_cell(0, 0).display_fg(true), crate::ansi::COLOR_PALETTE[1]);
+    }
+
+    #[test]
+    fn test_wide_char_advances_two_columns() {
+        let mut grid = grid_new(5, 10);
+        grid.put('中');
+        grid.advance();
+        assert_eq!(grid.col, 2);
+        assert_eq!(grid.get_cell(0, 0).ch, '中');
+
+        // Combining marks do not move the cursor
+        grid.put('e');
+        grid.advance();
+        grid.put('\u{0301}');
+        grid.advance();
+        assert_eq!(grid.col, 3);
+    }
+
+    #[test]
+    fn test_autowrap_is_deferred_until_next_character() {
+        use crate::ansi::AnsiParser;
+        let mut grid = grid_new(3, 4);
+        let mut parser = AnsiParser::new();
+
+        // Filling the line leaves the cursor on the last column
+        parser.feed_str("abcd", &mut grid);
+        assert_eq!((grid.row, grid.col), (0, 3));
+        // CR LF after a full line does not produce an empty line
+        parser.feed_str("\r\nx", &mut grid);
+        assert_eq!(grid.get_cell(1, 0).ch, 'x');
+
+        // The next character wraps first, scrolling at the bottom
+        parser.feed_str("\x1b[3;1Hwxyz!", &mut grid);
+        assert_eq!(grid.get_cell(1, 3).ch, 'z');
+        assert_eq!(grid.get_cell(2, 0).ch, '!');
+        assert_eq!((grid.row, grid.col), (2, 1));
+
+        // Cursor movement cancels the pending wrap
+        parser.feed_str("\x1b[1;1Habcd\x1b[1;4HZ", &mut grid);
+        assert_eq!(grid.get_cell(0, 3).ch, 'Z');
+        assert_eq!(grid.row, 0);
+
+        // Without DECAWM the last column is overwritten
+        parser.feed_str("\x1b[?7l\x1b[2;1Hmnopq", &mut grid);
+        assert_eq!(grid.get_cell(1, 3).ch, 'q');
+        assert_eq!((grid.row, grid.col), (1, 3));
+    }
+
+    #[test]
+    fn test_reverse_wraparound_backspace() {
+        use crate::ansi::AnsiParser;
+        let mut grid = grid_new(3, 4);
+        let mut parser = AnsiParser::new();
+
+        // Off by default: backspace stops at column 0
+        parser.feed_str("\x1b[2;1H\x08", &mut grid);
+        assert_eq!((grid.row, grid.col), (1, 0));
+
+        parser.feed_str("\x1b[?45h\x08", &mut grid);
+        assert_eq!((grid.row, grid.col), (0, 3));
+
+        // A full line: the first BS only cancels the pending wrap
+        parser.feed_str("\x1b[2;1Habcd\x08", &mut grid);
+        assert_eq!((grid.row, grid.col), (1, 3));
+        parser.feed_str("\x08\x08\x08\x08", &mut grid);
+        assert_eq!((grid.row, grid.col), (0, 3));
+
+        // Requires autowrap; never wraps above the top row
+        parser.feed_str("\x1b[?7l\x1b[2;1H\x08", &mut grid);
+        assert_eq!((grid.row, grid.col), (1, 0));
+        parser.feed_str("\x1b[?7h\x1b[1;1H\x08", &mut grid);
+        assert_eq!((grid.row, grid.col), (0, 0));
+    }
+
+    #[test]
+    fn test_edits_never_leave_half_a_wide_char() {
+        use crate::ansi::AnsiParser;
+        let row = |grid: &Grid| (0..grid.cols).map(|c| grid.get_cell(0, c).ch).map(|ch| if ch == '\0' { '.' } else { ch }).collect::<String>();
+        let mut grid = grid_new(2, 8);
+        let mut parser = AnsiParser::new();
+
+        // Cursor motion counts columns: CUB 3 from after "a中b" lands on 中's right half
+        parser.feed_str("a中b\x1b[3D", &mut grid);
+        assert_eq!(grid.col, 1);
+        parser.feed_str("\x1b[1C", &mut grid);
+        assert_eq!(grid.col, 2);
+
+        // Writing over the right half erases the left one
+        parser.feed_str("x", &mut grid);
+        assert_eq!(row(&grid), "a.xb....");
+
+        // Writing a wide character over the left half of another
+        parser.feed_str("\x1b[1;1H中文\x1b[1;2H字", &mut grid);
+        assert_eq!(row(&grid), ".字......");
+
+        // ECH and DCH starting on a right half
+        parser.feed_str("\x1b[1;1H中文z\x1b[1;2H\x1b[1X", &mut grid);
+        assert_eq!(row(&grid), "..文.z...");
+        parser.feed_str("\x1b[1;4H\x1b[1P", &mut grid);
+        assert_eq!(row(&grid), "...z....");
+
+        // EL from a right half, ICH pushing a wide character off the line
+        parser.feed_str("\x1b[1;1H中文\x1b[1;4H\x1b[K", &mut grid);
+        assert_eq!(row(&grid), "中.......");
+        parser.feed_str("\x1b[1;1Habcdef中\x1b[1;1H\x1b[1@", &mut grid);
+        assert_eq!(row(&grid), ".abcdef.");
+    }
+
+    #[test]
+    fn test_wide_char_wraps_at_right_margin() {
+        let mut grid = grid_new(5, 4);
+        grid.move_abs(0, 3);
+        grid.put('中');
+        grid.advance();
+        assert_eq!(grid.get_cell(1, 0).ch, '中');
+        assert_eq!((grid.row, grid.col), (1, 2));
+    }
+
+    #[test]
+    fn test_combining_marks_stay_with_their_character() {
+        use crate::ansi::AnsiParser;
+        let mut grid = grid_new(2, 4);
+        // e + acute + dot below; a mark on the last column with a wrap
+        // pending; one after a wide character
+        AnsiParser::new().feed_str("e\u{301}\u{323}xyz\u{308}中\u{301}", &mut grid);
+        assert_eq!(grid.combining_marks(grid.get_cell(0, 0)), "\u{301}\u{323}");
+        assert_eq!(grid.combining_marks(grid.get_cell(0, 1)), "");
+        assert_eq!(grid.combining_marks(grid.get_cell(0, 3)), "\u{308}");
+        assert_eq!(grid.combining_marks(grid.get_cell(1, 0)), "\u{301}");
+        assert_eq!((grid.row, grid.col), (1, 2));
+
+        grid.start_selection(0, 0);
+        grid.update_selection(0, 3);
+        grid.complete_selection(0, 3);
+        assert_eq!(grid.get_selected_text(), "e\u{301}\u{323}xyz\u{308}");
+
+        // Overwriting the character drops its marks
+        AnsiParser::new().feed_str("\x1b[1;1Ha", &mut grid);
+        assert_eq!(grid.combining_marks(grid.get_cell(0, 0)), "");
+    }
+
+    #[test]
+    fn test_wide_char_is_a_cell_pair() {
+        use crate::ansi::AnsiParser;
+        let mut grid = grid_new(2, 6);
+        AnsiParser::new().feed_str("a中b", &mut grid);
+        let (head, tail) = (grid.get_cell(0, 1), grid.get_cell(0, 2));
+        assert!(head.wide && !head.spacer);
+        assert!(tail.spacer && !tail.wide);
+        assert_eq!(tail.ch, '\0');
+        assert_eq!(grid.get_cell(0, 3).ch, 'b');
+        assert_eq!(grid.get_row_text(0), "a中b");
+
+        // Without autowrap a wide character at the last column has no room
+        // for its right half and stays narrow
+        AnsiParser::new().feed_str("\x1b[?7l\x1b[2;6H中", &mut grid);
+        assert!(!grid.get_cell(1, 5).wide);
+    }
+
+    #[test]
+    fn test_selection_takes_wide_chars_whole() {
+        use crate::ansi::AnsiParser;
+        let mut grid = grid_new(2, 8);
+        AnsiParser::new().feed_str("x中文y", &mut grid);
+
+        // From the right half of 中 to the left half of 文
+        grid.start_selection(0, 2);
+        grid.update_selection(0, 3);
+        grid.complete_selection(0, 3);
+        assert_eq!(grid.get_selected_text(), "中文");
+        assert!(grid.is_selected(0, 1) && grid.is_selected(0, 4));
+        assert!(!grid.is_selected(0, 5));
+        let spans: Vec<_> = grid.selection_spans().iter().map(|s| (s.start_col, s.end_col)).collect();
+        assert_eq!(spans, [(1, 5)]);
+
+        // Double click on a right half selects the word, right half included
+        grid.select_word(0, 4);
+        assert_eq!(grid.get_normalized_bounds(), Some(((0, 0), (0, 5))));
+        assert_eq!(grid.get_selected_text(), "x中文y");
+        grid.select_line(0);
+        assert_eq!(grid.get_selected_text(), "x中文y");
+    }
+
+    #[test]
+    fn test_rewrap_moves_wide_chars_whole() {
+        use crate::ansi::AnsiParser;
+        let mut grid = grid_new(4, 6);
+        AnsiParser::new().feed_str("ab中文", &mut grid);
+        grid.resize_with_rewrap(3, 4);
+        let text = |grid: &Grid, row: usize| grid.get_row_text(row);
+        assert_eq!(text(&grid, 0), "ab");
+        assert_eq!(text(&grid, 1), "中");
+        assert_eq!(text(&grid, 2), "文");
+        assert!(grid.get_cell(1, 0).wide && grid.get_cell(1, 1).spacer);
+        assert!(grid.is_row_wrapped(0));
+    }
+
+    #[test]
+    fn test_column_shift_takes_wide_chars_whole() {
+        use crate::ansi::AnsiParser;
+        let row = |grid: &Grid| (0..grid.cols).map(|c| grid.get_cell(0, c).ch).map(|ch| if ch == '\0' { '.' } else { ch }).collect::<String>();
+        let mut grid = grid_new(2, 6);
+        let mut parser = AnsiParser::new();
+        // DECDC on the right half of 中 erases all of it
+        parser.feed_str("a中b\x1b[1;3H\x1b[1'~", &mut grid);
+        assert_eq!(row(&grid), "a.b...");
+        assert!(!grid.get_cell(0, 1).wide);
+    }
+
+    #[test]
+    fn test_ambiguous_width_config() {
+        use crate::width::AmbiguousWidth;
+        let config = crate::config::TerminalConfig::default()
+            .with_ambiguous_width(AmbiguousWidth::Wide);
+        let mut grid = Grid::new(10, 5, std::sync::Arc::new(config));
+        grid.put('±');
+        grid.advance();
+        assert_eq!(grid.col, 2);
+
+        let mut narrow = grid_new(5, 10);
+        narrow.put('±');
+        narrow.advance();
+        assert_eq!(narrow.col, 1);
+    }
+
+    #[test]
+    fn test_emoji_vs16_width_policy() {
+        use crate::width::EmojiWidth;
+        let mut grid = grid_new(5, 10);
+        for ch in ['\u{2764}', '\u{FE0F}'] {
+            grid.put(ch);
+            grid.advance();
+        }
+        assert_eq!(grid.col, 2);
+        assert!(grid.get_cell(0, 0).wide && grid.get_cell(0, 1).spacer);
+
+        let config = crate::config::TerminalConfig::default()
+            .with_emoji_width(EmojiWidth::WcwidthCompat);
+        let mut legacy = Grid::new(10, 5, std::sync::Arc::new(config));
+        for ch in ['\u{2764}', '\u{FE0F}'] {
+            legacy.put(ch);
+            legacy.advance();
+        }
+        assert_eq!(legacy.col, 1);
+        assert!(!legacy.get_cell(0, 0).wide);
+    }
+
+    #[test]
+    fn test_write_notice_on_fresh_line() {
+        let mut grid = grid_new(5, 20);
+        grid.put('$');
+        grid.advance();
+        grid.set_bold(true);
+        grid.write_notice("[parser recovered]");
+
+        assert_eq!(grid.get_row_text(1).trim_end(), "[parser recovered]");
+        assert!(!grid.get_cell(1, 0).bold);
+        assert_eq!((grid.row, grid.col), (2, 0));
+        assert!(grid.bold);
+    }
+
+    #[test]
+    fn test_alternate_font_slot_stored_in_cells() {
+        let mut grid = grid_new(5, 10);
+        grid.set_font(3);
+        grid.put('a');
+        grid.advance();
+        grid.reset_attrs();
+        grid.put('b');
+
+        assert_eq!(grid.get_cell(0, 0).font, 3);
+        assert_eq!(grid.get_cell(0, 1).font, crate::ansi::PRIMARY_FONT);
+    }
+
+    #[test]
+    fn test_default_colors_follow_theme() {
+        use crate::ansi::Color;
+        let theme_bg = Color::rgb(0.99, 0.96, 0.89);
+        let theme_fg = Color::rgb(0.2, 0.2, 0.2);
+        let config = crate::config::TerminalConfig::default().with_colors(theme_fg, theme_bg);
+        let mut grid = Grid::new(10, 3, std::sync::Arc::new(config.clone()));
+
+        grid.set_bg_indexed(4);
+        grid.set_bg_default(); // SGR 49
+        grid.set_fg_indexed(1);
+        grid.set_fg_default(); // SGR 39
+        grid.put('x');
+
+        let cell = *grid.get_cell(0, 0);
+        assert_eq!(cell.bg_logical, LogicalColor::Default);
+        assert_eq!(config.resolve_bg(&cell), theme_bg);
+        assert_eq!(config.resolve_fg(&cell), theme_fg);
+
+        // Untouched cells resolve to the theme as well
+        let blank = *grid.get_cell(1, 0);
+        assert_eq!(config.resolve_bg(&blank), theme_bg);
+
+        // A theme change applies to existing cells at render time
+        let dark = config.with_background_color(Color::rgb(0.0, 0.0, 0.1));
+        assert_eq!(dark.resolve_bg(&cell), Color::rgb(0.0, 0.0, 0.1));
+    }
+
+    #[test]
+    fn test_color_query_responses() {
+        use crate::ansi::{AnsiParser, Color};
+        let config = crate::config::TerminalConfig::default()
+            .with_colors(Color::rgb(1.0, 1.0, 1.0), Color::rgb(0.0, 0.0, 0.0));
+        let mut grid = Grid::new(10, 3, std::sync::Arc::new(config));
+        let mut parser = AnsiParser::new();
+
+        // vim's background detection
+        parser.feed_str("\x1B]11;?\x1B\\", &mut grid);
+        parser.feed_str("\x1B]4;1;?\x07", &mut grid);
+        assert_eq!(
+            grid.take_responses(),
+            "\x1B]11;rgb:0000/0000/0000\x1B\\\x1B]4;1;rgb:cccc/0000/0000\x07"
+        );
+        assert!(grid.take_responses().is_empty());
+
+        // Queries do not move the cursor
+        assert_eq!((grid.row, grid.col), (0, 0));
+    }
+
+    #[test]
+    fn test_color_scheme_switch_is_observable() {
+        use crate::ansi::{AnsiParser, Color};
+        use crate::theme::Theme;
+        let light = Theme::new(Color::rgb(0.0, 0.0, 0.0), Color::rgb(1.0, 1.0, 1.0));
+        let dark = Theme::new(Color::rgb(1.0, 1.0, 1.0), Color::rgb(0.0, 0.0, 0.0));
+        let config = crate::config::TerminalConfig::default().with_themes(light, dark);
+        let mut grid = Grid::new(10, 3, std::sync::Arc::new(config));
+        let mut parser = AnsiParser::new();
+
+        // Without mode 2031 the switch is only seen by querying
+        assert!(grid.set_color_scheme(ColorScheme::Light));
+        assert!(grid.take_responses().is_empty());
+        parser.feed_str("\x1B]11;?\x1B\\", &mut grid);
+        assert_eq!(grid.take_responses(), "\x1B]11;rgb:ffff/ffff/ffff\x1B\\");
+
+        parser.feed_str("\x1B[?2031h\x1B[?996n", &mut grid);
+        assert_eq!(grid.take_responses(), "\x1B[?997;2n");
+        assert!(grid.set_color_scheme(ColorScheme::Dark));
+        assert!(!grid.set_color_scheme(ColorScheme::Dark));
+        assert_eq!(grid.take_responses(), "\x1B[?997;1n");
+
+        // Without themes the built-in dark colors stay, and so does the report
+        let mut grid = Grid::new(10, 3, std::sync::Arc::new(crate::config::TerminalConfig::default()));
+        parser.feed_str("\x1B[?2031h", &mut grid);
+        grid.set_color_scheme(ColorScheme::Light);
+        assert!(grid.take_responses().is_empty());
+        parser.feed_str("\x1B[?996n", &mut grid);
+        assert_eq!(grid.take_responses(), "\x1B[?997;1n");
+    }
+
+    #[test]
+    fn test_bell_count() {
+        use crate::ansi::AnsiParser;
+        let mut grid = Grid::new(10, 3, config());
+        let mut parser = AnsiParser::new();
+        parser.feed_str("\x07ok\x07\x1B]2;title\x07", &mut grid);
+        assert_eq!(grid.bell_count(), 2);
+        assert_eq!(grid.get_cell(0, 0).ch, 'o');
+    }
+
+    #[test]
+    fn test_local_echo_prediction() {
+        use crate::ansi::AnsiParser;
+        use crate::predict::LocalEcho;
+        let config = crate::config::TerminalConfig::default().with_local_echo(LocalEcho::Always);
+        let mut grid = Grid::new(10, 3, std::sync::Arc::new(config));
+        let mut parser = AnsiParser::new();
+        parser.feed_str("$ ", &mut grid);
+
+        grid.predict_echo("ls", TextSource::Key);
+        let predicted: Vec<_> = grid.predicted_cells().iter().map(|(r, c, cell)| (*r, *c, cell.ch, cell.underline)).collect();
+        assert_eq!(predicted, vec![(0, 2, 'l', true), (0, 3, 's', true)]);
+
+        // The echo replaces the predictions
+        parser.feed_str("ls", &mut grid);
+        grid.reconcile_predictions();
+        assert!(grid.predicted_cells().is_empty());
+
+        // Pasted text and full-screen applications are not predicted
+        grid.predict_echo("x", TextSource::Paste);
+        assert!(grid.predicted_cells().is_empty());
+        parser.feed_str("\x1B[?1049h", &mut grid);
+        grid.predict_echo("x", TextSource::Key);
+        assert!(grid.predicted_cells().is_empty());
+    }
+
+    #[test]
+    fn test_prompt_marks_and_navigation() {
+        use crate::ansi::AnsiParser;
+        use crate::marks::{MarkCategory, MarkDirection};
+        let mut grid = grid_new(3, 10);
+        let mut parser = AnsiParser::new();
+
+        // Three prompts, each followed by two lines of output
+        for _ in 0..3 {
+            parser.feed_str("\x1B]133;A\x07$ cmd\r\nout\r\nout\r\n", &mut grid);
+        }
+        let rows: Vec<u64> = grid.scroll_marks().iter().map(|m| m.row).collect();
+        assert_eq!(rows, vec![0, 3, 6]);
+        assert_eq!(grid.absolute_row(0), 7);
+
+        let mark = grid.scroll_to_mark(MarkDirection::Previous, Some(MarkCategory::Prompt));
+        assert_eq!(mark.map(|m| m.row), Some(6));
+        assert_eq!(grid.viewport_top_row(), 6);
+        assert_eq!(grid.scroll_offset, 1);
+
+        grid.scroll_to_mark(MarkDirection::Previous, None);
+        assert_eq!(grid.viewport_top_row(), 3);
+        grid.scroll_to_mark(MarkDirection::Previous, None);
+        assert_eq!(grid.viewport_top_row(), 0);
+        assert_eq!(grid.scroll_to_mark(MarkDirection::Previous, None), None);
+        assert_eq!(grid.viewport_top_row(), 0);
+
+        grid.scroll_to_mark(MarkDirection::Next, Some(MarkCategory::Prompt));
+        assert_eq!(grid.viewport_top_row(), 3);
+
+        // Other categories are filtered out
+        grid.add_mark_at(1, MarkCategory::SearchMatch);
+        assert_eq!(grid.scroll_to_mark(MarkDirection::Previous, Some(MarkCategory::ErrorTrigger)), None);
+        assert_eq!(grid.scroll_to_mark(MarkDirection::Previous, None).map(|m| m.category), Some(MarkCategory::SearchMatch));
+        grid.clear_marks(MarkCategory::SearchMatch);
+        assert_eq!(grid.scroll_marks().len(), 3);
+    }
+
+    #[test]
+    fn test_marks_trimmed_with_scrollback() {
+        let mut grid = grid_new(2, 4);
+        grid.add_mark(crate::marks::MarkCategory::Prompt);
+        for _ in 0..crate::constants::SCROLLBACK_LIMIT + 2 {
+            grid.newline();
+        }
+        assert!(grid.first_absolute_row() > 0);
+        assert!(grid.scroll_marks().is_empty());
+
+        // Rows keep their absolute numbers as the scrollback is trimmed
+        let row = grid.absolute_row(grid.row);
+        grid.add_mark(crate::marks::MarkCategory::ErrorTrigger);
+        grid.newline();
+        assert_eq!(grid.scroll_marks()[0].row, row);
+        assert_eq!(grid.absolute_row(grid.row), row + 1);
+    }
+
+    #[test]
+    fn test_row_annotations_follow_scrolling_and_trimming() {
+        use crate::annotations::Annotation;
+        let mut grid = grid_new(2, 4);
+        let row = grid.absolute_row(0);
+        let id = grid.annotate_row(row, Annotation::new().with_icon("bookmark")).unwrap();
+        assert_eq!(grid.visible_annotations().len(), 1);
+
+        // Scrolled off the screen, but still in the scrollback
+        for _ in 0..6 {
+            grid.newline();
+        }
+        assert!(grid.visible_annotations().is_empty());
+        grid.scroll_offset = grid.scrollback_rows();
+        assert_eq!(grid.visible_annotations()[0].id, id);
+
+        for _ in 0..crate::constants::SCROLLBACK_LIMIT {
+            grid.newline();
+        }
+        assert!(grid.row_annotations().is_empty());
+        assert_eq!(grid.annotate_row(row, Annotation::new()), None);
+    }
+
+    #[test]
+    fn test_sixel_images_are_placed_at_the_cursor() {
+        use crate::ansi::AnsiParser;
+        let mut grid = grid_new(4, 10);
+        grid.set_cell_pixel_size(10, 6);
+        let mut parser = AnsiParser::new();
+
+        // 15x12 pixels cover 2x2 cells; the cursor ends below the image
+        parser.feed_str("ab\x1BPq#1;2;100;0;0!15~-!15~\x1B\\x", &mut grid);
+        let placed: Vec<_> = grid.visible_images().map(|i| (i.row, i.col, i.cols, i.rows)).collect();
+        assert_eq!(placed, [(0, 2, 2, 2)]);
+        assert_eq!(grid.get_cell(2, 2).ch, 'x');
+        assert_eq!(grid.image_bytes(), 15 * 12 * 4);
+
+        // Scrolled off the screen, but still in the scrollback
+        parser.feed_str("\r\n\r\n\r\n", &mut grid);
+        assert_eq!(grid.visible_images().count(), 0);
+        grid.scroll_offset = grid.scrollback_rows();
+        assert_eq!(grid.visible_images().count(), 1);
+        grid.scroll_offset = 0;
+
+        // Erasing the screen drops its images only
+        parser.feed_str("\x1BPq~\x1B\\\x1B[2J", &mut grid);
+        assert_eq!(grid.visible_images().count(), 0);
+        assert_eq!(grid.image_bytes(), 15 * 12 * 4);
+        grid.clear();
+        assert_eq!(grid.image_bytes(), 0);
+    }
+
+    #[test]
+    fn test_sixel_images_move_with_scrolled_text_and_go_when_overwritten() {
+        use crate::ansi::AnsiParser;
+        let mut grid = grid_new(4, 4);
+        grid.set_cell_pixel_size(10, 6);
+        let mut parser = AnsiParser::new();
+        let rows = |grid: &Grid| grid.visible_images().map(|i| i.row - grid.viewport_top_row()).collect::<Vec<_>>();
+
+        // On the alternate screen, a scroll moves the image up with its text
+        parser.feed_str("\x1B[?1049h\x1B[2;1H\x1BPq~\x1B\\\x1B[4;1H\n", &mut grid);
+        assert_eq!(rows(&grid), [0]);
+        parser.feed_str("\n", &mut grid);
+        assert!(rows(&grid).is_empty());
+
+        // SU/SD and IL/DL move it too
+        parser.feed_str("\x1B[2;1H\x1BPq~\x1B\\\x1B[1T", &mut grid);
+        assert_eq!(rows(&grid), [2]);
+        parser.feed_str("\x1B[1;1H\x1B[1L", &mut grid);
+        assert_eq!(rows(&grid), [3]);
+        parser.feed_str("\x1B[2M", &mut grid);
+        assert_eq!(rows(&grid), [1]);
+        parser.feed_str("\x1B[1S", &mut grid);
+        assert_eq!(rows(&grid), [0]);
+
+        // Text written or erased over the image removes it
+        parser.feed_str("\x1B[1;1Hx", &mut grid);
+        assert!(rows(&grid).is_empty());
+        parser.feed_str("\x1B[2;1H\x1BPq~\x1B\\\x1B[2;1H\x1B[K", &mut grid);
+        assert!(rows(&grid).is_empty());
+        assert_eq!(grid.image_bytes(), 0);
+    }
+
+    #[test]
+    fn test_search_scrollback_uses_absolute_rows() {
+        use crate::ansi::AnsiParser;
+        let mut grid = grid_new(2, 20);
+        let mut parser = AnsiParser::new();
+        parser.feed_str("make all\r\nerror: foo\r\nok\r\nERROR: bar\r\n", &mut grid);
+
+        // "ERROR: bar" is the last row in the scrollback; the screen is not indexed
+        assert_eq!(grid.scrollback_rows(), 3);
+        assert_eq!(grid.search_scrollback("Error:"), vec![1]);
+        parser.feed_str("\r\n", &mut grid);
+        assert_eq!(grid.search_scrollback("error:"), vec![1, 3]);
+        assert!(grid.scrollback_index_bytes() > 0);
+
+        grid.truncate_scrollback(2);
+        assert_eq!(grid.search_scrollback("error:"), vec![1]);
+        grid.clear();
+        assert!(grid.search_scrollback("error:").is_empty());
+    }
+
+    #[test]
+    fn test_search_scrollback_follows_trimming() {
+        let mut grid = grid_new(2, 4);
+        grid.put('x');
+        for _ in 0..crate::constants::SCROLLBACK_LIMIT + 2 {
+            grid.newline();
+        }
+        // The only 'x' row was trimmed away
+        assert!(grid.search_scrollback("x").is_empty());
+
+        grid.put('y');
+        let row = grid.absolute_row(grid.row);
+        grid.newline();
+        grid.newline();
+        assert_eq!(grid.search_scrollback("Y"), vec![row]);
+    }
+
+    #[test]
+    fn test_pointer_over_urls_and_while_typing() {
+        use crate::ansi::AnsiParser;
+        let mut grid = grid_new(2, 40);
+        AnsiParser::new().feed_str("docs: https://example.com/x.", &mut grid);
+        assert_eq!(grid.url_at(0, 10).as_deref(), Some("https://example.com/x"));
+        assert_eq!(grid.url_at(0, 2), None);
+        assert_eq!(grid.url_at(5, 0), None);
+
+        assert!(grid.pointer_moved(0, 10));
+        assert_eq!(grid.pointer_shape(), PointerShape::Link);
+        assert!(grid.pointer_key_typed());
+        assert_eq!(grid.pointer_shape(), PointerShape::Hidden);
+        assert!(grid.pointer_moved(1, 0));
+        assert_eq!(grid.pointer_shape(), PointerShape::Text);
+
+        let config = std::sync::Arc::new(crate::config::TerminalConfig::default().with_hide_pointer_while_typing(false));
+        let mut grid = Grid::new(10, 2, config);
+        assert!(!grid.pointer_key_typed());
+        assert_eq!(grid.pointer_shape(), PointerShape::Text);
+    }
+
+    #[test]
+    fn test_link_rules_make_patterns_clickable() {
+        use crate::ansi::AnsiParser;
+        use crate::link_rules::LinkRule;
+        let config = crate::config::TerminalConfig::default()
+            .with_link_rule(LinkRule::new(r"#(\d+)", "https://example.com/issues/$1").unwrap());
+        let mut grid = Grid::new(40, 2, std::sync::Arc::new(config));
+        AnsiParser::new().feed_str("closes #12, see https://example.com/#34", &mut grid);
+
+        let link = grid.link_at(0, 9).unwrap();
+        assert_eq!((link.uri.as_str(), link.cols), ("https://example.com/issues/12", 7..10));
+        // Literal URLs come first
+        assert_eq!(grid.url_at(0, 37).as_deref(), Some("https://example.com/#34"));
+        assert_eq!(grid.url_at(0, 2), None);
+    }
+
+    #[test]
+    fn test_titles_are_sanitized_and_prefixed() {
+        use crate::ansi::AnsiParser;
+        use crate::security::{SecurityConfig, TitlePolicy};
+        let security = SecurityConfig {
+            title_policy: TitlePolicy::default().with_allowed_pattern("^make").unwrap().with_prefix("[remote] "),
+            ..SecurityConfig::default()
+        };
+        let mut grid = Grid::new(10, 2, std::sync::Arc::new(crate::config::TerminalConfig::default().with_security(security)));
+        let mut parser = AnsiParser::new();
+        parser.feed_str("\x1b]2;make\u{202E}all\x07", &mut grid);
+        assert_eq!(grid.title(), "[remote] makeall");
+        // Rejected titles leave the current one in place
+        parser.feed_str("\x1b]0;sudo password:\x07", &mut grid);
+        assert_eq!(grid.title(), "[remote] makeall");
+    }
+
+    #[test]
+    fn test_osc_22_pointer_shape() {
+        use crate::ansi::AnsiParser;
+        let mut grid = grid_new(2, 10);
+        let mut parser = AnsiParser::new();
+        parser.feed_str("\x1b]22;grab\x07", &mut grid);
+        assert_eq!(grid.pointer_shape(), PointerShape::Named("grab"));
+
+        // Unknown names are ignored, an empty name restores the default
+        parser.feed_str("\x1b]22;url(x.png)\x07", &mut grid);
+        assert_eq!(grid.pointer_shape(), PointerShape::Named("grab"));
+        parser.feed_str("\x1b]22;\x07", &mut grid);
+        assert_eq!(grid.pointer_shape(), PointerShape::Text);
+    }
+
+    #[test]
+    fn test_held_output_keeps_viewport() {
+        use crate::ansi::AnsiParser;
+        let mut grid = grid_new(3, 10);
+        let mut parser = AnsiParser::new();
+        parser.feed_str("1\r\n2\r\n3\r\n4", &mut grid);
+        grid.set_output_held(true);
+        let top = grid.viewport_top_row();
+
+        // Output keeps arriving but the viewport shows the same rows
+        parser.feed_str("\r\n5\r\n6\r\n7", &mut grid);
+        assert_eq!(grid.scroll_offset, 3);
+        assert_eq!(grid.viewport_top_row(), top);
+        assert_eq!(grid.get_cell(2, 0).ch, '7');
+
+        // Releasing jumps to the live tail, and output follows it again
+        grid.set_output_held(false);
+        assert_eq!(grid.scroll_offset, 0);
+        grid.scroll_offset = 2;
+        parser.feed_str("\r\n8", &mut grid);
+        assert_eq!(grid.scroll_offset, 0);
+    }
+
+    #[test]
+    fn test_selection_freezes_viewport() {
+        use crate::ansi::AnsiParser;
+        let config = crate::config::TerminalConfig::default().with_freeze_on_select(true);
+        let mut grid = Grid::new(10, 3, std::sync::Arc::new(config));
+        let mut parser = AnsiParser::new();
+        parser.feed_str("1\r\n2\r\n3", &mut grid);
+        grid.start_selection(0, 0);
+        grid.update_selection(1, 0);
+        assert!(grid.is_selection_frozen());
+
+        // The rows being selected stay under the pointer
+        parser.feed_str("\r\n4\r\n5", &mut grid);
+        assert_eq!(grid.absolute_row_cells(grid.viewport_top_row()).unwrap()[0].ch, '1');
+        assert_eq!(grid.unseen_output(), 2);
+
+        // Once released, output follows the tail again
+        grid.complete_selection(1, 0);
+        assert!(!grid.is_selection_frozen());
+        parser.feed_str("\r\n6", &mut grid);
+        assert_eq!(grid.scroll_offset, 0);
+
+        // A cancelled drag releases the viewport too, keeping its selection
+        grid.start_selection(0, 0);
+        grid.update_selection(1, 0);
+        grid.cancel_selection();
+        assert!(!grid.is_selection_frozen());
+        assert!(grid.has_selection());
+        parser.feed_str("\r\n7", &mut grid);
+        assert_eq!(grid.scroll_offset, 0);
+    }
+
+    #[test]
+    fn test_invariant_violations() {
+        let mut grid = Grid::new(10, 3, config());
+        let mut parser = AnsiParser::new();
+        parser.feed_str("one\r\ntwo\r\nthree\r\nfour\x1b[?1049h\x1b[5;20Hx", &mut grid);
+        grid.resize_with_rewrap(6, 4);
+        assert_eq!(grid.invariant_violations(), Vec::<String>::new());
+
+        grid.col = 6;
+        grid.alternate_cells = ScreenBuffer::new(6, 3, Cell::default());
+        grid.scroll_offset = 9;
+        assert_eq!(grid.invariant_violations().len(), 3);
+    }
+
+    #[test]
+    fn test_unseen_output_below_scrolled_viewport() {
+        use crate::ansi::AnsiParser;
+        let config = crate::config::TerminalConfig::default().with_scroll_on_output(false);
+        let mut grid = Grid::new(10, 3, std::sync::Arc::new(config));
+        let mut parser = AnsiParser::new();
+        parser.feed_str("1\r\n2\r\n3\r\n4\r\n5", &mut grid);
+        assert_eq!(grid.unseen_output(), 0);
+
+        // Scrolled back, the viewport stays and new rows are counted
+        grid.scroll_offset = 1;
+        let top = grid.viewport_top_row();
+        parser.feed_str("\r\n6\r\n7", &mut grid);
+        assert_eq!(grid.viewport_top_row(), top);
+        assert_eq!(grid.unseen_output(), 2);
+
+        // Rows scrolled to by hand count as seen
+        grid.scroll_offset = 1;
+        assert_eq!(grid.unseen_output(), 1);
+        grid.scroll_to_bottom();
+        assert_eq!(grid.unseen_output(), 0);
+        parser.feed_str("\r\n8", &mut grid);
+        assert_eq!((grid.scroll_offset, grid.unseen_output()), (0, 0));
+    }
+
+    #[test]
+    fn test_wheel_scrolling() {
+        use crate::ansi::AnsiParser;
+        let lines: Vec<String> = (0..20).map(|i| i.to_string()).collect();
+        let mut grid = Grid::new(10, 3, config());
+        AnsiParser::new().feed_str(&lines.join("\r\n"), &mut grid);
+        assert_eq!(grid.scrollback_rows(), 17);
+
+        // Up into the history, three lines per notch
+        assert!(grid.scroll_wheel(-1.0, false));
+        assert_eq!(grid.scroll_offset, 3);
+        // Half notches from a touchpad add up
+        grid.scroll_wheel(-0.5, false);
+        grid.scroll_wheel(-0.5, false);
+        assert_eq!(grid.scroll_offset, 6);
+        // Shift scrolls a page (the screen minus one row)
+        grid.scroll_wheel(-1.0, true);
+        assert_eq!(grid.scroll_offset, 8);
+
+        // Clamped at both ends
+        assert!(grid.scroll_wheel(10.0, false));
+        assert_eq!(grid.scroll_offset, 0);
+        assert!(!grid.scroll_wheel(1.0, false));
+        grid.scroll_wheel(-100.0, false);
+        assert_eq!(grid.scroll_offset, 17);
+
+        let config = crate::config::TerminalConfig::default()
+            .with_wheel_scroll_lines(1.0)
+            .with_natural_scrolling(true)
+            .with_shift_wheel_scrolls_page(false);
+        let mut grid = Grid::new(10, 3, std::sync::Arc::new(config));
+        AnsiParser::new().feed_str(&lines.join("\r\n"), &mut grid);
+        grid.scroll_wheel(2.0, true);
+        assert_eq!(grid.scroll_offset, 2);
+    }
+
+    #[test]
+    fn test_push_pop_sgr() {
+        use crate::ansi::AnsiParser;
+        let mut grid = grid_new(2, 10);
+        let mut parser = AnsiParser::new();
+        parser.feed_str("\x1b[1;31m\x1b[#{\x1b[0;3;44m", &mut grid);
+        parser.feed_str("\x1b[#}", &mut grid);
+        assert!(grid.bold && !grid.italic);
+        assert_eq!(grid.fg, palette_color(1));
+        assert_eq!(grid.bg, crate::constants::DEFAULT_BG);
+
+        // Only the listed attributes come back
+        parser.feed_str("\x1b[1;30#{\x1b[0;32;44m\x1b[#}", &mut grid);
+        assert!(grid.bold);
+        assert_eq!(grid.fg, palette_color(1));
+        assert_eq!(grid.bg, palette_color(4));
+
+        // Popping an empty stack changes nothing
+        parser.feed_str("\x1b[#}", &mut grid);
+        assert_eq!(grid.bg, palette_color(4));
+    }
+
+    #[test]
+    fn test_sgr_stack_is_bounded() {
+        use crate::ansi::AnsiParser;
+        use crate::constants::MAX_SGR_STACK_DEPTH;
+        let mut grid = grid_new(2, 10);
+        let mut parser = AnsiParser::new();
+        for color in 0..MAX_SGR_STACK_DEPTH + 5 {
+            parser.feed_str(&format!("\x1b[38;5;{}m\x1b[#{{", color), &mut grid);
+        }
+        assert_eq!(grid.sgr_stack.len(), MAX_SGR_STACK_DEPTH);
+
+        // The newest saves survive, the oldest were dropped
+        parser.feed_str("\x1b[#}", &mut grid);
+        assert_eq!(grid.fg, palette_color(MAX_SGR_STACK_DEPTH as u8 + 4));
+        for _ in 1..MAX_SGR_STACK_DEPTH {
+            parser.feed_str("\x1b[#}", &mut grid);
+        }
+        assert_eq!(grid.fg, palette_color(5));
+        assert!(grid.sgr_stack.is_empty());
+    }
+
+    #[test]
+    fn test_clear_screen_scrolls_into_scrollback() {
+        use crate::ansi::AnsiParser;
+        let mut grid = grid_new(4, 10);
+        let mut parser = AnsiParser::new();
+        parser.feed_str("one\r\n", &mut grid);
+        grid.handle_semantic_prompt('A', "");
+        parser.feed_str("$ make", &mut grid);
+        let prompt_row = grid.absolute_row(1);
+
+        // Ctrl+L in bash: home, erase display, redraw the prompt
+        parser.feed_str("\x1b[H\x1b[2J$ make", &mut grid);
+        assert_eq!(grid.scrollback_rows(), 2);
+        assert_eq!(grid.scrollback_row(0).unwrap()[0].ch, 'o');
+        assert_eq!(grid.scrollback_row(1).unwrap()[0].ch, '$');
+        assert_eq!(grid.get_cell(0, 0).ch, '$');
+        assert_eq!(grid.get_cell(1, 0).ch, '\0');
+        assert_eq!(grid.search_scrollback("one"), vec![0]);
+        // The prompt mark still points at the row, now in the scrollback
+        assert_eq!(grid.scroll_marks()[0].row, prompt_row);
+
+        // An empty screen adds nothing
+        parser.feed_str("\x1b[2J\x1b[2J", &mut grid);
+        assert_eq!(grid.scrollback_rows(), 3);
+
+        // `clear` follows ED 2 with ED 3, which erases the scrollback
+        parser.feed_str("\x1b[3J", &mut grid);
+        assert_eq!(grid.scrollback_rows(), 0);
+        assert!(grid.scroll_marks().is_empty());
+        assert!(grid.search_scrollback("one").is_empty());
+        assert_eq!(grid.first_absolute_row(), 3);
+    }
+
+    #[test]
+    fn test_clear_screen_on_alternate_screen_keeps_scrollback_unchanged() {
+        use crate::ansi::AnsiParser;
+        let mut grid = grid_new(3, 10);
+        let mut parser = AnsiParser::new();
+        grid.use_alternate_screen(true);
+        parser.feed_str("vim\x1b[2J", &mut grid);
+        assert_eq!(grid.scrollback_rows(), 0);
+    }
+
+    #[test]
+    fn test_no_marks_on_alternate_screen() {
+        let mut grid = grid_new(3, 10);
+        grid.use_alternate_screen(true);
+        grid.handle_semantic_prompt('A', "");
+        assert!(grid.scroll_marks().is_empty());
+    }
+
+    #[test]
+    fn test_underline_style_stored_in_cells() {
+        use crate::ansi::AnsiParser;
+        let mut grid = grid_new(2, 10);
+        let mut parser = AnsiParser::new();
+
+        parser.feed_str("\x1B[4:3ma\x1B[4mb\x1B[24mc", &mut grid);
+        assert_eq!(grid.get_cell(0, 0).underline_style, UnderlineStyle::Curly);
+        assert!(grid.get_cell(0, 0).underline);
+        assert_eq!(grid.get_cell(0, 1).underline_style, UnderlineStyle::Single);
+        assert_eq!(grid.get_cell(0, 2).underline_style, UnderlineStyle::None);
+        assert!(!grid.get_cell(0, 2).underline);
+    }
+
+    #[test]
+    fn test_decoration_color_follows_fg_until_set() {
+        use crate::ansi::AnsiParser;
+        let config = crate::config::TerminalConfig::default();
+        let mut grid = grid_new(2, 10);
+        let mut parser = AnsiParser::new();
+
+        // Without SGR 58 the decoration takes whatever the foreground is
+        parser.feed_str("\x1B[4;9;53;31ma\x1B[32mb", &mut grid);
+        let a = *grid.get_cell(0, 0);
+        assert!(a.underline && a.strikethrough && a.overline);
+        assert_eq!(config.resolve_decoration(&a), None);
+        assert_ne!(config.resolve_fg(&a), config.resolve_fg(grid.get_cell(0, 1)));
+
+        // SGR 58 pins it across foreground changes, 59 releases it
+        parser.feed_str("\x1B[58:5:4mc\x1B[33md\x1B[59me", &mut grid);
+        assert_eq!(config.resolve_decoration(grid.get_cell(0, 2)), Some(palette_color(4)));
+        assert_eq!(config.resolve_decoration(grid.get_cell(0, 3)), Some(palette_color(4)));
+        assert_eq!(config.resolve_decoration(grid.get_cell(0, 4)), None);
+
+        // SGR 0 drops decorations and their color; 29 and 55 end their line only
+        parser.feed_str("\x1B[58:2::1:2:3m\x1B[0mf\x1B[9;53;29mg", &mut grid);
+        let f = *grid.get_cell(0, 5);
+        assert!(!f.underline && !f.strikethrough && !f.overline);
+        assert_eq!(config.resolve_decoration(&f), None);
+        let g = *grid.get_cell(0, 6);
+        assert!(!g.strikethrough && g.overline);
+    }
+
+    /// bash-preexec style session: prompt, typed command, run, exit status
+    fn run_command(grid: &mut Grid, command: &str, exit: i32) {
+        use crate::ansi::AnsiParser;
+        let mut parser = AnsiParser::new();
+        parser.feed_str(&format!("\x1B]133;A\x07$ \x1B]133;B\x07{command}\r\n\x1B]133;C\x07output\r\n"), grid);
+        parser.feed_str(&format!("\x1B]133;D;{exit}\x07"), grid);
+    }
+
+    #[test]
+    fn test_finished_command_reported_only_when_unfocused() {
+        let config = crate::config::TerminalConfig::default().with_command_notify_threshold(0);
+        let mut grid = Grid::new(20, 3, std::sync::Arc::new(config));
+
+        run_command(&mut grid, "true", 0);
+        assert!(grid.take_finished_commands().is_empty(), "focused terminal");
+
+        grid.set_focused(false);
+        // The command line scrolls into the scrollback before C on a full screen
+        run_command(&mut grid, "make -j8 all", 2);
+        let finished = grid.take_finished_commands();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].command, "make -j8 all");
+        assert_eq!(finished[0].exit_code, Some(2));
+        assert!(grid.take_finished_commands().is_empty());
+    }
+
+    #[test]
+    fn test_short_commands_and_disabled_notifications_not_reported() {
+        let config = crate::config::TerminalConfig::default().with_command_notify_threshold(60_000);
+        let mut grid = Grid::new(20, 3, std::sync::Arc::new(config));
+        grid.set_focused(false);
+        run_command(&mut grid, "ls", 0);
+        assert!(grid.take_finished_commands().is_empty());
+
+        let mut grid = grid_new(3, 20);
+        grid.set_focused(false);
+        run_command(&mut grid, "ls", 0);
+        assert!(grid.take_finished_commands().is_empty());
+    }
+
+    #[test]
+    fn test_exit_status_recorded_on_prompt_mark() {
+        let mut grid = Grid::new(20, 10, config());
+        assert_eq!(grid.last_command_status(), None);
+
+        run_command(&mut grid, "true", 0);
+        run_command(&mut grid, "false", 1);
+        assert_eq!(grid.last_command_status(), Some(1));
+
+        let codes: Vec<Option<i32>> = grid.scroll_marks().iter().map(|m| m.exit_code).collect();
+        assert_eq!(codes, vec![Some(0), Some(1)]);
+
+        // Next prompt, no status yet
+        use crate::ansi::AnsiParser;
+        AnsiParser::new().feed_str("\x1B]133;A\x07$ \x1B]133;D\x07", &mut grid);
+        assert_eq!(grid.last_command_status(), None);
+        assert_eq!(grid.scroll_marks()[2].exit_code, None);
+    }
+
+    #[test]
+    fn test_last_command_output() {
+        use crate::ansi::AnsiParser;
+        let mut grid = Grid::new(20, 10, config());
+        assert_eq!(grid.last_command_output(), None);
+
+        run_command(&mut grid, "ls", 0);
+        let mut parser = AnsiParser::new();
+        parser.feed_str("\x1B]133;A\x07$ \x1B]133;B\x07make\r\n\x1B]133;C\x07cc a.c\r\ncc b.c\r\n\r\n\x1B]133;D;0\x07", &mut grid);
+        parser.feed_str("\x1B]133;A\x07$ ", &mut grid);
+        assert_eq!(grid.last_command_output().as_deref(), Some("cc a.c\ncc b.c"));
+
+        // The visible output is selected for copying
+        assert_eq!(grid.select_last_command_output().as_deref(), Some("cc a.c\ncc b.c"));
+        assert_eq!(grid.get_normalized_bounds(), Some(((3, 0), (5, 19))));
+    }
+
+    #[test]
+    fn test_wide_output_is_copied_and_indexed_without_spacers() {
+        use crate::ansi::AnsiParser;
+        let mut grid = Grid::new(20, 3, config());
+        let mut parser = AnsiParser::new();
+        parser.feed_str("\x1B]133;A\x07$ \x1B]133;B\x07cat\r\n\x1B]133;C\x07中文 ok\r\n\x1B]133;D;0\x07", &mut grid);
+        parser.feed_str("\x1B]133;A\x07$ ", &mut grid);
+        assert_eq!(grid.last_command_output().as_deref(), Some("中文 ok"));
+
+        // Scrolled into the scrollback, the row is found as written
+        parser.feed_str("\r\n\r\n\r\n", &mut grid);
+        assert_eq!(grid.search_scrollback("中文"), vec![1]);
+        assert_eq!(grid.search_scrollback("中 文"), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn test_last_command_output_without_shell_integration() {
+        use crate::ansi::AnsiParser;
+        let mut grid = Grid::new(20, 10, config());
+        AnsiParser::new().feed_str("user@host:~$ ls\r\na.txt\r\nb.txt\r\nuser@host:~$ ", &mut grid);
+        assert_eq!(grid.last_command_output().as_deref(), Some("a.txt\nb.txt"));
+
+        // No earlier prompt to measure from
+        let mut grid = Grid::new(20, 10, config());
+        AnsiParser::new().feed_str("banner\r\nuser@host:~$ ", &mut grid);
+        assert_eq!(grid.last_command_output(), None);
+    }
+
+    fn row_text(grid: &Grid, row: usize) -> String {
+        grid.cells.row(row).iter().map(|c| if c.ch == '\0' { ' ' } else { c.ch }).collect::<String>().trim_end().to_string()
+    }
+
+    #[test]
+    fn test_dec_special_graphics_table() {
+        let table = [
+            ('_', ' '), ('`', '◆'), ('a', '▒'), ('b', '␉'), ('c', '␌'), ('d', '␍'), ('e', '␊'), ('f', '°'),
+            ('g', '±'), ('h', '␤'), ('i', '␋'), ('j', '┘'), ('k', '┐'), ('l', '┌'), ('m', '└'), ('n', '┼'),
+            ('o', '⎺'), ('p', '⎻'), ('q', '─'), ('r', '⎼'), ('s', '⎽'), ('t', '├'), ('u', '┤'), ('v', '┴'),
+            ('w', '┬'), ('x', '│'), ('y', '≤'), ('z', '≥'), ('{', 'π'), ('|', '≠'), ('}', '£'), ('~', '·'),
+            // Outside 0x5F-0x7E nothing changes
+            ('A', 'A'), ('#', '#'), ('0', '0'),
+        ];
+        for (ascii, glyph) in table {
+            let mut grid = grid_new(1, 4);
+            AnsiParser::new().feed_str(&format!("\x1b(0{ascii}\x1b(B{ascii}"), &mut grid);
+            assert_eq!(grid.cells[0].ch, glyph, "DEC graphics for {ascii:?}");
+            assert_eq!(grid.cells[1].ch, ascii, "US-ASCII for {ascii:?}");
+        }
+    }
+
+    #[test]
+    fn test_charset_designation_and_shifts() {
+        // tput smacs / rmacs (xterm): designate G0
+        let mut grid = grid_new(6, 10);
+        AnsiParser::new().feed_str("\x1b(0lqqk\r\nx  x\r\nmqqj\x1b(B ok", &mut grid);
+        assert_eq!(row_text(&grid, 0), "┌──┐");
+        assert_eq!(row_text(&grid, 1), "│  │");
+        assert_eq!(row_text(&grid, 2), "└──┘ ok");
+
+        // Graphics in G1, switched with SO / SI; UK in G2 for one character
+        let mut parser = AnsiParser::new();
+        parser.feed_str("\x1b[4;1H\x1b)0q\x0eq\x0fq", &mut grid);
+        assert_eq!(row_text(&grid, 3), "q─q");
+        parser.feed_str("\x1b[5;1H\x1b*A#\x1bN##\x1b(A#", &mut grid);
+        assert_eq!(row_text(&grid, 4), "#£#£");
     }
 }
@@ -0,0 +1,106 @@
+// src/grid_view.rs
+//! Read-only view of the screen
+//!
+//! Embedders that read the screen - status bars, accessibility, tests -
+//! need the cells, cursor, title, selection and scrollback, not the
+//! mutators the parser and input handling drive. [`GridView`] is that
+//! surface: `Grid` implements it, `VteTerminalCore::read_grid` lends it out
+//! under the grid's read lock, and the prelude exports it instead of `Grid`.
+
+use crate::ansi::Cell;
+use crate::grid::Grid;
+use crate::selection::SelectionSpan;
+use std::borrow::Cow;
+
+/// What an embedder can read from a terminal's grid
+pub trait GridView {
+    /// Screen width and height in cells
+    fn size(&self) -> (usize, usize);
+
+    /// Cell at `row`, `col` of the screen shown (main or alternate); panics
+    /// outside the screen
+    fn cell(&self, row: usize, col: usize) -> &Cell;
+
+    /// The cursor's viewport row and column; `None` while scrolled out of view
+    fn cursor(&self) -> Option<(usize, usize)>;
+
+    /// Window title set with OSC 0 or 2
+    fn title(&self) -> &str;
+
+    /// Selection highlight spans on the screen
+    fn selection_spans(&self) -> Vec<SelectionSpan>;
+
+    /// Text of the selection; empty without one
+    fn selected_text(&self) -> String;
+
+    /// Rows held in the scrollback
+    fn scrollback_rows(&self) -> usize;
+
+    /// Scrollback row `index`, 0 being the oldest
+    fn scrollback_row(&self, index: usize) -> Option<Cow<'_, [Cell]>>;
+}
+
+impl GridView for Grid {
+    fn size(&self) -> (usize, usize) {
+        (self.cols, self.rows)
+    }
+
+    fn cell(&self, row: usize, col: usize) -> &Cell {
+        self.get_cell(row, col)
+    }
+
+    fn cursor(&self) -> Option<(usize, usize)> {
+        self.cursor_viewport_cell()
+    }
+
+    fn title(&self) -> &str {
+        Grid::title(self)
+    }
+
+    fn selection_spans(&self) -> Vec<SelectionSpan> {
+        Grid::selection_spans(self)
+    }
+
+    fn selected_text(&self) -> String {
+        self.get_selected_text()
+    }
+
+    fn scrollback_rows(&self) -> usize {
+        Grid::scrollback_rows(self)
+    }
+
+    fn scrollback_row(&self, index: usize) -> Option<Cow<'_, [Cell]>> {
+        Grid::scrollback_row(self, index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::AnsiParser;
+    use crate::config::TerminalConfig;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_view_reads_the_grid() {
+        let mut grid = Grid::new(6, 2, Arc::new(TerminalConfig::default()));
+        let mut parser = AnsiParser::new();
+        parser.feed_str("\x1b]2;logs\x07one\r\ntwo", &mut grid);
+        grid.select_line(1);
+        {
+            let view: &dyn GridView = &grid;
+            assert_eq!(view.size(), (6, 2));
+            assert_eq!(view.cell(1, 0).ch, 't');
+            assert_eq!(view.cursor(), Some((1, 3)));
+            assert_eq!(view.title(), "logs");
+            assert_eq!(view.selected_text().trim_end(), "two");
+            assert_eq!(view.selection_spans().len(), 1);
+            assert_eq!(view.scrollback_rows(), 0);
+        }
+
+        parser.feed_str("\r\nsix", &mut grid);
+        let view: &dyn GridView = &grid;
+        assert_eq!(view.scrollback_rows(), 1);
+        assert_eq!(view.scrollback_row(0).unwrap()[0].ch, 'o');
+    }
+}
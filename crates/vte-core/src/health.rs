@@ -0,0 +1,135 @@
+// src/health.rs
+//! Parser health reports for long-running embedders
+//!
+//! A program flooding the terminal with malformed sequences shows up as
+//! parse errors, which the PTY reader already condenses into one
+//! `TerminalError::ParserError` per burst. Embedders that want a steady view,
+//! e.g. a diagnostics panel, set `TerminalConfig::parser_health_interval_ms`:
+//! the reader records the parser's counters with every chunk, and every
+//! interval in which they moved the core emits `TerminalEvent::ParserHealth`
+//! with rates over that interval and the last error seen. An idle terminal
+//! sends nothing.
+
+use crate::ansi::ParserStats;
+use std::time::{Duration, Instant};
+
+/// Parser activity over one reporting interval
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParserHealth {
+    /// Length of the interval the rates are measured over
+    pub interval: Duration,
+    /// Escape sequences processed per second
+    pub sequences_per_sec: f64,
+    /// Parse errors per second
+    pub errors_per_sec: f64,
+    /// OSC sequences handled in the interval
+    pub osc_dispatched: u64,
+    /// Parse errors since the terminal started
+    pub total_errors: u64,
+    /// Description of the most recent parse error, if there was one
+    pub last_error: Option<String>,
+}
+
+/// Parser counters as last recorded and as last reported
+#[derive(Debug, Clone)]
+pub struct HealthMonitor {
+    current: ParserStats,
+    reported: ParserStats,
+    reported_at: Instant,
+    last_error: Option<String>,
+}
+
+impl HealthMonitor {
+    /// Monitor whose first interval starts at `now`
+    pub fn new(now: Instant) -> Self {
+        Self { current: ParserStats::default(), reported: ParserStats::default(), reported_at: now, last_error: None }
+    }
+
+    /// Record the parser's counters after a chunk; `last_error` is the text
+    /// of its most recent error, if any
+    pub fn record(&mut self, stats: &ParserStats, last_error: Option<&str>) {
+        self.current = stats.clone();
+        if let Some(error) = last_error {
+            self.last_error = Some(error.to_string());
+        }
+    }
+
+    /// Report for the interval ending at `now`, if the parser did anything
+    /// in it; the next interval starts at `now` either way
+    pub fn poll(&mut self, now: Instant) -> Option<ParserHealth> {
+        let interval = now.saturating_duration_since(self.reported_at);
+        self.reported_at = now;
+        let sequences = self.current.sequences_processed.saturating_sub(self.reported.sequences_processed);
+        let errors = self.current.errors_encountered.saturating_sub(self.reported.errors_encountered);
+        let osc_dispatched = self.current.osc_dispatched.saturating_sub(self.reported.osc_dispatched);
+        self.reported = self.current.clone();
+        if sequences == 0 && errors == 0 && osc_dispatched == 0 {
+            return None;
+        }
+        let per_sec = |count: u64| if interval.is_zero() { 0.0 } else { count as f64 / interval.as_secs_f64() };
+        Some(ParserHealth {
+            interval,
+            sequences_per_sec: per_sec(sequences),
+            errors_per_sec: per_sec(errors),
+            osc_dispatched,
+            total_errors: self.current.errors_encountered,
+            last_error: self.last_error.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::AnsiParser;
+    use crate::config::TerminalConfig;
+    use crate::grid::Grid;
+    use std::sync::Arc;
+
+    fn stats(sequences: u64, errors: u64, osc: u64) -> ParserStats {
+        ParserStats { sequences_processed: sequences, errors_encountered: errors, osc_dispatched: osc, ..ParserStats::default() }
+    }
+
+    #[test]
+    fn test_rates_over_interval() {
+        let start = Instant::now();
+        let mut monitor = HealthMonitor::new(start);
+        monitor.record(&stats(400, 20, 3), Some("parameter too large"));
+        let health = monitor.poll(start + Duration::from_secs(2)).unwrap();
+        assert_eq!(health.interval, Duration::from_secs(2));
+        assert_eq!(health.sequences_per_sec, 200.0);
+        assert_eq!(health.errors_per_sec, 10.0);
+        assert_eq!(health.osc_dispatched, 3);
+        assert_eq!(health.last_error.as_deref(), Some("parameter too large"));
+
+        // Only the next interval's activity counts; the last error stays
+        monitor.record(&stats(500, 20, 3), None);
+        let health = monitor.poll(start + Duration::from_secs(3)).unwrap();
+        assert_eq!(health.sequences_per_sec, 100.0);
+        assert_eq!(health.errors_per_sec, 0.0);
+        assert_eq!(health.total_errors, 20);
+        assert_eq!(health.last_error.as_deref(), Some("parameter too large"));
+    }
+
+    #[test]
+    fn test_idle_interval_not_reported() {
+        let start = Instant::now();
+        let mut monitor = HealthMonitor::new(start);
+        assert_eq!(monitor.poll(start + Duration::from_secs(1)), None);
+        monitor.record(&stats(1, 0, 0), None);
+        assert!(monitor.poll(start + Duration::from_secs(2)).is_some());
+        assert_eq!(monitor.poll(start + Duration::from_secs(3)), None);
+    }
+
+    #[test]
+    fn test_grid_records_parser_stats() {
+        let mut grid = Grid::new(20, 2, Arc::new(TerminalConfig::default()));
+        let mut parser = AnsiParser::new();
+        parser.feed_str("\x1b[1m\x1b]0;title\x07", &mut grid);
+        grid.record_parser_stats(parser.stats(), None);
+        let health = grid.poll_parser_health().unwrap();
+        assert_eq!(health.osc_dispatched, 1);
+        assert_eq!(health.total_errors, 0);
+        assert_eq!(grid.poll_parser_health(), None);
+    }
+}
@@ -0,0 +1,170 @@
+// src/highlights.rs
+//! Embedder-defined text highlights
+//!
+//! A find panel or linter built by the embedder marks ranges of text
+//! without touching the user's selection: `Grid::highlight_ranges` adds
+//! ranges drawn in a [`HighlightStyle`], `Grid::clear_highlights` removes
+//! them all. Ranges are (absolute row, column) pairs, start inclusive and
+//! end exclusive, so they stay on their text as it scrolls and are dropped
+//! once it is trimmed from the scrollback. Renderers draw the visible part
+//! in the overlay pass, under the selection.
+
+use crate::ansi::Color;
+use crate::selection::{Selection, SelectionSpan};
+use std::ops::Range;
+
+/// Fill color of a highlight, and optionally the color its text is redrawn in
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HighlightStyle {
+    pub background: Color,
+    pub foreground: Option<Color>,
+}
+
+impl HighlightStyle {
+    pub fn new(background: Color) -> Self {
+        Self { background, foreground: None }
+    }
+
+    pub fn with_foreground(mut self, foreground: Color) -> Self {
+        self.foreground = Some(foreground);
+        self
+    }
+}
+
+/// Screen span of a highlight, for an overlay pass
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HighlightSpan {
+    pub span: SelectionSpan,
+    pub style: HighlightStyle,
+}
+
+/// Highlighted ranges, in the order they were added
+#[derive(Clone, Debug, Default)]
+pub struct Highlights {
+    ranges: Vec<(Range<(u64, usize)>, HighlightStyle)>,
+}
+
+impl Highlights {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `ranges` in `style`; empty ranges are skipped
+    pub fn add(&mut self, ranges: Vec<Range<(u64, usize)>>, style: HighlightStyle) {
+        self.ranges.extend(ranges.into_iter().filter(|range| range.start < range.end).map(|range| (range, style)));
+    }
+
+    pub fn clear(&mut self) {
+        self.ranges.clear();
+    }
+
+    /// Drop ranges ending before absolute row `row`, which was trimmed
+    pub fn prune_before(&mut self, row: u64) {
+        self.ranges.retain(|(range, _)| range.end > (row, 0));
+    }
+
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Spans of the ranges on a `rows` x `cols` viewport whose top is
+    /// absolute row `top`, in the order the ranges were added
+    pub fn spans(&self, top: u64, rows: usize, cols: usize) -> Vec<HighlightSpan> {
+        self.ranges
+            .iter()
+            .filter_map(|(range, style)| Some((visible_part(range, top, rows, cols)?, *style)))
+            .flat_map(|(((start_row, start_col), (end_row, end_col)), style)| {
+                let mut selection = Selection::new();
+                selection.create_selection(start_row, start_col, end_row, end_col);
+                selection.spans(rows, cols).into_iter().map(move |span| HighlightSpan { span, style })
+            })
+            .collect()
+    }
+}
+
+/// Part of `range` on a `rows` x `cols` viewport whose top is absolute row
+/// `top`, as screen (row, column) pairs with both ends inclusive
+pub(crate) fn visible_part(
+    range: &Range<(u64, usize)>,
+    top: u64,
+    rows: usize,
+    cols: usize,
+) -> Option<((usize, usize), (usize, usize))> {
+    let bottom = top + rows as u64;
+    let (start, end) = (range.start, range.end);
+    if start >= end || cols == 0 {
+        return None;
+    }
+    // The end is exclusive; a range ending at column 0 ends on the row above
+    let (end_row, end_col) = match end.1.min(cols) {
+        0 => (end.0.checked_sub(1)?, cols - 1),
+        col => (end.0, col - 1),
+    };
+    if start.0 >= bottom || end_row < top {
+        return None;
+    }
+    let (start_row, start_col) = if start.0 < top { (top, 0) } else { (start.0, start.1.min(cols - 1)) };
+    let (end_row, end_col) = if end_row >= bottom { (bottom - 1, cols - 1) } else { (end_row, end_col) };
+    Some((((start_row - top) as usize, start_col), ((end_row - top) as usize, end_col)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TerminalConfig;
+    use crate::grid::Grid;
+    use std::sync::Arc;
+
+    fn style() -> HighlightStyle {
+        HighlightStyle::new(Color::rgb(1.0, 1.0, 0.0))
+    }
+
+    fn spans(highlights: &Highlights, top: u64) -> Vec<(usize, usize, usize)> {
+        highlights.spans(top, 3, 10).iter().map(|h| (h.span.row, h.span.start_col, h.span.end_col)).collect()
+    }
+
+    #[test]
+    fn test_spans_are_clipped_to_the_viewport() {
+        let mut highlights = Highlights::new();
+        highlights.add(vec![(5, 2)..(5, 6), (6, 8)..(8, 0), (9, 1)..(9, 1)], style());
+        assert_eq!(highlights.len(), 2);
+
+        assert_eq!(spans(&highlights, 5), vec![(0, 2, 6), (1, 8, 10), (2, 0, 10)]);
+        // Scrolled so the second range starts above the viewport
+        assert_eq!(spans(&highlights, 7), vec![(0, 0, 10)]);
+        assert!(spans(&highlights, 20).is_empty());
+
+        highlights.prune_before(7);
+        assert_eq!(highlights.len(), 1);
+        highlights.clear();
+        assert!(highlights.is_empty());
+    }
+
+    #[test]
+    fn test_grid_highlights_and_selects_absolute_ranges() {
+        let mut grid = Grid::new(10, 3, Arc::new(TerminalConfig::default()));
+        for line in ["one", "two", "three", "four", "five"] {
+            crate::ansi::AnsiParser::new().feed_str(&format!("{line}\r\n"), &mut grid);
+        }
+        let top = grid.first_absolute_row();
+
+        grid.highlight_ranges(vec![(top + 1, 0)..(top + 1, 3)], style());
+        assert!(grid.highlight_spans().is_empty());
+        assert!(grid.select_range((top + 1, 0), (top + 1, 3)));
+        assert_eq!(grid.viewport_top_row(), top + 1);
+        assert_eq!(grid.highlight_spans().len(), 1);
+        let selected: Vec<_> = grid.selection_spans().iter().map(|s| (s.row, s.start_col, s.end_col)).collect();
+        assert_eq!(selected, vec![(0, 0, 3)]);
+
+        // Highlights are independent of the user's selection
+        grid.clear_selection();
+        assert_eq!(grid.highlight_spans().len(), 1);
+        grid.clear_highlights();
+        assert!(grid.highlight_spans().is_empty());
+        assert!(!grid.select_range((top + 2, 0), (top + 2, 0)));
+    }
+}
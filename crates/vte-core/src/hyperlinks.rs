@@ -0,0 +1,172 @@
+// src/hyperlinks.rs
+//! OSC 8 hyperlink storage
+//!
+//! Cells carry a small link id (`Cell::hyperlink`, 0 for none) instead of the
+//! URI itself so they stay `Copy`. The grid owns a [`HyperlinkTable`] mapping
+//! ids to URIs; an OSC 8 `id=` parameter keeps separately opened runs of the
+//! same link together, as the spec requires.
+//!
+//! The grid bounds its table at `MAX_HYPERLINKS` links. When a new link does
+//! not fit, the links no cell refers to any more are dropped and their ids
+//! reused; if every link is still referenced, the new one is shown as plain
+//! text.
+
+use std::collections::HashMap;
+
+/// Id stored in `Cell::hyperlink` for cells outside any link
+pub const NO_HYPERLINK: u32 = 0;
+
+/// What copying a selection produces for hyperlinked text
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HyperlinkCopyMode {
+    /// The visible text only
+    #[default]
+    TextOnly,
+    /// The link target instead of its text
+    UriOnly,
+    /// `[text](uri)`
+    Markdown,
+}
+
+impl HyperlinkCopyMode {
+    /// Clipboard text for a run of link text pointing at `uri`
+    pub fn format(&self, text: &str, uri: &str) -> String {
+        match self {
+            HyperlinkCopyMode::TextOnly => text.to_string(),
+            HyperlinkCopyMode::UriOnly => uri.to_string(),
+            HyperlinkCopyMode::Markdown => format!("[{}]({})", text, uri),
+        }
+    }
+}
+
+/// URIs of the hyperlinks referenced by cells, by id
+#[derive(Clone, Debug)]
+pub struct HyperlinkTable {
+    /// URI per id, `None` for ids freed by `retain`
+    uris: Vec<Option<String>>,
+    ids: HashMap<(Option<String>, String), u32>,
+    /// Freed ids, handed out again before new ones
+    free: Vec<u32>,
+    capacity: usize,
+}
+
+impl Default for HyperlinkTable {
+    fn default() -> Self {
+        Self::with_capacity(usize::MAX)
+    }
+}
+
+impl HyperlinkTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Table holding at most `capacity` links at once
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self { uris: Vec::new(), ids: HashMap::new(), free: Vec::new(), capacity }
+    }
+
+    /// Id for the link to `uri` opened with OSC 8 `params`, reusing the id of
+    /// an identical earlier link; `None` when a new link does not fit
+    ///
+    /// Links without an `id=` parameter are shared by URI.
+    pub fn intern(&mut self, params: Option<&str>, uri: &str) -> Option<u32> {
+        let link_id = params.and_then(|params| {
+            params.split(':').find_map(|param| param.strip_prefix("id=")).map(str::to_string)
+        });
+        let key = (link_id, uri.to_string());
+        if let Some(&id) = self.ids.get(&key) {
+            return Some(id);
+        }
+        if self.len() >= self.capacity {
+            return None;
+        }
+        let id = match self.free.pop() {
+            Some(id) => {
+                self.uris[id as usize - 1] = Some(uri.to_string());
+                id
+            }
+            None => {
+                self.uris.push(Some(uri.to_string()));
+                self.uris.len() as u32
+            }
+        };
+        self.ids.insert(key, id);
+        Some(id)
+    }
+
+    /// URI of a link id; `None` for [`NO_HYPERLINK`] and unknown ids
+    pub fn uri(&self, id: u32) -> Option<&str> {
+        let index = (id as usize).checked_sub(1)?;
+        self.uris.get(index)?.as_deref()
+    }
+
+    /// Keep only the links whose id `keep` accepts, freeing the other ids
+    pub fn retain(&mut self, mut keep: impl FnMut(u32) -> bool) {
+        self.ids.retain(|_, id| keep(*id));
+        for (index, uri) in self.uris.iter_mut().enumerate() {
+            let id = index as u32 + 1;
+            if uri.is_some() && !keep(id) {
+                *uri = None;
+                self.free.push(id);
+            }
+        }
+    }
+
+    /// Links held
+    pub fn len(&self) -> usize {
+        self.uris.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intern_shares_ids() {
+        let mut table = HyperlinkTable::new();
+        let a = table.intern(None, "https://example.com").unwrap();
+        assert_ne!(a, NO_HYPERLINK);
+        assert_eq!(table.intern(None, "https://example.com"), Some(a));
+
+        // An explicit id separates otherwise identical links
+        let b = table.intern(Some("id=1"), "https://example.com").unwrap();
+        assert_ne!(b, a);
+        assert_eq!(table.intern(Some("foo=bar:id=1"), "https://example.com"), Some(b));
+
+        assert_eq!(table.uri(b), Some("https://example.com"));
+        assert_eq!(table.uri(NO_HYPERLINK), None);
+        assert_eq!(table.uri(99), None);
+        assert_eq!(table.len(), 2);
+    }
+
+    #[test]
+    fn test_full_table_reuses_freed_ids() {
+        let mut table = HyperlinkTable::with_capacity(2);
+        let a = table.intern(None, "https://a").unwrap();
+        let b = table.intern(None, "https://b").unwrap();
+        assert_eq!(table.intern(None, "https://c"), None);
+        // Known links still resolve while full
+        assert_eq!(table.intern(None, "https://a"), Some(a));
+
+        table.retain(|id| id == b);
+        assert_eq!((table.len(), table.uri(a)), (1, None));
+        assert_eq!(table.intern(None, "https://c"), Some(a));
+        assert_eq!(table.uri(a), Some("https://c"));
+        assert_eq!(table.uri(b), Some("https://b"));
+        assert_eq!(table.intern(None, "https://a"), None);
+    }
+
+    #[test]
+    fn test_copy_modes() {
+        let uri = "https://example.com/docs";
+        assert_eq!(HyperlinkCopyMode::TextOnly.format("docs", uri), "docs");
+        assert_eq!(HyperlinkCopyMode::UriOnly.format("docs", uri), uri);
+        assert_eq!(HyperlinkCopyMode::Markdown.format("docs", uri), "[docs](https://example.com/docs)");
+    }
+}
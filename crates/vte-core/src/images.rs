@@ -0,0 +1,178 @@
+// src/images.rs
+//! Images placed on the grid, e.g. decoded [sixel](crate::sixel) graphics
+//!
+//! An image hangs off the absolute row it was placed on, like
+//! [annotations](crate::annotations), so it scrolls with the text around it
+//! and is dropped once its last row is trimmed from the scrollback. Scrolls
+//! that move text without feeding the scrollback (the alternate screen, SU/SD,
+//! IL/DL) move the images with it, and text written or erased over an image
+//! removes it. The layer keeps the cells an image covers for placement and
+//! clearing; renderers draw the pixels at their natural size from the
+//! top-left cell.
+
+use crate::traits::ImageData;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Image anchored to the cell at absolute `row` and `col`
+#[derive(Clone, Debug)]
+pub struct PlacedImage {
+    pub row: u64,
+    pub col: usize,
+    /// Columns and rows the image covers at the cell size it was placed with
+    pub cols: usize,
+    pub rows: usize,
+    pub image: Arc<ImageData>,
+}
+
+impl PlacedImage {
+    /// Absolute row just below the image
+    pub fn end_row(&self) -> u64 {
+        self.row + self.rows as u64
+    }
+}
+
+/// Placed images sorted by absolute row, oldest first within a row
+#[derive(Clone, Debug, Default)]
+pub struct ImageLayer {
+    images: Vec<PlacedImage>,
+    /// Pixel bytes held by `images`
+    bytes: usize,
+}
+
+impl ImageLayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `image`, dropping the topmost (usually oldest) images while the
+    /// layer holds more than `max_bytes` of pixels
+    pub fn add(&mut self, image: PlacedImage, max_bytes: usize) {
+        self.bytes += image.image.data.len();
+        let idx = self.images.partition_point(|i| i.row <= image.row);
+        self.images.insert(idx, image);
+        while self.bytes > max_bytes && self.images.len() > 1 {
+            let removed = self.images.remove(0);
+            self.bytes -= removed.image.data.len();
+        }
+    }
+
+    /// Remove all images
+    pub fn clear(&mut self) {
+        self.images.clear();
+        self.bytes = 0;
+    }
+
+    /// Drop images whose rows all lie before `first_row` (trimmed from the scrollback)
+    pub fn prune_before(&mut self, first_row: u64) {
+        self.retain(|i| i.end_row() > first_row);
+    }
+
+    /// Drop images placed on `first_row` or below, e.g. on a screen being erased
+    pub fn remove_from(&mut self, first_row: u64) {
+        self.retain(|i| i.row < first_row);
+    }
+
+    /// Move the images placed on rows `start..end` by `delta` rows, dropping
+    /// those moved out of that range
+    pub fn shift_rows(&mut self, start: u64, end: u64, delta: i64) {
+        let range = start..end;
+        self.images.retain_mut(|image| {
+            if !range.contains(&image.row) {
+                return true;
+            }
+            match image.row.checked_add_signed(delta) {
+                Some(row) if range.contains(&row) => {
+                    image.row = row;
+                    true
+                }
+                _ => false,
+            }
+        });
+        self.bytes = self.images.iter().map(|i| i.image.data.len()).sum();
+        // Stable, so images on one row keep their order
+        self.images.sort_by_key(|i| i.row);
+    }
+
+    /// Drop images covering any of `cols` on absolute `row`
+    pub fn remove_covering(&mut self, row: u64, cols: Range<usize>) {
+        self.retain(|i| !((i.row..i.end_row()).contains(&row) && i.col < cols.end && cols.start < i.col + i.cols));
+    }
+
+    fn retain(&mut self, keep: impl Fn(&PlacedImage) -> bool) {
+        self.images.retain(keep);
+        self.bytes = self.images.iter().map(|i| i.image.data.len()).sum();
+    }
+
+    /// Images covering any of the rows `start..end`
+    pub fn in_rows(&self, start: u64, end: u64) -> impl Iterator<Item = &PlacedImage> {
+        let to = self.images.partition_point(|i| i.row < end);
+        self.images[..to].iter().filter(move |i| i.end_row() > start)
+    }
+
+    /// Pixel bytes held, for memory diagnostics
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub fn len(&self) -> usize {
+        self.images.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placed(row: u64, rows: usize, bytes: usize) -> PlacedImage {
+        let image = Arc::new(ImageData { data: vec![0; bytes], width: bytes / 4, height: 1 });
+        PlacedImage { row, col: 0, cols: 1, rows, image }
+    }
+
+    #[test]
+    fn test_rows_pruning_and_budget() {
+        let mut layer = ImageLayer::new();
+        layer.add(placed(10, 3, 8), usize::MAX);
+        layer.add(placed(2, 2, 8), usize::MAX);
+        assert_eq!(layer.in_rows(0, 3).map(|i| i.row).collect::<Vec<_>>(), [2]);
+        // An image starting above the range still covers it
+        assert_eq!(layer.in_rows(12, 20).map(|i| i.row).collect::<Vec<_>>(), [10]);
+
+        layer.prune_before(4);
+        assert_eq!(layer.len(), 1);
+        layer.remove_from(10);
+        assert!(layer.is_empty());
+        assert_eq!(layer.bytes(), 0);
+
+        // Past the budget the topmost image goes, but never the last one
+        layer.add(placed(1, 1, 8), 12);
+        layer.add(placed(2, 1, 8), 12);
+        assert_eq!(layer.in_rows(0, 10).map(|i| i.row).collect::<Vec<_>>(), [2]);
+        layer.add(placed(3, 1, 16), 12);
+        assert_eq!((layer.len(), layer.bytes()), (1, 16));
+    }
+
+    #[test]
+    fn test_shift_and_remove_covering() {
+        let mut layer = ImageLayer::new();
+        layer.add(placed(2, 2, 8), usize::MAX);
+        layer.add(placed(5, 1, 8), usize::MAX);
+        layer.add(placed(9, 1, 8), usize::MAX);
+
+        // Rows outside the range stay; those moved out of it go
+        layer.shift_rows(2, 8, -2);
+        assert_eq!(layer.in_rows(0, 20).map(|i| i.row).collect::<Vec<_>>(), [3, 9]);
+        layer.shift_rows(0, 10, 6);
+        assert_eq!(layer.in_rows(0, 20).map(|i| i.row).collect::<Vec<_>>(), [9]);
+        assert_eq!(layer.bytes(), 8);
+
+        layer.remove_covering(9, 1..4);
+        assert_eq!(layer.len(), 1);
+        layer.remove_covering(9, 0..1);
+        assert!(layer.is_empty());
+    }
+}
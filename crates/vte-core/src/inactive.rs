@@ -0,0 +1,74 @@
+// src/inactive.rs
+//! Rendering of unfocused terminals
+//!
+//! When several terminals are visible, e.g. the panes of a split, the
+//! container marks those without keyboard focus inactive and backends pass
+//! every color they draw through [`InactiveStyle::apply`]: colors lose
+//! saturation and fade toward the terminal background. This happens at draw
+//! time only; the grid keeps its colors, so refocusing restores them exactly.
+
+use crate::ansi::Color;
+
+/// How an inactive terminal's colors are muted
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InactiveStyle {
+    /// Fraction by which colors fade toward the background, 0.0 to 1.0
+    pub dim: f64,
+    /// Fraction by which colors fade toward gray, 0.0 to 1.0
+    pub desaturate: f64,
+}
+
+impl Default for InactiveStyle {
+    fn default() -> Self {
+        Self { dim: 0.35, desaturate: 0.0 }
+    }
+}
+
+impl InactiveStyle {
+    /// Inactive terminals look the same as the focused one
+    pub const NONE: InactiveStyle = InactiveStyle { dim: 0.0, desaturate: 0.0 };
+
+    /// `color` as drawn in an inactive terminal with the given background
+    ///
+    /// Alpha is kept, so a transparent background stays transparent.
+    pub fn apply(&self, color: Color, background: Color) -> Color {
+        let mix = |from: f64, to: f64, amount: f64| from + (to - from) * amount.clamp(0.0, 1.0);
+        let gray = 0.2126 * color.r + 0.7152 * color.g + 0.0722 * color.b;
+        let channel = |v: f64, bg: f64| mix(mix(v, gray, self.desaturate), bg, self.dim);
+        Color {
+            r: channel(color.r, background.r),
+            g: channel(color.g, background.g),
+            b: channel(color.b, background.b),
+            a: color.a,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLACK: Color = Color { r: 0.0, g: 0.0, b: 0.0, a: 1.0 };
+
+    #[test]
+    fn test_dim_fades_toward_background() {
+        let style = InactiveStyle { dim: 0.5, desaturate: 0.0 };
+        assert_eq!(style.apply(Color::rgb(1.0, 0.5, 0.0), BLACK), Color::rgb(0.5, 0.25, 0.0));
+        let white = Color::rgb(1.0, 1.0, 1.0);
+        assert_eq!(style.apply(BLACK, white), Color::rgb(0.5, 0.5, 0.5));
+        // The background itself is unchanged, and so is any alpha
+        assert_eq!(style.apply(white, white), white);
+        assert_eq!(style.apply(Color::rgba(0.0, 0.0, 0.0, 0.0), white).a, 0.0);
+    }
+
+    #[test]
+    fn test_desaturate_and_none() {
+        let red = Color::rgb(1.0, 0.0, 0.0);
+        let gray = InactiveStyle { dim: 0.0, desaturate: 1.0 }.apply(red, BLACK);
+        assert!((gray.r - 0.2126).abs() < 1e-9 && gray.r == gray.g && gray.g == gray.b);
+
+        assert_eq!(InactiveStyle::NONE.apply(red, BLACK), red);
+        // Out-of-range amounts are clamped
+        assert_eq!(InactiveStyle { dim: 3.0, desaturate: 0.0 }.apply(red, BLACK), BLACK);
+    }
+}
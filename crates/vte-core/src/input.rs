@@ -1,4 +1,6 @@
 use crate::grid::Grid;
+use crate::keys::{Key, KeyEncoder};
+use crate::text_input::{encode_text, TextSource};
 use gtk4::prelude::*;
 use gtk4::{
     DrawingArea, EventControllerKey, EventControllerMotion,
@@ -40,7 +42,8 @@ impl InputHandler {
             }
 
             // special keys
-            if let Some(seq) = Self::handle_special_keys(keyval, state) {
+            let encoder = grid.read().map(|g| g.key_encoder()).unwrap_or_default();
+            if let Some(seq) = Self::handle_special_keys(keyval, state, &encoder) {
                 Self::write_to_writer(&writer, seq);
                 let _ = tx.send_blocking(());
                 return Propagation::Stop;
@@ -81,7 +84,7 @@ impl InputHandler {
                 if !gr.is_selected(r, c) {
                     gr.clear_selection();
                 }
-                gr.start_selection(r, c);
+                gr.start_selection_at(r, c, x, y);
             }).ok();
             let _ = t.send_blocking(());
         });
@@ -109,7 +112,7 @@ impl InputHandler {
 
             g.write().map(|mut gr| {
                 if gr.is_selecting() {
-                    gr.update_selection(r, c);
+                    gr.update_selection_at(r, c, x, y);
                     let _ = t.send_blocking(());
                 }
             }).ok();
@@ -120,16 +123,9 @@ impl InputHandler {
         let g = grid;
         let t = tx;
         let scroll = EventControllerScroll::new(EventControllerScrollFlags::VERTICAL);
-        scroll.connect_scroll(move |_, _, dy| {
-            g.write().map(|mut gr| {
-                let lines = (dy * 3.0) as isize;
-                gr.scroll_offset = if lines > 0 {
-                    gr.scroll_offset.saturating_sub(lines as usize)
-                } else {
-                    let max = gr.scrollback.len() / gr.cols;
-                    (gr.scroll_offset as isize - lines).min(max as isize) as usize
-                };
-            }).ok();
+        scroll.connect_scroll(move |controller, _, dy| {
+            let shift = controller.current_event_state().contains(gdk::ModifierType::SHIFT_MASK);
+            g.write().map(|mut gr| gr.scroll_wheel(dy, shift)).ok();
             let _ = t.send_blocking(());
             Propagation::Stop
         });
@@ -144,7 +140,7 @@ impl InputHandler {
         let gr = grid.read().unwrap();
         let c = (x / cw) as usize;
         let screen_r = (y / ch) as usize;
-        let scrollback_rows = gr.scrollback.len() / gr.cols;
+        let scrollback_rows = gr.scrollback_rows();
         let r = if gr.scroll_offset == 0 {
             scrollback_rows + screen_r
         } else {
@@ -177,7 +173,7 @@ impl InputHandler {
             let new_offset = if lines > 0 {
                 gr.scroll_offset.saturating_sub(lines as usize)
             } else {
-                let max = (gr.scrollback.len() / gr.cols).max(gr.scroll_offset);
+                let max = gr.scrollback_rows().max(gr.scroll_offset);
                 gr.scroll_offset + (-lines as usize).min(max - gr.scroll_offset)
             };
             gr.scroll_offset = new_offset;
@@ -218,6 +214,8 @@ impl InputHandler {
                      state.contains(gdk::ModifierType::CONTROL_MASK | gdk::ModifierType::SHIFT_MASK))
                     && keyval == gdk::Key::v;
         if paste {
+            let bracketed = grid.read().map(|g| g.is_bracketed_paste_mode()).unwrap_or(false);
+            let g = grid.clone();
             let w = writer.clone();
             let t = tx.clone();
             gdk::Display::default()
@@ -225,7 +223,9 @@ impl InputHandler {
                 .clipboard()
                 .read_text_async(None::<&gtk4::gio::Cancellable>, move |res| {
                     if let Ok(Some(txt)) = res {
-                        Self::write_to_writer(&w, txt.as_bytes());
+                        let bytes = encode_text(&txt, TextSource::Paste, bracketed);
+                        g.write().map(|mut gr| gr.trace_text(&txt, TextSource::Paste, &bytes)).ok();
+                        Self::write_to_writer(&w, &bytes);
                         let _ = t.send_blocking(());
                     }
                 });
@@ -235,43 +235,47 @@ impl InputHandler {
         false
     }
 
-    fn handle_special_keys(keyval: gdk::Key, state: gdk::ModifierType) -> Option<&'static [u8]> {
-        use gdk::Key;
-        match keyval {
-            Key::Return => Some(b"\r"),
-            Key::BackSpace => Some(b"\x7f"),
-            Key::Tab => Some(b"\t"),
-            Key::Home => Some(b"\x1b[H"),
-            Key::End => Some(b"\x1b[F"),
-            Key::Delete => Some(b"\x1b[3~"),
-            Key::Insert => Some(b"\x1b[2~"),
-            Key::Page_Up => Some(b"\x1b[5~"),
-            Key::Page_Down => Some(b"\x1b[6~"),
-            Key::Up => Some(b"\x1b[A"),
-            Key::Down => Some(b"\x1b[B"),
-            Key::Right => Some(b"\x1b[C"),
-            Key::Left => Some(b"\x1b[D"),
-            Key::F1 => Some(b"\x1bOP"),
-            Key::F2 => Some(b"\x1bOQ"),
-            Key::F3 => Some(b"\x1bOR"),
-            Key::F4 => Some(b"\x1bOS"),
-            Key::F5 => Some(b"\x1b[15~"),
-            Key::F6 => Some(b"\x1b[17~"),
-            Key::F7 => Some(b"\x1b[18~"),
-            Key::F8 => Some(b"\x1b[19~"),
-            Key::F9 => Some(b"\x1b[20~"),
-            Key::F10 => Some(b"\x1b[21~"),
-            Key::F11 => Some(b"\x1b[23~"),
-            Key::F12 => Some(b"\x1b[24~"),
-            _ if state.contains(gdk::ModifierType::CONTROL_MASK) => match keyval {
-                Key::d => Some(b"\x04"),
-                Key::l => Some(b"\x0c"),
-                Key::c => Some(b"\x03"),
-                Key::z => Some(b"\x1a"),
-                _ => None,
-            },
-            _ => None,
-        }
+    fn handle_special_keys(keyval: gdk::Key, state: gdk::ModifierType, encoder: &KeyEncoder) -> Option<&'static [u8]> {
+        use gdk::Key as K;
+        let key = match keyval {
+            K::Return => Key::Enter,
+            K::KP_Enter => Key::KeypadEnter,
+            K::BackSpace => Key::Backspace,
+            K::Tab => Key::Tab,
+            K::Home => Key::Home,
+            K::End => Key::End,
+            K::Delete => Key::Delete,
+            K::Insert => Key::Insert,
+            K::Page_Up => Key::PageUp,
+            K::Page_Down => Key::PageDown,
+            K::Up => Key::Up,
+            K::Down => Key::Down,
+            K::Right => Key::Right,
+            K::Left => Key::Left,
+            K::F1 => Key::F(1),
+            K::F2 => Key::F(2),
+            K::F3 => Key::F(3),
+            K::F4 => Key::F(4),
+            K::F5 => Key::F(5),
+            K::F6 => Key::F(6),
+            K::F7 => Key::F(7),
+            K::F8 => Key::F(8),
+            K::F9 => Key::F(9),
+            K::F10 => Key::F(10),
+            K::F11 => Key::F(11),
+            K::F12 => Key::F(12),
+            _ if state.contains(gdk::ModifierType::CONTROL_MASK) => {
+                return match keyval {
+                    K::d => Some(b"\x04"),
+                    K::l => Some(b"\x0c"),
+                    K::c => Some(b"\x03"),
+                    K::z => Some(b"\x1a"),
+                    _ => None,
+                };
+            }
+            _ => return None,
+        };
+        encoder.encode(key)
     }
 }
 
@@ -286,14 +290,22 @@ mod tests {
 
     #[test]
     fn special_keys_plain() {
-        assert_eq!(InputHandler::handle_special_keys(Key::Return, gdk::ModifierType::empty()), Some(b(b"\r")));
-        assert_eq!(InputHandler::handle_special_keys(Key::F1,   gdk::ModifierType::empty()), Some(b(b"\x1bOP")));
-        assert_eq!(InputHandler::handle_special_keys(Key::Up,  gdk::ModifierType::empty()), Some(b(b"\x1b[A")));
+        let enc = KeyEncoder::default();
+        assert_eq!(InputHandler::handle_special_keys(Key::Return, gdk::ModifierType::empty(), &enc), Some(b(b"\r")));
+        assert_eq!(InputHandler::handle_special_keys(Key::F1,   gdk::ModifierType::empty(), &enc), Some(b(b"\x1bOP")));
+        assert_eq!(InputHandler::handle_special_keys(Key::Up,  gdk::ModifierType::empty(), &enc), Some(b(b"\x1b[A")));
+    }
+
+    #[test]
+    fn special_keys_application_cursor() {
+        let enc = KeyEncoder::new(true, false);
+        assert_eq!(InputHandler::handle_special_keys(Key::Up, gdk::ModifierType::empty(), &enc), Some(b(b"\x1bOA")));
+        assert_eq!(InputHandler::handle_special_keys(Key::Home, gdk::ModifierType::empty(), &enc), Some(b(b"\x1bOH")));
     }
 
     #[test]
     fn special_keys_unknown() {
-        assert_eq!(InputHandler::handle_special_keys(Key::a, gdk::ModifierType::empty()), None);
+        assert_eq!(InputHandler::handle_special_keys(Key::a, gdk::ModifierType::empty(), &KeyEncoder::default()), None);
     }
 
     #[test]
@@ -331,9 +343,13 @@ mod tests {
         let grid = Arc::new(RwLock::new(Grid::new(10, 5, Arc::new(crate::config::TerminalConfig::default()))));
         {
             let mut g = grid.write().unwrap();
-            g.scrollback = (0..30).map(|_| Cell::default()).collect();
+            let mut store = crate::scrollback::MemoryScrollback::new();
+            for _ in 0..3 {
+                crate::scrollback::ScrollbackStore::append_row(&mut store, &[Cell::default(); 10]);
+            }
+            g.set_scrollback_store(Box::new(store));
         }
-        // (0,0)  -> row 3 (30/10), col 0
+        // (0,0)  -> row 3 (3 scrollback rows), col 0
         let (r, c) = InputHandler::xy_to_cell(0.0, 0.0, 10.0, 10.0, &grid);
         assert_eq!((r, c), (3, 0));
 
@@ -0,0 +1,232 @@
+// src/input_trace.rs
+//! Opt-in trace of key input, for debugging key encoding
+//!
+//! "F5 does nothing in program X" usually comes down to the bytes the key
+//! produced under the modes X had set: DECCKM, the keypad mode, a
+//! DECUDK definition. With `TerminalConfig::input_trace` (or
+//! `VteTerminalCore::set_input_tracing`) every key event, and all text typed,
+//! committed by an input method, pasted or sent, is recorded with the bytes
+//! written to the PTY (after line-ending normalization, bracketed paste
+//! wrapping and sanitization) and the modes in effect. The last [`MAX_INPUT_TRACE_ENTRIES`] are kept for
+//! [`InputTrace::dump`]. With `TerminalConfig::input_trace_file` each entry is
+//! also appended to that file as it happens, so a trace survives a crash; a
+//! thread of its own does the writing, so recording never waits on the disk
+//! while the grid is locked.
+//!
+//! Typed and pasted text is recorded as well, so a trace holds whatever the
+//! user typed or pasted; it is off by default and meant for short debugging sessions.
+
+use crate::constants::MAX_INPUT_TRACE_ENTRIES;
+use crate::keys::Key;
+use crate::modes::Modes;
+use crate::text_input::TextSource;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+use tracing::warn;
+
+/// What a trace entry records
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TracedInput {
+    /// A key press, encoded under the modes in effect
+    Key(Key),
+    /// Text written as a whole, e.g. an input method's commit or a paste
+    Text(String),
+}
+
+/// One key press or piece of text and what it was sent as
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InputTraceEntry {
+    pub input: TracedInput,
+    /// `TextSource::Key` for key presses; for text, where it came from
+    pub source: TextSource,
+    /// Bytes written to the PTY; `None` if the key has no encoding
+    pub bytes: Option<Vec<u8>>,
+    /// Modes in effect when the key was encoded
+    pub modes: Modes,
+    /// DECUDK definitions active at the time
+    pub user_keys: usize,
+}
+
+impl InputTraceEntry {
+    /// The entry as one line: key or text and source, escaped bytes,
+    /// key-relevant modes
+    pub fn to_line(&self) -> String {
+        let bytes = match &self.bytes {
+            Some(bytes) => format!("\"{}\"", bytes.escape_ascii()),
+            None => "(not sent)".to_string(),
+        };
+        let input = match &self.input {
+            TracedInput::Key(key) => format!("{:?}", key),
+            TracedInput::Text(text) => format!("{:?}", text),
+        };
+        let source = match (&self.input, self.source) {
+            (TracedInput::Key(_), _) => "",
+            (TracedInput::Text(_), TextSource::Key) => " (typed)",
+            (TracedInput::Text(_), TextSource::InputMethod) => " (input method)",
+            (TracedInput::Text(_), TextSource::Paste) => " (paste)",
+            (TracedInput::Text(_), TextSource::Synthetic) => " (sent)",
+        };
+        let modes = &self.modes;
+        format!(
+            "{}{} -> {} [cursor={} keypad={} screen={} paste={} udk={}]",
+            input,
+            source,
+            bytes,
+            if modes.application_cursor_keys { "app" } else { "normal" },
+            if modes.application_keypad { "app" } else { "numeric" },
+            if modes.alternate_screen { "alternate" } else { "primary" },
+            if modes.bracketed_paste { "bracketed" } else { "plain" },
+            self.user_keys,
+        )
+    }
+}
+
+/// Recent key events, recorded while enabled
+#[derive(Clone, Debug, Default)]
+pub struct InputTrace {
+    enabled: bool,
+    entries: VecDeque<InputTraceEntry>,
+    /// Lines for the thread appending them to the trace file
+    file: Option<mpsc::Sender<String>>,
+}
+
+impl InputTrace {
+    pub fn new(enabled: bool, file: Option<PathBuf>) -> Self {
+        Self { enabled, entries: VecDeque::new(), file: file.and_then(spawn_writer) }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Start or stop recording; entries recorded so far are kept
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Record `entry` if enabled, dropping the oldest past the limit
+    pub fn record(&mut self, entry: InputTraceEntry) {
+        if !self.enabled {
+            return;
+        }
+        if let Some(file) = &self.file {
+            // The writer is gone only after a write error it reported
+            let _ = file.send(entry.to_line());
+        }
+        if self.entries.len() == MAX_INPUT_TRACE_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// Entries, oldest first
+    pub fn entries(&self) -> impl ExactSizeIterator<Item = &InputTraceEntry> {
+        self.entries.iter()
+    }
+
+    /// All entries as lines, oldest first
+    pub fn dump(&self) -> String {
+        self.entries.iter().map(|entry| entry.to_line() + "\n").collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Start the thread appending trace lines to `path`, which stops once every
+/// copy of the trace is dropped
+fn spawn_writer(path: PathBuf) -> Option<mpsc::Sender<String>> {
+    let (lines, received) = mpsc::channel::<String>();
+    let spawned = thread::Builder::new().name("input-trace".to_string()).spawn(move || {
+        let mut file = match std::fs::OpenOptions::new().create(true).append(true).open(&path) {
+            Ok(file) => file,
+            Err(e) => {
+                warn!("Failed to open input trace {}: {}", path.display(), e);
+                return;
+            }
+        };
+        for line in received {
+            if let Err(e) = writeln!(file, "{}", line) {
+                warn!("Failed to append to input trace {}: {}", path.display(), e);
+                return;
+            }
+        }
+    });
+    match spawned {
+        Ok(_) => Some(lines),
+        Err(e) => {
+            warn!("Failed to start the input trace writer: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::AnsiParser;
+    use crate::config::TerminalConfig;
+    use crate::grid::Grid;
+    use crate::keys::Key;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_disabled_by_default_and_bounded() {
+        let entry = |ch| InputTraceEntry {
+            input: TracedInput::Key(Key::Char(ch)),
+            source: TextSource::Key,
+            bytes: None,
+            modes: Modes::default(),
+            user_keys: 0,
+        };
+        let mut trace = InputTrace::default();
+        trace.record(entry('a'));
+        assert_eq!(trace.entries().len(), 0);
+
+        trace.set_enabled(true);
+        for _ in 0..MAX_INPUT_TRACE_ENTRIES {
+            trace.record(entry('a'));
+        }
+        trace.record(entry('b'));
+        assert_eq!(trace.entries().len(), MAX_INPUT_TRACE_ENTRIES);
+        assert_eq!(trace.entries().last().map(|e| &e.input), Some(&TracedInput::Key(Key::Char('b'))));
+        trace.clear();
+        assert_eq!(trace.dump(), "");
+    }
+
+    #[test]
+    fn test_grid_records_encoding_and_modes() {
+        let path = std::env::temp_dir().join(format!("hugovte-input-trace-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let config = TerminalConfig::default().with_input_trace(Some(path.clone()));
+        let mut grid = Grid::new(10, 2, Arc::new(config));
+
+        grid.encode_key(Key::Up);
+        AnsiParser::new().feed_str("\x1b[?1h", &mut grid);
+        grid.encode_key(Key::Up);
+        grid.encode_key(Key::F(15));
+        grid.trace_text("é", TextSource::InputMethod, "é".as_bytes());
+        AnsiParser::new().feed_str("\x1b[?2004h", &mut grid);
+        grid.trace_text("a\nb", TextSource::Paste, b"\x1b[200~a\rb\x1b[201~");
+
+        let dump = grid.input_trace().dump();
+        let lines: Vec<&str> = dump.lines().collect();
+        assert_eq!(lines[0], r#"Up -> "\x1b[A" [cursor=normal keypad=numeric screen=primary paste=plain udk=0]"#);
+        assert!(lines[1].starts_with(r#"Up -> "\x1bOA" [cursor=app"#));
+        assert!(lines[2].starts_with("F(15) -> (not sent)"));
+        assert!(lines[3].starts_with(r#""é" (input method) -> "\xc3\xa9""#));
+        assert_eq!(lines[4], r#""a\nb" (paste) -> "\x1b[200~a\rb\x1b[201~" [cursor=app keypad=numeric screen=primary paste=bracketed udk=0]"#);
+
+        // The writer thread catches up with the trace
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while std::fs::read_to_string(&path).unwrap_or_default() != dump && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), dump);
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,61 @@
+// src/journal.rs
+//! Journaled (double-buffered) grid updates
+//!
+//! With `TerminalConfig::journaled_parsing` the PTY reader parses each chunk
+//! into a working copy of the grid and commits it only once the whole chunk
+//! went through. A parser panic mid-sequence then leaves the last committed
+//! screen intact for display and diagnosis instead of a half-applied update.
+//! Copying the grid per chunk costs time proportional to the scrollback, so
+//! journaling is opt-in, meant for chasing parser bugs.
+
+/// Run `update` on a working copy of `committed`, replacing `committed` with
+/// the result once `update` returns
+///
+/// If `update` panics the working copy is dropped during unwinding and
+/// `committed` is left exactly as it was.
+pub fn commit_with<T: Clone, R>(committed: &mut T, update: impl FnOnce(&mut T) -> R) -> R {
+    let mut working = committed.clone();
+    let result = update(&mut working);
+    *committed = working;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::AnsiParser;
+    use crate::config::TerminalConfig;
+    use crate::grid::Grid;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::sync::Arc;
+
+    fn screen_text(grid: &Grid) -> String {
+        (0..grid.cols).map(|col| grid.get_cell(0, col).ch).filter(|&ch| ch != '\0').collect()
+    }
+
+    #[test]
+    fn test_commit_applies_update() {
+        let mut grid = Grid::new(10, 2, Arc::new(TerminalConfig::default()));
+        let mut parser = AnsiParser::new();
+        commit_with(&mut grid, |working| parser.feed_str("hello", working));
+        assert_eq!(screen_text(&grid), "hello");
+        assert_eq!(grid.col, 5);
+    }
+
+    #[test]
+    fn test_panic_mid_chunk_keeps_last_committed_screen() {
+        let mut grid = Grid::new(10, 2, Arc::new(TerminalConfig::default()));
+        let mut parser = AnsiParser::new();
+        commit_with(&mut grid, |working| parser.feed_str("ok", working));
+
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            commit_with(&mut grid, |working| {
+                parser.feed_str("\x1b[2J\x1b[Hbroken", working);
+                panic!("parser bug");
+            })
+        }));
+        assert!(result.is_err());
+        assert_eq!(screen_text(&grid), "ok");
+        assert_eq!((grid.row, grid.col), (0, 2));
+    }
+}
@@ -0,0 +1,218 @@
+// src/keys.rs
+//! Special key encoding: non-text keys to PTY bytes
+//!
+//! Cursor keys, Home/End and the function keys are sent as escape sequences
+//! whose shape depends on modes the application set through the parser:
+//! DECCKM (`CSI ? 1 h`) switches cursor keys and Home/End to SS3 form
+//! (`ESC O A`), and DECKPAM (`ESC =`) does the same for keypad Enter.
+//! Sequences follow xterm, which is what terminfo's `xterm-256color` lists.
+//!
+//! [`Key`] also covers character keys, plain or with Ctrl or Alt held, for
+//! embedders that synthesize key presses (`VteTerminalCore::send_key`).
+//! [`KeyEncoder::encode_key`] encodes those too, and shifted function keys:
+//! as in xterm, Shift+Fn sends what the application programmed for it with
+//! DECUDK (see `user_keys`), and xterm's `CSI n ; 2 ~` otherwise.
+
+use crate::user_keys::UserKeys;
+use std::sync::Arc;
+
+/// Key press, independent of the UI toolkit
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Key {
+    Up,
+    Down,
+    Right,
+    Left,
+    Home,
+    End,
+    Insert,
+    Delete,
+    PageUp,
+    PageDown,
+    Enter,
+    KeypadEnter,
+    Backspace,
+    Tab,
+    /// Function key F1..=F20; F13 and up send nothing
+    F(u8),
+    /// Function key F1..=F20 with Shift held, which sends its DECUDK
+    /// definition if it has one
+    ShiftF(u8),
+    /// Character typed as is
+    Char(char),
+    /// Character with Ctrl held: the C0 control for `@`, letters and `[\]^_`,
+    /// DEL for `?`
+    Ctrl(char),
+    /// Character with Alt held: prefixed with ESC, as xterm's `metaSendsEscape`
+    Alt(char),
+}
+
+/// Encodes special keys according to the current input modes
+///
+/// Cheap to clone; take a fresh one from [`Grid::key_encoder`](crate::Grid::key_encoder)
+/// for every key press so mode changes are picked up.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KeyEncoder {
+    /// DECCKM: cursor keys send `ESC O x` instead of `ESC [ x`
+    pub application_cursor: bool,
+    /// DECKPAM: keypad keys send application sequences
+    pub application_keypad: bool,
+    /// Function keys programmed with DECUDK
+    pub user_keys: Arc<UserKeys>,
+}
+
+impl KeyEncoder {
+    pub fn new(application_cursor: bool, application_keypad: bool) -> Self {
+        Self { application_cursor, application_keypad, user_keys: Arc::default() }
+    }
+
+    pub fn with_user_keys(mut self, user_keys: Arc<UserKeys>) -> Self {
+        self.user_keys = user_keys;
+        self
+    }
+
+    /// Bytes to write for a special `key`, or `None` for keys without a fixed
+    /// sequence (F13+, shifted function keys and character keys)
+    ///
+    /// These are the sequences of the current modes only; `encode_key`
+    /// also encodes character keys and shifted function keys.
+    pub fn encode(&self, key: Key) -> Option<&'static [u8]> {
+        let seq: &'static [u8] = match key {
+            Key::Up => self.cursor(b"\x1b[A", b"\x1bOA"),
+            Key::Down => self.cursor(b"\x1b[B", b"\x1bOB"),
+            Key::Right => self.cursor(b"\x1b[C", b"\x1bOC"),
+            Key::Left => self.cursor(b"\x1b[D", b"\x1bOD"),
+            Key::Home => self.cursor(b"\x1b[H", b"\x1bOH"),
+            Key::End => self.cursor(b"\x1b[F", b"\x1bOF"),
+            Key::Insert => b"\x1b[2~",
+            Key::Delete => b"\x1b[3~",
+            Key::PageUp => b"\x1b[5~",
+            Key::PageDown => b"\x1b[6~",
+            Key::Enter => b"\r",
+            Key::KeypadEnter if self.application_keypad => b"\x1bOM",
+            Key::KeypadEnter => b"\r",
+            Key::Backspace => b"\x7f",
+            Key::Tab => b"\t",
+            Key::F(n) => return function_key(n),
+            Key::ShiftF(_) | Key::Char(_) | Key::Ctrl(_) | Key::Alt(_) => return None,
+        };
+        Some(seq)
+    }
+
+    /// Bytes to write for `key`, or `None` if it has no encoding
+    pub fn encode_key(&self, key: Key) -> Option<Vec<u8>> {
+        match key {
+            Key::ShiftF(n) => self.user_keys.get(n).map(<[u8]>::to_vec).or_else(|| shifted_function_key(n)),
+            Key::Char(ch) => Some(ch.to_string().into_bytes()),
+            Key::Ctrl(ch) => control_byte(ch).map(|byte| vec![byte]),
+            Key::Alt(ch) => Some(format!("\x1b{}", ch).into_bytes()),
+            key => self.encode(key).map(<[u8]>::to_vec),
+        }
+    }
+
+    fn cursor(&self, normal: &'static [u8], application: &'static [u8]) -> &'static [u8] {
+        if self.application_cursor {
+            application
+        } else {
+            normal
+        }
+    }
+}
+
+/// C0 control sent for Ctrl+`ch`
+fn control_byte(ch: char) -> Option<u8> {
+    match ch.to_ascii_uppercase() {
+        ' ' => Some(0),
+        ch @ '@'..='_' => Some(ch as u8 & 0x1f),
+        '?' => Some(0x7f),
+        _ => None,
+    }
+}
+
+/// F1-F4 are always SS3; F5 and up use `CSI n ~` with xterm's numbering gaps
+fn function_key(n: u8) -> Option<&'static [u8]> {
+    let seq: &'static [u8] = match n {
+        1 => b"\x1bOP",
+        2 => b"\x1bOQ",
+        3 => b"\x1bOR",
+        4 => b"\x1bOS",
+        5 => b"\x1b[15~",
+        6 => b"\x1b[17~",
+        7 => b"\x1b[18~",
+        8 => b"\x1b[19~",
+        9 => b"\x1b[20~",
+        10 => b"\x1b[21~",
+        11 => b"\x1b[23~",
+        12 => b"\x1b[24~",
+        _ => return None,
+    };
+    Some(seq)
+}
+
+/// xterm's sequence for Shift+F`n`: the key's own with modifier parameter 2
+fn shifted_function_key(n: u8) -> Option<Vec<u8>> {
+    match function_key(n)? {
+        [0x1b, b'O', last] => Some([b"[1;2", &[*last][..]].concat()),
+        [0x1b, b'[', code @ .., b'~'] => Some([b"[", code, b";2~"].concat()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_keys_follow_decckm() {
+        let normal = KeyEncoder::default();
+        let app = KeyEncoder::new(true, false);
+        assert_eq!(normal.encode(Key::Up), Some(&b"\x1b[A"[..]));
+        assert_eq!(app.encode(Key::Up), Some(&b"\x1bOA"[..]));
+        assert_eq!(app.encode(Key::End), Some(&b"\x1bOF"[..]));
+        // Editing keys are unaffected
+        assert_eq!(app.encode(Key::Delete), normal.encode(Key::Delete));
+    }
+
+    #[test]
+    fn test_function_keys() {
+        let enc = KeyEncoder::new(true, true);
+        assert_eq!(enc.encode(Key::F(1)), Some(&b"\x1bOP"[..]));
+        assert_eq!(enc.encode(Key::F(11)), Some(&b"\x1b[23~"[..]));
+        assert_eq!(enc.encode(Key::F(0)), None);
+        assert_eq!(enc.encode(Key::F(13)), None);
+    }
+
+    #[test]
+    fn test_character_keys() {
+        let app = KeyEncoder::new(true, false);
+        assert_eq!(app.encode_key(Key::Up), Some(b"\x1bOA".to_vec()));
+        assert_eq!(app.encode_key(Key::Char('\u{e9}')), Some("\u{e9}".as_bytes().to_vec()));
+        assert_eq!(app.encode_key(Key::Ctrl('c')), Some(vec![0x03]));
+        assert_eq!(app.encode_key(Key::Ctrl('[')), Some(vec![0x1b]));
+        assert_eq!(app.encode_key(Key::Ctrl('?')), Some(vec![0x7f]));
+        assert_eq!(app.encode_key(Key::Ctrl('1')), None);
+        assert_eq!(app.encode_key(Key::Alt('b')), Some(b"\x1bb".to_vec()));
+        assert_eq!(app.encode_key(Key::F(20)), None);
+    }
+
+    #[test]
+    fn test_user_defined_keys() {
+        let mut user_keys = UserKeys::new();
+        user_keys.define(&[(17, "make\r".to_string()), (31, "ls\r".to_string())], true, false);
+        let enc = KeyEncoder::default().with_user_keys(Arc::new(user_keys));
+        assert_eq!(enc.encode_key(Key::ShiftF(6)), Some(b"make\r".to_vec()));
+        assert_eq!(enc.encode_key(Key::ShiftF(17)), Some(b"ls\r".to_vec()));
+        // Only with Shift held, as in xterm
+        assert_eq!(enc.encode_key(Key::F(6)), Some(b"\x1b[17~".to_vec()));
+        assert_eq!(enc.encode_key(Key::F(17)), None);
+        assert_eq!(enc.encode_key(Key::ShiftF(7)), Some(b"\x1b[18;2~".to_vec()));
+        assert_eq!(enc.encode_key(Key::ShiftF(1)), Some(b"\x1b[1;2P".to_vec()));
+        assert_eq!(enc.encode_key(Key::ShiftF(13)), None);
+    }
+
+    #[test]
+    fn test_keypad_enter_follows_deckpam() {
+        assert_eq!(KeyEncoder::default().encode(Key::KeypadEnter), Some(&b"\r"[..]));
+        assert_eq!(KeyEncoder::new(false, true).encode(Key::KeypadEnter), Some(&b"\x1bOM"[..]));
+    }
+}
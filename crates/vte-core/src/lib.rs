@@ -1,29 +1,137 @@
 //! VTE Core - GTK-agnostic virtual terminal emulator core
 //!
 //! This crate provides the core functionality for a terminal emulator,
-//! independent of any specific UI backend.
+//! independent of any specific UI backend. Embedders usually start from
+//! [`prelude`].
 
-pub mod ansi;
+pub mod activity;
+pub mod annotations;
+pub mod automation;
+pub mod bell;
+pub mod broadcast;
+pub mod changes;
+pub mod clipboard;
+pub mod clock;
+pub mod color_adjust;
+pub mod color_env;
+pub mod commands;
 pub mod config;
+/// Tuning values shared with the backend crates; not a stable interface
+#[doc(hidden)]
 pub mod constants;
+pub mod contrast;
+pub mod cursor;
+pub mod damage;
+pub mod diff;
 pub mod drawing;
+pub mod encoding;
+#[doc(hidden)]
 pub mod dummy_backend;
 pub mod error;
+pub mod events;
+pub mod feed_filter;
 pub mod font;
 pub mod grid;
+pub mod grid_view;
+pub mod health;
+pub mod highlights;
+pub mod hyperlinks;
+pub mod images;
+pub mod inactive;
 pub mod input;
+pub mod input_trace;
+pub mod journal;
+pub mod keys;
+pub mod link_rules;
+pub mod locale;
+pub mod locator;
+pub mod marks;
+pub mod modes;
+pub mod osc_policy;
+pub mod parse_budget;
+pub mod paste;
+pub mod pointer;
+pub mod predict;
+pub mod prelude;
+pub mod regions;
+pub mod remote;
+pub mod row_cache;
+pub mod screen;
+pub mod scrollback;
+pub mod scrollback_index;
 pub mod security;
 pub mod selection;
+pub mod sixel;
+pub mod snapshots;
+pub mod termcap;
 pub mod terminal;
+pub mod text_input;
+pub mod theme;
 pub mod traits;
+pub mod user_keys;
+pub mod width;
+pub mod working_directory;
+#[cfg(test)]
+mod test_support;
+
+/// ANSI/VT parser, grid trait and cell types, owned by the `vte-ansi` crate
+///
+/// `vte_core::ansi::Cell` and `vte_ansi::Cell` are the same type; there is a
+/// single definition of each parser-facing type in the workspace.
+pub use vte_ansi as ansi;
 
 // Re-export main types
 pub use ansi::{AnsiParser, AnsiGrid, Color, Cell, KeyEvent, MouseEvent};
+pub use activity::ActivityMonitor;
+pub use annotations::{Annotation, AnnotationId, RowAnnotation};
+pub use automation::{Automation, WaitMatch};
+pub use bell::{BellAction, BellFlash, BellGate, BellPolicy, VisualBell, VisualBellStyle};
+pub use broadcast::{BroadcastGroup, InputSink, MemberId};
+pub use changes::{ChangeCounters, ChangeHookId, GridChange};
+pub use clipboard::ClipboardRequest;
+pub use clock::{Clock, MockClock, SystemClock};
+pub use color_adjust::ColorAdjust;
+pub use color_env::{ChildColorEnv, ColorPolicy};
+pub use commands::FinishedCommand;
 pub use config::TerminalConfig;
-pub use error::TerminalError;
+pub use cursor::{CursorPaint, CursorPosition};
+pub use diff::RowDelta;
+pub use encoding::Encoding;
+pub use error::{ErrorSeverity, TerminalError};
+pub use events::TerminalEvent;
+pub use feed_filter::FilterPolicy;
 pub use grid::Grid;
-pub use security::{sanitize_paste, validate_osc_sequence, RateLimiter, SecurityConfig};
-pub use terminal::VteTerminalCore;
+pub use grid_view::GridView;
+pub use health::ParserHealth;
+pub use highlights::{HighlightSpan, HighlightStyle};
+pub use hyperlinks::HyperlinkCopyMode;
+pub use images::{ImageLayer, PlacedImage};
+pub use inactive::InactiveStyle;
+pub use input_trace::{InputTrace, InputTraceEntry, TracedInput};
+pub use keys::{Key, KeyEncoder};
+pub use link_rules::LinkRule;
+pub use locale::{ChildLocale, LocalePolicy};
+pub use locator::{Locator, LocatorButton};
+pub use marks::{MarkCategory, MarkDirection, ScrollMark};
+pub use modes::{DisabledModes, ForcibleMode, Modes, MouseEncoding, MouseTracking};
+pub use osc_policy::{DeniedOsc, OscAction, OscCategory, OscPolicy, OscRequest};
+pub use parse_budget::{FrameAck, ParseBudget};
+pub use paste::PasteStreamer;
+pub use pointer::{HoverTracker, HoveredLink};
+pub use predict::{EchoPredictor, LocalEcho, Prediction};
+pub use remote::RemoteCursor;
+pub use row_cache::RowCache;
+pub use screen::ScreenBuffer;
+pub use scrollback::{MemoryScrollback, ScrollbackStore};
+pub use security::{sanitize_paste, sanitize_title, validate_osc_sequence, RateLimiter, SecurityConfig, TitlePolicy};
+pub use selection::SelectionSpan;
+pub use snapshots::ScreenSnapshot;
+pub use terminal::{ColorSchemeHandle, OutputHoldHandle, ResizeHandle, VteTerminalCore};
+pub use text_input::{encode_text, TextSource};
+pub use theme::{ColorScheme, Theme, ThemePair};
+pub use user_keys::UserKeys;
+pub use width::{AmbiguousWidth, EmojiWidth, WidthPolicy};
+pub use working_directory::{local_hostname, WorkingDirectory};
 
 // Re-export traits and types
 pub use traits::*;
@@ -34,5 +142,6 @@ pub struct MemoryInfo {
     pub primary_buffer_bytes: usize,
     pub alternate_buffer_bytes: usize,
     pub scrollback_buffer_bytes: usize,
+    pub scrollback_index_bytes: usize,
     pub total_grid_bytes: usize,
 }
@@ -0,0 +1,102 @@
+// src/link_rules.rs
+//! Pattern rules that turn plain text into links
+//!
+//! Besides literal URLs, output is full of references that only make sense
+//! with some context: `#123` is an issue in the project's tracker, a run of
+//! hex digits is a commit. Each [`LinkRule`] pairs a regular expression with
+//! a URL template, and `TerminalConfig::link_rules` holds the rules of one
+//! profile. URL detection (`Grid::link_at`) tries literal URLs first, then
+//! the rules in order; the first match covering the pointer wins.
+//!
+//! Templates expand capture groups with `$1`, `${name}` and `$0` (the whole
+//! match), as in [`regex::Captures::expand`].
+
+use crate::error::{TerminalError, TerminalResult};
+use regex::Regex;
+use std::ops::Range;
+
+/// Regular expression and the URL its matches link to
+#[derive(Clone, Debug)]
+pub struct LinkRule {
+    regex: Regex,
+    url_template: String,
+}
+
+impl LinkRule {
+    /// Rule linking matches of `pattern` to `url_template`, e.g.
+    /// `LinkRule::new(r"#(\d+)", "https://github.com/org/repo/issues/$1")`
+    pub fn new(pattern: &str, url_template: &str) -> TerminalResult<Self> {
+        let regex = Regex::new(pattern).map_err(|e| TerminalError::InvalidPattern {
+            pattern: pattern.to_string(),
+            message: e.to_string(),
+        })?;
+        Ok(Self { regex, url_template: url_template.to_string() })
+    }
+
+    pub fn pattern(&self) -> &str {
+        self.regex.as_str()
+    }
+
+    pub fn url_template(&self) -> &str {
+        &self.url_template
+    }
+}
+
+/// Column range and generated URL of the first rule match covering `col`
+/// in a row of characters
+pub fn find_rule_link_at(rules: &[LinkRule], line: &[char], col: usize) -> Option<(Range<usize>, String)> {
+    if rules.is_empty() || col >= line.len() {
+        return None;
+    }
+    let text: String = line.iter().collect();
+    let byte_col = text.char_indices().nth(col).map(|(i, _)| i)?;
+    let to_col = |byte: usize| text[..byte].chars().count();
+
+    rules.iter().find_map(|rule| {
+        let captures = rule
+            .regex
+            .captures_iter(&text)
+            .take_while(|c| c.get(0).is_some_and(|m| m.start() <= byte_col))
+            .find(|c| c.get(0).is_some_and(|m| m.end() > byte_col))?;
+        let whole = captures.get(0)?;
+        let mut url = String::new();
+        captures.expand(&rule.url_template, &mut url);
+        Some((to_col(whole.start())..to_col(whole.end()), url))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    fn rules() -> Vec<LinkRule> {
+        vec![
+            LinkRule::new(r"#(\d+)", "https://example.com/issues/$1").unwrap(),
+            LinkRule::new(r"\b[0-9a-f]{7,40}\b", "https://example.com/commit/$0").unwrap(),
+        ]
+    }
+
+    #[test]
+    fn test_rules_expand_captures() {
+        let line = chars("fix #42 in 1a2b3c4d");
+        assert_eq!(find_rule_link_at(&rules(), &line, 5), Some((4..7, "https://example.com/issues/42".to_string())));
+        assert_eq!(find_rule_link_at(&rules(), &line, 18), Some((11..19, "https://example.com/commit/1a2b3c4d".to_string())));
+        assert_eq!(find_rule_link_at(&rules(), &line, 8), None);
+        assert_eq!(find_rule_link_at(&[], &line, 5), None);
+    }
+
+    #[test]
+    fn test_columns_count_characters() {
+        let line = chars("é → #7");
+        assert_eq!(find_rule_link_at(&rules(), &line, 4), Some((4..6, "https://example.com/issues/7".to_string())));
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_rejected() {
+        assert!(matches!(LinkRule::new("(", "x"), Err(TerminalError::InvalidPattern { .. })));
+    }
+}
@@ -0,0 +1,177 @@
+// src/locale.rs
+//! Locale environment of the child process
+//!
+//! The shell inherits the host application's environment, and apps started
+//! from a desktop launcher or a service manager often run without `LANG`, or
+//! with a legacy codeset. Programs in the terminal then write Latin-1 or
+//! plain ASCII, and their non-ASCII characters come out as mojibake.
+//! `TerminalConfig::locale` picks what the child gets:
+//!
+//! - [`LocalePolicy::EnsureUtf8`] (default) keeps the inherited locale if its
+//!   codeset is UTF-8. Otherwise it switches the codeset to UTF-8, keeping
+//!   the language (`de_DE.ISO-8859-1` becomes `de_DE.UTF-8`, no locale at all
+//!   becomes `C.UTF-8`).
+//! - [`LocalePolicy::Inherit`] passes the environment on unchanged.
+//! - [`LocalePolicy::Fixed`] sets `LANG` and drops every inherited `LC_*`.
+//!
+//! The codeset is read like the C library does for `LC_CTYPE`: `LC_ALL`,
+//! then `LC_CTYPE`, then `LANG`. [`ChildLocale`] records the variables
+//! changed and the resulting `LC_CTYPE` locale
+//! (`VteTerminalCore::child_locale`).
+
+use crate::constants::FALLBACK_LOCALE;
+
+/// Locale environment for the child process
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum LocalePolicy {
+    /// Inherit the locale, switching its codeset to UTF-8 if it is another
+    #[default]
+    EnsureUtf8,
+    /// Inherit the locale unchanged
+    Inherit,
+    /// Use this locale, e.g. `en_US.UTF-8`, whatever was inherited
+    Fixed(String),
+}
+
+/// Locale variables as given to the child
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChildLocale {
+    /// Variables set for the child, overriding inherited values
+    pub set: Vec<(String, String)>,
+    /// Inherited variables removed
+    pub removed: Vec<String>,
+    /// Locale the child's `LC_CTYPE` resolves to; `C` when nothing is set
+    pub ctype: String,
+}
+
+impl ChildLocale {
+    /// Apply `policy` to the inherited environment `vars`
+    pub fn resolve(policy: &LocalePolicy, vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        let vars: Vec<(String, String)> =
+            vars.into_iter().filter(|(name, value)| is_locale_var(name) && !value.is_empty()).collect();
+        let get = |name: &str| vars.iter().find(|(n, _)| n == name).map(|(_, value)| value.clone());
+        let inherited = get("LC_ALL").or_else(|| get("LC_CTYPE")).or_else(|| get("LANG"));
+
+        let mut locale = ChildLocale::default();
+        match policy {
+            LocalePolicy::Inherit => {}
+            LocalePolicy::Fixed(name) => {
+                locale.removed = vars.iter().map(|(n, _)| n.clone()).filter(|n| n != "LANG").collect();
+                locale.set.push(("LANG".to_string(), name.clone()));
+            }
+            LocalePolicy::EnsureUtf8 => {
+                if !inherited.as_deref().is_some_and(is_utf8_locale) {
+                    let utf8 = utf8_variant(inherited.as_deref().unwrap_or(""));
+                    // LC_ALL overrides LC_CTYPE, so replace whichever decides
+                    let var = if get("LC_ALL").is_some() { "LC_ALL" } else { "LC_CTYPE" };
+                    locale.set.push((var.to_string(), utf8.clone()));
+                    if get("LANG").is_none() {
+                        locale.set.push(("LANG".to_string(), utf8));
+                    }
+                }
+            }
+        }
+
+        let child = |name: &str| {
+            locale.set.iter().find(|(n, _)| n == name).map(|(_, value)| value.clone()).or_else(|| {
+                (!locale.removed.iter().any(|n| n == name)).then(|| get(name)).flatten()
+            })
+        };
+        locale.ctype = child("LC_ALL")
+            .or_else(|| child("LC_CTYPE"))
+            .or_else(|| child("LANG"))
+            .unwrap_or_else(|| "C".to_string());
+        locale
+    }
+
+    /// Whether the child's character encoding is UTF-8
+    pub fn is_utf8(&self) -> bool {
+        is_utf8_locale(&self.ctype)
+    }
+
+    /// Whether any variable differs from the inherited environment
+    pub fn is_changed(&self) -> bool {
+        !self.set.is_empty() || !self.removed.is_empty()
+    }
+}
+
+fn is_locale_var(name: &str) -> bool {
+    name == "LANG" || name.starts_with("LC_")
+}
+
+/// Whether locale `name` (`language_TERRITORY.codeset@modifier`) uses UTF-8
+pub fn is_utf8_locale(name: &str) -> bool {
+    let codeset = name.split_once('.').map_or("", |(_, rest)| rest.split('@').next().unwrap_or(""));
+    let codeset = codeset.to_ascii_lowercase().replace('-', "");
+    codeset == "utf8"
+}
+
+/// `name` with its codeset replaced by UTF-8; `C`, `POSIX` and no locale
+/// become [`FALLBACK_LOCALE`]
+fn utf8_variant(name: &str) -> String {
+    let (base, modifier) = name.split_once('@').map_or((name, None), |(base, modifier)| (base, Some(modifier)));
+    let language = base.split('.').next().unwrap_or("");
+    if language.is_empty() || language == "C" || language == "POSIX" {
+        return FALLBACK_LOCALE.to_string();
+    }
+    match modifier {
+        Some(modifier) => format!("{}.UTF-8@{}", language, modifier),
+        None => format!("{}.UTF-8", language),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect()
+    }
+
+    fn set(locale: &ChildLocale) -> Vec<(&str, &str)> {
+        locale.set.iter().map(|(n, v)| (n.as_str(), v.as_str())).collect()
+    }
+
+    #[test]
+    fn test_utf8_detection() {
+        for name in ["en_US.UTF-8", "de_DE.utf8", "C.UTF-8", "sr_RS.UTF-8@latin"] {
+            assert!(is_utf8_locale(name), "{}", name);
+        }
+        for name in ["", "C", "POSIX", "de_DE", "de_DE.ISO-8859-1", "ja_JP.eucJP@utf8"] {
+            assert!(!is_utf8_locale(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_ensure_utf8_keeps_the_language() {
+        let policy = LocalePolicy::EnsureUtf8;
+        let locale = ChildLocale::resolve(&policy, env(&[("LANG", "en_GB.UTF-8"), ("PATH", "/bin")]));
+        assert!(!locale.is_changed() && locale.is_utf8());
+        assert_eq!(locale.ctype, "en_GB.UTF-8");
+
+        let locale = ChildLocale::resolve(&policy, env(&[("LANG", "de_DE.ISO-8859-15@euro")]));
+        assert_eq!(set(&locale), [("LC_CTYPE", "de_DE.UTF-8@euro")]);
+        assert!(locale.is_utf8());
+
+        // LC_ALL decides, so it is the one replaced
+        let locale = ChildLocale::resolve(&policy, env(&[("LANG", "fr_FR.UTF-8"), ("LC_ALL", "C")]));
+        assert_eq!(set(&locale), [("LC_ALL", FALLBACK_LOCALE)]);
+
+        let locale = ChildLocale::resolve(&policy, Vec::new());
+        assert_eq!(set(&locale), [("LC_CTYPE", FALLBACK_LOCALE), ("LANG", FALLBACK_LOCALE)]);
+        assert_eq!(locale.ctype, FALLBACK_LOCALE);
+    }
+
+    #[test]
+    fn test_inherit_and_fixed() {
+        let vars = env(&[("LANG", "C"), ("LC_CTYPE", "pl_PL.ISO-8859-2"), ("LC_TIME", "en_DK.UTF-8")]);
+        let locale = ChildLocale::resolve(&LocalePolicy::Inherit, vars.clone());
+        assert!(!locale.is_changed() && !locale.is_utf8());
+        assert_eq!(locale.ctype, "pl_PL.ISO-8859-2");
+
+        let locale = ChildLocale::resolve(&LocalePolicy::Fixed("ja_JP.UTF-8".to_string()), vars);
+        assert_eq!(set(&locale), [("LANG", "ja_JP.UTF-8")]);
+        assert_eq!(locale.removed, ["LC_CTYPE", "LC_TIME"]);
+        assert_eq!(locale.ctype, "ja_JP.UTF-8");
+    }
+}
@@ -0,0 +1,267 @@
+// src/locator.rs
+//! DEC locator reporting (DECELR, DECSLE, DECRQLP)
+//!
+//! Some legacy applications, CAD front ends in particular, read the mouse
+//! through the DEC locator instead of xterm mouse modes. The application
+//! enables the locator with DECELR (`CSI Ps ; Pu ' z`), picks the button
+//! events it wants reported unasked with DECSLE (`CSI Pm ' {`), and asks for
+//! the current position with DECRQLP (`CSI Ps ' |`).
+//!
+//! Backends feed pointer motion and button changes through
+//! `Grid::locator_moved`, `Grid::locator_button` and `Grid::locator_left`.
+//! Reports are DECLRP sequences (`CSI Pe ; Pb ; Pr ; Pc ; Pp & w`): answers
+//! to DECRQLP are queued on the grid's response channel, button reports are
+//! returned by `locator_button` for the backend to send. Positions are 1-based character cells, or
+//! pixels from the top-left of the text area when DECELR asked for them.
+//! A one-shot enable (`Ps` 2) turns reporting off after the first report.
+//! Filter rectangles (DECEFR) are not supported.
+
+use crate::drawing::CellGeometry;
+
+/// Locator button, as reported in DECLRP
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LocatorButton {
+    Left,
+    Middle,
+    Right,
+    /// Fourth button (M4)
+    Fourth,
+}
+
+impl LocatorButton {
+    /// Button for a backend button number (1 left, 2 middle, 3 right, 8 back)
+    pub fn from_number(button: u32) -> Option<Self> {
+        match button {
+            1 => Some(Self::Left),
+            2 => Some(Self::Middle),
+            3 => Some(Self::Right),
+            8 => Some(Self::Fourth),
+            _ => None,
+        }
+    }
+
+    /// Bit of the button in DECLRP's `Pb` mask
+    fn mask(self) -> u8 {
+        match self {
+            Self::Right => 1,
+            Self::Middle => 2,
+            Self::Left => 4,
+            Self::Fourth => 8,
+        }
+    }
+
+    /// DECLRP event code (`Pe`) for a press or release of the button
+    fn event(self, pressed: bool) -> u8 {
+        let down = match self {
+            Self::Left => 2,
+            Self::Middle => 4,
+            Self::Right => 6,
+            Self::Fourth => 8,
+        };
+        if pressed { down } else { down + 1 }
+    }
+}
+
+/// Pointer position in cells and in pixels, both 1-based
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct LocatorPoint {
+    row: usize,
+    col: usize,
+    y: usize,
+    x: usize,
+}
+
+impl LocatorPoint {
+    fn at(x: f64, y: f64, geometry: &CellGeometry) -> Self {
+        let (row, col) = geometry.cell_at(x, y);
+        let pixel = |v: f64| ((v - geometry.padding).max(0.0) * geometry.scale) as usize + 1;
+        Self { row: row + 1, col: col + 1, y: pixel(y), x: pixel(x) }
+    }
+}
+
+/// DEC locator state set by the application and fed by the backend
+#[derive(Clone, Debug, Default)]
+pub struct Locator {
+    enabled: bool,
+    one_shot: bool,
+    pixels: bool,
+    report_presses: bool,
+    report_releases: bool,
+    /// Buttons held down (DECLRP `Pb` mask)
+    buttons: u8,
+    /// Last pointer position; `None` outside the terminal
+    position: Option<LocatorPoint>,
+}
+
+impl Locator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether locator reports are enabled
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// DECELR: `mode` 0 off, 1 on, 2 on for one report; unknown modes are
+    /// ignored. Disabling also forgets the DECSLE event selection.
+    pub fn set_reporting(&mut self, mode: u16, pixels: bool) {
+        match mode {
+            0 => {
+                self.enabled = false;
+                self.report_presses = false;
+                self.report_releases = false;
+            }
+            1 | 2 => {
+                self.enabled = true;
+                self.one_shot = mode == 2;
+                self.pixels = pixels;
+            }
+            _ => {}
+        }
+    }
+
+    /// DECSLE: apply each selector in order; an empty list means 0
+    pub fn select_events(&mut self, events: &[u16]) {
+        let events = if events.is_empty() { &[0][..] } else { events };
+        for &event in events {
+            match event {
+                0 => {
+                    self.report_presses = false;
+                    self.report_releases = false;
+                }
+                1 => self.report_presses = true,
+                2 => self.report_presses = false,
+                3 => self.report_releases = true,
+                4 => self.report_releases = false,
+                _ => {}
+            }
+        }
+    }
+
+    /// Record a pointer move to (`x`, `y`) logical pixels
+    pub fn moved(&mut self, x: f64, y: f64, geometry: &CellGeometry) {
+        self.position = Some(LocatorPoint::at(x, y, geometry));
+    }
+
+    /// Record that the pointer left the terminal
+    pub fn left(&mut self) {
+        self.position = None;
+    }
+
+    /// DECRQLP: the report to send, `None` while the locator is disabled
+    pub fn request(&mut self) -> Option<String> {
+        if !self.enabled {
+            return None;
+        }
+        Some(self.report(1))
+    }
+
+    /// A button changed at (`x`, `y`); the report to send if DECSLE selected
+    /// this kind of event
+    pub fn button(&mut self, button: LocatorButton, pressed: bool, x: f64, y: f64, geometry: &CellGeometry) -> Option<String> {
+        self.moved(x, y, geometry);
+        if pressed {
+            self.buttons |= button.mask();
+        } else {
+            self.buttons &= !button.mask();
+        }
+        let wanted = if pressed { self.report_presses } else { self.report_releases };
+        if !self.enabled || !wanted {
+            return None;
+        }
+        Some(self.report(button.event(pressed)))
+    }
+
+    /// DECLRP for `event`; position unknown reports as event 0 alone
+    fn report(&mut self, event: u8) -> String {
+        if self.one_shot {
+            self.set_reporting(0, false);
+        }
+        match self.position {
+            None => "\x1b[0&w".to_string(),
+            Some(point) => {
+                let (row, col) = if self.pixels { (point.y, point.x) } else { (point.row, point.col) };
+                format!("\x1b[{};{};{};{};1&w", event, self.buttons, row, col)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::AnsiParser;
+    use crate::config::TerminalConfig;
+    use crate::grid::Grid;
+    use std::sync::Arc;
+
+    fn geometry() -> CellGeometry {
+        CellGeometry::new(10.0, 20.0, 15.0, 1.0)
+    }
+
+    #[test]
+    fn test_request_reports_cell_or_pixel_position() {
+        let mut locator = Locator::new();
+        locator.moved(25.0, 45.0, &geometry());
+        assert_eq!(locator.request(), None);
+
+        locator.set_reporting(1, false);
+        assert_eq!(locator.request().as_deref(), Some("\x1b[1;0;3;3;1&w"));
+        locator.set_reporting(1, true);
+        assert_eq!(locator.request().as_deref(), Some("\x1b[1;0;46;26;1&w"));
+
+        locator.left();
+        assert_eq!(locator.request().as_deref(), Some("\x1b[0&w"));
+    }
+
+    #[test]
+    fn test_button_events_follow_selection() {
+        let geometry = geometry();
+        let mut locator = Locator::new();
+        locator.set_reporting(1, false);
+        assert_eq!(locator.button(LocatorButton::Left, true, 5.0, 5.0, &geometry), None);
+        assert_eq!(locator.button(LocatorButton::Left, false, 5.0, 5.0, &geometry), None);
+
+        locator.select_events(&[1, 3]);
+        assert_eq!(locator.button(LocatorButton::Left, true, 5.0, 5.0, &geometry).as_deref(), Some("\x1b[2;4;1;1;1&w"));
+        assert_eq!(locator.button(LocatorButton::Right, true, 15.0, 5.0, &geometry).as_deref(), Some("\x1b[6;5;1;2;1&w"));
+        assert_eq!(locator.button(LocatorButton::Left, false, 15.0, 5.0, &geometry).as_deref(), Some("\x1b[3;1;1;2;1&w"));
+
+        // Releases only
+        locator.select_events(&[2]);
+        assert_eq!(locator.button(LocatorButton::Right, false, 15.0, 5.0, &geometry).as_deref(), Some("\x1b[7;0;1;2;1&w"));
+        locator.select_events(&[0]);
+        assert_eq!(locator.button(LocatorButton::Middle, false, 15.0, 5.0, &geometry), None);
+    }
+
+    #[test]
+    fn test_one_shot_disables_after_report() {
+        let mut locator = Locator::new();
+        locator.moved(0.0, 0.0, &geometry());
+        locator.set_reporting(2, false);
+        assert!(locator.request().is_some());
+        assert!(!locator.is_enabled());
+        assert_eq!(locator.request(), None);
+    }
+
+    #[test]
+    fn test_grid_answers_through_response_channel() {
+        let geometry = geometry();
+        let mut grid = Grid::new(80, 24, Arc::new(TerminalConfig::default()));
+        let mut parser = AnsiParser::new();
+        grid.locator_moved(35.0, 65.0, &geometry);
+        parser.feed_str("\x1b[1;2'z\x1b[1'{\x1b['|", &mut grid);
+        assert_eq!(grid.take_responses(), "\x1b[1;0;4;4;1&w");
+
+        // Button reports leave other queued responses alone
+        parser.feed_str("\x1b['|", &mut grid);
+        let report = grid.locator_button(LocatorButton::Middle, true, 35.0, 65.0, &geometry);
+        assert_eq!(report.as_deref(), Some("\x1b[4;2;4;4;1&w"));
+        assert_eq!(grid.locator_button(LocatorButton::Middle, false, 35.0, 65.0, &geometry), None);
+        assert_eq!(grid.take_responses(), "\x1b[1;0;4;4;1&w");
+
+        parser.feed_str("\x1b[0'z\x1b['|", &mut grid);
+        assert_eq!(grid.take_responses(), "");
+    }
+}
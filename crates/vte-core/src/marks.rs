@@ -0,0 +1,167 @@
+// src/marks.rs
+//! Scroll marks for scrollbar annotations and prompt jumping
+//!
+//! Marks are anchored to absolute rows: row 0 is the first line the terminal
+//! ever produced, and a row keeps its number as it scrolls into and through
+//! the scrollback. Marks whose rows are trimmed from the scrollback are
+//! discarded.
+
+/// What a scroll mark points at
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MarkCategory {
+    /// Start of a shell prompt (OSC 133;A command boundary)
+    Prompt,
+    /// Row containing a search match
+    SearchMatch,
+    /// Row where an error trigger fired
+    ErrorTrigger,
+}
+
+/// Scroll mark at an absolute row
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScrollMark {
+    pub row: u64,
+    pub category: MarkCategory,
+    /// Exit status of the command run from this prompt (OSC 133;D); prompts only
+    pub exit_code: Option<i32>,
+}
+
+/// Direction for mark navigation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarkDirection {
+    /// Towards newer output (down)
+    Next,
+    /// Towards older output (up)
+    Previous,
+}
+
+/// Marks sorted by absolute row
+#[derive(Clone, Debug, Default)]
+pub struct ScrollMarks {
+    marks: Vec<ScrollMark>,
+}
+
+impl ScrollMarks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a mark, ignoring duplicates of the same row and category
+    pub fn add(&mut self, row: u64, category: MarkCategory) {
+        let mark = ScrollMark { row, category, exit_code: None };
+        let idx = self.marks.partition_point(|m| m.row <= row);
+        if self.marks[..idx].iter().rev().take_while(|m| m.row == row).any(|m| m.category == category) {
+            return;
+        }
+        self.marks.insert(idx, mark);
+    }
+
+    /// Record `exit_code` on the most recent prompt mark; returns false if there is none
+    pub fn set_last_prompt_exit_code(&mut self, exit_code: i32) -> bool {
+        match self.marks.iter_mut().rev().find(|m| m.category == MarkCategory::Prompt) {
+            Some(mark) => {
+                mark.exit_code = Some(exit_code);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove every mark of one category (e.g. when a search is cleared)
+    pub fn clear_category(&mut self, category: MarkCategory) {
+        self.marks.retain(|m| m.category != category);
+    }
+
+    /// Remove all marks
+    pub fn clear(&mut self) {
+        self.marks.clear();
+    }
+
+    /// Drop marks on rows before `first_row` (trimmed from the scrollback)
+    pub fn prune_before(&mut self, first_row: u64) {
+        let idx = self.marks.partition_point(|m| m.row < first_row);
+        self.marks.drain(..idx);
+    }
+
+    /// All marks in row order
+    pub fn as_slice(&self) -> &[ScrollMark] {
+        &self.marks
+    }
+
+    /// Nearest mark strictly after (`Next`) or before (`Previous`) `row`,
+    /// optionally restricted to one category
+    pub fn find(&self, row: u64, direction: MarkDirection, category: Option<MarkCategory>) -> Option<ScrollMark> {
+        let matches = |m: &&ScrollMark| category.map_or(true, |c| m.category == c);
+        match direction {
+            MarkDirection::Next => self.marks.iter().filter(|m| m.row > row).find(matches).copied(),
+            MarkDirection::Previous => self.marks.iter().rev().filter(|m| m.row < row).find(matches).copied(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.marks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.marks.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_keeps_order_and_dedupes() {
+        let mut marks = ScrollMarks::new();
+        marks.add(10, MarkCategory::Prompt);
+        marks.add(3, MarkCategory::SearchMatch);
+        marks.add(10, MarkCategory::ErrorTrigger);
+        marks.add(10, MarkCategory::Prompt);
+
+        let rows: Vec<u64> = marks.as_slice().iter().map(|m| m.row).collect();
+        assert_eq!(rows, vec![3, 10, 10]);
+    }
+
+    #[test]
+    fn test_find_by_direction_and_category() {
+        let mut marks = ScrollMarks::new();
+        marks.add(5, MarkCategory::Prompt);
+        marks.add(8, MarkCategory::SearchMatch);
+        marks.add(12, MarkCategory::Prompt);
+
+        assert_eq!(marks.find(5, MarkDirection::Next, None).map(|m| m.row), Some(8));
+        assert_eq!(marks.find(5, MarkDirection::Next, Some(MarkCategory::Prompt)).map(|m| m.row), Some(12));
+        assert_eq!(marks.find(12, MarkDirection::Previous, None).map(|m| m.row), Some(8));
+        assert_eq!(marks.find(12, MarkDirection::Previous, Some(MarkCategory::Prompt)).map(|m| m.row), Some(5));
+        assert_eq!(marks.find(12, MarkDirection::Next, None), None);
+        assert_eq!(marks.find(0, MarkDirection::Next, Some(MarkCategory::ErrorTrigger)), None);
+    }
+
+    #[test]
+    fn test_prune_and_clear_category() {
+        let mut marks = ScrollMarks::new();
+        marks.add(1, MarkCategory::Prompt);
+        marks.add(4, MarkCategory::SearchMatch);
+        marks.add(9, MarkCategory::Prompt);
+
+        marks.prune_before(4);
+        assert_eq!(marks.len(), 2);
+        marks.clear_category(MarkCategory::SearchMatch);
+        assert_eq!(marks.as_slice(), &[ScrollMark { row: 9, category: MarkCategory::Prompt, exit_code: None }]);
+    }
+
+    #[test]
+    fn test_exit_code_goes_to_last_prompt() {
+        let mut marks = ScrollMarks::new();
+        assert!(!marks.set_last_prompt_exit_code(0));
+
+        marks.add(2, MarkCategory::Prompt);
+        marks.add(6, MarkCategory::Prompt);
+        marks.add(8, MarkCategory::SearchMatch);
+        assert!(marks.set_last_prompt_exit_code(1));
+
+        let codes: Vec<Option<i32>> = marks.as_slice().iter().map(|m| m.exit_code).collect();
+        assert_eq!(codes, vec![None, Some(1), None]);
+    }
+}
@@ -0,0 +1,171 @@
+// src/modes.rs
+//! Snapshot of the terminal modes set by the running application
+//!
+//! [`Grid::modes`](crate::grid::Grid::modes) reports what the application
+//! enabled through DECSET/DECRST and friends. An embedder can force some
+//! of them off with [`Grid::set_mode_disabled`](crate::grid::Grid::set_mode_disabled),
+//! e.g. to give local selection back to a user whose application grabs the
+//! mouse. A disabled mode reads as off in the snapshot; the application's
+//! own request is remembered and applies again once the mode is re-enabled,
+//! except for the alternate screen, which is only entered on the next
+//! request.
+
+/// Mouse events the application asked to receive (DECSET 1000/1002/1003)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MouseTracking {
+    /// No reporting; the mouse selects text locally
+    #[default]
+    Off,
+    /// Button presses and releases (1000)
+    Click,
+    /// Presses, releases and motion while a button is held (1002)
+    Drag,
+    /// All motion, with or without a button (1003)
+    Motion,
+}
+
+/// How mouse reports are encoded (DECSET 1005/1006)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MouseEncoding {
+    /// X10 bytes, limited to 223 columns and rows
+    #[default]
+    Default,
+    /// Coordinates as UTF-8 characters (1005)
+    Utf8,
+    /// `CSI < b ; x ; y M/m` (1006)
+    Sgr,
+}
+
+/// Modes an embedder can force off regardless of the application
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ForcibleMode {
+    /// Bracketed paste; pastes are sent as typed text
+    BracketedPaste,
+    /// Alternate screen; full-screen applications draw on the primary screen
+    /// and their output stays in the scrollback
+    AlternateScreen,
+}
+
+/// Which [`ForcibleMode`]s are forced off
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DisabledModes {
+    pub bracketed_paste: bool,
+    pub alternate_screen: bool,
+}
+
+impl DisabledModes {
+    pub fn contains(&self, mode: ForcibleMode) -> bool {
+        match mode {
+            ForcibleMode::BracketedPaste => self.bracketed_paste,
+            ForcibleMode::AlternateScreen => self.alternate_screen,
+        }
+    }
+
+    pub fn set(&mut self, mode: ForcibleMode, disabled: bool) {
+        match mode {
+            ForcibleMode::BracketedPaste => self.bracketed_paste = disabled,
+            ForcibleMode::AlternateScreen => self.alternate_screen = disabled,
+        }
+    }
+}
+
+/// Terminal modes in effect, as returned by [`Grid::modes`](crate::grid::Grid::modes)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Modes {
+    pub mouse_tracking: MouseTracking,
+    pub mouse_encoding: MouseEncoding,
+    /// Focus in/out reports (DECSET 1004)
+    pub focus_reporting: bool,
+    /// Pastes are wrapped in `CSI 200~` / `CSI 201~` (DECSET 2004)
+    pub bracketed_paste: bool,
+    /// The alternate screen is shown (DECSET 47/1049)
+    pub alternate_screen: bool,
+    /// Cursor keys send `SS3` sequences (DECCKM)
+    pub application_cursor_keys: bool,
+    /// The keypad sends application sequences (DECKPAM)
+    pub application_keypad: bool,
+    /// Insert mode (IRM)
+    pub insert: bool,
+    /// Auto-wrap at the right margin (DECAWM)
+    pub auto_wrap: bool,
+    /// Cursor addressing relative to the scroll region (DECOM)
+    pub origin: bool,
+    /// Cursor visibility (DECTCEM)
+    pub cursor_visible: bool,
+    /// Modes the embedder forced off; they read as off above
+    pub disabled: DisabledModes,
+}
+
+impl Modes {
+    /// Whether the application receives mouse events instead of local selection
+    pub fn mouse_reporting(&self) -> bool {
+        self.mouse_tracking != MouseTracking::Off
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::AnsiParser;
+    use crate::config::TerminalConfig;
+    use crate::grid::Grid;
+    use std::sync::Arc;
+
+    fn grid() -> Grid {
+        Grid::new(20, 4, Arc::new(TerminalConfig::default()))
+    }
+
+    #[test]
+    fn test_snapshot_tracks_application_modes() {
+        let mut grid = grid();
+        assert_eq!(grid.modes(), Modes { auto_wrap: true, cursor_visible: true, ..Modes::default() });
+
+        let mut parser = AnsiParser::new();
+        parser.feed_str("\x1b[?1002h\x1b[?1006h\x1b[?1004h\x1b[?2004h\x1b[?1049h\x1b[?1h", &mut grid);
+        let modes = grid.modes();
+        assert_eq!(modes.mouse_tracking, MouseTracking::Drag);
+        assert_eq!(modes.mouse_encoding, MouseEncoding::Sgr);
+        assert!(modes.mouse_reporting() && modes.focus_reporting && modes.bracketed_paste);
+        assert!(modes.alternate_screen && modes.application_cursor_keys);
+
+        parser.feed_str("\x1b[?1000l\x1b[?1006l\x1b[?2004l\x1b[?1049l", &mut grid);
+        let modes = grid.modes();
+        assert_eq!(modes.mouse_tracking, MouseTracking::Off);
+        assert_eq!(modes.mouse_encoding, MouseEncoding::Default);
+        assert!(!modes.bracketed_paste && !modes.alternate_screen);
+    }
+
+    #[test]
+    fn test_disabled_mode_reads_off_and_restores() {
+        let mut grid = grid();
+        let mut parser = AnsiParser::new();
+        parser.feed_str("\x1b[?2004h", &mut grid);
+
+        grid.set_mode_disabled(ForcibleMode::BracketedPaste, true);
+        let modes = grid.modes();
+        assert!(!modes.bracketed_paste && !grid.is_bracketed_paste_mode());
+        assert!(modes.disabled.contains(ForcibleMode::BracketedPaste));
+
+        // The application's request still applies once re-enabled
+        grid.set_mode_disabled(ForcibleMode::BracketedPaste, false);
+        assert!(grid.modes().bracketed_paste);
+    }
+
+    #[test]
+    fn test_disabled_alternate_screen() {
+        let mut grid = grid();
+        let mut parser = AnsiParser::new();
+        parser.feed_str("\x1b[?1049h", &mut grid);
+        grid.set_mode_disabled(ForcibleMode::AlternateScreen, true);
+        assert!(!grid.modes().alternate_screen);
+
+        parser.feed_str("\x1b[?1049h", &mut grid);
+        assert!(!grid.modes().alternate_screen);
+
+        // Only the next request enters it again
+        grid.set_mode_disabled(ForcibleMode::AlternateScreen, false);
+        assert!(!grid.modes().alternate_screen);
+        parser.feed_str("\x1b[?1049h", &mut grid);
+        assert!(grid.modes().alternate_screen);
+    }
+}
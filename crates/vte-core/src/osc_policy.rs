@@ -0,0 +1,306 @@
+// src/osc_policy.rs
+//! Per-category policy for OSC sequences
+//!
+//! OSC sequences let whatever runs in the terminal (possibly a remote host)
+//! rename the window, write the clipboard, plant links or pop up desktop
+//! notifications. `SecurityConfig::osc_policy` sets an [`OscAction`] for
+//! each [`OscCategory`]; the parser asks the grid before dispatching any
+//! sequence in one of them (`AnsiGrid::allow_osc`), so every handler is
+//! covered by the same check.
+//!
+//! - **Allow** dispatches the sequence.
+//! - **Deny** drops it and records it in the audit log
+//!   (`VteTerminalCore::denied_osc_log`).
+//! - **Ask** holds it and emits `TerminalEvent::OscPermissionRequest`; the
+//!   embedder asks the user and answers with
+//!   `VteTerminalCore::answer_osc_request`, which runs an allowed sequence
+//!   late or logs a refused one. Clipboard reads are already confirmed
+//!   request by request (see `clipboard`), so for them Ask and Allow both
+//!   queue a `ClipboardReadRequest`.
+//!
+//! Custom sequences (OSC numbers without a well-known meaning) only reach
+//! the embedder, as `TerminalEvent::CustomOsc`, for the numbers listed in
+//! `OscPolicy::custom_codes`; others are ignored without logging.
+
+use crate::constants::{MAX_OSC_AUDIT_ENTRIES, MAX_PENDING_OSC_PROMPTS, OSC_AUDIT_PREVIEW_LEN};
+use std::collections::VecDeque;
+use std::time::Instant;
+use tracing::warn;
+
+pub use crate::ansi::OscCategory;
+
+/// What to do with OSC sequences of one category
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum OscAction {
+    #[default]
+    Allow,
+    Deny,
+    /// Hold the sequence until the user decides
+    Ask,
+}
+
+/// Action for each OSC category
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OscPolicy {
+    /// Window title (OSC 0, 2)
+    pub title: OscAction,
+    /// Setting the clipboard (OSC 52)
+    pub clipboard_write: OscAction,
+    /// Reading the clipboard (OSC 52 `?`)
+    pub clipboard_read: OscAction,
+    /// Hyperlinks (OSC 8)
+    pub hyperlinks: OscAction,
+    /// Desktop notifications (OSC 9, OSC 777)
+    pub notifications: OscAction,
+    /// Working directory reports (OSC 7)
+    pub current_directory: OscAction,
+    /// Palette and dynamic colors, set or queried (OSC 4, 10-12)
+    pub colors: OscAction,
+    /// Mouse pointer shape (OSC 22)
+    pub pointer_shape: OscAction,
+    /// Clickable regions (OSC 7777), which also need
+    /// `SecurityConfig::allow_active_regions`
+    pub active_regions: OscAction,
+    /// Sequences for `custom_codes`
+    pub custom: OscAction,
+    /// OSC numbers forwarded to the embedder as `TerminalEvent::CustomOsc`
+    pub custom_codes: Vec<u16>,
+}
+
+impl Default for OscPolicy {
+    fn default() -> Self {
+        Self {
+            title: OscAction::Allow,
+            clipboard_write: OscAction::Allow,
+            clipboard_read: OscAction::Deny,
+            hyperlinks: OscAction::Allow,
+            notifications: OscAction::Allow,
+            current_directory: OscAction::Allow,
+            colors: OscAction::Allow,
+            pointer_shape: OscAction::Allow,
+            active_regions: OscAction::Allow,
+            custom: OscAction::Allow,
+            custom_codes: Vec::new(),
+        }
+    }
+}
+
+impl OscPolicy {
+    /// Action configured for `category`
+    pub fn action(&self, category: OscCategory) -> OscAction {
+        match category {
+            OscCategory::Title => self.title,
+            OscCategory::ClipboardWrite => self.clipboard_write,
+            OscCategory::ClipboardRead => self.clipboard_read,
+            OscCategory::Hyperlink => self.hyperlinks,
+            OscCategory::Notification => self.notifications,
+            OscCategory::CurrentDirectory => self.current_directory,
+            OscCategory::Colors => self.colors,
+            OscCategory::PointerShape => self.pointer_shape,
+            OscCategory::ActiveRegion => self.active_regions,
+            OscCategory::Custom => self.custom,
+        }
+    }
+
+    /// Whether custom OSC `code` is forwarded to the embedder
+    pub fn handles_custom(&self, code: u16) -> bool {
+        self.custom_codes.contains(&code)
+    }
+}
+
+/// An OSC sequence the policy (or the user) refused
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeniedOsc {
+    pub category: OscCategory,
+    /// Start of the sequence (`number;data`), at most
+    /// `OSC_AUDIT_PREVIEW_LEN` characters
+    pub preview: String,
+    pub at: Instant,
+}
+
+/// An OSC sequence awaiting the user's permission
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OscRequest {
+    pub id: u64,
+    pub category: OscCategory,
+    /// Start of the sequence, to show the user
+    pub preview: String,
+    osc: String,
+}
+
+/// Held sequences and the audit log of one grid
+#[derive(Clone, Debug, Default)]
+pub struct OscGate {
+    pending: Vec<OscRequest>,
+    /// Requests not yet handed out by `take_new`
+    unannounced: usize,
+    next_id: u64,
+    denied: VecDeque<DeniedOsc>,
+}
+
+impl OscGate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply `action` to `osc`; returns whether to dispatch it now
+    ///
+    /// Asking with too many requests already waiting denies.
+    pub fn check(&mut self, category: OscCategory, action: OscAction, osc: &str, now: Instant) -> bool {
+        match action {
+            OscAction::Allow => true,
+            OscAction::Ask if self.pending.len() < MAX_PENDING_OSC_PROMPTS => {
+                self.next_id += 1;
+                self.pending.push(OscRequest { id: self.next_id, category, preview: preview(osc), osc: osc.to_string() });
+                self.unannounced += 1;
+                false
+            }
+            OscAction::Ask | OscAction::Deny => {
+                self.deny(category, osc, now);
+                false
+            }
+        }
+    }
+
+    /// Requests held since the last call, to ask the user about
+    pub fn take_new(&mut self) -> Vec<OscRequest> {
+        let new = self.pending[self.pending.len() - self.unannounced..].to_vec();
+        self.unannounced = 0;
+        new
+    }
+
+    /// Settle request `id`; returns the sequence to dispatch if allowed,
+    /// logging it as denied otherwise
+    pub fn answer(&mut self, id: u64, allow: bool, now: Instant) -> Option<String> {
+        let index = self.pending.iter().position(|request| request.id == id)?;
+        // An answer can arrive before the request was taken
+        if index >= self.pending.len() - self.unannounced {
+            self.unannounced -= 1;
+        }
+        let request = self.pending.remove(index);
+        if allow {
+            Some(request.osc)
+        } else {
+            self.deny(request.category, &request.osc, now);
+            None
+        }
+    }
+
+    /// Denied sequences, oldest first; only the latest
+    /// `MAX_OSC_AUDIT_ENTRIES` are kept
+    pub fn denied(&self) -> &VecDeque<DeniedOsc> {
+        &self.denied
+    }
+
+    fn deny(&mut self, category: OscCategory, osc: &str, now: Instant) {
+        let preview = preview(osc);
+        warn!("Denied OSC sequence ({:?}): {:?}", category, preview);
+        if self.denied.len() >= MAX_OSC_AUDIT_ENTRIES {
+            self.denied.pop_front();
+        }
+        self.denied.push_back(DeniedOsc { category, preview, at: now });
+    }
+}
+
+fn preview(osc: &str) -> String {
+    osc.chars().take(OSC_AUDIT_PREVIEW_LEN).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::AnsiParser;
+    use crate::config::TerminalConfig;
+    use crate::constants::MAX_QUEUED_OSC_EVENTS;
+    use crate::grid::Grid;
+    use crate::security::SecurityConfig;
+    use std::sync::Arc;
+
+    fn grid_with(policy: OscPolicy) -> Grid {
+        let security = SecurityConfig { osc_policy: policy, ..SecurityConfig::default() };
+        Grid::new(20, 2, Arc::new(TerminalConfig::default().with_security(security)))
+    }
+
+    #[test]
+    fn test_denied_sequences_are_logged() {
+        let mut grid = grid_with(OscPolicy { title: OscAction::Deny, ..OscPolicy::default() });
+        AnsiParser::new().feed_str("\x1b]2;first\x07\x1b]2;second\x07", &mut grid);
+        assert_eq!(grid.title(), "");
+        let log: Vec<_> = grid.denied_osc().iter().map(|d| (d.category, d.preview.clone())).collect();
+        assert_eq!(log, vec![(OscCategory::Title, "2;first".to_string()), (OscCategory::Title, "2;second".to_string())]);
+    }
+
+    #[test]
+    fn test_ask_holds_until_answered() {
+        let mut grid = grid_with(OscPolicy { title: OscAction::Ask, ..OscPolicy::default() });
+        AnsiParser::new().feed_str("\x1b]0;build\x07\x1b]0;evil\x07", &mut grid);
+        let requests = grid.take_osc_requests();
+        assert_eq!(requests.len(), 2);
+        assert!(grid.take_osc_requests().is_empty());
+        assert_eq!(grid.title(), "");
+
+        assert!(grid.answer_osc_request(requests[0].id, true));
+        assert_eq!(grid.title(), "build");
+        assert!(!grid.answer_osc_request(requests[1].id, false));
+        assert!(!grid.answer_osc_request(requests[0].id, true));
+        assert_eq!(grid.title(), "build");
+        assert_eq!(grid.denied_osc().len(), 1);
+    }
+
+    #[test]
+    fn test_notifications_and_custom_codes() {
+        let mut grid = grid_with(OscPolicy { custom_codes: vec![5113], ..OscPolicy::default() });
+        AnsiParser::new().feed_str("\x1b]777;notify;make;done\x07\x1b]5113;a=1\x07\x1b]5114;b\x07", &mut grid);
+        assert_eq!(grid.take_notifications(), vec![("make".to_string(), "done".to_string())]);
+        assert_eq!(grid.take_custom_osc(), vec![(5113, "a=1".to_string())]);
+        // Unregistered custom codes are ignored, not audited
+        assert!(grid.denied_osc().is_empty());
+    }
+
+    #[test]
+    fn test_held_links_open_only_while_the_stream_is_inside_them() {
+        let mut grid = grid_with(OscPolicy { hyperlinks: OscAction::Ask, ..OscPolicy::default() });
+        let mut parser = AnsiParser::new();
+        // Closes are never held; a link allowed after its close has nothing to cover
+        parser.feed_str("\x1b]8;;https://example.com/a\x1b\\ab\x1b]8;;\x1b\\cd", &mut grid);
+        let requests = grid.take_osc_requests();
+        assert_eq!(requests.len(), 1);
+        assert!(!grid.answer_osc_request(requests[0].id, true));
+        parser.feed_str("ef", &mut grid);
+        assert!((0..6).all(|col| grid.hyperlink_at(0, col).is_none()));
+
+        // Allowed while still open, the link covers the text up to its close
+        parser.feed_str("\r\n\x1b]8;;https://example.com/b\x1b\\g", &mut grid);
+        let requests = grid.take_osc_requests();
+        assert!(grid.answer_osc_request(requests[0].id, true));
+        parser.feed_str("h\x1b]8;;\x1b\\i", &mut grid);
+        assert_eq!(grid.hyperlink_at(1, 0), None);
+        assert_eq!(grid.hyperlink_at(1, 1), Some("https://example.com/b"));
+        assert_eq!(grid.hyperlink_at(1, 2), None);
+    }
+
+    #[test]
+    fn test_embedder_queues_are_bounded() {
+        let mut grid = grid_with(OscPolicy { custom_codes: vec![5113], ..OscPolicy::default() });
+        let mut parser = AnsiParser::new();
+        for _ in 0..=MAX_QUEUED_OSC_EVENTS {
+            parser.feed_str("\x1b]9;done\x07\x1b]5113;a\x07", &mut grid);
+        }
+        assert_eq!(grid.take_notifications().len(), MAX_QUEUED_OSC_EVENTS);
+        assert_eq!(grid.take_custom_osc().len(), MAX_QUEUED_OSC_EVENTS);
+        parser.feed_str("\x1b]9;again\x07", &mut grid);
+        assert_eq!(grid.take_notifications().len(), 1);
+    }
+
+    #[test]
+    fn test_pending_prompts_are_bounded() {
+        let mut gate = OscGate::new();
+        let now = Instant::now();
+        for _ in 0..MAX_PENDING_OSC_PROMPTS {
+            assert!(!gate.check(OscCategory::Title, OscAction::Ask, "2;x", now));
+        }
+        gate.check(OscCategory::Title, OscAction::Ask, "2;x", now);
+        assert_eq!(gate.take_new().len(), MAX_PENDING_OSC_PROMPTS);
+        assert_eq!(gate.denied().len(), 1);
+    }
+}
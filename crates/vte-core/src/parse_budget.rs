@@ -0,0 +1,206 @@
+// src/parse_budget.rs
+//! Bounded parsing of PTY output
+//!
+//! The reader thread parses output under the grid's write lock. A large read,
+//! e.g. a flood of `cat` output that piled up while the reader was
+//! descheduled, parsed in one go holds that lock long enough to freeze
+//! rendering. The reader instead parses each read in slices of at most
+//! `TerminalConfig::parse_slice_bytes` bytes or `parse_slice_us`
+//! microseconds: after each slice it commits the grid, releases the lock and
+//! requests a redraw. When the embedder reports drawn frames through a
+//! [`FrameAck`], the reader continues with the next slice only once the
+//! requested frame was drawn (or `FRAME_ACK_TIMEOUT_MS` passed, e.g. while the
+//! widget is hidden); otherwise it continues right away.
+
+use crate::constants::FRAME_ACK_TIMEOUT_MS;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Grapheme clusters parsed between clock reads
+const CLOCK_CHECK_INTERVAL: usize = 64;
+
+/// Longest wait for the embedder to draw a slice
+const FRAME_ACK_TIMEOUT: Duration = Duration::from_millis(FRAME_ACK_TIMEOUT_MS);
+
+/// Limits on one slice of parsing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseBudget {
+    /// Bytes of decoded text parsed per slice
+    pub max_bytes: usize,
+    /// Time spent parsing per slice
+    pub max_time: Duration,
+}
+
+impl ParseBudget {
+    pub fn new(max_bytes: usize, max_time: Duration) -> Self {
+        Self { max_bytes, max_time }
+    }
+
+    /// Pass grapheme clusters from the start of `text` to `feed` until the
+    /// budget is spent, returning the bytes passed
+    ///
+    /// At least one cluster of non-empty text is passed, so every slice makes
+    /// progress; the returned length always ends on a cluster boundary.
+    pub fn run(&self, text: &str, mut feed: impl FnMut(&str)) -> usize {
+        let start = Instant::now();
+        let mut consumed = 0;
+        for (i, grapheme) in text.graphemes(true).enumerate() {
+            if consumed > 0
+                && (consumed >= self.max_bytes
+                    || (i % CLOCK_CHECK_INTERVAL == 0 && start.elapsed() >= self.max_time))
+            {
+                break;
+            }
+            feed(grapheme);
+            consumed += grapheme.len();
+        }
+        consumed
+    }
+}
+
+/// Frames drawn by the embedder, which the reader waits for between slices
+///
+/// Obtained from `VteTerminalCore::frame_ack`; the embedder calls
+/// [`FrameAck::presented`] after each frame it draws.
+#[derive(Debug, Clone, Default)]
+pub struct FrameAck {
+    shared: Arc<(Mutex<FrameState>, Condvar)>,
+}
+
+#[derive(Debug, Default)]
+struct FrameState {
+    /// An embedder reports frames
+    attached: bool,
+    /// Frames reported so far
+    presented: u64,
+    /// The last wait timed out; no frames are being drawn
+    stalled: bool,
+}
+
+impl FrameAck {
+    /// Report that a frame was drawn
+    pub fn presented(&self) {
+        let (state, drawn) = &*self.shared;
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        state.presented += 1;
+        state.stalled = false;
+        drawn.notify_all();
+    }
+
+    /// Have the reader wait for frames from now on
+    pub(crate) fn attach(&self) {
+        let (state, _) = &*self.shared;
+        state.lock().unwrap_or_else(|e| e.into_inner()).attached = true;
+    }
+
+    /// Frames reported so far
+    pub(crate) fn frames(&self) -> u64 {
+        let (state, _) = &*self.shared;
+        state.lock().unwrap_or_else(|e| e.into_inner()).presented
+    }
+
+    /// Wait until a frame after the first `seen` was drawn
+    ///
+    /// Returns at once when no embedder reports frames. A wait that times
+    /// out marks the embedder stalled, and later waits return at once until
+    /// it draws again, so a hidden widget does not slow the reader down.
+    pub(crate) fn wait_past(&self, seen: u64) {
+        let (state, drawn) = &*self.shared;
+        let state = state.lock().unwrap_or_else(|e| e.into_inner());
+        if !state.attached || state.stalled {
+            return;
+        }
+        let (mut state, wait) = drawn
+            .wait_timeout_while(state, FRAME_ACK_TIMEOUT, |state| state.presented <= seen)
+            .unwrap_or_else(|e| e.into_inner());
+        state.stalled = wait.timed_out();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::AnsiParser;
+    use crate::config::TerminalConfig;
+    use crate::grid::Grid;
+    use std::sync::Arc;
+
+    fn slices(budget: ParseBudget, text: &str) -> Vec<&str> {
+        let mut slices = Vec::new();
+        let mut parsed = 0;
+        while parsed < text.len() {
+            let consumed = budget.run(&text[parsed..], |_| {});
+            slices.push(&text[parsed..parsed + consumed]);
+            parsed += consumed;
+        }
+        slices
+    }
+
+    #[test]
+    fn test_slices_end_on_cluster_boundaries() {
+        let budget = ParseBudget::new(4, Duration::from_secs(1));
+        // "e" + combining acute is one cluster of 3 bytes
+        assert_eq!(slices(budget, "abcde\u{301}fgh"), ["abcd", "e\u{301}f", "gh"]);
+        // A cluster larger than the budget still goes through
+        let budget = ParseBudget::new(1, Duration::from_secs(1));
+        assert_eq!(slices(budget, "\u{1F44D}\u{1F3FD}x"), ["\u{1F44D}\u{1F3FD}", "x"]);
+        assert_eq!(budget.run("", |_| panic!("nothing to feed")), 0);
+    }
+
+    #[test]
+    fn test_spent_time_ends_slice() {
+        let budget = ParseBudget::new(usize::MAX, Duration::ZERO);
+        let text = "x".repeat(CLOCK_CHECK_INTERVAL * 3);
+        assert_eq!(budget.run(&text, |_| {}), CLOCK_CHECK_INTERVAL);
+    }
+
+    #[test]
+    fn test_frame_ack_waits_only_for_drawing_embedders() {
+        let ack = FrameAck::default();
+        // Nobody draws: no wait
+        let start = Instant::now();
+        ack.wait_past(0);
+        assert!(start.elapsed() < FRAME_ACK_TIMEOUT);
+
+        // A frame drawn meanwhile ends the wait
+        ack.attach();
+        let embedder = ack.clone();
+        let drawer = std::thread::spawn(move || embedder.presented());
+        ack.wait_past(0);
+        drawer.join().unwrap();
+        assert_eq!(ack.frames(), 1);
+
+        // Once a frame does not come, waits end at once until one is drawn
+        let start = Instant::now();
+        ack.wait_past(1);
+        assert!(start.elapsed() >= FRAME_ACK_TIMEOUT);
+        let start = Instant::now();
+        ack.wait_past(1);
+        assert!(start.elapsed() < FRAME_ACK_TIMEOUT);
+        ack.presented();
+        assert!(!ack.shared.0.lock().unwrap().stalled);
+    }
+
+    #[test]
+    fn test_sliced_parse_matches_whole_parse() {
+        let text = "\x1b[1;31mred\x1b[0m plain \x1b]0;title\x07\r\nnext line \x1b[2;5Hmoved";
+        let mut whole = Grid::new(20, 4, Arc::new(TerminalConfig::default()));
+        AnsiParser::new().feed_str(text, &mut whole);
+
+        let mut sliced = Grid::new(20, 4, Arc::new(TerminalConfig::default()));
+        let mut parser = AnsiParser::new();
+        let budget = ParseBudget::new(3, Duration::from_secs(1));
+        let mut parsed = 0;
+        while parsed < text.len() {
+            parsed += budget.run(&text[parsed..], |grapheme| parser.feed_str(grapheme, &mut sliced));
+        }
+        assert_eq!((sliced.row, sliced.col), (whole.row, whole.col));
+        assert_eq!(sliced.title(), whole.title());
+        for row in 0..4 {
+            for col in 0..20 {
+                assert_eq!(sliced.get_cell(row, col), whole.get_cell(row, col));
+            }
+        }
+    }
+}
@@ -0,0 +1,314 @@
+// src/paste.rs
+//! Streaming of large pastes to the PTY
+//!
+//! Writing a paste with one `write_all` holds the writer lock until the
+//! child has read nearly all of it: the PTY buffer only takes a few
+//! kilobytes, so a multi-megabyte paste blocks the caller (and every
+//! keystroke) for as long as the application needs to consume it. Pastes
+//! above `PASTE_STREAM_THRESHOLD` are instead written in `PASTE_CHUNK_SIZE`
+//! pieces on a background thread. The writer lock is taken once per chunk,
+//! and each blocking write paces the stream to the reader.
+//!
+//! Streams report `TerminalEvent::PasteProgress` and `PasteFinished`, go out
+//! one at a time in the order they were started, and stop on
+//! [`PasteStreamer::cancel`]. A cancelled bracketed paste is still closed
+//! with its end marker so the application leaves paste mode. Typed input
+//! sent with [`PasteStreamer::send_input`] while a paste is in flight queues
+//! behind it instead of landing in the middle of the pasted text.
+
+use crate::broadcast::InputSink;
+use crate::constants::{PASTE_CHUNK_SIZE, PASTE_STREAM_THRESHOLD};
+use crate::error::TerminalError;
+use crate::events::{EventEmitter, TerminalEvent};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+const PASTE_START: &[u8] = b"\x1b[200~";
+const PASTE_END: &[u8] = b"\x1b[201~";
+
+/// Minimum time between two `PasteProgress` events of one stream
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+struct Shared {
+    sink: InputSink,
+    events: EventEmitter,
+    /// Writes waiting for the background writer, in the order they were sent
+    queue: Mutex<Queue>,
+    /// Bumped by `cancel`; streams started before the bump stop
+    cancel_epoch: AtomicU64,
+    in_flight: AtomicUsize,
+}
+
+#[derive(Debug, Default)]
+struct Queue {
+    jobs: VecDeque<Job>,
+    /// Whether a writer thread is draining `jobs`
+    writing: bool,
+}
+
+#[derive(Debug)]
+struct Job {
+    bytes: Vec<u8>,
+    /// Cancel epoch when sent; `None` for typed input, which is never dropped
+    epoch: Option<u64>,
+}
+
+/// Cloneable handle writing pastes to one terminal's PTY
+#[derive(Clone, Debug)]
+pub struct PasteStreamer {
+    shared: Arc<Shared>,
+}
+
+impl PasteStreamer {
+    pub fn new(sink: InputSink, events: EventEmitter) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                sink,
+                events,
+                queue: Mutex::new(Queue::default()),
+                cancel_epoch: AtomicU64::new(0),
+                in_flight: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    /// Write an encoded paste (see `encode_text`)
+    ///
+    /// Pastes up to `PASTE_STREAM_THRESHOLD` bytes are written before this
+    /// returns, unless a stream is in flight: then they queue behind it.
+    /// Errors of a background stream are published as `TerminalEvent::Error`.
+    pub fn send(&self, bytes: Vec<u8>) -> Result<(), TerminalError> {
+        if bytes.len() <= PASTE_STREAM_THRESHOLD && !self.is_streaming() {
+            return self.shared.sink.send(&bytes);
+        }
+
+        let epoch = self.shared.cancel_epoch.load(Ordering::SeqCst);
+        self.queue(Job { bytes, epoch: Some(epoch) })
+    }
+
+    /// Write typed input (keys, input method commits), queued behind any
+    /// paste in flight so it is not interleaved with the pasted text; unlike
+    /// a paste it is not dropped by `cancel`
+    pub fn send_input(&self, bytes: &[u8]) -> Result<(), TerminalError> {
+        if !self.is_streaming() {
+            return self.shared.sink.send(bytes);
+        }
+        self.queue(Job { bytes: bytes.to_vec(), epoch: None })
+    }
+
+    /// Append `job` to the queue, starting a writer thread if none is draining it
+    fn queue(&self, job: Job) -> Result<(), TerminalError> {
+        self.shared.in_flight.fetch_add(1, Ordering::SeqCst);
+        let mut queue = self.shared.queue.lock().unwrap_or_else(|e| e.into_inner());
+        queue.jobs.push_back(job);
+        if queue.writing {
+            return Ok(());
+        }
+
+        let shared = Arc::clone(&self.shared);
+        if let Err(e) = thread::Builder::new().name("paste".to_string()).spawn(move || write_queued(&shared)) {
+            queue.jobs.pop_back();
+            self.shared.in_flight.fetch_sub(1, Ordering::SeqCst);
+            return Err(TerminalError::from(e));
+        }
+        queue.writing = true;
+        Ok(())
+    }
+
+    /// Abort every paste started so far; returns false if none was in flight
+    pub fn cancel(&self) -> bool {
+        self.shared.cancel_epoch.fetch_add(1, Ordering::SeqCst);
+        self.is_streaming()
+    }
+
+    /// Whether a paste is being streamed or queued
+    pub fn is_streaming(&self) -> bool {
+        self.shared.in_flight.load(Ordering::SeqCst) > 0
+    }
+}
+
+/// Writer thread: write queued jobs in order until the queue is empty
+fn write_queued(shared: &Shared) {
+    loop {
+        let job = {
+            let mut queue = shared.queue.lock().unwrap_or_else(|e| e.into_inner());
+            match queue.jobs.pop_front() {
+                Some(job) => job,
+                None => {
+                    queue.writing = false;
+                    return;
+                }
+            }
+        };
+        let finished = write_job(shared, job);
+        // Settled before the event goes out, so a listener sees the stream done
+        shared.in_flight.fetch_sub(1, Ordering::SeqCst);
+        if let Some(finished) = finished {
+            shared.events.emit(finished);
+        }
+    }
+}
+
+/// Write `job`; returns the `PasteFinished` event of a paste
+fn write_job(shared: &Shared, job: Job) -> Option<TerminalEvent> {
+    let Some(epoch) = job.epoch else {
+        if let Err(e) = shared.sink.send(&job.bytes) {
+            warn!("Queued input failed: {}", e);
+            shared.events.emit_error(&e);
+        }
+        return None;
+    };
+
+    let total = job.bytes.len();
+    let mut last_progress = Instant::now();
+    let result = stream(
+        &shared.sink,
+        &job.bytes,
+        PASTE_CHUNK_SIZE,
+        || shared.cancel_epoch.load(Ordering::SeqCst) != epoch,
+        |sent| {
+            if last_progress.elapsed() >= PROGRESS_INTERVAL {
+                last_progress = Instant::now();
+                shared.events.emit(TerminalEvent::PasteProgress { sent, total });
+            }
+        },
+    );
+    Some(match result {
+        Ok((sent, cancelled)) => {
+            debug!("Paste stream of {} bytes finished after {} (cancelled: {})", total, sent, cancelled);
+            TerminalEvent::PasteFinished { sent, total, cancelled }
+        }
+        Err(e) => {
+            warn!("Paste stream failed: {}", e);
+            shared.events.emit_error(&e);
+            TerminalEvent::PasteFinished { sent: 0, total, cancelled: true }
+        }
+    })
+}
+
+/// Write `bytes` in chunks until done or `cancelled()`, calling `progress`
+/// with the bytes written after each chunk; returns the bytes written and
+/// whether the stream was cancelled
+fn stream(
+    sink: &InputSink,
+    bytes: &[u8],
+    chunk_size: usize,
+    cancelled: impl Fn() -> bool,
+    mut progress: impl FnMut(usize),
+) -> Result<(usize, bool), TerminalError> {
+    let bracketed = bytes.starts_with(PASTE_START) && bytes.ends_with(PASTE_END);
+    let body_end = if bracketed { bytes.len() - PASTE_END.len() } else { bytes.len() };
+    // Never split the start marker, so a cancelled stream can always close it
+    let chunk_size = chunk_size.max(PASTE_START.len());
+
+    let mut sent = 0;
+    while sent < bytes.len() {
+        if cancelled() {
+            if bracketed && sent > 0 {
+                // Close the bracket, or finish the end marker already begun
+                let rest = if sent > body_end { &bytes[sent..] } else { PASTE_END };
+                sink.send(rest)?;
+            }
+            return Ok((sent, true));
+        }
+        let end = (sent + chunk_size).min(bytes.len());
+        sink.send(&bytes[sent..end])?;
+        sent = end;
+        progress(sent);
+    }
+    Ok((sent, false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::SharedBuffer;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_stream_writes_all_chunks() {
+        let buffer = SharedBuffer::default();
+        let mut reported = Vec::new();
+        let result = stream(&InputSink::new(buffer.clone()), b"0123456789abcdef", 6, || false, |sent| reported.push(sent));
+        assert_eq!(result.unwrap(), (16, false));
+        assert_eq!(reported, vec![6, 12, 16]);
+        assert_eq!(buffer.writes(), [&b"012345"[..], b"6789ab", b"cdef"]);
+    }
+
+    #[test]
+    fn test_cancelled_bracketed_paste_is_closed() {
+        let buffer = SharedBuffer::default();
+        let chunks = Cell::new(0);
+        let paste = b"\x1b[200~line one\nline two\n\x1b[201~";
+        let result = stream(&InputSink::new(buffer.clone()), paste, 15, || chunks.get() == 1, |_| chunks.set(chunks.get() + 1));
+        assert_eq!(result.unwrap(), (15, true));
+        assert_eq!(buffer.contents(), b"\x1b[200~line one\n\x1b[201~");
+
+        // Cancelled before anything was written: nothing to close
+        let buffer = SharedBuffer::default();
+        assert_eq!(stream(&InputSink::new(buffer.clone()), paste, 8, || true, |_| {}).unwrap(), (0, true));
+        assert!(buffer.contents().is_empty());
+    }
+
+    #[test]
+    fn test_cancel_inside_end_marker_finishes_it() {
+        let buffer = SharedBuffer::default();
+        let chunks = Cell::new(0);
+        let paste = b"\x1b[200~abcd\x1b[201~";
+        stream(&InputSink::new(buffer.clone()), paste, 6, || chunks.get() == 2, |_| chunks.set(chunks.get() + 1)).unwrap();
+        assert_eq!(buffer.contents(), paste);
+    }
+
+    #[test]
+    fn test_large_paste_streams_in_background() {
+        let buffer = SharedBuffer::default();
+        let events = EventEmitter::new();
+        let rx = events.subscribe();
+        let streamer = PasteStreamer::new(InputSink::new(buffer.clone()), events);
+
+        streamer.send(b"small".to_vec()).unwrap();
+        assert_eq!(buffer.contents(), b"small");
+
+        let large = vec![b'x'; PASTE_STREAM_THRESHOLD + 1];
+        streamer.send(large.clone()).unwrap();
+        let finished = loop {
+            match rx.recv_blocking().unwrap() {
+                TerminalEvent::PasteFinished { sent, total, cancelled } => break (sent, total, cancelled),
+                _ => continue,
+            }
+        };
+        assert_eq!(finished, (large.len(), large.len(), false));
+        assert_eq!(buffer.contents().len(), 5 + large.len());
+        assert!(!streamer.is_streaming());
+        assert!(!streamer.cancel());
+    }
+
+    #[test]
+    fn test_typed_input_queues_behind_pastes_in_order() {
+        let buffer = SharedBuffer::default();
+        let streamer = PasteStreamer::new(InputSink::new(buffer.clone()), EventEmitter::new());
+
+        let first = vec![b'a'; PASTE_STREAM_THRESHOLD + 1];
+        streamer.send(first.clone()).unwrap();
+        streamer.send_input(b"typed").unwrap();
+        streamer.send(b"second".to_vec()).unwrap();
+        // Cancelling drops the pastes still queued but never typed input
+        streamer.send(vec![b'c'; PASTE_STREAM_THRESHOLD + 1]).unwrap();
+        streamer.cancel();
+        while streamer.is_streaming() {
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        let contents = buffer.contents();
+        let typed = contents.windows(5).position(|w| w == b"typed").unwrap();
+        assert!(contents[..typed].iter().all(|&b| b == b'a'));
+        assert!(!contents.contains(&b'c'));
+        // Streams sent before the cancel may or may not have finished
+        assert!(contents[typed + 5..].is_empty() || contents[typed + 5..] == *b"second");
+    }
+}
@@ -0,0 +1,353 @@
+// src/pointer.rs
+//! Mouse pointer state and URL detection
+//!
+//! The pointer is an I-beam over text, a hand over links, and hidden while
+//! the user types. Backends feed key presses and motion into
+//! [`PointerState`] and apply the resulting [`PointerShape`] through
+//! `UIRenderer::set_pointer_shape`.
+//!
+//! Applications with mouse support may request a shape with OSC 22. Names
+//! outside [`APP_POINTER_SHAPES`] (or their X11 cursor-font aliases) are
+//! ignored; the requested shape shows wherever the terminal has no shape of
+//! its own, i.e. except over links and while typing.
+//!
+//! Link previews are debounced by [`HoverTracker`]: once the pointer has
+//! rested on a link for `TerminalConfig::hover_delay_ms` the backend emits
+//! [`TerminalEvent::HoverUrl`], and [`TerminalEvent::HoverUrlCancelled`] when
+//! the pointer leaves it, so embedders can show a status-bar preview or
+//! tooltip.
+
+use crate::events::TerminalEvent;
+use crate::traits::PointerShape;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+/// CSS cursor names applications may request with OSC 22
+pub const APP_POINTER_SHAPES: &[&str] = &[
+    "default", "text", "vertical-text", "pointer", "crosshair", "cell", "move", "grab", "grabbing",
+    "help", "wait", "progress", "not-allowed", "no-drop", "copy", "alias", "context-menu",
+    "e-resize", "w-resize", "n-resize", "s-resize", "ne-resize", "nw-resize", "se-resize", "sw-resize",
+    "ew-resize", "ns-resize", "col-resize", "row-resize", "all-scroll", "zoom-in", "zoom-out",
+];
+
+/// X11 cursor-font names sent by xterm-era applications, with their CSS names
+const X11_POINTER_ALIASES: &[(&str, &str)] = &[
+    ("left_ptr", "default"),
+    ("xterm", "text"),
+    ("hand2", "pointer"),
+    ("hand1", "pointer"),
+    ("watch", "wait"),
+    ("fleur", "move"),
+    ("question_arrow", "help"),
+    ("crosshair", "crosshair"),
+    ("sb_h_double_arrow", "ew-resize"),
+    ("sb_v_double_arrow", "ns-resize"),
+];
+
+/// Allowlisted CSS name for an OSC 22 request, if the name is known
+pub fn app_pointer_shape(name: &str) -> Option<&'static str> {
+    APP_POINTER_SHAPES
+        .iter()
+        .copied()
+        .find(|&shape| shape == name)
+        .or_else(|| X11_POINTER_ALIASES.iter().find(|(alias, _)| *alias == name).map(|&(_, shape)| shape))
+}
+
+/// URL schemes recognized in plain terminal output
+const URL_SCHEMES: &[&str] = &["https://", "http://", "ftp://", "file://", "mailto:"];
+
+/// Pointer visibility and hover state
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PointerState {
+    hidden: bool,
+    over_link: bool,
+    /// Shape requested with OSC 22
+    requested: Option<&'static str>,
+}
+
+impl PointerState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current shape
+    pub fn shape(&self) -> PointerShape {
+        if self.hidden {
+            PointerShape::Hidden
+        } else if self.over_link {
+            PointerShape::Link
+        } else if let Some(name) = self.requested {
+            PointerShape::Named(name)
+        } else {
+            PointerShape::Text
+        }
+    }
+
+    /// The application requested a shape (`None`: the default); returns true
+    /// if the shape changed
+    pub fn request(&mut self, shape: Option<&'static str>) -> bool {
+        self.update(|state| state.requested = shape)
+    }
+
+    /// A key was typed; returns true if the shape changed
+    pub fn key_typed(&mut self) -> bool {
+        self.update(|state| state.hidden = true)
+    }
+
+    /// The pointer moved over a link (or not); returns true if the shape changed
+    pub fn moved(&mut self, over_link: bool) -> bool {
+        self.update(|state| {
+            state.hidden = false;
+            state.over_link = over_link;
+        })
+    }
+
+    fn update(&mut self, change: impl FnOnce(&mut Self)) -> bool {
+        let before = self.shape();
+        change(self);
+        self.shape() != before
+    }
+}
+
+/// Link under the pointer and the screen cells it covers
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HoveredLink {
+    /// OSC 8 target or detected URL
+    pub uri: String,
+    /// Screen row
+    pub row: usize,
+    /// Columns covered by the link, end exclusive
+    pub cols: Range<usize>,
+}
+
+impl HoveredLink {
+    fn event(&self) -> TerminalEvent {
+        TerminalEvent::HoverUrl {
+            uri: self.uri.clone(),
+            row: self.row,
+            start_col: self.cols.start,
+            end_col: self.cols.end,
+        }
+    }
+}
+
+/// Debounces link hover into `HoverUrl` / `HoverUrlCancelled` events
+#[derive(Clone, Debug)]
+pub struct HoverTracker {
+    delay: Duration,
+    /// Link under the pointer that has not been reported yet, and since when
+    pending: Option<(HoveredLink, Instant)>,
+    /// Link last reported with `HoverUrl`
+    shown: Option<HoveredLink>,
+}
+
+impl HoverTracker {
+    /// Tracker reporting a link once the pointer has rested on it for `delay`
+    pub fn new(delay: Duration) -> Self {
+        Self { delay, pending: None, shown: None }
+    }
+
+    /// The pointer moved onto `link`, or off any link
+    ///
+    /// Returns `HoverUrlCancelled` when this leaves the reported link.
+    /// Moving within one link neither restarts the delay nor cancels.
+    pub fn moved(&mut self, link: Option<HoveredLink>, now: Instant) -> Option<TerminalEvent> {
+        if link.is_some() && link == self.shown {
+            self.pending = None;
+            return None;
+        }
+        if link.is_some() && link.as_ref() == self.pending.as_ref().map(|(pending, _)| pending) {
+            return None;
+        }
+        self.pending = link.map(|link| (link, now));
+        self.shown.take().map(|_| TerminalEvent::HoverUrlCancelled)
+    }
+
+    /// The pointer left the terminal
+    pub fn left(&mut self) -> Option<TerminalEvent> {
+        self.moved(None, Instant::now())
+    }
+
+    /// When the pending link is due, for the backend's timer
+    pub fn deadline(&self) -> Option<Instant> {
+        self.pending.as_ref().map(|(_, since)| *since + self.delay)
+    }
+
+    /// `HoverUrl` for the pending link once its delay has passed
+    pub fn poll(&mut self, now: Instant) -> Option<TerminalEvent> {
+        if self.deadline()? > now {
+            return None;
+        }
+        let (link, _) = self.pending.take()?;
+        let event = link.event();
+        self.shown = Some(link);
+        Some(event)
+    }
+}
+
+/// Column range of the URL covering `col` in a row of characters, if any
+///
+/// Trailing sentence punctuation and unbalanced closing brackets are not
+/// part of the URL, so `(see https://example.com).` yields just the address.
+pub fn find_url_at(line: &[char], col: usize) -> Option<Range<usize>> {
+    let mut start = 0;
+    while start < line.len() {
+        let Some(scheme) = URL_SCHEMES.iter().find(|s| starts_with(&line[start..], s)) else {
+            start += 1;
+            continue;
+        };
+        let body = start + scheme.chars().count();
+        let end = url_end(line, body);
+        if end > body && (start..end).contains(&col) {
+            return Some(start..end);
+        }
+        if start > col {
+            return None;
+        }
+        start = end.max(start + 1);
+    }
+    None
+}
+
+fn starts_with(line: &[char], prefix: &str) -> bool {
+    let mut chars = line.iter();
+    prefix.chars().all(|p| chars.next().is_some_and(|c| c.eq_ignore_ascii_case(&p)))
+}
+
+/// End (exclusive) of the URL whose body starts at `body`
+fn url_end(line: &[char], body: usize) -> usize {
+    let mut end = body;
+    while end < line.len() && is_url_char(line[end]) {
+        end += 1;
+    }
+
+    // Drop trailing punctuation and closing brackets without an opener
+    while end > body {
+        let last = line[end - 1];
+        let unbalanced = match last {
+            ')' => count(&line[body..end], '(') < count(&line[body..end], ')'),
+            ']' => count(&line[body..end], '[') < count(&line[body..end], ']'),
+            _ => false,
+        };
+        if matches!(last, '.' | ',' | ';' | ':' | '!' | '?' | '\'') || unbalanced {
+            end -= 1;
+        } else {
+            break;
+        }
+    }
+    end
+}
+
+fn is_url_char(c: char) -> bool {
+    !c.is_whitespace() && !c.is_control() && !matches!(c, '<' | '>' | '"' | '`' | '{' | '}' | '|' | '\\' | '^')
+}
+
+fn count(chars: &[char], target: char) -> usize {
+    chars.iter().filter(|&&c| c == target).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    #[test]
+    fn test_pointer_hides_while_typing() {
+        let mut pointer = PointerState::new();
+        assert_eq!(pointer.shape(), PointerShape::Text);
+        assert!(pointer.key_typed());
+        assert!(!pointer.key_typed());
+        assert_eq!(pointer.shape(), PointerShape::Hidden);
+
+        assert!(pointer.moved(true));
+        assert_eq!(pointer.shape(), PointerShape::Link);
+        assert!(!pointer.moved(true));
+        assert!(pointer.moved(false));
+        assert_eq!(pointer.shape(), PointerShape::Text);
+    }
+
+    fn link(uri: &str, cols: Range<usize>) -> Option<HoveredLink> {
+        Some(HoveredLink { uri: uri.to_string(), row: 0, cols })
+    }
+
+    #[test]
+    fn test_hover_is_debounced() {
+        let mut hover = HoverTracker::new(Duration::from_millis(300));
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        assert_eq!(hover.moved(link("https://a", 0..9), at(0)), None);
+        assert_eq!(hover.poll(at(200)), None);
+        // Moving within the link keeps the timer running
+        assert_eq!(hover.moved(link("https://a", 0..9), at(250)), None);
+        assert_eq!(hover.deadline(), Some(at(300)));
+        assert_eq!(
+            hover.poll(at(300)),
+            Some(TerminalEvent::HoverUrl { uri: "https://a".to_string(), row: 0, start_col: 0, end_col: 9 })
+        );
+        assert_eq!(hover.poll(at(900)), None);
+        assert_eq!(hover.moved(link("https://a", 0..9), at(950)), None);
+
+        // Leaving the link cancels the preview
+        assert_eq!(hover.moved(None, at(1000)), Some(TerminalEvent::HoverUrlCancelled));
+        assert_eq!(hover.deadline(), None);
+    }
+
+    #[test]
+    fn test_hover_passing_over_links_reports_nothing() {
+        let mut hover = HoverTracker::new(Duration::from_millis(300));
+        let start = Instant::now();
+        assert_eq!(hover.moved(link("https://a", 0..9), start), None);
+        assert_eq!(hover.moved(None, start + Duration::from_millis(100)), None);
+        assert_eq!(hover.poll(start + Duration::from_secs(1)), None);
+
+        // Going straight from one reported link to another cancels the first
+        hover.moved(link("https://a", 0..9), start);
+        hover.poll(start + Duration::from_millis(300));
+        assert_eq!(hover.moved(link("https://b", 10..19), start), Some(TerminalEvent::HoverUrlCancelled));
+        assert_eq!(hover.left(), None);
+    }
+
+    #[test]
+    fn test_requested_shape_and_allowlist() {
+        assert_eq!(app_pointer_shape("pointer"), Some("pointer"));
+        assert_eq!(app_pointer_shape("hand2"), Some("pointer"));
+        assert_eq!(app_pointer_shape("url(evil.png)"), None);
+        assert_eq!(app_pointer_shape("Pointer"), None);
+
+        let mut pointer = PointerState::new();
+        assert!(pointer.request(Some("crosshair")));
+        assert_eq!(pointer.shape(), PointerShape::Named("crosshair"));
+        // Links and typing still take precedence
+        pointer.moved(true);
+        assert_eq!(pointer.shape(), PointerShape::Link);
+        pointer.key_typed();
+        assert_eq!(pointer.shape(), PointerShape::Hidden);
+        pointer.moved(false);
+        assert!(pointer.request(None));
+        assert_eq!(pointer.shape(), PointerShape::Text);
+    }
+
+    #[test]
+    fn test_find_url_at() {
+        let line = chars("see https://example.com/a?b=1 now");
+        assert_eq!(find_url_at(&line, 4), Some(4..29));
+        assert_eq!(find_url_at(&line, 28), Some(4..29));
+        assert_eq!(find_url_at(&line, 29), None);
+        assert_eq!(find_url_at(&line, 0), None);
+        assert_eq!(find_url_at(&chars("HTTP://X.ORG"), 0), Some(0..12));
+        assert_eq!(find_url_at(&chars("https:// alone"), 0), None);
+    }
+
+    #[test]
+    fn test_find_url_trims_punctuation() {
+        let line = chars("(see https://en.wikipedia.org/wiki/Rust_(language)).");
+        assert_eq!(find_url_at(&line, 10), Some(5..50));
+        let line = chars("mail mailto:dev@example.com, or file:///tmp/x.");
+        assert_eq!(find_url_at(&line, 5), Some(5..27));
+        assert_eq!(find_url_at(&line, 40), Some(32..45));
+    }
+}
@@ -0,0 +1,217 @@
+// src/predict.rs
+//! Local echo prediction for high-latency links
+//!
+//! Over a slow transport such as SSH, each keystroke takes a round trip
+//! before the shell's echo appears. With `TerminalConfig::local_echo` set,
+//! typed printable characters are drawn right away, underlined, where the
+//! echo is expected. PTY output then settles each prediction once the cursor
+//! moves past it: the cell holding the predicted character confirms it,
+//! anything else is a misprediction that drops every pending prediction and
+//! pauses display for a while. Predictions never echoed (password prompts)
+//! expire the same way: they stop being shown once they are older than
+//! `PREDICTION_TIMEOUT`, even if no output arrives to settle them.
+//!
+//! The grid does not predict in full-screen applications: on the alternate
+//! screen, with application cursor keys, or while the cursor is hidden.
+
+use std::time::{Duration, Instant};
+
+/// Echo delay above which `LocalEcho::Adaptive` shows predictions
+const DISPLAY_LATENCY: Duration = Duration::from_millis(30);
+/// Age at which an unconfirmed prediction counts as mispredicted
+const PREDICTION_TIMEOUT: Duration = Duration::from_secs(2);
+/// How long predictions stay hidden after a misprediction
+const MISPREDICTION_PAUSE: Duration = Duration::from_secs(1);
+
+/// When typed characters are echoed locally
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum LocalEcho {
+    #[default]
+    Off,
+    /// Show predictions once echoes are measured to lag behind keystrokes
+    Adaptive,
+    /// Show predictions for every keystroke
+    Always,
+}
+
+/// A typed character awaiting its echo
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Prediction {
+    pub row: usize,
+    pub col: usize,
+    pub ch: char,
+    sent: Instant,
+}
+
+/// Pending predictions and the measured echo delay
+#[derive(Clone, Debug, Default)]
+pub struct EchoPredictor {
+    predictions: Vec<Prediction>,
+    /// Smoothed delay between a keystroke and its echo
+    srtt: Option<Duration>,
+    /// Display is paused after a misprediction until this time
+    paused_until: Option<Instant>,
+}
+
+impl EchoPredictor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Predict the echo of `text` typed with the cursor at `cursor`
+    ///
+    /// Characters follow any pending predictions on the same row. Control
+    /// characters make the outcome unknowable and drop all predictions;
+    /// nothing is predicted past the last of `cols` columns or for
+    /// characters that are not plain ASCII.
+    pub fn predict(&mut self, text: &str, cursor: (usize, usize), cols: usize, now: Instant) {
+        let (row, mut col) = match self.predictions.last() {
+            Some(last) => (last.row, last.col + 1),
+            None => cursor,
+        };
+        for ch in text.chars() {
+            if !(ch.is_ascii_graphic() || ch == ' ') {
+                self.clear();
+                return;
+            }
+            if col >= cols {
+                return;
+            }
+            self.predictions.push(Prediction { row, col, ch, sent: now });
+            col += 1;
+        }
+    }
+
+    /// Settle predictions against the screen after output was processed
+    ///
+    /// `cell` returns the character at a row and column.
+    pub fn reconcile(&mut self, cursor: (usize, usize), cell: impl Fn(usize, usize) -> Option<char>, now: Instant) {
+        while let Some(p) = self.predictions.first().copied() {
+            if cursor <= (p.row, p.col) {
+                if now.saturating_duration_since(p.sent) >= PREDICTION_TIMEOUT {
+                    self.mispredicted(now);
+                }
+                return;
+            }
+            if cell(p.row, p.col) != Some(p.ch) {
+                self.mispredicted(now);
+                return;
+            }
+            let sample = now.saturating_duration_since(p.sent);
+            self.srtt = Some(match self.srtt {
+                Some(srtt) => (srtt * 7 + sample) / 8,
+                None => sample,
+            });
+            self.predictions.remove(0);
+        }
+    }
+
+    /// Predictions to draw under `mode`; none once the oldest has expired
+    pub fn visible(&self, mode: LocalEcho, now: Instant) -> &[Prediction] {
+        let paused = self.paused_until.is_some_and(|until| now < until);
+        let shown = match mode {
+            LocalEcho::Off => false,
+            LocalEcho::Adaptive => self.srtt.is_some_and(|srtt| srtt >= DISPLAY_LATENCY),
+            LocalEcho::Always => true,
+        };
+        let expired = self.predictions.first().is_some_and(|p| now.saturating_duration_since(p.sent) >= PREDICTION_TIMEOUT);
+        if shown && !paused && !expired { &self.predictions } else { &[] }
+    }
+
+    /// Time until the predictions drawn under `mode` expire, while any are
+    pub fn expires_in(&self, mode: LocalEcho, now: Instant) -> Option<Duration> {
+        let oldest = self.visible(mode, now).first()?;
+        Some((oldest.sent + PREDICTION_TIMEOUT).saturating_duration_since(now))
+    }
+
+    /// Smoothed echo delay, once an echo was confirmed
+    pub fn echo_delay(&self) -> Option<Duration> {
+        self.srtt
+    }
+
+    /// Drop all pending predictions
+    pub fn clear(&mut self) {
+        self.predictions.clear();
+    }
+
+    fn mispredicted(&mut self, now: Instant) {
+        self.clear();
+        self.paused_until = Some(now + MISPREDICTION_PAUSE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screen(line: &'static str) -> impl Fn(usize, usize) -> Option<char> {
+        move |row, col| if row == 0 { line.chars().nth(col) } else { None }
+    }
+
+    #[test]
+    fn test_echo_confirms_predictions() {
+        let start = Instant::now();
+        let mut predictor = EchoPredictor::new();
+        predictor.predict("ls", (0, 2), 80, start);
+        predictor.predict(" -l", (0, 2), 80, start);
+        let cols: Vec<_> = predictor.visible(LocalEcho::Always, start).iter().map(|p| (p.col, p.ch)).collect();
+        assert_eq!(cols, vec![(2, 'l'), (3, 's'), (4, ' '), (5, '-'), (6, 'l')]);
+
+        // A partial echo confirms what the cursor moved past
+        let later = start + Duration::from_millis(80);
+        predictor.reconcile((0, 4), screen("$ ls"), later);
+        assert_eq!(predictor.visible(LocalEcho::Always, later).len(), 3);
+        assert_eq!(predictor.echo_delay(), Some(Duration::from_millis(80)));
+        // The delay is high enough for adaptive display
+        assert_eq!(predictor.visible(LocalEcho::Adaptive, later).len(), 3);
+        assert!(predictor.visible(LocalEcho::Off, later).is_empty());
+
+        predictor.reconcile((0, 7), screen("$ ls -l"), later);
+        assert!(predictor.visible(LocalEcho::Always, later).is_empty());
+    }
+
+    #[test]
+    fn test_misprediction_pauses_display() {
+        let start = Instant::now();
+        let mut predictor = EchoPredictor::new();
+        predictor.predict("ab", (0, 0), 80, start);
+        predictor.reconcile((0, 1), screen("*"), start);
+        assert!(predictor.visible(LocalEcho::Always, start).is_empty());
+
+        predictor.predict("c", (0, 1), 80, start);
+        assert!(predictor.visible(LocalEcho::Always, start).is_empty());
+        assert_eq!(predictor.visible(LocalEcho::Always, start + MISPREDICTION_PAUSE).len(), 1);
+
+        // Never echoed: expires
+        let expired = start + PREDICTION_TIMEOUT;
+        predictor.reconcile((0, 1), screen("*"), expired);
+        assert!(predictor.visible(LocalEcho::Always, expired + MISPREDICTION_PAUSE).is_empty());
+    }
+
+    #[test]
+    fn test_unechoed_predictions_expire_without_output() {
+        let start = Instant::now();
+        let mut predictor = EchoPredictor::new();
+        predictor.predict("hunter2", (0, 10), 80, start);
+        let later = start + Duration::from_millis(500);
+        assert_eq!(predictor.visible(LocalEcho::Always, later).len(), 7);
+        assert_eq!(predictor.expires_in(LocalEcho::Always, later), Some(PREDICTION_TIMEOUT - Duration::from_millis(500)));
+
+        // No reconcile: the password prompt echoes nothing
+        let expired = start + PREDICTION_TIMEOUT;
+        assert!(predictor.visible(LocalEcho::Always, expired).is_empty());
+        assert_eq!(predictor.expires_in(LocalEcho::Always, expired), None);
+    }
+
+    #[test]
+    fn test_unpredictable_input() {
+        let now = Instant::now();
+        let mut predictor = EchoPredictor::new();
+        predictor.predict("abc", (0, 78), 80, now);
+        assert_eq!(predictor.visible(LocalEcho::Always, now).len(), 2);
+        predictor.predict("\r", (0, 78), 80, now);
+        assert!(predictor.visible(LocalEcho::Always, now).is_empty());
+        predictor.predict("é", (0, 0), 80, now);
+        assert!(predictor.visible(LocalEcho::Always, now).is_empty());
+    }
+}
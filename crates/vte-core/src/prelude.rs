@@ -0,0 +1,46 @@
+// src/prelude.rs
+//! The types an embedder needs, for a glob import
+//!
+//! `use vte_core::prelude::*;` brings in the terminal core and its
+//! configuration, themes, events and errors, the input helpers, the
+//! read-only [`GridView`] of the grid and the backend traits, and nothing
+//! else. Backend crates
+//! re-export this prelude instead of the whole crate, so their users see one
+//! curated surface without constants or test backends. Everything else stays
+//! reachable through `vte_core` and its modules.
+//!
+//! `tests/public_api.rs` compares the prelude and the crate root against a
+//! checked-in snapshot, so additions and removals show up in review.
+
+// Core and configuration
+pub use crate::bell::{BellAction, BellPolicy, VisualBellStyle};
+pub use crate::color_env::ColorPolicy;
+pub use crate::config::TerminalConfig;
+pub use crate::locale::LocalePolicy;
+pub use crate::security::{SecurityConfig, TitlePolicy};
+pub use crate::terminal::{ResizeHandle, VteTerminalCore};
+pub use crate::width::{AmbiguousWidth, EmojiWidth};
+
+// Themes
+pub use crate::ansi::Color;
+pub use crate::theme::{ColorScheme, Theme, ThemePair};
+
+// Events and errors
+pub use crate::error::{ErrorSeverity, TerminalError, TerminalResult};
+pub use crate::events::TerminalEvent;
+
+// Input sent to the child
+pub use crate::broadcast::InputSink;
+pub use crate::keys::Key;
+pub use crate::paste::PasteStreamer;
+pub use crate::text_input::{encode_text, TextSource};
+
+// Reading the screen
+pub use crate::ansi::Cell;
+pub use crate::cursor::CursorPosition;
+pub use crate::grid_view::GridView;
+pub use crate::selection::SelectionSpan;
+pub use crate::snapshots::ScreenSnapshot;
+
+// Backend traits
+pub use crate::traits::{Backend, BackendError, CursorShape, EventLoop, InputHandler, PointerShape, Renderer};
@@ -0,0 +1,97 @@
+// src/regions.rs
+//! Active regions: clickable buttons drawn by the application
+//!
+//! A hugovte extension for simple TUI buttons. `OSC 7777 ; payload ST`
+//! starts a region: the cells written after it, up to `OSC 7777 ; ST`, form a
+//! button, styled with ordinary SGR like any other text. Clicking it sends
+//! `OSC 7777 ; payload ST` to the application's standard input. Cells carry
+//! a region id (`Cell::region`) resolved through a table, as for OSC 8
+//! hyperlinks, bounded at `MAX_ACTIVE_REGIONS` payloads the same way. An
+//! open region ends at RIS and when the screen is switched.
+//!
+//! Anything the application can make the user click ends up as input, so
+//! regions are ignored unless `SecurityConfig::allow_active_regions` is set,
+//! and payloads are limited to [`MAX_ACTIVE_REGION_PAYLOAD`] bytes of
+//! printable text. See docs/ACTIVE_REGIONS.md.
+
+use crate::constants::MAX_ACTIVE_REGION_PAYLOAD;
+
+/// OSC number of the extension
+pub const ACTIVE_REGION_OSC: &str = "7777";
+
+/// Id stored in `Cell::region` for cells outside any region
+pub const NO_REGION: u32 = 0;
+
+/// Whether `payload` may define a region
+pub fn valid_payload(payload: &str) -> bool {
+    !payload.is_empty() && payload.len() <= MAX_ACTIVE_REGION_PAYLOAD && !payload.chars().any(char::is_control)
+}
+
+/// Input reporting a click on the region defined with `payload`
+pub fn click_report(payload: &str) -> String {
+    format!("\x1B]{};{}\x1B\\", ACTIVE_REGION_OSC, payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::AnsiParser;
+    use crate::config::TerminalConfig;
+    use crate::constants::MAX_ACTIVE_REGIONS;
+    use crate::grid::Grid;
+    use crate::security::SecurityConfig;
+    use std::sync::Arc;
+
+    fn grid(allow: bool) -> Grid {
+        let security = SecurityConfig { allow_active_regions: allow, ..SecurityConfig::default() };
+        Grid::new(20, 2, Arc::new(TerminalConfig::default().with_security(security)))
+    }
+
+    #[test]
+    fn test_regions_report_clicks() {
+        let mut grid = grid(true);
+        AnsiParser::new().feed_str("[\x1b]7777;save:1\x07Save\x1b]7777;\x1b\\] [\x1b]7777;quit\x07Quit\x1b]7777;\x07]", &mut grid);
+        assert_eq!(grid.active_region_at(0, 1), Some("save:1"));
+        assert_eq!(grid.active_region_at(0, 5), None);
+        assert_eq!(grid.active_region_at(0, 8), Some("quit"));
+        assert_eq!(grid.click_active_region(0, 4).as_deref(), Some("\x1b]7777;save:1\x1b\\"));
+        assert_eq!(grid.click_active_region(0, 0), None);
+    }
+
+    #[test]
+    fn test_open_regions_end_at_reset_and_screen_switch() {
+        let mut grid = grid(true);
+        let mut parser = AnsiParser::new();
+        parser.feed_str("\x1b]7777;a\x07\x1b[?1049hx", &mut grid);
+        assert_eq!(grid.active_region_at(0, 0), None);
+        parser.feed_str("\x1b]7777;b\x07\x1b[?1049ly", &mut grid);
+        assert_eq!(grid.active_region_at(0, 0), None);
+        parser.feed_str("\x1b]7777;c\x07\x1bcz", &mut grid);
+        assert_eq!(grid.active_region_at(0, 0), None);
+    }
+
+    #[test]
+    fn test_regions_no_cell_refers_to_are_dropped() {
+        let mut grid = grid(true);
+        let mut parser = AnsiParser::new();
+        parser.feed_str("\x1b[2H\x1b]7777;kept\x07k\x1b]7777;\x07\x1b[H", &mut grid);
+        for i in 0..MAX_ACTIVE_REGIONS * 2 {
+            parser.feed_str(&format!("\x1b]7777;button {}\x07x\x1b]7777;\x07\r", i), &mut grid);
+        }
+        let last = format!("button {}", MAX_ACTIVE_REGIONS * 2 - 1);
+        assert_eq!(grid.active_region_at(0, 0), Some(last.as_str()));
+        assert_eq!(grid.active_region_at(1, 0), Some("kept"));
+    }
+
+    #[test]
+    fn test_regions_need_opt_in() {
+        let mut grid = grid(false);
+        AnsiParser::new().feed_str("\x1b]7777;rm -rf ~\x07Click me\x1b]7777;\x07", &mut grid);
+        assert_eq!(grid.click_active_region(0, 0), None);
+
+        assert!(valid_payload("open 3"));
+        assert!(!valid_payload(""));
+        assert!(!valid_payload("a\rb"));
+        assert!(!valid_payload(&"x".repeat(MAX_ACTIVE_REGION_PAYLOAD + 1)));
+    }
+}
@@ -0,0 +1,147 @@
+// src/remote.rs
+//! Cursors and selections of remote collaborators
+//!
+//! Pair-programming embedders show where other participants are in the same
+//! terminal. They feed each participant's position (and optional selection)
+//! to the grid with `Grid::set_remote_cursor`; renderers draw the live ones
+//! in an overlay pass. A participant that stops sending updates for
+//! `TerminalConfig::remote_cursor_timeout_ms` disappears. Transport and
+//! identity are the embedder's concern: the core only keeps the registry.
+
+use crate::ansi::Color;
+use crate::selection::{Selection, SelectionSpan};
+use std::time::{Duration, Instant};
+
+/// One participant's cursor, in screen coordinates
+#[derive(Clone, Debug, PartialEq)]
+pub struct RemoteCursor {
+    /// Embedder-chosen participant id
+    pub id: u64,
+    pub row: usize,
+    pub col: usize,
+    pub color: Color,
+    /// Selected range, start and end inclusive, in either order
+    pub selection: Option<((usize, usize), (usize, usize))>,
+    updated: Instant,
+}
+
+impl RemoteCursor {
+    /// Highlight spans of the selection on a `rows` x `cols` screen
+    pub fn selection_spans(&self, rows: usize, cols: usize) -> Vec<SelectionSpan> {
+        let Some((a, b)) = self.selection else {
+            return Vec::new();
+        };
+        let ((start_row, start_col), (end_row, end_col)) = (a.min(b), a.max(b));
+        let mut selection = Selection::new();
+        selection.create_selection(start_row, start_col, end_row, end_col);
+        selection.spans(rows, cols)
+    }
+}
+
+/// Registry of remote cursors, expiring silent ones
+#[derive(Clone, Debug, Default)]
+pub struct RemoteCursors {
+    cursors: Vec<RemoteCursor>,
+}
+
+impl RemoteCursors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Place participant `id`'s cursor at `pos`, adding it if new
+    pub fn set_cursor(&mut self, id: u64, pos: (usize, usize), color: Color, now: Instant) {
+        let (row, col) = pos;
+        match self.cursors.iter_mut().find(|cursor| cursor.id == id) {
+            Some(cursor) => {
+                (cursor.row, cursor.col, cursor.color, cursor.updated) = (row, col, color, now);
+            }
+            None => self.cursors.push(RemoteCursor { id, row, col, color, selection: None, updated: now }),
+        }
+    }
+
+    /// Set or clear participant `id`'s selection; returns false if `id` has no cursor
+    pub fn set_selection(&mut self, id: u64, selection: Option<((usize, usize), (usize, usize))>, now: Instant) -> bool {
+        let Some(cursor) = self.cursors.iter_mut().find(|cursor| cursor.id == id) else {
+            return false;
+        };
+        cursor.selection = selection;
+        cursor.updated = now;
+        true
+    }
+
+    /// Drop participant `id`; returns false if it had no cursor
+    pub fn remove(&mut self, id: u64) -> bool {
+        let before = self.cursors.len();
+        self.cursors.retain(|cursor| cursor.id != id);
+        self.cursors.len() != before
+    }
+
+    /// Drop cursors not updated within `timeout`; returns whether any were dropped
+    pub fn expire(&mut self, now: Instant, timeout: Duration) -> bool {
+        let before = self.cursors.len();
+        self.cursors.retain(|cursor| now.saturating_duration_since(cursor.updated) < timeout);
+        self.cursors.len() != before
+    }
+
+    /// Cursors updated within `timeout`, in the order they were added
+    pub fn live(&self, now: Instant, timeout: Duration) -> impl Iterator<Item = &RemoteCursor> {
+        self.cursors.iter().filter(move |cursor| now.saturating_duration_since(cursor.updated) < timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::config::TerminalConfig;
+    use crate::grid::Grid;
+    use std::sync::Arc;
+
+    const RED: Color = Color { r: 1.0, g: 0.0, b: 0.0, a: 1.0 };
+    const BLUE: Color = Color { r: 0.0, g: 0.0, b: 1.0, a: 1.0 };
+
+    #[test]
+    fn test_registry_updates_and_expires() {
+        let start = Instant::now();
+        let timeout = Duration::from_secs(10);
+        let mut cursors = RemoteCursors::new();
+        cursors.set_cursor(1, (0, 0), RED, start);
+        cursors.set_cursor(2, (1, 4), BLUE, start);
+        assert!(cursors.set_selection(2, Some(((1, 4), (0, 2))), start));
+        assert!(!cursors.set_selection(3, None, start));
+
+        // Updating a cursor keeps it alive
+        let later = start + Duration::from_secs(8);
+        cursors.set_cursor(1, (0, 5), RED, later);
+        let live: Vec<_> = cursors.live(start + timeout, timeout).map(|c| (c.id, c.col)).collect();
+        assert_eq!(live, vec![(1, 5)]);
+        assert!(cursors.expire(start + timeout, timeout));
+        assert!(!cursors.expire(start + timeout, timeout));
+        assert!(cursors.remove(1));
+        assert_eq!(cursors.live(start, timeout).count(), 0);
+    }
+
+    #[test]
+    fn test_remote_selection_spans() {
+        let mut cursors = RemoteCursors::new();
+        let now = Instant::now();
+        cursors.set_cursor(7, (1, 1), BLUE, now);
+        cursors.set_selection(7, Some(((1, 5), (0, 2))), now);
+        let cursor = cursors.live(now, Duration::from_secs(1)).next().unwrap();
+        let spans: Vec<_> = cursor.selection_spans(3, 8).iter().map(|s| (s.row, s.start_col, s.end_col)).collect();
+        assert_eq!(spans, vec![(0, 2, 8), (1, 0, 6)]);
+    }
+
+    #[test]
+    fn test_grid_hides_silent_participants() {
+        let clock = MockClock::new();
+        let config = TerminalConfig { remote_cursor_timeout_ms: 1000, ..TerminalConfig::default() };
+        let mut grid = Grid::new(10, 3, Arc::new(config));
+        grid.set_clock(Arc::new(clock.clone()));
+        grid.set_remote_cursor(1, (2, 3), RED);
+        assert_eq!(grid.remote_cursors().len(), 1);
+        clock.advance(Duration::from_millis(1000));
+        assert!(grid.remote_cursors().is_empty());
+    }
+}
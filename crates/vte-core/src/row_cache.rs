@@ -0,0 +1,215 @@
+// src/row_cache.rs
+//! Per-row render cache keyed by row content
+//!
+//! Most frames of an editor or pager repaint the same rows. A backend keeps a
+//! [`RowCache`] across frames and stores each row's rendered result (a Cairo
+//! surface, a shaped layout) under [`row_key`], a hash of everything that
+//! affects the row's pixels: characters, attributes and the colors the theme
+//! resolves them to. Unchanged rows are then copied instead of redrawn.
+//! Identical rows, such as blank ones, share a single entry.
+//!
+//! The cache is swept per frame: entries not used between
+//! [`RowCache::begin_frame`] and [`RowCache::end_frame`] are dropped, so it
+//! holds little more than one screenful.
+
+use crate::ansi::{Cell, Color};
+use crate::config::TerminalConfig;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+/// Content hash of a row of cells as `config` renders them
+pub fn row_key(cells: &[Cell], config: &TerminalConfig) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    let hash_color = |color: Color, hasher: &mut DefaultHasher| {
+        for channel in [color.r, color.g, color.b, color.a] {
+            channel.to_bits().hash(hasher);
+        }
+    };
+
+    config.decoration_scale().to_bits().hash(&mut hasher);
+    for cell in cells {
+        (cell.ch, cell.combining, cell.wide, cell.spacer).hash(&mut hasher);
+        hash_color(config.resolve_fg(cell), &mut hasher);
+        hash_color(config.resolve_bg(cell), &mut hasher);
+        (cell.bold, cell.italic, cell.dim, cell.font).hash(&mut hasher);
+        (cell.underline, cell.underline_style as u8, cell.strikethrough, cell.overline).hash(&mut hasher);
+        if let Some(color) = config.resolve_decoration(cell) {
+            hash_color(color, &mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+#[derive(Debug)]
+struct Entry<T> {
+    value: T,
+    used: bool,
+}
+
+/// Rendered rows by [`row_key`], kept while they stay on screen
+#[derive(Debug)]
+pub struct RowCache<T> {
+    entries: HashMap<u64, Entry<T>>,
+    layout: Option<u64>,
+    hits: usize,
+    misses: usize,
+}
+
+impl<T> Default for RowCache<T> {
+    fn default() -> Self {
+        Self { entries: HashMap::new(), layout: None, hits: 0, misses: 0 }
+    }
+}
+
+impl<T> RowCache<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a frame drawn with `layout`, a hash of the cell geometry and
+    /// anything else outside the cells that changes the rendered pixels
+    ///
+    /// A new layout discards every entry.
+    pub fn begin_frame(&mut self, layout: u64) {
+        if self.layout != Some(layout) {
+            self.entries.clear();
+            self.layout = Some(layout);
+        }
+        for entry in self.entries.values_mut() {
+            entry.used = false;
+        }
+        self.hits = 0;
+        self.misses = 0;
+    }
+
+    /// The row rendered for `key`, rendering it with `render` on a miss
+    ///
+    /// Returns `None` if `render` fails; nothing is cached then.
+    pub fn get_or_render(&mut self, key: u64, render: impl FnOnce() -> Option<T>) -> Option<&T> {
+        if self.entries.contains_key(&key) {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+            self.entries.insert(key, Entry { value: render()?, used: false });
+        }
+        let entry = self.entries.get_mut(&key)?;
+        entry.used = true;
+        Some(&entry.value)
+    }
+
+    /// Finish the frame, dropping rows it did not draw
+    pub fn end_frame(&mut self) {
+        self.entries.retain(|_, entry| entry.used);
+    }
+
+    /// Rows served from the cache in the current frame
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// Rows rendered afresh in the current frame
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    /// Number of cached rows
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Drop every entry, e.g. after a font change
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.layout = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::AnsiParser;
+    use crate::grid::Grid;
+    use std::sync::Arc;
+
+    fn screen(input: &str, config: &TerminalConfig) -> Grid {
+        let mut grid = Grid::new(6, 3, Arc::new(config.clone()));
+        AnsiParser::new().feed_str(input, &mut grid);
+        grid
+    }
+
+    fn keys(grid: &Grid, config: &TerminalConfig) -> Vec<u64> {
+        grid.cells.iter_rows().map(|row| row_key(row, config)).collect()
+    }
+
+    /// Draw every row of `grid` through `cache`, counting renders
+    fn frame(cache: &mut RowCache<u64>, grid: &Grid, config: &TerminalConfig, renders: &mut usize) {
+        cache.begin_frame(0);
+        for key in keys(grid, config) {
+            cache.get_or_render(key, || {
+                *renders += 1;
+                Some(key)
+            });
+        }
+        cache.end_frame();
+    }
+
+    #[test]
+    fn test_key_follows_content_and_attributes() {
+        let config = TerminalConfig::default();
+        let plain = keys(&screen("abc", &config), &config);
+        assert_eq!(plain, keys(&screen("abc", &config), &config));
+        // Blank rows share a key
+        assert_eq!(plain[1], plain[2]);
+
+        assert_ne!(plain[0], keys(&screen("abd", &config), &config)[0]);
+        assert_ne!(plain[0], keys(&screen("\x1b[1mabc", &config), &config)[0]);
+        assert_ne!(plain[0], keys(&screen("\x1b[4:3mabc", &config), &config)[0]);
+        assert_ne!(plain[0], keys(&screen("\x1b[4mabc", &config), &config)[0]);
+    }
+
+    #[test]
+    fn test_key_follows_theme() {
+        let config = TerminalConfig::default();
+        let themed = config.clone().with_foreground_color(Color::rgb(0.0, 1.0, 0.0));
+        let grid = screen("abc", &config);
+        assert_ne!(keys(&grid, &config)[0], keys(&grid, &themed)[0]);
+    }
+
+    #[test]
+    fn test_unchanged_rows_are_reused() {
+        let config = TerminalConfig::default();
+        let mut cache = RowCache::new();
+        let mut renders = 0;
+
+        let mut grid = screen("one\r\ntwo", &config);
+        frame(&mut cache, &grid, &config, &mut renders);
+        // "one", "two" and the shared blank row
+        assert_eq!((renders, cache.misses(), cache.hits()), (3, 3, 0));
+        assert_eq!(cache.len(), 3);
+
+        AnsiParser::new().feed_str("\x1b[3;1Hsix", &mut grid);
+        frame(&mut cache, &grid, &config, &mut renders);
+        assert_eq!(renders, 4);
+        assert_eq!((cache.misses(), cache.hits()), (1, 2));
+        // The blank row went off screen and was swept
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn test_layout_change_and_failed_render() {
+        let mut cache = RowCache::new();
+        cache.begin_frame(1);
+        assert_eq!(cache.get_or_render(7, || Some("row")), Some(&"row"));
+        assert_eq!(cache.get_or_render(8, || None), None);
+        cache.end_frame();
+        assert_eq!(cache.len(), 1);
+
+        cache.begin_frame(2);
+        assert!(cache.is_empty());
+    }
+}
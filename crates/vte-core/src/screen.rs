@@ -0,0 +1,256 @@
+// src/screen.rs
+//! Screen cell storage with lazily materialized rows
+//!
+//! The grid keeps two screens (primary and alternate), and a 300x100 window
+//! holds 30,000 cells in each. Most of their rows are blank most of the
+//! time: all of the alternate screen until a full-screen application draws
+//! on it, the bottom of the primary screen after a clear. [`ScreenBuffer`]
+//! keeps each row behind an `Arc`, and every blank row shares one default
+//! row; a row gets cells of its own on its first write. Clearing, scrolling,
+//! resizing and switching screens then cost a pointer per row plus a copy of
+//! the rows that hold content, and a clone of the buffer shares its rows
+//! until either side writes.
+//!
+//! Cells are still addressed by flat index (`row * cols + col`), as with the
+//! `Vec<Cell>` this replaces; [`ScreenBuffer::row`] borrows a whole row.
+
+use crate::ansi::Cell;
+use std::ops::{Index, IndexMut, Range};
+use std::sync::Arc;
+
+/// `rows` x `cols` cells, blank rows sharing one allocation
+#[derive(Clone, Debug)]
+pub struct ScreenBuffer {
+    cols: usize,
+    rows: Vec<Arc<Vec<Cell>>>,
+    /// Row of `cols` blank cells shared by every row not written yet
+    blank: Arc<Vec<Cell>>,
+    blank_cell: Cell,
+}
+
+impl ScreenBuffer {
+    /// Blank screen of `cols` x `rows` copies of `blank_cell`
+    pub fn new(cols: usize, rows: usize, blank_cell: Cell) -> Self {
+        let blank = Arc::new(vec![blank_cell; cols]);
+        Self { cols, rows: vec![Arc::clone(&blank); rows], blank, blank_cell }
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Number of cells, `cols * rows`
+    pub fn len(&self) -> usize {
+        self.cols * self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Cell at flat index `index`, if inside the screen
+    pub fn get(&self, index: usize) -> Option<&Cell> {
+        (index < self.len()).then(|| &self[index])
+    }
+
+    /// Cells of one row; panics past the last row, like indexing
+    pub fn row(&self, row: usize) -> &[Cell] {
+        &self.rows[row]
+    }
+
+    pub fn get_row(&self, row: usize) -> Option<&[Cell]> {
+        self.rows.get(row).map(|cells| cells.as_slice())
+    }
+
+    /// Cells of one row for writing, giving the row cells of its own
+    pub fn row_mut(&mut self, row: usize) -> &mut [Cell] {
+        Arc::make_mut(&mut self.rows[row]).as_mut_slice()
+    }
+
+    /// Rows top to bottom
+    pub fn iter_rows(&self) -> impl ExactSizeIterator<Item = &[Cell]> + '_ {
+        self.rows.iter().map(|cells| cells.as_slice())
+    }
+
+    /// Replace a row, sharing the blank row if `cells` is blank; `cells` is
+    /// cut or padded to the width
+    pub fn set_row(&mut self, row: usize, mut cells: Vec<Cell>) {
+        cells.resize(self.cols, self.blank_cell);
+        self.rows[row] = if cells == *self.blank { Arc::clone(&self.blank) } else { Arc::new(cells) };
+    }
+
+    /// Whether `row` was never written since it was last blanked
+    ///
+    /// A row written back to blank cells still counts as materialized.
+    pub fn is_blank_row(&self, row: usize) -> bool {
+        Arc::ptr_eq(&self.rows[row], &self.blank)
+    }
+
+    /// Blank every row
+    pub fn clear(&mut self) {
+        for row in &mut self.rows {
+            *row = Arc::clone(&self.blank);
+        }
+    }
+
+    pub fn clear_row(&mut self, row: usize) {
+        self.rows[row] = Arc::clone(&self.blank);
+    }
+
+    /// Move rows `range` up by `n`, blanking the `n` rows at its bottom
+    pub fn scroll_up(&mut self, range: Range<usize>, n: usize) {
+        let n = n.min(range.len());
+        self.rows[range.clone()].rotate_left(n);
+        for row in range.end - n..range.end {
+            self.clear_row(row);
+        }
+    }
+
+    /// Move rows `range` down by `n`, blanking the `n` rows at its top
+    pub fn scroll_down(&mut self, range: Range<usize>, n: usize) {
+        let n = n.min(range.len());
+        self.rows[range.clone()].rotate_right(n);
+        for row in range.start..range.start + n {
+            self.clear_row(row);
+        }
+    }
+
+    /// Change the size, keeping the cells that still fit at the top left
+    ///
+    /// Only rows holding content are copied; blank ones switch to the new
+    /// blank row.
+    pub fn resize(&mut self, cols: usize, rows: usize) {
+        let blank = Arc::new(vec![self.blank_cell; cols]);
+        let old = std::mem::replace(&mut self.rows, vec![Arc::clone(&blank); rows]);
+        for (row, cells) in old.into_iter().take(rows).enumerate() {
+            if Arc::ptr_eq(&cells, &self.blank) {
+                continue;
+            }
+            let mut cells = Arc::unwrap_or_clone(cells);
+            cells.resize(cols, self.blank_cell);
+            // A wide character cut off at the new margin stays as a narrow one
+            if let Some(last) = cells.last_mut() {
+                last.wide = false;
+            }
+            self.rows[row] = Arc::new(cells);
+        }
+        self.cols = cols;
+        self.blank = blank;
+    }
+
+    /// Copy of all cells, row-major
+    pub fn to_vec(&self) -> Vec<Cell> {
+        self.rows.iter().flat_map(|cells| cells.iter().copied()).collect()
+    }
+
+    /// Whether the cells equal the row-major `cells`
+    pub fn same_cells(&self, cells: &[Cell]) -> bool {
+        cells.len() == self.len() && self.iter_rows().zip(cells.chunks(self.cols.max(1))).all(|(a, b)| a == b)
+    }
+
+    /// Rows with cells of their own
+    pub fn materialized_rows(&self) -> usize {
+        self.rows.iter().filter(|cells| !Arc::ptr_eq(cells, &self.blank)).count()
+    }
+
+    /// Heap bytes of the cells: the shared blank row and each materialized one
+    pub fn memory_bytes(&self) -> usize {
+        let row_bytes = self.cols * std::mem::size_of::<Cell>();
+        (self.materialized_rows() + 1) * row_bytes + self.rows.len() * std::mem::size_of::<Arc<Vec<Cell>>>()
+    }
+
+    /// Whether `row` is the same allocation in `self` and `other`, so
+    /// unchanged since one was copied from the other
+    pub(crate) fn shares_row(&self, other: &ScreenBuffer, row: usize) -> bool {
+        Arc::ptr_eq(&self.rows[row], &other.rows[row])
+    }
+
+    /// Make `row` share `other`'s cells for that row
+    pub(crate) fn share_row(&mut self, other: &ScreenBuffer, row: usize) {
+        self.rows[row] = Arc::clone(&other.rows[row]);
+    }
+}
+
+impl Index<usize> for ScreenBuffer {
+    type Output = Cell;
+
+    fn index(&self, index: usize) -> &Cell {
+        &self.rows[index / self.cols][index % self.cols]
+    }
+}
+
+impl IndexMut<usize> for ScreenBuffer {
+    fn index_mut(&mut self, index: usize) -> &mut Cell {
+        let cols = self.cols;
+        &mut Arc::make_mut(&mut self.rows[index / cols])[index % cols]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(ch: char) -> Cell {
+        Cell { ch, ..Cell::default() }
+    }
+
+    fn text(screen: &ScreenBuffer) -> Vec<String> {
+        screen.iter_rows().map(|row| row.iter().map(|c| if c.ch == '\0' { '.' } else { c.ch }).collect()).collect()
+    }
+
+    #[test]
+    fn test_rows_materialize_on_write() {
+        let mut screen = ScreenBuffer::new(4, 3, Cell::default());
+        assert_eq!(screen.materialized_rows(), 0);
+        let blank_bytes = screen.memory_bytes();
+
+        screen[5] = cell('a');
+        assert_eq!(screen.row(1)[1].ch, 'a');
+        assert_eq!(screen.materialized_rows(), 1);
+        assert!(screen.memory_bytes() > blank_bytes);
+        assert!(screen.is_blank_row(0) && !screen.is_blank_row(1));
+
+        // A clone shares rows until either side writes
+        let copy = screen.clone();
+        screen.row_mut(1)[0] = cell('b');
+        assert_eq!(copy.row(1)[0].ch, '\0');
+        assert!(copy.shares_row(&screen, 0) && !copy.shares_row(&screen, 1));
+
+        screen.clear();
+        assert_eq!(screen.materialized_rows(), 0);
+        assert_eq!(screen.memory_bytes(), blank_bytes);
+    }
+
+    #[test]
+    fn test_scrolling_moves_rows() {
+        let mut screen = ScreenBuffer::new(2, 4, Cell::default());
+        for (row, ch) in ['a', 'b', 'c', 'd'].into_iter().enumerate() {
+            screen.set_row(row, vec![cell(ch)]);
+        }
+        screen.scroll_up(1..4, 1);
+        assert_eq!(text(&screen), ["a.", "c.", "d.", ".."]);
+        screen.scroll_down(0..4, 2);
+        assert_eq!(text(&screen), ["..", "..", "a.", "c."]);
+        assert!(screen.is_blank_row(0) && screen.is_blank_row(1));
+    }
+
+    #[test]
+    fn test_resize_keeps_top_left_cells() {
+        let mut screen = ScreenBuffer::new(3, 3, Cell::default());
+        screen.set_row(0, vec![cell('a'), cell('b'), cell('c')]);
+        screen.set_row(2, vec![cell('x')]);
+        screen.set_row(1, Vec::new());
+        assert_eq!(screen.materialized_rows(), 2);
+
+        screen.resize(2, 4);
+        assert_eq!(text(&screen), ["ab", "..", "x.", ".."]);
+        assert_eq!((screen.cols(), screen.row_count(), screen.len()), (2, 4, 8));
+        assert_eq!(screen.materialized_rows(), 2);
+        assert!(screen.same_cells(&screen.to_vec()));
+        assert_eq!(screen.get(8), None);
+    }
+}
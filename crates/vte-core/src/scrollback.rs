@@ -0,0 +1,292 @@
+// src/scrollback.rs
+//! Scrollback storage
+//!
+//! The grid keeps rows that scrolled off the top of the primary screen in a
+//! [`ScrollbackStore`]. The default, [`MemoryScrollback`], is an in-memory
+//! ring of rows; embedders with long histories can supply compressed,
+//! disk-backed or shared-memory stores through `Grid::set_scrollback_store`
+//! without changing the grid.
+//!
+//! Invariants a store can rely on, and must keep:
+//!
+//! - Rows are indexed from 0, the oldest, to `len() - 1`, the newest. The
+//!   grid only appends at the newest end and trims at the oldest end, apart
+//!   from `truncate` and `clear`.
+//! - Each row is stored as appended. Rows keep the width the screen had when
+//!   they scrolled off: a resize, with or without rewrap, neither rewrites
+//!   stored rows nor changes their length, so readers must not assume
+//!   `get_row(i).len() == grid.cols`.
+//! - `get_row(i)` for `i < len()` returns the row last appended at that
+//!   index; after `trim(n)` the old row `n` is row 0.
+//! - The grid enforces the scrollback limit by trimming; a store does not
+//!   need to bound itself.
+//! - `get_row(i)` returns a row equal to the one appended, cell for cell,
+//!   however the store keeps it internally.
+//! - Grids are cloned, e.g. per chunk with journaled parsing, so
+//!   `clone_box` must return a store that behaves independently of the
+//!   original (sharing immutable data is fine).
+
+use crate::ansi::Cell;
+use std::borrow::Cow;
+use std::collections::VecDeque;
+
+/// Storage for scrollback rows
+pub trait ScrollbackStore: Send + Sync {
+    /// Add `row` as the newest row
+    fn append_row(&mut self, row: &[Cell]);
+
+    /// Row `index`, 0 being the oldest
+    fn get_row(&self, index: usize) -> Option<Cow<'_, [Cell]>>;
+
+    /// Number of rows held
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop the `rows` oldest rows (all of them if fewer are held)
+    fn trim(&mut self, rows: usize);
+
+    /// Keep only the `rows` oldest rows
+    fn truncate(&mut self, rows: usize);
+
+    /// Drop every row
+    fn clear(&mut self) {
+        self.trim(self.len());
+    }
+
+    /// Heap memory held, in bytes; 0 for stores that keep rows elsewhere
+    fn memory_bytes(&self) -> usize {
+        0
+    }
+
+    /// Release spare capacity, e.g. after trimming
+    fn shrink_to_fit(&mut self) {}
+
+    /// Independent copy of this store (see the module invariants)
+    fn clone_box(&self) -> Box<dyn ScrollbackStore>;
+}
+
+impl Clone for Box<dyn ScrollbackStore> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+/// Whether `cell` may be dropped from the end of a stored row
+///
+/// Only rows ending in plain or background-colored space shrink; a row
+/// ending in text or a soft-wrap mark is kept whole.
+fn is_blank(cell: &Cell) -> bool {
+    matches!(cell.ch, '\0' | ' ') && !cell.wrapped
+}
+
+/// Scrollback row with its trailing blank run cut down to one cell
+#[derive(Debug, Clone)]
+struct StoredRow {
+    /// Cells up to and including the first cell of the trailing blank run
+    cells: Box<[Cell]>,
+    /// Width of the row as appended
+    width: usize,
+}
+
+impl StoredRow {
+    fn new(row: &[Cell]) -> Self {
+        let kept = match row.last() {
+            Some(fill) if is_blank(fill) => {
+                let run = row.iter().rev().take_while(|cell| *cell == fill).count();
+                row.len() - run + 1
+            }
+            _ => row.len(),
+        };
+        Self { cells: row[..kept].into(), width: row.len() }
+    }
+
+    /// The row as appended, borrowed when nothing was cut
+    fn expand(&self) -> Cow<'_, [Cell]> {
+        match self.cells.last() {
+            Some(&fill) if self.cells.len() < self.width => {
+                let mut row = Vec::with_capacity(self.width);
+                row.extend_from_slice(&self.cells);
+                row.resize(self.width, fill);
+                Cow::Owned(row)
+            }
+            _ => Cow::Borrowed(&self.cells[..]),
+        }
+    }
+}
+
+/// In-memory ring of rows, the default store
+///
+/// Rows are kept with their trailing blank cells cut (see [`StoredRow`]),
+/// so sparse output such as short lines on a wide screen costs about as
+/// much as its text. `get_row` rebuilds such rows at full width.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryScrollback {
+    rows: VecDeque<StoredRow>,
+}
+
+impl MemoryScrollback {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ScrollbackStore for MemoryScrollback {
+    fn append_row(&mut self, row: &[Cell]) {
+        self.rows.push_back(StoredRow::new(row));
+    }
+
+    fn get_row(&self, index: usize) -> Option<Cow<'_, [Cell]>> {
+        self.rows.get(index).map(StoredRow::expand)
+    }
+
+    fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn trim(&mut self, rows: usize) {
+        self.rows.drain(..rows.min(self.rows.len()));
+    }
+
+    fn truncate(&mut self, rows: usize) {
+        self.rows.truncate(rows);
+    }
+
+    fn memory_bytes(&self) -> usize {
+        self.rows.iter().map(|row| row.cells.len() * std::mem::size_of::<Cell>()).sum::<usize>()
+            + self.rows.capacity() * std::mem::size_of::<StoredRow>()
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.rows.shrink_to_fit();
+    }
+
+    fn clone_box(&self) -> Box<dyn ScrollbackStore> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::{AnsiParser, Color};
+    use crate::config::TerminalConfig;
+    use crate::grid::Grid;
+    use std::sync::{Arc, Mutex};
+
+    fn row(text: &str) -> Vec<Cell> {
+        text.chars().map(|ch| Cell { ch, ..Cell::default() }).collect()
+    }
+
+    fn text(row: &[Cell]) -> String {
+        row.iter().map(|cell| cell.ch).filter(|&ch| ch != '\0').collect()
+    }
+
+    #[test]
+    fn test_memory_store_ring() {
+        let mut store = MemoryScrollback::new();
+        for line in ["one", "two", "three"] {
+            store.append_row(&row(line));
+        }
+        store.trim(1);
+        assert_eq!(store.len(), 2);
+        assert_eq!(text(&store.get_row(0).unwrap()), "two");
+        store.truncate(1);
+        assert_eq!(text(&store.get_row(0).unwrap()), "two");
+        assert!(store.get_row(1).is_none());
+        store.clear();
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_memory_store_cuts_trailing_blanks() {
+        let mut sparse = row("ab");
+        sparse.resize(80, Cell::default());
+        let colored = Cell { ch: ' ', bg: Color::rgb(0.1, 0.2, 0.3), ..Cell::default() };
+        let mut erased = row("x");
+        erased.resize(80, colored);
+        let mut wrapped = row("wrap");
+        wrapped.resize(80, Cell::default());
+        wrapped[79].wrapped = true;
+
+        let mut store = MemoryScrollback::new();
+        for line in [&sparse, &erased, &wrapped] {
+            store.append_row(line);
+        }
+        assert_eq!(store.get_row(0).unwrap().as_ref(), sparse.as_slice());
+        assert_eq!(store.get_row(1).unwrap().as_ref(), erased.as_slice());
+        assert_eq!(store.get_row(2).unwrap().as_ref(), wrapped.as_slice());
+        assert!(matches!(store.get_row(2).unwrap(), Cow::Borrowed(_)));
+        assert!(store.memory_bytes() < 2 * 80 * std::mem::size_of::<Cell>());
+
+        // Empty rows survive too
+        store.append_row(&[]);
+        assert!(store.get_row(3).unwrap().is_empty());
+    }
+
+    /// Store that counts the rows handed to it, standing in for an external one
+    #[derive(Clone, Default)]
+    struct CountingStore {
+        inner: MemoryScrollback,
+        appended: Arc<Mutex<usize>>,
+    }
+
+    impl ScrollbackStore for CountingStore {
+        fn append_row(&mut self, row: &[Cell]) {
+            *self.appended.lock().unwrap() += 1;
+            self.inner.append_row(row);
+        }
+        fn get_row(&self, index: usize) -> Option<Cow<'_, [Cell]>> {
+            self.inner.get_row(index)
+        }
+        fn len(&self) -> usize {
+            self.inner.len()
+        }
+        fn trim(&mut self, rows: usize) {
+            self.inner.trim(rows);
+        }
+        fn truncate(&mut self, rows: usize) {
+            self.inner.truncate(rows);
+        }
+        fn clone_box(&self) -> Box<dyn ScrollbackStore> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn test_grid_uses_custom_store() {
+        let mut grid = Grid::new(5, 2, Arc::new(TerminalConfig::default()));
+        let mut parser = AnsiParser::new();
+        parser.feed_str("old\r\n\r\n", &mut grid);
+
+        // Rows already in the scrollback move to the new store
+        let store = CountingStore::default();
+        let appended = Arc::clone(&store.appended);
+        grid.set_scrollback_store(Box::new(store));
+        parser.feed_str("new\r\n\r\n", &mut grid);
+        assert_eq!(*appended.lock().unwrap(), 3);
+        assert_eq!(grid.scrollback_rows(), 3);
+        assert_eq!(text(&grid.scrollback_row(0).unwrap()), "old");
+        assert_eq!(grid.search_scrollback("new"), vec![2]);
+
+        // Rows keep their width across a resize
+        grid.resize_with_rewrap(8, 2);
+        assert_eq!(grid.scrollback_row(0).unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_selection_spans_cut_rows() {
+        let mut grid = Grid::new(40, 2, Arc::new(TerminalConfig::default()));
+        let mut parser = AnsiParser::new();
+        parser.feed_str("short\r\nx\r\ny", &mut grid);
+        assert_eq!(grid.scrollback_rows(), 1);
+        assert_eq!(grid.scrollback_row(0).unwrap().len(), 40);
+        assert!(grid.scrollback_bytes() < 40 * std::mem::size_of::<Cell>());
+
+        grid.selection.create_selection(0, 0, 1, 39);
+        assert_eq!(grid.get_selected_text(), format!("short{}\nx{}", " ".repeat(35), " ".repeat(39)));
+        assert_eq!(grid.search_scrollback("short"), vec![0]);
+    }
+}
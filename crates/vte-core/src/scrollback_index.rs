@@ -0,0 +1,170 @@
+// src/scrollback_index.rs
+//! Lowercased shadow text of the scrollback for fast case-insensitive search
+//!
+//! Rows are appended as they scroll off the top of the screen and dropped as
+//! the scrollback is trimmed, so the index never has to be rebuilt. All rows
+//! live in one contiguous buffer, which lets a search scan the whole history
+//! with a single SIMD substring search instead of visiting cells row by row.
+
+use memchr::memmem;
+use std::collections::VecDeque;
+
+/// Trimmed bytes are only compacted away once they exceed this size
+const COMPACT_MIN_BYTES: usize = 64 * 1024;
+
+/// Searchable text of every scrollback row, oldest first
+#[derive(Clone, Debug, Default)]
+pub struct ScrollbackIndex {
+    /// Lowercased row text, each row terminated by '\n'
+    text: String,
+    /// Byte offset in `text` where each live row starts
+    starts: VecDeque<usize>,
+    /// Bytes at the front of `text` that belong to trimmed rows
+    dead: usize,
+}
+
+impl ScrollbackIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append the newest scrollback row
+    pub fn push_row(&mut self, row: impl IntoIterator<Item = char>) {
+        self.starts.push_back(self.text.len());
+        let start = self.text.len();
+        for ch in row {
+            self.text.extend(ch.to_lowercase());
+        }
+        let trimmed = self.text[start..].trim_end_matches(' ').len();
+        self.text.truncate(start + trimmed);
+        self.text.push('\n');
+    }
+
+    /// Drop the `n` oldest rows
+    pub fn trim_front(&mut self, n: usize) {
+        let n = n.min(self.starts.len());
+        self.starts.drain(..n);
+        self.dead = self.starts.front().copied().unwrap_or(self.text.len());
+
+        if self.dead >= COMPACT_MIN_BYTES && self.dead * 2 >= self.text.len() {
+            self.text.drain(..self.dead);
+            for start in &mut self.starts {
+                *start -= self.dead;
+            }
+            self.dead = 0;
+        }
+    }
+
+    /// Keep only the `rows` oldest rows
+    pub fn truncate(&mut self, rows: usize) {
+        if let Some(&end) = self.starts.get(rows) {
+            self.text.truncate(end);
+            self.starts.truncate(rows);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.starts.clear();
+        self.dead = 0;
+    }
+
+    /// Number of indexed rows
+    pub fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.starts.is_empty()
+    }
+
+    /// Heap bytes held by the index, for `MemoryInfo`
+    pub fn memory_bytes(&self) -> usize {
+        self.text.capacity() + self.starts.capacity() * std::mem::size_of::<usize>()
+    }
+
+    /// Indices (oldest row = 0) of rows containing `query`, ignoring case
+    ///
+    /// Each row is reported once. Queries spanning rows (containing '\n')
+    /// never match.
+    pub fn search(&self, query: &str) -> Vec<usize> {
+        let needle = query.to_lowercase();
+        if needle.is_empty() || needle.contains('\n') {
+            return Vec::new();
+        }
+
+        let finder = memmem::Finder::new(needle.as_bytes());
+        let haystack = self.text.as_bytes();
+        let mut rows = Vec::new();
+        let mut pos = self.dead;
+
+        while let Some(found) = finder.find(&haystack[pos..]) {
+            let row = self.starts.partition_point(|&start| start <= pos + found) - 1;
+            rows.push(row);
+            // Resume at the next row so each row is reported once
+            match self.starts.get(row + 1) {
+                Some(&next) => pos = next,
+                None => break,
+            }
+        }
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(rows: &[&str]) -> ScrollbackIndex {
+        let mut index = ScrollbackIndex::new();
+        for row in rows {
+            index.push_row(row.chars());
+        }
+        index
+    }
+
+    #[test]
+    fn test_search_is_case_insensitive_and_per_row() {
+        let index = index(&["Error: disk full", "ok", "error error", "ÉCOLE"]);
+        assert_eq!(index.search("ERROR"), vec![0, 2]);
+        assert_eq!(index.search("école"), vec![3]);
+        assert!(index.search("missing").is_empty());
+        assert!(index.search("").is_empty());
+        // Matches never span rows
+        assert!(index.search("full\nok").is_empty());
+        assert!(index.search("fullok").is_empty());
+    }
+
+    #[test]
+    fn test_trailing_blanks_do_not_match() {
+        let index = index(&["ab      ", "  cd"]);
+        assert!(index.search("b ").is_empty());
+        assert_eq!(index.search(" c"), vec![1]);
+    }
+
+    #[test]
+    fn test_trim_and_truncate_keep_rows_aligned() {
+        let mut index = index(&["one", "two", "three", "four"]);
+        index.trim_front(2);
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.search("t"), vec![0]);
+        assert!(index.search("one").is_empty());
+
+        index.truncate(1);
+        assert_eq!(index.search("four"), Vec::<usize>::new());
+        assert_eq!(index.search("three"), vec![0]);
+    }
+
+    #[test]
+    fn test_compaction_preserves_results() {
+        let mut index = ScrollbackIndex::new();
+        let long_row = "x".repeat(1000);
+        for i in 0..200 {
+            index.push_row(format!("{long_row} row{i}").chars());
+        }
+        index.trim_front(150);
+        assert_eq!(index.dead, 0, "trimmed bytes should have been compacted");
+        assert_eq!(index.search("row150"), vec![0]);
+        assert_eq!(index.search("row199"), vec![49]);
+    }
+}
@@ -4,6 +4,11 @@
 //! vulnerabilities including paste-based attacks, malicious escape sequences,
 //! and resource exhaustion.
 
+use crate::constants::MAX_TITLE_LEN;
+use crate::error::{TerminalError, TerminalResult};
+use crate::osc_policy::{OscAction, OscCategory, OscPolicy};
+use regex::Regex;
+
 /// Sanitize pasted text to prevent injection attacks
 ///
 /// This function processes text that will be pasted into the terminal,
@@ -32,8 +37,14 @@
 pub fn sanitize_paste(text: &str, bracketed: bool) -> String {
     if bracketed {
         // Use bracketed paste mode - wrap in paste escape sequences
-        // This is the safest option as it prevents interpretation of escape sequences
-        format!("\x1b[200~{}\x1b[201~", text)
+        // This is the safest option as it prevents interpretation of escape sequences.
+        // An embedded end marker would let the payload escape the bracket, so drop it,
+        // again until none is left: removing one can join the text around it into another.
+        let mut body = text.to_string();
+        while body.contains("\x1b[201~") {
+            body = body.replace("\x1b[201~", "");
+        }
+        format!("\x1b[200~{}\x1b[201~", body)
     } else {
         // Legacy mode - remove potentially dangerous characters
         sanitize_unbracketed_paste(text)
@@ -72,6 +83,10 @@ fn sanitize_unbracketed_paste(text: &str) -> String {
             ch if ch.is_alphanumeric() || ch.is_whitespace() || is_safe_punctuation(ch) => {
                 result.push(ch);
             },
+            // Non-ASCII symbols and emoji, except C1 controls and bidi overrides
+            ch if !ch.is_ascii() && !ch.is_control() && !is_bidi_control(ch) => {
+                result.push(ch);
+            },
             _ => {
                 // Skip other potentially dangerous characters
             }
@@ -83,6 +98,29 @@ fn sanitize_unbracketed_paste(text: &str) -> String {
 
 
 
+/// Make an application-supplied window title safe to display
+///
+/// Control characters (C0, DEL, C1) and bidi overrides, which could hide
+/// or reorder what the title bar shows, are removed and the result is cut
+/// to `max_chars` characters.
+///
+/// # Examples
+/// ```
+/// use vte_core::security::sanitize_title;
+///
+/// assert_eq!(sanitize_title("vim\x07 \u{202E}txt.exe", 80), "vim txt.exe");
+/// assert_eq!(sanitize_title("build log", 5), "build");
+/// ```
+pub fn sanitize_title(title: &str, max_chars: usize) -> String {
+    title.chars().filter(|&ch| !ch.is_control() && !is_bidi_control(ch)).take(max_chars).collect()
+}
+
+/// Bidirectional formatting characters that can disguise pasted commands:
+/// embeddings, overrides, isolates and the LRM, RLM and ALM marks
+fn is_bidi_control(ch: char) -> bool {
+    matches!(ch, '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}' | '\u{200E}' | '\u{200F}' | '\u{061C}')
+}
+
 /// Check if a punctuation character is safe for terminal input
 fn is_safe_punctuation(ch: char) -> bool {
     matches!(ch,
@@ -237,6 +275,58 @@ impl RateLimiter {
     }
 }
 
+/// How window titles set by applications (OSC 0/2) are cleaned up
+#[derive(Debug, Clone)]
+pub struct TitlePolicy {
+    /// Longest title kept, in characters; longer ones are cut
+    pub max_chars: usize,
+    /// When not empty, titles must match one of these patterns or are
+    /// ignored; an empty title (restoring the default) always passes
+    pub allowed_patterns: Vec<Regex>,
+    /// Shown before every application title, e.g. `"[remote] "` in an SSH
+    /// profile, so a remote host cannot pose as a local window
+    pub prefix: Option<String>,
+}
+
+impl Default for TitlePolicy {
+    fn default() -> Self {
+        Self { max_chars: MAX_TITLE_LEN, allowed_patterns: Vec::new(), prefix: None }
+    }
+}
+
+impl TitlePolicy {
+    /// Only accept titles matching `pattern` (in addition to earlier patterns)
+    pub fn with_allowed_pattern(mut self, pattern: &str) -> TerminalResult<Self> {
+        let regex = Regex::new(pattern).map_err(|e| TerminalError::InvalidPattern {
+            pattern: pattern.to_string(),
+            message: e.to_string(),
+        })?;
+        self.allowed_patterns.push(regex);
+        Ok(self)
+    }
+
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = Some(prefix.to_string());
+        self
+    }
+
+    /// Title to show for an application's `title`, or `None` if the
+    /// allowlist rejects it
+    pub fn apply(&self, title: &str) -> Option<String> {
+        let clean = sanitize_title(title, self.max_chars);
+        if clean.is_empty() {
+            return Some(clean);
+        }
+        if !self.allowed_patterns.is_empty() && !self.allowed_patterns.iter().any(|p| p.is_match(&clean)) {
+            return None;
+        }
+        Some(match &self.prefix {
+            Some(prefix) => format!("{}{}", prefix, clean),
+            None => clean,
+        })
+    }
+}
+
 /// Security configuration options
 #[derive(Debug, Clone)]
 pub struct SecurityConfig {
@@ -250,6 +340,18 @@ pub struct SecurityConfig {
     pub filter_osc_sequences: bool,
     /// Rate limit for resize operations (operations per second)
     pub resize_rate_limit: u64,
+    /// Let applications ask for the clipboard contents (OSC 52 `?`); each
+    /// request still needs the user's permission (see `clipboard`)
+    pub allow_clipboard_read: bool,
+    /// Let applications define clickable regions whose clicks are sent back
+    /// as input (OSC 7777, see `regions`)
+    pub allow_active_regions: bool,
+    /// Let applications program function keys (DECUDK, see `user_keys`)
+    pub allow_user_keys: bool,
+    /// Allow, deny or ask per OSC category (see `osc_policy`)
+    pub osc_policy: OscPolicy,
+    /// Sanitizing, allowlist and prefix for application titles
+    pub title_policy: TitlePolicy,
 }
 
 impl Default for SecurityConfig {
@@ -260,6 +362,22 @@ impl Default for SecurityConfig {
             max_csi_params: 32,
             filter_osc_sequences: false,
             resize_rate_limit: 10, // 10 resize operations per second max
+            allow_clipboard_read: false,
+            allow_active_regions: false,
+            allow_user_keys: false,
+            osc_policy: OscPolicy::default(),
+            title_policy: TitlePolicy::default(),
+        }
+    }
+}
+
+impl SecurityConfig {
+    /// Effective action for OSC sequences in `category`; `allow_clipboard_read`
+    /// turns a denied clipboard read into asking
+    pub fn osc_action(&self, category: OscCategory) -> OscAction {
+        match self.osc_policy.action(category) {
+            OscAction::Deny if category == OscCategory::ClipboardRead && self.allow_clipboard_read => OscAction::Ask,
+            action => action,
         }
     }
 }
@@ -268,6 +386,21 @@ impl Default for SecurityConfig {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_title_policy() {
+        let policy = TitlePolicy::default();
+        assert_eq!(policy.apply("a\x1b]2;b\u{9b}c\u{2066}d").as_deref(), Some("a]2;bcd"));
+        // Directional marks reorder text as well as the overrides do
+        assert_eq!(policy.apply("a\u{200E}b\u{200F}c\u{061C}d").as_deref(), Some("abcd"));
+        assert_eq!(policy.apply(&"x".repeat(1000)).map(|t| t.chars().count()), Some(MAX_TITLE_LEN));
+
+        let policy = TitlePolicy::default().with_allowed_pattern(r"^vim ").unwrap().with_prefix("[remote] ");
+        assert_eq!(policy.apply("vim notes.txt").as_deref(), Some("[remote] vim notes.txt"));
+        assert_eq!(policy.apply("Your session expired - enter password"), None);
+        assert_eq!(policy.apply("").as_deref(), Some(""));
+        assert!(TitlePolicy::default().with_allowed_pattern("(").is_err());
+    }
+
     #[test]
     fn test_sanitize_paste_bracketed() {
         let input = "echo 'hello'; rm -rf /";
@@ -286,6 +419,24 @@ mod tests {
         assert_eq!(result, "echo 'hello'retext");
     }
 
+    #[test]
+    fn test_sanitize_paste_end_marker_injection() {
+        let result = sanitize_paste("ls\x1b[201~rm -rf ~", true);
+        assert_eq!(result, "\x1b[200~lsrm -rf ~\x1b[201~");
+
+        // Removing the inner marker must not leave a new one behind
+        let result = sanitize_paste("ls\x1b[20\x1b[201~1~rm -rf ~", true);
+        assert_eq!(result, "\x1b[200~lsrm -rf ~\x1b[201~");
+    }
+
+    #[test]
+    fn test_sanitize_paste_unicode() {
+        assert_eq!(sanitize_paste("\u{20ac}\u{1F600}\u{00E9}", false), "\u{20ac}\u{1F600}\u{00E9}");
+        // C1 controls and bidi overrides are removed
+        assert_eq!(sanitize_paste("a\u{009B}b\u{202E}c", false), "abc");
+        assert_eq!(sanitize_paste("a\u{200E}b\u{200F}c\u{061C}d", false), "abcd");
+    }
+
     #[test]
     fn test_validate_osc_clipboard() {
         assert!(validate_osc_sequence("52", "c;SGVsbG8=")); // Valid base64
@@ -358,6 +509,9 @@ mod tests {
         assert!(config.max_osc_length > 0);
         assert!(config.max_csi_params > 0);
         assert!(config.resize_rate_limit > 0);
+        assert!(!config.allow_clipboard_read);
+        assert!(!config.allow_active_regions);
+        assert_eq!(config.osc_action(OscCategory::ClipboardRead), OscAction::Deny);
     }
 
     #[test]
@@ -369,11 +523,20 @@ mod tests {
             max_csi_params: 16,
             filter_osc_sequences: true,
             resize_rate_limit: 5,
+            allow_clipboard_read: true,
+            allow_active_regions: true,
+            osc_policy: OscPolicy { title: OscAction::Deny, ..OscPolicy::default() },
+            title_policy: TitlePolicy::default(),
+            allow_user_keys: false,
         };
 
         assert_eq!(config.max_osc_length, 4096);
         assert_eq!(config.resize_rate_limit, 5);
         assert!(!config.bracketed_paste_default);
         assert!(config.filter_osc_sequences);
+        assert!(config.allow_clipboard_read);
+        assert!(config.allow_active_regions);
+        assert_eq!(config.osc_action(OscCategory::Title), OscAction::Deny);
+        assert_eq!(config.osc_action(OscCategory::ClipboardRead), OscAction::Ask);
     }
 }
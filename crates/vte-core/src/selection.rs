@@ -1,21 +1,36 @@
 //! Selection state machine and logic
 
-use std::time::Instant;
-use crate::constants::CLICK_TIMEOUT_MS;
+use std::time::{Duration, Instant};
 
 /// Selection State Machine
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SelectionState {
     /// No selection active
     Idle,
-    /// Mouse button pressed, waiting to see if it's a click or drag
-    Pressed { start: (usize, usize), timestamp: Instant },
+    /// Mouse button pressed, waiting to see if it's a click or drag; `point`
+    /// is where in pixels, when the backend knows it
+    Pressed { start: (usize, usize), timestamp: Instant, point: Option<(f64, f64)> },
     /// Actively dragging to extend selection
     Dragging { start: (usize, usize), current: (usize, usize) },
     /// Selection is complete and visible
     Complete { start: (usize, usize), end: (usize, usize) },
 }
 
+/// Selected cells of one screen row, drawn as a single highlight
+///
+/// Corners are rounded only where the highlight's outline turns outward, so
+/// the spans of a multi-row selection join into one shape.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelectionSpan {
+    pub row: usize,
+    /// First selected column
+    pub start_col: usize,
+    /// One past the last selected column
+    pub end_col: usize,
+    /// Rounded corners: top-left, top-right, bottom-right, bottom-left
+    pub rounded: [bool; 4],
+}
+
 #[derive(Debug, Clone)]
 pub struct Selection {
     state: SelectionState,
@@ -101,6 +116,44 @@ impl Selection {
         }
     }
 
+    /// Highlight spans of a `rows` x `cols` screen, top to bottom
+    ///
+    /// Middle rows of a multi-row selection span the full width. A pressed
+    /// button that has not moved yet highlights nothing.
+    pub fn spans(&self, rows: usize, cols: usize) -> Vec<SelectionSpan> {
+        if self.is_pressed() || cols == 0 {
+            return Vec::new();
+        }
+        let Some(((min_row, _), (max_row, _))) = self.get_normalized_bounds() else {
+            return Vec::new();
+        };
+
+        let mut spans: Vec<SelectionSpan> = (min_row..=max_row.min(rows.saturating_sub(1)))
+            .filter_map(|row| {
+                let start_col = (0..cols).find(|&col| self.is_position_selected(row, col))?;
+                let end_col = (start_col..cols).take_while(|&col| self.is_position_selected(row, col)).count() + start_col;
+                Some(SelectionSpan { row, start_col, end_col, rounded: [false; 4] })
+            })
+            .collect();
+
+        for i in 0..spans.len() {
+            let span = spans[i];
+            let above = i.checked_sub(1).map(|j| spans[j]).filter(|s| s.row + 1 == span.row);
+            let below = spans.get(i + 1).copied().filter(|s| s.row == span.row + 1);
+            // A corner is exposed unless the neighbouring row covers it
+            let covers = |other: Option<SelectionSpan>, col: usize| {
+                other.is_some_and(|s| s.start_col <= col && col < s.end_col)
+            };
+            spans[i].rounded = [
+                !covers(above, span.start_col),
+                !covers(above, span.end_col - 1),
+                !covers(below, span.end_col - 1),
+                !covers(below, span.start_col),
+            ];
+        }
+        spans
+    }
+
     // State machine transitions
     pub fn clear(&mut self) {
         self.state = SelectionState::Idle;
@@ -109,10 +162,16 @@ impl Selection {
     pub fn start(&mut self, row: usize, col: usize, timestamp: Instant) {
         self.state = SelectionState::Pressed { 
             start: (row, col), 
-            timestamp 
+            timestamp,
+            point: None,
         };
     }
 
+    /// Press at a cell and pixel `point`, so `drag` can ignore small moves
+    pub fn start_at(&mut self, row: usize, col: usize, point: (f64, f64), timestamp: Instant) {
+        self.state = SelectionState::Pressed { start: (row, col), timestamp, point: Some(point) };
+    }
+
     pub fn update(&mut self, row: usize, col: usize) {
         self.state = match self.state {
             SelectionState::Pressed { start, .. } | SelectionState::Dragging { start, .. } => {
@@ -123,11 +182,24 @@ impl Selection {
         };
     }
 
-    pub fn complete(&mut self, row: usize, col: usize, timestamp: Instant) -> bool {
+    /// Pointer moved to a cell and pixel `point`; a press only turns into a
+    /// drag once the pointer is `threshold` pixels away from where it started
+    pub fn drag(&mut self, row: usize, col: usize, point: (f64, f64), threshold: f64) {
+        if let SelectionState::Pressed { point: Some(pressed), .. } = self.state {
+            if (point.0 - pressed.0).hypot(point.1 - pressed.1) < threshold {
+                return;
+            }
+        }
+        self.update(row, col);
+    }
+
+    /// Release at a cell; a press shorter than `click_timeout` is a click
+    /// and leaves no selection
+    pub fn complete(&mut self, row: usize, col: usize, timestamp: Instant, click_timeout: Duration) -> bool {
         match self.state {
-            SelectionState::Pressed { start, timestamp: press_time } => {
-                // Quick click (less than CLICK_TIMEOUT_MS) - clear selection, don't create single-cell selection
-                if timestamp.duration_since(press_time).as_millis() < CLICK_TIMEOUT_MS {
+            SelectionState::Pressed { start, timestamp: press_time, .. } => {
+                // Quick click - clear selection, don't create single-cell selection
+                if timestamp.saturating_duration_since(press_time) < click_timeout {
                     self.state = SelectionState::Idle;
                     false // No selection was created
                 } else {
@@ -145,6 +217,16 @@ impl Selection {
         }
     }
 
+    /// The press or drag ended without a release, e.g. the gesture was
+    /// cancelled; a drag keeps the cells it covered so far
+    pub fn cancel(&mut self) {
+        self.state = match self.state {
+            SelectionState::Dragging { start, current } => SelectionState::Complete { start, end: current },
+            SelectionState::Pressed { .. } => SelectionState::Idle,
+            other => other,
+        };
+    }
+
     // Query methods
     pub fn is_pressed(&self) -> bool {
         matches!(self.state, SelectionState::Pressed { .. })
@@ -172,10 +254,30 @@ impl Selection {
     }
 }
 
+/// Counts consecutive clicks on one cell: 1 for a single click, 2 for a
+/// double click (word), 3 for a triple click (line), then 1 again
+#[derive(Debug, Clone, Default)]
+pub struct ClickCounter {
+    last: Option<((usize, usize), Instant)>,
+    count: u32,
+}
+
+impl ClickCounter {
+    /// Register a click at `cell`; clicks less than `timeout` apart on the
+    /// same cell continue the sequence
+    pub fn click(&mut self, cell: (usize, usize), now: Instant, timeout: Duration) -> u32 {
+        let repeated = self.last.is_some_and(|(last_cell, at)| last_cell == cell && now.saturating_duration_since(at) < timeout);
+        self.count = if repeated { self.count % 3 + 1 } else { 1 };
+        self.last = Some((cell, now));
+        self.count
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::time::Duration;
+
+    const TIMEOUT: Duration = Duration::from_millis(200);
 
     #[test]
     fn test_selection_creation() {
@@ -212,7 +314,7 @@ mod tests {
         assert!(selection.is_selecting());
 
         // Complete selection
-        let completed = selection.complete(5, 6, timestamp + Duration::from_millis(1000));
+        let completed = selection.complete(5, 6, timestamp + Duration::from_millis(1000), TIMEOUT);
         assert!(completed);
         match selection.state {
             SelectionState::Complete { start, end } if start == (1, 2) && end == (5, 6) => {},
@@ -231,12 +333,30 @@ mod tests {
         selection.start(1, 2, timestamp);
 
         // Quick click should clear selection
-        let completed = selection.complete(1, 2, timestamp + Duration::from_millis(50));
+        let completed = selection.complete(1, 2, timestamp + Duration::from_millis(50), TIMEOUT);
         assert!(!completed);
         assert_eq!(selection.state, SelectionState::Idle);
         assert!(!selection.has_selection());
     }
 
+    #[test]
+    fn test_drag_starts_past_threshold() {
+        let mut selection = Selection::new();
+        selection.start_at(1, 2, (20.0, 30.0), Instant::now());
+
+        // A jitter across a cell border is still a click
+        selection.drag(1, 3, (23.0, 32.0), 5.0);
+        assert!(selection.is_pressed());
+        selection.drag(1, 3, (25.0, 30.0), 5.0);
+        assert!(selection.is_dragging());
+        assert_eq!(selection.get_bounds(), Some(((1, 2), (1, 3))));
+
+        // Without a press point any move drags
+        selection.start(1, 2, Instant::now());
+        selection.drag(1, 2, (20.0, 30.0), 5.0);
+        assert!(selection.is_dragging());
+    }
+
     #[test]
     fn test_long_press_creates_selection() {
         let mut selection = Selection::new();
@@ -246,7 +366,7 @@ mod tests {
         selection.start(2, 3, timestamp);
 
         // Long press should create selection
-        let completed = selection.complete(2, 3, timestamp + Duration::from_millis(300)); // Longer than CLICK_TIMEOUT_MS
+        let completed = selection.complete(2, 3, timestamp + Duration::from_millis(300), TIMEOUT);
         assert!(completed);
         match selection.state {
             SelectionState::Complete { start, end } if start == (2, 3) && end == (2, 3) => {},
@@ -262,7 +382,7 @@ mod tests {
         // Create a selection (bottom-right to top-left)
         selection.start(5, 7, timestamp);
         selection.update(2, 3);
-        selection.complete(2, 3, timestamp + Duration::from_millis(1000));
+        selection.complete(2, 3, timestamp + Duration::from_millis(1000), TIMEOUT);
 
         // Test get_bounds returns raw bounds
         let bounds = selection.get_bounds().unwrap();
@@ -281,7 +401,7 @@ mod tests {
         // Create single row selection
         selection.start(1, 2, timestamp);
         selection.update(1, 5);
-        selection.complete(1, 5, timestamp + Duration::from_millis(1000));
+        selection.complete(1, 5, timestamp + Duration::from_millis(1000), TIMEOUT);
 
         // Test normalized bounds
         let normalized = selection.get_normalized_bounds().unwrap();
@@ -304,7 +424,7 @@ mod tests {
         // Create multi-row selection
         selection.start(1, 3, timestamp);
         selection.update(4, 7);
-        selection.complete(4, 7, timestamp + Duration::from_millis(1000));
+        selection.complete(4, 7, timestamp + Duration::from_millis(1000), TIMEOUT);
 
         // Test normalized bounds
         let normalized = selection.get_normalized_bounds().unwrap();
@@ -340,7 +460,7 @@ mod tests {
         // Create selection
         selection.start(1, 2, timestamp);
         selection.update(3, 4);
-        selection.complete(3, 4, timestamp + Duration::from_millis(1000));
+        selection.complete(3, 4, timestamp + Duration::from_millis(1000), TIMEOUT);
 
         assert!(selection.has_selection());
 
@@ -360,7 +480,7 @@ mod tests {
         // Start from top-left, drag to bottom-right (normal)
         selection.start(0, 0, timestamp);
         selection.update(5, 8);
-        selection.complete(5, 8, timestamp + Duration::from_millis(1000));
+        selection.complete(5, 8, timestamp + Duration::from_millis(1000), TIMEOUT);
 
         let bounds = selection.get_bounds().unwrap();
         assert_eq!(bounds, ((0, 0), (5, 8)));
@@ -370,7 +490,7 @@ mod tests {
         // Start from bottom-right, drag to top-left (reverse)
         selection.start(5, 8, timestamp);
         selection.update(0, 0);
-        selection.complete(0, 0, timestamp + Duration::from_millis(1000));
+        selection.complete(0, 0, timestamp + Duration::from_millis(1000), TIMEOUT);
 
         let bounds = selection.get_bounds().unwrap();
         assert_eq!(bounds, ((5, 8), (0, 0))); // Note: raw bounds preserve direction
@@ -394,7 +514,7 @@ mod tests {
         let mut selection = Selection::new();
 
         // Complete on idle state should do nothing
-        let completed = selection.complete(1, 2, Instant::now());
+        let completed = selection.complete(1, 2, Instant::now(), TIMEOUT);
         assert!(!completed);
         assert_eq!(selection.state, SelectionState::Idle);
     }
@@ -425,10 +545,44 @@ mod tests {
         assert!(!selection.has_selection());
 
         // Complete state
-        selection.complete(3, 4, timestamp + Duration::from_millis(1000));
+        selection.complete(3, 4, timestamp + Duration::from_millis(1000), TIMEOUT);
         assert!(!selection.is_pressed());
         assert!(!selection.is_dragging());
         assert!(!selection.is_selecting());
         assert!(selection.has_selection());
     }
+
+    #[test]
+    fn test_spans_merge_rows_into_one_shape() {
+        let mut selection = Selection::new();
+        selection.create_selection(1, 3, 3, 4);
+
+        let spans = selection.spans(10, 8);
+        let ranges: Vec<(usize, usize, usize)> = spans.iter().map(|s| (s.row, s.start_col, s.end_col)).collect();
+        assert_eq!(ranges, vec![(1, 3, 8), (2, 0, 8), (3, 0, 5)]);
+
+        // Outer corners only; where rows join, the outline does not turn outward
+        assert_eq!(spans[0].rounded, [true, true, false, false]);
+        assert_eq!(spans[1].rounded, [true, false, true, false]);
+        assert_eq!(spans[2].rounded, [false, false, true, true]);
+    }
+
+    #[test]
+    fn test_spans_single_row_and_clipping() {
+        let mut selection = Selection::new();
+        assert!(selection.spans(5, 5).is_empty());
+
+        selection.start(0, 1, Instant::now());
+        assert!(selection.spans(5, 5).is_empty(), "a press alone highlights nothing");
+
+        selection.update(0, 3);
+        let spans = selection.spans(5, 5);
+        assert_eq!(spans.len(), 1);
+        assert_eq!((spans[0].start_col, spans[0].end_col), (1, 4));
+        assert_eq!(spans[0].rounded, [true; 4]);
+
+        // Rows below the screen are dropped
+        selection.create_selection(3, 0, 9, 2);
+        assert_eq!(selection.spans(5, 5).len(), 2);
+    }
 }
@@ -0,0 +1,265 @@
+// src/sixel.rs
+//! Sixel image decoding
+//!
+//! A sixel image arrives as `DCS P1 ; P2 ; P3 q data ST`. The data paints
+//! columns of six pixels at a time: each character from `?` to `~` is a
+//! 6-bit pattern drawn in the current color, `!n` repeats the next one `n`
+//! times, `$` returns to the start of the band and `-` moves to the next
+//! band. `#Pc` selects a color register and `#Pc;Pu;Px;Py;Pz` defines it
+//! (`Pu` 1 is HLS, 2 is RGB, both in percent), and `"Pan;Pad;Ph;Pv` gives
+//! the raster size. Registers start with the VT340 palette.
+//!
+//! [`decode`] turns the string into an [`ImageData`]; the grid places it in
+//! its image layer (see `Grid::visible_images`). Data painting the same
+//! pixels over and over (`!2048~$` repeated) is abandoned once it has
+//! painted `SIXEL_MAX_PAINTED` pixels, so a short string cannot keep the
+//! decoder busy under the grid lock.
+
+use crate::constants::{SIXEL_COLOR_REGISTERS, SIXEL_MAX_HEIGHT, SIXEL_MAX_PAINTED, SIXEL_MAX_WIDTH};
+use crate::traits::ImageData;
+
+/// Pixel value of positions no sixel painted; defined colors are opaque
+const UNSET: [u8; 4] = [0, 0, 0, 0];
+
+/// VT340 default color registers, in percent RGB
+const VT340_PALETTE: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (20, 20, 80),
+    (80, 13, 13),
+    (20, 80, 20),
+    (80, 20, 80),
+    (20, 80, 80),
+    (80, 80, 20),
+    (53, 53, 53),
+    (26, 26, 26),
+    (33, 33, 60),
+    (60, 26, 26),
+    (33, 60, 33),
+    (60, 33, 60),
+    (33, 60, 60),
+    (60, 60, 33),
+    (80, 80, 80),
+];
+
+/// Decode a sixel image from the P values before the `q` and the data after
+/// it; pixels no sixel painted are `background` (RGBA), or transparent when
+/// P2 is 1. `None` for an image without pixels or one painting more than
+/// `SIXEL_MAX_PAINTED` pixels.
+pub fn decode(params: &str, data: &str, background: [u8; 4]) -> Option<ImageData> {
+    let transparent = params.split(';').nth(1).is_some_and(|p2| p2.trim() == "1");
+    let mut canvas = Canvas::new();
+    let mut chars = data.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '?'..='~' => canvas.paint(ch as u8 - b'?', 1),
+            '!' => {
+                let count = numbers(&mut chars).first().copied().unwrap_or(1).max(1);
+                if let Some(sixel @ '?'..='~') = chars.next() {
+                    canvas.paint(sixel as u8 - b'?', count);
+                }
+            }
+            '$' => canvas.x = 0,
+            '-' => {
+                canvas.x = 0;
+                canvas.y = canvas.y.saturating_add(6);
+            }
+            '#' => canvas.color(&numbers(&mut chars)),
+            '"' => {
+                let raster = numbers(&mut chars);
+                canvas.declared = (
+                    raster.get(2).map_or(0, |&w| (w as usize).min(SIXEL_MAX_WIDTH)),
+                    raster.get(3).map_or(0, |&h| (h as usize).min(SIXEL_MAX_HEIGHT)),
+                );
+            }
+            _ => {}
+        }
+        if canvas.painted > SIXEL_MAX_PAINTED {
+            tracing::debug!("Abandoning sixel image after {} painted pixels", canvas.painted);
+            return None;
+        }
+    }
+    canvas.finish(if transparent { UNSET } else { background })
+}
+
+/// Pixels painted so far, one growing row per pixel line
+struct Canvas {
+    rows: Vec<Vec<[u8; 4]>>,
+    palette: Vec<[u8; 4]>,
+    current: [u8; 4],
+    x: usize,
+    /// Top pixel line of the current band
+    y: usize,
+    /// Width and height from the raster attributes
+    declared: (usize, usize),
+    /// Pixels written so far, counting repaints
+    painted: usize,
+}
+
+impl Canvas {
+    fn new() -> Self {
+        let mut palette = vec![[0, 0, 0, 255]; SIXEL_COLOR_REGISTERS];
+        for (register, &(r, g, b)) in palette.iter_mut().zip(VT340_PALETTE.iter()) {
+            *register = [percent(r as u32), percent(g as u32), percent(b as u32), 255];
+        }
+        Self { rows: Vec::new(), current: palette[0], palette, x: 0, y: 0, declared: (0, 0), painted: 0 }
+    }
+
+    /// Paint `bits` in the current color `count` times, moving right
+    fn paint(&mut self, bits: u8, count: u32) {
+        let end = self.x.saturating_add(count as usize);
+        let columns = self.x.min(SIXEL_MAX_WIDTH)..end.min(SIXEL_MAX_WIDTH);
+        for bit in (0..6).filter(|bit| bits & (1 << bit) != 0) {
+            let y = self.y + bit;
+            if y >= SIXEL_MAX_HEIGHT || columns.is_empty() {
+                continue;
+            }
+            if self.rows.len() <= y {
+                self.rows.resize_with(y + 1, Vec::new);
+            }
+            let row = &mut self.rows[y];
+            if row.len() < columns.end {
+                row.resize(columns.end, UNSET);
+            }
+            row[columns.clone()].fill(self.current);
+            self.painted += columns.len();
+        }
+        self.x = end;
+    }
+
+    /// `#Pc` selects register Pc; `#Pc;Pu;Px;Py;Pz` also defines it
+    fn color(&mut self, args: &[u32]) {
+        let Some(&register) = args.first() else {
+            return;
+        };
+        let register = register as usize % SIXEL_COLOR_REGISTERS;
+        if let [_, space, x, y, z] = *args {
+            let rgb = match space {
+                1 => hls_to_rgb(x, y, z),
+                2 => [percent(x), percent(y), percent(z)],
+                _ => return,
+            };
+            self.palette[register] = [rgb[0], rgb[1], rgb[2], 255];
+        }
+        self.current = self.palette[register];
+    }
+
+    /// The painted pixels as RGBA, at least the declared raster size, with
+    /// unpainted ones set to `background`
+    fn finish(self, background: [u8; 4]) -> Option<ImageData> {
+        let width = self.rows.iter().map(Vec::len).max().unwrap_or(0).max(self.declared.0);
+        let height = self.rows.len().max(self.declared.1);
+        if width == 0 || height == 0 {
+            return None;
+        }
+        let mut data = Vec::with_capacity(width * height * 4);
+        for y in 0..height {
+            let row = self.rows.get(y).map_or(&[][..], Vec::as_slice);
+            for x in 0..width {
+                let pixel = row.get(x).copied().unwrap_or(UNSET);
+                data.extend_from_slice(if pixel == UNSET { &background } else { &pixel });
+            }
+        }
+        Some(ImageData { data, width, height })
+    }
+}
+
+/// The `;`-separated numbers at the front of `chars`; empty ones are 0
+fn numbers(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Vec<u32> {
+    let mut values = vec![0u32];
+    while let Some(&ch) = chars.peek() {
+        match ch {
+            '0'..='9' => {
+                let last = values.last_mut().unwrap();
+                *last = last.saturating_mul(10).saturating_add(ch as u32 - '0' as u32);
+            }
+            ';' => values.push(0),
+            _ => break,
+        }
+        chars.next();
+    }
+    values
+}
+
+/// A 0-100 percentage as a 0-255 channel
+fn percent(value: u32) -> u8 {
+    (value.min(100) * 255 / 100) as u8
+}
+
+/// DEC HLS (hue 0-360 with blue at 0, lightness and saturation in percent) to RGB
+fn hls_to_rgb(hue: u32, lightness: u32, saturation: u32) -> [u8; 3] {
+    // DEC puts blue at 0 degrees, red at 120 and green at 240
+    let h = ((hue % 360 + 240) % 360) as f64 / 360.0;
+    let l = lightness.min(100) as f64 / 100.0;
+    let s = saturation.min(100) as f64 / 100.0;
+    let q = if l < 0.5 { l * (1.0 + s) } else { l + s - l * s };
+    let p = 2.0 * l - q;
+    let channel = |t: f64| {
+        let t = t.rem_euclid(1.0);
+        let v = if t < 1.0 / 6.0 {
+            p + (q - p) * 6.0 * t
+        } else if t < 0.5 {
+            q
+        } else if t < 2.0 / 3.0 {
+            p + (q - p) * (2.0 / 3.0 - t) * 6.0
+        } else {
+            p
+        };
+        (v * 255.0).round() as u8
+    };
+    [channel(h + 1.0 / 3.0), channel(h), channel(h - 1.0 / 3.0)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BG: [u8; 4] = [1, 2, 3, 255];
+
+    fn pixel(image: &ImageData, x: usize, y: usize) -> [u8; 4] {
+        let i = (y * image.width + x) * 4;
+        image.data[i..i + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn test_bands_repeats_and_colors() {
+        // Red RGB register 1: a 3-wide full column, then one pixel in the next band
+        let image = decode("", "#1;2;100;0;0!3~-@", BG).unwrap();
+        assert_eq!((image.width, image.height), (3, 7));
+        assert_eq!(pixel(&image, 2, 5), [255, 0, 0, 255]);
+        assert_eq!(pixel(&image, 0, 6), [255, 0, 0, 255]);
+        assert_eq!(pixel(&image, 1, 6), BG);
+
+        // `$` paints over the same band; registers keep their VT340 defaults
+        let image = decode("", "#2@$#3A", BG).unwrap();
+        assert_eq!(pixel(&image, 0, 0), [204, 33, 33, 255]);
+        assert_eq!(pixel(&image, 0, 1), [51, 204, 51, 255]);
+    }
+
+    #[test]
+    fn test_hls_and_transparency() {
+        // DEC hue 120 is red; P2 1 leaves unpainted pixels transparent
+        let image = decode("0;1", "\"1;1;2;1#5;1;120;50;100@", BG).unwrap();
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(pixel(&image, 0, 0), [255, 0, 0, 255]);
+        assert_eq!(pixel(&image, 1, 0), [0, 0, 0, 0]);
+        assert_eq!(hls_to_rgb(0, 50, 100), [0, 0, 255]);
+        assert_eq!(hls_to_rgb(240, 50, 100), [0, 255, 0]);
+    }
+
+    #[test]
+    fn test_limits() {
+        assert!(decode("", "", BG).is_none());
+        assert!(decode("", "#0;2;1;1;1$-", BG).is_none());
+        let image = decode("", "!999999999@", BG).unwrap();
+        assert_eq!((image.width, image.height), (SIXEL_MAX_WIDTH, 1));
+        let image = decode("", "\"1;1;1;999999?", BG).unwrap();
+        assert_eq!((image.width, image.height), (1, SIXEL_MAX_HEIGHT));
+
+        // Repainting one full-width band past the work cap gives up
+        let band = "!2048~$";
+        let repaints = SIXEL_MAX_PAINTED / (6 * 2048) + 1;
+        assert!(decode("", &band.repeat(repaints), BG).is_none());
+        assert!(decode("", &band.repeat(repaints - 1), BG).is_some());
+    }
+}
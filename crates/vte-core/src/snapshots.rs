@@ -0,0 +1,244 @@
+// src/snapshots.rs
+//! Time-travel screen snapshots
+//!
+//! Scrollback keeps lines that scrolled off the top, but not what a
+//! full-screen application drew and then overwrote. With
+//! `TerminalConfig::snapshot_interval_ms` set, the grid copies the screen
+//! and cursor whenever a committed batch changed it, so the user can step
+//! back through recent screens (`Grid::step_snapshot_back`) or look one up
+//! by time (`Grid::snapshot_at`).
+//!
+//! At most one screen per interval goes into the ring, and it is the last
+//! one of the interval: each copy is held as pending until the next change,
+//! which records it if the interval since the previous recording is over.
+//! Screens that lasted only until the next change within an interval are
+//! dropped, and the one still showing is never lost.
+//!
+//! Each snapshot is a full copy of the screen cells; the ring holds at most
+//! `TerminalConfig::snapshot_capacity` of them, dropping the oldest first.
+
+use crate::ansi::Cell;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Screen contents and cursor at one point in time
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScreenSnapshot {
+    pub taken_at: Instant,
+    pub cols: usize,
+    pub rows: usize,
+    /// Row-major screen cells, `cols * rows` of them
+    pub cells: Vec<Cell>,
+    /// Cursor (row, col)
+    pub cursor: (usize, usize),
+    pub cursor_visible: bool,
+}
+
+impl ScreenSnapshot {
+    pub fn cell(&self, row: usize, col: usize) -> Option<&Cell> {
+        if row >= self.rows || col >= self.cols {
+            return None;
+        }
+        self.cells.get(row * self.cols + col)
+    }
+
+    /// Screen text, one line per row with trailing blanks removed and
+    /// double-width characters once
+    pub fn text(&self) -> String {
+        let lines: Vec<String> = self
+            .cells
+            .chunks(self.cols.max(1))
+            .map(|row| {
+                let text: String = row.iter().filter(|c| !c.spacer).map(|c| if c.ch == '\0' { ' ' } else { c.ch }).collect();
+                text.trim_end().to_string()
+            })
+            .collect();
+        lines.join("\n")
+    }
+}
+
+/// Recent snapshots, oldest first, and the one being shown
+#[derive(Clone, Debug, Default)]
+pub struct SnapshotRing {
+    snapshots: VecDeque<ScreenSnapshot>,
+    capacity: usize,
+    interval: Duration,
+    /// Screen after the latest change, recorded once the next change shows
+    /// it was the last of its interval
+    pending: Option<ScreenSnapshot>,
+    /// Index of the snapshot shown instead of the live screen
+    shown: Option<usize>,
+}
+
+impl SnapshotRing {
+    pub fn new(interval: Duration, capacity: usize) -> Self {
+        Self { snapshots: VecDeque::new(), capacity, interval, pending: None, shown: None }
+    }
+
+    /// Whether snapshots are kept at all
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Note the screen after a change; the screen it replaces is recorded if
+    /// a snapshot is due
+    pub fn record(&mut self, snapshot: ScreenSnapshot) {
+        if !self.is_enabled() {
+            return;
+        }
+        if let Some(previous) = self.pending.take() {
+            if self.is_due(snapshot.taken_at) {
+                self.push(previous);
+            }
+        }
+        self.pending = Some(snapshot);
+    }
+
+    /// Whether a snapshot taken at `now` would be due
+    pub fn is_due(&self, now: Instant) -> bool {
+        self.capacity > 0 && self.snapshots.back().map_or(true, |last| now.saturating_duration_since(last.taken_at) >= self.interval)
+    }
+
+    /// Add a snapshot, dropping the oldest past the capacity
+    ///
+    /// A shown snapshot stays shown; if it is the one dropped, the oldest
+    /// remaining one is shown instead.
+    pub fn push(&mut self, snapshot: ScreenSnapshot) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.snapshots.push_back(snapshot);
+        if self.snapshots.len() > self.capacity {
+            self.snapshots.pop_front();
+            self.shown = self.shown.map(|i| i.saturating_sub(1));
+        }
+    }
+
+    /// Latest snapshot taken at or before `at`, including the pending one
+    pub fn at(&self, at: Instant) -> Option<&ScreenSnapshot> {
+        self.pending.iter().chain(self.snapshots.iter().rev()).find(|s| s.taken_at <= at)
+    }
+
+    pub fn shown(&self) -> Option<&ScreenSnapshot> {
+        self.snapshots.get(self.shown?)
+    }
+
+    /// Show the snapshot before the shown one; from the live screen, start
+    /// with the newest one unless `skip_newest` (it matches the live screen).
+    /// Returns false when there is nothing older.
+    pub fn step_back(&mut self, skip_newest: bool) -> bool {
+        let next = match self.shown {
+            Some(0) => return false,
+            Some(i) => i - 1,
+            None => match self.snapshots.len().checked_sub(if skip_newest { 2 } else { 1 }) {
+                Some(i) => i,
+                None => return false,
+            },
+        };
+        self.shown = Some(next);
+        true
+    }
+
+    /// Show the snapshot after the shown one, or the live screen after the
+    /// newest; returns false when the live screen was already shown
+    pub fn step_forward(&mut self) -> bool {
+        let Some(i) = self.shown else {
+            return false;
+        };
+        self.shown = (i + 1 < self.snapshots.len()).then_some(i + 1);
+        true
+    }
+
+    /// Show the live screen again
+    pub fn leave(&mut self) {
+        self.shown = None;
+    }
+
+    pub fn newest(&self) -> Option<&ScreenSnapshot> {
+        self.snapshots.back()
+    }
+
+    /// Snapshots held, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &ScreenSnapshot> + '_ {
+        self.snapshots.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(taken_at: Instant, ch: char) -> ScreenSnapshot {
+        let cells = vec![Cell { ch, ..Cell::default() }; 2];
+        ScreenSnapshot { taken_at, cols: 2, rows: 1, cells, cursor: (0, 0), cursor_visible: true }
+    }
+
+    #[test]
+    fn test_ring_is_bounded_and_looked_up_by_time() {
+        let start = Instant::now();
+        let mut ring = SnapshotRing::new(Duration::from_secs(1), 2);
+        assert!(ring.is_due(start));
+        ring.push(snapshot(start, 'a'));
+        assert!(!ring.is_due(start + Duration::from_millis(500)));
+        ring.push(snapshot(start + Duration::from_secs(1), 'b'));
+        ring.push(snapshot(start + Duration::from_secs(2), 'c'));
+
+        assert_eq!(ring.len(), 2);
+        assert_eq!(ring.at(start), None);
+        assert_eq!(ring.at(start + Duration::from_millis(1500)).map(|s| s.text()), Some("bb".to_string()));
+        assert_eq!(ring.at(start + Duration::from_secs(9)).map(|s| s.text()), Some("cc".to_string()));
+    }
+
+    #[test]
+    fn test_last_screen_of_each_interval_is_recorded() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        let mut ring = SnapshotRing::new(Duration::from_secs(1), 8);
+        ring.record(snapshot(at(0), 'a'));
+        ring.record(snapshot(at(100), 'b'));
+        ring.record(snapshot(at(600), 'c'));
+        ring.record(snapshot(at(900), 'd'));
+        // 'b' and 'c' were replaced within the interval after 'a' was recorded
+        ring.record(snapshot(at(1200), 'e'));
+        let texts: Vec<String> = ring.iter().map(|s| s.text()).collect();
+        assert_eq!(texts, ["aa", "dd"]);
+
+        // The screen still showing is found by time without being recorded
+        assert_eq!(ring.at(at(5000)).map(|s| s.text()), Some("ee".to_string()));
+        assert_eq!(ring.at(at(1000)).map(|s| s.text()), Some("dd".to_string()));
+    }
+
+    #[test]
+    fn test_stepping_through_snapshots() {
+        let start = Instant::now();
+        let mut ring = SnapshotRing::new(Duration::ZERO, 3);
+        assert!(!ring.step_back(false));
+        for (i, ch) in ['a', 'b', 'c'].into_iter().enumerate() {
+            ring.push(snapshot(start + Duration::from_secs(i as u64), ch));
+        }
+
+        assert!(ring.step_back(true));
+        assert_eq!(ring.shown().map(|s| s.text()), Some("bb".to_string()));
+        assert!(ring.step_back(true));
+        assert!(!ring.step_back(true));
+        assert_eq!(ring.shown().map(|s| s.text()), Some("aa".to_string()));
+
+        // Dropping the shown snapshot shows the oldest remaining one
+        ring.push(snapshot(start + Duration::from_secs(3), 'd'));
+        assert_eq!(ring.shown().map(|s| s.text()), Some("bb".to_string()));
+
+        assert!(ring.step_forward());
+        assert!(ring.step_forward());
+        assert!(ring.step_forward());
+        assert!(ring.shown().is_none());
+        assert!(!ring.step_forward());
+    }
+}
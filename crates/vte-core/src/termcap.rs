@@ -0,0 +1,222 @@
+// src/termcap.rs
+//! Terminal capability profile
+//!
+//! [`CAPABILITIES`] lists what the emulator actually implements, in terminfo
+//! terms. It answers XTGETTCAP queries (`DCS + q` name ST) and generates the
+//! bundled `hugovte` terminfo entry (`terminfo/hugovte.terminfo`, see
+//! [`terminfo_source`]), so the two cannot drift apart.
+//!
+//! Children see `TERM=xterm-256color` by default (`TerminalConfig::term`),
+//! which every host knows but which promises more than is implemented here
+//! (scroll regions, reverse video, absolute column moves). Embedders that
+//! control the host can install the exact entry with [`install_terminfo`]
+//! and set `TERM=hugovte`; see docs/TERMINFO.md.
+
+use crate::ansi::TermcapValue;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+/// Name of the bundled terminfo entry
+pub const TERMINFO_NAME: &str = "hugovte";
+
+/// Value of one capability in the table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    Flag,
+    Number(u32),
+    /// Control sequence or parameterized string, with actual ESC bytes
+    String(&'static str),
+}
+
+/// Capabilities implemented by the emulator, by terminfo name; extended
+/// (non-standard) names start with an upper-case letter
+pub const CAPABILITIES: &[(&str, Capability)] = &[
+    // Flags
+    ("am", Capability::Flag),
+    ("mir", Capability::Flag),
+    ("msgr", Capability::Flag),
+    ("RGB", Capability::Flag),
+    // Numbers
+    ("colors", Capability::Number(256)),
+    ("cols", Capability::Number(80)),
+    ("it", Capability::Number(4)),
+    ("lines", Capability::Number(24)),
+    ("pairs", Capability::Number(65536)),
+    // Cursor movement
+    ("bel", Capability::String("\x07")),
+    ("cr", Capability::String("\r")),
+    ("cub", Capability::String("\x1b[%p1%dD")),
+    ("cub1", Capability::String("\x08")),
+    ("cud", Capability::String("\x1b[%p1%dB")),
+    ("cud1", Capability::String("\n")),
+    ("cuf", Capability::String("\x1b[%p1%dC")),
+    ("cuf1", Capability::String("\x1b[C")),
+    ("cup", Capability::String("\x1b[%i%p1%d;%p2%dH")),
+    ("cuu", Capability::String("\x1b[%p1%dA")),
+    ("cuu1", Capability::String("\x1b[A")),
+    ("home", Capability::String("\x1b[H")),
+    ("ht", Capability::String("\t")),
+    ("ind", Capability::String("\n")),
+    ("indn", Capability::String("\x1b[%p1%dS")),
+    ("rc", Capability::String("\x1b8")),
+    ("ri", Capability::String("\x1bM")),
+    ("rin", Capability::String("\x1b[%p1%dT")),
+    ("sc", Capability::String("\x1b7")),
+    // Editing
+    ("clear", Capability::String("\x1b[H\x1b[2J")),
+    ("dch", Capability::String("\x1b[%p1%dP")),
+    ("dch1", Capability::String("\x1b[P")),
+    ("dl", Capability::String("\x1b[%p1%dM")),
+    ("dl1", Capability::String("\x1b[M")),
+    ("E3", Capability::String("\x1b[3J")),
+    ("ech", Capability::String("\x1b[%p1%dX")),
+    ("ed", Capability::String("\x1b[J")),
+    ("el", Capability::String("\x1b[K")),
+    ("el1", Capability::String("\x1b[1K")),
+    ("ich", Capability::String("\x1b[%p1%d@")),
+    ("il", Capability::String("\x1b[%p1%dL")),
+    ("il1", Capability::String("\x1b[L")),
+    ("rmir", Capability::String("\x1b[4l")),
+    ("smir", Capability::String("\x1b[4h")),
+    // Modes
+    ("civis", Capability::String("\x1b[?25l")),
+    ("cnorm", Capability::String("\x1b[?12l\x1b[?25h")),
+    ("cvvis", Capability::String("\x1b[?12h\x1b[?25h")),
+    ("rmam", Capability::String("\x1b[?7l")),
+    ("rmcup", Capability::String("\x1b[?1049l")),
+    ("rmkx", Capability::String("\x1b[?1l\x1b>")),
+    ("smam", Capability::String("\x1b[?7h")),
+    ("smcup", Capability::String("\x1b[?1049h")),
+    ("smkx", Capability::String("\x1b[?1h\x1b=")),
+    // Attributes and colors
+    ("bold", Capability::String("\x1b[1m")),
+    ("dim", Capability::String("\x1b[2m")),
+    ("op", Capability::String("\x1b[39;49m")),
+    ("ritm", Capability::String("\x1b[23m")),
+    ("rmul", Capability::String("\x1b[24m")),
+    ("setab", Capability::String("\x1b[%?%p1%{8}%<%t4%p1%d%e%p1%{16}%<%t10%p1%{8}%-%d%e48;5;%p1%d%;m")),
+    ("setaf", Capability::String("\x1b[%?%p1%{8}%<%t3%p1%d%e%p1%{16}%<%t9%p1%{8}%-%d%e38;5;%p1%d%;m")),
+    ("sgr0", Capability::String("\x1b[0m")),
+    ("sitm", Capability::String("\x1b[3m")),
+    ("Smulx", Capability::String("\x1b[4:%p1%dm")),
+    ("smul", Capability::String("\x1b[4m")),
+    // Keys (application cursor mode, as after `smkx`)
+    ("kbs", Capability::String("\x7f")),
+    ("kcub1", Capability::String("\x1bOD")),
+    ("kcud1", Capability::String("\x1bOB")),
+    ("kcuf1", Capability::String("\x1bOC")),
+    ("kcuu1", Capability::String("\x1bOA")),
+    ("kdch1", Capability::String("\x1b[3~")),
+    ("kend", Capability::String("\x1bOF")),
+    ("kent", Capability::String("\x1bOM")),
+    ("kf1", Capability::String("\x1bOP")),
+    ("kf2", Capability::String("\x1bOQ")),
+    ("kf3", Capability::String("\x1bOR")),
+    ("kf4", Capability::String("\x1bOS")),
+    ("kf5", Capability::String("\x1b[15~")),
+    ("kf6", Capability::String("\x1b[17~")),
+    ("kf7", Capability::String("\x1b[18~")),
+    ("kf8", Capability::String("\x1b[19~")),
+    ("kf9", Capability::String("\x1b[20~")),
+    ("kf10", Capability::String("\x1b[21~")),
+    ("kf11", Capability::String("\x1b[23~")),
+    ("kf12", Capability::String("\x1b[24~")),
+    ("khome", Capability::String("\x1bOH")),
+    ("kich1", Capability::String("\x1b[2~")),
+    ("knp", Capability::String("\x1b[6~")),
+    ("kpp", Capability::String("\x1b[5~")),
+];
+
+/// XTGETTCAP answer for `name`: a capability from [`CAPABILITIES`], `TN`
+/// (the terminal name, `term`) or the termcap alias `Co` (colors)
+pub fn lookup(name: &str, term: &str) -> Option<TermcapValue> {
+    let name = match name {
+        "TN" | "name" => return Some(TermcapValue::String(term.to_string())),
+        "Co" => "colors",
+        name => name,
+    };
+    let (_, capability) = CAPABILITIES.iter().find(|(cap, _)| *cap == name)?;
+    Some(match *capability {
+        Capability::Flag => TermcapValue::Flag,
+        Capability::Number(n) => TermcapValue::Number(n),
+        Capability::String(value) => TermcapValue::String(value.to_string()),
+    })
+}
+
+/// Terminfo source of the `hugovte` entry, for `tic -x`
+pub fn terminfo_source() -> String {
+    let mut source = format!("{}|hugovte terminal emulator,\n", TERMINFO_NAME);
+    for (name, capability) in CAPABILITIES {
+        let field = match capability {
+            Capability::Flag => name.to_string(),
+            Capability::Number(n) => format!("{}#{}", name, n),
+            Capability::String(value) => format!("{}={}", name, terminfo_escape(value)),
+        };
+        source.push_str(&format!("\t{},\n", field));
+    }
+    source
+}
+
+/// Compile the `hugovte` entry into the terminfo database at `dir` with
+/// `tic`, e.g. `~/.terminfo`; point `TerminalConfig::terminfo_dir` at `dir`
+/// when it is not searched by default
+pub fn install_terminfo(dir: &Path) -> io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let source_path = dir.join(format!("{}.terminfo", TERMINFO_NAME));
+    std::fs::write(&source_path, terminfo_source())?;
+    let status = Command::new("tic").arg("-x").arg("-o").arg(dir).arg(&source_path).status();
+    let _ = std::fs::remove_file(&source_path);
+    match status? {
+        status if status.success() => Ok(()),
+        status => Err(io::Error::other(format!("tic failed: {}", status))),
+    }
+}
+
+/// `value` in terminfo source notation: `\E` for ESC, `^X` for other
+/// control characters, separators escaped
+fn terminfo_escape(value: &str) -> String {
+    let mut escaped = String::new();
+    for ch in value.chars() {
+        match ch {
+            '\x1b' => escaped.push_str("\\E"),
+            '\x7f' => escaped.push_str("^?"),
+            ',' | '^' | '\\' | ':' => {
+                escaped.push('\\');
+                escaped.push(ch);
+            }
+            ch if (ch as u32) < 0x20 => {
+                escaped.push('^');
+                escaped.push((ch as u8 + b'@') as char);
+            }
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::AnsiParser;
+    use crate::config::TerminalConfig;
+    use crate::grid::Grid;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_bundled_terminfo_matches_table() {
+        assert_eq!(include_str!("../terminfo/hugovte.terminfo"), terminfo_source());
+        assert!(terminfo_source().contains("\tcup=\\E[%i%p1%d;%p2%dH,\n"));
+        assert!(terminfo_source().contains("\tkbs=^?,\n"));
+    }
+
+    #[test]
+    fn test_xtgettcap_reports_configured_name() {
+        let config = TerminalConfig::default().with_term("hugovte", None);
+        let mut grid = Grid::new(10, 2, Arc::new(config));
+        let mut parser = AnsiParser::new();
+        // TN; then "cs" (csr, scroll regions), which is not implemented
+        parser.feed_str("\x1bP+q544e;637372\x1b\\", &mut grid);
+        assert_eq!(grid.take_responses(), "\x1bP1+r544e=6875676F767465\x1b\\\x1bP0+r637372\x1b\\");
+    }
+}
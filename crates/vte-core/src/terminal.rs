@@ -4,14 +4,39 @@
 //! dependencies. Backend-agnostic rendering and event handling are provided through
 //! trait interfaces defined in lib.rs.
 
+use crate::automation::Automation;
+use crate::bell::BellPolicy;
+use crate::broadcast::InputSink;
+use crate::cursor::CursorPosition;
+use crate::damage::DamageReporter;
+use crate::drawing::DrawingCache;
 use crate::grid::Grid;
-use crate::ansi::{AnsiGrid, AnsiParser};
+use crate::grid_view::GridView;
+use crate::keys::Key;
+use crate::ansi::AnsiParser;
+use crate::annotations::{Annotation, AnnotationId, RowAnnotation};
+use crate::encoding::Encoding;
 use crate::error::{TerminalError, TerminalResult};
+use crate::events::{panic_message, EventEmitter, TerminalEvent};
+use crate::highlights::HighlightStyle;
+use crate::locale::ChildLocale;
+use crate::color_env::ChildColorEnv;
+use crate::marks::{MarkCategory, MarkDirection, ScrollMark};
+use crate::modes::{ForcibleMode, Modes};
+use crate::osc_policy::DeniedOsc;
+use crate::parse_budget::FrameAck;
+use crate::paste::PasteStreamer;
+use crate::text_input::{encode_text, TextSource};
+use crate::theme::ColorScheme;
+use crate::constants::{DEFAULT_COLS, DEFAULT_ROWS, SILENCE_POLL_INTERVAL_MS};
 
 use tracing::{error, warn, info, debug, trace};
 
 use portable_pty::{native_pty_system, CommandBuilder, PtySize};
 use std::sync::{Arc, RwLock, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::ops::Range;
+use std::panic::{self, AssertUnwindSafe};
 use std::thread;
 use std::io::{Read, Write};
 
@@ -22,50 +47,72 @@ use std::io::{Read, Write};
 /// delegated to backend implementations via traits.
     pub struct VteTerminalCore {
     pub grid: Arc<RwLock<Grid>>,
-    pty_pair: Arc<RwLock<Option<portable_pty::PtyPair>>>,
+    pty_pair: Arc<Mutex<Option<portable_pty::PtyPair>>>,
     _parser: AnsiParser,
     redraw_sender: Option<async_channel::Sender<()>>,
+    /// Redraw requests; holds at most one, as requests coalesce
+    redraw_receiver: async_channel::Receiver<()>,
+    /// Frames the embedder drew, paced against by the reader
+    frame_ack: FrameAck,
+    /// The timer checking silence and parser health runs; it stops once
+    /// neither is enabled
+    timer: Arc<AtomicBool>,
     writer: Arc<Mutex<Box<dyn Write + Send>>>,
+    events: EventEmitter,
+    pty_resize: Arc<Mutex<PtyResize>>,
+    paste: PasteStreamer,
+    /// Locale variables the child was spawned with
+    child_locale: ChildLocale,
 }
 
+/// Maximum number of times the PTY reader is restarted after a panic
+const MAX_READER_RESTARTS: usize = 5;
+
+/// Notice written to the screen after the reader recovered from a panic
+const RECOVERY_NOTICE: &str = "[parser recovered]";
+
+/// Parse errors within one window before a parse anomaly is reported
+const PARSE_ANOMALY_THRESHOLD: u64 = 32;
+
+/// Window over which parse errors are counted
+const PARSE_ANOMALY_WINDOW: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Bytes read from the PTY at once; floods are drained in few reads and
+/// parsed in slices (see `parse_budget`)
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
 impl VteTerminalCore {
     /// Create new terminal core with default configuration
     pub fn new() -> TerminalResult<Self> {
-        let init_cols = 80;
-        let init_rows = 24;
+        Self::with_config(crate::config::TerminalConfig::default())
+    }
+
+    /// Create new terminal core whose grid uses `config`
+    pub fn with_config(config: crate::config::TerminalConfig) -> TerminalResult<Self> {
+        Self::with_config_and_size(config, DEFAULT_COLS, DEFAULT_ROWS)
+    }
 
-        debug!("Creating VteTerminalCore with default dimensions: {}x{}", init_cols, init_rows);
+    /// Create new terminal core with the shell spawned at `cols` x `rows`
+    ///
+    /// Embedders that know the widget size up front use this to avoid
+    /// spawning at 80x24 and resizing, which makes the shell redraw its prompt.
+    pub fn new_with_size(cols: usize, rows: usize) -> TerminalResult<Self> {
+        Self::with_config_and_size(crate::config::TerminalConfig::default(), cols, rows)
+    }
 
-        // Create grid with default dimensions (no config in Phase 0/1)
-        let config = Arc::new(crate::config::TerminalConfig::default());
-        let grid = Arc::new(RwLock::new(Grid::new(init_cols, init_rows, config)));
+    /// Create new terminal core whose grid uses `config`, spawned at `cols` x `rows`
+    pub fn with_config_and_size(config: crate::config::TerminalConfig, cols: usize, rows: usize) -> TerminalResult<Self> {
+        let init_cols = cols.max(1);
+        let init_rows = rows.max(1);
 
-        // Create parser with error callback that converts AnsiError to TerminalError
-        let parser = AnsiParser::new().with_error_callback(|ansi_err| {
-            // Convert AnsiError to TerminalError
-            let terminal_err = match ansi_err {
-                crate::ansi::AnsiError::TooManyParams { sequence, count } =>
-                    TerminalError::ParserError {
-                        message: format!("Too many parameters ({}) in sequence: {}", count, sequence)
-                    },
-                crate::ansi::AnsiError::OscTooLong { length } =>
-                    TerminalError::ParserError {
-                        message: format!("OSC sequence too long: {} bytes", length)
-                    },
-                crate::ansi::AnsiError::ParamTooLarge { value } =>
-                    TerminalError::ParserError {
-                        message: format!("Parameter value {} exceeded maximum", value)
-                    },
-                crate::ansi::AnsiError::MalformedSequence { context } =>
-                    TerminalError::InvalidEscapeSequence {
-                        sequence: context.clone()
-                    },
-            };
-            warn!("ANSI parser error: {}", terminal_err);
-        });
+        debug!("Creating VteTerminalCore with dimensions: {}x{}", init_cols, init_rows);
+
+        // `env::vars` panics on a non-Unicode value; such variables are no locale names
+        let vars = std::env::vars_os().filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)));
+        let child_locale = ChildLocale::resolve(&config.locale, vars);
 
         // Create PTY pair
-        let pty_pair_result = Self::spawn_pty(init_cols, init_rows);
+        let pty_pair_result = Self::spawn_pty(init_cols, init_rows, &config, &child_locale);
         let pty_pair = match pty_pair_result {
             Ok(pair) => pair,
             Err(e) => return Err(e),
@@ -77,29 +124,96 @@ impl VteTerminalCore {
             Ok((r, w)) => (r, w),
             Err(e) => return Err(e),
         };
+
+        let core = Self::assemble(config, (init_cols, init_rows), pty_pair, reader, writer, child_locale);
+        core.send_welcome_message();
+
+        info!("Terminal core initialized successfully");
+        Ok(core)
+    }
+
+    /// Create a terminal core that reads output from `reader` and writes
+    /// input to `writer` instead of a shell on a PTY, e.g. a remote session
+    /// or test doubles
+    ///
+    /// No child process is spawned: `is_pty_alive` is false and resizes only
+    /// reflow the grid. `TerminalEvent::ChildExited` is sent when `reader`
+    /// reaches EOF or, like a Linux PTY master, fails with EIO.
+    pub fn with_io(
+        config: crate::config::TerminalConfig,
+        cols: usize,
+        rows: usize,
+        reader: impl Read + Send + 'static,
+        writer: impl Write + Send + 'static,
+    ) -> Self {
+        let size = (cols.max(1), rows.max(1));
+        Self::assemble(config, size, Arc::new(Mutex::new(None)), Box::new(reader), Box::new(writer), ChildLocale::default())
+    }
+
+    /// Build the core around its I/O and start the reader and watcher threads
+    fn assemble(
+        config: crate::config::TerminalConfig,
+        (cols, rows): (usize, usize),
+        pty_pair: Arc<Mutex<Option<portable_pty::PtyPair>>>,
+        reader: Box<dyn Read + Send>,
+        writer: Box<dyn Write + Send>,
+        child_locale: ChildLocale,
+    ) -> Self {
+        let config = Arc::new(config);
+        let grid = Arc::new(RwLock::new(Grid::new(cols, rows, Arc::clone(&config))));
+
+        // Create parser with error callback that converts AnsiError to TerminalError
+        let parser = AnsiParser::new().with_error_callback(|ansi_err| {
+            let terminal_err = TerminalError::from(ansi_err);
+            warn!("ANSI parser error: {}", terminal_err);
+        });
         let writer = Arc::new(Mutex::new(writer));
 
-        // Create redraw channel for backend communication
-        let (redraw_tx, _redraw_rx) = async_channel::unbounded::<()>();
+        // Create redraw channel for backend communication; a pending
+        // request covers any made before it is taken
+        let (redraw_tx, redraw_rx) = async_channel::bounded::<()>(1);
+        let events = EventEmitter::new();
+        let paste = PasteStreamer::new(InputSink::from_shared(Arc::clone(&writer)), events.clone());
 
         let core = Self {
             grid: Arc::clone(&grid),
             pty_pair,
             _parser: parser,
             redraw_sender: Some(redraw_tx),
+            redraw_receiver: redraw_rx,
+            frame_ack: FrameAck::default(),
+            timer: Arc::new(AtomicBool::new(false)),
             writer: Arc::clone(&writer),
+            events,
+            pty_resize: Arc::new(Mutex::new(PtyResize::new((cols, rows)))),
+            paste,
+            child_locale,
         };
 
-        // Start PTY reader thread and welcome message
+        // Start PTY reader thread
         core.start_pty_reader(reader, Arc::clone(&grid));
-        core.send_welcome_message();
-
-        info!("Terminal core initialized successfully");
-        Ok(core)
+        if let Some(interval_ms) = config.damage_event_interval_ms {
+            let damage = DamageReporter::new(core.events.clone(), std::time::Duration::from_millis(interval_ms.max(1)));
+            match grid.write() {
+                Ok(mut g) => {
+                    g.add_change_hook(damage.hook());
+                }
+                Err(e) => warn!("Failed to register damage reports (lock poisoned): {}", e),
+            }
+        }
+        if config.silence_threshold_ms.is_some() || config.parser_health_interval_ms.is_some() {
+            core.start_timer();
+        }
+        core
     }
 
     /// Spawn PTY process with configured shell
-    fn spawn_pty(cols: usize, rows: usize) -> TerminalResult<Arc<RwLock<Option<portable_pty::PtyPair>>>> {
+    fn spawn_pty(
+        cols: usize,
+        rows: usize,
+        config: &crate::config::TerminalConfig,
+        locale: &ChildLocale,
+    ) -> TerminalResult<Arc<Mutex<Option<portable_pty::PtyPair>>>> {
         debug!("Spawning PTY with dimensions {}x{}", cols, rows);
 
         let pty_system = native_pty_system();
@@ -115,10 +229,28 @@ impl VteTerminalCore {
             })?;
 
         let mut cmd = CommandBuilder::new("bash");
-        cmd.env("TERM", "xterm-256color");
-        cmd.env("COLORTERM", "truecolor");
-        cmd.env("CLICOLOR", "1");
-        cmd.env("LSCOLORS", "ExGxFxdxCxDxDxBxBxExEx");
+        let colors = ChildColorEnv::resolve(config.color_env, &config.term, config.colorterm.as_deref());
+        for name in &colors.removed {
+            cmd.env_remove(name);
+        }
+        for (name, value) in &colors.set {
+            cmd.env(name, value);
+        }
+        if let Some(dir) = &config.terminfo_dir {
+            cmd.env("TERMINFO", dir);
+        }
+        for name in &locale.removed {
+            cmd.env_remove(name);
+        }
+        for (name, value) in &locale.set {
+            cmd.env(name, value);
+        }
+        if locale.is_changed() {
+            info!("Child locale: {:?}, LC_CTYPE resolves to {}", locale.set, locale.ctype);
+        }
+        if !locale.is_utf8() {
+            warn!("Child locale {} is not UTF-8; non-ASCII output may be garbled", locale.ctype);
+        }
 
         pair.slave.spawn_command(cmd)
             .map_err(|_e| TerminalError::ProcessSpawnFailed {
@@ -127,13 +259,12 @@ impl VteTerminalCore {
 
         info!("PTY child process spawned successfully");
 
-        #[allow(clippy::arc_with_non_send_sync)]
-        Ok(Arc::new(RwLock::new(Some(pair))))
+        Ok(Arc::new(Mutex::new(Some(pair))))
     }
 
     /// Extract reader and writer handles from PTY pair
-    fn setup_pty_handles(pty_pair: &Arc<RwLock<Option<portable_pty::PtyPair>>>) -> TerminalResult<(Box<dyn Read + Send>, Box<dyn Write + Send>)> {
-        let pair_guard = pty_pair.read()
+    fn setup_pty_handles(pty_pair: &Arc<Mutex<Option<portable_pty::PtyPair>>>) -> TerminalResult<(Box<dyn Read + Send>, Box<dyn Write + Send>)> {
+        let pair_guard = pty_pair.lock()
             .map_err(|e| TerminalError::GridLockError {
                 message: format!("PTY pair lock poisoned: {}", e)
             })?;
@@ -157,49 +288,230 @@ impl VteTerminalCore {
     }
 
     /// Start PTY reader thread to process incoming data
-    fn start_pty_reader(&self, mut reader: Box<dyn Read + Send>, grid: Arc<RwLock<Grid>>) {
-        let _writer_pty = Arc::clone(&self.writer);
+    ///
+    /// The read/parse loop runs under `catch_unwind`: if the parser panics, the
+    /// panic is reported as a [`TerminalEvent::ReaderRecovered`] event, the
+    /// reader is re-created from the PTY master, and a visible notice is
+    /// written to the screen before the loop resumes.
+    fn start_pty_reader(&self, reader: Box<dyn Read + Send>, grid: Arc<RwLock<Grid>>) {
         let tx = self.redraw_sender.as_ref().cloned();
+        let frame_ack = self.frame_ack.clone();
+        let pty_pair = Arc::clone(&self.pty_pair);
+        let events = self.events.clone();
+        let writer = Arc::clone(&self.writer);
 
         thread::spawn(move || {
             debug!("PTY reader thread starting");
-            let mut parser = AnsiParser::new().with_error_callback(|err| {
-                warn!("ANSI parser error in thread: {}", err);
-            });
-
-            let mut buf = [0u8; 4096];
-            let mut consecutive_errors = 0;
+            let mut reader = reader;
+            let mut restarts = 0;
 
             loop {
-                match reader.read(&mut buf) {
-                    Ok(0) => {
-                        debug!("PTY reader: received EOF, shutting down");
-                        break;
-                    }
-                    Ok(n) => {
-                        consecutive_errors = 0; // Reset error counter on success
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    Self::run_reader_loop(reader.as_mut(), &grid, &writer, tx.as_ref(), &frame_ack, &events);
+                }));
+
+                let payload = match result {
+                    Ok(()) => break,
+                    Err(payload) => payload,
+                };
+
+                restarts += 1;
+                let message = panic_message(payload.as_ref());
+                error!("PTY reader panicked (restart {}/{}): {}", restarts, MAX_READER_RESTARTS, message);
+                events.emit(TerminalEvent::ReaderRecovered {
+                    panic_message: message,
+                    restarts,
+                });
+
+                if restarts > MAX_READER_RESTARTS {
+                    error!("PTY reader exceeded restart limit, giving up");
+                    events.emit_error(&TerminalError::PtyDisconnected {
+                        message: format!("reader panicked {} times", restarts),
+                    });
+                    break;
+                }
+
+                match Self::recreate_reader(&pty_pair) {
+                    Some(new_reader) => reader = new_reader,
+                    None => warn!("Could not re-create PTY reader, reusing existing handle"),
+                }
+
+                Self::show_recovery_notice(&grid, &events);
+                if let Some(ref sender) = tx {
+                    let _ = sender.try_send(());
+                }
+            }
 
+            info!("PTY reader thread exiting");
+        });
+
+        info!("PTY reader thread started successfully");
+    }
+
+    /// Deliver query replies generated by the grid back to the child process
+    fn write_responses(writer: &Mutex<Box<dyn Write + Send>>, data: &[u8], events: &EventEmitter) {
+        trace!("Writing {} bytes of query responses to PTY", data.len());
+        let mut w = match writer.lock() {
+            Ok(w) => w,
+            Err(e) => {
+                error!("Failed to acquire writer lock for query responses: {}", e);
+                events.emit_error(&TerminalError::GridLockError { message: format!("Writer lock poisoned: {}", e) });
+                return;
+            }
+        };
+        if let Err(e) = w.write_all(data).and_then(|_| w.flush()) {
+            warn!("Failed to write query responses to PTY: {}", e);
+            events.emit_error(&TerminalError::from(e));
+        }
+    }
+
+    /// Read and parse PTY output until EOF or a persistent read failure
+    fn run_reader_loop(
+        reader: &mut dyn Read,
+        grid: &RwLock<Grid>,
+        writer: &Mutex<Box<dyn Write + Send>>,
+        tx: Option<&async_channel::Sender<()>>,
+        frame_ack: &FrameAck,
+        events: &EventEmitter,
+    ) {
+        let last_parse_error = std::rc::Rc::new(std::cell::RefCell::new(String::new()));
+        let last_parse_error_cb = std::rc::Rc::clone(&last_parse_error);
+        let mut parser = AnsiParser::new().with_error_callback(move |err| {
+            warn!("ANSI parser error in thread: {}", err);
+            *last_parse_error_cb.borrow_mut() = err.to_string();
+        });
+
+        let mut buf = vec![0u8; READ_BUFFER_SIZE];
+        let mut consecutive_errors = 0;
+        let mut anomaly_window_start = std::time::Instant::now();
+        let mut anomaly_window_errors = 0;
+        let mut anomaly_reported = false;
+
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) => {
+                    debug!("PTY reader: received EOF, shutting down");
+                    events.emit(TerminalEvent::ChildExited);
+                    break;
+                }
+                Ok(n) => {
+                    consecutive_errors = 0; // Reset error counter on success
+                    trace!("PTY read {} bytes", n);
+
+                    // Decode (UTF-8, or a detected legacy encoding) once, then
+                    // parse in slices bounded by the parse budget, releasing the
+                    // grid lock after each so the renderer can draw during floods
+                    let mut text: Option<String> = None;
+                    let mut parsed = 0;
+                    let mut resumed = false;
+                    while !text.as_ref().is_some_and(|s| parsed >= s.len()) {
+                        let frames_seen;
                         let acquire_lock = grid.write();
                         match acquire_lock {
                             Ok(mut g) => {
-                                // Process input as grapheme clusters for Unicode support
-                                let s = String::from_utf8_lossy(&buf[..n]);
-                                trace!("PTY read {} bytes", n);
+                                // State before the slice, to report what it changed
+                                let encoding_before = g.encoding();
+                                let unseen_before = g.unseen_output();
+                                let title_before = g.title().to_string();
+                                let host_before = g.remote_host().map(str::to_string);
+                                if text.is_none() {
+                                    text = Some(g.decode_output(&buf[..n]));
+                                    resumed = g.note_output();
+                                }
+                                let rest = &text.as_deref().unwrap_or_default()[parsed..];
 
                                 // Process grapheme clusters to handle Unicode properly
-                                use unicode_segmentation::UnicodeSegmentation;
-                                for grapheme in s.graphemes(true) {
-                                    parser.feed_str(grapheme, &mut *g);
-
-                                    // Wide character handling: advance cursor extra for multi-column chars
-                                    use unicode_width::UnicodeWidthStr;
-                                    let width = grapheme.width();
-                                    if width > 1 {
-                                        // Advance additional columns for wide characters
-                                        for _ in 1..width {
-                                            g.advance();
-                                        }
-                                    }
+                                // (character widths are resolved by the grid's width policy)
+                                let budget = g.config.parse_budget();
+                                let errors_before = parser.stats().errors_encountered;
+                                let mut feed = |target: &mut Grid| {
+                                    budget.run(rest, |grapheme| parser.feed_str(grapheme, target))
+                                };
+                                parsed += if g.config.journaled_parsing {
+                                    // Commit the slice whole; a panic keeps the last committed screen
+                                    crate::journal::commit_with(&mut *g, feed)
+                                } else {
+                                    feed(&mut g)
+                                };
+                                g.reconcile_predictions();
+                                g.commit_generation();
+
+                                // Surface bursts of malformed input as a single anomaly per window
+                                if anomaly_window_start.elapsed() >= PARSE_ANOMALY_WINDOW {
+                                    anomaly_window_start = std::time::Instant::now();
+                                    anomaly_window_errors = 0;
+                                    anomaly_reported = false;
+                                }
+                                let chunk_errors = parser.stats().errors_encountered - errors_before;
+                                anomaly_window_errors += chunk_errors;
+                                if !anomaly_reported && anomaly_window_errors >= PARSE_ANOMALY_THRESHOLD {
+                                    anomaly_reported = true;
+                                    events.emit_error(&TerminalError::ParserError {
+                                        message: format!("{} parse errors within {:?}, last: {}",
+                                            anomaly_window_errors, PARSE_ANOMALY_WINDOW, last_parse_error.borrow()),
+                                    });
+                                }
+                                g.record_parser_stats(parser.stats(), (chunk_errors > 0).then_some(last_parse_error.borrow().as_str()));
+
+                                // Answer queries (OSC 4/10/11/12) once the grid lock is released
+                                let responses = g.take_responses();
+                                let finished = g.take_finished_commands();
+                                let bell = g.take_bell();
+                                let clipboard_reads = g.take_clipboard_requests();
+                                let osc_requests = g.take_osc_requests();
+                                let notifications = g.take_notifications();
+                                let custom_osc = g.take_custom_osc();
+                                let encoding = g.encoding();
+                                let unseen = g.unseen_output();
+                                let title = (g.title() != title_before).then(|| g.title().to_string());
+                                let host = (g.remote_host() != host_before.as_deref()).then(|| g.remote_host().map(str::to_string));
+                                let cursor = g.take_cursor_move();
+                                drop(g);
+                                if std::mem::take(&mut resumed) {
+                                    events.emit(TerminalEvent::Activity);
+                                }
+                                if !responses.is_empty() {
+                                    Self::write_responses(writer, responses.as_bytes(), events);
+                                }
+                                for command in finished {
+                                    events.emit(TerminalEvent::from(command));
+                                }
+                                if let Some(action) = bell {
+                                    events.emit(TerminalEvent::Bell { action });
+                                }
+                                if let Some(title) = title {
+                                    events.emit(TerminalEvent::TitleChanged { title });
+                                }
+                                if let Some(host) = host {
+                                    events.emit(TerminalEvent::RemoteHostChanged { host });
+                                }
+                                if let Some((row, col)) = cursor {
+                                    events.emit(TerminalEvent::CursorMoved { row, col });
+                                }
+                                for request in clipboard_reads {
+                                    events.emit(TerminalEvent::ClipboardReadRequest {
+                                        id: request.id,
+                                        primary: request.wants_primary(),
+                                    });
+                                }
+                                for request in osc_requests {
+                                    events.emit(TerminalEvent::OscPermissionRequest {
+                                        id: request.id,
+                                        category: request.category,
+                                        preview: request.preview,
+                                    });
+                                }
+                                for (title, body) in notifications {
+                                    events.emit(TerminalEvent::Notification { title, body });
+                                }
+                                for (code, data) in custom_osc {
+                                    events.emit(TerminalEvent::CustomOsc { code, data });
+                                }
+                                if encoding != encoding_before {
+                                    events.emit(TerminalEvent::EncodingChanged { encoding });
+                                }
+                                if unseen > unseen_before {
+                                    events.emit(TerminalEvent::UnseenOutput { rows: unseen });
                                 }
 
                                 // Enforce automatic memory limits (scrollback cleanup)
@@ -207,37 +519,153 @@ impl VteTerminalCore {
                                 // For now, we rely on cleanup_memory() being called manually or on drop
 
                                 // Notify backend of redraw
-                                if let Some(ref sender) = tx {
-                                    if let Err(e) = sender.send_blocking(()) {
-                                        warn!("Failed to send redraw signal: {}", e);
+                                frames_seen = frame_ack.frames();
+                                if let Some(sender) = tx {
+                                    if let Err(async_channel::TrySendError::Closed(_)) = sender.try_send(()) {
+                                        warn!("Failed to send redraw signal: channel closed");
                                     }
                                 }
                             }
                             Err(e) => {
                                 error!("Failed to acquire grid write lock (attempting recovery): {}", e);
+                                events.emit_error(&TerminalError::GridLockError {
+                                    message: format!("grid lock poisoned, clearing: {}", e),
+                                });
+                                grid.clear_poison();
                                 std::thread::sleep(std::time::Duration::from_millis(10));
                                 continue;
                             }
                         }
-                    }
-                    Err(e) => {
-                        consecutive_errors += 1;
-                        if consecutive_errors > 3 {
-                            error!("PTY read failed consecutively {} times, giving up: {}", consecutive_errors, e);
-                            break;
-                        } else {
-                            warn!("PTY read error (attempt {}) - retrying: {}", consecutive_errors, e);
-                            std::thread::sleep(std::time::Duration::from_millis(100));
-                            continue;
+                        if text.as_ref().is_some_and(|s| parsed < s.len()) {
+                            // The next slice continues the read once the event loop
+                            // drew the frame just requested
+                            frame_ack.wait_past(frames_seen);
                         }
                     }
                 }
+                Err(e) if is_pty_closed(&e) => {
+                    debug!("PTY reader: child side closed ({}), shutting down", e);
+                    events.emit(TerminalEvent::ChildExited);
+                    break;
+                }
+                Err(e) => {
+                    consecutive_errors += 1;
+                    if consecutive_errors > 3 {
+                        error!("PTY read failed consecutively {} times, giving up: {}", consecutive_errors, e);
+                        events.emit_error(&TerminalError::PtyDisconnected {
+                            message: format!("read failed {} times: {}", consecutive_errors, e),
+                        });
+                        break;
+                    } else {
+                        warn!("PTY read error (attempt {}) - retrying: {}", consecutive_errors, e);
+                        events.emit_error(&TerminalError::PtyReadError { source: e });
+                        std::thread::sleep(std::time::Duration::from_millis(100));
+                        continue;
+                    }
+                }
             }
+        }
+    }
 
-            info!("PTY reader thread exiting");
+    /// Start the one timer thread behind `TerminalEvent::Silence` and
+    /// `TerminalEvent::ParserHealth`, unless it already runs
+    ///
+    /// It checks the silence threshold every `SILENCE_POLL_INTERVAL_MS` while
+    /// one is set, taking the write lock only when silence is due, and
+    /// reports parser health every `TerminalConfig::parser_health_interval_ms`
+    /// in which the parser was busy (see `health`). It stops with the grid or
+    /// once neither is enabled.
+    fn start_timer(&self) {
+        if self.timer.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let grid = Arc::downgrade(&self.grid);
+        let events = self.events.clone();
+        let running = Arc::clone(&self.timer);
+        let silence_interval = std::time::Duration::from_millis(SILENCE_POLL_INTERVAL_MS);
+        thread::spawn(move || {
+            let health_interval = match grid.upgrade() {
+                Some(grid) => grid.read().ok().and_then(|g| g.config.parser_health_interval_ms),
+                None => return,
+            }
+            .map(|ms| std::time::Duration::from_millis(ms.max(1)));
+            let start = std::time::Instant::now();
+            let mut next_silence = start + silence_interval;
+            let mut next_health = health_interval.map(|interval| start + interval);
+            loop {
+                let next = next_health.map_or(next_silence, |health| health.min(next_silence));
+                thread::sleep(next.saturating_duration_since(std::time::Instant::now()));
+                let Some(grid) = grid.upgrade() else {
+                    break;
+                };
+                let now = std::time::Instant::now();
+                let health_due = next_health.is_some_and(|at| at <= now);
+                let silence_due = next_silence <= now;
+                let Ok(g) = grid.read() else {
+                    continue;
+                };
+                let silence_enabled = g.silence_threshold().is_some();
+                if !silence_enabled && health_interval.is_none() {
+                    // Cleared under the lock, so a threshold set after this
+                    // read starts a new timer
+                    running.store(false, Ordering::Release);
+                    break;
+                }
+                let poll_silence = silence_due && silence_enabled && g.silence_due();
+                drop(g);
+
+                if silence_due {
+                    next_silence = now + silence_interval;
+                }
+                if let (true, Some(interval)) = (health_due, health_interval) {
+                    next_health = Some(now + interval);
+                }
+                if !poll_silence && !health_due {
+                    continue;
+                }
+                let Ok(mut g) = grid.write() else {
+                    continue;
+                };
+                let quiet = if poll_silence { g.poll_silence() } else { None };
+                let health = if health_due { g.poll_parser_health() } else { None };
+                drop(g);
+                if let Some(duration) = quiet {
+                    events.emit(TerminalEvent::Silence { duration });
+                }
+                if let Some(health) = health {
+                    events.emit(TerminalEvent::ParserHealth { health });
+                }
+            }
+            debug!("Silence and parser health timer stopped");
         });
+    }
 
-        info!("PTY reader thread started successfully");
+    /// Clone a fresh reader handle from the PTY master
+    fn recreate_reader(pty_pair: &Mutex<Option<portable_pty::PtyPair>>) -> Option<Box<dyn Read + Send>> {
+        let guard = pty_pair.lock().ok()?;
+        let pair = guard.as_ref()?;
+        match pair.master.try_clone_reader() {
+            Ok(reader) => Some(reader),
+            Err(e) => {
+                warn!("Failed to clone PTY reader during recovery: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Clear lock poisoning left by the panic and print the recovery notice
+    fn show_recovery_notice(grid: &RwLock<Grid>, events: &EventEmitter) {
+        if grid.is_poisoned() {
+            events.emit_error(&TerminalError::GridLockError {
+                message: "grid lock poisoned by reader panic, recovered".to_string(),
+            });
+            grid.clear_poison();
+        }
+        let mut g = match grid.write() {
+            Ok(g) => g,
+            Err(e) => e.into_inner(),
+        };
+        g.write_notice(RECOVERY_NOTICE);
     }
 
     /// Send welcome message on terminal startup
@@ -266,81 +694,331 @@ impl VteTerminalCore {
 
             // Notify backend of initial redraw
             if let Some(ref sender) = tx {
-                if let Err(e) = sender.send_blocking(()) {
-                    warn!("Failed to send initial redraw signal: {}", e);
+                if let Err(async_channel::TrySendError::Closed(_)) = sender.try_send(()) {
+                    warn!("Failed to send initial redraw signal: channel closed");
                 }
             }
         });
     }
 
+    /// Report keyboard focus changes, used to decide on command notifications
+    pub fn set_focused(&self, focused: bool) {
+        if let Ok(mut g) = self.grid.write() {
+            g.set_focused(focused);
+        }
+    }
+
     /// Send data to terminal process
     pub fn send_input(&self, data: &[u8]) -> Result<(), TerminalError> {
-        let mut writer = self.writer.lock()
-            .map_err(|_| TerminalError::GridLockError { message: "Writer lock poisoned".to_string() })?;
+        self.input_sink().send(data)
+    }
 
-        writer.write_all(data).map_err(TerminalError::from)?;
-        writer.flush().map_err(TerminalError::from)?;
+    /// Handle writing to this terminal's PTY, for a `BroadcastGroup`
+    pub fn input_sink(&self) -> InputSink {
+        InputSink::from_shared(Arc::clone(&self.writer))
+    }
 
-        Ok(())
+    /// Handle streaming pastes to this terminal's PTY; large pastes are
+    /// written in chunks on a background thread (see `commit_text`)
+    pub fn paste_streamer(&self) -> PasteStreamer {
+        self.paste.clone()
+    }
+
+    /// Abort in-flight pastes (e.g. on Escape); returns false if there were none
+    pub fn cancel_paste(&self) -> bool {
+        self.paste.cancel()
+    }
+
+    /// Expect-style scripting handle: `send`, `wait_for`, `capture_between`
+    ///
+    /// Waits search output written after this call.
+    pub fn automation(&self) -> Automation {
+        Automation::new(Arc::clone(&self.grid), self.input_sink())
     }
 
     /// Resize terminal to new dimensions with line rewrapping
     pub fn resize(&self, cols: usize, rows: usize) {
-        debug!("Resizing terminal to {}x{} with rewrapping", cols, rows);
+        self.resize_handle().resize(cols, rows);
+    }
 
-        // Update grid first with rewrapping logic
-        if let Ok(mut g) = self.grid.write() {
-            g.resize_with_rewrap(cols, rows);
-        } else {
-            warn!("Failed to resize grid with rewrap - lock error");
-            return;
+    /// Handle resizing this terminal from callbacks that cannot borrow it,
+    /// e.g. toolkit signal handlers
+    pub fn resize_handle(&self) -> ResizeHandle {
+        ResizeHandle {
+            grid: Arc::clone(&self.grid),
+            pty_pair: Arc::clone(&self.pty_pair),
+            redraw_sender: self.redraw_sender.clone(),
+            pty_resize: Arc::clone(&self.pty_resize),
         }
+    }
 
-        // Update PTY size
-        if let Ok(pair_guard) = self.pty_pair.read() {
-            if let Some(ref pair) = *pair_guard {
-                if let Err(e) = pair.master.resize(PtySize {
-                    rows: rows as u16,
-                    cols: cols as u16,
-                    pixel_width: 0,
-                    pixel_height: 0,
-                }) {
-                    warn!("Failed to resize PTY: {}", e);
+    /// Read the grid through `view` under its read lock; `None` if the lock
+    /// is poisoned
+    pub fn read_grid<R>(&self, view: impl FnOnce(&dyn GridView) -> R) -> Option<R> {
+        self.grid.read().ok().map(|grid| view(&*grid))
+    }
+
+    /// Get access to the terminal grid (read-only)
+    pub fn grid(&self) -> &Arc<RwLock<Grid>> {
+        &self.grid
+    }
+
+    /// Search the scrollback (ignoring case) and mark the matching rows
+    ///
+    /// Replaces the previous search's marks, so `scroll_to_mark` with
+    /// `MarkCategory::SearchMatch` steps through the results. Returns the
+    /// absolute rows that matched.
+    pub fn search_scrollback(&self, query: &str) -> Vec<u64> {
+        let rows = match self.grid.write() {
+            Ok(mut grid) => {
+                let rows = grid.search_scrollback(query);
+                grid.clear_marks(MarkCategory::SearchMatch);
+                for &row in &rows {
+                    grid.add_mark_at(row, MarkCategory::SearchMatch);
                 }
+                rows
             }
-        } else {
-            warn!("Could not access PTY for resize");
+            Err(e) => {
+                warn!("Failed to search scrollback (lock poisoned): {}", e);
+                return Vec::new();
+            }
+        };
+
+        if let Some(ref sender) = self.redraw_sender {
+            let _ = sender.try_send(());
         }
+        rows
+    }
 
-        // Notify backend of resize
+    /// Exit status of the last shell command (OSC 133;D), if reported
+    pub fn last_command_status(&self) -> Option<i32> {
+        match self.grid.read() {
+            Ok(grid) => grid.last_command_status(),
+            Err(e) => {
+                warn!("Failed to read command status (lock poisoned): {}", e);
+                None
+            }
+        }
+    }
+
+    /// Whether a split or tab may start in this terminal's working directory
+    /// (see `Grid::can_inherit_cwd`)
+    pub fn can_inherit_cwd(&self) -> bool {
+        match self.grid.read() {
+            Ok(grid) => grid.can_inherit_cwd(),
+            Err(e) => {
+                warn!("Failed to read working directory (lock poisoned): {}", e);
+                false
+            }
+        }
+    }
+
+    /// Snapshot of the scroll marks (prompts, search matches, error triggers)
+    pub fn scroll_marks(&self) -> Vec<ScrollMark> {
+        match self.grid.read() {
+            Ok(grid) => grid.scroll_marks().to_vec(),
+            Err(e) => {
+                warn!("Failed to read scroll marks (lock poisoned): {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Scroll the viewport to the next/previous mark, optionally of one category
+    pub fn scroll_to_mark(&self, direction: MarkDirection, category: Option<MarkCategory>) -> Option<ScrollMark> {
+        let mark = match self.grid.write() {
+            Ok(mut grid) => grid.scroll_to_mark(direction, category),
+            Err(e) => {
+                warn!("Failed to scroll to mark (lock poisoned): {}", e);
+                return None;
+            }
+        };
+
+        if mark.is_some() {
+            if let Some(ref sender) = self.redraw_sender {
+                let _ = sender.try_send(());
+            }
+        }
+        mark
+    }
+
+    /// Attach `annotation` to an absolute row for the embedder's gutter
+    ///
+    /// Returns `None` if the row was already trimmed from the scrollback.
+    pub fn annotate_row(&self, row: u64, annotation: Annotation) -> Option<AnnotationId> {
+        let id = match self.grid.write() {
+            Ok(mut grid) => grid.annotate_row(row, annotation),
+            Err(e) => {
+                warn!("Failed to annotate row (lock poisoned): {}", e);
+                return None;
+            }
+        };
         if let Some(ref sender) = self.redraw_sender {
-            if let Err(e) = sender.send_blocking(()) {
-                warn!("Failed to send resize redraw signal: {}", e);
+            let _ = sender.try_send(());
+        }
+        id
+    }
+
+    /// Remove an annotation; returns false if it is gone already
+    pub fn remove_annotation(&self, id: AnnotationId) -> bool {
+        let removed = match self.grid.write() {
+            Ok(mut grid) => grid.remove_annotation(id),
+            Err(e) => {
+                warn!("Failed to remove annotation (lock poisoned): {}", e);
+                return false;
+            }
+        };
+        if removed {
+            if let Some(ref sender) = self.redraw_sender {
+                let _ = sender.try_send(());
             }
         }
+        removed
     }
 
-    /// Get access to the terminal grid (read-only)
-    pub fn grid(&self) -> &Arc<RwLock<Grid>> {
-        &self.grid
+    /// Snapshot of the row annotations, in row order
+    pub fn row_annotations(&self) -> Vec<RowAnnotation> {
+        match self.grid.read() {
+            Ok(grid) => grid.row_annotations().to_vec(),
+            Err(e) => {
+                warn!("Failed to read row annotations (lock poisoned): {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Select the text from `start` up to `end` (exclusive), as (absolute
+    /// row, column) pairs, scrolling it into view (see `Grid::select_range`)
+    pub fn select_range(&self, start: (u64, usize), end: (u64, usize)) -> bool {
+        let selected = match self.grid.write() {
+            Ok(mut grid) => grid.select_range(start, end),
+            Err(e) => {
+                warn!("Failed to select range (lock poisoned): {}", e);
+                return false;
+            }
+        };
+        if selected {
+            if let Some(ref sender) = self.redraw_sender {
+                let _ = sender.try_send(());
+            }
+        }
+        selected
+    }
+
+    /// Highlight absolute ranges in `style`, independently of the selection
+    pub fn highlight_ranges(&self, ranges: Vec<Range<(u64, usize)>>, style: HighlightStyle) {
+        match self.grid.write() {
+            Ok(mut grid) => grid.highlight_ranges(ranges, style),
+            Err(e) => {
+                warn!("Failed to highlight ranges (lock poisoned): {}", e);
+                return;
+            }
+        }
+        if let Some(ref sender) = self.redraw_sender {
+            let _ = sender.try_send(());
+        }
+    }
+
+    /// Remove every highlight added with `highlight_ranges`
+    pub fn clear_highlights(&self) {
+        match self.grid.write() {
+            Ok(mut grid) => grid.clear_highlights(),
+            Err(e) => {
+                warn!("Failed to clear highlights (lock poisoned): {}", e);
+                return;
+            }
+        }
+        if let Some(ref sender) = self.redraw_sender {
+            let _ = sender.try_send(());
+        }
+    }
+
+    /// Hold output (scroll lock) or release it, jumping back to the live tail
+    ///
+    /// Output is still read and parsed while held; only the viewport stops
+    /// following it. Emits `TerminalEvent::OutputHold` when the state changes.
+    pub fn set_output_held(&self, held: bool) {
+        self.output_hold_handle().set(held);
+    }
+
+    /// Whether output is held (scroll lock)
+    pub fn is_output_held(&self) -> bool {
+        self.output_hold_handle().is_held()
+    }
+
+    /// Handle holding output from callbacks that cannot borrow this
+    /// terminal, e.g. key handlers
+    pub fn output_hold_handle(&self) -> OutputHoldHandle {
+        OutputHoldHandle {
+            grid: Arc::clone(&self.grid),
+            events: self.events.clone(),
+            redraw_sender: self.redraw_sender.clone(),
+        }
+    }
+
+    /// Snapshot of the terminal modes the application set, e.g. whether it
+    /// enabled mouse reporting or shows the alternate screen
+    pub fn modes(&self) -> Modes {
+        match self.grid.read() {
+            Ok(grid) => grid.modes(),
+            Err(e) => {
+                warn!("Failed to read terminal modes (lock poisoned): {}", e);
+                Modes::default()
+            }
+        }
+    }
+
+    /// Force `mode` off regardless of the application, or stop doing so,
+    /// e.g. for a "disable bracketed paste" toggle
+    pub fn set_mode_disabled(&self, mode: ForcibleMode, disabled: bool) {
+        match self.grid.write() {
+            Ok(mut grid) => grid.set_mode_disabled(mode, disabled),
+            Err(e) => {
+                warn!("Failed to set terminal mode (lock poisoned): {}", e);
+                return;
+            }
+        }
+        if let Some(ref sender) = self.redraw_sender {
+            let _ = sender.try_send(());
+        }
+    }
+
+    /// Switch to the light or dark theme, e.g. when the desktop preference changes
+    ///
+    /// Applications that enabled mode 2031 are notified right away; others
+    /// see the new colors through OSC 10/11 queries.
+    pub fn set_color_scheme(&self, scheme: ColorScheme) {
+        self.color_scheme_handle().set(scheme);
+    }
+
+    /// Handle switching this terminal's color scheme from callbacks that
+    /// cannot borrow it, e.g. desktop settings notifications
+    pub fn color_scheme_handle(&self) -> ColorSchemeHandle {
+        ColorSchemeHandle {
+            grid: Arc::clone(&self.grid),
+            writer: Arc::clone(&self.writer),
+            events: self.events.clone(),
+            redraw_sender: self.redraw_sender.clone(),
+        }
     }
 
     /// Get memory usage statistics
     pub fn get_memory_usage(&self) -> crate::MemoryInfo {
         let grid_size = {
             if let Ok(grid) = self.grid.read() {
-                // Primary buffer memory
-                let primary_bytes = grid.cells.len() * std::mem::size_of::<crate::ansi::Cell>();
-
-                // Alternate buffer memory
-                let alternate_bytes = grid.alternate_cells.len() * std::mem::size_of::<crate::ansi::Cell>();
+                // Screen buffers: the shared blank row plus rows written to
+                let primary_bytes = grid.cells.memory_bytes();
+                let alternate_bytes = grid.alternate_cells.memory_bytes();
 
                 // Scrollback buffer memory
-                let scrollback_bytes = grid.scrollback.len() * std::mem::size_of::<crate::ansi::Cell>();
+                let scrollback_bytes = grid.scrollback_bytes();
 
-                (primary_bytes, alternate_bytes, scrollback_bytes)
+                // Scrollback search index
+                let index_bytes = grid.scrollback_index_bytes();
+
+                (primary_bytes, alternate_bytes, scrollback_bytes, index_bytes)
             } else {
-                (0, 0, 0)
+                (0, 0, 0, 0)
             }
         };
 
@@ -348,7 +1026,8 @@ impl VteTerminalCore {
             primary_buffer_bytes: grid_size.0,
             alternate_buffer_bytes: grid_size.1,
             scrollback_buffer_bytes: grid_size.2,
-            total_grid_bytes: grid_size.0 + grid_size.1 + grid_size.2,
+            scrollback_index_bytes: grid_size.3,
+            total_grid_bytes: grid_size.0 + grid_size.1 + grid_size.2 + grid_size.3,
         }
     }
 
@@ -357,15 +1036,14 @@ impl VteTerminalCore {
         if let Ok(mut grid) = self.grid.write() {
             // Trim scrollback to configured limit
             let max_scroll = crate::constants::SCROLLBACK_LIMIT;
-            if grid.scrollback.len() > max_scroll * grid.cols {
+            if grid.scrollback_rows() > max_scroll {
                 let keep_rows = max_scroll;
-                let new_len = keep_rows * grid.cols;
-                grid.scrollback.truncate(new_len);
-                grid.scrollback.shrink_to_fit();
+                grid.truncate_scrollback(keep_rows);
+                grid.shrink_scrollback();
                 debug!("Trimmed scrollback buffer to {} lines", keep_rows);
             }
 
-            grid.scrollback.shrink_to_fit();
+            grid.shrink_scrollback();
         } else {
             warn!("Failed to access grid for memory cleanup");
         }
@@ -376,17 +1054,14 @@ impl VteTerminalCore {
         if let Ok(mut grid) = self.grid.write() {
             // Automatically enforce scrollback limits during normal operation
             let max_scroll = crate::constants::SCROLLBACK_LIMIT;
-            let scrollback_rows = grid.scrollback.len() / grid.cols;
+            let scrollback_rows = grid.scrollback_rows();
             if scrollback_rows > max_scroll {
                 let keep_rows = max_scroll;
-                let new_len = keep_rows * grid.cols;
-                grid.scrollback.resize(new_len, crate::ansi::Cell::default());
-                // Note: We use resize instead of truncate to avoid bounds issues
-                // and fill with default cells since scrollback is a flat vector
+                grid.truncate_scrollback(keep_rows);
 
                 // Only shrink if significantly over limit to avoid frequent allocations
                 if scrollback_rows > max_scroll + 50 {
-                    grid.scrollback.shrink_to_fit();
+                    grid.shrink_scrollback();
                 }
 
                 trace!("Auto-trimmed scrollback buffer to {} lines", keep_rows);
@@ -396,8 +1071,8 @@ impl VteTerminalCore {
 
     /// Check if PTY process is still alive (for timeout detection)
     pub fn is_pty_alive(&self) -> bool {
-        if let Ok(pair_guard) = self.pty_pair.read() {
-            if let Some(ref pair) = *pair_guard {
+        if let Ok(pair_guard) = self.pty_pair.lock() {
+            if let Some(ref _pair) = *pair_guard {
                 // Check if we can still write to the PTY
                 if let Ok(mut writer) = self.writer.try_lock() {
                     // Try a no-op write to test if PTY is responsive
@@ -415,31 +1090,468 @@ impl VteTerminalCore {
         }
     }
 
+    /// Receiver for terminal events (diagnostics, state changes)
+    pub fn events(&self) -> async_channel::Receiver<TerminalEvent> {
+        self.events.subscribe()
+    }
+
+    /// Publishing handle for events that backends detect, e.g. link hover
+    pub fn event_emitter(&self) -> EventEmitter {
+        self.events.clone()
+    }
+
+    /// Answer a `TerminalEvent::ClipboardReadRequest` with the clipboard
+    /// `text` once the user allowed it, or `None` to refuse
+    ///
+    /// Unknown or already answered requests are ignored.
+    pub fn answer_clipboard_request(&self, id: u64, text: Option<&str>) -> Result<(), TerminalError> {
+        let reply = self.grid.write()
+            .map_err(|e| TerminalError::GridLockError { message: format!("Grid lock poisoned: {}", e) })?
+            .answer_clipboard_request(id, text);
+        match reply {
+            Some(reply) => self.input_sink().send(reply.as_bytes()),
+            None => Ok(()),
+        }
+    }
+
+    /// Answer a `TerminalEvent::OscPermissionRequest`: run the held sequence
+    /// if `allow`, log it as denied otherwise
+    ///
+    /// Unknown or already answered requests are ignored.
+    pub fn answer_osc_request(&self, id: u64, allow: bool) -> Result<(), TerminalError> {
+        let (ran, responses) = {
+            let mut grid = self.grid.write()
+                .map_err(|e| TerminalError::GridLockError { message: format!("Grid lock poisoned: {}", e) })?;
+            let ran = grid.answer_osc_request(id, allow);
+            (ran, grid.take_responses())
+        };
+        if !responses.is_empty() {
+            self.input_sink().send(responses.as_bytes())?;
+        }
+        if ran {
+            if let Some(ref sender) = self.redraw_sender {
+                let _ = sender.try_send(());
+            }
+        }
+        Ok(())
+    }
+
+    /// OSC sequences denied by `SecurityConfig::osc_policy` or the user,
+    /// oldest first (see `osc_policy`)
+    pub fn denied_osc_log(&self) -> Vec<DeniedOsc> {
+        self.grid.read().map(|g| g.denied_osc()).unwrap_or_default()
+    }
+
+    /// Locale variables the child process was spawned with, after
+    /// `TerminalConfig::locale` was applied to the inherited environment
+    pub fn child_locale(&self) -> &ChildLocale {
+        &self.child_locale
+    }
+
+    /// Decode the child's output as `encoding` from now on, or detect it
+    /// again with `None` (see `TerminalConfig::encoding`)
+    pub fn set_encoding(&self, encoding: Option<Encoding>) -> Result<(), TerminalError> {
+        self.grid.write()
+            .map_err(|e| TerminalError::GridLockError { message: format!("Grid lock poisoned: {}", e) })?
+            .set_encoding(encoding);
+        Ok(())
+    }
+
+    /// Redraw requests from the reader and the core's other threads
+    ///
+    /// Requests coalesce: the channel holds at most one, which stands for
+    /// every change made before the embedder takes it.
+    pub fn redraw_receiver(&self) -> async_channel::Receiver<()> {
+        self.redraw_receiver.clone()
+    }
+
+    /// Pace the reader against the embedder's frames
+    ///
+    /// Once this is called, the reader waits between slices of a large read
+    /// until the embedder reports drawing the frame it requested (see
+    /// `parse_budget`). Embedders call [`FrameAck::presented`] after each frame.
+    pub fn frame_ack(&self) -> FrameAck {
+        self.frame_ack.attach();
+        self.frame_ack.clone()
+    }
+
     /// Set redraw callback sender for backend communication
     pub fn set_redraw_sender(&mut self, sender: async_channel::Sender<()>) {
         self.redraw_sender = Some(sender);
     }
 
-    /// Process incoming data with bracketed paste awareness
-    /// If bracketed paste mode is enabled, data between start/end sequences is treated as a paste
-    pub fn handle_paste_data(&mut self, _data: &[u8]) -> Result<(), TerminalError> {
-        // In a real implementation, we'd track paste state and handle start/end markers
-        // For now, just ensure we can lock the grid (commits the access)
-        // Ensure grid lock can be acquired (validates grid accessibility)
-        let _grid_guard = self.grid.write().map_err(|_| TerminalError::GridLockError {
-            message: "Grid lock poisoned in paste".to_string()
-        })?;
-        // The actual parsing is handled at the terminal level by send_input
+    /// Paste data into the terminal, wrapped in bracketed paste markers when the
+    /// application enabled bracketed paste mode
+    pub fn handle_paste_data(&mut self, data: &[u8]) -> Result<(), TerminalError> {
+        self.paste(&String::from_utf8_lossy(data))
+    }
+
+    /// Paste `text` as from the clipboard: wrapped in bracketed paste markers
+    /// (with any embedded end marker removed) when the application enabled
+    /// bracketed paste mode, stripped of control sequences otherwise
+    pub fn paste(&self, text: &str) -> Result<(), TerminalError> {
+        self.commit_text(text, TextSource::Paste)
+    }
+
+    /// Send a synthesized key press, encoded like real keyboard input
+    ///
+    /// Special keys follow the application's cursor and keypad modes, and
+    /// character keys feed local echo prediction as typed text does. Keys
+    /// without an encoding are ignored.
+    pub fn send_key(&self, key: Key) -> Result<(), TerminalError> {
+        let bytes = {
+            let mut grid = self.grid.write()
+                .map_err(|_| TerminalError::GridLockError { message: "Grid lock poisoned in key input".to_string() })?;
+            let bytes = grid.encode_key(key);
+            if !matches!(key, Key::Char(_)) {
+                grid.predict_echo("", TextSource::Key);
+            }
+            bytes
+        };
+        if let Key::Char(ch) = key {
+            let mut buf = [0u8; 4];
+            return self.commit_text(ch.encode_utf8(&mut buf), TextSource::Key);
+        }
+        match bytes {
+            // Queued behind a paste in flight, like typed text
+            Some(bytes) => self.paste.send_input(&bytes),
+            None => Ok(()),
+        }
+    }
+
+    /// Start or stop recording key events with their encoding and the modes
+    /// in effect (see `input_trace`)
+    pub fn set_input_tracing(&self, enabled: bool) {
+        match self.grid.write() {
+            Ok(mut grid) => grid.set_input_tracing(enabled),
+            Err(e) => warn!("Failed to set input tracing (lock poisoned): {}", e),
+        }
+    }
+
+    /// The key events traced so far, one per line, oldest first
+    pub fn input_trace_dump(&self) -> String {
+        self.grid.read().map(|g| g.input_trace().dump()).unwrap_or_default()
+    }
+
+    /// Where the cursor is in the viewport, with its cell in pixels for the
+    /// font metrics of `cache` and the configured spacing, e.g. to place an
+    /// autocomplete popover; `None` while scrolled back past the cursor
+    pub fn cursor_screen_position(&self, cache: &DrawingCache) -> Option<CursorPosition> {
+        let grid = self.grid.read().ok()?;
+        let geometry = cache.cell_geometry().with_config_spacing(&grid.config);
+        grid.cursor_screen_position(&geometry)
+    }
+
+    /// Cell size in pixels, so sixel images cover the right cells; call it
+    /// whenever the font metrics change
+    pub fn set_cell_pixel_size(&self, width: usize, height: usize) {
+        match self.grid.write() {
+            Ok(mut grid) => grid.set_cell_pixel_size(width, height),
+            Err(e) => warn!("Failed to set cell pixel size (lock poisoned): {}", e),
+        }
+    }
+
+    /// Replace the bell policy, e.g. with the one of the profile the
+    /// terminal switched to
+    pub fn set_bell_policy(&self, policy: BellPolicy) {
+        match self.grid.write() {
+            Ok(mut grid) => grid.set_bell_policy(policy),
+            Err(e) => warn!("Failed to set bell policy (lock poisoned): {}", e),
+        }
+    }
+
+    /// Emit `TerminalEvent::Silence` after `threshold` without output, and
+    /// `TerminalEvent::Activity` when it resumes; `None` stops monitoring
+    pub fn set_silence_threshold(&self, threshold: Option<std::time::Duration>) {
+        match self.grid.write() {
+            Ok(mut grid) => grid.set_silence_threshold(threshold),
+            Err(e) => {
+                warn!("Failed to set silence threshold (lock poisoned): {}", e);
+                return;
+            }
+        }
+        if threshold.is_some() {
+            self.start_timer();
+        }
+    }
+
+    /// Send text on the user's behalf, e.g. a command from an IDE
+    ///
+    /// Multi-line text is pasted, wrapped in bracketed paste markers when the
+    /// application enabled them; a single line is typed as it is, so
+    /// `send_text("make\r")` runs the command.
+    pub fn send_text(&self, text: &str) -> Result<(), TerminalError> {
+        self.commit_text(text, TextSource::for_sent_text(text))
+    }
+
+    /// Send committed text (typed, IME, paste or synthetic) to the PTY
+    ///
+    /// Bracketed paste wrapping is applied to `TextSource::Paste` only, based
+    /// on the mode the application currently has enabled. Pastes above
+    /// `PASTE_STREAM_THRESHOLD` bytes return before they are fully written;
+    /// `TerminalEvent::PasteFinished` reports when they are.
+    pub fn commit_text(&self, text: &str, source: TextSource) -> Result<(), TerminalError> {
+        let (bytes, predicted) = {
+            let mut grid = self.grid.write()
+                .map_err(|_| TerminalError::GridLockError { message: "Grid lock poisoned in text input".to_string() })?;
+            grid.predict_echo(text, source);
+            let bytes = encode_text(text, source, grid.is_bracketed_paste_mode());
+            grid.trace_text(text, source, &bytes);
+            (bytes, !grid.predicted_cells().is_empty())
+        };
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        if source == TextSource::Paste {
+            self.paste.send(bytes)?;
+        } else {
+            self.paste.send_input(&bytes)?;
+        }
+        // Show the local echo without waiting for output
+        if predicted {
+            if let Some(ref sender) = self.redraw_sender {
+                let _ = sender.try_send(());
+            }
+        }
         Ok(())
     }
 }
 
+/// PTY size bookkeeping for debounced resizes
+///
+/// Interactive resizing changes the size many times a second. Each PTY
+/// resize sends the child a SIGWINCH, and full-screen apps redraw on every
+/// one, so only the size that has been stable for the debounce interval is
+/// passed on.
+#[derive(Debug)]
+struct PtyResize {
+    /// Size the PTY was last resized to
+    applied: (usize, usize),
+    /// Latest requested size and when it was requested
+    pending: Option<((usize, usize), std::time::Instant)>,
+    /// A waiter thread will apply `pending`
+    waiting: bool,
+}
+
+/// What a PTY resize waiter does next
+#[derive(Debug, PartialEq)]
+enum PtyResizeStep {
+    /// The size changed recently; check again after this long
+    Wait(std::time::Duration),
+    /// The size settled; resize the PTY to it
+    Apply(usize, usize),
+    /// Nothing left to do; the PTY already has the final size
+    Done,
+}
+
+impl PtyResize {
+    fn new(size: (usize, usize)) -> Self {
+        Self { applied: size, pending: None, waiting: false }
+    }
+
+    /// Record `size` as the latest request; true if no waiter is running yet
+    fn request(&mut self, size: (usize, usize), now: std::time::Instant) -> bool {
+        self.pending = Some((size, now));
+        !std::mem::replace(&mut self.waiting, true)
+    }
+
+    /// Advance the waiter at `now`, taking the pending size once it has been
+    /// stable for `delay`
+    fn step(&mut self, now: std::time::Instant, delay: std::time::Duration) -> PtyResizeStep {
+        let Some((size, requested)) = self.pending else {
+            self.waiting = false;
+            return PtyResizeStep::Done;
+        };
+        let quiet = now.saturating_duration_since(requested);
+        if quiet < delay {
+            return PtyResizeStep::Wait(delay - quiet);
+        }
+        self.pending = None;
+        self.waiting = false;
+        if size == self.applied {
+            return PtyResizeStep::Done;
+        }
+        self.applied = size;
+        PtyResizeStep::Apply(size.0, size.1)
+    }
+}
+
+/// Cloneable handle holding and releasing a terminal's output
+#[derive(Clone)]
+pub struct OutputHoldHandle {
+    grid: Arc<RwLock<Grid>>,
+    events: EventEmitter,
+    redraw_sender: Option<async_channel::Sender<()>>,
+}
+
+impl OutputHoldHandle {
+    /// See `VteTerminalCore::set_output_held`
+    pub fn set(&self, held: bool) {
+        let changed = match self.grid.write() {
+            Ok(mut grid) => {
+                let changed = grid.is_output_held() != held;
+                grid.set_output_held(held);
+                changed
+            }
+            Err(e) => {
+                warn!("Failed to set output hold (lock poisoned): {}", e);
+                return;
+            }
+        };
+
+        if changed {
+            self.events.emit(TerminalEvent::OutputHold { held });
+            if let Some(ref sender) = self.redraw_sender {
+                let _ = sender.try_send(());
+            }
+        }
+    }
+
+    /// Whether output is held (scroll lock)
+    pub fn is_held(&self) -> bool {
+        self.grid.read().map(|grid| grid.is_output_held()).unwrap_or(false)
+    }
+}
+
+/// Cloneable handle switching a terminal's color scheme
+#[derive(Clone)]
+pub struct ColorSchemeHandle {
+    grid: Arc<RwLock<Grid>>,
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+    events: EventEmitter,
+    redraw_sender: Option<async_channel::Sender<()>>,
+}
+
+impl ColorSchemeHandle {
+    /// See `VteTerminalCore::set_color_scheme`
+    pub fn set(&self, scheme: ColorScheme) {
+        let replies = match self.grid.write() {
+            Ok(mut grid) => {
+                if !grid.set_color_scheme(scheme) {
+                    return;
+                }
+                grid.take_responses()
+            }
+            Err(e) => {
+                warn!("Failed to set color scheme (lock poisoned): {}", e);
+                return;
+            }
+        };
+
+        if !replies.is_empty() {
+            VteTerminalCore::write_responses(&self.writer, replies.as_bytes(), &self.events);
+        }
+        if let Some(ref sender) = self.redraw_sender {
+            let _ = sender.try_send(());
+        }
+    }
+}
+
+/// Cloneable handle resizing a terminal's grid and PTY
+#[derive(Clone)]
+pub struct ResizeHandle {
+    grid: Arc<RwLock<Grid>>,
+    pty_pair: Arc<Mutex<Option<portable_pty::PtyPair>>>,
+    redraw_sender: Option<async_channel::Sender<()>>,
+    pty_resize: Arc<Mutex<PtyResize>>,
+}
+
+impl ResizeHandle {
+    /// Cell size in pixels, so sixel images cover the right cells (see
+    /// `VteTerminalCore::set_cell_pixel_size`)
+    pub fn set_cell_pixel_size(&self, width: usize, height: usize) {
+        match self.grid.write() {
+            Ok(mut grid) => grid.set_cell_pixel_size(width, height),
+            Err(e) => warn!("Failed to set cell pixel size (lock poisoned): {}", e),
+        }
+    }
+
+    /// Resize the grid (rewrapping lines) and the PTY
+    ///
+    /// The grid follows immediately; the PTY follows once the size has been
+    /// stable for `TerminalConfig::resize_debounce_ms`, so intermediate
+    /// sizes never reach the child. The final size is always applied.
+    pub fn resize(&self, cols: usize, rows: usize) {
+        debug!("Resizing terminal to {}x{} with rewrapping", cols, rows);
+
+        // Update grid first with rewrapping logic
+        let (debounce_ms, now) = if let Ok(mut g) = self.grid.write() {
+            g.resize_with_rewrap(cols, rows);
+            (g.config.resize_debounce_ms, g.now())
+        } else {
+            warn!("Failed to resize grid with rewrap - lock error");
+            return;
+        };
+
+        // Update PTY size, now or once resizing settles
+        let Ok(mut pty_resize) = self.pty_resize.lock() else {
+            warn!("Could not access PTY resize state");
+            return;
+        };
+        if debounce_ms == 0 {
+            pty_resize.pending = None;
+            pty_resize.applied = (cols, rows);
+            drop(pty_resize);
+            self.resize_pty(cols, rows);
+        } else if pty_resize.request((cols, rows), now) {
+            drop(pty_resize);
+            self.start_pty_resize_waiter(std::time::Duration::from_millis(debounce_ms));
+        }
+
+        // Notify backend of resize
+        if let Some(ref sender) = self.redraw_sender {
+            if let Err(async_channel::TrySendError::Closed(_)) = sender.try_send(()) {
+                warn!("Failed to send resize redraw signal: channel closed");
+            }
+        }
+    }
+
+    /// Wait until the requested size has been stable for `delay` by the
+    /// grid's clock, then resize the PTY
+    fn start_pty_resize_waiter(&self, delay: std::time::Duration) {
+        let handle = self.clone();
+        thread::spawn(move || loop {
+            let Ok(now) = handle.grid.read().map(|g| g.now()) else { return };
+            let step = match handle.pty_resize.lock() {
+                Ok(mut pty_resize) => pty_resize.step(now, delay),
+                Err(_) => return,
+            };
+            match step {
+                PtyResizeStep::Wait(remaining) => thread::sleep(remaining),
+                PtyResizeStep::Apply(cols, rows) => {
+                    handle.resize_pty(cols, rows);
+                    return;
+                }
+                PtyResizeStep::Done => return,
+            }
+        });
+    }
+
+    fn resize_pty(&self, cols: usize, rows: usize) {
+        debug!("Resizing PTY to {}x{}", cols, rows);
+        if let Ok(pair_guard) = self.pty_pair.lock() {
+            if let Some(ref pair) = *pair_guard {
+                if let Err(e) = pair.master.resize(PtySize {
+                    rows: rows as u16,
+                    cols: cols as u16,
+                    pixel_width: 0,
+                    pixel_height: 0,
+                }) {
+                    warn!("Failed to resize PTY: {}", e);
+                }
+            }
+        } else {
+            warn!("Could not access PTY for resize");
+        }
+    }
+}
+
 impl Drop for VteTerminalCore {
     fn drop(&mut self) {
         info!("Cleaning up VteTerminalCore resources...");
 
         // Clean up PTY resources (may already be handled by child process termination)
-        if let Ok(mut pair_guard) = self.pty_pair.write() {
+        if let Ok(mut pair_guard) = self.pty_pair.lock() {
             if pair_guard.is_some() {
                 debug!("Dropping PTY pair reference");
                 *pair_guard = None;
@@ -453,8 +1565,8 @@ impl Drop for VteTerminalCore {
         // Force cleanup of Grid resources
         if let Ok(mut grid) = self.grid.write() {
             // Clear scrollback buffer to free memory immediately
-            grid.scrollback.clear();
-            grid.scrollback.shrink_to_fit();
+            grid.truncate_scrollback(0);
+            grid.shrink_scrollback();
             debug!("Cleared scrollback buffer on drop");
         } else {
             warn!("Could not access grid for cleanup during drop");
@@ -464,6 +1576,224 @@ impl Drop for VteTerminalCore {
     }
 }
 
+/// Whether a PTY read error means the child side is gone: Linux reports
+/// that as EIO on the master rather than as end of file
+fn is_pty_closed(e: &std::io::Error) -> bool {
+    #[cfg(unix)]
+    return e.raw_os_error() == Some(nix::errno::Errno::EIO as i32);
+    #[cfg(not(unix))]
+    return false;
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spawn_with_initial_size() {
+        let terminal = VteTerminalCore::new_with_size(132, 40).expect("Failed to create terminal for testing");
+        let grid = terminal.grid.read().unwrap();
+        assert_eq!((grid.cols, grid.rows), (132, 40));
+        drop(grid);
+
+        // The shell sees the same size from the start
+        let pair = terminal.pty_pair.lock().unwrap();
+        let size = pair.as_ref().unwrap().master.get_size().unwrap();
+        assert_eq!((size.cols, size.rows), (132, 40));
+    }
+
+    #[test]
+    fn test_pty_resize_applies_final_size_once_stable() {
+        let delay = std::time::Duration::from_millis(50);
+        let start = std::time::Instant::now();
+        let at = |ms: u64| start + std::time::Duration::from_millis(ms);
+        let mut pty_resize = PtyResize::new((80, 24));
+
+        // Only the first request of a burst starts a waiter
+        assert!(pty_resize.request((90, 24), at(0)));
+        assert!(!pty_resize.request((100, 30), at(20)));
+        assert_eq!(pty_resize.step(at(50), delay), PtyResizeStep::Wait(std::time::Duration::from_millis(20)));
+        assert!(!pty_resize.request((110, 35), at(60)));
+        assert_eq!(pty_resize.step(at(110), delay), PtyResizeStep::Apply(110, 35));
+
+        // Dragging back to the applied size ends without a SIGWINCH
+        assert!(pty_resize.request((120, 40), at(200)));
+        assert!(!pty_resize.request((110, 35), at(210)));
+        assert_eq!(pty_resize.step(at(260), delay), PtyResizeStep::Done);
+        assert!(pty_resize.request((120, 40), at(300)));
+    }
+
+    /// PTY output handed over by the test, one read per message
+    struct ChannelReader(std::sync::mpsc::Receiver<Vec<u8>>);
+
+    impl Read for ChannelReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.0.recv() {
+                Ok(data) => {
+                    buf[..data.len()].copy_from_slice(&data);
+                    Ok(data.len())
+                }
+                Err(_) => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn test_reader_parses_one_slice_per_drawn_frame() {
+        // Each line is one 7-byte slice
+        let config = crate::config::TerminalConfig::default().with_parse_slice(7, u64::MAX);
+        let (output, reader) = std::sync::mpsc::channel();
+        let terminal = VteTerminalCore::with_io(config, 20, 10, ChannelReader(reader), std::io::sink());
+        let frames = terminal.frame_ack();
+        let redraws = terminal.redraw_receiver();
+
+        let text: String = (0..6).map(|i| format!("line{}\r\n", i)).collect();
+        output.send(text.into_bytes()).unwrap();
+
+        // Every requested frame shows one more slice: drawing takes a while,
+        // and the reader waits for the frame instead of running ahead
+        let mut rows = Vec::new();
+        while rows.last() != Some(&6) {
+            redraws.recv_blocking().unwrap();
+            thread::sleep(std::time::Duration::from_millis(5));
+            rows.push(terminal.grid.read().unwrap().row);
+            frames.presented();
+        }
+        assert_eq!(rows, [1, 2, 3, 4, 5, 6]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_eio_reports_child_exit() {
+        /// Reads like a Linux PTY master after the child exited
+        struct ClosedPty;
+
+        impl Read for ClosedPty {
+            fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::from_raw_os_error(nix::errno::Errno::EIO as i32))
+            }
+        }
+
+        let config = crate::config::TerminalConfig::default();
+        let terminal = VteTerminalCore::with_io(config, 20, 5, ClosedPty, std::io::sink());
+        let events = terminal.event_emitter().subscribe();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(2);
+        let mut exited = false;
+        while !exited && std::time::Instant::now() < deadline {
+            match events.try_recv() {
+                Ok(TerminalEvent::ChildExited) => exited = true,
+                Ok(TerminalEvent::Error { .. }) => panic!("EIO reported as a read error"),
+                _ => thread::sleep(std::time::Duration::from_millis(5)),
+            }
+        }
+        assert!(exited);
+    }
+
+    #[test]
+    fn test_timer_runs_only_while_enabled() {
+        let config = crate::config::TerminalConfig::default();
+        let terminal = VteTerminalCore::with_io(config, 20, 5, std::io::empty(), std::io::sink());
+        let running = || terminal.timer.load(Ordering::Acquire);
+        assert!(!running());
+
+        terminal.set_silence_threshold(Some(std::time::Duration::from_secs(60)));
+        assert!(running());
+
+        // Clearing the threshold stops the timer at its next check
+        terminal.set_silence_threshold(None);
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(2);
+        while running() && std::time::Instant::now() < deadline {
+            thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert!(!running());
+        terminal.set_silence_threshold(Some(std::time::Duration::from_secs(60)));
+        assert!(running());
+    }
+
+    #[test]
+    fn test_one_timer_reports_silence_and_parser_health() {
+        let config = crate::config::TerminalConfig::default().with_parser_health(20).with_silence_threshold(30);
+        let (output, reader) = std::sync::mpsc::channel();
+        let terminal = VteTerminalCore::with_io(config, 20, 5, ChannelReader(reader), std::io::sink());
+        let events = terminal.event_emitter().subscribe();
+        output.send(b"\x1b[1mbusy\x1b[0m".to_vec()).unwrap();
+
+        let (mut silence, mut health) = (false, false);
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(2);
+        while !(silence && health) && std::time::Instant::now() < deadline {
+            match events.try_recv() {
+                Ok(TerminalEvent::Silence { .. }) => silence = true,
+                Ok(TerminalEvent::ParserHealth { .. }) => health = true,
+                _ => thread::sleep(std::time::Duration::from_millis(5)),
+            }
+        }
+        assert!(silence && health);
+
+        // Parser health keeps the timer running without a silence threshold
+        terminal.set_silence_threshold(None);
+        thread::sleep(std::time::Duration::from_millis(SILENCE_POLL_INTERVAL_MS * 2));
+        assert!(terminal.timer.load(Ordering::Acquire));
+    }
+
+    #[test]
+    fn test_read_grid_lends_a_view() {
+        let config = crate::config::TerminalConfig::default();
+        let terminal = VteTerminalCore::with_io(config, 20, 5, std::io::empty(), std::io::sink());
+        assert_eq!(terminal.read_grid(|view| view.size()), Some((20, 5)));
+    }
+
+    #[test]
+    fn test_output_hold_handle_reports_changes() {
+        let config = crate::config::TerminalConfig::default();
+        let terminal = VteTerminalCore::with_io(config, 20, 5, std::io::empty(), std::io::sink());
+        let events = terminal.event_emitter().subscribe();
+        let holds = || std::iter::from_fn(|| events.try_recv().ok())
+            .filter_map(|event| match event {
+                TerminalEvent::OutputHold { held } => Some(held),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        // A handle acts like the terminal, reporting only changes
+        let handle = terminal.output_hold_handle();
+        handle.set(!handle.is_held());
+        handle.set(true);
+        assert!(terminal.is_output_held());
+        terminal.set_output_held(false);
+        assert_eq!(holds(), [true, false]);
+    }
+
+    #[test]
+    fn test_resize_debounces_pty() {
+        use crate::clock::MockClock;
+
+        let config = crate::config::TerminalConfig::default().with_resize_debounce(30);
+        let terminal = VteTerminalCore::with_config_and_size(config, 80, 24).expect("Failed to create terminal for testing");
+        let clock = MockClock::new();
+        terminal.grid.write().unwrap().set_clock(Arc::new(clock.clone()));
+        let pty_size = || {
+            let pair = terminal.pty_pair.lock().unwrap();
+            let size = pair.as_ref().unwrap().master.get_size().unwrap();
+            (size.cols, size.rows)
+        };
+
+        for cols in [90, 100, 110] {
+            terminal.resize(cols, 30);
+        }
+        // The grid follows at once, the PTY only after the size settles
+        let grid_size = terminal.grid.read().map(|g| (g.cols, g.rows)).unwrap();
+        assert_eq!(grid_size, (110, 30));
+        assert_eq!(pty_size(), (80, 24));
+
+        // By the grid's clock the size has not been stable for 30ms yet
+        clock.advance(std::time::Duration::from_millis(29));
+        assert_eq!(pty_size(), (80, 24));
+
+        clock.advance(std::time::Duration::from_millis(1));
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(2);
+        while pty_size() != (110, 30) && std::time::Instant::now() < deadline {
+            thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert_eq!(pty_size(), (110, 30));
+    }
 }
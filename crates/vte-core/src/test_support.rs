@@ -0,0 +1,39 @@
+// src/test_support.rs
+//! Fixtures shared by the unit tests, and by the integration tests through
+//! `#[path]`
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+/// Writer recording every write, standing in for the PTY so tests can
+/// inspect the bytes sent to the child
+#[derive(Clone, Default)]
+pub struct SharedBuffer(Arc<Mutex<Vec<Vec<u8>>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().push(buf.to_vec());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SharedBuffer {
+    /// Everything written so far
+    pub fn contents(&self) -> Vec<u8> {
+        self.0.lock().unwrap().concat()
+    }
+
+    /// Everything written so far, emptying the buffer
+    pub fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut *self.0.lock().unwrap()).concat()
+    }
+
+    /// The writes made so far, one per `write` call
+    pub fn writes(&self) -> Vec<Vec<u8>> {
+        self.0.lock().unwrap().clone()
+    }
+}
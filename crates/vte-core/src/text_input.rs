@@ -0,0 +1,104 @@
+// src/text_input.rs
+//! Text input pipeline: committed strings to PTY bytes
+//!
+//! Text reaches the terminal from several places: characters typed directly,
+//! strings committed by an input method (IME, dead keys, compose sequences),
+//! clipboard pastes and synthetic input from embedders. All of them end up as
+//! UTF-8 written to the PTY, but only pastes are subject to bracketed paste
+//! wrapping and sanitization; typed and committed text is sent as-is.
+//! Text an embedder sends with `VteTerminalCore::send_text` counts as a paste
+//! when it spans lines ([`TextSource::for_sent_text`]), so a shell with
+//! bracketed paste does not run it line by line; a single command ending in
+//! a line break is sent as typed, so it runs.
+
+use crate::security::sanitize_paste;
+
+/// Where a piece of text input comes from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextSource {
+    /// Character produced directly by a key press
+    Key,
+    /// String committed by an input method (IME, dead keys, compose)
+    InputMethod,
+    /// Clipboard or drag-and-drop paste
+    Paste,
+    /// Text injected programmatically by the embedder
+    Synthetic,
+}
+
+impl TextSource {
+    /// Source of `text` sent by an embedder: a paste if a line break is
+    /// followed by more text, synthetic input otherwise (`"cmd\r"` included)
+    pub fn for_sent_text(text: &str) -> Self {
+        if text.trim_end_matches(['\n', '\r']).contains(['\n', '\r']) {
+            TextSource::Paste
+        } else {
+            TextSource::Synthetic
+        }
+    }
+}
+
+/// Encode text for the PTY
+///
+/// Line endings are normalized to carriage returns, which is what the Enter
+/// key sends. Pastes are wrapped in `ESC [200~` / `ESC [201~` when the
+/// application enabled bracketed paste mode (DECSET 2004), or stripped of
+/// control sequences otherwise.
+pub fn encode_text(text: &str, source: TextSource, bracketed_paste: bool) -> Vec<u8> {
+    if text.is_empty() {
+        return Vec::new();
+    }
+    let text = normalize_newlines(text);
+    match source {
+        TextSource::Paste => sanitize_paste(&text, bracketed_paste).into_bytes(),
+        TextSource::Key | TextSource::InputMethod | TextSource::Synthetic => text.into_bytes(),
+    }
+}
+
+/// Convert `\r\n` and `\n` line endings to `\r`
+fn normalize_newlines(text: &str) -> String {
+    text.replace("\r\n", "\r").replace('\n', "\r")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_committed_text_is_utf8() {
+        assert_eq!(encode_text("é", TextSource::InputMethod, false), "é".as_bytes());
+        assert_eq!(encode_text("日本語", TextSource::InputMethod, true), "日本語".as_bytes());
+        assert_eq!(encode_text("😀", TextSource::Key, false), "😀".as_bytes());
+        assert!(encode_text("", TextSource::Paste, true).is_empty());
+    }
+
+    #[test]
+    fn test_bracketed_paste_only_for_paste() {
+        // Committed text never gets paste markers, even in bracketed mode
+        assert_eq!(encode_text("ls", TextSource::InputMethod, true), b"ls");
+        assert_eq!(encode_text("ls", TextSource::Synthetic, true), b"ls");
+        assert_eq!(encode_text("ls", TextSource::Paste, true), b"\x1b[200~ls\x1b[201~");
+        assert_eq!(encode_text("ls", TextSource::Paste, false), b"ls");
+    }
+
+    #[test]
+    fn test_sent_text_is_pasted_when_multiline() {
+        assert_eq!(TextSource::for_sent_text("ls -l"), TextSource::Synthetic);
+        assert_eq!(TextSource::for_sent_text("make\nmake install"), TextSource::Paste);
+        assert_eq!(TextSource::for_sent_text("ls\r"), TextSource::Synthetic);
+        assert_eq!(TextSource::for_sent_text("make\r\n"), TextSource::Synthetic);
+        assert_eq!(TextSource::for_sent_text("cd /\rls\r"), TextSource::Paste);
+    }
+
+    #[test]
+    fn test_newlines_become_carriage_returns() {
+        assert_eq!(encode_text("a\nb\r\nc", TextSource::Synthetic, false), b"a\rb\rc");
+        assert_eq!(encode_text("a\nb", TextSource::Paste, true), b"\x1b[200~a\rb\x1b[201~");
+    }
+
+    #[test]
+    fn test_unbracketed_paste_keeps_non_ascii() {
+        let encoded = encode_text("caf\u{e9} \u{20ac}5 \u{1F600}\x1b[31m", TextSource::Paste, false);
+        assert_eq!(String::from_utf8(encoded).unwrap(), "caf\u{e9} \u{20ac}5 \u{1F600}");
+    }
+}
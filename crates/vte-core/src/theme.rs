@@ -0,0 +1,91 @@
+// src/theme.rs
+//! Light and dark themes following the desktop color scheme
+//!
+//! With `TerminalConfig::themes` set, switching the color scheme (from the
+//! desktop preference or `VteTerminalCore::set_color_scheme`) replaces the
+//! default foreground and background. Applications see the change: OSC 10/11
+//! queries answer with the new colors, and those that enabled mode 2031 are
+//! sent `CSI ? 997 ; 1 n` (dark) or `CSI ? 997 ; 2 n` (light).
+
+use crate::ansi::Color;
+
+/// Desktop light/dark preference
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ColorScheme {
+    Light,
+    /// The built-in colors (white on transparent) are a dark theme
+    #[default]
+    Dark,
+}
+
+impl ColorScheme {
+    /// Mode 2031 report, `CSI ? 997 ; Ps n`
+    pub fn report(self) -> &'static str {
+        match self {
+            ColorScheme::Dark => "\x1b[?997;1n",
+            ColorScheme::Light => "\x1b[?997;2n",
+        }
+    }
+}
+
+/// Default colors of one theme
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Theme {
+    pub foreground: Color,
+    pub background: Color,
+}
+
+impl Theme {
+    pub const fn new(foreground: Color, background: Color) -> Self {
+        Self { foreground, background }
+    }
+}
+
+/// A light and a dark theme, one per color scheme
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ThemePair {
+    pub light: Theme,
+    pub dark: Theme,
+}
+
+impl ThemePair {
+    /// Theme to use under `scheme`
+    pub fn theme(&self, scheme: ColorScheme) -> Theme {
+        match scheme {
+            ColorScheme::Light => self.light,
+            ColorScheme::Dark => self.dark,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TerminalConfig;
+
+    fn themes() -> (Theme, Theme) {
+        let light = Theme::new(Color::rgb(0.1, 0.1, 0.1), Color::rgb(0.98, 0.98, 0.96));
+        let dark = Theme::new(Color::rgb(0.9, 0.9, 0.9), Color::rgb(0.12, 0.12, 0.14));
+        (light, dark)
+    }
+
+    #[test]
+    fn test_scheme_selects_theme_colors() {
+        let (light, dark) = themes();
+        let mut config = TerminalConfig::default().with_themes(light, dark);
+        assert_eq!((config.default_fg, config.default_bg), (dark.foreground, dark.background));
+
+        assert!(config.apply_color_scheme(ColorScheme::Light));
+        assert_eq!((config.default_fg, config.default_bg), (light.foreground, light.background));
+        assert!(!config.apply_color_scheme(ColorScheme::Light));
+    }
+
+    #[test]
+    fn test_scheme_without_themes_keeps_colors() {
+        let mut config = TerminalConfig::default();
+        let colors = (config.default_fg, config.default_bg);
+        assert!(config.apply_color_scheme(ColorScheme::Light));
+        assert_eq!((config.default_fg, config.default_bg), colors);
+        assert_eq!(config.color_scheme, ColorScheme::Light);
+    }
+}
@@ -3,9 +3,10 @@ use crate::drawing::CharMetrics;
 use crate::grid::Grid;
 
 /// Available cursor shapes for terminals
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub enum CursorShape {
     /// Solid block cursor
+    #[default]
     Block,
     /// Underscore cursor
     Underline,
@@ -13,6 +14,21 @@ pub enum CursorShape {
     Bar,
 }
 
+/// Mouse pointer shapes requested by the terminal
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PointerShape {
+    /// I-beam over the text area
+    #[default]
+    Text,
+    /// Hand pointer over a hyperlink or detected URL
+    Link,
+    /// Hidden while the user is typing
+    Hidden,
+    /// Shape requested by the application with OSC 22: a CSS cursor name
+    /// from `pointer::APP_POINTER_SHAPES`
+    Named(&'static str),
+}
+
 use std::sync::{Arc, RwLock, Mutex};
 use std::io::Write;
 
@@ -32,7 +48,10 @@ pub trait TextRenderer {
 
 /// Graphics rendering sub-trait
 pub trait GraphicsRenderer {
-    fn draw_sixel(&mut self, data: &[u8], x: usize, y: usize);
+    /// Composite a placed sixel image (see `Grid::visible_images`) with its
+    /// top-left corner at logical pixel (`x`, `y`); `y` is negative for an
+    /// image starting above the viewport
+    fn draw_sixel(&mut self, image: &ImageData, x: f64, y: f64);
     fn draw_image(&mut self, image: ImageData, x: usize, y: usize);
 }
 
@@ -41,6 +60,8 @@ pub trait UIRenderer {
     fn clear(&mut self);
     fn flush(&mut self);
     fn set_cursor_shape(&mut self, shape: CursorShape);
+    /// Mouse pointer shape over the terminal (see `Grid::pointer_shape`)
+    fn set_pointer_shape(&mut self, shape: PointerShape);
     /// Handle hyperlink click (OSC 8) - return true if handled
     fn handle_hyperlink(&mut self, url: &str) -> bool;
 }
@@ -50,7 +71,10 @@ pub trait InputHandler {
     fn handle_key(&mut self, key: KeyEvent, grid: &Arc<RwLock<Grid>>,
                   writer: &Arc<Mutex<Box<dyn Write + Send>>>);
     fn handle_mouse(&mut self, event: MouseEvent, grid: &Arc<RwLock<Grid>>);
-    fn handle_scroll(&mut self, delta: f64, grid: &Arc<RwLock<Grid>>);
+    /// Mouse wheel moved by `delta` notches (positive down), with or without
+    /// Shift; implementations scroll with `Grid::scroll_wheel` so the
+    /// configured speed, paging and direction apply
+    fn handle_scroll(&mut self, delta: f64, shift: bool, grid: &Arc<RwLock<Grid>>);
 }
 
 /// Event loop trait
@@ -87,6 +111,8 @@ pub trait ClipboardHandler {
 /// Image data for graphics rendering
 #[derive(Clone, Debug)]
 pub struct ImageData {
+    /// Pixels row by row, four bytes each: red, green, blue and straight
+    /// (not premultiplied) alpha
     pub data: Vec<u8>,
     pub width: usize,
     pub height: usize,
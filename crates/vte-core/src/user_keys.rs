@@ -0,0 +1,185 @@
+// src/user_keys.rs
+//! Function keys programmed by applications (DECUDK)
+//!
+//! `DCS Pc ; Pl | key/hex ; ... ST` replaces what a function key sends with
+//! Shift held (as in xterm) with a string of the application's choosing.
+//! Whatever can write to the terminal could then make a key the user
+//! presses type a command, so
+//! definitions are only accepted with `SecurityConfig::allow_user_keys`
+//! (off by default) and the table holds at most `MAX_USER_KEY_BYTES` in
+//! total. Keys are numbered by their `CSI n ~` code: 11-15 for F1-F5 as in
+//! xterm, 17-34 for F6-F20 as on the VT220.
+//!
+//! `Pl 0` locks the table: later definitions are ignored until the user
+//! unlocks it (`Grid::reset_user_keys`), as in the VT220's Set-Up. A full
+//! (RIS) or soft (DECSTR) reset clears and unlocks it as well.
+
+use crate::constants::MAX_USER_KEY_BYTES;
+use std::collections::BTreeMap;
+use tracing::debug;
+
+/// Programmed strings by function key number (1 = F1)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UserKeys {
+    keys: BTreeMap<u8, Vec<u8>>,
+    locked: bool,
+}
+
+impl UserKeys {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Function key (1 = F1) with DECUDK key number `number`
+    pub fn function_key(number: u16) -> Option<u8> {
+        let key = match number {
+            11..=15 => number - 10,
+            17..=21 => number - 11,
+            23..=26 => number - 12,
+            28 | 29 => number - 13,
+            31..=34 => number - 14,
+            _ => return None,
+        };
+        Some(key as u8)
+    }
+
+    /// Apply one DECUDK sequence: `keys` holds (key number, string) pairs,
+    /// `clear` erases every definition first, `lock` locks the table
+    /// afterwards. Returns false if the table was locked.
+    ///
+    /// An empty string removes a key's definition; unknown key numbers and
+    /// strings past the size limit are skipped.
+    pub fn define(&mut self, keys: &[(u16, String)], clear: bool, lock: bool) -> bool {
+        if self.locked {
+            return false;
+        }
+        if clear {
+            self.keys.clear();
+        }
+        for (number, value) in keys {
+            let Some(key) = Self::function_key(*number) else {
+                debug!("Ignoring user-defined key {}", number);
+                continue;
+            };
+            self.keys.remove(&key);
+            if value.is_empty() {
+                continue;
+            }
+            if self.total_bytes() + value.len() > MAX_USER_KEY_BYTES {
+                debug!("Ignoring user-defined key {}: table full", number);
+                continue;
+            }
+            self.keys.insert(key, value.as_bytes().to_vec());
+        }
+        self.locked = lock;
+        true
+    }
+
+    /// String programmed for function key `key` (1 = F1)
+    pub fn get(&self, key: u8) -> Option<&[u8]> {
+        self.keys.get(&key).map(Vec::as_slice)
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    /// Remove every definition and unlock the table
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn total_bytes(&self) -> usize {
+        self.keys.values().map(Vec::len).sum()
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::AnsiParser;
+    use crate::config::TerminalConfig;
+    use crate::grid::Grid;
+    use crate::keys::Key;
+    use crate::security::SecurityConfig;
+    use std::sync::Arc;
+
+    fn def(number: u16, value: &str) -> (u16, String) {
+        (number, value.to_string())
+    }
+
+    #[test]
+    fn test_key_numbers() {
+        assert_eq!(UserKeys::function_key(11), Some(1));
+        assert_eq!(UserKeys::function_key(17), Some(6));
+        assert_eq!(UserKeys::function_key(24), Some(12));
+        assert_eq!(UserKeys::function_key(34), Some(20));
+        assert_eq!(UserKeys::function_key(16), None);
+        assert_eq!(UserKeys::function_key(35), None);
+    }
+
+    #[test]
+    fn test_define_clear_and_lock() {
+        let mut keys = UserKeys::new();
+        assert!(keys.define(&[def(17, "ls\r"), def(18, "make\r")], true, false));
+        assert_eq!(keys.get(6), Some(&b"ls\r"[..]));
+
+        // Pc 1 keeps the other keys; an empty string removes one
+        assert!(keys.define(&[def(17, ""), def(99, "x")], false, true));
+        assert_eq!(keys.get(6), None);
+        assert_eq!(keys.get(7), Some(&b"make\r"[..]));
+
+        assert!(!keys.define(&[def(17, "rm -rf ~\r")], true, false));
+        assert_eq!(keys.get(6), None);
+        keys.reset();
+        assert!(!keys.is_locked() && keys.is_empty());
+    }
+
+    #[test]
+    fn test_table_is_bounded() {
+        let mut keys = UserKeys::new();
+        let long = "x".repeat(MAX_USER_KEY_BYTES - 1);
+        keys.define(&[def(17, &long), def(18, "ab"), def(19, "c")], true, false);
+        assert_eq!(keys.get(7), None);
+        assert_eq!(keys.get(8), Some(&b"c"[..]));
+        assert_eq!(keys.total_bytes(), MAX_USER_KEY_BYTES);
+    }
+
+    #[test]
+    fn test_grid_needs_opt_in() {
+        let f6 = |grid: &Grid| grid.key_encoder().encode_key(Key::ShiftF(6));
+        let mut grid = Grid::new(20, 2, Arc::new(TerminalConfig::default()));
+        AnsiParser::new().feed_str("\x1bP|17/6C730D\x1b\\", &mut grid);
+        assert_eq!(f6(&grid), Some(b"\x1b[17;2~".to_vec()));
+
+        let security = SecurityConfig { allow_user_keys: true, ..SecurityConfig::default() };
+        let mut grid = Grid::new(20, 2, Arc::new(TerminalConfig::default().with_security(security)));
+        AnsiParser::new().feed_str("\x1bP|17/6C730D\x1b\\\x1bP1;1|17/6C730D\x1b\\", &mut grid);
+        assert_eq!(f6(&grid), Some(b"ls\r".to_vec()));
+        assert!(grid.user_keys().is_locked());
+        assert_eq!(grid.key_encoder().encode_key(Key::F(6)), Some(b"\x1b[17~".to_vec()));
+        grid.reset_user_keys();
+        assert_eq!(f6(&grid), Some(b"\x1b[17;2~".to_vec()));
+    }
+
+    #[test]
+    fn test_resets_unlock_the_table() {
+        let security = SecurityConfig { allow_user_keys: true, ..SecurityConfig::default() };
+        let mut grid = Grid::new(20, 2, Arc::new(TerminalConfig::default().with_security(security)));
+        let mut parser = AnsiParser::new();
+        for reset in ["\x1bc", "\x1b[!p"] {
+            parser.feed_str("\x1bP1;0|17/6C730D\x1b\\", &mut grid);
+            assert!(grid.user_keys().is_locked());
+            parser.feed_str(reset, &mut grid);
+            assert!(!grid.user_keys().is_locked() && grid.user_keys().is_empty());
+        }
+    }
+}
@@ -0,0 +1,158 @@
+// src/width.rs
+//! Display width policy for Unicode characters
+//!
+//! Terminals and the applications running inside them must agree on how many
+//! columns a character occupies, otherwise cursor positioning drifts. The
+//! rules are not universal: East Asian "ambiguous" characters are narrow in
+//! Western locales but wide in CJK ones, and emoji presentation selectors
+//! (VS16) are honoured by modern software but ignored by legacy `wcwidth()`.
+//!
+//! Zero-width characters (combining marks, variation selectors) take no
+//! column of their own. The grid stores them with the character before the
+//! cursor, as a `Cell::combining` id into a table of mark sequences, so they
+//! are drawn and copied with it.
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Id stored in `Cell::combining` for cells without combining marks
+pub const NO_COMBINING: u32 = 0;
+
+/// VARIATION SELECTOR-15 (request text presentation)
+pub const VS15: char = '\u{FE0E}';
+/// VARIATION SELECTOR-16 (request emoji presentation)
+pub const VS16: char = '\u{FE0F}';
+
+/// How East Asian ambiguous-width characters (e.g. `±`, `§`, Greek/Cyrillic
+/// letters in CJK fonts) are laid out
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AmbiguousWidth {
+    /// One column (Western locales, default)
+    #[default]
+    Narrow,
+    /// Two columns (CJK locales)
+    Wide,
+}
+
+/// How emoji and emoji presentation sequences are laid out
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EmojiWidth {
+    /// Unicode 9+ rules: VS16 widens text-default emoji to two columns
+    #[default]
+    Unicode,
+    /// Legacy `wcwidth()` compatibility: per-codepoint widths only, variation
+    /// selectors never change the width of the preceding character
+    WcwidthCompat,
+}
+
+/// Width policy consulted by the grid when placing characters
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WidthPolicy {
+    pub ambiguous: AmbiguousWidth,
+    pub emoji: EmojiWidth,
+}
+
+impl WidthPolicy {
+    pub fn new(ambiguous: AmbiguousWidth, emoji: EmojiWidth) -> Self {
+        Self { ambiguous, emoji }
+    }
+
+    /// Number of columns a single character occupies (0, 1 or 2)
+    ///
+    /// Control characters report 0; the parser never puts them into the grid.
+    pub fn char_width(&self, ch: char) -> usize {
+        let width = match self.ambiguous {
+            AmbiguousWidth::Narrow => ch.width(),
+            AmbiguousWidth::Wide => ch.width_cjk(),
+        };
+        width.unwrap_or(0)
+    }
+
+    /// Extra columns a variation selector adds to the preceding character
+    ///
+    /// Returns 1 when `selector` is VS16 and turns a narrow, text-default emoji
+    /// into its wide emoji presentation, 0 otherwise.
+    pub fn selector_widening(&self, base: char, selector: char) -> usize {
+        if selector != VS16 || self.emoji == EmojiWidth::WcwidthCompat {
+            return 0;
+        }
+        if self.char_width(base) != 1 {
+            return 0;
+        }
+        let seq: String = [base, VS16].iter().collect();
+        if seq.width() >= 2 { 1 } else { 0 }
+    }
+
+    /// Number of columns a grapheme cluster occupies under this policy
+    pub fn grapheme_width(&self, grapheme: &str) -> usize {
+        let mut chars = grapheme.chars();
+        let Some(first) = chars.next() else {
+            return 0;
+        };
+        match self.emoji {
+            EmojiWidth::WcwidthCompat => {
+                // wcswidth(): plain sum of codepoint widths
+                grapheme.chars().map(|c| self.char_width(c)).sum()
+            }
+            EmojiWidth::Unicode => {
+                let base = self.char_width(first);
+                let widen = chars
+                    .find(|&c| c == VS16 || c == VS15)
+                    .map_or(0, |vs| self.selector_widening(first, vs));
+                (base + widen).min(2)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ambiguous_width_policy() {
+        let narrow = WidthPolicy::new(AmbiguousWidth::Narrow, EmojiWidth::Unicode);
+        let wide = WidthPolicy::new(AmbiguousWidth::Wide, EmojiWidth::Unicode);
+
+        // U+00B1 PLUS-MINUS SIGN is East Asian ambiguous
+        assert_eq!(narrow.char_width('±'), 1);
+        assert_eq!(wide.char_width('±'), 2);
+
+        // Unambiguous characters are unaffected
+        assert_eq!(narrow.char_width('a'), 1);
+        assert_eq!(wide.char_width('a'), 1);
+        assert_eq!(narrow.char_width('中'), 2);
+        assert_eq!(wide.char_width('中'), 2);
+    }
+
+    #[test]
+    fn test_zero_width_characters() {
+        let policy = WidthPolicy::default();
+        assert_eq!(policy.char_width('\u{0301}'), 0); // combining acute
+        assert_eq!(policy.char_width(VS16), 0);
+        assert_eq!(policy.char_width('\x07'), 0);
+    }
+
+    #[test]
+    fn test_vs16_widening() {
+        let unicode = WidthPolicy::new(AmbiguousWidth::Narrow, EmojiWidth::Unicode);
+        let compat = WidthPolicy::new(AmbiguousWidth::Narrow, EmojiWidth::WcwidthCompat);
+
+        // U+2764 HEAVY BLACK HEART is text-default; VS16 requests emoji presentation
+        assert_eq!(unicode.selector_widening('\u{2764}', VS16), 1);
+        assert_eq!(compat.selector_widening('\u{2764}', VS16), 0);
+        assert_eq!(unicode.grapheme_width("\u{2764}\u{FE0F}"), 2);
+        assert_eq!(compat.grapheme_width("\u{2764}\u{FE0F}"), 1);
+
+        // VS15 never widens, and already-wide emoji stay at two columns
+        assert_eq!(unicode.selector_widening('\u{2764}', VS15), 0);
+        assert_eq!(unicode.grapheme_width("😀\u{FE0F}"), 2);
+    }
+
+    #[test]
+    fn test_grapheme_width_combining() {
+        let policy = WidthPolicy::default();
+        assert_eq!(policy.grapheme_width("e\u{0301}"), 1);
+        assert_eq!(policy.grapheme_width(""), 0);
+        assert_eq!(policy.grapheme_width("中"), 2);
+    }
+}
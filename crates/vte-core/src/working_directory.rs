@@ -0,0 +1,179 @@
+// src/working_directory.rs
+//! Working directory reported by the shell (OSC 7)
+//!
+//! Shells announce their directory as `OSC 7 ; file://HOST/PATH ST`, with the
+//! path percent-encoded; some send a bare absolute path instead. The host
+//! tells a local session from an SSH one, where the path names a directory
+//! on another machine. Reports that do not decode to a valid UTF-8 path
+//! (bad escapes, NUL or other control characters) are ignored, so a
+//! container never receives a mangled path to `cd` into.
+//!
+//! Containers opening a split or tab ask `Grid::can_inherit_cwd` before
+//! starting the new shell in the reported directory; for a remote session
+//! `Grid::remote_host` names the machine, e.g. to offer an SSH profile to it
+//! instead. `TerminalEvent::RemoteHostChanged` announces when a session moves
+//! between machines.
+
+use std::sync::OnceLock;
+
+/// A decoded OSC 7 report
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WorkingDirectory {
+    /// The report as sent
+    pub uri: String,
+    /// Host named in the URL; `None` when empty or for a bare path
+    pub host: Option<String>,
+    /// Absolute path, percent-decoded
+    pub path: String,
+}
+
+impl WorkingDirectory {
+    /// Decode an OSC 7 payload; `None` if it is not a usable directory
+    pub fn parse(uri: &str) -> Option<Self> {
+        let (host, path) = if uri.starts_with('/') {
+            (None, uri.to_string())
+        } else {
+            let rest = uri.strip_prefix("file://").or_else(|| uri.strip_prefix("kitty-shell-cwd://"))?;
+            let (host, path) = rest.find('/').map_or((rest, "/"), |slash| rest.split_at(slash));
+            let host = (!host.is_empty()).then(|| host.to_string());
+            (host, percent_decode(path)?)
+        };
+        if path.chars().any(char::is_control) || host.as_deref().is_some_and(|h| h.chars().any(|c| c.is_control() || c == '%')) {
+            return None;
+        }
+        Some(Self { uri: uri.to_string(), host, path })
+    }
+
+    /// Whether the directory is on the machine named `hostname`: no host,
+    /// `localhost`, or `hostname` itself (ignoring case)
+    pub fn is_local_to(&self, hostname: &str) -> bool {
+        let Some(host) = &self.host else {
+            return true;
+        };
+        host.eq_ignore_ascii_case("localhost") || host.eq_ignore_ascii_case(hostname)
+    }
+
+    /// Whether the directory is on this machine (see `local_hostname`)
+    pub fn is_local(&self) -> bool {
+        self.is_local_to(local_hostname().unwrap_or("localhost"))
+    }
+}
+
+/// Name of this machine, read once with `gethostname` (`COMPUTERNAME` on
+/// Windows), as shells put it in their reports; `None` when unavailable
+pub fn local_hostname() -> Option<&'static str> {
+    static HOSTNAME: OnceLock<Option<String>> = OnceLock::new();
+    HOSTNAME
+        .get_or_init(|| system_hostname().map(|name| name.trim().to_string()).filter(|name| !name.is_empty()))
+        .as_deref()
+}
+
+#[cfg(unix)]
+fn system_hostname() -> Option<String> {
+    nix::unistd::gethostname().ok()?.into_string().ok()
+}
+
+#[cfg(windows)]
+fn system_hostname() -> Option<String> {
+    std::env::var("COMPUTERNAME").ok()
+}
+
+#[cfg(not(any(unix, windows)))]
+fn system_hostname() -> Option<String> {
+    None
+}
+
+/// `%XX` escapes decoded; `None` for a malformed escape or bytes that are
+/// not UTF-8
+fn percent_decode(text: &str) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ansi::AnsiParser;
+    use crate::config::TerminalConfig;
+    use crate::grid::Grid;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_parse_reports() {
+        let dir = WorkingDirectory::parse("file://box.example.org/home/me/caf%C3%A9%20bar").unwrap();
+        assert_eq!(dir.host.as_deref(), Some("box.example.org"));
+        assert_eq!(dir.path, "/home/me/café bar");
+
+        let dir = WorkingDirectory::parse("file:///tmp").unwrap();
+        assert_eq!((dir.host, dir.path.as_str()), (None, "/tmp"));
+        assert_eq!(WorkingDirectory::parse("file://host").unwrap().path, "/");
+        assert_eq!(WorkingDirectory::parse("/srv/ünïcode").unwrap().path, "/srv/ünïcode");
+        assert_eq!(WorkingDirectory::parse("kitty-shell-cwd://h/x").unwrap().path, "/x");
+
+        // Truncated or invalid escapes, non-UTF-8 bytes, control characters
+        for bad in ["file://h/a%C3", "file://h/a%zz", "file://h/%FF", "file://h/a%00b", "file://h/a\u{7}", "http://h/x", "relative"] {
+            assert_eq!(WorkingDirectory::parse(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_local_hosts() {
+        let at = |uri: &str| WorkingDirectory::parse(uri).unwrap();
+        assert!(at("file:///tmp").is_local_to("desk"));
+        assert!(at("file://localhost/tmp").is_local_to("desk"));
+        assert!(at("file://DESK.lan/tmp").is_local_to("desk.lan"));
+        assert!(!at("file://server/tmp").is_local_to("desk"));
+        // A shared first label does not make another machine local
+        assert!(!at("file://desk.lan/tmp").is_local_to("desk"));
+        assert!(!at("file://build.eu.example.com/tmp").is_local_to("build.us.example.com"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_reports_naming_this_machine_are_local() {
+        let host = local_hostname().expect("gethostname");
+        assert!(WorkingDirectory::parse(&format!("file://{}/tmp", host)).unwrap().is_local());
+    }
+
+    #[test]
+    fn test_grid_inherits_only_local_directories() {
+        let mut grid = Grid::new(10, 2, Arc::new(TerminalConfig::default()));
+        let mut parser = AnsiParser::new();
+        assert!(!grid.can_inherit_cwd());
+
+        parser.feed_str("\x1b]7;file://localhost/tmp\x07", &mut grid);
+        assert!(grid.can_inherit_cwd());
+        assert_eq!(grid.remote_host(), None);
+
+        parser.feed_str("\x1b]7;file://build-07.invalid/srv\x07", &mut grid);
+        assert!(!grid.can_inherit_cwd());
+        assert_eq!(grid.remote_host(), Some("build-07.invalid"));
+
+        // Bare paths come from this machine
+        parser.feed_str("\x1b]7;/home\x07", &mut grid);
+        assert!(grid.can_inherit_cwd());
+    }
+
+    #[test]
+    fn test_grid_ignores_invalid_reports() {
+        let mut grid = Grid::new(10, 2, Arc::new(TerminalConfig::default()));
+        let mut parser = AnsiParser::new();
+        parser.feed_str("\x1b]7;file://h/tmp/%C3%A9\x07", &mut grid);
+        assert_eq!(grid.working_directory().map(|d| d.path.as_str()), Some("/tmp/é"));
+
+        parser.feed_str("\x1b]7;file://h/tmp/%C3\x07", &mut grid);
+        assert_eq!(grid.current_directory(), Some("file://h/tmp/%C3%A9"));
+    }
+}
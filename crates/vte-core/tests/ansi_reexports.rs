@@ -0,0 +1,22 @@
+// tests/ansi_reexports.rs
+//! vte-core re-exports the vte-ansi types rather than defining its own
+
+use vte_core::ansi;
+
+/// Compiles only if both arguments have the same type
+fn same_type<T>(_: &T, _: &T) {}
+
+#[test]
+fn ansi_types_are_shared() {
+    same_type(&vte_core::Cell::default(), &vte_ansi::Cell::default());
+    same_type(&vte_core::Color::default(), &vte_ansi::Color::default());
+    same_type(&ansi::LogicalColor::Default, &vte_ansi::LogicalColor::Default);
+    same_type(&ansi::PRIMARY_FONT, &vte_ansi::PRIMARY_FONT);
+    same_type(&vte_core::AnsiParser::new(), &vte_ansi::AnsiParser::new());
+}
+
+#[test]
+fn core_grid_implements_ansi_grid() {
+    fn assert_ansi_grid<G: vte_ansi::AnsiGrid>() {}
+    assert_ansi_grid::<vte_core::Grid>();
+}
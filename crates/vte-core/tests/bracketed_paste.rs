@@ -0,0 +1,132 @@
+// tests/bracketed_paste.rs
+//! Paste flows end to end: mode set by application output, `paste` on the
+//! core, bytes as written to the child
+//!
+//! Each case runs a `VteTerminalCore` on an in-memory reader and writer. The
+//! reader plays the application's output (DECSET/DECRST 2004), and the
+//! writer records every write, so the tests see the exact byte stream
+//! including how large pastes are split into chunks.
+
+use std::io::Read;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::{Duration, Instant};
+use vte_core::constants::{PASTE_CHUNK_SIZE, PASTE_STREAM_THRESHOLD};
+use vte_core::{ForcibleMode, TerminalConfig, TerminalEvent, VteTerminalCore};
+
+#[path = "../src/test_support.rs"]
+mod test_support;
+use test_support::SharedBuffer;
+
+/// Output of the application, fed to the core as it is sent
+struct Output(Receiver<Vec<u8>>, Vec<u8>);
+
+impl Read for Output {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.1.is_empty() {
+            match self.0.recv() {
+                Ok(bytes) => self.1 = bytes,
+                Err(_) => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.1.len());
+        buf[..n].copy_from_slice(&self.1[..n]);
+        self.1.drain(..n);
+        Ok(n)
+    }
+}
+
+struct Session {
+    core: VteTerminalCore,
+    output: Sender<Vec<u8>>,
+    writes: SharedBuffer,
+}
+
+impl Session {
+    fn new() -> Self {
+        let (output, rx) = channel();
+        let writes = SharedBuffer::default();
+        let core = VteTerminalCore::with_io(TerminalConfig::default(), 80, 24, Output(rx, Vec::new()), writes.clone());
+        Self { core, output, writes }
+    }
+
+    /// Send application output and wait until bracketed paste is `expected`
+    fn set_mode(&self, output: &str, expected: bool) {
+        self.output.send(output.as_bytes().to_vec()).unwrap();
+        wait_until(|| self.core.modes().bracketed_paste == expected);
+    }
+}
+
+fn wait_until(mut done: impl FnMut() -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !done() {
+        assert!(Instant::now() < deadline, "timed out");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn paste_is_sanitized_until_the_application_enables_bracketed_paste() {
+    let session = Session::new();
+    assert!(!session.core.modes().bracketed_paste);
+
+    session.core.paste("ls -l\necho \x1b[31mred\x07\n").unwrap();
+    assert_eq!(session.writes.take(), b"ls -l\recho red\r");
+
+    session.set_mode("user@host:~$ \x1b[?2004h", true);
+    session.core.paste("ls -l\r\necho \x1b[31mred\n").unwrap();
+    assert_eq!(session.writes.take(), b"\x1b[200~ls -l\recho \x1b[31mred\r\x1b[201~");
+
+    session.set_mode("\x1b[?2004l", false);
+    session.core.paste("a\tb").unwrap();
+    assert_eq!(session.writes.take(), b"a\tb");
+}
+
+#[test]
+fn embedded_end_marker_cannot_escape_the_bracket() {
+    let session = Session::new();
+    session.set_mode("\x1b[?2004h", true);
+    session.core.paste("harmless\x1b[201~rm -rf ~\n").unwrap();
+    assert_eq!(session.writes.take(), b"\x1b[200~harmlessrm -rf ~\r\x1b[201~");
+}
+
+#[test]
+fn forced_off_mode_reads_as_off_and_pastes_plain() {
+    let session = Session::new();
+    session.core.set_mode_disabled(ForcibleMode::BracketedPaste, true);
+    session.output.send(b"\x1b[?2004hready".to_vec()).unwrap();
+    wait_until(|| session.core.grid.read().unwrap().get_cell(0, 4).ch == 'y');
+
+    let modes = session.core.modes();
+    assert!(!modes.bracketed_paste && modes.disabled.bracketed_paste);
+    session.core.paste("x\n").unwrap();
+    assert_eq!(session.writes.take(), b"x\r");
+
+    session.core.set_mode_disabled(ForcibleMode::BracketedPaste, false);
+    assert!(session.core.modes().bracketed_paste);
+}
+
+#[test]
+fn large_bracketed_paste_is_streamed_in_chunks() {
+    let session = Session::new();
+    let events = session.core.events();
+    session.set_mode("\x1b[?2004h", true);
+
+    let text = "0123456789abcde\n".repeat(PASTE_STREAM_THRESHOLD / 16 + 1);
+    session.core.paste(&text).unwrap();
+    let (sent, total) = loop {
+        match events.recv_blocking().unwrap() {
+            TerminalEvent::PasteFinished { sent, total, cancelled } => {
+                assert!(!cancelled);
+                break (sent, total);
+            }
+            _ => continue,
+        }
+    };
+
+    let expected = [b"\x1b[200~".as_slice(), text.replace('\n', "\r").as_bytes(), b"\x1b[201~"].concat();
+    assert_eq!((sent, total), (expected.len(), expected.len()));
+    assert_eq!(session.writes.contents(), expected);
+    let writes = session.writes.writes();
+    assert_eq!(writes.len(), expected.len().div_ceil(PASTE_CHUNK_SIZE));
+    assert!(writes.iter().all(|chunk| chunk.len() <= PASTE_CHUNK_SIZE));
+}
@@ -0,0 +1,138 @@
+// tests/key_encoding.rs
+//! Key encoding regression suite against real programs
+//!
+//! Each case feeds the mode-setting output a program writes on startup (and
+//! exit) through the parser into a `Grid`, then checks the bytes the key
+//! encoder produces against what that program's terminfo entry expects.
+//! Startup sequences were captured under `TERM=xterm-256color` and trimmed to
+//! the mode changes plus a little surrounding output.
+
+use std::sync::Arc;
+use vte_core::{AnsiParser, Grid, Key, TerminalConfig};
+
+/// bash 5.2 readline: prompt plus bracketed paste, keypad left alone
+const BASH_PROMPT: &str = "\x1b[?2004huser@host:~$ ";
+/// vim 9: smcup, XTMODKEYS, smkx, clear
+const VIM_START: &str = "\x1b[?1049h\x1b[22;0;0t\x1b[>4;2m\x1b[?1h\x1b=\x1b[H\x1b[2J";
+/// vim 9: rmkx, XTMODKEYS reset, rmcup
+const VIM_EXIT: &str = "\x1b[?1l\x1b>\x1b[>4;m\x1b[?1049l\x1b[23;0;0t";
+/// htop 3 via ncurses: smcup, scroll region, reset attrs, smkx, hide cursor
+const HTOP_START: &str = "\x1b[?1049h\x1b[22;0;0t\x1b[1;24r\x1b(B\x1b[m\x1b[4l\x1b[?7h\x1b[?1h\x1b=\x1b[?25l";
+/// htop 3 via ncurses: rmkx, rmcup, show cursor
+const HTOP_EXIT: &str = "\x1b[?1l\x1b>\x1b[?1049l\x1b[23;0;0t\x1b[?12l\x1b[?25h";
+
+struct Session {
+    parser: AnsiParser,
+    grid: Grid,
+}
+
+impl Session {
+    fn new() -> Self {
+        Self {
+            parser: AnsiParser::new(),
+            grid: Grid::new(80, 24, Arc::new(TerminalConfig::default())),
+        }
+    }
+
+    fn output(&mut self, bytes: &str) -> &mut Self {
+        self.parser.feed_str(bytes, &mut self.grid);
+        self
+    }
+
+    fn key(&self, key: Key) -> &'static [u8] {
+        self.grid.key_encoder().encode(key).expect("key has a sequence")
+    }
+}
+
+/// Keys whose encoding depends on DECCKM, with (normal, application) bytes
+const CURSOR_KEYS: &[(Key, &[u8], &[u8])] = &[
+    (Key::Up, b"\x1b[A", b"\x1bOA"),
+    (Key::Down, b"\x1b[B", b"\x1bOB"),
+    (Key::Right, b"\x1b[C", b"\x1bOC"),
+    (Key::Left, b"\x1b[D", b"\x1bOD"),
+    (Key::Home, b"\x1b[H", b"\x1bOH"),
+    (Key::End, b"\x1b[F", b"\x1bOF"),
+];
+
+/// Keys that encode the same in both modes
+const FIXED_KEYS: &[(Key, &[u8])] = &[
+    (Key::F(1), b"\x1bOP"),
+    (Key::F(4), b"\x1bOS"),
+    (Key::F(5), b"\x1b[15~"),
+    (Key::F(10), b"\x1b[21~"),
+    (Key::F(12), b"\x1b[24~"),
+    (Key::Insert, b"\x1b[2~"),
+    (Key::Delete, b"\x1b[3~"),
+    (Key::PageUp, b"\x1b[5~"),
+    (Key::PageDown, b"\x1b[6~"),
+];
+
+fn assert_normal_mode(session: &Session, program: &str) {
+    for &(key, normal, _) in CURSOR_KEYS {
+        assert_eq!(session.key(key), normal, "{program}: {key:?} in normal mode");
+    }
+    for &(key, bytes) in FIXED_KEYS {
+        assert_eq!(session.key(key), bytes, "{program}: {key:?}");
+    }
+    assert_eq!(session.key(Key::KeypadEnter), b"\r", "{program}: keypad Enter");
+}
+
+fn assert_application_mode(session: &Session, program: &str) {
+    for &(key, _, app) in CURSOR_KEYS {
+        assert_eq!(session.key(key), app, "{program}: {key:?} in application mode");
+    }
+    for &(key, bytes) in FIXED_KEYS {
+        assert_eq!(session.key(key), bytes, "{program}: {key:?}");
+    }
+    assert_eq!(session.key(Key::KeypadEnter), b"\x1bOM", "{program}: keypad Enter");
+}
+
+#[test]
+fn bash_readline_uses_normal_mode() {
+    let mut session = Session::new();
+    session.output(BASH_PROMPT);
+    assert_normal_mode(&session, "bash");
+    assert!(session.grid.is_bracketed_paste_mode());
+}
+
+#[test]
+fn vim_switches_to_application_mode_and_back() {
+    let mut session = Session::new();
+    session.output(BASH_PROMPT).output(VIM_START);
+    assert_application_mode(&session, "vim");
+
+    session.output(VIM_EXIT);
+    assert_normal_mode(&session, "bash after vim");
+}
+
+#[test]
+fn htop_switches_to_application_mode_and_back() {
+    let mut session = Session::new();
+    session.output(BASH_PROMPT).output(HTOP_START);
+    assert_application_mode(&session, "htop");
+
+    session.output(HTOP_EXIT);
+    assert_normal_mode(&session, "bash after htop");
+}
+
+#[test]
+fn modes_survive_output_split_across_reads() {
+    // The PTY may split a program's startup output anywhere
+    let mut session = Session::new();
+    for chunk in VIM_START.as_bytes().chunks(3) {
+        session.output(std::str::from_utf8(chunk).unwrap());
+    }
+    assert_application_mode(&session, "vim (chunked)");
+}
+
+#[test]
+fn cursor_and_keypad_modes_are_independent() {
+    let mut session = Session::new();
+    session.output("\x1b[?1h");
+    assert_eq!(session.key(Key::Up), b"\x1bOA");
+    assert_eq!(session.key(Key::KeypadEnter), b"\r");
+
+    session.output("\x1b[?1l\x1b=");
+    assert_eq!(session.key(Key::Up), b"\x1b[A");
+    assert_eq!(session.key(Key::KeypadEnter), b"\x1bOM");
+}
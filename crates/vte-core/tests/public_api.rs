@@ -0,0 +1,112 @@
+// tests/public_api.rs
+//! Snapshot of the public surface embedders build on
+//!
+//! Lists the crate's public modules, the names re-exported at its root and
+//! the names in `prelude`, and compares them against `tests/public_api.txt`.
+//! This is a scan of the source text, not an API dump: it sees names only,
+//! not signatures or the items inside modules, and it reads one `pub use`
+//! per line, so a re-export split over several lines fails the test rather
+//! than going unseen. A change to the surface fails here until the snapshot
+//! is updated:
+//!
+//! ```text
+//! UPDATE_PUBLIC_API=1 cargo test -p vte-core --test public_api
+//! ```
+
+use std::path::Path;
+
+const UPDATE_VAR: &str = "UPDATE_PUBLIC_API";
+
+/// Names a single-line `pub use` item brings into scope
+fn exported_names(item: &str) -> Vec<String> {
+    assert!(item.ends_with(';'), "`pub use {}` spans several lines; keep re-exports on one line", item);
+    let path = item.trim_end_matches(';');
+    if let Some((_, alias)) = path.split_once(" as ") {
+        return vec![alias.trim().to_string()];
+    }
+    match path.split_once('{') {
+        Some((prefix, names)) => names
+            .trim_end_matches('}')
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| if name == "*" { format!("{}*", prefix) } else { name.rsplit("::").next().unwrap_or(name).to_string() })
+            .collect(),
+        None if path.ends_with("::*") => vec![path.to_string()],
+        None => vec![path.rsplit("::").next().unwrap_or(path).to_string()],
+    }
+}
+
+/// `mod`, root and `prelude::` entries of the sources, sorted per section
+fn surface(lib: &str, prelude: &str) -> String {
+    let mut modules = Vec::new();
+    let mut root = Vec::new();
+    let mut hidden = false;
+    for line in lib.lines().map(str::trim) {
+        if line == "#[doc(hidden)]" {
+            hidden = true;
+            continue;
+        }
+        if let Some(name) = line.strip_prefix("pub mod ") {
+            let name = name.trim_end_matches(';');
+            modules.push(if hidden { format!("mod {} (hidden)", name) } else { format!("mod {}", name) });
+        } else if let Some(item) = line.strip_prefix("pub use ") {
+            root.extend(exported_names(item));
+        }
+        hidden = false;
+    }
+    let mut prelude: Vec<String> = prelude
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("pub use "))
+        .flat_map(exported_names)
+        .map(|name| format!("prelude::{}", name))
+        .collect();
+    modules.sort();
+    root.sort();
+    prelude.sort();
+    [modules, root, prelude].concat().join("\n") + "\n"
+}
+
+#[test]
+fn public_api_matches_snapshot() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let read = |path: &str| std::fs::read_to_string(dir.join(path)).unwrap();
+    let current = surface(&read("src/lib.rs"), &read("src/prelude.rs"));
+    let snapshot_path = dir.join("tests/public_api.txt");
+
+    if std::env::var_os(UPDATE_VAR).is_some_and(|value| value != "0") {
+        std::fs::write(&snapshot_path, &current).unwrap();
+        return;
+    }
+    let snapshot = std::fs::read_to_string(&snapshot_path).unwrap_or_default();
+    assert!(
+        current == snapshot,
+        "public API changed; review the difference and rerun with {}=1 to accept it\n--- snapshot\n{}\n--- current\n{}",
+        UPDATE_VAR,
+        snapshot,
+        current
+    );
+}
+
+#[test]
+fn prelude_covers_embedding() {
+    use vte_core::prelude::*;
+
+    // An embedder configures a core, reads its grid and handles its events
+    let _: fn(TerminalConfig) -> TerminalResult<VteTerminalCore> = VteTerminalCore::with_config;
+    let config = TerminalConfig::default().with_color_env(ColorPolicy::Monochrome);
+    let grid = vte_core::Grid::new(4, 2, std::sync::Arc::new(config));
+    let view: &dyn GridView = &grid;
+    let cell: &Cell = view.cell(0, 0);
+    assert_eq!(cell.ch, '\0');
+    let event = TerminalEvent::from_error(&TerminalError::GridLockError { message: "poisoned".to_string() });
+    assert!(matches!(event, TerminalEvent::Error { severity: ErrorSeverity::Warning, .. }));
+}
+
+#[test]
+fn names_from_use_items() {
+    assert_eq!(exported_names("vte_ansi as ansi;"), ["ansi"]);
+    assert_eq!(exported_names("config::TerminalConfig;"), ["TerminalConfig"]);
+    assert_eq!(exported_names("theme::{ColorScheme, Theme};"), ["ColorScheme", "Theme"]);
+    assert_eq!(exported_names("traits::*;"), ["traits::*"]);
+}
@@ -0,0 +1,260 @@
+// examples/embedded_app.rs
+//! A small terminal application built on the embedding API
+//!
+//! Opens a window with two terminal tabs and wires up everything an embedder
+//! typically needs: a custom light/dark theme, settings read from a config
+//! file, the "new output below" pill, and the event stream - window and tab
+//! titles follow OSC 0/2, the bell marks background tabs, and a tab closes
+//! when its shell exits (the window closes with the last one).
+//!
+//! ```text
+//! cargo run -p vte-gtk4 --example embedded_app
+//! cargo run -p vte-gtk4 --example embedded_app -- my-terminal.conf
+//! ```
+//!
+//! Without an argument the config is read from
+//! `$XDG_CONFIG_HOME/hugovte/embedded_app.conf`, if it exists. It holds
+//! `key = value` lines; `#` starts a comment:
+//!
+//! ```text
+//! font_family = Monaco
+//! font_size = 14
+//! foreground = #d8dee9          # dark theme
+//! background = #2e3440
+//! light_foreground = #2e3440    # light theme
+//! light_background = #eceff4
+//! visual_bell = border          # off, border, invert or pulse
+//! high_contrast = false
+//! ```
+
+use gtk4::prelude::*;
+use std::io;
+use std::path::{Path, PathBuf};
+use vte_gtk4::{
+    show_event_notification, BellAction, BellPolicy, Color, ColorScheme, ErrorSeverity, TerminalConfig,
+    TerminalEvent, Theme, VisualBellStyle, VteTerminalWidget,
+};
+
+const APP_ID: &str = "com.example.hugovte.EmbeddedApp";
+const CONFIG_FILE: &str = "hugovte/embedded_app.conf";
+const TAB_COUNT: usize = 2;
+/// Prefix of a background tab's label after its bell rang
+const BELL_MARK: &str = "🔔 ";
+
+/// The example's own light and dark theme (Nord-like)
+fn default_themes() -> (Theme, Theme) {
+    let light = Theme::new(Color::rgb(0.18, 0.20, 0.25), Color::rgb(0.93, 0.94, 0.96));
+    let dark = Theme::new(Color::rgb(0.85, 0.87, 0.91), Color::rgb(0.18, 0.20, 0.25));
+    (light, dark)
+}
+
+/// `#rrggbb` to a color
+fn parse_color(value: &str) -> Option<Color> {
+    let hex = value.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let rgb = u32::from_str_radix(hex, 16).ok()?;
+    let channel = |shift: u32| ((rgb >> shift) & 0xff) as f64 / 255.0;
+    Some(Color::rgb(channel(16), channel(8), channel(0)))
+}
+
+/// Build the terminal configuration from the text of a config file
+fn parse_config(text: &str) -> Result<TerminalConfig, String> {
+    // The focused tab beeps, background tabs get a mark
+    let bell = BellPolicy { focused: BellAction::Sound, unfocused: BellAction::MarkTab, ..BellPolicy::default() };
+    let mut config = TerminalConfig::default().with_bell_policy(bell);
+    let (mut light, mut dark) = default_themes();
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("line {}: expected `key = value`", number + 1))?;
+        // Colors start with `#` too; a comment after a value follows whitespace
+        let value = value.split_once(" #").map_or(value, |(value, _)| value);
+        let (key, value) = (key.trim(), value.trim());
+        let bad_value = || format!("line {}: invalid value for {}: {:?}", number + 1, key, value);
+        let color = || parse_color(value).ok_or_else(bad_value);
+
+        match key {
+            "font_family" => config = config.with_font_family(value),
+            "font_size" => config = config.with_font_size(value.parse().map_err(|_| bad_value())?),
+            "foreground" => dark.foreground = color()?,
+            "background" => dark.background = color()?,
+            "light_foreground" => light.foreground = color()?,
+            "light_background" => light.background = color()?,
+            "visual_bell" => {
+                let style = match value {
+                    "off" => VisualBellStyle::Off,
+                    "border" => VisualBellStyle::BorderFlash,
+                    "invert" => VisualBellStyle::Invert,
+                    "pulse" => VisualBellStyle::BackgroundPulse,
+                    _ => return Err(bad_value()),
+                };
+                let duration_ms = config.visual_bell_duration_ms;
+                config = config.with_visual_bell(style, duration_ms);
+            }
+            "high_contrast" => config = config.with_high_contrast(value.parse().map_err(|_| bad_value())?),
+            _ => return Err(format!("line {}: unknown setting {:?}", number + 1, key)),
+        }
+    }
+    Ok(config.with_themes(light, dark))
+}
+
+/// Read the config at `path`; a missing file means the defaults unless the
+/// user named it
+fn load_config(path: &Path, explicit: bool) -> Result<TerminalConfig, String> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => parse_config(&text).map_err(|e| format!("{}: {}", path.display(), e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound && !explicit => parse_config(""),
+        Err(e) => Err(format!("{}: {}", path.display(), e)),
+    }
+}
+
+/// Label text of a tab, without the bell mark
+fn tab_title(label: &gtk4::Label) -> String {
+    label.text().trim_start_matches(BELL_MARK).to_string()
+}
+
+/// Add a tab whose shell starts once GTK has sized it; `scheme` forces its
+/// color scheme, `None` follows the desktop
+fn add_tab(
+    window: &gtk4::ApplicationWindow,
+    notebook: &gtk4::Notebook,
+    config: TerminalConfig,
+    scheme: Option<ColorScheme>,
+    number: usize,
+) {
+    let label = gtk4::Label::new(Some(&format!("Terminal {}", number)));
+    let page = gtk4::Overlay::new();
+
+    let (window_ref, notebook_ref, label_ref, page_ref) = (window.clone(), notebook.clone(), label.clone(), page.clone());
+    let area = VteTerminalWidget::spawn_on_allocation(config, move |terminal| {
+        let terminal = match terminal {
+            Ok(terminal) => terminal,
+            Err(e) => {
+                eprintln!("embedded_app: could not start a shell: {}", e);
+                label_ref.set_text("(failed)");
+                return;
+            }
+        };
+        if let Some(scheme) = scheme {
+            terminal.backend().set_color_scheme(scheme);
+        }
+        page_ref.add_overlay(&terminal.backend().output_indicator());
+        terminal.widget().grab_focus();
+        watch_events(terminal, &window_ref, &notebook_ref, &label_ref, &page_ref);
+    });
+    page.set_child(Some(&area));
+
+    notebook.append_page(&page, Some(&label));
+    notebook.set_tab_reorderable(&page, true);
+}
+
+/// Drain the terminal's events on the main loop for as long as its shell runs
+fn watch_events(
+    terminal: VteTerminalWidget,
+    window: &gtk4::ApplicationWindow,
+    notebook: &gtk4::Notebook,
+    label: &gtk4::Label,
+    page: &gtk4::Overlay,
+) {
+    let events = terminal.backend().terminal().events();
+    let (window, notebook, label, page) = (window.clone(), notebook.clone(), label.clone(), page.clone());
+
+    glib::spawn_future_local(async move {
+        while let Ok(event) = events.recv().await {
+            let is_current = notebook.page_num(&page).is_some_and(|n| notebook.current_page() == Some(n));
+            match &event {
+                TerminalEvent::TitleChanged { title } => {
+                    let marked = label.text().starts_with(BELL_MARK);
+                    label.set_text(&format!("{}{}", if marked { BELL_MARK } else { "" }, title));
+                    if is_current {
+                        window.set_title(Some(title.as_str()));
+                    }
+                }
+                TerminalEvent::Bell { action: BellAction::Sound } => terminal.widget().error_bell(),
+                TerminalEvent::Bell { action: BellAction::MarkTab } => {
+                    if !is_current && !label.text().starts_with(BELL_MARK) {
+                        label.set_text(&format!("{}{}", BELL_MARK, tab_title(&label)));
+                    }
+                }
+                TerminalEvent::ChildExited => break,
+                TerminalEvent::Error { severity: ErrorSeverity::Fatal, message, .. } => {
+                    eprintln!("embedded_app: terminal failed: {}", message);
+                    break;
+                }
+                TerminalEvent::ClipboardReadRequest { .. } => {
+                    terminal.backend().prompt_clipboard_read(&event);
+                }
+                TerminalEvent::CommandFinished { .. } => {
+                    show_event_notification(terminal.widget(), &event);
+                }
+                _ => {}
+            }
+        }
+
+        // The shell is gone: close its tab, and the window with the last one
+        if let Some(n) = notebook.page_num(&page) {
+            notebook.remove_page(Some(n));
+        }
+        if notebook.n_pages() == 0 {
+            window.close();
+        }
+    });
+}
+
+fn build_window(app: &gtk4::Application, config: &TerminalConfig) {
+    let window = gtk4::ApplicationWindow::builder()
+        .application(app)
+        .title("Terminal")
+        .default_width(900)
+        .default_height(600)
+        .build();
+    let notebook = gtk4::Notebook::new();
+    notebook.set_scrollable(true);
+
+    // The window shows the title of the visible tab, which also loses its bell mark
+    let window_ref = window.clone();
+    notebook.connect_switch_page(move |notebook, page, _| {
+        if let Some(label) = notebook.tab_label(page).and_downcast::<gtk4::Label>() {
+            let title = tab_title(&label);
+            label.set_text(&title);
+            window_ref.set_title(Some(&title));
+        }
+    });
+
+    // The second tab stays light whatever the desktop prefers, to show both themes
+    for number in 1..=TAB_COUNT {
+        let scheme = (number == TAB_COUNT).then_some(ColorScheme::Light);
+        add_tab(&window, &notebook, config.clone(), scheme, number);
+    }
+
+    window.set_child(Some(&notebook));
+    window.present();
+}
+
+fn main() -> glib::ExitCode {
+    let mut args = std::env::args();
+    let program = args.next().unwrap_or_else(|| "embedded_app".to_string());
+    let (path, explicit) = match args.next() {
+        Some(path) => (PathBuf::from(path), true),
+        None => (glib::user_config_dir().join(CONFIG_FILE), false),
+    };
+    let config = match load_config(&path, explicit) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("embedded_app: {}", e);
+            return glib::ExitCode::FAILURE;
+        }
+    };
+
+    let app = gtk4::Application::builder().application_id(APP_ID).build();
+    app.connect_activate(move |app| build_window(app, &config));
+    // The config path is ours; keep GTK from parsing it as an option
+    app.run_with_args(&[program])
+}
@@ -1,15 +1,21 @@
 //! GTK4 backend implementation combining all traits
 
-use crate::cairo_renderer::{CairoTextRenderer, CairoGraphicsRenderer, CairoUIRenderer};
 use crate::input::{Gtk4InputHandler, Gtk4EventLoop};
-use gtk4::DrawingArea;
-use gtk4::prelude::DrawingAreaExtManual;
-use cairo;
-use vte_core::{VteTerminalCore, TerminalConfig, Renderer, ImageData, Cell, Color, CursorShape, TerminalError};
-use vte_core::font::FontCache;
+use gtk4::{gdk, DrawingArea, EventControllerScroll, EventControllerScrollFlags};
+use gtk4::prelude::*;
+use vte_core::{VteTerminalCore, TerminalConfig, BackendError, Color, ColorAdjust, ColorScheme, CursorPosition, EventLoop, InputSink, PointerShape, ResizeHandle,
+               TerminalError, TerminalEvent, VisualBell, VisualBellStyle};
+use vte_core::events::EventEmitter;
+use vte_core::constants::{DEFAULT_COLS, DEFAULT_ROWS};
+use vte_core::drawing::CellGeometry;
+use vte_render_cairo::{render_font_key, render_fonts, CairoRenderer, CairoUIRenderer, RowSurfaceCache, DEFAULT_CELL_HEIGHT, DEFAULT_CELL_WIDTH};
 use async_channel::{self, Receiver, Sender};
-use std::sync::{Arc, Mutex};
-use std::io::Write;
+use std::path::Path;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+use tracing::warn;
 
 /// Complete GTK4 backend for the vte-core terminal
 pub struct Gtk4Backend {
@@ -17,6 +23,20 @@ pub struct Gtk4Backend {
     event_loop: Gtk4EventLoop,
     redraw_rx: Receiver<()>,
     redraw_tx: Sender<()>,
+    /// Render settings shared with the draw function (runtime toggles);
+    /// changed copy-on-write, so frames share them without copying
+    config: Rc<RefCell<Arc<TerminalConfig>>>,
+    /// Cell layout shared with the draw function and mouse hit-testing
+    geometry: Rc<std::cell::Cell<CellGeometry>>,
+    /// Drawn muted as an unfocused pane, shared with the draw function
+    inactive: Rc<std::cell::Cell<bool>>,
+    /// Visual bell state, rung and drawn by the draw function
+    bell: Rc<RefCell<VisualBell>>,
+    /// Whether the timer redrawing collaborators' cursors as they expire runs
+    remote_timer: Rc<std::cell::Cell<bool>>,
+    /// "New output below" pill, if the embedder asked for one
+    output_pill: Rc<RefCell<Option<glib::WeakRef<gtk4::Button>>>>,
+    area: DrawingArea,
     char_w: f64,
     char_h: f64,
 }
@@ -24,60 +44,284 @@ pub struct Gtk4Backend {
 impl Gtk4Backend {
     /// Create a new GTK4 backend with the given configuration
     pub fn new(config: TerminalConfig, area: &DrawingArea) -> Result<Self, TerminalError> {
+        Self::with_size(config, area, DEFAULT_COLS, DEFAULT_ROWS)
+    }
+
+    /// Create a backend whose shell starts at the size that fits `width` x
+    /// `height` pixels of `area`, e.g. its first allocation
+    pub fn for_allocation(config: TerminalConfig, area: &DrawingArea, width: i32, height: i32) -> Result<Self, TerminalError> {
+        let geometry = cell_geometry(area, DEFAULT_CELL_WIDTH, DEFAULT_CELL_HEIGHT, &config);
+        let (cols, rows) = geometry.grid_size(width as f64, height as f64);
+        Self::with_size(config, area, cols, rows)
+    }
+
+    /// Create a backend whose shell starts at `cols` x `rows`
+    pub fn with_size(config: TerminalConfig, area: &DrawingArea, cols: usize, rows: usize) -> Result<Self, TerminalError> {
         // Estimate character dimensions
-        let char_w = 10.0; // Approximate monospace width
-        let char_h = 16.0; // Approximate monospace height
+        let char_w = DEFAULT_CELL_WIDTH; // Approximate monospace width
+        let char_h = DEFAULT_CELL_HEIGHT; // Approximate monospace height
 
         // Create async channel for redraw signals
         let (redraw_tx, redraw_rx) = async_channel::unbounded::<()>();
 
+        // Start in the desktop's light/dark scheme, with its click timing
+        let config = with_desktop_pointer_settings(config.with_color_scheme(system_color_scheme()));
+
         // Create terminal core
-        let terminal = VteTerminalCore::new()?;
+        let terminal = VteTerminalCore::with_config_and_size(config.clone(), cols, rows)?;
 
         // Set up drawing
         let terminal_clone: Arc<std::sync::RwLock<vte_core::grid::Grid>> = Arc::clone(&terminal.grid);
         let redraw_tx_clone = redraw_tx.clone();
 
-        let drawing_config = config.clone();
-        area.set_draw_func(move |area, cr, _w, _h| {
-            // Handle drawing through renderer
-            let mut renderer = Gtk4Renderer::new(cr, area, char_w, char_h);
+        let config = Rc::new(RefCell::new(Arc::new(config)));
+
+        // Cell layout snapped to device pixels, re-snapped when the widget
+        // moves to a monitor with a different scale factor
+        let geometry = Rc::new(std::cell::Cell::new(cell_geometry(area, char_w, char_h, &config.borrow())));
+        {
+            let geometry = Rc::clone(&geometry);
+            let config = Rc::clone(&config);
+            area.connect_scale_factor_notify(move |area| {
+                geometry.set(cell_geometry(area, char_w, char_h, &config.borrow()));
+                area.queue_draw();
+            });
+        }
+
+        // Follow the desktop's high-contrast theme and light/dark preference
+        // as they change
+        if let Some(settings) = gtk4::Settings::default() {
+            let follow_scheme = {
+                let area = area.downgrade();
+                let color_scheme = terminal.color_scheme_handle();
+                let config = Rc::clone(&config);
+                move || {
+                    let Some(area) = area.upgrade() else {
+                        return;
+                    };
+                    let scheme = system_color_scheme();
+                    Arc::make_mut(&mut config.borrow_mut()).apply_color_scheme(scheme);
+                    // Mode 2031 notifications go out right away
+                    color_scheme.set(scheme);
+                    area.queue_draw();
+                }
+            };
+            let follow_scheme = Rc::new(follow_scheme);
+            {
+                let follow_scheme = Rc::clone(&follow_scheme);
+                settings.connect_gtk_theme_name_notify(move |_| follow_scheme());
+            }
+            settings.connect_gtk_application_prefer_dark_theme_notify(move |_| follow_scheme());
+        }
+
+        let drawing_config = Rc::clone(&config);
+        let inactive = Rc::new(std::cell::Cell::new(false));
+        let drawing_inactive = Rc::clone(&inactive);
+        // Rendered rows survive between frames; only changed rows are redrawn
+        let row_cache = Rc::new(RefCell::new(RowSurfaceCache::new()));
+        let drawing_geometry = Rc::clone(&geometry);
+        // Glyphs shared with the process's other terminals, held while the
+        // widget lives and swapped when the scale factor or zoom changes
+        let fonts = RefCell::new(None::<Arc<vte_core::font::SharedFontCache>>);
+        // The bell rings when the grid has seen a BEL since the last frame
+        let bell = Rc::new(RefCell::new(VisualBell::from_config(&config.borrow())));
+        let drawing_bell = Rc::clone(&bell);
+        let bells_seen = std::cell::Cell::new(0u64);
+        let output_pill: Rc<RefCell<Option<glib::WeakRef<gtk4::Button>>>> = Rc::new(RefCell::new(None));
+        let drawing_pill = Rc::clone(&output_pill);
+        // Settings as last rendered, reused while neither they nor the
+        // desktop's contrast change
+        let rendered = RefCell::new(None::<(Arc<TerminalConfig>, Arc<TerminalConfig>)>);
+        let unseen_shown = std::cell::Cell::new(0usize);
+        // Redraw when shown echo predictions expire, as at a password prompt
+        // nothing else would
+        let prediction_timer = Rc::new(std::cell::Cell::new(false));
+        // The reader parses large reads one drawn frame at a time
+        let frames = terminal.frame_ack();
+        // Scrolling, resizing and zooming move the cursor on screen as
+        // output does; each frame reports what changed since the last
+        let cursor_events = terminal.event_emitter();
+        let cursor_geometry = std::cell::Cell::new(None::<CellGeometry>);
+        area.set_draw_func(move |area, cr, w, h| {
+            // Handle drawing through the shared Cairo renderer
+            let ui_renderer = CairoUIRenderer::new(cr.clone()).with_pointer_handler({
+                let area = area.clone();
+                move |shape| apply_pointer_shape(&area, shape)
+            });
+            let geometry = drawing_geometry.get();
+            let settings = Arc::clone(&drawing_config.borrow());
+            let reused = rendered.borrow().as_ref()
+                .filter(|(source, config)| {
+                    Arc::ptr_eq(source, &settings) && config.high_contrast == (settings.high_contrast || system_prefers_high_contrast())
+                })
+                .map(|(_, config)| Arc::clone(config));
+            let config = reused.unwrap_or_else(|| {
+                let config = render_config(&settings);
+                *rendered.borrow_mut() = Some((settings, Arc::clone(&config)));
+                config
+            });
+            let key = render_font_key(&config, geometry.scale);
+            let held = fonts.borrow().clone().filter(|held| *held.key() == key);
+            let shared = match held.map_or_else(|| render_fonts(&config, geometry.scale), Ok) {
+                Ok(shared) => shared,
+                Err(e) => {
+                    warn!("Failed to create renderer: {}", e);
+                    return;
+                }
+            };
+            *fonts.borrow_mut() = Some(Arc::clone(&shared));
+            let mut renderer = match CairoRenderer::with_fonts(cr, geometry, shared) {
+                Ok(renderer) => renderer.with_ui_renderer(ui_renderer).with_row_cache(Rc::clone(&row_cache)),
+                Err(e) => {
+                    warn!("Failed to create renderer: {}", e);
+                    return;
+                }
+            };
+            renderer.apply_config(&config);
+            renderer.set_inactive(drawing_inactive.get());
 
             // Draw from terminal grid
             if let Ok(g) = terminal_clone.read() {
-                for r in 0..g.rows {
-                    for c in 0..g.cols {
-                        let cell = g.get_cell(r, c);
-                        renderer.text_renderer().draw_cell(r, c, cell);
+                if bells_seen.replace(g.bell_count()) != g.bell_count() {
+                    let mut event_loop = Gtk4EventLoop::new();
+                    event_loop.set_area(area);
+                    let area = area.downgrade();
+                    VisualBell::animate(&drawing_bell, &mut event_loop, move || {
+                        if let Some(area) = area.upgrade() {
+                            area.queue_draw();
+                        }
+                    });
+                }
+                renderer.set_visual_bell(drawing_bell.borrow().flash(Instant::now()));
+                renderer.render_grid(&g, w as f64, h as f64);
+                if let Some(expiry) = g.prediction_expiry() {
+                    if !prediction_timer.replace(true) {
+                        let area = area.downgrade();
+                        let timer = Rc::clone(&prediction_timer);
+                        glib::timeout_add_local_once(expiry, move || {
+                            timer.set(false);
+                            if let Some(area) = area.upgrade() {
+                                area.queue_draw();
+                            }
+                        });
                     }
                 }
 
-                // Draw cursor if visible
-                if g.row < g.rows && g.col < g.cols && g.is_cursor_visible() && g.scroll_offset == 0 {
-                    // Draw cursor outline
-                    renderer.ui_renderer().set_cursor_shape(CursorShape::Block);
+                // Widgets are not changed while drawing; the pill follows on idle
+                let unseen = g.unseen_output();
+                if unseen_shown.replace(unseen) != unseen {
+                    if let Some(pill) = drawing_pill.borrow().as_ref().and_then(|pill| pill.upgrade()) {
+                        glib::idle_add_local_once(move || update_output_pill(&pill, unseen));
+                    }
+                }
+            }
+
+            if let Ok(mut g) = terminal_clone.write() {
+                let moved = g.take_cursor_move();
+                let relaid = cursor_geometry.replace(Some(geometry)) != Some(geometry);
+                if let Some((row, col)) = moved.or_else(|| relaid.then(|| g.cursor_viewport_cell()).flatten()) {
+                    cursor_events.emit(TerminalEvent::CursorMoved { row, col });
                 }
             }
 
             // Signal redraw completion
+            frames.presented();
             let _ = redraw_tx_clone.send_blocking(());
         });
 
-        // Set up input handling
-        let writer_arc: Arc<Mutex<Box<dyn Write + Send>>> = Arc::new(Mutex::new(Box::new(std::io::sink())));
+        // Output redraws the widget
+        {
+            let redraws = terminal.redraw_receiver();
+            let area = area.downgrade();
+            glib::spawn_future_local(async move {
+                while redraws.recv().await.is_ok() {
+                    let Some(area) = area.upgrade() else {
+                        break;
+                    };
+                    area.queue_draw();
+                }
+            });
+        }
+
+        // Ctrl+wheel zooms; the capture phase runs ahead of wheel scrolling
+        let zoom_controller = EventControllerScroll::new(EventControllerScrollFlags::VERTICAL);
+        zoom_controller.set_propagation_phase(gtk4::PropagationPhase::Capture);
+        {
+            let area = area.downgrade();
+            let config = Rc::clone(&config);
+            let geometry = Rc::clone(&geometry);
+            let resizer = terminal.resize_handle();
+            let events = terminal.event_emitter();
+            zoom_controller.connect_scroll(move |controller, _, dy| {
+                let Some(area) = area.upgrade() else {
+                    return glib::Propagation::Proceed;
+                };
+                if !controller.current_event_state().contains(gdk::ModifierType::CONTROL_MASK) {
+                    return glib::Propagation::Proceed;
+                }
+                apply_zoom(&area, &config, &geometry, &resizer, &events, (char_w, char_h), |config| config.zoom_by(dy));
+                glib::Propagation::Stop
+            });
+        }
+        area.add_controller(zoom_controller);
+
+        // Set up input handling; keys and pastes share the core's paste
+        // streamer, so Escape cancels its pastes and typing queues behind them
+        Gtk4InputHandler::setup_keyboard(area, Arc::clone(&terminal.grid), terminal.paste_streamer(), redraw_tx.clone(), terminal.output_hold_handle());
+        Gtk4InputHandler::setup_mouse(
+            area,
+            Arc::clone(&terminal.grid),
+            redraw_tx.clone(),
+            terminal.event_emitter(),
+            Rc::clone(&geometry),
+            terminal.input_sink(),
+        );
 
-        Gtk4InputHandler::setup_keyboard(area, Arc::clone(&terminal.grid), writer_arc, redraw_tx.clone());
-        Gtk4InputHandler::setup_mouse(area, Arc::clone(&terminal.grid), redraw_tx.clone(), char_w, char_h);
+        // Actions for the embedder's menus, e.g. "terminal.copy-last-output"
+        let actions = gtk4::gio::SimpleActionGroup::new();
+        let copy_last_output = gtk4::gio::SimpleAction::new("copy-last-output", None);
+        {
+            let grid = Arc::clone(&terminal.grid);
+            let redraw_tx = redraw_tx.clone();
+            copy_last_output.connect_activate(move |_, _| {
+                Gtk4InputHandler::copy_last_command_output(&grid, &redraw_tx);
+            });
+        }
+        actions.add_action(&copy_last_output);
+        area.insert_action_group("terminal", Some(&actions));
 
         // Create event loop
         let mut event_loop = Gtk4EventLoop::new();
         event_loop.set_area(area);
 
+        // Cursor blink: the grid decides whether to blink (config default or
+        // the application's DECSET 12), so the timer runs for the area's lifetime
+        let blink_grid = Arc::clone(&terminal.grid);
+        let blink_area = area.downgrade();
+        event_loop.schedule_timer(config.borrow().cursor_blink_interval_ms, Box::new(move || {
+            let Some(area) = blink_area.upgrade() else {
+                return false;
+            };
+            if let Ok(mut g) = blink_grid.write() {
+                if g.cursor_blinks() && g.tick_cursor_blink() {
+                    area.queue_draw();
+                }
+            }
+            true
+        }));
+
         Ok(Gtk4Backend {
             terminal,
             event_loop,
             redraw_rx,
             redraw_tx,
+            config,
+            geometry,
+            inactive,
+            bell,
+            remote_timer: Rc::new(std::cell::Cell::new(false)),
+            output_pill,
+            area: area.clone(),
             char_w,
             char_h,
         })
@@ -103,6 +347,255 @@ impl Gtk4Backend {
         let _ = self.redraw_tx.send_blocking(());
     }
 
+    /// Render the current screen to a PNG file (screenshots, golden tests)
+    pub fn render_to_png(&self, path: impl AsRef<Path>) -> Result<(), BackendError> {
+        let grid = self.terminal.grid.read()
+            .map_err(|e| BackendError::Rendering(format!("grid lock poisoned: {}", e)))?;
+        vte_render_cairo::render_to_png(&grid, &render_config(&self.config.borrow()), path)
+    }
+
+    /// Render the current screen scaled to fit `width` x `height` pixels, e.g.
+    /// to refresh a tab thumbnail on `TerminalEvent::Damage`
+    pub fn render_thumbnail(&self, width: i32, height: i32) -> Result<cairo::ImageSurface, BackendError> {
+        let grid = self.terminal.grid.read()
+            .map_err(|e| BackendError::Rendering(format!("grid lock poisoned: {}", e)))?;
+        vte_render_cairo::render_thumbnail(&grid, &render_config(&self.config.borrow()), width, height)
+    }
+
+    /// Turn high-contrast rendering on or off at runtime
+    ///
+    /// A desktop high-contrast theme keeps the mode on regardless.
+    pub fn set_high_contrast(&self, enabled: bool) {
+        Arc::make_mut(&mut self.config.borrow_mut()).high_contrast = enabled;
+        self.area.queue_draw();
+    }
+
+    /// Change the brightness, contrast and gamma of everything drawn, e.g.
+    /// for a projector
+    ///
+    /// Only the rendering changes; colors reported to applications stay the
+    /// configured ones.
+    pub fn set_color_adjust(&self, adjust: ColorAdjust) {
+        Arc::make_mut(&mut self.config.borrow_mut()).color_adjust = adjust;
+        self.area.queue_draw();
+    }
+
+    /// Change the extra space between columns and rows and around the cells
+    ///
+    /// Drawing and mouse hit-testing pick up the new layout at once; call
+    /// `fit_to_size` afterwards so the grid fills the widget again.
+    pub fn set_cell_spacing(&self, letter_spacing: f64, line_spacing: f64, padding: f64) {
+        {
+            let mut config = self.config.borrow_mut();
+            *config = Arc::new(TerminalConfig::clone(&config).with_cell_spacing(letter_spacing, line_spacing).with_padding(padding));
+        }
+        self.geometry.set(cell_geometry(&self.area, self.char_w, self.char_h, &self.config.borrow()));
+        self.area.queue_draw();
+    }
+
+    /// Resize the terminal to the columns and rows that fit `width` x `height`
+    /// pixels of widget, e.g. from the widget's `resize` signal
+    pub fn fit_to_size(&self, width: i32, height: i32) {
+        let geometry = self.geometry.get();
+        let (cols, rows) = geometry.grid_size(width as f64, height as f64);
+        self.terminal.resize(cols, rows);
+        self.terminal.set_cell_pixel_size(geometry.width.round() as usize, geometry.height.round() as usize);
+    }
+
+    /// Switch between the light and dark themes of `TerminalConfig::themes`
+    ///
+    /// The widget follows the desktop preference on its own; this overrides
+    /// it until the preference next changes.
+    pub fn set_color_scheme(&self, scheme: ColorScheme) {
+        Arc::make_mut(&mut self.config.borrow_mut()).apply_color_scheme(scheme);
+        self.terminal.set_color_scheme(scheme);
+        self.area.queue_draw();
+    }
+
+    /// Change how the bell is shown and how long the effect fades
+    pub fn set_visual_bell(&self, style: VisualBellStyle, duration_ms: u64) {
+        let mut config = self.config.borrow_mut();
+        *config = Arc::new(TerminalConfig::clone(&config).with_visual_bell(style, duration_ms));
+        *self.bell.borrow_mut() = VisualBell::from_config(&config);
+    }
+
+    /// Set the font zoom factor, clamped to `MIN_ZOOM..=MAX_ZOOM`
+    ///
+    /// The grid is refitted to the widget at the new cell size and
+    /// `TerminalEvent::ZoomChanged` is emitted, as for Ctrl+wheel.
+    pub fn set_zoom(&self, zoom: f64) {
+        apply_zoom(&self.area, &self.config, &self.geometry, &self.terminal.resize_handle(),
+            &self.terminal.event_emitter(), (self.char_w, self.char_h), |config| config.set_zoom(zoom));
+    }
+
+    /// Current font zoom factor
+    pub fn zoom(&self) -> f64 {
+        self.config.borrow().zoom
+    }
+
+    /// Show collaborator `id`'s cursor at screen position `pos` in `color`
+    ///
+    /// The embedder calls this for every position update it receives; the
+    /// cursor disappears after `TerminalConfig::remote_cursor_timeout_ms`
+    /// without one.
+    pub fn set_remote_cursor(&self, id: u64, pos: (usize, usize), color: Color) {
+        if let Ok(mut g) = self.terminal.grid.write() {
+            g.set_remote_cursor(id, pos, color);
+        }
+        self.watch_remote_cursors();
+        self.area.queue_draw();
+    }
+
+    /// Set or clear collaborator `id`'s selection, start and end inclusive
+    pub fn set_remote_selection(&self, id: u64, selection: Option<((usize, usize), (usize, usize))>) {
+        if self.terminal.grid.write().is_ok_and(|mut g| g.set_remote_selection(id, selection)) {
+            self.area.queue_draw();
+        }
+    }
+
+    /// Stop showing collaborator `id`, e.g. when they leave the session
+    pub fn remove_remote_cursor(&self, id: u64) {
+        if self.terminal.grid.write().is_ok_and(|mut g| g.remove_remote_cursor(id)) {
+            self.area.queue_draw();
+        }
+    }
+
+    /// Redraw every second while collaborators' cursors are shown, so ones
+    /// that time out disappear without other activity
+    fn watch_remote_cursors(&self) {
+        if self.remote_timer.replace(true) {
+            return;
+        }
+        let grid = Arc::clone(&self.terminal.grid);
+        let area = self.area.downgrade();
+        let running = Rc::clone(&self.remote_timer);
+        glib::timeout_add_local(std::time::Duration::from_secs(1), move || {
+            let Some(area) = area.upgrade() else {
+                running.set(false);
+                return glib::ControlFlow::Break;
+            };
+            area.queue_draw();
+            if grid.read().is_ok_and(|g| !g.remote_cursors().is_empty()) {
+                glib::ControlFlow::Continue
+            } else {
+                running.set(false);
+                glib::ControlFlow::Break
+            }
+        });
+    }
+
+    /// Floating "↓ N new lines" pill, shown while output arrived below a
+    /// scrolled-back viewport (see `TerminalConfig::scroll_on_output`);
+    /// clicking it jumps to the bottom
+    ///
+    /// Place it over the terminal area, e.g. as a `gtk4::Overlay` overlay.
+    /// Only the pill returned by the latest call is kept up to date.
+    pub fn output_indicator(&self) -> gtk4::Button {
+        let pill = gtk4::Button::new();
+        pill.add_css_class("osd");
+        pill.add_css_class("pill");
+        pill.set_halign(gtk4::Align::Center);
+        pill.set_valign(gtk4::Align::End);
+        pill.set_margin_bottom(12);
+        update_output_pill(&pill, self.terminal.grid.read().map_or(0, |g| g.unseen_output()));
+
+        let grid = Arc::clone(&self.terminal.grid);
+        let area = self.area.downgrade();
+        pill.connect_clicked(move |pill| {
+            if let Ok(mut g) = grid.write() {
+                g.scroll_to_bottom();
+            }
+            update_output_pill(pill, 0);
+            if let Some(area) = area.upgrade() {
+                area.queue_draw();
+            }
+        });
+        self.output_pill.replace(Some(pill.downgrade()));
+        pill
+    }
+
+    /// Ask the user whether the application may read the clipboard, for a
+    /// `TerminalEvent::ClipboardReadRequest`
+    ///
+    /// Embedders draining `VteTerminalCore::events` pass each event here. On
+    /// "Allow" the clipboard (or primary selection) is sent to the
+    /// application; on "Deny" nothing is. Returns false for other events.
+    pub fn prompt_clipboard_read(&self, event: &TerminalEvent) -> bool {
+        let TerminalEvent::ClipboardReadRequest { id, primary } = *event else {
+            return false;
+        };
+        let dialog = gtk4::MessageDialog::builder()
+            .modal(true)
+            .message_type(gtk4::MessageType::Question)
+            .text("Allow the application to read the clipboard?")
+            .secondary_text("Programs in the terminal, including those on remote hosts, will receive its contents.")
+            .build();
+        if let Some(window) = self.area.root().and_downcast::<gtk4::Window>() {
+            dialog.set_transient_for(Some(&window));
+        }
+        dialog.add_button("Deny", gtk4::ResponseType::Reject);
+        dialog.add_button("Allow", gtk4::ResponseType::Accept);
+        dialog.set_default_response(gtk4::ResponseType::Reject);
+
+        let grid = Arc::clone(&self.terminal.grid);
+        let input = self.terminal.input_sink();
+        let area = self.area.clone();
+        dialog.connect_response(move |dialog, response| {
+            dialog.close();
+            if response != gtk4::ResponseType::Accept {
+                answer_clipboard_read(&grid, &input, id, None);
+                return;
+            }
+            let clipboard = if primary { area.primary_clipboard() } else { area.clipboard() };
+            let (grid, input) = (Arc::clone(&grid), input.clone());
+            clipboard.read_text_async(None::<&gtk4::gio::Cancellable>, move |res| {
+                let text = res.ok().flatten();
+                answer_clipboard_read(&grid, &input, id, Some(text.as_ref().map_or("", |text| text.as_str())));
+            });
+        });
+        dialog.present();
+        true
+    }
+
+    /// Select the output of the most recent command and copy it to the
+    /// clipboard, as Ctrl+Shift+Up does; returns false if there is none
+    ///
+    /// Uses the OSC 133 command zones when the shell sends them, the rows
+    /// between the last two prompts otherwise (see
+    /// `Grid::last_command_output_zone`). Menus can use the widget's
+    /// `terminal.copy-last-output` action instead.
+    pub fn copy_last_command_output(&self) -> bool {
+        Gtk4InputHandler::copy_last_command_output(&self.terminal.grid, &self.redraw_tx)
+    }
+
+    /// Where the cursor is, with its cell in widget coordinates at the
+    /// current zoom and spacing, for placing a popover at it; follow
+    /// `TerminalEvent::CursorMoved` to keep it there
+    pub fn cursor_screen_position(&self) -> Option<CursorPosition> {
+        let grid = self.terminal.grid.read().ok()?;
+        grid.cursor_screen_position(&self.geometry.get())
+    }
+
+    /// Whether high-contrast rendering is in effect (configured or from the desktop)
+    pub fn is_high_contrast(&self) -> bool {
+        render_config(&self.config.borrow()).high_contrast
+    }
+
+    /// Draw the terminal muted (`TerminalConfig::inactive_style`) or normally
+    ///
+    /// Called by the container when focus moves between visible terminals,
+    /// e.g. the panes of a split.
+    pub fn set_inactive(&self, inactive: bool) {
+        if self.inactive.replace(inactive) != inactive {
+            self.area.queue_draw();
+        }
+    }
+
+    /// Whether the terminal is drawn muted
+    pub fn is_inactive(&self) -> bool {
+        self.inactive.get()
+    }
+
     /// Process pending redraws
     pub fn process_events(&self) {
         // Try to receive redraw signals (non-blocking)
@@ -110,42 +603,123 @@ impl Gtk4Backend {
     }
 }
 
-/// Composite GTK4 renderer
-pub struct Gtk4Renderer {
-    text_renderer: CairoTextRenderer,
-    graphics_renderer: CairoGraphicsRenderer,
-    ui_renderer: CairoUIRenderer,
+/// Cell layout for `area`'s scale factor with the zoom, spacing and padding of `config`
+fn cell_geometry(area: &DrawingArea, char_w: f64, char_h: f64, config: &TerminalConfig) -> CellGeometry {
+    let (char_w, char_h) = (char_w * config.zoom, char_h * config.zoom);
+    CellGeometry::new(char_w, char_h, char_h * 0.75, area.scale_factor() as f64).with_config_spacing(config)
 }
 
-impl Gtk4Renderer {
-    pub fn new(context: &cairo::Context, _area: &DrawingArea, char_w: f64, char_h: f64) -> Self {
-        // Create font cache with fallback chains
-        let font_cache = FontCache::new("DejaVu Sans Mono", 13.0)
-            .unwrap_or_else(|_| panic!("Failed to create font cache"));
-
-        let text_renderer = CairoTextRenderer::new(context.clone(), font_cache, char_w, char_h)
-            .unwrap_or_else(|_| panic!("Failed to create text renderer"));
-        let graphics_renderer = CairoGraphicsRenderer::new(context.clone());
-        let ui_renderer = CairoUIRenderer::new(context.clone());
-
-        Gtk4Renderer {
-            text_renderer,
-            graphics_renderer,
-            ui_renderer,
-        }
+/// Change the zoom of `config` with `change`; when it changed, lay the cells
+/// out again, refit the grid to `area` and emit `TerminalEvent::ZoomChanged`
+fn apply_zoom(
+    area: &DrawingArea,
+    config: &RefCell<Arc<TerminalConfig>>,
+    geometry: &std::cell::Cell<CellGeometry>,
+    resizer: &ResizeHandle,
+    events: &EventEmitter,
+    (char_w, char_h): (f64, f64),
+    change: impl FnOnce(&mut TerminalConfig) -> bool,
+) {
+    if !change(Arc::make_mut(&mut config.borrow_mut())) {
+        return;
     }
+    let config = config.borrow();
+    geometry.set(cell_geometry(area, char_w, char_h, &config));
+    let geometry = geometry.get();
+    let (cols, rows) = geometry.grid_size(area.width() as f64, area.height() as f64);
+    resizer.resize(cols, rows);
+    resizer.set_cell_pixel_size(geometry.width.round() as usize, geometry.height.round() as usize);
+    events.emit(TerminalEvent::ZoomChanged { zoom: config.zoom });
+    area.queue_draw();
 }
 
-impl Renderer for Gtk4Renderer {
-    fn text_renderer(&mut self) -> &mut dyn vte_core::TextRenderer {
-        &mut self.text_renderer
+/// Send the reply to clipboard read `id`, if the user allowed it
+fn answer_clipboard_read(grid: &RwLock<vte_core::grid::Grid>, input: &InputSink, id: u64, text: Option<&str>) {
+    let reply = grid.write().ok().and_then(|mut g| g.answer_clipboard_request(id, text));
+    if let Some(reply) = reply {
+        let _ = input.send(reply.as_bytes());
     }
+}
+
+/// Whether the desktop uses a high-contrast GTK theme (e.g. `HighContrast`)
+fn system_prefers_high_contrast() -> bool {
+    gtk4::Settings::default()
+        .and_then(|settings| settings.gtk_theme_name())
+        .is_some_and(|name| name.contains("HighContrast"))
+}
 
-    fn graphics_renderer(&mut self) -> &mut dyn vte_core::GraphicsRenderer {
-        &mut self.graphics_renderer
+/// Light/dark preference of the desktop: the application's prefer-dark
+/// setting or a dark GTK theme (e.g. `Adwaita-dark`)
+fn system_color_scheme() -> ColorScheme {
+    let dark = gtk4::Settings::default().is_some_and(|settings| {
+        settings.is_gtk_application_prefer_dark_theme()
+            || settings.gtk_theme_name().is_some_and(|name| name.to_lowercase().contains("dark"))
+    });
+    if dark { ColorScheme::Dark } else { ColorScheme::Light }
+}
+
+/// `config` with the desktop's double-click time and drag threshold where
+/// it keeps the built-in defaults
+fn with_desktop_pointer_settings(mut config: TerminalConfig) -> TerminalConfig {
+    let Some(settings) = gtk4::Settings::default() else {
+        return config;
+    };
+    let defaults = TerminalConfig::default();
+    if config.click_timeout_ms == defaults.click_timeout_ms {
+        config.click_timeout_ms = settings.gtk_double_click_time().max(0) as u128;
+    }
+    if config.drag_threshold_px == defaults.drag_threshold_px {
+        config.drag_threshold_px = settings.gtk_dnd_drag_threshold().max(0) as f64;
+    }
+    config
+}
+
+/// Configuration to render with: `config`, with high contrast forced on by
+/// the desktop; copied only when the desktop forces it
+fn render_config(config: &Arc<TerminalConfig>) -> Arc<TerminalConfig> {
+    if config.high_contrast || !system_prefers_high_contrast() {
+        return Arc::clone(config);
     }
+    let mut config = TerminalConfig::clone(config);
+    config.high_contrast = true;
+    Arc::new(config)
+}
+
+/// Show `event` as a desktop notification through the widget's `gtk4::Application`
+///
+/// Embedders draining `VteTerminalCore::events` pass each event here, e.g. to
+/// announce "make finished (2m13s, exit 0)". Returns false if the event has
+/// no notification text or the widget is not in an application window.
+pub fn show_event_notification(widget: &impl IsA<gtk4::Widget>, event: &TerminalEvent) -> bool {
+    let Some(text) = event.notification_text() else {
+        return false;
+    };
+    let Some(app) = widget.root().and_downcast::<gtk4::Window>().and_then(|window| window.application()) else {
+        return false;
+    };
+    app.send_notification(None, &gtk4::gio::Notification::new(&text));
+    true
+}
+
+/// Show the pill with the count of `unseen` rows, or hide it at 0
+fn update_output_pill(pill: &gtk4::Button, unseen: usize) {
+    pill.set_visible(unseen > 0);
+    if unseen > 0 {
+        let plural = if unseen == 1 { "" } else { "s" };
+        pill.set_label(&format!("↓ {} new line{}", unseen, plural));
+    }
+}
 
-    fn ui_renderer(&mut self) -> &mut dyn vte_core::UIRenderer {
-        &mut self.ui_renderer
+/// Apply a pointer shape hint to the widget (CSS cursor names)
+fn apply_pointer_shape(area: &DrawingArea, shape: PointerShape) {
+    let name = match shape {
+        PointerShape::Text => "text",
+        PointerShape::Link => "pointer",
+        PointerShape::Hidden => "none",
+        PointerShape::Named(name) => name,
+    };
+    // Called on every draw; only touch the widget when the shape changes
+    if area.cursor().and_then(|c| c.name()).as_deref() != Some(name) {
+        area.set_cursor_from_name(Some(name));
     }
 }
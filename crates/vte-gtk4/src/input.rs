@@ -1,13 +1,17 @@
 //! Input handling for GTK4 backend
 
-use gtk4::{DrawingArea, EventControllerKey, EventControllerMotion, EventControllerScroll, GestureClick, EventControllerScrollFlags};
+use gtk4::{DrawingArea, EventControllerFocus, EventControllerKey, EventControllerMotion, EventControllerScroll, GestureClick, EventControllerScrollFlags, IMMulticontext};
 use gtk4::gdk;
 use gtk4::prelude::*;
 use glib;
 use glib::Propagation;
-use std::sync::{Arc, Mutex};
-use std::io::Write;
-use vte_core::{InputHandler, EventLoop};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use vte_core::drawing::CellGeometry;
+use vte_core::events::EventEmitter;
+use vte_core::{encode_text, HoverTracker, InputHandler, InputSink, EventLoop, Key, LocatorButton, OutputHoldHandle, PasteStreamer, TextSource};
 use async_channel::{Sender, Receiver};
 
 /// Combined GTK4 input handler and event loop
@@ -58,78 +62,207 @@ impl Gtk4InputHandler {
     pub fn setup_keyboard(
         area: &DrawingArea,
         grid: Arc<std::sync::RwLock<vte_core::Grid>>,
-        writer: Arc<Mutex<Box<dyn Write + Send>>>,
+        paste: PasteStreamer,
         redraw_tx: Sender<()>,
+        output_hold: OutputHoldHandle,
     ) {
         let key_controller = EventControllerKey::new();
 
-        key_controller.connect_key_pressed(move |_, keyval, _keycode, state| {
-            Self::handle_key_event(keyval, state, &grid, &writer, &redraw_tx)
-        });
+        // Printable text goes through the input method so dead keys, compose
+        // sequences and IME preedit work; the IM delivers the result on commit
+        let im_context = IMMulticontext::new();
+        im_context.set_client_widget(Some(area));
+        {
+            let grid = Arc::clone(&grid);
+            let paste = paste.clone();
+            let redraw_tx = redraw_tx.clone();
+            im_context.connect_commit(move |_, text| {
+                Self::commit_text(text, TextSource::InputMethod, &grid, &paste, &redraw_tx);
+            });
+        }
+        key_controller.set_im_context(Some(&im_context));
+
+        {
+            let grid = Arc::clone(&grid);
+            key_controller.connect_key_pressed(move |_, keyval, _keycode, state| {
+                Self::handle_key_event(keyval, state, &grid, &paste, &redraw_tx, &output_hold)
+            });
+        }
 
         area.add_controller(key_controller);
+
+        // Focus feeds the IM and the grid (command completion notifications)
+        let focus_controller = EventControllerFocus::new();
+        {
+            let im_context = im_context.clone();
+            let grid = Arc::clone(&grid);
+            focus_controller.connect_enter(move |_| {
+                im_context.focus_in();
+                Self::set_focused(&grid, true);
+            });
+        }
+        focus_controller.connect_leave(move |_| {
+            im_context.focus_out();
+            Self::set_focused(&grid, false);
+        });
+        area.add_controller(focus_controller);
+    }
+
+    fn set_focused(grid: &std::sync::RwLock<vte_core::Grid>, focused: bool) {
+        if let Ok(mut g) = grid.write() {
+            g.set_focused(focused);
+        }
     }
 
     pub fn setup_mouse(
         area: &DrawingArea,
         grid: Arc<std::sync::RwLock<vte_core::Grid>>,
         redraw_tx: Sender<()>,
-        char_w: f64,
-        char_h: f64,
+        events: EventEmitter,
+        geometry: Rc<std::cell::Cell<CellGeometry>>,
+        input: InputSink,
     ) {
+        let hover_delay = grid.read().map_or(0, |g| g.config.hover_delay_ms);
+
         // Mouse click gestures
         let click_gesture = GestureClick::new();
         click_gesture.set_button(0); // Any button
 
+        let click_grid = Arc::clone(&grid);
+        let click_redraw_tx = redraw_tx.clone();
+        let click_geometry = Rc::clone(&geometry);
+        let click_events = events.clone();
+        let click_input = input.clone();
         click_gesture.connect_pressed(move |gesture, n_press, x, y| {
-            let (r, c) = Self::xy_to_cell(x, y, char_w, char_h, &grid);
+            let (r, c) = click_geometry.get().cell_at(x, y);
             let button = gesture.current_button();
 
-            // Handle selection
-            if let Ok(mut g) = grid.write() {
-                if n_press == 1 {
-                    g.start_selection(r, c);
-                } else if n_press == 2 {
-                    g.select_word(r, c);
-                } else if n_press == 3 {
-                    g.select_line(r);
+            // Applications using the DEC locator get the press instead
+            if let Some(locator_button) = LocatorButton::from_number(button) {
+                let handled = click_grid.write().ok().and_then(|mut g| {
+                    if !g.locator_enabled() {
+                        return None;
+                    }
+                    Some(g.locator_button(locator_button, true, x, y, &click_geometry.get()))
+                });
+                if let Some(report) = handled {
+                    if let Some(report) = report {
+                        if let Err(e) = click_input.send(report.as_bytes()) {
+                            click_events.emit_error(&e);
+                        }
+                    }
+                    return;
                 }
-                let _ = redraw_tx.send_blocking(());
+            }
+
+            // A click on an application-defined button is reported to it
+            // instead of starting a selection
+            if n_press == 1 && button == gdk::BUTTON_PRIMARY {
+                let report = click_grid.read().ok().and_then(|g| g.click_active_region(r, c));
+                if let Some(report) = report {
+                    if let Err(e) = click_input.send(report.as_bytes()) {
+                        click_events.emit_error(&e);
+                    }
+                    return;
+                }
+            }
+
+            // Handle selection; clicks are counted with the configured
+            // double-click interval rather than GTK's
+            if let Ok(mut g) = click_grid.write() {
+                match g.register_click(r, c) {
+                    1 => g.start_selection_at(r, c, x, y),
+                    2 => g.select_word(r, c),
+                    _ => g.select_line(r),
+                }
+                let _ = click_redraw_tx.send_blocking(());
             }
         });
 
-        click_gesture.connect_released(move |_, _, x, y| {
-            let (r, c) = Self::xy_to_cell(x, y, char_w, char_h, &grid);
-            if let Ok(mut g) = grid.write() {
+        let release_grid = Arc::clone(&grid);
+        let release_redraw_tx = redraw_tx.clone();
+        let release_geometry = Rc::clone(&geometry);
+        let release_events = events.clone();
+        let release_input = input.clone();
+        click_gesture.connect_released(move |gesture, _, x, y| {
+            let (r, c) = release_geometry.get().cell_at(x, y);
+            if let Ok(mut g) = release_grid.write() {
+                if g.locator_enabled() {
+                    if let Some(button) = LocatorButton::from_number(gesture.current_button()) {
+                        if let Some(report) = g.locator_button(button, false, x, y, &release_geometry.get()) {
+                            drop(g);
+                            if let Err(e) = release_input.send(report.as_bytes()) {
+                                release_events.emit_error(&e);
+                            }
+                        }
+                        return;
+                    }
+                }
                 if g.complete_selection(r, c) {
-                    let _ = redraw_tx.send_blocking(());
+                    let _ = release_redraw_tx.send_blocking(());
+                }
+            }
+        });
+
+        // A cancelled gesture sees no release; end the drag and any freeze
+        let cancel_grid = Arc::clone(&grid);
+        let cancel_redraw_tx = redraw_tx.clone();
+        click_gesture.connect_cancel(move |_, _| {
+            if let Ok(mut g) = cancel_grid.write() {
+                if g.is_selecting() {
+                    g.cancel_selection();
+                    let _ = cancel_redraw_tx.send_blocking(());
                 }
             }
         });
 
         area.add_controller(click_gesture);
 
-        // Mouse motion for selection dragging
+        // Mouse motion for selection dragging and link hover previews
+        let hover = Rc::new(RefCell::new(HoverTracker::new(Duration::from_millis(hover_delay))));
+        let hover_timer = Rc::new(std::cell::Cell::new(false));
         let motion_controller = EventControllerMotion::new();
-        motion_controller.connect_motion(move |_, x, y| {
-            let (r, c) = Self::xy_to_cell(x, y, char_w, char_h, &grid);
-            if let Ok(mut g) = grid.write() {
-                g.update_selection(r, c);
-                if g.is_dragging() {
-                    let _ = redraw_tx.send_blocking(());
+        {
+            let grid = Arc::clone(&grid);
+            let redraw_tx = redraw_tx.clone();
+            let hover = Rc::clone(&hover);
+            let events = events.clone();
+            motion_controller.connect_motion(move |_, x, y| {
+                let (r, c) = geometry.get().cell_at(x, y);
+                if let Ok(mut g) = grid.write() {
+                    g.locator_moved(x, y, &geometry.get());
+                    g.update_selection_at(r, c, x, y);
+                    // Shows the pointer again and switches to a hand over URLs
+                    let pointer_changed = g.pointer_moved(r, c);
+                    if g.is_dragging() || pointer_changed {
+                        let _ = redraw_tx.send_blocking(());
+                    }
+
+                    let link = g.link_at(r, c);
+                    drop(g);
+                    Self::hover_moved(&hover, &hover_timer, link, &events);
                 }
-            }
-        });
+            });
+        }
+        {
+            let grid = Arc::clone(&grid);
+            motion_controller.connect_leave(move |_| {
+                if let Ok(mut g) = grid.write() {
+                    g.locator_left();
+                }
+                if let Some(event) = hover.borrow_mut().left() {
+                    events.emit(event);
+                }
+            });
+        }
 
         area.add_controller(motion_controller);
 
-        // Mouse wheel scrolling
+        // Mouse wheel scrolling (speed, Shift paging and direction per config)
         let scroll_controller = EventControllerScroll::new(EventControllerScrollFlags::VERTICAL);
-        scroll_controller.connect_scroll(move |_, _, dy| {
-            if let Ok(mut g) = grid.write() {
-                let lines = (dy * 3.0) as isize; // 3 lines per scroll unit
-                g.scroll_offset = (g.scroll_offset as isize + lines)
-                    .max(0) as usize;
+        scroll_controller.connect_scroll(move |controller, _, dy| {
+            let shift = controller.current_event_state().contains(gdk::ModifierType::SHIFT_MASK);
+            if grid.write().map(|mut g| g.scroll_wheel(dy, shift)).unwrap_or(false) {
                 let _ = redraw_tx.send_blocking(());
             }
             Propagation::Stop
@@ -138,15 +271,73 @@ impl Gtk4InputHandler {
         area.add_controller(scroll_controller);
     }
 
+    /// Feed a pointer move into the hover tracker, emitting a cancel now and
+    /// arming the timer for the pending link's `HoverUrl`
+    fn hover_moved(
+        hover: &Rc<RefCell<HoverTracker>>,
+        timer: &Rc<std::cell::Cell<bool>>,
+        link: Option<vte_core::HoveredLink>,
+        events: &EventEmitter,
+    ) {
+        if let Some(event) = hover.borrow_mut().moved(link, Instant::now()) {
+            events.emit(event);
+        }
+        Self::arm_hover_timer(hover, timer, events);
+    }
+
+    /// Start the hover timer for the pending link's deadline, unless it is
+    /// already running; one timer serves all pointer moves
+    fn arm_hover_timer(hover: &Rc<RefCell<HoverTracker>>, timer: &Rc<std::cell::Cell<bool>>, events: &EventEmitter) {
+        if timer.get() {
+            return;
+        }
+        let Some(deadline) = hover.borrow().deadline() else {
+            return;
+        };
+        timer.set(true);
+        let hover = Rc::clone(hover);
+        let timer = Rc::clone(timer);
+        let events = events.clone();
+        glib::timeout_add_local_once(deadline.saturating_duration_since(Instant::now()), move || {
+            timer.set(false);
+            if let Some(event) = hover.borrow_mut().poll(Instant::now()) {
+                events.emit(event);
+            }
+            // A link entered since the timer started has a later deadline
+            Self::arm_hover_timer(&hover, &timer, &events);
+        });
+    }
+
     fn handle_key_event(
         keyval: gdk::Key,
         state: gdk::ModifierType,
         grid: &Arc<std::sync::RwLock<vte_core::Grid>>,
-        writer: &Arc<Mutex<Box<dyn Write + Send>>>,
+        paste: &PasteStreamer,
         redraw_tx: &Sender<()>,
+        output_hold: &OutputHoldHandle,
     ) -> Propagation {
         // Copy/Paste handling
-        if Self::handle_copy_paste(keyval, state, grid, writer, redraw_tx) {
+        if Self::handle_copy_paste(keyval, state, grid, paste, redraw_tx) {
+            return Propagation::Stop;
+        }
+
+        // Escape aborts a paste that is still being streamed
+        if keyval == gdk::Key::Escape && paste.cancel() {
+            return Propagation::Stop;
+        }
+
+        // Output hold (Scroll Lock or Ctrl+Shift+S)
+        let hold = keyval == gdk::Key::Scroll_Lock
+            || (state.contains(gdk::ModifierType::CONTROL_MASK | gdk::ModifierType::SHIFT_MASK)
+                && matches!(keyval, gdk::Key::s | gdk::Key::S));
+        if hold {
+            output_hold.set(!output_hold.is_held());
+            let _ = redraw_tx.send_blocking(());
+            return Propagation::Stop;
+        }
+
+        // Time travel through screen snapshots (Ctrl+Shift+Left/Right, Escape to leave)
+        if Self::handle_time_travel_keys(keyval, state, grid, redraw_tx) {
             return Propagation::Stop;
         }
 
@@ -155,18 +346,29 @@ impl Gtk4InputHandler {
             return Propagation::Stop;
         }
 
-        // Special keys
-        if let Some(seq) = Self::handle_special_keys(keyval, state) {
-            Self::write_to_writer(writer, &seq);
+        // Special keys, encoded through the grid so the input trace sees them
+        if let Some(key) = Self::handle_special_keys(keyval, state) {
+            let seq = grid.write().ok().and_then(|mut g| g.encode_key(key));
+            let Some(seq) = seq else {
+                return Propagation::Stop;
+            };
+            Self::hide_pointer_for_typing(grid, redraw_tx);
+            if let Ok(mut g) = grid.write() {
+                g.predict_echo("", TextSource::Key);
+            }
+            // Typed keys queue behind a paste in flight
+            let _ = paste.send_input(&seq);
             let _ = redraw_tx.send_blocking(());
             return Propagation::Stop;
         }
 
-        // Unicode input
+        // Keys the input method did not consume (e.g. with IM disabled)
         if let Some(ch) = keyval.to_unicode() {
+            if let Ok(mut g) = grid.write() {
+                g.encode_key(Key::Char(ch));
+            }
             let mut buf = [0u8; 4];
-            Self::write_to_writer(writer, ch.encode_utf8(&mut buf).as_bytes());
-            let _ = redraw_tx.send_blocking(());
+            Self::commit_text(ch.encode_utf8(&mut buf), TextSource::Key, grid, paste, redraw_tx);
         }
 
         Propagation::Stop
@@ -176,7 +378,7 @@ impl Gtk4InputHandler {
         keyval: gdk::Key,
         state: gdk::ModifierType,
         grid: &Arc<std::sync::RwLock<vte_core::Grid>>,
-        writer: &Arc<Mutex<Box<dyn Write + Send>>>,
+        paste: &PasteStreamer,
         redraw_tx: &Sender<()>,
     ) -> bool {
         // Copy (Ctrl+Shift+C or Cmd+C)
@@ -198,20 +400,29 @@ impl Gtk4InputHandler {
             return true;
         }
 
+        // Copy the last command's output (Ctrl+Shift+Up); with no output to
+        // copy, the key is handled as usual
+        if state.contains(gdk::ModifierType::CONTROL_MASK | gdk::ModifierType::SHIFT_MASK)
+            && keyval == gdk::Key::Up
+            && Self::copy_last_command_output(grid, redraw_tx)
+        {
+            return true;
+        }
+
         // Paste (Ctrl+Shift+V or Cmd+V)
-        let paste = (state.contains(gdk::ModifierType::META_MASK) ||
+        let paste_requested = (state.contains(gdk::ModifierType::META_MASK) ||
                     state.contains(gdk::ModifierType::CONTROL_MASK | gdk::ModifierType::SHIFT_MASK))
                    && keyval == gdk::Key::v;
 
-        if paste {
-            let writer_clone = Arc::clone(writer);
+        if paste_requested {
+            let grid_clone = Arc::clone(grid);
+            let paste_clone = paste.clone();
             let tx_clone = redraw_tx.clone();
 
             if let Some(display) = gdk::Display::default() {
                 display.clipboard().read_text_async(None::<&gtk4::gio::Cancellable>, move |res| {
                     if let Ok(Some(text)) = res {
-                        Self::write_to_writer(&writer_clone, text.as_bytes());
-                        let _ = tx_clone.send_blocking(());
+                        Self::paste_text(&text, &grid_clone, &paste_clone, &tx_clone);
                     }
                 });
             }
@@ -221,6 +432,50 @@ impl Gtk4InputHandler {
         false
     }
 
+    /// Step through screen snapshots; returns true if the key was consumed
+    ///
+    /// Without snapshots (`TerminalConfig::snapshot_interval_ms` unset), or
+    /// with none to step to, the keys go to the application.
+    fn handle_time_travel_keys(
+        keyval: gdk::Key,
+        state: gdk::ModifierType,
+        grid: &std::sync::RwLock<vte_core::Grid>,
+        redraw_tx: &Sender<()>,
+    ) -> bool {
+        let Ok(mut g) = grid.write() else {
+            return false;
+        };
+        let ctrl_shift = state.contains(gdk::ModifierType::CONTROL_MASK | gdk::ModifierType::SHIFT_MASK)
+            && g.config.snapshot_interval_ms.is_some();
+        let consumed = match keyval {
+            // Kept while time travelling, even at the oldest snapshot
+            gdk::Key::Left if ctrl_shift => g.step_snapshot_back() || g.shown_snapshot().is_some(),
+            gdk::Key::Right if ctrl_shift => g.step_snapshot_forward(),
+            gdk::Key::Escape if g.shown_snapshot().is_some() => {
+                g.leave_snapshot();
+                true
+            }
+            _ => false,
+        };
+        if consumed {
+            let _ = redraw_tx.send_blocking(());
+        }
+        consumed
+    }
+
+    /// Select the output of the most recent command and copy it to the
+    /// clipboard; returns false if there is none
+    pub(crate) fn copy_last_command_output(grid: &std::sync::RwLock<vte_core::Grid>, redraw_tx: &Sender<()>) -> bool {
+        let Some(text) = grid.write().ok().and_then(|mut g| g.select_last_command_output()) else {
+            return false;
+        };
+        if let Some(display) = gdk::Display::default() {
+            display.clipboard().set_text(&text);
+        }
+        let _ = redraw_tx.send_blocking(());
+        true
+    }
+
     fn handle_scroll_keys(
         keyval: gdk::Key,
         grid: &Arc<std::sync::RwLock<vte_core::Grid>>,
@@ -242,68 +497,107 @@ impl Gtk4InputHandler {
         true
     }
 
-    fn handle_special_keys(keyval: gdk::Key, state: gdk::ModifierType) -> Option<&'static [u8]> {
-        use gdk::Key;
-        match keyval {
-            Key::Return => Some(b"\r"),
-            Key::BackSpace => Some(b"\x7f"),
-            Key::Tab => Some(b"\t"),
-            Key::Home => Some(b"\x1b[H"),
-            Key::End => Some(b"\x1b[F"),
-            Key::Delete => Some(b"\x1b[3~"),
-            Key::Insert => Some(b"\x1b[2~"),
-            Key::Page_Up => Some(b"\x1b[5~"),
-            Key::Page_Down => Some(b"\x1b[6~"),
-            Key::Up => Some(b"\x1b[A"),
-            Key::Down => Some(b"\x1b[B"),
-            Key::Right => Some(b"\x1b[C"),
-            Key::Left => Some(b"\x1b[D"),
-            Key::F1 => Some(b"\x1bOP"),
-            Key::F2 => Some(b"\x1bOQ"),
-            Key::F3 => Some(b"\x1bOR"),
-            Key::F4 => Some(b"\x1bOS"),
-            Key::F5 => Some(b"\x1b[15~"),
-            Key::F6 => Some(b"\x1b[17~"),
-            Key::F7 => Some(b"\x1b[18~"),
-            Key::F8 => Some(b"\x1b[19~"),
-            Key::F9 => Some(b"\x1b[20~"),
-            Key::F10 => Some(b"\x1b[21~"),
-            Key::F11 => Some(b"\x1b[23~"),
-            Key::F12 => Some(b"\x1b[24~"),
-            _ if state.contains(gdk::ModifierType::CONTROL_MASK) => match keyval {
-                Key::d => Some(b"\x04"),
-                Key::l => Some(b"\x0c"),
-                Key::c => Some(b"\x03"),
-                Key::z => Some(b"\x1a"),
-                _ => None,
-            },
-            _ => None,
+    fn handle_special_keys(keyval: gdk::Key, state: gdk::ModifierType) -> Option<Key> {
+        use gdk::Key as K;
+        let key = match keyval {
+            K::Return => Key::Enter,
+            K::KP_Enter => Key::KeypadEnter,
+            K::BackSpace => Key::Backspace,
+            K::Tab => Key::Tab,
+            K::Home => Key::Home,
+            K::End => Key::End,
+            K::Delete => Key::Delete,
+            K::Insert => Key::Insert,
+            K::Page_Up => Key::PageUp,
+            K::Page_Down => Key::PageDown,
+            K::Up => Key::Up,
+            K::Down => Key::Down,
+            K::Right => Key::Right,
+            K::Left => Key::Left,
+            K::F1 => Key::F(1),
+            K::F2 => Key::F(2),
+            K::F3 => Key::F(3),
+            K::F4 => Key::F(4),
+            K::F5 => Key::F(5),
+            K::F6 => Key::F(6),
+            K::F7 => Key::F(7),
+            K::F8 => Key::F(8),
+            K::F9 => Key::F(9),
+            K::F10 => Key::F(10),
+            K::F11 => Key::F(11),
+            K::F12 => Key::F(12),
+            K::F13 => Key::F(13),
+            K::F14 => Key::F(14),
+            K::F15 => Key::F(15),
+            K::F16 => Key::F(16),
+            K::F17 => Key::F(17),
+            K::F18 => Key::F(18),
+            K::F19 => Key::F(19),
+            K::F20 => Key::F(20),
+            _ if state.contains(gdk::ModifierType::CONTROL_MASK) => {
+                return match keyval {
+                    K::d => Some(Key::Ctrl('d')),
+                    K::l => Some(Key::Ctrl('l')),
+                    K::c => Some(Key::Ctrl('c')),
+                    K::z => Some(Key::Ctrl('z')),
+                    _ => None,
+                };
+            }
+            _ => return None,
+        };
+        // Shift+Fn sends the key's DECUDK definition through `encode_key`
+        match key {
+            Key::F(n) if state.contains(gdk::ModifierType::SHIFT_MASK) => Some(Key::ShiftF(n)),
+            key => Some(key),
         }
     }
 
-    fn xy_to_cell(
-        x: f64,
-        y: f64,
-        char_w: f64,
-        char_h: f64,
+    /// Encode committed text through the core's text input pipeline and write it
+    fn commit_text(
+        text: &str,
+        source: TextSource,
         grid: &Arc<std::sync::RwLock<vte_core::Grid>>,
-    ) -> (usize, usize) {
-        let (c, r) = if let Ok(g) = grid.read() {
-            (
-                (x / char_w) as usize,
-                (y / char_h) as usize,
-            )
-        } else {
-            (0, 0)
-        };
-        (r, c)
+        paste: &PasteStreamer,
+        redraw_tx: &Sender<()>,
+    ) {
+        if matches!(source, TextSource::Key | TextSource::InputMethod) {
+            Self::hide_pointer_for_typing(grid, redraw_tx);
+        }
+        let bytes = grid.write().map(|mut g| {
+            g.predict_echo(text, source);
+            let bytes = encode_text(text, source, g.is_bracketed_paste_mode());
+            g.trace_text(text, source, &bytes);
+            bytes
+        }).unwrap_or_else(|_| encode_text(text, source, false));
+        if !bytes.is_empty() {
+            let _ = paste.send_input(&bytes);
+            let _ = redraw_tx.send_blocking(());
+        }
     }
 
-    #[inline]
-    fn write_to_writer(writer: &Arc<Mutex<Box<dyn Write + Send>>>, data: &[u8]) {
-        let _ = writer.lock().map(|mut w| {
-            w.write_all(data)?;
-            w.flush()
-        });
+    /// Paste clipboard text; large pastes are streamed in the background
+    fn paste_text(
+        text: &str,
+        grid: &Arc<std::sync::RwLock<vte_core::Grid>>,
+        paste: &PasteStreamer,
+        redraw_tx: &Sender<()>,
+    ) {
+        let bytes = grid.write().map(|mut g| {
+            g.predict_echo(text, TextSource::Paste);
+            let bytes = encode_text(text, TextSource::Paste, g.is_bracketed_paste_mode());
+            g.trace_text(text, TextSource::Paste, &bytes);
+            bytes
+        }).unwrap_or_else(|_| encode_text(text, TextSource::Paste, false));
+        if !bytes.is_empty() {
+            let _ = paste.send(bytes);
+            let _ = redraw_tx.send_blocking(());
+        }
+    }
+
+    /// Hide the mouse pointer while typing (if configured)
+    fn hide_pointer_for_typing(grid: &Arc<std::sync::RwLock<vte_core::Grid>>, redraw_tx: &Sender<()>) {
+        if grid.write().map(|mut g| g.pointer_key_typed()).unwrap_or(false) {
+            let _ = redraw_tx.send_blocking(());
+        }
     }
 }
@@ -3,22 +3,18 @@
 //! This crate provides a complete GTK4 implementation of the vte-core traits,
 //! enabling terminal emulation with GTK4 user interface components.
 
-use crate::backend::Gtk4Backend;
-use crate::terminal::VteTerminalWidget;
-use crate::cairo_renderer::{CairoTextRenderer, CairoGraphicsRenderer, CairoUIRenderer};
-use crate::input::{Gtk4InputHandler, Gtk4EventLoop};
-use gtk4::prelude::*;
-use vte_core::{Renderer, InputHandler, EventLoop, TerminalConfig};
-
-mod cairo_renderer;
 mod input;
 mod backend;
 mod terminal;
 
+// Embedding API: the widget and its backend
+pub use backend::{show_event_notification, Gtk4Backend};
+pub use terminal::VteTerminalWidget;
 
 
-// Re-export vte-core types for convenience
-pub use vte_core::*;
+// Re-export the embedding surface of vte-core (see `vte_core::prelude`);
+// everything else is imported from `vte_core` itself
+pub use vte_core::prelude::*;
 
 // Placeholder for GTK backend implementation
 // TODO: Implement GTK-specific Renderer, InputHandler, EventLoop
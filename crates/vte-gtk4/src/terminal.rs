@@ -2,6 +2,7 @@
 
 use crate::backend::Gtk4Backend;
 use gtk4::{DrawingArea, prelude::*};
+use std::cell::RefCell;
 use vte_core::{TerminalConfig, TerminalError};
 
 /// GTK4 terminal widget wrapper
@@ -18,15 +19,43 @@ impl VteTerminalWidget {
 
     /// Create a new GTK4 terminal widget with custom configuration
     pub fn with_config(config: TerminalConfig) -> Result<Self, TerminalError> {
+        let area = Self::new_area();
+        let backend = Gtk4Backend::new(config, &area)?;
+
+        Ok(VteTerminalWidget { area, backend })
+    }
+
+    /// Create the widget's drawing area now and spawn the shell once GTK
+    /// has sized it
+    ///
+    /// The shell starts at the size of the first allocation instead of 80x24,
+    /// so its prompt is not re-laid out at startup. Pack the returned area
+    /// into the window; `on_ready` receives the terminal when it exists.
+    pub fn spawn_on_allocation(
+        config: TerminalConfig,
+        on_ready: impl FnOnce(Result<Self, TerminalError>) + 'static,
+    ) -> DrawingArea {
+        let area = Self::new_area();
+        let pending = RefCell::new(Some((config, on_ready)));
+        area.connect_resize(move |area, width, height| {
+            // Only the first allocation spawns; later ones are plain resizes
+            let Some((config, on_ready)) = pending.borrow_mut().take() else {
+                return;
+            };
+            let widget = Gtk4Backend::for_allocation(config, area, width, height)
+                .map(|backend| VteTerminalWidget { area: area.clone(), backend });
+            on_ready(widget);
+        });
+        area
+    }
+
+    fn new_area() -> DrawingArea {
         let area = DrawingArea::new();
         area.set_focusable(true);
         area.set_hexpand(true);
         area.set_vexpand(true);
         area.grab_focus();
-
-        let backend = Gtk4Backend::new(config, &area)?;
-
-        Ok(VteTerminalWidget { area, backend })
+        area
     }
 
     /// Get the GTK widget
@@ -0,0 +1,336 @@
+// examples/hugovte-cat.rs
+//! Render a file or pipe through the emulator
+//!
+//! Feeds the input through `AnsiParser` into a headless `Grid` - no PTY, no
+//! widget - and prints the resulting scrollback and screen as plain text or
+//! HTML, or the screen as PNG. Handy
+//! for checking what the stack makes of real program output, and the smallest
+//! complete example of screen-only embedding.
+//!
+//! ```text
+//! ls --color=always | cargo run -p vte-render-cairo --example hugovte-cat
+//! cargo run -p vte-render-cairo --example hugovte-cat -- --format html --cols 120 log.txt > log.html
+//! cargo run -p vte-render-cairo --example hugovte-cat -- --format png --output shot.png capture.ansi
+//! ```
+
+use std::borrow::Cow;
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+use vte_core::{AnsiParser, Cell, Color, FilterPolicy, Grid, TerminalConfig};
+
+const USAGE: &str = "\
+Usage: hugovte-cat [OPTIONS] [FILE]
+
+Reads FILE (or stdin) and prints what the terminal ends up showing: the
+scrollback followed by the screen (the screen alone for png).
+
+Options:
+  -f, --format <text|html|png>  Output format [default: text]
+  -o, --output <PATH>           Output file [default: stdout; required for png]
+      --cols <N>                Screen width [default: 80]
+      --rows <N>                Screen height [default: 24]
+      --no-scrollback           Print only the screen
+      --high-contrast           Use the high-contrast theme
+  -h, --help                    Print this help";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Text,
+    Html,
+    Png,
+}
+
+#[derive(Debug)]
+struct Options {
+    format: Format,
+    input: Option<PathBuf>,
+    output: Option<PathBuf>,
+    cols: usize,
+    rows: usize,
+    scrollback: bool,
+    high_contrast: bool,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        format: Format::Text,
+        input: None,
+        output: None,
+        cols: 80,
+        rows: 24,
+        scrollback: true,
+        high_contrast: false,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{} needs a value", name));
+        match arg.as_str() {
+            "-h" | "--help" => return Err(String::new()),
+            "-f" | "--format" => {
+                options.format = match value(&arg)?.as_str() {
+                    "text" => Format::Text,
+                    "html" => Format::Html,
+                    "png" => Format::Png,
+                    other => return Err(format!("unknown format '{}'", other)),
+                }
+            }
+            "-o" | "--output" => options.output = Some(value(&arg)?.into()),
+            "--cols" => options.cols = parse_size(&arg, &value(&arg)?)?,
+            "--rows" => options.rows = parse_size(&arg, &value(&arg)?)?,
+            "--no-scrollback" => options.scrollback = false,
+            "--high-contrast" => options.high_contrast = true,
+            _ if arg.starts_with('-') && arg != "-" => return Err(format!("unknown option '{}'", arg)),
+            _ if options.input.is_some() => return Err("only one input file is supported".to_string()),
+            _ => options.input = (arg != "-").then(|| arg.into()),
+        }
+    }
+
+    if options.format == Format::Png && options.output.is_none() {
+        return Err("--format png needs --output".to_string());
+    }
+    Ok(options)
+}
+
+fn parse_size(name: &str, value: &str) -> Result<usize, String> {
+    match value.parse() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!("{} must be a positive number, got '{}'", name, value)),
+    }
+}
+
+/// Rows of the scrollback (oldest first) if `scrollback`, then of the
+/// screen, trailing blank rows dropped
+fn output_rows(grid: &Grid, scrollback: bool) -> Vec<Cow<'_, [Cell]>> {
+    let history = if scrollback { grid.scrollback_rows() } else { 0 };
+    let mut rows: Vec<Cow<'_, [Cell]>> = (0..history)
+        .filter_map(|index| grid.scrollback_row(index))
+        .chain(grid.cells.iter_rows().map(Cow::Borrowed))
+        .collect();
+    while rows.last().is_some_and(|row| row.iter().all(|cell| is_blank(cell.ch))) {
+        rows.pop();
+    }
+    rows
+}
+
+fn is_blank(ch: char) -> bool {
+    ch == '\0' || ch == ' '
+}
+
+/// Cells of `row` that start a character, skipping the column covered by a wide one
+fn visible_cells(row: &[Cell]) -> Vec<&Cell> {
+    row.iter().filter(|cell| !cell.spacer).collect()
+}
+
+fn render_text(grid: &Grid, scrollback: bool) -> String {
+    let mut out = String::new();
+    for row in output_rows(grid, scrollback) {
+        let line: String = visible_cells(&row)
+            .iter()
+            .map(|cell| if cell.ch == '\0' { ' ' } else { cell.ch })
+            .collect();
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+fn css_color(color: Color) -> String {
+    let channel = |v: f64| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    if color.a >= 1.0 {
+        format!("#{:02x}{:02x}{:02x}", channel(color.r), channel(color.g), channel(color.b))
+    } else {
+        format!("rgba({}, {}, {}, {:.2})", channel(color.r), channel(color.g), channel(color.b), color.a)
+    }
+}
+
+/// Inline CSS for one cell, relative to the `<pre>` defaults
+fn cell_style(config: &TerminalConfig, cell: &Cell) -> String {
+    let mut style = String::new();
+    let fg = config.resolve_fg(cell);
+    let bg = config.resolve_bg(cell);
+    if fg != config.resolve_fg(&Cell::default()) {
+        let _ = write!(style, "color:{};", css_color(fg));
+    }
+    if bg != config.background() {
+        let _ = write!(style, "background:{};", css_color(bg));
+    }
+    if cell.bold {
+        style.push_str("font-weight:bold;");
+    }
+    if cell.italic {
+        style.push_str("font-style:italic;");
+    }
+    if cell.underline {
+        style.push_str("text-decoration:underline;");
+    }
+    if cell.dim {
+        style.push_str("opacity:0.6;");
+    }
+    style
+}
+
+fn escape_html(ch: char, out: &mut String) {
+    match ch {
+        '<' => out.push_str("&lt;"),
+        '>' => out.push_str("&gt;"),
+        '&' => out.push_str("&amp;"),
+        '\0' => out.push(' '),
+        _ => out.push(ch),
+    }
+}
+
+/// Standalone HTML page; runs of cells with the same style share one `<span>`
+fn render_html(grid: &Grid, scrollback: bool) -> String {
+    let config = &grid.config;
+    let mut out = String::new();
+    let _ = writeln!(out, "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>hugovte-cat</title></head>\n<body>");
+    let _ = write!(
+        out,
+        "<pre style=\"font-family:'{}',monospace;color:{};background:{};padding:0.5em\">",
+        config.font_family,
+        css_color(config.resolve_fg(&Cell::default())),
+        css_color(config.background()),
+    );
+
+    for row in output_rows(grid, scrollback) {
+        let cells = visible_cells(&row);
+        // Trailing blanks in the default style carry nothing
+        let end = cells
+            .iter()
+            .rposition(|cell| !is_blank(cell.ch) || !cell_style(config, cell).is_empty())
+            .map_or(0, |i| i + 1);
+
+        let mut open: Option<String> = None;
+        for cell in &cells[..end] {
+            let style = cell_style(config, cell);
+            if open.as_ref() != Some(&style) {
+                if open.take().is_some_and(|s| !s.is_empty()) {
+                    out.push_str("</span>");
+                }
+                if !style.is_empty() {
+                    let _ = write!(out, "<span style=\"{}\">", style);
+                }
+                open = Some(style);
+            }
+            escape_html(cell.ch, &mut out);
+        }
+        if open.is_some_and(|s| !s.is_empty()) {
+            out.push_str("</span>");
+        }
+        out.push('\n');
+    }
+
+    out.push_str("</pre>\n</body>\n</html>\n");
+    out
+}
+
+/// Grid showing what `input` leaves on a terminal configured by `options`
+fn feed(options: &Options, config: &TerminalConfig, input: &[u8]) -> Grid {
+    let mut grid = Grid::new(options.cols, options.rows, Arc::new(config.clone()));
+    // The input may come from anywhere; only the screen contents matter
+    grid.set_feed_filter(Some(FilterPolicy::untrusted()));
+    // A file has no line discipline to turn LF into CR LF; the grid's newline
+    // already returns to column 0, so the bytes can be fed as they are
+    AnsiParser::new().feed_str(&String::from_utf8_lossy(input), &mut grid);
+    grid
+}
+
+fn run(options: Options) -> Result<(), Box<dyn std::error::Error>> {
+    let mut input = Vec::new();
+    match &options.input {
+        Some(path) => input = std::fs::read(path)?,
+        None => {
+            io::stdin().read_to_end(&mut input)?;
+        }
+    }
+
+    let config = TerminalConfig::default().with_high_contrast(options.high_contrast);
+    let grid = feed(&options, &config, &input);
+
+    let rendered = match options.format {
+        Format::Text => render_text(&grid, options.scrollback),
+        Format::Html => render_html(&grid, options.scrollback),
+        Format::Png => {
+            let path = options.output.as_ref().expect("checked in parse_args");
+            vte_render_cairo::render_to_png(&grid, &config, path)?;
+            return Ok(());
+        }
+    };
+
+    match &options.output {
+        Some(path) => std::fs::write(path, rendered)?,
+        None => io::stdout().lock().write_all(rendered.as_bytes())?,
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) if message.is_empty() => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(message) => {
+            eprintln!("hugovte-cat: {}\n\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("hugovte-cat: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(args: &[&str]) -> Options {
+        parse_args(args.iter().map(|arg| arg.to_string())).expect("valid arguments")
+    }
+
+    #[test]
+    fn test_text_includes_scrollback() {
+        let options = options(&["--cols", "10", "--rows", "2"]);
+        let grid = feed(&options, &TerminalConfig::default(), b"one\ntwo\n\x1b[1mthree\x1b[0m\n");
+        assert_eq!(render_text(&grid, true), "one\ntwo\nthree\n");
+        assert_eq!(render_text(&grid, false), "three\n");
+    }
+
+    #[test]
+    fn test_html_escapes_and_styles() {
+        let options = options(&["--format", "html", "--no-scrollback"]);
+        assert!(!options.scrollback);
+        let grid = feed(&options, &TerminalConfig::default(), b"a<b\x1b[1m&c\x1b[0m\n");
+        let html = render_html(&grid, options.scrollback);
+        assert!(html.contains("a&lt;b<span style=\"font-weight:bold;\">&amp;c</span>\n</pre>"), "{}", html);
+    }
+
+    #[test]
+    fn test_writes_png() {
+        let dir = std::env::temp_dir().join(format!("hugovte-cat-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (input, output) = (dir.join("in.ansi"), dir.join("out.png"));
+        std::fs::write(&input, "\x1b[31mred\x1b[0m\n").unwrap();
+        let args = ["--format", "png", "--output", output.to_str().unwrap(), input.to_str().unwrap()];
+        run(options(&args)).expect("rendered");
+        assert!(std::fs::read(&output).unwrap().starts_with(b"\x89PNG"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_bad_arguments() {
+        let parse = |args: &[&str]| parse_args(args.iter().map(|arg| arg.to_string()));
+        assert!(parse(&["--format", "png"]).is_err());
+        assert!(parse(&["--cols", "0"]).is_err());
+        assert!(parse(&["a", "b"]).is_err());
+    }
+}
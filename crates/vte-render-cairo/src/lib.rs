@@ -0,0 +1,342 @@
+//! VTE Render Cairo - Cairo cell renderer for vte-core
+//!
+//! Draws a [`Grid`] onto any `cairo::Context`, so one implementation serves
+//! every Cairo target:
+//!
+//! - a GTK4 `DrawingArea` draw function, or a snapshot via
+//!   `Snapshot::append_cairo`
+//! - a GTK3 `DrawingArea` `draw` signal
+//! - an offscreen `ImageSurface`, e.g. [`render_to_png`] for screenshot-based
+//!   golden tests and documentation images, or [`render_thumbnail`] for
+//!   tab previews
+//!
+//! The crate has no toolkit dependency; pointer shape hints are handed to a
+//! callback (see [`CairoUIRenderer::with_pointer_handler`]).
+
+use cairo::{Context, Filter, Format, ImageSurface};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+use std::rc::Rc;
+use vte_core::drawing::CellGeometry;
+use std::sync::Arc;
+use vte_core::font::{SharedFontCache, SharedFontKey};
+use vte_core::width::NO_COMBINING;
+use vte_core::{BackendError, BellFlash, Cell, Color, CursorShape, Grid, Renderer, ScreenSnapshot, TerminalConfig};
+
+mod renderer;
+
+pub use renderer::{CairoGraphicsRenderer, CairoTextRenderer, CairoUIRenderer, RowSurfaceCache};
+
+/// Cell width used when no font metrics are available, in pixels
+pub const DEFAULT_CELL_WIDTH: f64 = 10.0;
+/// Cell height used when no font metrics are available, in pixels
+pub const DEFAULT_CELL_HEIGHT: f64 = 16.0;
+/// Key of the render font for `config` at `scale`: glyphs are rasterized in
+/// `TerminalConfig::font_family` at `TerminalConfig::zoomed_font_size`, so
+/// they follow the configured font and grow and shrink with the zoom
+pub fn render_font_key(config: &TerminalConfig, scale: f64) -> SharedFontKey {
+    SharedFontKey::new(&config.font_family, config.zoomed_font_size() as f32, scale)
+}
+
+/// The process's shared cache for the render font of `config` at `scale`
+///
+/// Widgets hold on to it between frames so that their renderers, and those
+/// of every other terminal with the same [`render_font_key`], reuse its glyphs.
+pub fn render_fonts(config: &TerminalConfig, scale: f64) -> Result<Arc<SharedFontCache>, BackendError> {
+    let key = render_font_key(config, scale);
+    SharedFontCache::acquire(&key.family, key.size(), key.scale()).map_err(|e| BackendError::Font(e.to_string()))
+}
+
+/// Composite Cairo renderer drawing the whole grid
+pub struct CairoRenderer {
+    text_renderer: CairoTextRenderer,
+    graphics_renderer: CairoGraphicsRenderer,
+    ui_renderer: CairoUIRenderer,
+    cursor_shape: CursorShape,
+    /// Rendered rows shared across frames, if the owner keeps one
+    row_cache: Option<Rc<RefCell<RowSurfaceCache>>>,
+    /// Visual bell drawn over this frame
+    bell: Option<BellFlash>,
+    /// Color of border flashes and background pulses (the foreground)
+    bell_color: Color,
+}
+
+impl CairoRenderer {
+    /// Renderer for an unscaled target; the cell size is snapped to whole pixels
+    pub fn new(context: &Context, char_w: f64, char_h: f64) -> Result<Self, BackendError> {
+        Self::with_geometry(context, CellGeometry::new(char_w, char_h, char_h * 0.75, 1.0))
+    }
+
+    /// Renderer laying cells out on `geometry`, e.g. from
+    /// `DrawingCache::cell_geometry` at the widget's scale factor
+    pub fn with_geometry(context: &Context, geometry: CellGeometry) -> Result<Self, BackendError> {
+        Self::with_fonts(context, geometry, render_fonts(&TerminalConfig::default(), geometry.scale)?)
+    }
+
+    /// Renderer drawing glyphs from `fonts`, e.g. a cache from
+    /// [`render_fonts`] the widget keeps between frames
+    pub fn with_fonts(context: &Context, geometry: CellGeometry, fonts: Arc<SharedFontCache>) -> Result<Self, BackendError> {
+        let text_renderer = CairoTextRenderer::new(context.clone(), fonts, geometry)
+            .map_err(|e| BackendError::Rendering(e.to_string()))?;
+        let graphics_renderer = CairoGraphicsRenderer::new(context.clone());
+        let ui_renderer = CairoUIRenderer::new(context.clone());
+
+        Ok(CairoRenderer {
+            text_renderer,
+            graphics_renderer,
+            ui_renderer,
+            cursor_shape: CursorShape::default(),
+            row_cache: None,
+            bell: None,
+            bell_color: vte_core::constants::DEFAULT_FG,
+        })
+    }
+
+    /// Apply render-time settings (theme colors, bold-is-bright, alternate
+    /// fonts, zoomed font size) from the terminal configuration, shared
+    /// rather than copied
+    pub fn apply_config(&mut self, config: &Arc<TerminalConfig>) {
+        self.text_renderer.apply_config(config);
+        self.cursor_shape = config.cursor_shape;
+        self.bell_color = config.default_fg;
+    }
+
+    /// Draw `flash`, from `VisualBell::flash`, over the frame
+    pub fn set_visual_bell(&mut self, flash: Option<BellFlash>) {
+        self.bell = flash;
+    }
+
+    /// Draw the terminal muted with `TerminalConfig::inactive_style`
+    ///
+    /// Containers with several visible terminals set this on the ones without
+    /// keyboard focus; the grid is left untouched.
+    pub fn set_inactive(&mut self, inactive: bool) {
+        self.text_renderer.set_inactive(inactive);
+    }
+
+    /// Replace the UI renderer, e.g. one with a pointer handler attached
+    pub fn with_ui_renderer(mut self, ui_renderer: CairoUIRenderer) -> Self {
+        self.ui_renderer = ui_renderer;
+        self
+    }
+
+    /// Reuse unchanged rows from `cache`, which the owner keeps across frames
+    ///
+    /// Each row is rendered once into an offscreen surface and copied while
+    /// its content stays the same; mostly static screens such as editors
+    /// then only redraw the rows that changed.
+    pub fn with_row_cache(mut self, cache: Rc<RefCell<RowSurfaceCache>>) -> Self {
+        self.row_cache = Some(cache);
+        self
+    }
+
+    /// Draw every cell, the selection, row annotations, the cursor and the
+    /// scroll mark strip of `grid` with collaborators' cursors and selections,
+    /// then any visual bell
+    ///
+    /// `width` and `height` are the size of the target area in pixels.
+    pub fn render_grid(&mut self, grid: &Grid, width: f64, height: f64) {
+        // Time travel: a past screen stands in for the live one
+        if let Some(snapshot) = grid.shown_snapshot() {
+            let cells = (0..snapshot.rows).flat_map(|r| (0..snapshot.cols).filter_map(move |c| snapshot.cell(r, c)));
+            self.text_renderer.set_combining_marks(combining_marks(grid, cells));
+            self.render_snapshot(snapshot);
+            if let Some(flash) = self.bell {
+                let color = self.text_renderer.paint_color(self.bell_color);
+                self.ui_renderer.draw_visual_bell(flash, color, width, height);
+            }
+            return;
+        }
+
+        let cells = (0..grid.rows).flat_map(|r| (0..grid.cols).map(move |c| grid.get_cell(r, c)));
+        self.text_renderer.set_combining_marks(combining_marks(grid, cells));
+        if let Some(cache) = self.row_cache.clone() {
+            let mut cache = cache.borrow_mut();
+            cache.begin_frame(self.text_renderer.layout_key());
+            for r in 0..grid.rows {
+                let cells: Vec<_> = (0..grid.cols).map(|c| *grid.get_cell(r, c)).collect();
+                self.text_renderer.draw_row_cached(r, &cells, &mut cache);
+            }
+            cache.end_frame();
+        } else {
+            for r in 0..grid.rows {
+                for c in 0..grid.cols {
+                    let cell = grid.get_cell(r, c);
+                    self.text_renderer().draw_cell(r, c, cell);
+                }
+            }
+        }
+
+        // Sixel images, over the text they were placed on
+        let geometry = self.text_renderer.cell_geometry();
+        let top = grid.viewport_top_row();
+        for placed in grid.visible_images() {
+            let y = geometry.row_y(0) + (placed.row as f64 - top as f64) * (geometry.row_y(1) - geometry.row_y(0));
+            // Muted and adjusted like the text around them
+            let painted = self.text_renderer.paint_image(&placed.image);
+            let image = painted.as_ref().unwrap_or(&placed.image);
+            self.graphics_renderer().draw_sixel(image, geometry.col_x(placed.col), y);
+        }
+
+        // Local echo of typed characters still awaiting output
+        if grid.scroll_offset == 0 {
+            for (r, c, cell) in grid.predicted_cells() {
+                self.text_renderer().draw_cell(r, c, &cell);
+            }
+        }
+
+        // Embedder highlights (find matches), under every selection
+        self.text_renderer.draw_highlights(&grid.highlight_spans(), |r, c| *grid.get_cell(r, c));
+
+        // Collaborators' selections, under the local one
+        let remote_cursors = if grid.scroll_offset == 0 { grid.remote_cursors() } else { Vec::new() };
+        for remote in &remote_cursors {
+            self.text_renderer.draw_remote_selection(&remote.selection_spans(grid.rows, grid.cols), remote.color);
+        }
+
+        // Selection overlay, over the text and under the cursor
+        self.text_renderer.draw_selection(&grid.selection_spans(), |r, c| *grid.get_cell(r, c));
+
+        // Collaborators' cursors, under the local one
+        for remote in remote_cursors.iter().filter(|remote| remote.row < grid.rows && remote.col < grid.cols) {
+            self.text_renderer.draw_remote_cursor(remote.row, remote.col, remote.color);
+        }
+
+        // Embedder gutter markers (bookmarks, build status)
+        let top = grid.viewport_top_row();
+        for annotation in grid.visible_annotations() {
+            if let Some(color) = annotation.annotation.color {
+                self.text_renderer.draw_row_annotation((annotation.row - top) as usize, color);
+            }
+        }
+
+        // Draw cursor if visible
+        if grid.row < grid.rows && grid.col < grid.cols && grid.is_cursor_visible() && grid.scroll_offset == 0 {
+            let cell = grid.get_cell(grid.row, grid.col);
+            let shape = self.cursor_shape;
+            self.text_renderer.draw_cursor(grid.row, grid.col, cell, shape);
+            self.ui_renderer().set_cursor_shape(shape);
+        }
+
+        // I-beam, hand over URLs, hidden while typing
+        self.ui_renderer().set_pointer_shape(grid.pointer_shape());
+
+        // Scrollbar overlay: prompts, search matches, error triggers
+        let total_rows = (grid.scrollback_rows() + grid.rows) as u64;
+        let paint = |color| self.text_renderer.paint_color(color);
+        self.ui_renderer.draw_scroll_marks(grid.scroll_marks(), grid.first_absolute_row(), total_rows, width, height, paint);
+
+        // Visual bell, over everything else
+        if let Some(flash) = self.bell {
+            let color = self.text_renderer.paint_color(self.bell_color);
+            self.ui_renderer.draw_visual_bell(flash, color, width, height);
+        }
+    }
+
+    /// Draw the cells and cursor of a time-travel snapshot
+    fn render_snapshot(&mut self, snapshot: &ScreenSnapshot) {
+        for r in 0..snapshot.rows {
+            for c in 0..snapshot.cols {
+                if let Some(cell) = snapshot.cell(r, c) {
+                    self.text_renderer().draw_cell(r, c, cell);
+                }
+            }
+        }
+        let (row, col) = snapshot.cursor;
+        match snapshot.cell(row, col) {
+            Some(cell) if snapshot.cursor_visible => self.text_renderer.draw_cursor(row, col, cell, self.cursor_shape),
+            _ => {}
+        }
+    }
+}
+
+/// Combining marks of those `cells` that carry any, by `Cell::combining` id
+fn combining_marks<'a>(grid: &Grid, cells: impl Iterator<Item = &'a Cell>) -> HashMap<u32, String> {
+    cells
+        .filter(|cell| cell.combining != NO_COMBINING)
+        .map(|cell| (cell.combining, grid.combining_marks(cell).to_string()))
+        .collect()
+}
+
+impl Renderer for CairoRenderer {
+    fn text_renderer(&mut self) -> &mut dyn vte_core::TextRenderer {
+        &mut self.text_renderer
+    }
+
+    fn graphics_renderer(&mut self) -> &mut dyn vte_core::GraphicsRenderer {
+        &mut self.graphics_renderer
+    }
+
+    fn ui_renderer(&mut self) -> &mut dyn vte_core::UIRenderer {
+        &mut self.ui_renderer
+    }
+}
+
+/// Render `grid` into a new ARGB32 image surface sized to fit it, with the
+/// configured zoom, cell spacing and padding; `char_w` and `char_h` are the
+/// unzoomed cell size
+///
+/// Unlike a widget, an offscreen surface has nothing behind it, so it is
+/// first filled with the configured default background.
+pub fn render_to_surface(grid: &Grid, config: &TerminalConfig, char_w: f64, char_h: f64) -> Result<ImageSurface, BackendError> {
+    let (char_w, char_h) = (char_w * config.zoom, char_h * config.zoom);
+    let geometry = CellGeometry::new(char_w, char_h, char_h * 0.75, 1.0).with_config_spacing(config);
+    let (width, height) = geometry.size_for(grid.cols, grid.rows);
+    let surface = ImageSurface::create(Format::ARgb32, width as i32, height as i32)
+        .map_err(|e| BackendError::Rendering(e.to_string()))?;
+
+    {
+        let context = Context::new(&surface).map_err(|e| BackendError::Rendering(e.to_string()))?;
+        let bg = config.color_adjust.apply(config.background());
+        context.set_source_rgba(bg.r, bg.g, bg.b, bg.a);
+        context.paint().map_err(|e| BackendError::Rendering(e.to_string()))?;
+
+        let mut renderer = CairoRenderer::with_geometry(&context, geometry)?;
+        renderer.apply_config(&Arc::new(config.clone()));
+        renderer.render_grid(grid, width, height);
+    }
+
+    surface.flush();
+    Ok(surface)
+}
+
+/// Render `grid` scaled down to fit `width` x `height` pixels, e.g. for tab
+/// thumbnails
+///
+/// The screen keeps its aspect ratio and is centered on the default
+/// background.
+pub fn render_thumbnail(grid: &Grid, config: &TerminalConfig, width: i32, height: i32) -> Result<ImageSurface, BackendError> {
+    let screen = render_to_surface(grid, config, DEFAULT_CELL_WIDTH, DEFAULT_CELL_HEIGHT)?;
+    let thumbnail = ImageSurface::create(Format::ARgb32, width.max(1), height.max(1))
+        .map_err(|e| BackendError::Rendering(e.to_string()))?;
+
+    {
+        let context = Context::new(&thumbnail).map_err(|e| BackendError::Rendering(e.to_string()))?;
+        let bg = config.color_adjust.apply(config.background());
+        context.set_source_rgba(bg.r, bg.g, bg.b, bg.a);
+        context.paint().map_err(|e| BackendError::Rendering(e.to_string()))?;
+
+        let (screen_w, screen_h) = (f64::from(screen.width()), f64::from(screen.height()));
+        let (thumb_w, thumb_h) = (f64::from(thumbnail.width()), f64::from(thumbnail.height()));
+        let scale = (thumb_w / screen_w).min(thumb_h / screen_h);
+        context.translate((thumb_w - screen_w * scale) / 2.0, (thumb_h - screen_h * scale) / 2.0);
+        context.scale(scale, scale);
+        context.set_source_surface(&screen, 0.0, 0.0).map_err(|e| BackendError::Rendering(e.to_string()))?;
+        context.source().set_filter(Filter::Good);
+        context.paint().map_err(|e| BackendError::Rendering(e.to_string()))?;
+    }
+
+    thumbnail.flush();
+    Ok(thumbnail)
+}
+
+/// Render `grid` to a PNG file at `path` using the default cell size
+pub fn render_to_png(grid: &Grid, config: &TerminalConfig, path: impl AsRef<Path>) -> Result<(), BackendError> {
+    let surface = render_to_surface(grid, config, DEFAULT_CELL_WIDTH, DEFAULT_CELL_HEIGHT)?;
+    let mut file = File::create(path)?;
+    surface
+        .write_to_png(&mut file)
+        .map_err(|e| BackendError::Rendering(e.to_string()))
+}
@@ -0,0 +1,704 @@
+//! Cairo implementations of the vte-core renderer traits
+
+use cairo::{Context, FontSlant, FontWeight, ImageSurface, Format};
+use vte_core::{
+    BellFlash, ImageData, Cell, Color, CursorPaint, CursorShape, HighlightSpan, MarkCategory, PointerShape, ScrollMark, SelectionSpan,
+    TerminalConfig, TextRenderer, GraphicsRenderer, UIRenderer, VisualBellStyle
+};
+use vte_core::constants::SELECTION_BG;
+use vte_core::ansi::{UnderlineStyle, PRIMARY_FONT};
+use vte_core::font::{Glyph, SharedFontCache, FontWeight as VteFontWeight, FontSlant as VteFontSlant};
+use vte_core::drawing::{CellGeometry, CharMetrics};
+use vte_core::row_cache::{row_key, RowCache};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// Rendered rows kept across frames by [`CairoTextRenderer::draw_row_cached`]
+pub type RowSurfaceCache = RowCache<ImageSurface>;
+
+/// Rows of room a cached row surface keeps above and below the row, so
+/// glyph ink and decorations that cross the row edge are not clipped
+const ROW_OVERDRAW: usize = 1;
+
+/// Cairo-based text renderer using a shared font cache with fallback support
+pub struct CairoTextRenderer {
+    context: cairo::Context,
+    /// Fonts and glyphs shared with the process's other terminals
+    fonts: Arc<SharedFontCache>,
+    /// Cell layout snapped to device pixels
+    geometry: CellGeometry,
+    /// Render-time settings: theme colors, bold-is-bright, alternate fonts
+    config: Arc<TerminalConfig>,
+    /// Draw muted with `config.inactive_style` (unfocused pane)
+    inactive: bool,
+    /// Combining marks of the cells being drawn, by `Cell::combining` id
+    combining: HashMap<u32, String>,
+}
+
+impl CairoTextRenderer {
+    pub fn new(
+        context: cairo::Context,
+        fonts: Arc<SharedFontCache>,
+        geometry: CellGeometry,
+    ) -> Result<Self, cairo::Error> {
+        Ok(CairoTextRenderer {
+            context,
+            fonts,
+            geometry,
+            config: Arc::default(),
+            inactive: false,
+            combining: HashMap::new(),
+        })
+    }
+
+    /// Apply render-time settings (theme colors, bold-is-bright, alternate
+    /// fonts) from `config`, switching to the fonts for its zoomed size; the
+    /// current fonts stay when those cannot be loaded
+    pub fn apply_config(&mut self, config: &Arc<TerminalConfig>) {
+        if *self.fonts.key() != crate::render_font_key(config, self.geometry.scale) {
+            if let Ok(fonts) = crate::render_fonts(config, self.geometry.scale) {
+                self.fonts = fonts;
+            }
+        }
+        self.config = Arc::clone(config);
+    }
+
+    /// Cell layout the renderer draws on
+    pub fn cell_geometry(&self) -> CellGeometry {
+        self.geometry
+    }
+
+    /// Combining marks to draw with the characters of the cells that refer
+    /// to them (see `Grid::combining_marks`)
+    pub fn set_combining_marks(&mut self, combining: HashMap<u32, String>) {
+        self.combining = combining;
+    }
+
+    /// Mute every color drawn from now on, as for an unfocused pane
+    pub fn set_inactive(&mut self, inactive: bool) {
+        self.inactive = inactive;
+    }
+
+    /// `color` as drawn in the current active/inactive state, with
+    /// `config.color_adjust` applied
+    pub fn paint_color(&self, color: Color) -> Color {
+        let color = if self.inactive {
+            self.config.inactive_style.apply(color, self.config.background())
+        } else {
+            color
+        };
+        self.config.color_adjust.apply(color)
+    }
+
+    /// `image` with every pixel painted like [`paint_color`](Self::paint_color)
+    /// paints a color, or `None` when that leaves it unchanged
+    pub fn paint_image(&self, image: &ImageData) -> Option<ImageData> {
+        if !self.inactive && self.config.color_adjust.is_none() {
+            return None;
+        }
+        let channel = |value: f64| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        let mut data = image.data.clone();
+        for rgba in data.chunks_exact_mut(4) {
+            let [r, g, b, a] = [rgba[0], rgba[1], rgba[2], rgba[3]].map(|c| f64::from(c) / 255.0);
+            let color = self.paint_color(Color { r, g, b, a });
+            rgba[..3].copy_from_slice(&[channel(color.r), channel(color.g), channel(color.b)]);
+        }
+        Some(ImageData { data, width: image.width, height: image.height })
+    }
+
+    /// Draw the text cursor over an already drawn cell
+    ///
+    /// Follows the `vte_core::cursor` contract: a block cursor re-renders the
+    /// glyph in the computed cursor text color, bar and underline cursors
+    /// only paint their strip.
+    pub fn draw_cursor(&mut self, row: usize, col: usize, cell: &Cell, shape: CursorShape) {
+        let mut paint = CursorPaint::for_cell(cell, shape, &self.config);
+        paint.bg = self.paint_color(paint.bg);
+        paint.fg = self.paint_color(paint.fg);
+        let (x, y, w, h) = paint.rect(self.geometry.width, self.geometry.height);
+        self.context.set_source_rgba(paint.bg.r, paint.bg.g, paint.bg.b, paint.bg.a);
+        self.context.rectangle(self.geometry.col_x(col) + x, self.geometry.row_y(row) + y, w, h);
+        let _ = self.context.fill();
+
+        if paint.repaints_glyph() {
+            self.draw_glyph(row, col, cell, paint.fg);
+        }
+    }
+
+    /// Draw the selection highlight over already drawn rows
+    ///
+    /// Each span is one rectangle, so a multi-row selection reads as a single
+    /// shape; only the corners `Selection::spans` marks as exposed get
+    /// `TerminalConfig::selection_corner_radius`. The highlight is opaque in
+    /// `TerminalConfig::selection_colors`, and the glyphs of the cells
+    /// `cell_at(row, col)` returns are re-rendered on it in the selection
+    /// text color.
+    pub fn draw_selection(&mut self, spans: &[SelectionSpan], cell_at: impl Fn(usize, usize) -> Cell) {
+        let (bg, fg) = self.config.selection_colors();
+        let (bg, fg) = (self.paint_color(bg), self.paint_color(fg));
+        self.fill_spans(spans, bg);
+        for span in spans {
+            for col in span.start_col..span.end_col {
+                let cell = cell_at(span.row, col);
+                if cell.ch != '\0' {
+                    self.draw_glyph(span.row, col, &cell, fg);
+                }
+            }
+        }
+    }
+
+    /// Draw embedder highlights (`vte_core::highlights`) over already drawn
+    /// rows, re-rendering the glyphs on them in the style's foreground color,
+    /// or in the cell's own for styles without one
+    pub fn draw_highlights(&mut self, highlights: &[HighlightSpan], cell_at: impl Fn(usize, usize) -> Cell) {
+        for highlight in highlights {
+            self.fill_spans(&[highlight.span], self.paint_color(highlight.style.background));
+            let fg = highlight.style.foreground.map(|fg| self.paint_color(fg));
+            for col in highlight.span.start_col..highlight.span.end_col {
+                let cell = cell_at(highlight.span.row, col);
+                if cell.ch != '\0' {
+                    let fg = fg.unwrap_or_else(|| self.fg_for(&cell));
+                    self.draw_glyph(highlight.span.row, col, &cell, fg);
+                }
+            }
+        }
+    }
+
+    /// Draw a collaborator's selection (`vte_core::remote`) tinted with
+    /// their cursor `color`
+    pub fn draw_remote_selection(&self, spans: &[SelectionSpan], color: Color) {
+        let color = self.paint_color(color);
+        self.fill_spans(spans, Color { a: SELECTION_BG.a, ..color });
+    }
+
+    /// Outline the cell a collaborator's cursor is on, leaving the glyph visible
+    pub fn draw_remote_cursor(&self, row: usize, col: usize, color: Color) {
+        let color = self.paint_color(color);
+        let line = REMOTE_CURSOR_LINE_WIDTH;
+        self.context.set_source_rgba(color.r, color.g, color.b, color.a);
+        self.context.set_line_width(line);
+        self.context.rectangle(
+            self.geometry.col_x(col) + line / 2.0,
+            self.geometry.row_y(row) + line / 2.0,
+            self.geometry.width - line,
+            self.geometry.height - line,
+        );
+        let _ = self.context.stroke();
+    }
+
+    /// Draw a row annotation as a colored bar along the left edge of `row`
+    pub fn draw_row_annotation(&self, row: usize, color: Color) {
+        let color = self.paint_color(color);
+        self.context.set_source_rgba(color.r, color.g, color.b, color.a);
+        self.context.rectangle(0.0, self.geometry.row_y(row), ROW_ANNOTATION_WIDTH, self.geometry.height);
+        let _ = self.context.fill();
+    }
+
+    /// Fill the rounded outline of `spans` with `color`
+    fn fill_spans(&self, spans: &[SelectionSpan], color: Color) {
+        let cr = &self.context;
+        let radius = self.config.selection_corner_radius.min(self.geometry.height / 2.0);
+        cr.set_source_rgba(color.r, color.g, color.b, color.a);
+
+        for span in spans {
+            let (left, right) = (self.geometry.col_x(span.start_col), self.geometry.col_x(span.end_col));
+            let (top, bottom) = (self.geometry.row_y(span.row), self.geometry.row_y(span.row + 1));
+            let r = |corner: usize| if span.rounded[corner] { radius.min((right - left) / 2.0) } else { 0.0 };
+
+            // Clockwise from the top-left corner
+            cr.new_sub_path();
+            cr.arc(left + r(0), top + r(0), r(0), PI, 1.5 * PI);
+            cr.arc(right - r(1), top + r(1), r(1), 1.5 * PI, 2.0 * PI);
+            cr.arc(right - r(2), bottom - r(2), r(2), 0.0, 0.5 * PI);
+            cr.arc(left + r(3), bottom - r(3), r(3), 0.5 * PI, PI);
+            cr.close_path();
+        }
+        let _ = cr.fill();
+    }
+
+    /// Hash of the row layout inputs outside the cells: geometry, font slots,
+    /// the inactive state and the color adjustment
+    pub fn layout_key(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        let g = &self.geometry;
+        for value in [g.width, g.height, g.ascent, g.scale, g.glyph_offset] {
+            value.to_bits().hash(&mut hasher);
+        }
+        self.config.alternate_fonts.hash(&mut hasher);
+        if self.inactive {
+            let style = self.config.inactive_style;
+            (style.dim.to_bits(), style.desaturate.to_bits()).hash(&mut hasher);
+        }
+        let adjust = self.config.color_adjust;
+        for value in [adjust.brightness, adjust.contrast, adjust.gamma] {
+            value.to_bits().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Draw `cells` as screen row `row`, copying the row from `cache` when
+    /// an identical row was rendered in the previous frame
+    ///
+    /// The copy includes the ink the row draws over its neighbours, so rows
+    /// must be drawn top to bottom like uncached ones.
+    pub fn draw_row_cached(&mut self, row: usize, cells: &[Cell], cache: &mut RowSurfaceCache) {
+        let key = row_key(cells, &self.config);
+        let Some(surface) = cache.get_or_render(key, || self.render_row_surface(cells)) else {
+            // No offscreen surface; draw in place
+            for (col, cell) in cells.iter().enumerate() {
+                self.draw_cell(row, col, cell);
+            }
+            return;
+        };
+
+        let (x, y) = (self.geometry.col_x(0), self.geometry.row_y(row));
+        let margin = self.geometry.row_y(ROW_OVERDRAW) - self.geometry.row_y(0);
+        let _ = self.context.set_source_surface(surface, x, y - margin);
+        self.context.rectangle(x, y - margin, self.geometry.col_x(cells.len()) - x, surface.height() as f64 / self.geometry.scale);
+        let _ = self.context.fill();
+    }
+
+    /// Render `cells` into a transparent surface one row high plus
+    /// `ROW_OVERDRAW` rows above and below
+    fn render_row_surface(&mut self, cells: &[Cell]) -> Option<ImageSurface> {
+        let width = self.geometry.device_width() as usize * cells.len();
+        let height = self.geometry.device_height() as usize * (1 + 2 * ROW_OVERDRAW);
+        let surface = ImageSurface::create(Format::ARgb32, width as i32, height as i32).ok()?;
+        surface.set_device_scale(self.geometry.scale, self.geometry.scale);
+        let row_context = Context::new(&surface).ok()?;
+
+        // The surface holds just the cells; padding is added when it is copied
+        let target = std::mem::replace(&mut self.context, row_context);
+        let geometry = self.geometry;
+        self.geometry = geometry.with_padding(0.0);
+        for (col, cell) in cells.iter().enumerate() {
+            self.draw_cell(ROW_OVERDRAW, col, cell);
+        }
+        self.context = target;
+        self.geometry = geometry;
+
+        surface.flush();
+        Some(surface)
+    }
+
+    /// Stroke the underline of one cell in the current source color
+    fn draw_underline(&self, row: usize, col: usize, style: UnderlineStyle) {
+        let cell_height = self.geometry.height;
+        let underline_y = self.geometry.row_y(row) + (cell_height * 0.85); // Baseline + descent
+        let thickness = cell_height * 0.05 * self.config.decoration_scale(); // 5% of cell height
+        let start_x = self.geometry.col_x(col);
+        let end_x = self.geometry.col_x(col + 1);
+        let cr = &self.context;
+        cr.set_line_width(thickness);
+
+        match style {
+            UnderlineStyle::Double => {
+                let gap = thickness * 1.5;
+                for y in [underline_y - gap, underline_y + gap] {
+                    cr.move_to(start_x, y);
+                    cr.line_to(end_x, y);
+                }
+            }
+            UnderlineStyle::Curly => {
+                // One wave period per cell, so adjacent cells join up
+                let amplitude = thickness * 1.5;
+                let quarter = self.geometry.width / 4.0;
+                cr.move_to(start_x, underline_y);
+                cr.curve_to(start_x + quarter, underline_y - amplitude, start_x + quarter, underline_y - amplitude,
+                            start_x + 2.0 * quarter, underline_y);
+                cr.curve_to(start_x + 3.0 * quarter, underline_y + amplitude, start_x + 3.0 * quarter, underline_y + amplitude,
+                            end_x, underline_y);
+            }
+            UnderlineStyle::Dotted | UnderlineStyle::Dashed => {
+                let dash = if style == UnderlineStyle::Dotted { thickness } else { self.geometry.width / 3.0 };
+                cr.set_dash(&[dash, dash], start_x);
+                cr.move_to(start_x, underline_y);
+                cr.line_to(end_x, underline_y);
+            }
+            UnderlineStyle::Single | UnderlineStyle::None => {
+                cr.move_to(start_x, underline_y);
+                cr.line_to(end_x, underline_y);
+            }
+        }
+        let _ = cr.stroke();
+        cr.set_dash(&[], 0.0);
+    }
+
+    /// Foreground color to draw a cell with
+    fn fg_for(&self, cell: &Cell) -> Color {
+        self.paint_color(self.config.resolve_fg(cell))
+    }
+
+    /// Configured family for a non-primary font slot, if any
+    fn alternate_family(&self, slot: u8) -> Option<&str> {
+        (slot as usize).checked_sub(1)
+            .and_then(|i| self.config.alternate_fonts.get(i))
+            .and_then(|family| family.as_deref())
+    }
+}
+
+impl TextRenderer for CairoTextRenderer {
+    fn draw_cell(&mut self, row: usize, col: usize, cell: &Cell) {
+        let fg = self.fg_for(cell);
+        let bg = self.paint_color(self.config.resolve_bg(cell));
+
+        // Draw background if not transparent
+        if bg.a > 0.01 {
+            self.context.set_source_rgba(bg.r, bg.g, bg.b, bg.a);
+            self.context.rectangle(
+                self.geometry.col_x(col),
+                self.geometry.row_y(row),
+                self.geometry.width,
+                self.geometry.height,
+            );
+            self.context.fill().unwrap();
+        }
+
+        self.draw_glyph(row, col, cell, fg);
+    }
+
+    fn set_font(&mut self, _family: &str, _size: f64) {
+        // Font is managed by FontCache - this method is for compatibility
+        // Actual font selection happens in draw_cell with fallback chains
+    }
+
+    fn get_char_metrics(&self, _ch: char) -> CharMetrics {
+        // Return default monospace metrics for trait compatibility
+        // Actual glyph metrics are handled in draw_cell with caching
+        CharMetrics {
+            width: self.geometry.width,
+            height: self.geometry.height,
+            ascent: self.geometry.ascent,
+        }
+    }
+}
+
+impl CairoTextRenderer {
+    /// Draw a cell's glyph and underline in `fg`, leaving the background alone
+    fn draw_glyph(&mut self, row: usize, col: usize, cell: &Cell, fg: Color) {
+        let alternate_family = if cell.font != PRIMARY_FONT { self.alternate_family(cell.font) } else { None };
+        let marks = self.combining.get(&cell.combining);
+
+        // Draw text if not null character
+        if let (Some(family), true) = (alternate_family, cell.ch != '\0') {
+            // Cells in a configured alternate font slot are drawn with that family
+            self.draw_text_with_family(cell, row, col, family, fg);
+        } else if let (Some(_), true) = (marks, cell.ch != '\0') {
+            // Marks are placed by the font over the character before them
+            self.draw_text_with_family(cell, row, col, &self.config.font_family, fg);
+        } else if cell.ch != '\0' {
+            // Select font with fallback support
+            let vte_font_weight = if cell.bold { VteFontWeight::Bold } else { VteFontWeight::Normal };
+            let vte_font_slant = if cell.italic { VteFontSlant::Italic } else { VteFontSlant::Normal };
+
+            // Glyphs are rasterized with fontdue once per process and font
+            match self.fonts.glyph(cell.ch, vte_font_weight, vte_font_slant) {
+                Some(glyph) => {
+                    // Create Cairo surface from glyph bitmap and draw it
+                    let scale = self.fonts.key().scale();
+                    if let Some(surface) = glyph_surface(&glyph, scale) {
+                        // Cell origin and baseline sit on whole device pixels; the
+                        // bitmap is placed against them by the glyph's metrics,
+                        // which are in device pixels
+                        let glyph_x = self.geometry.glyph_x(col) + f64::from(glyph.xmin) / scale;
+                        let glyph_y = self.geometry.baseline_y(row) - f64::from(glyph.ymin + glyph.height as i32) / scale;
+
+                        self.context.set_source_rgba(fg.r, fg.g, fg.b, fg.a);
+                        self.context.mask_surface(&surface, glyph_x, glyph_y).unwrap();
+                    } else {
+                        // Fallback to Cairo text rendering
+                        self.fallback_draw_text(cell, row, col, fg);
+                    }
+                }
+                None => {
+                    // Fallback to Cairo text rendering if no font has the glyph
+                    self.fallback_draw_text(cell, row, col, fg);
+                }
+            }
+        }
+
+        // Decorations follow the glyph unless SGR 58 gave them a color
+        let decoration = self.config.resolve_decoration(cell).map_or(fg, |color| self.paint_color(color));
+        self.context.set_source_rgba(decoration.r, decoration.g, decoration.b, decoration.a);
+        if cell.underline {
+            self.draw_underline(row, col, cell.underline_style);
+        }
+        if cell.strikethrough {
+            self.draw_rule(row, col, STRIKETHROUGH_POSITION);
+        }
+        if cell.overline {
+            self.draw_rule(row, col, OVERLINE_POSITION);
+        }
+    }
+
+    /// Stroke a straight line across one cell at `position` (a fraction of
+    /// the cell height) in the current source color
+    fn draw_rule(&self, row: usize, col: usize, position: f64) {
+        let y = self.geometry.row_y(row) + self.geometry.height * position;
+        let cr = &self.context;
+        cr.set_line_width(self.geometry.height * 0.05 * self.config.decoration_scale());
+        cr.move_to(self.geometry.col_x(col), y);
+        cr.line_to(self.geometry.col_x(col + 1), y);
+        let _ = cr.stroke();
+    }
+
+    /// Fallback text rendering using Cairo's built-in font system
+    fn fallback_draw_text(&self, cell: &Cell, row: usize, col: usize, fg: Color) {
+        // Use system monospace font as last resort
+        self.draw_text_with_family(cell, row, col, "monospace", fg);
+    }
+
+    /// Render a cell's glyph with Cairo's toy text API in the given family
+    fn draw_text_with_family(&self, cell: &Cell, row: usize, col: usize, family: &str, fg: Color) {
+        let slant = if cell.italic { FontSlant::Italic } else { FontSlant::Normal };
+        let weight = if cell.bold { FontWeight::Bold } else { FontWeight::Normal };
+        self.context.select_font_face(family, slant, weight);
+        self.context.set_font_size(self.geometry.height * 0.7);
+
+        let x = self.geometry.glyph_x(col);
+        let y = self.geometry.baseline_y(row);
+
+        let mut text = cell.ch.to_string();
+        if let Some(marks) = self.combining.get(&cell.combining) {
+            text.push_str(marks);
+        }
+        self.context.set_source_rgba(fg.r, fg.g, fg.b, fg.a);
+        self.context.move_to(x, y);
+        self.context.show_text(&text).unwrap();
+    }
+}
+
+/// A8 surface holding `glyph`'s bitmap, its rows padded to the stride Cairo
+/// requires and drawn at `scale` device pixels per logical pixel; `None` for
+/// empty glyphs such as space
+fn glyph_surface(glyph: &Glyph, scale: f64) -> Option<ImageSurface> {
+    let (width, height) = (glyph.width as usize, glyph.height as usize);
+    if width == 0 || height == 0 {
+        return None;
+    }
+    let stride = Format::A8.stride_for_width(glyph.width).ok()? as usize;
+    let mut data = vec![0u8; stride * height];
+    for (row, pixels) in data.chunks_exact_mut(stride).zip(glyph.bitmap.chunks_exact(width)) {
+        row[..width].copy_from_slice(pixels);
+    }
+    let surface = ImageSurface::create_for_data(data, Format::A8, width as i32, height as i32, stride as i32).ok()?;
+    surface.set_device_scale(scale, scale);
+    Some(surface)
+}
+
+/// Cairo-based graphics renderer for images and sixel graphics
+pub struct CairoGraphicsRenderer {
+    context: cairo::Context,
+}
+
+impl CairoGraphicsRenderer {
+    pub fn new(context: cairo::Context) -> Self {
+        CairoGraphicsRenderer { context }
+    }
+}
+
+impl GraphicsRenderer for CairoGraphicsRenderer {
+    fn draw_sixel(&mut self, image: &ImageData, x: f64, y: f64) {
+        if let Some(surface) = rgba_surface(image) {
+            let _ = self.context.set_source_surface(&surface, x, y);
+            let _ = self.context.paint();
+        }
+    }
+
+    fn draw_image(&mut self, image: ImageData, x: usize, y: usize) {
+        if let Some(surface) = rgba_surface(&image) {
+            let _ = self.context.set_source_surface(&surface, x as f64, y as f64);
+            let _ = self.context.paint();
+        }
+    }
+}
+
+/// Surface holding the RGBA pixels of `image` as premultiplied ARGB32;
+/// `None` for an empty image or one with fewer bytes than its size needs
+fn rgba_surface(image: &ImageData) -> Option<ImageSurface> {
+    let pixels = image.width * image.height;
+    if pixels == 0 || image.data.len() < pixels * 4 {
+        return None;
+    }
+    let mut argb = Vec::with_capacity(pixels * 4);
+    for rgba in image.data.chunks_exact(4).take(pixels) {
+        let alpha = rgba[3] as u32;
+        let premultiply = |channel: u8| (channel as u32 * alpha + 127) / 255;
+        let pixel = (alpha << 24) | (premultiply(rgba[0]) << 16) | (premultiply(rgba[1]) << 8) | premultiply(rgba[2]);
+        argb.extend_from_slice(&pixel.to_ne_bytes());
+    }
+    ImageSurface::create_for_data(argb, Format::ARgb32, image.width as i32, image.height as i32, image.width as i32 * 4).ok()
+}
+
+/// Cairo-based UI renderer for clear/flush operations
+pub struct CairoUIRenderer {
+    context: cairo::Context,
+    /// Receives `set_pointer_shape` hints; the toolkit applies them to its widget
+    pointer_handler: Option<Box<dyn FnMut(PointerShape)>>,
+}
+
+/// Width of the scroll mark strip along the right edge, in pixels
+const SCROLL_MARK_WIDTH: f64 = 4.0;
+/// Width of the visual bell's border flash, in pixels
+const BELL_BORDER_WIDTH: f64 = 3.0;
+/// Opacity of the visual bell's background pulse at full intensity
+const BELL_PULSE_ALPHA: f64 = 0.3;
+/// Outline width of collaborators' cursors, in pixels
+const REMOTE_CURSOR_LINE_WIDTH: f64 = 1.0;
+/// Width of the gutter bar drawn for colored row annotations, in pixels
+const ROW_ANNOTATION_WIDTH: f64 = 3.0;
+/// Height of the strikethrough line, as a fraction of the cell height from its top
+const STRIKETHROUGH_POSITION: f64 = 0.5;
+/// Height of the overline, as a fraction of the cell height from its top
+const OVERLINE_POSITION: f64 = 0.05;
+
+impl CairoUIRenderer {
+    pub fn new(context: cairo::Context) -> Self {
+        CairoUIRenderer { context, pointer_handler: None }
+    }
+
+    /// Forward pointer shape hints to `handler`
+    pub fn with_pointer_handler(mut self, handler: impl FnMut(PointerShape) + 'static) -> Self {
+        self.pointer_handler = Some(Box::new(handler));
+        self
+    }
+
+    /// Draw scroll marks as colored ticks along the right edge
+    ///
+    /// `first_row` is the absolute row of the oldest scrollback line and
+    /// `total_rows` the number of rows (scrollback + screen) the strip spans.
+    /// Mark colors are drawn as `paint` maps them, e.g. with
+    /// [`CairoTextRenderer::paint_color`].
+    pub fn draw_scroll_marks(
+        &self,
+        marks: &[ScrollMark],
+        first_row: u64,
+        total_rows: u64,
+        width: f64,
+        height: f64,
+        paint: impl Fn(Color) -> Color,
+    ) {
+        if marks.is_empty() || total_rows == 0 {
+            return;
+        }
+        let x = width - SCROLL_MARK_WIDTH;
+        let tick = (height / total_rows as f64).max(2.0);
+        for mark in marks {
+            let offset = mark.row.saturating_sub(first_row) as f64;
+            let y = (offset / total_rows as f64 * height).min(height - tick);
+            let color = paint(scroll_mark_color(mark));
+            self.context.set_source_rgba(color.r, color.g, color.b, color.a);
+            self.context.rectangle(x, y, SCROLL_MARK_WIDTH, tick);
+            let _ = self.context.fill();
+        }
+    }
+
+    /// Draw the visual bell over the whole `width` x `height` area
+    ///
+    /// Border flashes and pulses use `color` (the foreground) faded by the
+    /// flash intensity; an inverted screen stays inverted until the flash ends.
+    pub fn draw_visual_bell(&self, flash: BellFlash, color: Color, width: f64, height: f64) {
+        let cr = &self.context;
+        let _ = cr.save();
+        match flash.style {
+            VisualBellStyle::Off => {}
+            VisualBellStyle::BorderFlash => {
+                cr.set_source_rgba(color.r, color.g, color.b, flash.intensity);
+                cr.set_line_width(BELL_BORDER_WIDTH);
+                let inset = BELL_BORDER_WIDTH / 2.0;
+                cr.rectangle(inset, inset, width - BELL_BORDER_WIDTH, height - BELL_BORDER_WIDTH);
+                let _ = cr.stroke();
+            }
+            VisualBellStyle::Invert => {
+                cr.set_operator(cairo::Operator::Difference);
+                cr.set_source_rgb(1.0, 1.0, 1.0);
+                cr.rectangle(0.0, 0.0, width, height);
+                let _ = cr.fill();
+            }
+            VisualBellStyle::BackgroundPulse => {
+                cr.set_source_rgba(color.r, color.g, color.b, BELL_PULSE_ALPHA * flash.intensity);
+                cr.rectangle(0.0, 0.0, width, height);
+                let _ = cr.fill();
+            }
+        }
+        let _ = cr.restore();
+    }
+}
+
+/// Scrollbar color of a mark; prompts show their command's exit status
+fn scroll_mark_color(mark: &ScrollMark) -> Color {
+    match mark.category {
+        MarkCategory::Prompt => match mark.exit_code {
+            Some(0) => Color::rgba(0.3, 0.75, 0.35, 0.9),
+            Some(_) => Color::rgba(0.9, 0.25, 0.25, 0.9),
+            None => Color::rgba(0.35, 0.55, 0.95, 0.9),
+        },
+        MarkCategory::SearchMatch => Color::rgba(0.95, 0.8, 0.2, 0.9),
+        MarkCategory::ErrorTrigger => Color::rgba(0.9, 0.25, 0.25, 0.9),
+    }
+}
+
+impl UIRenderer for CairoUIRenderer {
+    fn clear(&mut self) {
+        // Don't clear - preserve transparency for the host widget
+    }
+
+    fn flush(&mut self) {
+        // Cairo operations are already flushed
+    }
+
+    fn set_cursor_shape(&mut self, _shape: vte_core::CursorShape) {
+        // The host toolkit handles cursor shape
+    }
+
+    fn set_pointer_shape(&mut self, shape: PointerShape) {
+        if let Some(handler) = self.pointer_handler.as_mut() {
+            handler(shape);
+        }
+    }
+
+    fn handle_hyperlink(&mut self, url: &str) -> bool {
+        // Handle HTTPS hyperlinks by opening them in the default browser
+        if url.starts_with("https://") || url.starts_with("http://") {
+            use std::process::Command;
+
+            // Cross-platform: try xdg-open (Linux), open (macOS), start (Windows)
+            #[cfg(target_os = "linux")]
+            let cmd_result = Command::new("xdg-open").arg(url).spawn();
+
+            #[cfg(target_os = "macos")]
+            let cmd_result = Command::new("open").arg(url).spawn();
+
+            #[cfg(target_os = "windows")]
+            let cmd_result = {
+                use std::os::windows::process::CommandExt;
+                Command::new("cmd")
+                    .args(&["/C", "start", url])
+                    .creation_flags(0x00000008) // DETACHED_PROCESS
+                    .spawn()
+            };
+
+            #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+            let cmd_result = Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "Unsupported platform for hyperlink handling"));
+
+            match cmd_result {
+                Ok(_) => {
+                    eprintln!("Opened hyperlink: {}", url);
+                    true
+                }
+                Err(e) => {
+                    eprintln!("Failed to open hyperlink {}: {}", url, e);
+                    false
+                }
+            }
+        } else {
+            // For non-HTTPS links, we could emit a signal or call a callback
+            // For now, just log and return false
+            eprintln!("Unsupported hyperlink protocol: {}", url);
+            false
+        }
+    }
+}
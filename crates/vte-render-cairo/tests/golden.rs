@@ -0,0 +1,132 @@
+// tests/golden.rs
+//! Golden-image regression suite
+//!
+//! Each fixture feeds fixed escape sequences into a small grid, renders it
+//! with the Cairo renderer at the default cell size in `GOLDEN_FONT`, and
+//! compares the result with `tests/golden/<name>.png` (see
+//! `support/golden.rs`). A missing golden fails. After an intended
+//! rendering change, or for a new fixture, write the images and review them
+//! with the diff:
+//!
+//! ```text
+//! UPDATE_GOLDEN=1 cargo test -p vte-render-cairo --test golden
+//! ```
+//!
+//! Failures leave `<name>.actual.png` and `<name>.diff.png` under
+//! `target/tmp/golden`.
+
+use std::path::Path;
+use std::sync::Arc;
+use vte_core::{AnsiParser, Color, CursorShape, Grid, TerminalConfig};
+use vte_render_cairo::{render_to_surface, DEFAULT_CELL_HEIGHT, DEFAULT_CELL_WIDTH};
+
+mod support {
+    pub mod golden;
+}
+
+use support::golden::{check_golden, GoldenTolerance};
+
+/// Font every fixture renders in, so goldens do not follow the desktop's monospace
+const GOLDEN_FONT: &str = "DejaVu Sans Mono";
+
+/// Opaque colors and a steady cursor, so frames do not depend on timing
+fn golden_config() -> TerminalConfig {
+    TerminalConfig::default()
+        .with_font_family(GOLDEN_FONT)
+        .with_colors(Color::rgb(0.9, 0.9, 0.9), Color::rgb(0.1, 0.1, 0.12))
+        .with_cursor_blink(false)
+}
+
+/// Render `input` on a `cols` x `rows` grid and compare it with golden `name`
+fn assert_golden(name: &str, cols: usize, rows: usize, config: TerminalConfig, input: &str, setup: impl FnOnce(&mut Grid)) {
+    let mut grid = Grid::new(cols, rows, Arc::new(config.clone()));
+    AnsiParser::new().feed_str(input, &mut grid);
+    setup(&mut grid);
+    let surface = render_to_surface(&grid, &config, DEFAULT_CELL_WIDTH, DEFAULT_CELL_HEIGHT).unwrap();
+
+    let golden_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let artifact_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    if let Err(message) = check_golden(name, &surface, &golden_dir, &artifact_dir, GoldenTolerance::default()) {
+        panic!("{}", message);
+    }
+}
+
+#[test]
+fn golden_text_attributes() {
+    assert_golden(
+        "text_attributes",
+        24,
+        4,
+        golden_config(),
+        "plain \x1b[1mbold\x1b[22m \x1b[3mitalic\x1b[23m\r\n\
+         \x1b[2mdim\x1b[22m \x1b[7mreverse\x1b[27m \x1b[9mstrike\x1b[29m\r\n\
+         \x1b[31mred \x1b[42mon green\x1b[0m \x1b[38;2;255;128;0mrgb\x1b[0m\r\n\
+         \x1b[?25l",
+        |_| {},
+    );
+}
+
+#[test]
+fn golden_palette() {
+    let swatches: String = (0..16).map(|i| format!("\x1b[48;5;{}m  ", i)).collect();
+    assert_golden("palette", 32, 1, golden_config(), &format!("{}\x1b[0m\x1b[?25l", swatches), |_| {});
+}
+
+#[test]
+fn golden_underline_styles() {
+    assert_golden(
+        "underline_styles",
+        16,
+        5,
+        golden_config(),
+        "\x1b[4:1msingle\x1b[0m\r\n\
+         \x1b[4:2mdouble\x1b[0m\r\n\
+         \x1b[4:3mcurly\x1b[0m\r\n\
+         \x1b[4:4mdotted\x1b[0m\r\n\
+         \x1b[4:5mdashed\x1b[0m\x1b[?25l",
+        |_| {},
+    );
+}
+
+#[test]
+fn golden_sgr_double_underline() {
+    // SGR 21 draws like 4:2; 22 keeps the underline, 24 ends it
+    assert_golden(
+        "sgr_double_underline",
+        16,
+        2,
+        golden_config(),
+        "\x1b[1;21mbold\x1b[22mplain\x1b[24mnone\r\n\x1b[4mone\x1b[21mtwo\x1b[0m\x1b[?25l",
+        |_| {},
+    );
+}
+
+#[test]
+fn golden_cursor_shapes() {
+    for (name, shape) in [
+        ("cursor_block", CursorShape::Block),
+        ("cursor_underline", CursorShape::Underline),
+        ("cursor_bar", CursorShape::Bar),
+    ] {
+        let config = golden_config().with_cursor_shape(shape);
+        assert_golden(name, 8, 1, config, "ab\x1b[1D", |_| {});
+    }
+}
+
+#[test]
+fn golden_selection_overlay() {
+    assert_golden(
+        "selection_overlay",
+        12,
+        3,
+        golden_config(),
+        "first line\r\nsecond one\r\nthird\x1b[?25l",
+        |grid| grid.selection.create_selection(0, 6, 1, 5),
+    );
+}
+
+#[test]
+fn golden_high_contrast() {
+    let config = golden_config().with_high_contrast(true);
+    assert_golden("high_contrast", 16, 2, config, "\x1b[34mblue\x1b[0m \x1b[4munder\x1b[0m\r\n$ ", |_| {});
+}
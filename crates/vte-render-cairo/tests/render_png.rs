@@ -0,0 +1,364 @@
+// tests/render_png.rs
+//! Headless rendering: offscreen surfaces and PNG output
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+use vte_core::{Annotation, AnsiParser, Color, ColorAdjust, CursorShape, Grid, HighlightStyle, TerminalConfig};
+use vte_render_cairo::{
+    render_fonts, render_thumbnail, render_to_png, render_to_surface, CairoRenderer, RowSurfaceCache, DEFAULT_CELL_HEIGHT, DEFAULT_CELL_WIDTH,
+};
+
+fn grid_with(text: &str, config: &TerminalConfig) -> Grid {
+    let mut grid = Grid::new(8, 2, Arc::new(config.clone()));
+    AnsiParser::new().feed_str(text, &mut grid);
+    grid
+}
+
+/// (r, g, b) of one pixel of an opaque ARGB32 surface
+fn pixel(surface: &mut cairo::ImageSurface, x: usize, y: usize) -> (u8, u8, u8) {
+    let stride = surface.stride() as usize;
+    let data = surface.data().expect("surface data");
+    let i = y * stride + x * 4;
+    // Native-endian 0xAARRGGBB, i.e. B, G, R, A in memory on little-endian
+    (data[i + 2], data[i + 1], data[i])
+}
+
+#[test]
+fn surface_is_sized_to_grid_and_filled_with_background() {
+    let config = TerminalConfig::default().with_background_color(Color::rgb(0.0, 0.0, 1.0));
+    let grid = grid_with("\x1b[41m \x1b[0m", &config);
+    let mut surface = render_to_surface(&grid, &config, DEFAULT_CELL_WIDTH, DEFAULT_CELL_HEIGHT).unwrap();
+
+    assert_eq!(surface.width(), (8.0 * DEFAULT_CELL_WIDTH) as i32);
+    assert_eq!(surface.height(), (2.0 * DEFAULT_CELL_HEIGHT) as i32);
+
+    // First cell has a palette red background, the rest the configured default
+    let (r, g, b) = pixel(&mut surface, 2, 2);
+    assert!(r > 150 && g < 50 && b < 50, "expected red, got {:?}", (r, g, b));
+    assert_eq!(pixel(&mut surface, 50, 20), (0, 0, 255));
+}
+
+#[test]
+fn cell_spacing_and_padding_enlarge_the_layout() {
+    let config = TerminalConfig::default()
+        .with_background_color(Color::rgb(0.0, 0.0, 1.0))
+        .with_cell_spacing(2.0, 4.0)
+        .with_padding(3.0);
+    // Hide the cursor, which would otherwise cover the second cell
+    let grid = grid_with("\x1b[41m \x1b[0m\x1b[?25l", &config);
+    let mut surface = render_to_surface(&grid, &config, DEFAULT_CELL_WIDTH, DEFAULT_CELL_HEIGHT).unwrap();
+
+    let (cell_w, cell_h) = (DEFAULT_CELL_WIDTH + 2.0, DEFAULT_CELL_HEIGHT + 4.0);
+    assert_eq!(surface.width(), (8.0 * cell_w + 6.0) as i32);
+    assert_eq!(surface.height(), (2.0 * cell_h + 6.0) as i32);
+
+    // The red cell starts after the padding and covers the added spacing
+    assert_eq!(pixel(&mut surface, 1, 1), (0, 0, 255));
+    let (r, g, b) = pixel(&mut surface, 4, 4);
+    assert!(r > 150 && g < 50 && b < 50, "expected red, got {:?}", (r, g, b));
+    let (r, ..) = pixel(&mut surface, 3 + cell_w as usize - 1, 3 + cell_h as usize - 1);
+    assert!(r > 150);
+    assert_eq!(pixel(&mut surface, 3 + cell_w as usize + 1, 4), (0, 0, 255));
+}
+
+#[test]
+fn thumbnail_fits_the_screen_into_the_requested_size() {
+    let config = TerminalConfig::default().with_background_color(Color::rgb(0.0, 0.0, 1.0));
+    let grid = grid_with("\x1b[41m \x1b[0m\x1b[?25l", &config);
+    let mut thumbnail = render_thumbnail(&grid, &config, 40, 40).unwrap();
+    assert_eq!((thumbnail.width(), thumbnail.height()), (40, 40));
+
+    // The wide screen fills the width and is centered vertically
+    let scale = 40.0 / (8.0 * DEFAULT_CELL_WIDTH);
+    let top = (40.0 - 2.0 * DEFAULT_CELL_HEIGHT * scale) / 2.0;
+    assert!(top >= 2.0);
+    assert_eq!(pixel(&mut thumbnail, 20, 0), (0, 0, 255));
+    let red_cell = ((DEFAULT_CELL_WIDTH * scale / 2.0) as usize, (top + DEFAULT_CELL_HEIGHT * scale / 2.0) as usize);
+    let (r, g, b) = pixel(&mut thumbnail, red_cell.0, red_cell.1);
+    assert!(r > 150 && g < 50 && b < 50, "expected red, got {:?}", (r, g, b));
+    assert_eq!(pixel(&mut thumbnail, 30, red_cell.1), (0, 0, 255));
+}
+
+#[test]
+fn render_to_png_writes_png_file() {
+    let config = TerminalConfig::default();
+    let grid = grid_with("hello", &config);
+    let path = std::env::temp_dir().join(format!("vte-render-cairo-{}.png", std::process::id()));
+
+    render_to_png(&grid, &config, &path).unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(&bytes[..8], b"\x89PNG\r\n\x1a\n");
+
+    let mut file = std::io::Cursor::new(bytes);
+    let decoded = cairo::ImageSurface::create_from_png(&mut file).unwrap();
+    assert_eq!(decoded.width(), (8.0 * DEFAULT_CELL_WIDTH) as i32);
+}
+
+#[test]
+fn block_cursor_is_reverse_video_on_transparent_background() {
+    // Default theme: white text on a transparent background
+    let config = TerminalConfig::default();
+    let grid = grid_with("ab", &config);
+    let mut surface = render_to_surface(&grid, &config, DEFAULT_CELL_WIDTH, DEFAULT_CELL_HEIGHT).unwrap();
+
+    // Cursor sits on the blank third cell and is filled with the text color
+    assert_eq!(pixel(&mut surface, 21, 1), (255, 255, 255));
+    assert_eq!(pixel(&mut surface, 29, 15), (255, 255, 255));
+    assert_eq!(pixel(&mut surface, 31, 1), (0, 0, 0));
+}
+
+#[test]
+fn bar_cursor_paints_only_its_strip() {
+    let config = TerminalConfig::default()
+        .with_background_color(Color::rgb(0.0, 0.0, 0.0))
+        .with_cursor_shape(CursorShape::Bar)
+        .with_cursor_color(Color::rgb(0.0, 1.0, 0.0));
+    let grid = grid_with("ab", &config);
+    let mut surface = render_to_surface(&grid, &config, DEFAULT_CELL_WIDTH, DEFAULT_CELL_HEIGHT).unwrap();
+
+    assert_eq!(pixel(&mut surface, 20, 8), (0, 255, 0));
+    assert_eq!(pixel(&mut surface, 26, 8), (0, 0, 0));
+}
+
+/// Whether an (r, g, b) pixel is mostly the one channel `channel` (0 red, 1 green, 2 blue)
+fn mostly(pixel: (u8, u8, u8), channel: usize) -> bool {
+    let channels = [pixel.0, pixel.1, pixel.2];
+    channels.iter().enumerate().all(|(i, &v)| if i == channel { v > 150 } else { v < 80 })
+}
+
+#[test]
+fn decorations_follow_fg_mid_row_unless_colored() {
+    let config = TerminalConfig::default().with_background_color(Color::rgb(0.0, 0.0, 0.0));
+    // Underlined blanks: red, green, then pinned blue over a red foreground
+    let grid = grid_with(
+        "\x1b[4;38;2;255;0;0m \x1b[38;2;0;255;0m \x1b[58:2::0:0:255;38;2;255;0;0m \x1b[59m \x1b[0m",
+        &config,
+    );
+    let mut surface = render_to_surface(&grid, &config, DEFAULT_CELL_WIDTH, DEFAULT_CELL_HEIGHT).unwrap();
+
+    // The underline sits at 85% of the 16px cell
+    let underline_y = (DEFAULT_CELL_HEIGHT * 0.85) as usize;
+    let at = |surface: &mut cairo::ImageSurface, col: usize| pixel(surface, col * DEFAULT_CELL_WIDTH as usize + 5, underline_y);
+    assert!(mostly(at(&mut surface, 0), 0), "{:?}", at(&mut surface, 0));
+    assert!(mostly(at(&mut surface, 1), 1), "{:?}", at(&mut surface, 1));
+    assert!(mostly(at(&mut surface, 2), 2), "{:?}", at(&mut surface, 2));
+    assert!(mostly(at(&mut surface, 3), 0), "{:?}", at(&mut surface, 3));
+}
+
+#[test]
+fn block_cursor_keeps_decoration_color() {
+    let config = TerminalConfig::default().with_background_color(Color::rgb(0.0, 0.0, 0.0));
+    let underline_y = (DEFAULT_CELL_HEIGHT * 0.85) as usize;
+
+    // The SGR 58 color stays under the block cursor
+    let grid = grid_with("\x1b[4;58:2::0:0:255m \x1b[1D", &config);
+    let mut surface = render_to_surface(&grid, &config, DEFAULT_CELL_WIDTH, DEFAULT_CELL_HEIGHT).unwrap();
+    let under_cursor = pixel(&mut surface, 5, underline_y);
+    assert!(mostly(under_cursor, 2), "{:?}", under_cursor);
+
+    // A plain underline is drawn like the glyph, in the cursor text color
+    let grid = grid_with("\x1b[4m \x1b[1D", &config);
+    let mut surface = render_to_surface(&grid, &config, DEFAULT_CELL_WIDTH, DEFAULT_CELL_HEIGHT).unwrap();
+    let (r, g, b) = pixel(&mut surface, 5, underline_y);
+    assert!(r < 100 && g < 100 && b < 100, "{:?}", (r, g, b));
+}
+
+/// Pixels of `grid` drawn on an opaque black surface, optionally through `cache`
+fn render_frame(grid: &Grid, config: &TerminalConfig, cache: Option<&Rc<RefCell<RowSurfaceCache>>>) -> Vec<u8> {
+    let width = (grid.cols as f64 * DEFAULT_CELL_WIDTH) as i32;
+    let height = (grid.rows as f64 * DEFAULT_CELL_HEIGHT) as i32;
+    let mut surface = cairo::ImageSurface::create(cairo::Format::ARgb32, width, height).unwrap();
+    {
+        let context = cairo::Context::new(&surface).unwrap();
+        context.set_source_rgb(0.0, 0.0, 0.0);
+        context.paint().unwrap();
+        let mut renderer = CairoRenderer::new(&context, DEFAULT_CELL_WIDTH, DEFAULT_CELL_HEIGHT).unwrap();
+        if let Some(cache) = cache {
+            renderer = renderer.with_row_cache(Rc::clone(cache));
+        }
+        renderer.apply_config(&Arc::new(config.clone()));
+        renderer.render_grid(grid, width as f64, height as f64);
+    }
+    surface.flush();
+    let pixels = surface.data().unwrap().to_vec();
+    pixels
+}
+
+/// Whether two frames match, allowing for rounding where glyph edges are composited
+fn same_pixels(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.abs_diff(*y) <= 1)
+}
+
+#[test]
+fn row_cache_reuses_unchanged_rows_with_identical_pixels() {
+    let config = TerminalConfig::default().with_cursor_blink(false);
+    let mut grid = grid_with("\x1b[?25l\x1b[41mab\x1b[0m\r\n\x1b[4mcd", &config);
+    let uncached = render_frame(&grid, &config, None);
+    let cache = Rc::new(RefCell::new(RowSurfaceCache::new()));
+
+    assert!(same_pixels(&render_frame(&grid, &config, Some(&cache)), &uncached));
+    assert_eq!((cache.borrow().misses(), cache.borrow().hits()), (2, 0));
+    assert!(same_pixels(&render_frame(&grid, &config, Some(&cache)), &uncached));
+    assert_eq!((cache.borrow().misses(), cache.borrow().hits()), (0, 2));
+
+    // Only the edited row is rendered again
+    AnsiParser::new().feed_str("x", &mut grid);
+    assert!(same_pixels(&render_frame(&grid, &config, Some(&cache)), &render_frame(&grid, &config, None)));
+    assert_eq!((cache.borrow().misses(), cache.borrow().hits()), (1, 1));
+}
+
+#[test]
+fn selection_is_drawn_as_row_spans_with_rounded_outer_corners() {
+    let config = TerminalConfig::default().with_background_color(Color::rgb(0.0, 0.0, 0.0));
+    let mut grid = grid_with("\x1b[?25l", &config);
+    // Columns 1.. of the first row and ..=6 of the second
+    grid.selection.create_selection(0, 1, 1, 6);
+    let mut surface = render_to_surface(&grid, &config, DEFAULT_CELL_WIDTH, DEFAULT_CELL_HEIGHT).unwrap();
+
+    // The selection is a gray derived from the black background
+    let tinted = |(r, g, b): (u8, u8, u8)| r > 50 && r == g && g == b;
+    assert!(tinted(pixel(&mut surface, 40, 8)));
+    assert!(tinted(pixel(&mut surface, 20, 24)));
+    assert_eq!(pixel(&mut surface, 5, 8), (0, 0, 0));
+    assert_eq!(pixel(&mut surface, 75, 24), (0, 0, 0));
+
+    // The exposed top-left corner is rounded off; where the rows join it is not
+    assert!(!tinted(pixel(&mut surface, 10, 0)));
+    assert!(tinted(pixel(&mut surface, 10, 15)));
+
+    let square = config.clone().with_selection_corner_radius(0.0);
+    let mut surface = render_to_surface(&grid, &square, DEFAULT_CELL_WIDTH, DEFAULT_CELL_HEIGHT).unwrap();
+    assert!(tinted(pixel(&mut surface, 10, 0)));
+}
+
+#[test]
+fn highlights_without_foreground_keep_the_text_visible() {
+    let config = TerminalConfig::default()
+        .with_colors(Color::rgb(1.0, 1.0, 1.0), Color::rgb(0.0, 0.0, 0.0));
+    let mut grid = grid_with("\x1b[?25lW", &config);
+    let row = grid.absolute_row(0);
+    grid.highlight_ranges(vec![(row, 0)..(row, 1)], HighlightStyle::new(Color::rgb(0.0, 0.0, 1.0)));
+    let mut surface = render_to_surface(&grid, &config, DEFAULT_CELL_WIDTH, DEFAULT_CELL_HEIGHT).unwrap();
+
+    // The cell is blue, with the glyph drawn on it in its white foreground
+    let (mut blue, mut ink) = (0, 0);
+    for y in 0..DEFAULT_CELL_HEIGHT as usize {
+        for x in 0..DEFAULT_CELL_WIDTH as usize {
+            let (r, _, b) = pixel(&mut surface, x, y);
+            blue += usize::from(r < 64 && b > 192);
+            ink += usize::from(r > 128);
+        }
+    }
+    assert!(blue > 0 && ink > 0, "blue {} ink {}", blue, ink);
+}
+
+#[test]
+fn inactive_renderer_mutes_colors() {
+    let config = TerminalConfig::default().with_background_color(Color::rgb(0.0, 0.0, 0.0));
+    let grid = grid_with("\x1b[41m \x1b[0m", &config);
+    let width = (grid.cols as f64 * DEFAULT_CELL_WIDTH) as i32;
+    let height = (grid.rows as f64 * DEFAULT_CELL_HEIGHT) as i32;
+
+    // Red channel of the first cell's red background
+    let red = |inactive: bool| {
+        let mut surface = cairo::ImageSurface::create(cairo::Format::ARgb32, width, height).unwrap();
+        {
+            let context = cairo::Context::new(&surface).unwrap();
+            let mut renderer = CairoRenderer::new(&context, DEFAULT_CELL_WIDTH, DEFAULT_CELL_HEIGHT).unwrap();
+            renderer.apply_config(&Arc::new(config.clone()));
+            renderer.set_inactive(inactive);
+            renderer.render_grid(&grid, width as f64, height as f64);
+        }
+        surface.flush();
+        pixel(&mut surface, 2, 2).0
+    };
+
+    let (active, inactive) = (red(false), red(true));
+    assert!(inactive > 0 && inactive < active, "inactive {} vs active {}", inactive, active);
+}
+
+#[test]
+fn color_adjust_changes_drawn_colors() {
+    let config = TerminalConfig::default().with_background_color(Color::rgb(0.0, 0.0, 0.0));
+    let dimmed = config.clone().with_color_adjust(ColorAdjust { brightness: -0.5, ..ColorAdjust::NONE });
+    let grid = grid_with("\x1b[41m \x1b[0m", &dimmed);
+
+    let mut plain = render_to_surface(&grid, &config, DEFAULT_CELL_WIDTH, DEFAULT_CELL_HEIGHT).unwrap();
+    let mut adjusted = render_to_surface(&grid, &dimmed, DEFAULT_CELL_WIDTH, DEFAULT_CELL_HEIGHT).unwrap();
+    let (before, after) = (pixel(&mut plain, 2, 2).0, pixel(&mut adjusted, 2, 2).0);
+    assert!(after < before, "adjusted {} vs plain {}", after, before);
+}
+
+#[test]
+fn color_adjust_changes_overlay_colors() {
+    let config = TerminalConfig::default().with_background_color(Color::rgb(0.0, 0.0, 0.0));
+    let dimmed = config.clone().with_color_adjust(ColorAdjust { brightness: -0.5, ..ColorAdjust::NONE });
+    let mut grid = grid_with("\x1b[?25l", &config);
+    grid.annotate_row(grid.absolute_row(0), Annotation::new().with_color(Color::rgb(1.0, 0.0, 0.0)));
+
+    let mut plain = render_to_surface(&grid, &config, DEFAULT_CELL_WIDTH, DEFAULT_CELL_HEIGHT).unwrap();
+    let mut adjusted = render_to_surface(&grid, &dimmed, DEFAULT_CELL_WIDTH, DEFAULT_CELL_HEIGHT).unwrap();
+    let (before, after) = (pixel(&mut plain, 0, 2).0, pixel(&mut adjusted, 0, 2).0);
+    assert!(after < before, "adjusted gutter {} vs plain {}", after, before);
+}
+
+#[test]
+fn sixel_images_are_drawn_at_their_cell() {
+    let config = TerminalConfig::default().with_background_color(Color::rgb(0.0, 0.0, 0.0));
+    // A red 4x6 image after "a"; the cursor is hidden so only the image paints
+    let grid = grid_with("\x1b[?25la\x1bPq#1;2;100;0;0!4~\x1b\\", &config);
+    let mut surface = render_to_surface(&grid, &config, DEFAULT_CELL_WIDTH, DEFAULT_CELL_HEIGHT).unwrap();
+
+    let x = DEFAULT_CELL_WIDTH as usize;
+    assert_eq!(pixel(&mut surface, x, 0), (255, 0, 0));
+    assert_eq!(pixel(&mut surface, x + 3, 5), (255, 0, 0));
+    assert_eq!(pixel(&mut surface, x + 4, 0), (0, 0, 0));
+    assert_eq!(pixel(&mut surface, x, 6), (0, 0, 0));
+}
+
+#[test]
+fn sixel_images_follow_color_adjust() {
+    let config = TerminalConfig::default()
+        .with_background_color(Color::rgb(0.0, 0.0, 0.0))
+        .with_color_adjust(ColorAdjust { brightness: -0.5, ..ColorAdjust::NONE });
+    let grid = grid_with("\x1b[?25l\x1bPq#1;2;100;0;0!4~\x1b\\", &config);
+    let mut surface = render_to_surface(&grid, &config, DEFAULT_CELL_WIDTH, DEFAULT_CELL_HEIGHT).unwrap();
+
+    let (red, _, _) = pixel(&mut surface, 0, 0);
+    assert!(red < 200, "adjusted image pixel {}", red);
+}
+
+#[test]
+fn zoom_rasterizes_glyphs_at_the_zoomed_size() {
+    let config = TerminalConfig::default().with_zoom(2.0);
+    let grid = grid_with("H", &config);
+    // Held here, the zoomed cache is the one the renderer's lookup shares
+    let zoomed = render_fonts(&config, 1.0).unwrap();
+    assert_eq!(zoomed.key().size(), (config.font_size * 2.0) as f32);
+    assert_eq!(zoomed.glyph_count(), 0);
+
+    let surface = render_to_surface(&grid, &config, DEFAULT_CELL_WIDTH, DEFAULT_CELL_HEIGHT).unwrap();
+    assert_eq!(surface.height(), (4.0 * DEFAULT_CELL_HEIGHT) as i32);
+    assert!(zoomed.glyph_count() > 0, "glyphs were not drawn from the zoomed fonts");
+}
+
+#[test]
+fn combining_marks_are_drawn_with_their_character() {
+    let config = TerminalConfig::default()
+        .with_colors(Color::rgb(1.0, 1.0, 1.0), Color::rgb(0.0, 0.0, 0.0));
+    let ink = |text: &str| {
+        let grid = grid_with(text, &config);
+        let mut surface = render_to_surface(&grid, &config, DEFAULT_CELL_WIDTH, DEFAULT_CELL_HEIGHT).unwrap();
+        // Ink in the top third of the first cell, above any lowercase letter
+        let mut lit = 0;
+        for y in 0..DEFAULT_CELL_HEIGHT as usize / 3 {
+            for x in 0..DEFAULT_CELL_WIDTH as usize {
+                lit += usize::from(pixel(&mut surface, x, y).0 > 64);
+            }
+        }
+        lit
+    };
+    assert!(ink("e\u{301}\x1b[?25l") > ink("e\x1b[?25l"));
+}
@@ -0,0 +1,211 @@
+// tests/support/golden.rs
+//! Golden-image comparison for rendering regression tests
+//!
+//! A test renders a fixed input, e.g. with `vte_render_cairo::render_to_surface`,
+//! and calls [`check_golden`] with a name. The image is compared against
+//! `<dir>/<name>.png` with a perceptual per-pixel difference (the YIQ
+//! distance used by pixelmatch), so antialiasing noise between Cairo and
+//! FreeType versions passes while a moved cursor or a missing underline
+//! does not.
+//!
+//! - `UPDATE_GOLDEN=1` rewrites the goldens from the current output; review
+//!   the changed PNGs like any other diff.
+//! - A missing golden fails the check, so a fixture whose image was never
+//!   committed cannot pass silently; create it with `UPDATE_GOLDEN=1`.
+//! - On a mismatch the actual image and a diff image (mismatched pixels in
+//!   red over a faded copy of the golden) are written next to each other in
+//!   the artifact directory for review.
+
+use cairo::{Format, ImageSurface};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use vte_core::BackendError;
+
+/// Environment variable that makes [`check_golden`] rewrite the goldens
+pub const UPDATE_GOLDEN_VAR: &str = "UPDATE_GOLDEN";
+
+/// Largest YIQ distance between two colors (black and white)
+const MAX_YIQ_DELTA: f64 = 35215.0;
+
+/// How strictly an image has to match its golden
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GoldenTolerance {
+    /// Per-pixel perceptual distance, 0.0 (exact) to 1.0 (black vs white),
+    /// above which a pixel counts as mismatched
+    pub pixel_threshold: f64,
+    /// Fraction of mismatched pixels allowed
+    pub max_mismatch_ratio: f64,
+}
+
+impl Default for GoldenTolerance {
+    fn default() -> Self {
+        Self { pixel_threshold: 0.1, max_mismatch_ratio: 0.005 }
+    }
+}
+
+/// Result of comparing two images of the same size
+#[derive(Debug)]
+pub struct ImageDiff {
+    /// Pixels above the tolerance's threshold
+    pub mismatched: usize,
+    pub total: usize,
+    /// Mismatched pixels in red over a faded copy of the expected image
+    pub diff: ImageSurface,
+}
+
+impl ImageDiff {
+    pub fn mismatch_ratio(&self) -> f64 {
+        if self.total == 0 { 0.0 } else { self.mismatched as f64 / self.total as f64 }
+    }
+}
+
+/// Opaque (r, g, b) of every pixel of an ARGB32 surface, composited on white
+fn rgb_pixels(surface: &ImageSurface) -> Result<Vec<[f64; 3]>, BackendError> {
+    let (width, height) = (surface.width() as usize, surface.height() as usize);
+    // Reading needs an ARGB32 copy we own; the source may be shared or another format
+    let mut copy = ImageSurface::create(Format::ARgb32, width as i32, height as i32)
+        .map_err(|e| BackendError::Rendering(e.to_string()))?;
+    {
+        let context = cairo::Context::new(&copy).map_err(|e| BackendError::Rendering(e.to_string()))?;
+        context.set_source_surface(surface, 0.0, 0.0).map_err(|e| BackendError::Rendering(e.to_string()))?;
+        context.paint().map_err(|e| BackendError::Rendering(e.to_string()))?;
+    }
+    copy.flush();
+    let stride = copy.stride() as usize;
+    let data = copy.data().map_err(|e| BackendError::Rendering(e.to_string()))?;
+
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let i = y * stride + x * 4;
+            // Native-endian premultiplied 0xAARRGGBB, i.e. B, G, R, A on little-endian
+            let pixel = u32::from_ne_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]);
+            let channel = |shift: u32| f64::from((pixel >> shift) & 0xff);
+            let alpha = channel(24);
+            let on_white = |c: f64| c + (255.0 - alpha);
+            pixels.push([on_white(channel(16)), on_white(channel(8)), on_white(channel(0))]);
+        }
+    }
+    Ok(pixels)
+}
+
+/// Perceptual distance of two colors, 0.0 to 1.0
+fn yiq_delta(a: [f64; 3], b: [f64; 3]) -> f64 {
+    let yiq = |[r, g, b]: [f64; 3]| {
+        (
+            r * 0.298_895_31 + g * 0.586_622_47 + b * 0.114_482_23,
+            r * 0.595_977_99 - g * 0.274_176_10 - b * 0.321_801_89,
+            r * 0.211_470_17 - g * 0.522_617_11 + b * 0.311_146_94,
+        )
+    };
+    let ((y1, i1, q1), (y2, i2, q2)) = (yiq(a), yiq(b));
+    let delta = 0.5053 * (y1 - y2).powi(2) + 0.299 * (i1 - i2).powi(2) + 0.1957 * (q1 - q2).powi(2);
+    (delta / MAX_YIQ_DELTA).sqrt()
+}
+
+/// Compare `actual` with `expected` pixel by pixel
+///
+/// Fails if the sizes differ.
+pub fn diff_images(actual: &ImageSurface, expected: &ImageSurface, pixel_threshold: f64) -> Result<ImageDiff, BackendError> {
+    let (width, height) = (expected.width(), expected.height());
+    if (actual.width(), actual.height()) != (width, height) {
+        return Err(BackendError::Rendering(format!(
+            "image is {}x{}, golden is {}x{}",
+            actual.width(), actual.height(), width, height
+        )));
+    }
+    let (actual_pixels, expected_pixels) = (rgb_pixels(actual)?, rgb_pixels(expected)?);
+
+    let mut diff = ImageSurface::create(Format::Rgb24, width, height).map_err(|e| BackendError::Rendering(e.to_string()))?;
+    let stride = diff.stride() as usize;
+    let mut mismatched = 0;
+    {
+        let mut data = diff.data().map_err(|e| BackendError::Rendering(e.to_string()))?;
+        for (index, (a, e)) in actual_pixels.iter().zip(&expected_pixels).enumerate() {
+            let (x, y) = (index % width as usize, index / width as usize);
+            let pixel = if yiq_delta(*a, *e) > pixel_threshold {
+                mismatched += 1;
+                0x00ff_0000
+            } else {
+                // Faded gray of the golden, for orientation
+                let gray = (e[0] * 0.3 + e[1] * 0.59 + e[2] * 0.11) as u32;
+                let faded = 255 - (255 - gray.min(255)) / 4;
+                (faded << 16) | (faded << 8) | faded
+            };
+            let i = y * stride + x * 4;
+            data[i..i + 4].copy_from_slice(&pixel.to_ne_bytes());
+        }
+    }
+    diff.mark_dirty();
+    Ok(ImageDiff { mismatched, total: actual_pixels.len(), diff })
+}
+
+fn write_png(surface: &ImageSurface, path: &Path) -> Result<(), BackendError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    surface
+        .write_to_png(&mut File::create(path)?)
+        .map_err(|e| BackendError::Rendering(e.to_string()))
+}
+
+/// Compare `surface` with the golden `<golden_dir>/<name>.png`
+///
+/// Returns a description of the mismatch on failure, with the actual and
+/// diff images written to `artifact_dir`. Goldens are only written when
+/// `UPDATE_GOLDEN` is set; see the module docs.
+pub fn check_golden(
+    name: &str,
+    surface: &ImageSurface,
+    golden_dir: &Path,
+    artifact_dir: &Path,
+    tolerance: GoldenTolerance,
+) -> Result<(), String> {
+    let golden = golden_dir.join(format!("{}.png", name));
+    let update = std::env::var_os(UPDATE_GOLDEN_VAR).is_some_and(|value| value != "0");
+    let artifact = |suffix: &str| -> PathBuf { artifact_dir.join(format!("{}.{}.png", name, suffix)) };
+    if update {
+        write_png(surface, &golden).map_err(|e| format!("{}: writing {}: {}", name, golden.display(), e))?;
+        return Ok(());
+    }
+    if !golden.exists() {
+        let _ = write_png(surface, &artifact("actual"));
+        return Err(format!(
+            "{}: no golden at {}; see {} (rerun with {}=1 to create it)",
+            name,
+            golden.display(),
+            artifact("actual").display(),
+            UPDATE_GOLDEN_VAR,
+        ));
+    }
+
+    let expected = File::open(&golden)
+        .map_err(BackendError::from)
+        .and_then(|mut file| ImageSurface::create_from_png(&mut file).map_err(|e| BackendError::Rendering(e.to_string())))
+        .map_err(|e| format!("{}: reading {}: {}", name, golden.display(), e))?;
+
+    let diff = match diff_images(surface, &expected, tolerance.pixel_threshold) {
+        Ok(diff) => diff,
+        Err(e) => {
+            let _ = write_png(surface, &artifact("actual"));
+            return Err(format!("{}: {}", name, e));
+        }
+    };
+    if diff.mismatch_ratio() <= tolerance.max_mismatch_ratio {
+        return Ok(());
+    }
+
+    let _ = write_png(surface, &artifact("actual"));
+    let _ = write_png(&diff.diff, &artifact("diff"));
+    Err(format!(
+        "{}: {} of {} pixels differ from {} ({:.2}%, {:.2}% allowed); see {} (rerun with {}=1 to accept)",
+        name,
+        diff.mismatched,
+        diff.total,
+        golden.display(),
+        diff.mismatch_ratio() * 100.0,
+        tolerance.max_mismatch_ratio * 100.0,
+        artifact("diff").display(),
+        UPDATE_GOLDEN_VAR,
+    ))
+}
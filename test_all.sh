@@ -72,6 +72,18 @@ run_test_suite \
     "cargo test -p vte-core --quiet 2>/dev/null || cargo test -p vte-core --quiet -- --skip security" \
     "Terminal engine, drawing, PTY handling, configuration"
 
+# 4b. vte-core with grid invariant checks after every mutation batch
+run_test_suite \
+    "Core Grid Invariant Checks" \
+    "cargo test -p vte-core --lib --quiet --features debug-invariants" \
+    "Buffer sizes, cursor, scrollback and selection bounds stay consistent"
+
+# 4c. Rendered frames compared against golden images
+run_test_suite \
+    "Rendering Golden Images" \
+    "cargo test -p vte-render-cairo --test golden --quiet" \
+    "Text attributes, underline styles, cursor shapes and selection overlay match tests/golden"
+
 # 4. vte-gtk Frontend Tests
 run_test_suite \
     "GTK Frontend Tests" \